@@ -572,6 +572,15 @@ function describe_raire_result(output_div,explanation_div,data) {
         return ids.map(candidate_name).join(",")
     }
     if (data.solution && data.solution.Ok) {
+        if (data.solution.Ok.partial) {
+            add(output_div,"p","error").innerText="Warning : Time ran out before all alternate winners were ruled out. These assertions are NOT sufficient for an audit. "+(data.solution.Ok.unresolved_elimination_order_suffixes||[]).length+" elimination order suffixes were unresolved.";
+        }
+        if (data.solution.Ok.fallback) {
+            add(output_div,"p","warning").innerText="Warning : Time ran out before the best assertions were found, so these are the simple winner only assertions. They are sufficient for an audit, but may need a much larger sample.";
+        }
+        if (data.solution.Ok.refinement_incomplete) {
+            add(output_div,"p","warning").innerText="Warning : Time ran out while refining the assertions, so these are the easiest found in time. They are sufficient for an audit, but easier assertions may exist.";
+        }
         if (data.solution.Ok.warning_trim_timed_out) {
             add(output_div,"p","warning").innerText="Warning : Trimming timed out. Some assertions may be redundant.";
         }
@@ -581,19 +590,28 @@ function describe_raire_result(output_div,explanation_div,data) {
                 add(output_div,"p").innerText="Time to "+what+" : "+time_desc+" ("+time_taken.work+" operations)";
             }
         }
+        function describe_difficulty_units(units) { // see DifficultyUnits in audit_type.rs
+            if (units==="OneOnDilutedMargin") return " (1/diluted margin)";
+            if (units==="OneOnDilutedMarginSquared") return " (1/diluted margin²)";
+            if (units && units.ExpectedSampleSize) return " (expected ballots sampled at risk limit "+units.ExpectedSampleSize.risk_limit+")";
+            return "";
+        }
         describe_time("determine winners",data.solution.Ok.time_to_determine_winners);
         describe_time("find assertions",data.solution.Ok.time_to_find_assertions);
         describe_time("trim assertions",data.solution.Ok.time_to_trim_assertions);
         let heading_name = "Assertions";
         if (data.metadata.hasOwnProperty("contest")) heading_name+=" for "+data.metadata.contest;
-        if (data.solution.Ok.hasOwnProperty("difficulty")) heading_name+=" - difficulty = "+data.solution.Ok.difficulty;
+        if (data.solution.Ok.hasOwnProperty("difficulty")) heading_name+=" - difficulty = "+data.solution.Ok.difficulty+describe_difficulty_units(data.solution.Ok.difficulty_units);
         if (data.solution.Ok.hasOwnProperty("margin")) heading_name+=" margin = "+data.solution.Ok.margin;
         add(output_div,"h3","Assertions").innerText=heading_name;
+        const sample_sizes = data.sample_sizes; // present if the problem had a risk_limit.
+        if (sample_sizes) add(output_div,"p").innerText="Estimated initial sample size at risk limit "+sample_sizes.risk_limit+" : "+sample_sizes.overall+" ballots";
         let assertionRisks = data.metadata && data.metadata.assertionRisks; // a tool may add the risk limits from the audit to the metadata
         let riskLimit = data.metadata && data.metadata.riskLimit;
         let assertionIndex = 0;
         for (const av of data.solution.Ok.assertions) {
             let adiv = add(output_div,"div");
+            if (sample_sizes && sample_sizes.assertions.length>assertionIndex) adiv.title="Estimated sample size : "+sample_sizes.assertions[assertionIndex]+" ballots";
             if (Array.isArray(assertionRisks) && assertionRisks.length>assertionIndex) {
                 let risk = assertionRisks[assertionIndex];
                 let isGood = typeof riskLimit==="number"?(risk<=riskLimit?"risk_ok":"risk_bad"):"risk"
@@ -637,12 +655,26 @@ function describe_raire_result(output_div,explanation_div,data) {
         let err = data.solution.Err;
         if (err==="InvalidCandidateNumber") {
             add(output_div, "p", "error").innerText = "Invalid candidate number in the preference list. Candidate numbers should be 0 to num_candidates-1 inclusive.";
+        } else if (err.hasOwnProperty("InvalidCandidateNumberIn")) {
+            const e = err.InvalidCandidateNumberIn;
+            add(output_div, "p", "error").innerText = "Invalid candidate number "+e.candidate+" in "+e.context+". Candidate numbers should be 0 to "+(e.num_candidates-1)+" inclusive.";
+        } else if (err==="TooManyBallots") {
+            add(output_div, "p", "error").innerText = "The total number of ballots is too large to count.";
         } else if (err==="TimeoutCheckingWinner") {
                 add(output_div, "p", "error").innerText = "Timeout checking winner - either your problem is exceptionally difficult, or your timeout is exceedingly small.";
         } else if (err.hasOwnProperty("TimeoutFindingAssertions")) {
             add(output_div,"p","error").innerText="Timeout finding assertions - your problem is quite hard. Difficulty when interrupted : "+err.TimeoutFindingAssertions;
+        } else if (err.hasOwnProperty("Cancelled")) {
+            const partial = err.Cancelled.partial;
+            add(output_div,"p","error").innerText="Cancelled before finishing."+(partial?" "+partial.assertions.length+" assertions had been found"+(partial.partial?", not enough to rule out every other winner.":", but not trimmed."):"");
         } else if (err==="InvalidTimeout") {
             add(output_div,"p","error").innerText="Timeout is not valid. Timeout should be a number greater than zero.";
+        } else if (err==="InvalidRiskLimit") {
+            add(output_div,"p","error").innerText="Risk limit is not valid. It should be a number greater than zero and less than one.";
+        } else if (err==="InvalidErrorRates") {
+            add(output_div,"p","error").innerText="Error rates are not valid. They should be between zero and one, and add up to at most one.";
+        } else if (err==="ExactArithmeticNotSupported") {
+            add(output_div,"p","error").innerText="Exact arithmetic is not possible for this audit type. The difficulty must depend only on the margin, so it is not possible for BRAVO or stratified audits.";
         } else if (Array.isArray(err.CouldNotRuleOut)) {
             add(output_div,"p","error").innerText="Impossible to audit. Could not rule out the following elimination order:";
             for (let i=0;i<err.CouldNotRuleOut.length;i++) {
@@ -650,8 +682,62 @@ function describe_raire_result(output_div,explanation_div,data) {
             }
         } else if (Array.isArray(err.TiedWinners)) {
             add(output_div,"p","error").innerText="Audit not possible as "+candidate_name_list(err.TiedWinners)+" are tied IRV winners and a one vote difference would change the outcome.";
+            if (Array.isArray(data.tied_rounds)) { // the tallies of the count, showing the tie.
+                for (let i=0;i<data.tied_rounds.length;i++) {
+                    const round = data.tied_rounds[i];
+                    add(output_div,"p","candidate_name").innerText="Round "+(i+1)+" : "+round.continuing.map((c,j)=>candidate_name(c)+" "+round.tallies[j]).join(", ");
+                }
+            }
+        } else if (Array.isArray(err.AssertionsAllowOtherWinners)) {
+            add(output_div,"p","error").innerText="The assertions do not rule out the following elimination orders (the candidates shown are the last eliminated, ending with the alternate winner):";
+            for (const suffix of err.AssertionsAllowOtherWinners) {
+                add(output_div,"p","candidate_name").innerText=candidate_name_list(suffix);
+            }
+        } else if (Array.isArray(err.RemovedBallotsNotPresent)) {
+            add(output_div,"p","error").innerText="Could not remove ballots with preferences "+candidate_name_list(err.RemovedBallotsNotPresent)+" as there were not that many.";
+        } else if (err.hasOwnProperty("UnsupportedFormatVersion")) {
+            add(output_div,"p","error").innerText="The file has format version "+err.UnsupportedFormatVersion+", which is newer than this program understands.";
+        } else if (err.hasOwnProperty("UnknownCandidateName")) {
+            add(output_div,"p","error").innerText="There is no candidate named "+err.UnknownCandidateName+".";
+        } else if (Array.isArray(err.AmbiguousCandidateNames)) {
+            add(output_div,"p","error").innerText="The candidate names "+err.AmbiguousCandidateNames.join(" and ")+" are too similar to tell apart.";
+        } else if (err.hasOwnProperty("WinnerRemoved")) {
+            add(output_div,"p","error").innerText="Preprocessing would remove the winner "+candidate_name(err.WinnerRemoved)+".";
+        } else if (err.hasOwnProperty("WrongNumberOfVoteStrata")) {
+            add(output_div,"p","error").innerText="A stratified audit needs the stratum of each of the "+err.WrongNumberOfVoteStrata.expected+" votes, but vote_strata has "+err.WrongNumberOfVoteStrata.found+" entries.";
+        } else if (err.hasOwnProperty("InvalidStratum")) {
+            add(output_div,"p","error").innerText="Vote "+err.InvalidStratum.vote+" is in stratum "+err.InvalidStratum.stratum+", but the audit has only "+err.InvalidStratum.num_strata+" strata.";
+        } else if (err.hasOwnProperty("InvalidAllocationSteps")) {
+            add(output_div,"p","error").innerText="The stratified audit's allocation_steps should be between 1 and "+err.InvalidAllocationSteps.max+", not "+err.InvalidAllocationSteps.steps+".";
+        } else if (err.hasOwnProperty("StratumExceedsBound")) {
+            add(output_div,"p","error").innerText="Stratum "+err.StratumExceedsBound.stratum+" has "+err.StratumExceedsBound.ballots+" ballots, more than its upper bound of "+err.StratumExceedsBound.bound+".";
+        } else if (err.hasOwnProperty("ManifestSmallerThanVotes")) {
+            add(output_div,"p","error").innerText="The ballot manifest has "+err.ManifestSmallerThanVotes.manifest+" ballots, fewer than the "+err.ManifestSmallerThanVotes.votes+" votes. Check total_auditable_ballots.";
+        } else if (err.hasOwnProperty("ManifestDisagreesWithAudit")) {
+            add(output_div,"p","error").innerText="The ballot manifest has "+err.ManifestDisagreesWithAudit.manifest+" ballots, but the audit has "+err.ManifestDisagreesWithAudit.total_auditable_ballots+" total auditable ballots.";
+        } else if (err.hasOwnProperty("InvalidBallotManifest")) {
+            add(output_div,"p","error").innerText="The ballot manifest is invalid as "+err.InvalidBallotManifest+".";
+        } else if (err==="IncrementalStratifiedAudit") {
+            add(output_div,"p","error").innerText="Incremental updates are not supported for stratified audits.";
+        } else if (err.hasOwnProperty("StvNotSupported")) {
+            add(output_div,"p","error").innerText="Only single winner IRV contests can be audited, not contests with "+err.StvNotSupported.seats+" seats.";
+        } else if (err.hasOwnProperty("InternalErrorRuledOutWinner")) {
+            const e = err.InternalErrorRuledOutWinner;
+            add(output_div,"p","error").innerText="Internal error: assertions "+e.assertions.join(", ")+" rule out the winner"+(e.winner===undefined?"":" "+candidate_name(e.winner))+" with elimination order ending "+candidate_name_list(e.elimination_order)+". Please report this.";
+        } else if (err.hasOwnProperty("InternalErrorDidntRuleOutLoser")) {
+            const e = err.InternalErrorDidntRuleOutLoser;
+            add(output_div,"p","error").innerText="Internal error: the assertions do not rule out "+(e.candidate===undefined?"some candidate":candidate_name(e.candidate))+" winning with elimination order ending "+candidate_name_list(e.elimination_order)+". Please report this.";
         } else if (Array.isArray(err.WrongWinner)) {
             add(output_div,"p","error").innerText="The votes are not consistent with the provided winner. Perhaps "+candidate_name_list(err.WrongWinner)+"?";
+        } else if (err==="InvalidReportedEliminationOrder") {
+            add(output_div,"p","error").innerText="The reported elimination order should list every candidate, or every candidate but the winner, once each.";
+        } else if (err.hasOwnProperty("ReportedEliminationOrderDiffers")) {
+            const e = err.ReportedEliminationOrderDiffers;
+            add(output_div,"p","error").innerText="The reported elimination order differs from the count of the votes in round "+e.round+", where "+candidate_name_list(e.reported_eliminated)+" was reported eliminated, but the count eliminates "+candidate_name_list(e.computed_eliminated)+". "+e.affected_ballots+" votes are transferred in that round by one count but not the other. The tallies in that round were:";
+            for (let i=0;i<e.continuing.length;i++) {
+                add(output_div,"p","candidate_name").innerText=candidate_name(e.continuing[i])+" : "+e.tallies[i];
+            }
+            add(output_div,"p","candidate_name").innerText="Exhausted : "+e.exhausted;
         } else {
             add(output_div,"p","error").innerText="Error : "+JSON.stringify(err);
         }
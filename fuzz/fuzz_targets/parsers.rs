@@ -0,0 +1,44 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Arbitrary bytes into each of the ballot file parsers in `utilities`. Malformed files should give an error, not a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
+use raire::irv::BallotPaperCount;
+use utilities::parse::{blt, csv, nist_cdf, preflib, ParsedContest, BallotInterpretationRules};
+use utilities::parse_michelle_format::Contest;
+
+/// Contests with more candidates than this are not converted to RAIRE problems, as determining the winner with many tied
+/// candidates, which random files tend to have, takes exponential time.
+const MAX_CANDIDATES : usize = 10;
+
+fn convert(contest:&ParsedContest) {
+    if contest.candidate_names.len()<=MAX_CANDIDATES {
+        let audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(contest.num_ballots()) });
+        let _ = contest.to_raire_problem(audit);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let rules = BallotInterpretationRules::default();
+    if let Ok(contest) = blt::read_blt(data,"fuzz",&rules) { convert(&contest); }
+    if let Ok(contest) = preflib::read_preflib(data,"fuzz",&rules) { convert(&contest); }
+    if let Ok(contest) = csv::read_csv_ballots(data,"fuzz",&csv::CsvBallotFormat::default()) { convert(&contest); }
+    if let Ok(contest) = nist_cdf::read_cdf_json(data,"",&rules) { convert(&contest); }
+    if let Ok(contest) = nist_cdf::read_cdf_xml(data,"",&rules) { convert(&contest); }
+    if let Ok(contests) = Contest::parse_reader(data,"fuzz") {
+        for contest in contests.iter().filter(|c|c.num_candidates<=MAX_CANDIDATES) {
+            let _ = contest.to_raire_problem(Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(1) }));
+        }
+    }
+});
@@ -0,0 +1,23 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Arbitrary bytes into the [RaireProblem] deserializer. Anything that is accepted should serialize and read back.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use raire::RaireProblem;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(problem) = serde_json::from_slice::<RaireProblem>(data) {
+        let json = serde_json::to_string(&problem).unwrap();
+        let _ : RaireProblem = serde_json::from_str(&json).unwrap();
+    }
+});
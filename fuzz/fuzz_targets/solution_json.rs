@@ -0,0 +1,46 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Arbitrary bytes as the output of RAIRE, turned into each of the human readable forms produced by `raire explain`.
+//! The assertions, winner and number of candidates in such a file may be inconsistent; that should give a [raire::RaireError], not a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use raire::assertions::Assertion;
+use raire::explain::{candidate_names_from_metadata, explain_solution};
+use raire::export::shangrla::to_shangrla;
+use raire::graphviz::trees_to_dot;
+use raire::report::html::solution_to_html;
+use raire::svg::trees_to_svg;
+use raire::timeout::TimeOut;
+use raire::tree_json::EliminationTreesJson;
+use raire::RaireSolution;
+
+/// Larger problems are skipped, as the work done (and trees built) grows very quickly with the number of candidates.
+const MAX_CANDIDATES : u32 = 8;
+/// A limit on the work done building trees, so that large trees fail quickly.
+const WORK_LIMIT : u64 = 100000;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = serde_json::from_slice::<RaireSolution>(data) else { return };
+    let Ok(result) = &input.solution else { return };
+    if result.num_candidates>MAX_CANDIDATES { return; }
+    let _ = to_shangrla(&input,"fuzz");
+    let _ = solution_to_html(&input,&mut TimeOut::new(Some(WORK_LIMIT),None));
+    let candidate_names = candidate_names_from_metadata(&input.metadata);
+    let _ = explain_solution(result,&candidate_names,&mut TimeOut::new(Some(WORK_LIMIT),None));
+    if let Ok(trees) = result.elimination_trees(&mut TimeOut::new(Some(WORK_LIMIT),None)) {
+        let all_assertions : Vec<Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+        trees_to_dot(&trees,&all_assertions,&candidate_names);
+        trees_to_svg(&trees,&all_assertions,&candidate_names);
+        EliminationTreesJson::new(&trees,&all_assertions,result.num_candidates,&candidate_names);
+    }
+});
@@ -0,0 +1,42 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Arbitrary bytes as a RAIRE problem, solved with a tiny time limit. Problems that are malformed or can't be solved
+//! in time should give a [raire::RaireError], not a panic. Any assertions found are then checked independently.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use raire::irv::Votes;
+use raire::RaireProblem;
+use raire::timeout::TimeOut;
+use raire::verify::{verify_assertions, ClaimedAssertion};
+
+/// Larger problems are skipped, as they just spend the time limit (or memory, for huge candidate counts) without finding anything new.
+const MAX_CANDIDATES : usize = 10;
+const MAX_VOTE_RECORDS : usize = 1000;
+const TIME_LIMIT_SECONDS : f64 = 0.01;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(mut problem) = serde_json::from_slice::<RaireProblem>(data) else { return };
+    if problem.num_candidates>MAX_CANDIDATES || problem.votes.len()+problem.ranked_votes.as_ref().map_or(0,|v|v.len())>MAX_VOTE_RECORDS { return; }
+    problem.time_limit_seconds = Some(problem.time_limit_seconds.unwrap_or(TIME_LIMIT_SECONDS).min(TIME_LIMIT_SECONDS));
+    let votes = problem.interpreted_votes();
+    let num_candidates = problem.num_candidates;
+    let solution = problem.solve();
+    serde_json::to_string(&solution).unwrap();
+    if let Ok(result) = &solution.solution {
+        let votes = Votes::new(votes,num_candidates).expect("solved, so the votes are valid");
+        let assertions : Vec<ClaimedAssertion> = result.assertions.iter().map(|a|ClaimedAssertion{ assertion: a.assertion.clone(), margin: Some(a.margin) }).collect();
+        let report = verify_assertions(&votes,result.winner,&assertions,&mut TimeOut::never()).unwrap();
+        assert!(report.margins_correct && report.assertions_hold,"RAIRE produced assertions that don't check : {:?}",report);
+        if !result.partial { assert!(report.rules_out_other_winners,"RAIRE produced assertions that don't rule out other winners : {:?}",report); }
+    }
+});
@@ -0,0 +1,47 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Arbitrary bytes as ballots and claimed assertions for [raire::verify::verify_assertions], as used by `raire verify`,
+//! and as a previous solution for [RaireProblem::solve_incrementally]. Assertions may mention candidates that don't exist.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use raire::incremental::BallotDelta;
+use raire::irv::{CandidateIndex, Votes};
+use raire::timeout::TimeOut;
+use raire::verify::{verify_assertions, ClaimedAssertion};
+use raire::{RaireProblem, RaireSolution};
+use serde::Deserialize;
+
+const MAX_CANDIDATES : usize = 8;
+
+#[derive(Deserialize)]
+struct Input {
+    problem : RaireProblem,
+    winner : CandidateIndex,
+    assertions : Vec<ClaimedAssertion>,
+    #[serde(default)]
+    previous : Option<RaireSolution>,
+    #[serde(default)]
+    delta : Option<BallotDelta>,
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(mut input) = serde_json::from_slice::<Input>(data) else { return };
+    if input.problem.num_candidates>MAX_CANDIDATES { return; }
+    if let Ok(votes) = Votes::new(input.problem.interpreted_votes(),input.problem.num_candidates) {
+        let _ = verify_assertions(&votes,input.winner,&input.assertions,&mut TimeOut::never());
+    }
+    if let (Some(previous),Some(delta)) = (&input.previous,&input.delta) {
+        input.problem.time_limit_seconds = Some(0.01);
+        let _ = input.problem.solve_incrementally(previous,delta);
+    }
+});
@@ -0,0 +1,77 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Benchmarks of sorting, deduplicating and checking membership of the continuing candidates of NEN assertions in a contest
+//! with many candidates, compared with the plain sorted lists of candidates they used to be.
+
+use std::cmp::Ordering;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use raire::assertions::SortedCandidateSubset;
+use raire::irv::CandidateIndex;
+
+const NUM_CANDIDATES : u32 = 60;
+const NUM_SETS : usize = 5000;
+
+/// Sets of continuing candidates, from a fixed pseudo random sequence, mostly large with many sharing a prefix, as for NEN assertions
+/// late in the elimination order of a contest with many candidates. Some are repeated.
+fn continuing_sets() -> Vec<Vec<CandidateIndex>> {
+    let mut state : u64 = 0x2545F4914F6CDD1D;
+    let mut random = move |n:u32| { // xorshift64*
+        state^=state>>12; state^=state<<25; state^=state>>27;
+        (state.wrapping_mul(0x2545F4914F6CDD1D)>>33) as u32%n
+    };
+    (0..NUM_SETS).map(|_|{
+        let missing = 1+random(4);
+        let mut set : Vec<CandidateIndex> = (0..NUM_CANDIDATES).map(CandidateIndex).collect();
+        for _ in 0..missing { set.remove(random(set.len() as u32) as usize); }
+        set
+    }).collect()
+}
+
+/// How sets of continuing candidates were ordered when they were plain lists.
+fn compare_lists(a:&[CandidateIndex],b:&[CandidateIndex]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(||{
+        for i in 0..a.len() {
+            let res = a[i].0.cmp(&b[i].0);
+            if res!=Ordering::Equal { return res}
+        }
+        Ordering::Equal
+    })
+}
+
+fn sort_and_dedup(c:&mut Criterion) {
+    let lists = continuing_sets();
+    let subsets : Vec<SortedCandidateSubset> = lists.iter().map(|l|l.clone().into()).collect();
+    c.bench_function("sort and dedup Vec<CandidateIndex>",|b|b.iter(||{
+        let mut lists = lists.clone();
+        lists.sort_by(|a,b|compare_lists(a,b));
+        lists.dedup();
+        black_box(lists)
+    }));
+    c.bench_function("sort and dedup SortedCandidateSubset",|b|b.iter(||{
+        let mut subsets = subsets.clone();
+        subsets.sort();
+        subsets.dedup();
+        black_box(subsets)
+    }));
+}
+
+fn membership(c:&mut Criterion) {
+    let lists = continuing_sets();
+    let subsets : Vec<SortedCandidateSubset> = lists.iter().map(|l|l.clone().into()).collect();
+    c.bench_function("membership Vec<CandidateIndex>",|b|b.iter(||{
+        for list in &lists { for candidate in 0..NUM_CANDIDATES { black_box(list.binary_search_by_key(&candidate,|c|c.0).is_ok()); } }
+    }));
+    c.bench_function("membership SortedCandidateSubset",|b|b.iter(||{
+        for subset in &subsets { for candidate in 0..NUM_CANDIDATES { black_box(subset.includes(CandidateIndex(candidate))); } }
+    }));
+}
+
+criterion_group!(benches,sort_and_dedup,membership);
+criterion_main!(benches);
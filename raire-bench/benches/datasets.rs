@@ -0,0 +1,85 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! End to end benchmarks on real contests: solving, trimming the assertions, and scoring NEN assertions.
+//! See [raire_bench::datasets] for the contests used.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use raire::assertions::{AssertionAndDifficulty, NotEliminatedNext};
+use raire::irv::CandidateIndex;
+use raire::progress::NoProgressReporter;
+use raire::raire_algorithm::{raire_with_options, RaireOptions, TrimAlgorithm};
+use raire::timeout::TimeOut;
+use raire::tree_showing_what_assertions_pruned_leaves::order_assertions_and_remove_unnecessary;
+use raire_bench::datasets::{self, Dataset};
+
+fn solve(c:&mut Criterion) {
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+    for dataset in datasets::all() {
+        group.bench_function(&dataset.name,|b|b.iter(||{
+            let solution = dataset.problem.clone().solve();
+            assert!(solution.solution.is_ok(),"{} could not be solved",dataset.name);
+            black_box(solution)
+        }));
+    }
+    group.finish();
+}
+
+/// The assertions found for a dataset before trimming, and the winner.
+fn untrimmed_assertions(dataset:&Dataset) -> (Vec<AssertionAndDifficulty>,CandidateIndex) {
+    let options = RaireOptions{trim_algorithm:TrimAlgorithm::None,..Default::default()};
+    let result = raire_with_options(&dataset.votes(),dataset.problem.winner,&dataset.problem.audit,&options,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    (result.assertions,result.winner)
+}
+
+fn trim(c:&mut Criterion) {
+    let mut group = c.benchmark_group("trim");
+    group.sample_size(10);
+    let options = RaireOptions::default();
+    for dataset in datasets::all() {
+        let (assertions,winner) = untrimmed_assertions(&dataset);
+        let num_candidates = dataset.problem.num_candidates as u32;
+        group.bench_function(&dataset.name,|b|b.iter(||{
+            let mut assertions = assertions.clone();
+            order_assertions_and_remove_unnecessary(&mut assertions,winner,num_candidates,&options,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+            black_box(assertions)
+        }));
+    }
+    group.finish();
+}
+
+/// Contests with more candidates than this are skipped by [nen_scoring], as the number of continuing sets grows exponentially.
+const MAX_CANDIDATES_FOR_NEN_SCORING : usize = 12;
+
+/// Score the best NEN assertion for every candidate against every set of continuing candidates containing it,
+/// from scratch each time so the tallies are not cached between iterations.
+fn nen_scoring(c:&mut Criterion) {
+    let mut group = c.benchmark_group("nen_scoring");
+    group.sample_size(10);
+    for dataset in datasets::all().into_iter().filter(|d|d.problem.num_candidates<=MAX_CANDIDATES_FOR_NEN_SCORING) {
+        let votes = dataset.votes();
+        let num_candidates = dataset.problem.num_candidates;
+        let continuing_sets : Vec<Vec<CandidateIndex>> = (1u32..1<<num_candidates).filter(|set|set.count_ones()>1)
+            .map(|set|(0..num_candidates as u32).filter(|c|set&(1<<c)!=0).map(CandidateIndex).collect()).collect();
+        group.bench_function(&dataset.name,|b|b.iter(||{
+            votes.clear_tally_cache();
+            for continuing in &continuing_sets {
+                for &winner in continuing {
+                    black_box(NotEliminatedNext::find_best_difficulty(&votes,&dataset.problem.audit,continuing,winner));
+                }
+            }
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches,solve,trim,nen_scoring);
+criterion_main!(benches);
@@ -0,0 +1,66 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Benchmarks of checking assertions against elimination order suffixes, the inner loop of trimming.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use raire::assertions::{Assertion, PrecomputedAssertion};
+use raire::audit_type::BallotComparisonOneOnDilutedMargin;
+use raire::irv::{BallotPaperCount, CandidateIndex, CandidateSet};
+use raire::progress::NoProgressReporter;
+use raire::raire_algorithm::{raire, TrimAlgorithm};
+use raire::timeout::TimeOut;
+use raire::tree_showing_what_assertions_pruned_leaves::{HowFarToContinueSearchTreeWhenPruningAssertionFound, TreeNodeShowingWhatAssertionsPrunedIt};
+use raire_bench::close_contest;
+
+const NUM_CANDIDATES : usize = 8;
+const NUM_BALLOTS : usize = 20000;
+
+fn untrimmed_assertions() -> (Vec<Assertion>,CandidateIndex) {
+    let votes = close_contest(NUM_CANDIDATES,NUM_BALLOTS);
+    let audit = BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(NUM_BALLOTS) };
+    let result = raire(&votes,None,&audit,TrimAlgorithm::None,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    (result.assertions.into_iter().map(|a|a.assertion).collect(),result.winner)
+}
+
+/// Every elimination order suffix of length up to 3.
+fn short_suffixes() -> Vec<Vec<CandidateIndex>> {
+    let mut res = vec![];
+    let mut level : Vec<Vec<CandidateIndex>> = vec![vec![]];
+    for _ in 0..3 {
+        level = level.iter().flat_map(|s|(0..NUM_CANDIDATES as u32).map(CandidateIndex).filter(|c|!s.contains(c)).map(move |c|{ let mut v=vec![c]; v.extend_from_slice(s); v })).collect();
+        res.extend(level.iter().cloned());
+    }
+    res
+}
+
+fn check_suffixes(c:&mut Criterion) {
+    let (assertions,_) = untrimmed_assertions();
+    let precomputed : Vec<PrecomputedAssertion> = assertions.iter().map(|a|PrecomputedAssertion::new(a).unwrap()).collect();
+    let suffixes : Vec<(Vec<CandidateIndex>,CandidateSet)> = short_suffixes().into_iter().map(|s|{ let set = s.iter().cloned().collect(); (s,set) }).collect();
+    c.bench_function("Assertion::ok_elimination_order_suffix",|b|b.iter(||{
+        for (suffix,_) in &suffixes { for a in &assertions { black_box(a.ok_elimination_order_suffix(suffix)); } }
+    }));
+    c.bench_function("PrecomputedAssertion::ok_elimination_order_suffix",|b|b.iter(||{
+        for (suffix,set) in &suffixes { for a in &precomputed { black_box(a.ok_elimination_order_suffix(suffix,*set)); } }
+    }));
+}
+
+fn build_trees(c:&mut Criterion) {
+    let (assertions,winner) = untrimmed_assertions();
+    let relevant : Vec<usize> = (0..assertions.len()).collect();
+    c.bench_function("TreeNodeShowingWhatAssertionsPrunedIt::new",|b|b.iter(||{
+        for candidate in (0..NUM_CANDIDATES as u32).map(CandidateIndex).filter(|&c|c!=winner) {
+            black_box(TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&relevant,&assertions,NUM_CANDIDATES as u32,HowFarToContinueSearchTreeWhenPruningAssertionFound::Forever,&mut TimeOut::never()).unwrap());
+        }
+    }));
+}
+
+criterion_group!(benches,check_suffixes,build_trees);
+criterion_main!(benches);
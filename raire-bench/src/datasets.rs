@@ -0,0 +1,79 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Real contests to benchmark on, as RAIRE problems.
+//!
+//! Some contests are shipped with this repository: the main example from
+//! "A guide to RAIRE" and a selection of 2021 NSW Local Government mayoral contests
+//! (see `Australian Examples/NSW Local Government/Copyright.txt`). Other published contests, such as the San Francisco
+//! contests used in the original RAIRE paper, are not redistributable here; convert them to RAIRE JSON format
+//! (e.g. with `parse_raire_csv` on the files in [audit-irv-cp](https://github.com/michelleblom/audit-irv-cp/tree/raire-branch))
+//! and set the environment variable `RAIRE_BENCH_DATA` to the directory containing them.
+
+use std::path::{Path, PathBuf};
+use raire::irv::Votes;
+use raire::RaireProblem;
+
+/// The environment variable naming a directory of extra RAIRE problem `.json` files to benchmark on.
+pub const EXTRA_DATA_ENV : &str = "RAIRE_BENCH_DATA";
+
+/// Shipped contests, relative to the root of the repository.
+const SHIPPED : &[&str] = &[
+    "WebContent/example_input/a_guide_to_RAIRE_eg_guide.json",
+    "Australian Examples/NSW Local Government/2021/City of Sydney Mayoral.json",
+    "Australian Examples/NSW Local Government/2021/Byron Mayoral.json",
+    "Australian Examples/NSW Local Government/2021/City of Maitland Mayoral.json",
+    "Australian Examples/NSW Local Government/2021/City of Coffs Harbour Mayoral.json",
+];
+
+/// A contest to benchmark on.
+pub struct Dataset {
+    /// A short name, used as the benchmark id.
+    pub name : String,
+    pub problem : RaireProblem,
+}
+
+impl Dataset {
+    /// Read a RAIRE problem from a JSON file, named after the file.
+    pub fn load(path:&Path) -> Result<Dataset,Box<dyn std::error::Error>> {
+        let problem : RaireProblem = serde_json::from_reader(std::io::BufReader::new(std::fs::File::open(path)?))?;
+        let name = path.file_stem().map(|s|s.to_string_lossy().replace(' ',"_")).unwrap_or_default();
+        Ok(Dataset{name,problem})
+    }
+
+    /// The votes, with an empty tally cache.
+    pub fn votes(&self) -> Votes { Votes::new(self.problem.interpreted_votes(),self.problem.num_candidates).unwrap() }
+}
+
+fn repository_root() -> PathBuf { Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_path_buf() }
+
+/// The contests shipped with the repository.
+pub fn shipped() -> Vec<Dataset> {
+    let root = repository_root();
+    SHIPPED.iter().map(|path|Dataset::load(&root.join(path)).unwrap_or_else(|e|panic!("Could not load {path} : {e}"))).collect()
+}
+
+/// The `.json` files in the directory named by [EXTRA_DATA_ENV], if set, sorted by name.
+pub fn extra() -> Vec<Dataset> {
+    let Some(dir) = std::env::var_os(EXTRA_DATA_ENV) else { return vec![] };
+    let mut paths : Vec<PathBuf> = std::fs::read_dir(&dir).unwrap_or_else(|e|panic!("Could not read {EXTRA_DATA_ENV} directory {dir:?} : {e}"))
+        .filter_map(|entry|entry.ok().map(|e|e.path()))
+        .filter(|path|path.extension().is_some_and(|e|e=="json") && !path.file_stem().is_some_and(|s|s.to_string_lossy().ends_with("_out")))
+        .collect();
+    paths.sort();
+    paths.iter().map(|path|Dataset::load(path).unwrap_or_else(|e|panic!("Could not load {path:?} : {e}"))).collect()
+}
+
+/// All contests to benchmark on: the shipped ones, then any extra ones.
+pub fn all() -> Vec<Dataset> {
+    let mut res = shipped();
+    res.extend(extra());
+    res
+}
@@ -0,0 +1,34 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test data for the benchmarks.
+
+pub mod datasets;
+
+use raire::irv::{BallotPaperCount, CandidateIndex, Votes, VotesBuilder};
+
+/// A close contest with the given number of candidates and ballots, with preferences from a fixed pseudo random sequence
+/// so that every run benchmarks the same contest. Candidates have similar support, which makes trimming expensive.
+pub fn close_contest(num_candidates:usize,num_ballots:usize) -> Votes {
+    let mut state : u64 = 0x2545F4914F6CDD1D;
+    let mut random = move || { // xorshift64*
+        state^=state>>12; state^=state<<25; state^=state>>27;
+        (state.wrapping_mul(0x2545F4914F6CDD1D)>>11) as f64/(1u64<<53) as f64
+    };
+    let support : Vec<f64> = (0..num_candidates).map(|_|1.0+0.3*random()).collect();
+    let mut builder = VotesBuilder::new(num_candidates);
+    for _ in 0..num_ballots {
+        let mut scores : Vec<(f64,CandidateIndex)> = (0..num_candidates).map(|c|(support[c]*random(),CandidateIndex(c as u32))).collect();
+        scores.sort_unstable_by(|a,b|b.0.total_cmp(&a.0));
+        let length = 1+(random()*num_candidates as f64) as usize;
+        let prefs : Vec<CandidateIndex> = scores.iter().take(length.min(num_candidates)).map(|(_,c)|*c).collect();
+        builder.add_weighted(&prefs,BallotPaperCount(1)).unwrap();
+    }
+    builder.build()
+}
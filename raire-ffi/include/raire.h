@@ -0,0 +1,41 @@
+/*
+ * C interface to raire-rs. See raire-ffi/src/lib.rs for full documentation.
+ *
+ * This file is part of raire-rs.
+ * raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+ * raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+ *
+ * Problems and solutions are JSON, as described in the README. All strings returned must be freed with raire_string_free.
+ */
+
+#ifndef RAIRE_H
+#define RAIRE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An opaque RAIRE problem. */
+typedef struct RaireProblem RaireProblem;
+
+/* Parse a problem from len bytes of UTF-8 JSON. Returns NULL if invalid (or on a panic), setting *error (if error is not NULL) to a message. */
+RaireProblem *raire_problem_from_json(const uint8_t *json, size_t len, char **error);
+
+/* Find assertions, returning the solution as a nul terminated JSON string. A positive time_limit_seconds replaces the problem's time limit.
+   If RAIRE panics, the JSON is instead {"error":"Panic","message":...}. */
+char *raire_solve(const RaireProblem *problem, double time_limit_seconds);
+
+/* Free a problem returned by raire_problem_from_json. NULL is ignored. */
+void raire_problem_free(RaireProblem *problem);
+
+/* Free a string returned by this library. NULL is ignored. */
+void raire_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RAIRE_H */
@@ -0,0 +1,102 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A C interface to RAIRE, so that election management systems written in C, C++, C# etc. can call it in process.
+//!
+//! Problems and solutions are passed as the same JSON as used by the command line program and web service.
+//! The corresponding C header is [include/raire.h](../include/raire.h). All strings returned are allocated
+//! by this library, and must be freed with [raire_string_free].
+//!
+//! A panic inside RAIRE is caught rather than unwinding into the caller, which would abort it, and is reported as an error.
+
+use std::ffi::{c_char, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use raire::RaireProblem;
+
+/// Convert a Rust string into one owned by the caller, to be freed by [raire_string_free].
+fn to_c_string(s:String) -> *mut c_char {
+    // JSON and error messages never contain a nul, but be safe.
+    CString::new(s.replace('\0',"")).unwrap().into_raw()
+}
+
+/// Run `f`, catching any panic and returning its message.
+fn catch_panic<T>(f:impl FnOnce()->T) -> Result<T,String> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|payload|{
+        let message = payload.downcast_ref::<&str>().map(|s|s.to_string()).or_else(||payload.downcast_ref::<String>().cloned()).unwrap_or_else(||"unknown cause".to_string());
+        format!("internal error - panic : {}",message)
+    })
+}
+
+/// Parse a RAIRE problem from `len` bytes of UTF-8 JSON at `json`.
+///
+/// Returns a problem to be freed with [raire_problem_free], or null if the JSON is invalid, in which case if `error` is not
+/// null, `*error` is set to a description of what is wrong, to be freed with [raire_string_free]. A panic is reported the same way.
+///
+/// # Safety
+/// `json` must point to `len` readable bytes, and `error` must be null or point to a writable pointer.
+#[no_mangle]
+pub unsafe extern "C" fn raire_problem_from_json(json:*const u8,len:usize,error:*mut *mut c_char) -> *mut RaireProblem {
+    let bytes = if json.is_null() { &[] } else { std::slice::from_raw_parts(json,len) };
+    match catch_panic(||serde_json::from_slice::<RaireProblem>(bytes).map_err(|e|e.to_string())).and_then(|r|r) {
+        Ok(problem) => Box::into_raw(Box::new(problem)),
+        Err(e) => {
+            if !error.is_null() { *error = to_c_string(e); }
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Find assertions for a problem, returning the solution as a nul terminated JSON string, to be freed with [raire_string_free].
+/// Errors in finding assertions are reported in the solution JSON, as for the command line program. If RAIRE panics, the
+/// JSON is instead an object with fields `error` (`"Panic"`) and `message`, as for errors from the web service.
+///
+/// If `time_limit_seconds` is positive it replaces any time limit in the problem. The problem is not modified, and may be solved again.
+///
+/// # Safety
+/// `problem` must have been returned by [raire_problem_from_json] and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn raire_solve(problem:*const RaireProblem,time_limit_seconds:f64) -> *mut c_char {
+    let solve = ||{
+        let mut problem = (*problem).clone();
+        if time_limit_seconds>0.0 && time_limit_seconds.is_finite() { problem.time_limit_seconds=Some(time_limit_seconds); }
+        serde_json::to_string(&problem.solve()).unwrap_or_default()
+    };
+    to_c_string(catch_panic(solve).unwrap_or_else(|message|serde_json::json!({"error":"Panic","message":message}).to_string()))
+}
+
+/// Free a problem returned by [raire_problem_from_json]. Does nothing if `problem` is null.
+///
+/// # Safety
+/// `problem` must be null or have been returned by [raire_problem_from_json] and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn raire_problem_free(problem:*mut RaireProblem) {
+    if !problem.is_null() { drop(Box::from_raw(problem)); }
+}
+
+/// Free a string returned by this library. Does nothing if `s` is null.
+///
+/// # Safety
+/// `s` must be null or have been returned by this library and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn raire_string_free(s:*mut c_char) {
+    if !s.is_null() { drop(CString::from_raw(s)); }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::catch_panic;
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(Ok(3),catch_panic(||1+2));
+        assert_eq!(Err("internal error - panic : oops".to_string()),catch_panic(||->i32{ panic!("oops") }));
+        assert_eq!(Err("internal error - panic : 3 oops".to_string()),catch_panic(||->i32{ panic!("{} oops",3) }));
+    }
+}
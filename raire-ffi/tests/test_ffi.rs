@@ -0,0 +1,59 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Test the C interface, called from Rust.
+
+use std::ffi::{c_char, CStr};
+use raire_ffi::{raire_problem_free, raire_problem_from_json, raire_solve, raire_string_free};
+
+const PROBLEM : &str = r#"{"metadata":{"candidates":["Alice","Bob","Chuan","Diego"]},"num_candidates":4,"votes":[{"n":5000,"prefs":[2,1,0]},{"n":1000,"prefs":[1,2,3]},{"n":1500,"prefs":[3,0]},{"n":4000,"prefs":[0,3]},{"n":2000,"prefs":[3]}],"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13500}}"#;
+
+#[test]
+fn test_solve() {
+    unsafe {
+        let problem = raire_problem_from_json(PROBLEM.as_ptr(),PROBLEM.len(),std::ptr::null_mut());
+        assert!(!problem.is_null());
+        for time_limit in [0.0,10.0] { // solve twice, as the problem is not consumed.
+            let solution = raire_solve(problem,time_limit);
+            let json : serde_json::Value = serde_json::from_str(CStr::from_ptr(solution).to_str().unwrap()).unwrap();
+            assert_eq!(27.0,json["solution"]["Ok"]["difficulty"].as_f64().unwrap());
+            raire_string_free(solution);
+        }
+        raire_problem_free(problem);
+    }
+}
+
+#[test]
+fn test_invalid_json() {
+    unsafe {
+        let mut error : *mut c_char = std::ptr::null_mut();
+        let json = "{\"num_candidates\":";
+        let problem = raire_problem_from_json(json.as_ptr(),json.len(),&mut error);
+        assert!(problem.is_null());
+        assert!(!error.is_null());
+        assert!(!CStr::from_ptr(error).to_str().unwrap().is_empty());
+        raire_string_free(error);
+        assert!(raire_problem_from_json(std::ptr::null(),0,std::ptr::null_mut()).is_null());
+        raire_problem_free(std::ptr::null_mut());
+        raire_string_free(std::ptr::null_mut());
+    }
+}
+
+#[test]
+/// The C header must declare every function exported.
+fn test_header_declares_all_functions() {
+    let header = include_str!("../include/raire.h");
+    let source = include_str!("../src/lib.rs");
+    let exported : Vec<&str> = source.split("pub unsafe extern \"C\" fn ").skip(1).map(|rest|rest.split('(').next().unwrap()).collect();
+    assert_eq!(4,exported.len());
+    for function in exported {
+        assert!(header.contains(&format!(" {}(",function))||header.contains(&format!("*{}(",function)),"{} is not in raire.h",function);
+    }
+}
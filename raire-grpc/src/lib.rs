@@ -0,0 +1,151 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A gRPC service performing the RAIRE algorithm, defined in `proto/raire.proto`.
+//!
+//! This offers the same functionality as the web service (solving, verifying and explaining), for clients that prefer gRPC,
+//! with progress of a solve streamed to the client as it happens.
+
+// tonic::Status is large, but it is what tonic requires.
+#![allow(clippy::result_large_err)]
+
+use std::pin::Pin;
+use tokio::sync::mpsc;
+use tokio_stream::Stream;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use raire::{RaireProblem, RaireSolution};
+use raire::explain::{candidate_names_from_metadata, explain_assertion, explain_solution};
+use raire::irv::{CandidateIndex, Votes};
+use raire::progress::ProgressEvent;
+use raire::timeout::{CancellationToken, TimeOut};
+use raire::tree_json::EliminationTreesJson;
+use raire::verify::{verify_assertions, ClaimedAssertion};
+
+/// The types generated from `proto/raire.proto`.
+pub mod proto {
+    tonic::include_proto!("raire");
+}
+
+use proto::raire_server::Raire;
+use proto::{progress, solve_update, ExplainRequest, ExplainResponse, Progress, SolveRequest, SolveUpdate, VerifyRequest, VerifyResponse};
+
+/// Limits on what clients may ask of the service, as solving is CPU heavy.
+#[derive(Clone,Debug)]
+pub struct RaireService {
+    /// The maximum number of candidates in a problem.
+    pub max_candidates : usize,
+    /// The maximum time in seconds a solve may take. Problems asking for longer, or no limit, get this.
+    pub max_time_limit_seconds : f64,
+}
+
+impl RaireService {
+    /// A problem from a request, checked against the limits, with its time limit capped.
+    fn problem(&self,json:&str) -> Result<RaireProblem,Status> {
+        let mut problem : RaireProblem = serde_json::from_str(json).map_err(|e|Status::invalid_argument(format!("Invalid problem : {}",e)))?;
+        if problem.num_candidates>self.max_candidates {
+            return Err(Status::resource_exhausted(format!("Number of candidates {} exceeds the limit of {}",problem.num_candidates,self.max_candidates)));
+        }
+        let time_limit = problem.time_limit_seconds.map_or(self.max_time_limit_seconds,|t|t.min(self.max_time_limit_seconds));
+        problem.time_limit_seconds = Some(time_limit).filter(|t|t.is_finite());
+        Ok(problem)
+    }
+}
+
+impl From<ProgressEvent> for Progress {
+    fn from(event: ProgressEvent) -> Self {
+        let percent = event.percentage_estimate();
+        let event = match event {
+            ProgressEvent::DeterminedWinner{winner,time_taken} => progress::Event::DeterminedWinner(proto::DeterminedWinner{ winner: winner.0, seconds: time_taken.seconds }),
+            ProgressEvent::Searching{depth,frontier_size,current_difficulty,lower_bound,assertions_found,work_done} => progress::Event::Searching(proto::Searching{
+                depth: depth as u64, frontier_size: frontier_size as u64, current_difficulty, lower_bound, assertions_found: assertions_found as u64, work_done,
+            }),
+            ProgressEvent::FinishedSearch{assertions_found,difficulty,time_taken} => progress::Event::FinishedSearch(proto::FinishedSearch{ assertions_found: assertions_found as u64, difficulty, seconds: time_taken.seconds }),
+            ProgressEvent::TrimmingTreeBuilt{candidate,nodes,trees_built,trees_total} => progress::Event::TrimmingTreeBuilt(proto::TrimmingTreeBuilt{
+                candidate: candidate.0, nodes: nodes as u64, trees_built: trees_built as u64, trees_total: trees_total as u64,
+            }),
+            ProgressEvent::FinishedTrimming{assertions_before,assertions_after,time_taken} => progress::Event::FinishedTrimming(proto::FinishedTrimming{
+                assertions_before: assertions_before as u64, assertions_after: assertions_after as u64, seconds: time_taken.seconds,
+            }),
+            ProgressEvent::ImprovedSolution{result} => progress::Event::ImprovedSolution(proto::ImprovedSolution{
+                result_json: serde_json::to_string(&result).unwrap_or_default(), difficulty: result.difficulty, num_assertions: result.assertions.len() as u32,
+            }),
+        };
+        Progress{ percent, event: Some(event) }
+    }
+}
+
+impl TryFrom<&RaireSolution> for proto::Solution {
+    type Error = Status;
+    fn try_from(solution: &RaireSolution) -> Result<Self, Self::Error> {
+        let solution_json = serde_json::to_string(solution).map_err(|e|Status::internal(e.to_string()))?;
+        Ok(match &solution.solution {
+            Ok(result) => proto::Solution{
+                solution_json, error: None, winner: result.winner.0, difficulty: result.difficulty, margin: result.margin.0 as u64,
+                num_assertions: result.assertions.len() as u32, partial: result.partial,
+            },
+            Err(error) => proto::Solution{ solution_json, error: Some(error.to_string()), ..Default::default() },
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl Raire for RaireService {
+    type SolveStream = Pin<Box<dyn Stream<Item=Result<SolveUpdate,Status>> + Send>>;
+
+    async fn solve(&self, request: Request<SolveRequest>) -> Result<Response<Self::SolveStream>, Status> {
+        let problem = self.problem(&request.into_inner().problem_json)?;
+        let (sender,receiver) = mpsc::channel(100);
+        tokio::task::spawn_blocking(move ||{
+            // If the client has gone away, there is no point continuing.
+            let cancellation = CancellationToken::new();
+            let mut reporter = |event:ProgressEvent|{
+                if sender.blocking_send(Ok(SolveUpdate{ update: Some(solve_update::Update::Progress(event.into())) })).is_err() { cancellation.cancel(); }
+            };
+            let solution = problem.solve_cancellable(&mut reporter,cancellation.clone());
+            let _ = sender.blocking_send(proto::Solution::try_from(&solution).map(|solution|SolveUpdate{ update: Some(solve_update::Update::Solution(solution)) }));
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+
+    async fn verify(&self, request: Request<VerifyRequest>) -> Result<Response<VerifyResponse>, Status> {
+        let request = request.into_inner();
+        let problem = self.problem(&request.problem_json)?;
+        let invalid = |e:serde_json::Error|Status::invalid_argument(format!("Invalid assertions : {}",e));
+        let assertions_json : serde_json::Value = serde_json::from_str(&request.assertions_json).map_err(invalid)?;
+        let (assertions,solution_winner) = if let Some(result) = assertions_json.get("solution") { // RAIRE output
+            let result = result.get("Ok").ok_or_else(||Status::invalid_argument("The assertions record a failed RAIRE computation"))?;
+            let assertions : Vec<ClaimedAssertion> = serde_json::from_value(result["assertions"].clone()).map_err(invalid)?;
+            (assertions,serde_json::from_value::<Option<CandidateIndex>>(result["winner"].clone()).map_err(invalid)?)
+        } else { (serde_json::from_value(assertions_json).map_err(invalid)?,None) };
+        let winner = request.winner.map(CandidateIndex).or(solution_winner).or(problem.winner).ok_or_else(||Status::invalid_argument("No claimed winner"))?;
+        let report = tokio::task::spawn_blocking(move ||{
+            let votes = Votes::new(problem.interpreted_votes(),problem.num_candidates)?;
+            verify_assertions(&votes,winner,&assertions,&mut TimeOut::never())
+        }).await.map_err(|e|Status::internal(e.to_string()))?.map_err(|e|Status::invalid_argument(e.to_string()))?;
+        let report_json = serde_json::to_string(&report).map_err(|e|Status::internal(e.to_string()))?;
+        Ok(Response::new(VerifyResponse{ verified: report.verified, report_json }))
+    }
+
+    async fn explain(&self, request: Request<ExplainRequest>) -> Result<Response<ExplainResponse>, Status> {
+        let solution : RaireSolution = serde_json::from_str(&request.into_inner().solution_json).map_err(|e|Status::invalid_argument(format!("Invalid solution : {}",e)))?;
+        let candidate_names = candidate_names_from_metadata(&solution.metadata);
+        let result = solution.solution.map_err(|e|Status::failed_precondition(format!("The solution records a failed RAIRE computation : {}",e)))?;
+        tokio::task::spawn_blocking(move ||{
+            let unexplainable = |e:raire::RaireError|Status::failed_precondition(e.to_string());
+            let summary = explain_solution(&result,&candidate_names,&mut TimeOut::never()).map_err(unexplainable)?;
+            let assertions = result.assertions.iter().map(|a|explain_assertion(a,&candidate_names)).collect();
+            let all_assertions : Vec<_> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+            let trees = result.elimination_trees(&mut TimeOut::never()).map_err(unexplainable)?;
+            let trees_json = serde_json::to_string(&EliminationTreesJson::new(&trees,&all_assertions,result.num_candidates,&candidate_names)).map_err(|e|Status::internal(e.to_string()))?;
+            Ok(Response::new(ExplainResponse{ summary, assertions, trees_json }))
+        }).await.map_err(|e|Status::internal(e.to_string()))?
+    }
+}
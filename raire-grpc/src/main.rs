@@ -0,0 +1,45 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use std::net::{IpAddr, SocketAddr};
+use clap::Parser;
+use raire_grpc::proto::raire_server::RaireServer;
+use raire_grpc::RaireService;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// A server that performs the RAIRE algorithm as a gRPC service
+struct CliOptions {
+    /// The socket to listen on. Default is 50051.
+    #[arg(short, long, default_value_t = 50051)]
+    socket : u16,
+
+    /// The IP address to listen to. Default is 127.0.0.1
+    #[arg(short, long)]
+    ip : Option<IpAddr>,
+
+    /// The maximum time in seconds a solve may run for. Default 600.
+    #[arg(long, default_value_t = 600.0)]
+    time_limit : f64,
+
+    /// The maximum number of candidates in a problem. Default 100.
+    #[arg(long, default_value_t = 100)]
+    max_candidates : usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = CliOptions::parse();
+    let addr = SocketAddr::new(args.ip.unwrap_or(IpAddr::from([127, 0, 0, 1])), args.socket);
+    let service = RaireService{ max_candidates: args.max_candidates, max_time_limit_seconds: args.time_limit };
+    println!("Listening on {}", addr);
+    tonic::transport::Server::builder().add_service(RaireServer::new(service)).serve(addr).await?;
+    Ok(())
+}
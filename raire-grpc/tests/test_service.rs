@@ -0,0 +1,61 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Test the gRPC service, calling its methods directly.
+
+use tokio_stream::StreamExt;
+use tonic::{Code, Request};
+use raire_grpc::proto::raire_server::Raire;
+use raire_grpc::proto::{solve_update, ExplainRequest, SolveRequest, VerifyRequest};
+use raire_grpc::RaireService;
+
+const PROBLEM : &str = r#"{"metadata":{"candidates":["Alice","Bob","Chuan","Diego"]},"num_candidates":4,"votes":[{"n":5000,"prefs":[2,1,0]},{"n":1000,"prefs":[1,2,3]},{"n":1500,"prefs":[3,0]},{"n":4000,"prefs":[0,3]},{"n":2000,"prefs":[3]}],"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13500}}"#;
+
+const SERVICE : RaireService = RaireService{ max_candidates: 10, max_time_limit_seconds: 10.0 };
+
+#[tokio::test]
+async fn test_solve_verify_explain() {
+    let mut stream = SERVICE.solve(Request::new(SolveRequest{ problem_json: PROBLEM.to_string() })).await.unwrap().into_inner();
+    let mut progress_updates = 0;
+    let mut solution = None;
+    while let Some(update) = stream.next().await {
+        match update.unwrap().update.unwrap() {
+            solve_update::Update::Progress(_) => { assert!(solution.is_none()); progress_updates+=1; }
+            solve_update::Update::Solution(s) => solution = Some(s),
+        }
+    }
+    let solution = solution.unwrap();
+    assert!(progress_updates>0);
+    assert_eq!(None,solution.error);
+    assert_eq!(2,solution.winner);
+    assert_eq!(27.0,solution.difficulty);
+    assert!(!solution.partial);
+
+    let verified = SERVICE.verify(Request::new(VerifyRequest{ problem_json: PROBLEM.to_string(), assertions_json: solution.solution_json.clone(), winner: None })).await.unwrap().into_inner();
+    assert!(verified.verified);
+    let wrong_winner = SERVICE.verify(Request::new(VerifyRequest{ problem_json: PROBLEM.to_string(), assertions_json: solution.solution_json.clone(), winner: Some(0) })).await.unwrap().into_inner();
+    assert!(!wrong_winner.verified);
+
+    let explained = SERVICE.explain(Request::new(ExplainRequest{ solution_json: solution.solution_json })).await.unwrap().into_inner();
+    assert_eq!(solution.num_assertions as usize,explained.assertions.len());
+    assert!(explained.summary.contains("Chuan"));
+    assert!(serde_json::from_str::<serde_json::Value>(&explained.trees_json).is_ok());
+}
+
+#[tokio::test]
+async fn test_invalid_requests() {
+    let invalid = SERVICE.solve(Request::new(SolveRequest{ problem_json: "{\"num_candidates\":".to_string() })).await.err().unwrap();
+    assert_eq!(Code::InvalidArgument,invalid.code());
+    let too_many = PROBLEM.replace("\"num_candidates\":4","\"num_candidates\":11");
+    let too_large = SERVICE.solve(Request::new(SolveRequest{ problem_json: too_many })).await.err().unwrap();
+    assert_eq!(Code::ResourceExhausted,too_large.code());
+    let unexplainable = SERVICE.explain(Request::new(ExplainRequest{ solution_json: "{}".to_string() })).await.err().unwrap();
+    assert_eq!(Code::InvalidArgument,unexplainable.code());
+}
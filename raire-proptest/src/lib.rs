@@ -0,0 +1,81 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Random small elections, and a check that the assertions RAIRE produces for them are sound:
+//! they are true of the votes, and every elimination order consistent with them elects the true winner.
+//! The check uses [winners_consistent_with_assertions], which looks at every elimination order, independently
+//! of the code used to find and trim the assertions.
+
+use proptest::prelude::*;
+use raire::assertions::{elimination_orders_consistent_with_assertions, winners_consistent_with_assertions, Assertion};
+use raire::audit_type::BallotComparisonOneOnDilutedMargin;
+use raire::irv::{BallotPaperCount, CandidateIndex, Votes, VotesBuilder};
+use raire::progress::NoProgressReporter;
+use raire::raire_algorithm::{raire, TrimAlgorithm};
+use raire::timeout::TimeOut;
+use raire::RaireError;
+
+/// A small election: the number of candidates, and the preference list on each ballot.
+#[derive(Clone,Debug)]
+pub struct SmallElection {
+    pub num_candidates : usize,
+    pub ballots : Vec<Vec<CandidateIndex>>,
+}
+
+impl SmallElection {
+    pub fn votes(&self) -> Votes {
+        let mut builder = VotesBuilder::new(self.num_candidates);
+        for prefs in &self.ballots { builder.add_weighted(prefs,BallotPaperCount(1)).unwrap(); }
+        builder.build()
+    }
+}
+
+/// A ballot ranking some, at least one, of the candidates in a random order.
+pub fn ballot(num_candidates:usize) -> impl Strategy<Value=Vec<CandidateIndex>> {
+    (Just((0..num_candidates as u32).map(CandidateIndex).collect::<Vec<_>>()).prop_shuffle(),1..=num_candidates)
+        .prop_map(|(prefs,length)|prefs[..length].to_vec())
+}
+
+/// An election with 3 to 6 candidates and up to 200 random ballots.
+pub fn small_election() -> impl Strategy<Value=SmallElection> {
+    (3usize..=6).prop_flat_map(|num_candidates|proptest::collection::vec(ballot(num_candidates),1..200)
+        .prop_map(move |ballots|SmallElection{num_candidates,ballots}))
+}
+
+/// Run RAIRE on the election with each trim algorithm, and check the assertions are sound. Returns a description of the first problem found.
+///
+/// A contest whose winner is tied gives [RaireError::TiedWinners], and one that can't be audited without a full recount gives
+/// [RaireError::CouldNotRuleOut]; neither is a soundness problem.
+pub fn check_soundness(election:&SmallElection) -> Result<(),String> {
+    let votes = election.votes();
+    let num_candidates = election.num_candidates as u32;
+    let irv = votes.run_election(&mut TimeOut::never()).map_err(|e|format!("Could not count the votes : {}",e))?;
+    let audit = BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: votes.total_votes() };
+    for trim_algorithm in [TrimAlgorithm::None,TrimAlgorithm::MinimizeTree,TrimAlgorithm::MinimizeAssertions] {
+        match raire(&votes,None,&audit,trim_algorithm,&mut TimeOut::never(),&mut NoProgressReporter) {
+            Ok(result) => {
+                if irv.possible_winners!=[result.winner] { return Err(format!("{:?} : RAIRE says {:?} won, counting says {:?}",trim_algorithm,result.winner,irv.possible_winners)); }
+                let assertions : Vec<Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+                let winners = winners_consistent_with_assertions(&assertions,num_candidates);
+                if winners!=[result.winner] { return Err(format!("{:?} : assertions {:?} allow winners {:?}",trim_algorithm,assertions,winners)); }
+                if !elimination_orders_consistent_with_assertions(&assertions,num_candidates).contains(&irv.elimination_order) {
+                    return Err(format!("{:?} : assertions {:?} rule out the actual elimination order {:?}",trim_algorithm,assertions,irv.elimination_order));
+                }
+                if let Some(margin) = result.margin_of_victory {
+                    if margin.upper_bound.is_some_and(|upper|margin.lower_bound>upper) { return Err(format!("{:?} : margin of victory bounds {:?} are inconsistent",trim_algorithm,margin)); }
+                }
+            }
+            Err(RaireError::TiedWinners(_)) if irv.possible_winners.len()>1 => {}
+            Err(RaireError::CouldNotRuleOut(_)) => {}
+            Err(e) => { return Err(format!("{:?} : {}",trim_algorithm,e)); }
+        }
+    }
+    Ok(())
+}
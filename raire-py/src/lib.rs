@@ -0,0 +1,195 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Python bindings for RAIRE. Data is exchanged as plain Python ints, floats, strings, lists and dicts.
+//!
+//! ```python
+//! import raire
+//! problem = raire.RaireProblem(4, [(5000,[2,1,0]),(1000,[1,2,3]),(1500,[3,0]),(4000,[0,3]),(2000,[3])], total_auditable_ballots=13500, winner=2)
+//! solution = problem.solve()
+//! for a in solution.assertions: print(a, a.difficulty)
+//! report = raire.verify_assertions(problem, 2, solution.assertions)
+//! assert report["verified"]
+//! ```
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyAny;
+use raire::assertions::AssertionAndDifficulty;
+use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
+use raire::timeout::TimeOut;
+use raire::verify::ClaimedAssertion;
+use serde_json::json;
+
+create_exception!(raire, RaireError, PyException, "RAIRE could not find assertions, or the input was invalid.");
+
+fn raire_error(e:raire::RaireError) -> PyErr { RaireError::new_err(e.to_string()) }
+fn json_error(e:serde_json::Error) -> PyErr { PyValueError::new_err(e.to_string()) }
+
+/// Convert JSON into the equivalent plain Python object.
+fn to_python(py:Python<'_>,value:&serde_json::Value) -> PyResult<PyObject> {
+    Ok(py.import_bound("json")?.call_method1("loads",(value.to_string(),))?.unbind())
+}
+
+/// Convert a plain Python object into JSON.
+fn from_python(value:&Bound<'_,PyAny>) -> PyResult<serde_json::Value> {
+    let s : String = value.py().import_bound("json")?.call_method1("dumps",(value,))?.extract()?;
+    serde_json::from_str(&s).map_err(json_error)
+}
+
+/// An assertion, optionally with the difficulty and margin computed by RAIRE.
+#[pyclass(module="raire")]
+#[derive(Clone)]
+struct Assertion {
+    assertion : raire::assertions::Assertion,
+    #[pyo3(get)]
+    difficulty : Option<f64>,
+    #[pyo3(get)]
+    margin : Option<usize>,
+}
+
+#[pymethods]
+impl Assertion {
+    /// `kind` is "NEB" or "NEN". `continuing` is needed for, and only for, NEN assertions.
+    #[new]
+    #[pyo3(signature = (kind, winner, loser, continuing=None))]
+    fn new(kind:&str,winner:u32,loser:u32,continuing:Option<Vec<u32>>) -> PyResult<Self> {
+        let mut value = json!({"type":kind,"winner":winner,"loser":loser});
+        if let Some(continuing) = continuing { value["continuing"]=json!(continuing); }
+        let assertion = serde_json::from_value(value).map_err(json_error)?;
+        Ok(Assertion{assertion,difficulty:None,margin:None})
+    }
+    #[getter]
+    fn kind(&self) -> &'static str { if self.assertion.is_neb() { "NEB" } else { "NEN" } }
+    #[getter]
+    fn winner(&self) -> u32 {
+        match &self.assertion { raire::assertions::Assertion::NEB(a) => a.winner.0, raire::assertions::Assertion::NEN(a) => a.winner.0 }
+    }
+    #[getter]
+    fn loser(&self) -> u32 {
+        match &self.assertion { raire::assertions::Assertion::NEB(a) => a.loser.0, raire::assertions::Assertion::NEN(a) => a.loser.0 }
+    }
+    /// The continuing candidates for a NEN assertion, None for NEB.
+    #[getter]
+    fn continuing(&self) -> Option<Vec<u32>> {
+        match &self.assertion { raire::assertions::Assertion::NEB(_) => None, raire::assertions::Assertion::NEN(a) => Some(a.continuing.iter().map(|c|c.0).collect()) }
+    }
+    /// The assertion as a dict in the same form as the JSON output.
+    fn to_dict(&self,py:Python<'_>) -> PyResult<PyObject> { to_python(py,&serde_json::to_value(&self.assertion).map_err(json_error)?) }
+    fn __repr__(&self) -> String {
+        match self.continuing() {
+            None => format!("Assertion('NEB', {}, {})",self.winner(),self.loser()),
+            Some(continuing) => format!("Assertion('NEN', {}, {}, {:?})",self.winner(),self.loser(),continuing),
+        }
+    }
+}
+
+impl From<&AssertionAndDifficulty> for Assertion {
+    fn from(a:&AssertionAndDifficulty) -> Self { Assertion{assertion:a.assertion.clone(),difficulty:Some(a.difficulty),margin:Some(a.margin.0)} }
+}
+
+/// A contest for which RAIRE should find assertions.
+#[pyclass(module="raire")]
+#[derive(Clone)]
+struct RaireProblem(raire::RaireProblem);
+
+#[pymethods]
+impl RaireProblem {
+    /// `votes` is a list of `(count, preferences)` pairs, preferences being candidate indices, first preference first.
+    /// `audit` is the audit type as in the JSON input format, e.g. "OneOnMargin", "ALPHA" or "BRAVO"; `total_auditable_ballots`
+    /// defaults to the number of votes.
+    #[new]
+    #[pyo3(signature = (num_candidates, votes, total_auditable_ballots=None, audit="OneOnMargin", confidence=0.05, winner=None, candidates=None, time_limit_seconds=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(num_candidates:usize,votes:Vec<(usize,Vec<u32>)>,total_auditable_ballots:Option<usize>,audit:&str,confidence:f64,winner:Option<u32>,candidates:Option<Vec<String>>,time_limit_seconds:Option<f64>) -> PyResult<Self> {
+        let votes : Vec<Vote> = votes.into_iter().map(|(n,prefs)|Vote{n:BallotPaperCount(n),prefs:prefs.into_iter().map(CandidateIndex).collect()}).collect();
+        let total = total_auditable_ballots.unwrap_or_else(||votes.iter().map(|v|v.n.0).sum());
+        let mut problem = json!({"metadata":{},"num_candidates":num_candidates,"votes":votes,"audit":{"type":audit,"total_auditable_ballots":total,"confidence":confidence}});
+        if let Some(winner) = winner { problem["winner"]=json!(winner); }
+        if let Some(candidates) = candidates { problem["metadata"]["candidates"]=json!(candidates); }
+        if let Some(seconds) = time_limit_seconds { problem["time_limit_seconds"]=json!(seconds); }
+        Ok(RaireProblem(serde_json::from_value(problem).map_err(json_error)?))
+    }
+    /// Read a problem in the JSON input format.
+    #[staticmethod]
+    fn from_json(json:&str) -> PyResult<Self> { Ok(RaireProblem(serde_json::from_str(json).map_err(json_error)?)) }
+    fn to_json(&self) -> PyResult<String> { serde_json::to_string(&self.0).map_err(json_error) }
+    #[getter]
+    fn num_candidates(&self) -> usize { self.0.num_candidates }
+    /// Find assertions. Raises RaireError if they cannot be found. The Python global interpreter lock is released while solving.
+    fn solve(&self,py:Python<'_>) -> PyResult<RaireSolution> {
+        let problem = self.0.clone();
+        let solution = py.allow_threads(move ||problem.solve());
+        RaireSolution::new(solution)
+    }
+}
+
+/// The assertions found by RAIRE.
+#[pyclass(module="raire")]
+struct RaireSolution {
+    solution : raire::RaireSolution,
+    result : raire::raire_algorithm::RaireResult,
+}
+
+impl RaireSolution {
+    fn new(solution:raire::RaireSolution) -> PyResult<Self> {
+        let result = solution.solution.clone().map_err(raire_error)?;
+        Ok(RaireSolution{solution,result})
+    }
+}
+
+#[pymethods]
+impl RaireSolution {
+    #[getter]
+    fn assertions(&self) -> Vec<Assertion> { self.result.assertions.iter().map(Assertion::from).collect() }
+    #[getter]
+    fn difficulty(&self) -> f64 { self.result.difficulty }
+    #[getter]
+    fn margin(&self) -> usize { self.result.margin.0 }
+    #[getter]
+    fn winner(&self) -> u32 { self.result.winner.0 }
+    #[getter]
+    fn partial(&self) -> bool { self.result.partial }
+    /// The solution in the JSON output format.
+    fn to_json(&self) -> PyResult<String> { serde_json::to_string(&self.solution).map_err(json_error) }
+    /// The solution as a dict, in the same form as the JSON output.
+    fn to_dict(&self,py:Python<'_>) -> PyResult<PyObject> { to_python(py,&serde_json::to_value(&self.solution).map_err(json_error)?) }
+}
+
+/// Independently check that `assertions` (a list of Assertion, or of dicts as in the JSON output) are correct for the votes in `problem`
+/// and prove that `winner` won. Returns a dict describing the checks, with `verified` true if all passed.
+#[pyfunction]
+#[pyo3(signature = (problem, winner, assertions, time_limit_seconds=None))]
+fn verify_assertions(py:Python<'_>,problem:&RaireProblem,winner:u32,assertions:&Bound<'_,PyAny>,time_limit_seconds:Option<f64>) -> PyResult<PyObject> {
+    let mut claimed : Vec<ClaimedAssertion> = vec![];
+    for a in assertions.iter()? {
+        let a = a?;
+        claimed.push(match a.extract::<Assertion>() {
+            Ok(a) => ClaimedAssertion{assertion:a.assertion,margin:a.margin.map(BallotPaperCount)},
+            Err(_) => serde_json::from_value(from_python(&a)?).map_err(json_error)?,
+        });
+    }
+    let votes = Votes::new(problem.0.votes.clone(),problem.0.num_candidates).map_err(raire_error)?;
+    let mut timeout = TimeOut::new(None,time_limit_seconds.map(std::time::Duration::from_secs_f64));
+    let report = py.allow_threads(||raire::verify::verify_assertions(&votes,CandidateIndex(winner),&claimed,&mut timeout)).map_err(raire_error)?;
+    to_python(py,&serde_json::to_value(&report).map_err(json_error)?)
+}
+
+#[pymodule]
+#[pyo3(name = "raire")]
+fn raire_py(m:&Bound<'_,PyModule>) -> PyResult<()> {
+    m.add_class::<Assertion>()?;
+    m.add_class::<RaireProblem>()?;
+    m.add_class::<RaireSolution>()?;
+    m.add_function(wrap_pyfunction!(verify_assertions,m)?)?;
+    m.add("RaireError",m.py().get_type_bound::<RaireError>())?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A JavaScript friendly interface to RAIRE, so that assertions can be computed in a web browser without a server.
+//!
+//! ```js
+//! import init, { solve, solve_with_progress } from "./pkg/raire_wasm.js";
+//! await init();
+//! const solution = JSON.parse(solve(JSON.stringify(problem)));
+//! ```
+//!
+//! The problem and solution are the same JSON as used by the command line program and web service.
+
+use js_sys::Function;
+use raire::progress::{ProgressEvent, ProgressReporter};
+use raire::RaireProblem;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(start)]
+fn start() {
+    raire::timeout::set_clock(js_sys::Date::now);
+}
+
+/// Solve a RAIRE problem given as JSON, returning the solution as JSON. Throws an exception if the problem is not valid JSON for a RAIRE problem.
+#[wasm_bindgen]
+pub fn solve(problem_json:&str) -> Result<String,JsError> {
+    let problem : RaireProblem = serde_json::from_str(problem_json)?;
+    Ok(serde_json::to_string(&problem.solve())?)
+}
+
+/// Like [solve], but calls `progress` with each progress event, as a JSON string (see [ProgressEvent]), as the computation proceeds.
+///
+/// The computation runs on the calling thread, so to keep a page responsive call this from a web worker and post the events back.
+#[wasm_bindgen]
+pub fn solve_with_progress(problem_json:&str,progress:&Function) -> Result<String,JsError> {
+    let problem : RaireProblem = serde_json::from_str(problem_json)?;
+    Ok(serde_json::to_string(&problem.solve_with_progress(&mut JsProgressReporter(progress)))?)
+}
+
+/// Forward progress events to a JavaScript function.
+struct JsProgressReporter<'a>(&'a Function);
+
+impl ProgressReporter for JsProgressReporter<'_> {
+    fn report(&mut self,event:ProgressEvent) {
+        if let Ok(json) = serde_json::to_string(&event) {
+            let _ = self.0.call1(&JsValue::NULL,&JsValue::from_str(&json)); // an exception in the callback shouldn't stop the computation.
+        }
+    }
+}
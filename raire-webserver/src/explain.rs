@@ -0,0 +1,95 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Explanations of the solution to a job, so front ends such as the RAIRE explainer do not have to derive them themselves.
+
+use std::sync::Arc;
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use raire::assertions::{Assertion, AssertionAndDifficulty};
+use raire::explain::{candidate_names_from_metadata, explain_assertion, explain_solution};
+use raire::raire_algorithm::RaireResult;
+use raire::timeout::TimeOut;
+use raire::tree_json::EliminationTreesJson;
+use raire::tree_showing_what_assertions_pruned_leaves::TreeNodeShowingWhatAssertionsPrunedIt;
+use crate::jobs::{JobState, Jobs};
+
+/// A RAIRE result from a finished job, with the candidate names from its metadata.
+struct Solved {
+    result : RaireResult,
+    candidate_names : Vec<String>,
+    metadata : serde_json::Value,
+}
+
+type Failure = (StatusCode,String);
+
+impl Solved {
+    /// Get the result of a job, failing if there is no such job (404), it is not finished (409) or it did not find assertions (422).
+    fn get(jobs:&Jobs,id:u64) -> Result<Solved,Failure> {
+        let status = jobs.status(id).ok_or((StatusCode::NOT_FOUND,format!("No job {}",id)))?;
+        let Some(solution) = status.solution.filter(|_|status.state==JobState::Finished) else { return Err((StatusCode::CONFLICT,format!("Job {} is {:?}, not finished",id,status.state))); };
+        let result = solution.solution.map_err(|e|(StatusCode::UNPROCESSABLE_ENTITY,e.to_string()))?;
+        Ok(Solved{result,candidate_names:candidate_names_from_metadata(&solution.metadata),metadata:solution.metadata})
+    }
+    fn all_assertions(&self) -> Vec<Assertion> { self.result.assertions.iter().map(|a|a.assertion.clone()).collect() }
+    fn trees(&self) -> Result<Vec<TreeNodeShowingWhatAssertionsPrunedIt>,Failure> {
+        self.result.elimination_trees(&mut TimeOut::never()).map_err(|e|(StatusCode::UNPROCESSABLE_ENTITY,e.to_string()))
+    }
+}
+
+/// An assertion with a description in English.
+#[derive(Serialize)]
+pub struct ExplainedAssertion {
+    #[serde(flatten)]
+    assertion : AssertionAndDifficulty,
+    explanation : String,
+}
+
+/// Everything needed to explain a solution.
+#[derive(Serialize)]
+pub struct Explanation {
+    /// A description of the whole solution in English.
+    summary : String,
+    assertions : Vec<ExplainedAssertion>,
+    trees : EliminationTreesJson,
+}
+
+/// `GET /explain/{id}` : the trees and English descriptions of the solution to a job.
+pub async fn explain(State(jobs): State<Arc<Jobs>>, Path(id): Path<u64>) -> Result<Json<Explanation>,Failure> {
+    let solved = Solved::get(&jobs,id)?;
+    let summary = explain_solution(&solved.result,&solved.candidate_names,&mut TimeOut::never()).map_err(|e|(StatusCode::UNPROCESSABLE_ENTITY,e.to_string()))?;
+    let assertions = solved.result.assertions.iter().map(|a|ExplainedAssertion{assertion:a.clone(),explanation:explain_assertion(a,&solved.candidate_names)}).collect();
+    let trees = EliminationTreesJson::new(&solved.trees()?,&solved.all_assertions(),solved.result.num_candidates,&solved.candidate_names);
+    Ok(Json(Explanation{summary,assertions,trees}))
+}
+
+/// `GET /explain/{id}/trees` : just the trees of elimination orders of the solution to a job.
+pub async fn trees(State(jobs): State<Arc<Jobs>>, Path(id): Path<u64>) -> Result<Json<EliminationTreesJson>,Failure> {
+    let solved = Solved::get(&jobs,id)?;
+    Ok(Json(EliminationTreesJson::new(&solved.trees()?,&solved.all_assertions(),solved.result.num_candidates,&solved.candidate_names)))
+}
+
+/// `GET /explain/{id}/trees.svg` : a picture of the trees of elimination orders of the solution to a job.
+pub async fn trees_svg(State(jobs): State<Arc<Jobs>>, Path(id): Path<u64>) -> Result<Response,Failure> {
+    let solved = Solved::get(&jobs,id)?;
+    let svg = raire::svg::trees_to_svg(&solved.trees()?,&solved.all_assertions(),&solved.candidate_names);
+    Ok(([(header::CONTENT_TYPE,"image/svg+xml")],svg).into_response())
+}
+
+/// `GET /explain/{id}/report.html` : a self contained HTML report of the solution to a job.
+pub async fn report_html(State(jobs): State<Arc<Jobs>>, Path(id): Path<u64>) -> Result<Response,Failure> {
+    let solved = Solved::get(&jobs,id)?;
+    let solution = raire::RaireSolution{ metadata: solved.metadata, solution: Ok(solved.result), manifest: None, sample_sizes: None, tied_rounds: None, format_version: raire::schema::FORMAT_VERSION };
+    let html = raire::report::html::solution_to_html(&solution,&mut TimeOut::never()).map_err(|e|(StatusCode::UNPROCESSABLE_ENTITY,e.to_string()))?;
+    Ok(([(header::CONTENT_TYPE,"text/html; charset=utf-8")],html).into_response())
+}
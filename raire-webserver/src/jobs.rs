@@ -0,0 +1,171 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Solving RAIRE problems as background jobs, so that large contests do not tie up a request until a proxy times out.
+//!
+//! A job is submitted, and then polled until it is finished. At most a fixed number of jobs run at once; the rest wait in a queue.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use raire::progress::ProgressEvent;
+use raire::timeout::CancellationToken;
+use raire::{RaireProblem, RaireSolution};
+use raire::raire_algorithm::RaireResult;
+use crate::metrics::Metrics;
+use crate::store::{problem_hash, SolutionStore};
+
+/// How long a finished or cancelled job's result is kept for a client to collect.
+const JOB_RETENTION : Duration = Duration::from_secs(3600);
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize)]
+pub enum JobState {
+    /// Waiting for a worker.
+    Queued,
+    Running,
+    Finished,
+    /// Cancelled by the client. A job cancelled while running stops shortly afterwards, and its solution is then the error
+    /// `Cancelled`, containing any partial result.
+    Cancelled,
+}
+
+/// What a client is told about a job.
+#[derive(Clone,Debug,Serialize)]
+pub struct JobStatus {
+    pub id : u64,
+    pub state : JobState,
+    /// A rough estimate of the percentage of the work done, 0 to 100.
+    pub progress : f64,
+    /// The hash of the problem, under which the solution is kept if the server has a solution store. See [problem_hash].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub problem_hash : Option<String>,
+    /// Present when the job is finished, or has stopped after being cancelled while running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solution : Option<RaireSolution>,
+    /// For a two phase solve, the easiest assertions found so far, from the latest [ProgressEvent::ImprovedSolution].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub improved_solution : Option<RaireResult>,
+}
+
+struct Job {
+    status : JobStatus,
+    finished_at : Option<Instant>,
+    cancellation : CancellationToken,
+}
+
+/// The jobs submitted to this server, and the workers to run them.
+pub struct Jobs {
+    jobs : Mutex<HashMap<u64,Job>>,
+    next_id : Mutex<u64>,
+    workers : Semaphore,
+    /// The maximum time limit for a job; jobs asking for more (or not saying) get this.
+    max_time_limit_seconds : f64,
+    metrics : Arc<Metrics>,
+    store : Option<Arc<SolutionStore>>,
+}
+
+impl Jobs {
+    pub fn new(workers:usize,max_time_limit_seconds:f64,metrics:Arc<Metrics>,store:Option<Arc<SolutionStore>>) -> Self {
+        Jobs{ jobs: Mutex::new(HashMap::new()), next_id: Mutex::new(1), workers: Semaphore::new(workers.max(1)), max_time_limit_seconds, metrics, store }
+    }
+
+    /// The number of jobs (not yet forgotten) in the given state.
+    pub fn count_in_state(&self,state:JobState) -> usize {
+        self.jobs.lock().unwrap().values().filter(|job|job.status.state==state).count()
+    }
+
+    pub fn status(&self,id:u64) -> Option<JobStatus> {
+        self.jobs.lock().unwrap().get(&id).map(|job|job.status.clone())
+    }
+
+    /// Change the state of a job, unless it has been cancelled. Returns false if it has been cancelled (or forgotten).
+    fn update(&self,id:u64,f:impl FnOnce(&mut JobStatus)) -> bool {
+        match self.jobs.lock().unwrap().get_mut(&id) {
+            Some(job) if job.status.state!=JobState::Cancelled => { f(&mut job.status); if job.status.state==JobState::Finished { job.finished_at=Some(Instant::now()); } true }
+            _ => false,
+        }
+    }
+
+    /// Cancel a job that is not yet finished. Returns the resulting status, or None if there is no such job.
+    pub fn cancel(&self,id:u64) -> Option<JobStatus> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(&id)?;
+        if job.status.state!=JobState::Finished && job.status.state!=JobState::Cancelled {
+            job.status.state=JobState::Cancelled;
+            job.finished_at=Some(Instant::now());
+            job.cancellation.cancel();
+        }
+        Some(job.status.clone())
+    }
+
+    /// Record the solution of a job that has stopped running, finishing it unless it was cancelled.
+    fn finish(&self,id:u64,solution:RaireSolution) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&id) {
+            if job.status.state!=JobState::Cancelled {
+                job.status.state=JobState::Finished;
+                job.status.progress=100.0;
+                job.finished_at=Some(Instant::now());
+            }
+            job.status.solution=Some(solution);
+        }
+    }
+
+    /// Forget jobs finished long ago.
+    fn forget_old_jobs(&self) {
+        self.jobs.lock().unwrap().retain(|_,job|job.finished_at.is_none_or(|t|t.elapsed()<JOB_RETENTION));
+    }
+
+    /// Queue a problem to be solved, returning its status. `held` is kept until the job stops running.
+    /// If the solution is already in the store, the job is finished at once.
+    pub fn submit<H:Send+'static>(self:&Arc<Self>,mut problem:RaireProblem,held:H) -> JobStatus {
+        self.forget_old_jobs();
+        let hash = self.store.as_ref().map(|_|problem_hash(&problem));
+        let id = { let mut next_id = self.next_id.lock().unwrap(); *next_id+=1; *next_id-1 };
+        if let Some(solution) = self.store.as_ref().zip(hash.as_deref()).and_then(|(store,hash)|store.get_for_problem(&problem,hash)) {
+            let status = JobStatus{ id, state: JobState::Finished, progress: 100.0, problem_hash: hash, solution: Some(solution), improved_solution: None };
+            self.jobs.lock().unwrap().insert(id,Job{status:status.clone(),finished_at:Some(Instant::now()),cancellation:CancellationToken::new()});
+            return status;
+        }
+        let time_limit = problem.time_limit_seconds.map_or(self.max_time_limit_seconds,|t|t.min(self.max_time_limit_seconds));
+        problem.time_limit_seconds = Some(time_limit).filter(|t|t.is_finite());
+        let status = JobStatus{ id, state: JobState::Queued, progress: 0.0, problem_hash: hash.clone(), solution: None, improved_solution: None };
+        let cancellation = CancellationToken::new();
+        self.jobs.lock().unwrap().insert(id,Job{status:status.clone(),finished_at:None,cancellation:cancellation.clone()});
+        let jobs = self.clone();
+        tokio::spawn(async move {
+            let _held = held;
+            let Ok(_permit) = jobs.workers.acquire().await else { return; };
+            if !jobs.update(id,|status|status.state=JobState::Running) { return; } // cancelled while queued.
+            let worker_jobs = jobs.clone();
+            let solution = tokio::task::spawn_blocking(move ||{
+                let _span = tracing::info_span!("job",id,candidates=problem.num_candidates,votes=problem.votes.len()).entered();
+                let mut reporter = |event:ProgressEvent|{
+                    if let Some(progress) = event.percentage_estimate() { worker_jobs.update(id,|status|status.progress=progress); }
+                    if let ProgressEvent::ImprovedSolution{result} = event { worker_jobs.update(id,|status|status.improved_solution=Some(*result)); }
+                };
+                let (num_candidates,start) = (problem.num_candidates,Instant::now());
+                let solution = match worker_jobs.store.as_ref().zip(hash.as_deref()).and_then(|(store,hash)|store.snapshots(hash)) {
+                    Some(snapshots) => problem.solve_with_snapshots(&mut reporter,Some(cancellation),snapshots),
+                    None => problem.solve_cancellable(&mut reporter,cancellation),
+                };
+                worker_jobs.metrics.record_solve(num_candidates,&solution,start.elapsed());
+                if let (Some(store),Some(hash)) = (&worker_jobs.store,&hash) { store.put(hash,&solution); }
+                solution
+            }).await;
+            match solution {
+                Ok(solution) => jobs.finish(id,solution),
+                Err(e) => { tracing::error!("Job {} failed : {}",id,e); jobs.cancel(id); }
+            }
+        });
+        status
+    }
+}
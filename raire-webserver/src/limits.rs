@@ -0,0 +1,164 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Limits on what clients may ask of the server, as solving is CPU heavy and the input comes from anyone.
+//!
+//! Requests exceeding a limit get a JSON [ApiError] response, with status 413 (too large) or 429 (too many jobs at once).
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use axum::async_trait;
+use axum::extract::{ConnectInfo, FromRef, FromRequest, Request};
+use axum::extract::rejection::JsonRejection;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use raire::RaireProblem;
+
+/// Configurable limits. None means no limit.
+#[derive(Clone,Debug)]
+pub struct Limits {
+    pub max_candidates : Option<usize>,
+    /// The maximum total number of ballots in a problem.
+    pub max_ballots : Option<usize>,
+    /// The maximum size of a request body in bytes.
+    pub max_body_bytes : usize,
+    /// The maximum number of problems from one IP address being solved (or queued) at once.
+    pub max_jobs_per_ip : Option<usize>,
+}
+
+/// A structured error response.
+#[derive(Debug,Serialize)]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status : StatusCode,
+    /// A machine readable name for the error, e.g. `TooManyCandidates`.
+    pub error : &'static str,
+    pub message : String,
+    /// The limit exceeded, if relevant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit : Option<usize>,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response { (self.status,Json(self)).into_response() }
+}
+
+impl ApiError {
+    fn too_large(error:&'static str,what:&str,size:usize,limit:usize) -> Self {
+        ApiError{ status: StatusCode::PAYLOAD_TOO_LARGE, error, message: format!("{} {} exceeds the limit of {}",what,size,limit), limit: Some(limit) }
+    }
+    pub fn body_too_large(max_body_bytes:usize) -> Self {
+        ApiError{ status: StatusCode::PAYLOAD_TOO_LARGE, error: "BodyTooLarge", message: format!("The request is larger than the limit of {} bytes",max_body_bytes), limit: Some(max_body_bytes) }
+    }
+}
+
+/// Counts how many jobs each IP address has running.
+#[derive(Default)]
+pub struct JobsPerIp(Mutex<HashMap<IpAddr,usize>>);
+
+/// Held while a job runs, to count it against its IP address.
+pub struct IpJobGuard {
+    counts : Arc<JobsPerIp>,
+    ip : IpAddr,
+}
+
+impl Drop for IpJobGuard {
+    fn drop(&mut self) {
+        let mut counts = self.counts.0.lock().unwrap();
+        if let Some(count) = counts.get_mut(&self.ip) {
+            *count-=1;
+            if *count==0 { counts.remove(&self.ip); }
+        }
+    }
+}
+
+/// The state the limits need.
+#[derive(Clone)]
+pub struct LimitState {
+    pub limits : Limits,
+    pub jobs_per_ip : Arc<JobsPerIp>,
+}
+
+impl LimitState {
+    /// Start a job for the given IP address, failing with status 429 if it already has too many.
+    pub fn start_job(&self,ip:IpAddr) -> Result<IpJobGuard,ApiError> {
+        let mut counts = self.jobs_per_ip.0.lock().unwrap();
+        let count = counts.entry(ip).or_insert(0);
+        if let Some(limit) = self.limits.max_jobs_per_ip {
+            if *count>=limit {
+                return Err(ApiError{ status: StatusCode::TOO_MANY_REQUESTS, error: "TooManyJobs", message: format!("{} already has {} jobs running; wait for them to finish",ip,count), limit: Some(limit) });
+            }
+        }
+        *count+=1;
+        Ok(IpJobGuard{counts:self.jobs_per_ip.clone(),ip})
+    }
+
+    /// Check a problem is within the size limits, failing with status 413 if not.
+    pub fn check_problem(&self,problem:&RaireProblem) -> Result<(),ApiError> {
+        if let Some(limit) = self.limits.max_candidates {
+            if problem.num_candidates>limit { return Err(ApiError::too_large("TooManyCandidates","The number of candidates",problem.num_candidates,limit)); }
+        }
+        if let Some(limit) = self.limits.max_ballots {
+            let ballots = problem.votes.iter().map(|v|v.n).chain(problem.ranked_votes.iter().flatten().map(|v|v.n)).fold(0usize,|total,n|total.saturating_add(n.0));
+            if ballots>limit { return Err(ApiError::too_large("TooManyBallots","The number of ballots",ballots,limit)); }
+        }
+        Ok(())
+    }
+}
+
+/// Like [Json], but with a structured error if the body is too large or invalid.
+pub struct LimitedJson<T>(pub T);
+
+#[async_trait]
+impl <T:DeserializeOwned,S:Send+Sync> FromRequest<S> for LimitedJson<T> where LimitState : FromRef<S> {
+    type Rejection = ApiError;
+
+    async fn from_request(req:Request,state:&S) -> Result<Self,ApiError> {
+        let max_body_bytes = LimitState::from_ref(state).limits.max_body_bytes;
+        match Json::<T>::from_request(req,state).await {
+            Ok(Json(value)) => Ok(LimitedJson(value)),
+            Err(rejection) if rejection.status()==StatusCode::PAYLOAD_TOO_LARGE => Err(ApiError::body_too_large(max_body_bytes)),
+            Err(rejection) => Err(ApiError{ status: rejection.status(), error: json_rejection_name(&rejection), message: rejection.body_text(), limit: None }),
+        }
+    }
+}
+
+fn json_rejection_name(rejection:&JsonRejection) -> &'static str {
+    match rejection {
+        JsonRejection::JsonDataError(_) => "InvalidProblem",
+        JsonRejection::JsonSyntaxError(_) => "InvalidJson",
+        JsonRejection::MissingJsonContentType(_) => "MissingJsonContentType",
+        _ => "InvalidRequest",
+    }
+}
+
+/// A problem that is within the size limits, from a client that is allowed to start another job. The guard should be kept until the job finishes.
+pub struct CheckedProblem {
+    pub problem : RaireProblem,
+    pub guard : IpJobGuard,
+}
+
+#[async_trait]
+impl <S:Send+Sync> FromRequest<S> for CheckedProblem where LimitState : FromRef<S> {
+    type Rejection = ApiError;
+
+    async fn from_request(req:Request,state:&S) -> Result<Self,ApiError> {
+        let limit_state = LimitState::from_ref(state);
+        let ip = req.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(address)|address.ip()).unwrap_or(IpAddr::from([0,0,0,0]));
+        let LimitedJson(problem) = LimitedJson::<RaireProblem>::from_request(req,state).await?;
+        limit_state.check_problem(&problem)?;
+        let guard = limit_state.start_job(ip)?;
+        Ok(CheckedProblem{problem,guard})
+    }
+}
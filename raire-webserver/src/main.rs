@@ -8,15 +8,29 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 
+mod jobs;
+mod explain;
+mod limits;
+mod metrics;
+mod store;
+mod uploads;
 
 use axum::{
-    routing::{post},
-    http::StatusCode,
+    extract::{ConnectInfo, DefaultBodyLimit, FromRef, Multipart, Path, State},
+    extract::multipart::{MultipartError, MultipartRejection},
+    routing::{get, post},
+    http::{header, StatusCode},
     Json, Router,
 };
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use jobs::{JobStatus, Jobs};
+use limits::{ApiError, CheckedProblem, LimitState, Limits, LimitedJson};
+use metrics::Metrics;
+use store::SolutionStore;
 use tower_http::services::ServeDir;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use uploads::{UploadJobRequest, UploadStatus, Uploads};
 use clap::Parser;
 
 #[derive(Parser)]
@@ -31,6 +45,73 @@ struct CliOptions {
     #[arg(short, long)]
     ip : Option<IpAddr>,
 
+    /// The number of jobs submitted to `/raire/jobs` that may run at once. Default is the number of CPUs.
+    #[arg(short, long)]
+    workers : Option<usize>,
+
+    /// The maximum time in seconds a job submitted to `/raire/jobs` may run for. Default 600.
+    #[arg(long, default_value_t = 600.0)]
+    job_time_limit : f64,
+
+    /// The maximum number of candidates in a problem. Default 100.
+    #[arg(long, default_value_t = 100)]
+    max_candidates : usize,
+
+    /// The maximum total number of ballots in a problem. Default no limit.
+    #[arg(long)]
+    max_ballots : Option<usize>,
+
+    /// The maximum size of a request in bytes. Default 100000000 (100MB).
+    #[arg(long, default_value_t = 100_000_000)]
+    max_body_bytes : usize,
+
+    /// The maximum number of problems from one IP address that may be solved or queued at once. Default 4.
+    /// If the server is behind a proxy, all requests will appear to come from the proxy.
+    #[arg(long, default_value_t = 4)]
+    max_jobs_per_ip : usize,
+
+    /// A directory in which to keep solutions, so a problem solved before (even before a restart) is answered at once.
+    /// Default none, meaning solutions are not kept.
+    #[arg(long)]
+    store : Option<std::path::PathBuf>,
+
+    /// The maximum number of solutions kept in the `--store` directory; the least recently used are removed. Default 10000.
+    #[arg(long, default_value_t = 10000)]
+    store_max_entries : usize,
+
+    /// Remove solutions from the `--store` directory not used for this many days. Default no limit.
+    #[arg(long)]
+    store_max_age_days : Option<f64>,
+}
+
+/// The state shared by the request handlers.
+#[derive(Clone)]
+struct AppState {
+    jobs : Arc<Jobs>,
+    limits : LimitState,
+    metrics : Arc<Metrics>,
+    store : Option<Arc<SolutionStore>>,
+    uploads : Arc<Uploads>,
+}
+
+impl FromRef<AppState> for Arc<Jobs> {
+    fn from_ref(state:&AppState) -> Self { state.jobs.clone() }
+}
+
+impl FromRef<AppState> for Arc<Metrics> {
+    fn from_ref(state:&AppState) -> Self { state.metrics.clone() }
+}
+
+impl FromRef<AppState> for Option<Arc<SolutionStore>> {
+    fn from_ref(state:&AppState) -> Self { state.store.clone() }
+}
+
+impl FromRef<AppState> for LimitState {
+    fn from_ref(state:&AppState) -> Self { state.limits.clone() }
+}
+
+impl FromRef<AppState> for Arc<Uploads> {
+    fn from_ref(state:&AppState) -> Self { state.uploads.clone() }
 }
 
 
@@ -41,34 +122,169 @@ async fn main() {
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "example_static_file_server=debug,tower_http=debug".into()),
+                .unwrap_or_else(|_| "raire_webserver=info,raire=info,tower_http=debug".into()),
         )
         .with(tracing_subscriber::fmt::layer())
         .init();
 
     let serve_dir = ServeDir::new("WebContent");
 
-    // build our application with a route
-    let app = Router::new()
+    let workers = args.workers.unwrap_or_else(||std::thread::available_parallelism().map(|n|n.get()).unwrap_or(1));
+    let metrics = Arc::new(Metrics::default());
+    let store = args.store.map(|directory|{
+        let max_age = args.store_max_age_days.map(|days|std::time::Duration::from_secs_f64(days*86400.0));
+        Arc::new(SolutionStore::new(directory,args.store_max_entries,max_age).expect("Could not create the --store directory"))
+    });
+    let jobs = Arc::new(Jobs::new(workers,args.job_time_limit,metrics.clone(),store.clone()));
+    let limits = Limits{ max_candidates: Some(args.max_candidates), max_ballots: args.max_ballots, max_body_bytes: args.max_body_bytes, max_jobs_per_ip: Some(args.max_jobs_per_ip) };
+    let state = AppState{ jobs, limits: LimitState{ limits, jobs_per_ip: Default::default() }, metrics, store, uploads: Arc::new(Uploads::default()) };
+
+    // The API is available both under /v1 and, for compatibility with existing clients, unversioned.
+    let api = Router::new()
         // `POST /raire` goes to `raire`
         .route("/raire", post(raire))
+        // `POST /trees.svg` draws the elimination trees of a RAIRE solution
+        .route("/trees.svg", post(trees_svg))
+        // `POST /raire/jobs` solves a problem in the background, and `/raire/jobs/{id}` reports on (GET) or cancels (DELETE) it.
+        .route("/raire/jobs", post(submit_job))
+        .route("/raire/jobs/:id", get(job_status).delete(cancel_job))
+        // `GET /raire/solutions/{hash}` gets a kept solution by the hash of its problem, if the server has a `--store`.
+        .route("/raire/solutions/:hash", get(stored_solution))
+        // `POST /raire/uploads` keeps CVR files as exported by an election system, listing their contests; `/raire/uploads/{id}` reports
+        // on (GET) or removes (DELETE) them, and `POST /raire/uploads/{id}/jobs` solves one of their contests as a background job.
+        .route("/raire/uploads", post(upload))
+        .route("/raire/uploads/:id", get(upload_status).delete(remove_upload))
+        .route("/raire/uploads/:id/jobs", post(submit_upload_job))
+        // `GET /explain/{id}...` explains the solution to a finished job.
+        .route("/explain/:id", get(explain::explain))
+        .route("/explain/:id/trees", get(explain::trees))
+        .route("/explain/:id/trees.svg", get(explain::trees_svg))
+        .route("/explain/:id/report.html", get(explain::report_html))
+        .layer(DefaultBodyLimit::max(args.max_body_bytes))
+        .with_state(state.clone());
+
+    // build our application with a route
+    let app = Router::new()
+        .nest("/v1",api.clone())
+        .merge(api)
+        // `GET /metrics` gives counters for monitoring in the Prometheus text format. It is not versioned as it is for operators rather than clients.
+        .route("/metrics", get(metrics::metrics))
+        // `DELETE /admin/store` removes all kept solutions. Only allowed from the server's own machine.
+        .route("/admin/store", axum::routing::delete(purge_store))
+        .with_state(state)
         .nest_service("/",serve_dir);
 
 
     let address = format!("127.0.0.1:{}",args.socket.unwrap_or(3000));
     println!("listening on {}", address);
     let listener = tokio::net::TcpListener::bind(&address).await.unwrap();
-    axum::serve(listener,app).await.unwrap();
+    axum::serve(listener,app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
 }
 
 
 async fn raire(
+    State(metrics): State<Arc<Metrics>>,
+    State(store): State<Option<Arc<SolutionStore>>>,
     // this argument tells axum to parse the request body
-    // as JSON into a `CreateUser` type
-    Json(problem): Json<raire::RaireProblem>,
+    // as JSON into a `RaireProblem` type, checking it is within the limits
+    CheckedProblem{problem,guard}: CheckedProblem,
 ) -> (StatusCode, Json<raire::RaireSolution>) {
-    let solution = problem.solve();
+    let hash = store.as_ref().map(|_|store::problem_hash(&problem));
+    if let Some(solution) = store.as_ref().zip(hash.as_deref()).and_then(|(store,hash)|store.get_for_problem(&problem,hash)) {
+        return (StatusCode::OK, Json(solution));
+    }
+    let num_candidates = problem.num_candidates;
+    let start = std::time::Instant::now();
+    let snapshots = store.as_ref().zip(hash.as_deref()).and_then(|(store,hash)|store.snapshots(hash));
+    let solution = tracing::info_span!("solve",candidates=problem.num_candidates,votes=problem.votes.len()).in_scope(||match snapshots {
+        Some(snapshots) => problem.solve_with_snapshots(&mut raire::progress::NoProgressReporter,None,snapshots),
+        None => problem.solve(),
+    });
+    metrics.record_solve(num_candidates,&solution,start.elapsed());
+    if let (Some(store),Some(hash)) = (&store,&hash) { store.put(hash,&solution); }
+    drop(guard);
     // this will be converted into a JSON response
     // with a status code of `201 Created`
     (StatusCode::OK, Json(solution))
 }
+
+/// Draw the trees of elimination orders ruled out by the assertions in a solution (as returned by `/raire`) as an SVG picture.
+async fn trees_svg(LimitedJson(solution): LimitedJson<raire::RaireSolution>) -> (StatusCode, [(header::HeaderName,&'static str);1], String) {
+    let svg_header = [(header::CONTENT_TYPE,"image/svg+xml")];
+    match &solution.solution {
+        Ok(result) => match result.elimination_trees(&mut raire::timeout::TimeOut::never()) {
+            Ok(trees) => {
+                let all_assertions : Vec<raire::assertions::Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+                let candidate_names = raire::explain::candidate_names_from_metadata(&solution.metadata);
+                (StatusCode::OK, svg_header, raire::svg::trees_to_svg(&trees,&all_assertions,&candidate_names))
+            }
+            Err(e) => (StatusCode::UNPROCESSABLE_ENTITY, [(header::CONTENT_TYPE,"text/plain")], e.to_string()),
+        }
+        Err(e) => (StatusCode::UNPROCESSABLE_ENTITY, [(header::CONTENT_TYPE,"text/plain")], e.to_string()),
+    }
+}
+
+/// Queue a problem to be solved in the background. Returns the job status, including its `id`.
+async fn submit_job(State(jobs): State<Arc<Jobs>>, CheckedProblem{problem,guard}: CheckedProblem) -> (StatusCode, Json<JobStatus>) {
+    (StatusCode::ACCEPTED, Json(jobs.submit(problem,guard)))
+}
+
+/// The status of a job, including the solution once finished.
+async fn job_status(State(jobs): State<Arc<Jobs>>, Path(id): Path<u64>) -> Result<Json<JobStatus>,StatusCode> {
+    jobs.status(id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// A solution kept in the store, by the hash of its problem.
+async fn stored_solution(State(store): State<Option<Arc<SolutionStore>>>, Path(hash): Path<String>) -> Result<Json<raire::RaireSolution>,StatusCode> {
+    store.and_then(|store|store.get(&hash)).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Remove all the solutions kept in the store, returning how many there were.
+async fn purge_store(State(store): State<Option<Arc<SolutionStore>>>, ConnectInfo(address): ConnectInfo<SocketAddr>) -> Result<Json<usize>,StatusCode> {
+    if !address.ip().is_loopback() { return Err(StatusCode::FORBIDDEN); }
+    store.map(|store|Json(store.purge())).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Cancel a job.
+async fn cancel_job(State(jobs): State<Arc<Jobs>>, Path(id): Path<u64>) -> Result<Json<JobStatus>,StatusCode> {
+    jobs.cancel(id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Keep the CVR files in a `multipart/form-data` body, returning the format detected and the contests in them.
+async fn upload(State(uploads): State<Arc<Uploads>>, State(limits): State<LimitState>, multipart: Result<Multipart,MultipartRejection>) -> Result<(StatusCode, Json<UploadStatus>),ApiError> {
+    let mut multipart = multipart.map_err(|rejection|ApiError{ status: rejection.status(), error: "InvalidRequest", message: rejection.body_text(), limit: None })?;
+    let max_body_bytes = limits.limits.max_body_bytes;
+    let multipart_error = |e:MultipartError| if e.status()==StatusCode::PAYLOAD_TOO_LARGE { ApiError::body_too_large(max_body_bytes) } else { ApiError{ status: e.status(), error: "InvalidRequest", message: e.body_text(), limit: None } };
+    let mut files = vec![];
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        let Some(name) = field.file_name().map(|name|name.to_string()) else { continue; }; // not a file.
+        files.push((name,field.bytes().await.map_err(multipart_error)?.to_vec()));
+    }
+    let status = tokio::task::spawn_blocking(move||uploads.add(files)).await.expect("Upload task panicked")?;
+    Ok((StatusCode::CREATED, Json(status)))
+}
+
+/// The format and contests of an upload.
+async fn upload_status(State(uploads): State<Arc<Uploads>>, Path(id): Path<u64>) -> Result<Json<UploadStatus>,StatusCode> {
+    uploads.status(id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Remove an upload and its files.
+async fn remove_upload(State(uploads): State<Arc<Uploads>>, Path(id): Path<u64>) -> Result<Json<UploadStatus>,StatusCode> {
+    uploads.remove(id).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Queue a contest in an upload to be solved in the background, as for `/raire/jobs`.
+async fn submit_upload_job(
+    State(uploads): State<Arc<Uploads>>,
+    State(jobs): State<Arc<Jobs>>,
+    State(limits): State<LimitState>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Path(id): Path<u64>,
+    LimitedJson(request): LimitedJson<UploadJobRequest>,
+) -> Result<(StatusCode, Json<JobStatus>),ApiError> {
+    let problem = tokio::task::spawn_blocking(move||uploads.problem(id,&request)).await.expect("Upload task panicked")?;
+    limits.check_problem(&problem)?;
+    let guard = limits.start_job(address.ip())?;
+    Ok((StatusCode::ACCEPTED, Json(jobs.submit(problem,guard))))
+}
@@ -0,0 +1,139 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Counters describing the work done by the server, served at `/metrics` in the Prometheus text format
+//! (see <https://prometheus.io/docs/instrumenting/exposition_formats/>) for monitoring and capacity planning.
+
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use axum::extract::State;
+use axum::http::header;
+use raire::{RaireError, RaireSolution};
+use crate::jobs::{JobState, Jobs};
+
+/// Upper bounds of the buckets for solve durations, in seconds.
+const SECONDS_BUCKETS : [f64;12] = [0.01,0.03,0.1,0.3,1.0,3.0,10.0,30.0,100.0,300.0,1000.0,3000.0];
+/// Upper bounds of the buckets for the number of candidates in a problem.
+const CANDIDATES_BUCKETS : [f64;9] = [2.0,3.0,4.0,5.0,7.0,10.0,15.0,20.0,50.0];
+
+/// A Prometheus histogram with fixed buckets.
+struct Histogram {
+    bounds : &'static [f64],
+    /// The number of observations in each bucket (not cumulative), with one more for those above the last bound.
+    counts : Vec<u64>,
+    sum : f64,
+}
+
+impl Histogram {
+    fn new(bounds:&'static [f64]) -> Self { Histogram{ bounds, counts: vec![0;bounds.len()+1], sum: 0.0 } }
+
+    fn observe(&mut self,value:f64) {
+        let bucket = self.bounds.iter().position(|&bound|value<=bound).unwrap_or(self.bounds.len());
+        self.counts[bucket]+=1;
+        self.sum+=value;
+    }
+
+    fn write(&self,out:&mut String,name:&str,help:&str) {
+        let _ = writeln!(out,"# HELP {name} {help}\n# TYPE {name} histogram");
+        let mut cumulative = 0;
+        for (bound,count) in self.bounds.iter().zip(&self.counts) {
+            cumulative+=count;
+            let _ = writeln!(out,"{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        cumulative+=self.counts[self.bounds.len()];
+        let _ = writeln!(out,"{name}_bucket{{le=\"+Inf\"}} {cumulative}\n{name}_sum {}\n{name}_count {cumulative}",self.sum);
+    }
+}
+
+/// How a solve ended, used as the `outcome` label.
+#[derive(Clone,Copy,PartialEq,Eq)]
+enum Outcome { Ok, Timeout, Cancelled, Error }
+
+impl Outcome {
+    const ALL : [Outcome;4] = [Outcome::Ok,Outcome::Timeout,Outcome::Cancelled,Outcome::Error];
+    fn label(self) -> &'static str { match self { Outcome::Ok => "ok", Outcome::Timeout => "timeout", Outcome::Cancelled => "cancelled", Outcome::Error => "error" } }
+    /// A partial or fallback result, given when the time limit expires with `partial_on_timeout` or `winner_only_fallback`, counts as a timeout.
+    fn of(solution:&RaireSolution) -> Self {
+        match &solution.solution {
+            Ok(result) if result.partial || result.fallback => Outcome::Timeout,
+            Ok(_) => Outcome::Ok,
+            Err(RaireError::TimeoutCheckingWinner|RaireError::TimeoutFindingAssertions(_)) => Outcome::Timeout,
+            Err(RaireError::Cancelled{..}) => Outcome::Cancelled,
+            Err(_) => Outcome::Error,
+        }
+    }
+}
+
+struct Recorded {
+    solve_seconds : Histogram,
+    candidates : Histogram,
+    /// The number of solves with each [Outcome], in the order of [Outcome::ALL].
+    outcomes : [u64;4],
+    /// The number of solves whose trimming ran out of time, leaving redundant assertions.
+    trim_timeouts : u64,
+}
+
+/// What has been recorded since the server started.
+pub struct Metrics(Mutex<Recorded>);
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics(Mutex::new(Recorded{ solve_seconds: Histogram::new(&SECONDS_BUCKETS), candidates: Histogram::new(&CANDIDATES_BUCKETS), outcomes: [0;4], trim_timeouts: 0 }))
+    }
+}
+
+impl Metrics {
+    /// Record that a problem with `num_candidates` candidates was solved (either by `/raire` or as a job), giving `solution`, taking `elapsed`.
+    pub fn record_solve(&self,num_candidates:usize,solution:&RaireSolution,elapsed:Duration) {
+        let mut recorded = self.0.lock().unwrap();
+        recorded.solve_seconds.observe(elapsed.as_secs_f64());
+        recorded.candidates.observe(num_candidates as f64);
+        let outcome = Outcome::of(solution);
+        recorded.outcomes[Outcome::ALL.iter().position(|&o|o==outcome).unwrap()]+=1;
+        if solution.solution.as_ref().is_ok_and(|result|result.warning_trim_timed_out) { recorded.trim_timeouts+=1; }
+    }
+
+    /// The metrics in the Prometheus text format, including the current state of the jobs.
+    fn render(&self,jobs:&Jobs) -> String {
+        let mut out = String::new();
+        {
+            let recorded = self.0.lock().unwrap();
+            recorded.solve_seconds.write(&mut out,"raire_solve_duration_seconds","Wall clock time taken to solve a problem.");
+            recorded.candidates.write(&mut out,"raire_problem_candidates","The number of candidates in each problem solved.");
+            let _ = writeln!(out,"# HELP raire_solves_total Problems solved, by outcome (ok, timeout, cancelled or error).\n# TYPE raire_solves_total counter");
+            for (outcome,count) in Outcome::ALL.iter().zip(recorded.outcomes) {
+                let _ = writeln!(out,"raire_solves_total{{outcome=\"{}\"}} {count}",outcome.label());
+            }
+            let _ = writeln!(out,"# HELP raire_trim_timeouts_total Solutions where trimming ran out of time.\n# TYPE raire_trim_timeouts_total counter\nraire_trim_timeouts_total {}",recorded.trim_timeouts);
+        }
+        let _ = writeln!(out,"# HELP raire_jobs Jobs known to the server, by state.\n# TYPE raire_jobs gauge");
+        for state in [JobState::Queued,JobState::Running,JobState::Finished,JobState::Cancelled] {
+            let _ = writeln!(out,"raire_jobs{{state=\"{state:?}\"}} {}",jobs.count_in_state(state));
+        }
+        if let Some(bytes) = resident_memory_bytes() {
+            let _ = writeln!(out,"# HELP process_resident_memory_bytes Resident memory size in bytes.\n# TYPE process_resident_memory_bytes gauge\nprocess_resident_memory_bytes {bytes}");
+        }
+        out
+    }
+}
+
+/// The resident memory of this process, if known. Only available on Linux.
+fn resident_memory_bytes() -> Option<u64> {
+    const PAGE_SIZE : u64 = 4096; // true on all common Linux platforms.
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages : u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages*PAGE_SIZE)
+}
+
+/// `GET /metrics`
+pub async fn metrics(State(metrics):State<Arc<Metrics>>,State(jobs):State<Arc<Jobs>>) -> ([(header::HeaderName,&'static str);1],String) {
+    ([(header::CONTENT_TYPE,"text/plain; version=0.0.4")],metrics.render(&jobs))
+}
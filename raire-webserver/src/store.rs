@@ -0,0 +1,182 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A persistent store of solutions, so that a problem that has been solved before is answered immediately, even after a restart.
+//!
+//! Solutions are kept as JSON files in a directory, named by the hash of the problem (see [problem_hash]). The least recently
+//! used solutions are removed when there are too many, and any not used for too long are removed.
+//!
+//! Snapshots of searches in progress are also kept there (see [raire::snapshot]), so that a solve stopped by its time limit,
+//! or by the server stopping, continues where it got to when the problem is next submitted.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use raire::snapshot::SnapshotFile;
+use raire::{RaireError, RaireProblem, RaireSolution};
+use raire::manifest::problem_sha256;
+
+/// The SHA-256 hash, in hex, of the problem without its `metadata`, `time_limit_seconds` and `max_memory_mb`, which do not affect a solution
+/// that did not time out or run out of memory. Problems that differ only in these are the same contest, and get the same (stored) solution.
+/// The `risk_limit` and `error_rates` are kept, as the solution's `sample_sizes` (or an error if they are invalid) come from them.
+pub fn problem_hash(problem:&RaireProblem) -> String {
+    let canonical = RaireProblem{ metadata: serde_json::Value::Null, time_limit_seconds: None, max_memory_mb: None, ..problem.clone() };
+    problem_sha256(&canonical)
+}
+
+/// Whether a solution is worth keeping. One that ran out of time or memory (or was cancelled) may be different given more.
+fn should_store(solution:&RaireSolution) -> bool {
+    match &solution.solution {
+        Ok(result) => !(result.partial || result.fallback || result.warning_trim_timed_out),
+        Err(RaireError::TimeoutCheckingWinner|RaireError::TimeoutFindingAssertions(_)|RaireError::TimeoutTrimmingAssertions|RaireError::Cancelled{..}|RaireError::OutOfMemoryBudget{..}|RaireError::InvalidSnapshot(_)) => false,
+        Err(_) => true,
+    }
+}
+
+pub struct SolutionStore {
+    directory : PathBuf,
+    /// When there are more than this many solutions, the least recently used are removed.
+    max_entries : usize,
+    /// Solutions not used for this long are removed.
+    max_age : Option<Duration>,
+    /// Held while changing the directory, so evictions do not race.
+    lock : Mutex<()>,
+}
+
+impl SolutionStore {
+    /// Use the given directory, creating it if needed.
+    pub fn new(directory:PathBuf,max_entries:usize,max_age:Option<Duration>) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&directory)?;
+        Ok(SolutionStore{ directory, max_entries, max_age, lock: Mutex::new(()) })
+    }
+
+    fn path(&self,hash:&str) -> Option<PathBuf> {
+        // the hash comes from a URL, so make sure it cannot name some other file.
+        if hash.len()==64 && hash.chars().all(|c|c.is_ascii_hexdigit()) { Some(self.directory.join(format!("{}.json",hash.to_ascii_lowercase()))) } else { None }
+    }
+
+    /// The stored solution for the problem with the given hash, if any, marking it as recently used.
+    pub fn get(&self,hash:&str) -> Option<RaireSolution> {
+        let path = self.path(hash)?;
+        let _lock = self.lock.lock().unwrap();
+        let file = File::options().append(true).read(true).open(&path).ok()?;
+        let _ = file.set_modified(SystemTime::now());
+        match serde_json::from_reader(std::io::BufReader::new(file)) {
+            Ok(solution) => Some(solution),
+            Err(e) => { tracing::warn!("Ignoring unreadable stored solution {} : {}",path.display(),e); None }
+        }
+    }
+
+    /// Like [Self::get], but for the given problem, with the given hash, and with its metadata, as stored solutions have the
+    /// metadata of the problem first solved.
+    pub fn get_for_problem(&self,problem:&RaireProblem,hash:&str) -> Option<RaireSolution> {
+        Some(RaireSolution{ metadata: problem.metadata.clone(), ..self.get(hash)? })
+    }
+
+    /// Where to keep snapshots of the search for the problem with the given hash.
+    pub fn snapshots(&self,hash:&str) -> Option<Arc<SnapshotFile>> {
+        Some(Arc::new(SnapshotFile::new(self.path(hash)?.with_extension("snapshot"))))
+    }
+
+    /// Store the solution to the problem with the given hash, unless it timed out, removing old solutions if needed.
+    /// If the solution is that its snapshot could not be resumed from (e.g. it was from an older version), the snapshot is removed.
+    pub fn put(&self,hash:&str,solution:&RaireSolution) {
+        let Some(path) = self.path(hash) else { return; };
+        if let (Err(RaireError::InvalidSnapshot(_)),Some(snapshots)) = (&solution.solution,self.snapshots(hash)) { let _ = std::fs::remove_file(&snapshots.path); }
+        if !should_store(solution) { return; }
+        let _lock = self.lock.lock().unwrap();
+        let temporary = path.with_extension("tmp"); // so a reader never sees a partly written file.
+        let written = File::create(&temporary).map_err(|e|e.to_string()).and_then(|file|serde_json::to_writer(std::io::BufWriter::new(file),solution).map_err(|e|e.to_string()));
+        if let Err(e) = written.and_then(|_|std::fs::rename(&temporary,&path).map_err(|e|e.to_string())) {
+            tracing::warn!("Could not store solution {} : {}",path.display(),e);
+            let _ = std::fs::remove_file(&temporary);
+            return;
+        }
+        self.evict();
+    }
+
+    /// The stored solutions, with when each was last used, most recently used first.
+    fn entries(&self) -> Vec<(PathBuf,SystemTime)> {
+        let Ok(dir) = std::fs::read_dir(&self.directory) else { return vec![]; };
+        let mut entries : Vec<(PathBuf,SystemTime)> = dir.filter_map(|e|e.ok()).map(|e|e.path()).filter(|p|p.extension().is_some_and(|e|e=="json")).filter_map(|p|{
+            let modified = p.metadata().and_then(|m|m.modified()).ok()?;
+            Some((p,modified))
+        }).collect();
+        entries.sort_by(|(_,t1),(_,t2)|t2.cmp(t1));
+        entries
+    }
+
+    /// Remove solutions beyond the most recently used `max_entries`, and those older than `max_age`. The lock should be held.
+    fn evict(&self) {
+        let now = SystemTime::now();
+        for (i,(path,modified)) in self.entries().into_iter().enumerate() {
+            let too_old = self.max_age.is_some_and(|max_age|now.duration_since(modified).is_ok_and(|age|age>max_age));
+            if i>=self.max_entries || too_old { let _ = std::fs::remove_file(path); }
+        }
+    }
+
+    /// Remove all stored solutions, and any snapshots, returning how many solutions there were.
+    pub fn purge(&self) -> usize {
+        let _lock = self.lock.lock().unwrap();
+        if let Ok(dir) = std::fs::read_dir(&self.directory) {
+            for path in dir.filter_map(|e|e.ok()).map(|e|e.path()).filter(|p|p.extension().is_some_and(|e|e=="snapshot")) { let _ = std::fs::remove_file(path); }
+        }
+        self.entries().into_iter().filter(|(path,_)|std::fs::remove_file(path).is_ok()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(risk_limit:Option<f64>) -> RaireProblem {
+        let mut problem : RaireProblem = serde_json::from_value(serde_json::json!({
+            "metadata": { "candidates": ["Alice", "Bob", "Chuan","Diego" ] },
+            "num_candidates": 4,
+            "votes": [
+                { "n": 5000, "prefs": [ 2, 1, 0 ] },
+                { "n": 1000, "prefs": [ 1, 2, 3 ] },
+                { "n": 1500, "prefs": [ 3, 0 ] },
+                { "n": 4000, "prefs": [ 0, 3 ] },
+                { "n": 2000, "prefs": [ 3 ]  }
+            ],
+            "winner": 2,
+            "audit": { "type": "OneOnMargin", "total_auditable_ballots": 13500  }
+        })).unwrap();
+        problem.risk_limit = risk_limit;
+        problem
+    }
+
+    /// Solve the problem as the server does, using the store if it has the solution.
+    fn solve(store:&SolutionStore,problem:RaireProblem) -> RaireSolution {
+        let hash = problem_hash(&problem);
+        if let Some(solution) = store.get_for_problem(&problem,&hash) { return solution; }
+        let solution = problem.solve();
+        store.put(&hash,&solution);
+        solution
+    }
+
+    #[test]
+    /// Test that the same contest at different risk limits gets sample sizes for its own risk limit, not a stored one.
+    fn test_stored_solution_depends_on_risk_limit() {
+        let directory = std::env::temp_dir().join(format!("raire-store-test-{}",std::process::id()));
+        let store = SolutionStore::new(directory.clone(),100,None).unwrap();
+        let at_5_percent = solve(&store,problem(Some(0.05)));
+        assert_eq!(Some(0.05),at_5_percent.sample_sizes.as_ref().map(|s|s.risk_limit));
+        let at_1_percent = solve(&store,problem(Some(0.01)));
+        assert_eq!(Some(0.01),at_1_percent.sample_sizes.as_ref().map(|s|s.risk_limit));
+        assert!(solve(&store,problem(None)).sample_sizes.is_none());
+        assert!(solve(&store,problem(Some(1.5))).solution.is_err());
+        assert_eq!(at_5_percent.sample_sizes.map(|s|s.risk_limit),solve(&store,problem(Some(0.05))).sample_sizes.map(|s|s.risk_limit));
+        assert_eq!(4,store.purge());
+        let _ = std::fs::remove_dir_all(directory);
+    }
+}
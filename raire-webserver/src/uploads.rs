@@ -0,0 +1,312 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Cast vote records uploaded as exported by election systems, so that a client need not convert them into a RAIRE problem itself.
+//!
+//! A client uploads the files of an export as `multipart/form-data`, is told the format detected and the contests in it, and then
+//! submits a job for one of the contests. The files are kept in a temporary folder, as some exports (Dominion's and Hart's) are several files.
+//! The files of an export may be uploaded loose, as from a file input allowing several files, or in zip archives, which are unpacked.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
+use raire::ballot_interpretation::BallotInterpretationRules;
+use raire::irv::BallotPaperCount;
+use raire::RaireProblem;
+use utilities::parse::ParsedContest;
+use utilities::parse::blt::read_blt;
+use utilities::parse::csv::{read_csv_ballots, CsvBallotFormat};
+use utilities::parse::dominion::{read_dominion_contest, DominionManifests, DominionOptions};
+use utilities::parse::hart::{hart_contest_names, is_hart_cvr, read_hart_contest};
+use utilities::parse::nist_cdf::{cdf_contest_names, read_cdf_file, read_cdf_report};
+use utilities::parse::preflib::read_preflib;
+use crate::limits::ApiError;
+
+/// How long an upload is kept for a client to submit jobs from it.
+const UPLOAD_RETENTION : Duration = Duration::from_secs(3600);
+
+/// The most bytes the zip archives in an upload may unpack to, so that a small archive cannot fill the disk.
+const MAX_UNPACKED_BYTES : u64 = 1<<30;
+
+/// The formats of exports that can be uploaded.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize)]
+pub enum UploadFormat {
+    /// A Dominion export, uploaded as its separate files or a zip archive of them, including `ContestManifest.json`, `CandidateManifest.json` and the `CvrExport*.json` files.
+    Dominion,
+    /// A Hart InterCivic Verity export, uploaded as its separate XML files, one per ballot sheet, or a zip archive of them.
+    Hart,
+    /// A NIST CVR Common Data Format CastVoteRecordReport, a `.json` or `.xml` file.
+    NistCdf,
+    /// A `.csv` file with one ballot per row, in the default layout of `raire convert`.
+    Csv,
+    /// A `.blt` file.
+    Blt,
+    /// A PrefLib `.soc`, `.soi`, `.toc` or `.toi` file.
+    Preflib,
+}
+
+/// What a client is told about an upload.
+#[derive(Clone,Debug,Serialize)]
+pub struct UploadStatus {
+    pub id : u64,
+    pub format : UploadFormat,
+    /// The names of the files uploaded.
+    pub files : Vec<String>,
+    /// The names of the contests in the upload, one of which is given when submitting a job.
+    pub contests : Vec<String>,
+}
+
+/// A request to solve a contest in an upload.
+#[derive(Clone,Debug,Default,Deserialize)]
+pub struct UploadJobRequest {
+    /// The contest to solve. May be omitted if the upload has only one contest.
+    #[serde(default)]
+    pub contest : Option<String>,
+    /// The audit type. Default a ballot comparison audit with difficulty one on the diluted margin, of all the ballots in the contest.
+    #[serde(default)]
+    pub audit : Option<Audit>,
+}
+
+struct Upload {
+    status : UploadStatus,
+    folder : PathBuf,
+    created : Instant,
+}
+
+impl Drop for Upload {
+    fn drop(&mut self) { let _ = std::fs::remove_dir_all(&self.folder); }
+}
+
+/// Where to read a contest of an upload from, kept separately from the [Upload] so that reading does not hold the lock.
+struct UploadSource {
+    format : UploadFormat,
+    folder : PathBuf,
+    files : Vec<String>,
+    contests : Vec<String>,
+}
+
+/// The uploads to this server, each in a folder under a temporary folder for this process.
+pub struct Uploads {
+    uploads : Mutex<HashMap<u64,Upload>>,
+    next_id : Mutex<u64>,
+    folder : PathBuf,
+}
+
+impl Default for Uploads {
+    fn default() -> Self {
+        Uploads{ uploads: Mutex::new(HashMap::new()), next_id: Mutex::new(1), folder: std::env::temp_dir().join(format!("raire-uploads-{}",std::process::id())) }
+    }
+}
+
+fn invalid_upload(message:String) -> ApiError {
+    ApiError{ status: StatusCode::UNPROCESSABLE_ENTITY, error: "InvalidUpload", message, limit: None }
+}
+
+fn no_such_upload(id:u64) -> ApiError {
+    ApiError{ status: StatusCode::NOT_FOUND, error: "NoSuchUpload", message: format!("There is no upload {}",id), limit: None }
+}
+
+impl Uploads {
+    /// Forget uploads made long ago, removing their files.
+    fn forget_old_uploads(&self) {
+        self.uploads.lock().unwrap().retain(|_,upload|upload.created.elapsed()<UPLOAD_RETENTION);
+    }
+
+    /// Keep uploaded files, given as (file name, contents), unpacking zip archives, detecting their format and listing their contests.
+    /// This reads the files, so should not be called from an async task.
+    pub fn add(&self,files:Vec<(String,Vec<u8>)>) -> Result<UploadStatus,ApiError> {
+        self.forget_old_uploads();
+        let files = unpack_files(files).map_err(invalid_upload)?;
+        let format = detect_format(&files).map_err(invalid_upload)?;
+        let names : Vec<String> = files.iter().map(|(name,_)|name.clone()).collect();
+        let id = { let mut next_id = self.next_id.lock().unwrap(); *next_id+=1; *next_id-1 };
+        let folder = self.folder.join(id.to_string());
+        let mut upload = Upload{ status: UploadStatus{ id, format, files: names, contests: vec![] }, folder, created: Instant::now() }; // removes the folder if dropped on failure.
+        let write = || -> std::io::Result<()> {
+            std::fs::create_dir_all(&upload.folder)?;
+            for (name,contents) in &files { std::fs::write(upload.folder.join(name),contents)?; }
+            Ok(())
+        };
+        write().map_err(|e|ApiError{ status: StatusCode::INTERNAL_SERVER_ERROR, error: "UploadNotKept", message: format!("The upload could not be kept : {}",e), limit: None })?;
+        upload.status.contests = list_contests(format,&upload.folder,&upload.status.files).map_err(|e|invalid_upload(format!("The {:?} upload could not be read : {:#}",format,e)))?;
+        let status = upload.status.clone();
+        self.uploads.lock().unwrap().insert(id,upload);
+        Ok(status)
+    }
+
+    /// The status of an upload.
+    pub fn status(&self,id:u64) -> Option<UploadStatus> {
+        self.forget_old_uploads();
+        self.uploads.lock().unwrap().get(&id).map(|upload|upload.status.clone())
+    }
+
+    /// Remove an upload and its files, returning its status.
+    pub fn remove(&self,id:u64) -> Option<UploadStatus> {
+        self.uploads.lock().unwrap().remove(&id).map(|upload|upload.status.clone())
+    }
+
+    /// Make the RAIRE problem for a contest in an upload. This reads the files, so should not be called from an async task.
+    pub fn problem(&self,id:u64,request:&UploadJobRequest) -> Result<RaireProblem,ApiError> {
+        let source = self.uploads.lock().unwrap().get(&id).map(|upload|UploadSource{ format: upload.status.format, folder: upload.folder.clone(), files: upload.status.files.clone(), contests: upload.status.contests.clone() }).ok_or_else(||no_such_upload(id))?;
+        let contest = match (&request.contest,source.contests.as_slice()) {
+            (Some(contest),_) => contest.clone(),
+            (None,[contest]) => contest.clone(),
+            (None,_) => return Err(invalid_upload(format!("The upload has {} contests, so the contest must be given",source.contests.len()))),
+        };
+        let parsed = read_contest(&source,&contest).map_err(|e|invalid_upload(format!("Contest {} could not be read : {:#}",contest,e)))?;
+        let audit = request.audit.clone().unwrap_or_else(||Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(parsed.num_ballots()) }));
+        parsed.to_raire_problem(audit).map_err(|e|invalid_upload(format!("Contest {} could not be made into a RAIRE problem : {:#}",contest,e)))
+    }
+}
+
+/// The lower case extension of a file name.
+fn extension(file:&str) -> String {
+    Path::new(file).extension().and_then(|e|e.to_str()).unwrap_or("").to_ascii_lowercase()
+}
+
+/// The format of an upload, given its files.
+fn detect_format(files:&[(String,Vec<u8>)]) -> Result<UploadFormat,String> {
+    if let Some((archive,_)) = files.iter().find(|(f,_)|matches!(extension(f).as_str(),"zip"|"gz"|"tgz"|"tar"|"7z")) {
+        return Err(format!("{} is an archive, which is not accepted; only zip archives uploaded directly are unpacked",archive));
+    }
+    if files.iter().any(|(f,_)|f.eq_ignore_ascii_case("ContestManifest.json")) { return Ok(UploadFormat::Dominion); }
+    if files.iter().all(|(f,contents)|extension(f)=="xml" && is_hart_cvr(contents)) { return Ok(UploadFormat::Hart); }
+    let [(file,_)] = files else { return Err(format!("Expecting a single file, a Dominion export containing ContestManifest.json, or Hart CVR XML files, not {} files",files.len())); };
+    match extension(file).as_str() {
+        "json" | "xml" => Ok(UploadFormat::NistCdf),
+        "csv" => Ok(UploadFormat::Csv),
+        "blt" => Ok(UploadFormat::Blt),
+        "soc" | "soi" | "toc" | "toi" => Ok(UploadFormat::Preflib),
+        _ => Err(format!("The format of {} is not recognised from its extension",file)),
+    }
+}
+
+/// The name of the contest in a single contest file, its file name without the extension.
+fn file_stem(file:&str) -> String {
+    Path::new(file).file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// The contests in an upload. Single contest formats are read, so that a client finds out at once if they cannot be.
+fn list_contests(format:UploadFormat,folder:&Path,files:&[String]) -> anyhow::Result<Vec<String>> {
+    match format {
+        UploadFormat::Dominion => Ok(DominionManifests::read(folder)?.contests.into_iter().map(|c|c.description).collect()),
+        UploadFormat::Hart => hart_contest_names(folder),
+        UploadFormat::NistCdf => Ok(cdf_contest_names(&read_cdf_report(&folder.join(&files[0]))?)),
+        _ => {
+            let source = UploadSource{ format, folder: folder.to_path_buf(), files: files.to_vec(), contests: vec![] };
+            let name = file_stem(&files[0]);
+            read_contest(&source,&name)?;
+            Ok(vec![name])
+        }
+    }
+}
+
+/// Read a contest from an upload.
+fn read_contest(source:&UploadSource,contest:&str) -> anyhow::Result<ParsedContest> {
+    let rules = BallotInterpretationRules::default();
+    let file = || source.folder.join(&source.files[0]);
+    match source.format {
+        UploadFormat::Dominion => read_dominion_contest(&source.folder,contest,&DominionOptions::default()),
+        UploadFormat::Hart => read_hart_contest(&source.folder,contest,&rules),
+        UploadFormat::NistCdf => read_cdf_file(&file(),contest,&rules),
+        UploadFormat::Csv => read_csv_ballots(File::open(file())?,contest,&CsvBallotFormat::default()),
+        UploadFormat::Blt => read_blt(File::open(file())?,contest,&rules),
+        UploadFormat::Preflib => read_preflib(BufReader::new(File::open(file())?),contest,&rules),
+    }
+}
+
+/// The uploaded files, given as (file name, contents), with zip archives replaced by the files in them.
+/// File names are reduced to their last path component, and must be distinct and not start with `.`.
+/// Directories in an archive are ignored, as are hidden files such as the `._` files of macOS.
+fn unpack_files(uploaded:Vec<(String,Vec<u8>)>) -> Result<Vec<(String,Vec<u8>)>,String> {
+    let mut files : Vec<(String,Vec<u8>)> = vec![];
+    let mut add = |path:&str,contents:Vec<u8>| -> Result<(),String> {
+        let name = path.rsplit(['/','\\']).next().unwrap_or("");
+        if name.is_empty() || name.starts_with('.') { return Err(format!("The file name {} is not allowed",path)); }
+        if files.iter().any(|(n,_)|n==name) { return Err(format!("The file {} is uploaded more than once",name)); }
+        files.push((name.to_string(),contents));
+        Ok(())
+    };
+    let mut unpacked_bytes : u64 = 0;
+    for (path,contents) in uploaded {
+        if extension(&path)!="zip" { add(&path,contents)?; continue; }
+        let mut archive = zip::ZipArchive::new(Cursor::new(contents)).map_err(|e|format!("{} is not a valid zip archive : {}",path,e))?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e|format!("{} could not be read : {}",path,e))?;
+            if !entry.is_file() { continue; }
+            let Some(name) = entry.enclosed_name() else { return Err(format!("{} contains a file outside the archive",path)); };
+            if name.iter().any(|c|c.to_string_lossy().starts_with('.')||c=="__MACOSX") { continue; }
+            let mut unpacked = vec![];
+            (&mut entry).take(MAX_UNPACKED_BYTES-unpacked_bytes+1).read_to_end(&mut unpacked).map_err(|e|format!("{} in {} could not be unpacked : {}",name.display(),path,e))?;
+            unpacked_bytes+=unpacked.len() as u64;
+            if unpacked_bytes>MAX_UNPACKED_BYTES { return Err(format!("The archives unpack to more than the limit of {} bytes",MAX_UNPACKED_BYTES)); }
+            add(&name.to_string_lossy(),unpacked)?;
+        }
+    }
+    if files.is_empty() { return Err("No files were uploaded".to_string()); }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(names:&[&str],contents:&str) -> Vec<(String,Vec<u8>)> { names.iter().map(|name|(name.to_string(),contents.as_bytes().to_vec())).collect() }
+
+    #[test]
+    fn test_detect_format() {
+        let hart = r#"<?xml version="1.0" encoding="utf-8"?><Cvr xmlns="http://tempuri.org/CVRDesign.xsd"><Contests/></Cvr>"#;
+        assert_eq!(Ok(UploadFormat::Hart),detect_format(&files(&["cvr_1.xml","cvr_2.xml"],hart)));
+        assert_eq!(Ok(UploadFormat::Hart),detect_format(&files(&["cvr_1.xml"],hart)));
+        assert_eq!(Ok(UploadFormat::NistCdf),detect_format(&files(&["cvr.xml"],"<CastVoteRecordReport/>")));
+        assert!(detect_format(&files(&["cvr_1.xml","cvr_2.xml"],"<CastVoteRecordReport/>")).is_err());
+        assert_eq!(Ok(UploadFormat::Dominion),detect_format(&files(&["ContestManifest.json","CandidateManifest.json","CvrExport_0.json"],"{}")));
+        assert_eq!(Ok(UploadFormat::Csv),detect_format(&files(&["ballots.csv"],"")));
+        assert!(detect_format(&files(&["export.tar"],"")).unwrap_err().contains("archive"));
+        assert!(detect_format(&files(&["ContestManifest.json","CvrExport.zip"],"")).unwrap_err().contains("archive"));
+    }
+
+    /// A zip archive containing the given files, all with the given contents.
+    fn zip(names:&[&str],contents:&str) -> Vec<u8> {
+        use std::io::Write;
+        let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+        for name in names {
+            if name.ends_with('/') { writer.add_directory(*name,zip::write::SimpleFileOptions::default()).unwrap(); continue; }
+            writer.start_file(*name,zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    /// Zip archives are unpacked, keeping the files in them under their own names.
+    fn test_unpack_files() {
+        let names = |files:&[(String,Vec<u8>)]| files.iter().map(|(name,_)|name.clone()).collect::<Vec<_>>();
+        let export = zip(&["export/","export/ContestManifest.json","export/CandidateManifest.json","export/CvrExport_0.json","__MACOSX/export/._CvrExport_0.json"],"{}");
+        let unpacked = unpack_files(vec![("Export.zip".to_string(),export.clone())]).unwrap();
+        assert_eq!(vec!["ContestManifest.json","CandidateManifest.json","CvrExport_0.json"],names(&unpacked));
+        assert_eq!(b"{}".to_vec(),unpacked[0].1);
+        assert_eq!(Ok(UploadFormat::Dominion),detect_format(&unpacked));
+        let unpacked = unpack_files(vec![("ContestManifest.json".to_string(),b"{}".to_vec()),("CvrExport.zip".to_string(),zip(&["CvrExport_0.json","CvrExport_1.json"],"{}"))]).unwrap();
+        assert_eq!(vec!["ContestManifest.json","CvrExport_0.json","CvrExport_1.json"],names(&unpacked));
+        assert_eq!(vec!["ballots.csv"],names(&unpack_files(files(&["C:\\votes\\ballots.csv"],"")).unwrap()));
+        assert!(unpack_files(vec![("Export.zip".to_string(),export.clone()),("ContestManifest.json".to_string(),vec![])]).unwrap_err().contains("more than once"));
+        assert!(unpack_files(vec![("Export.zip".to_string(),b"not a zip".to_vec())]).unwrap_err().contains("not a valid zip"));
+        assert!(unpack_files(vec![("Export.zip".to_string(),zip(&["../ballots.csv"],""))]).unwrap_err().contains("outside"));
+        assert!(unpack_files(files(&[".hidden"],"")).is_err());
+        assert!(unpack_files(vec![]).is_err());
+    }
+}
@@ -11,8 +11,18 @@
 //! The types of assertions about the election, generally relative standings of various candidates
 
 
+#[cfg(feature = "std")]
 use crate::audit_type::{AssertionDifficulty, AuditType};
-use crate::irv::{BallotPaperCount, CandidateIndex, Votes};
+use crate::irv::{BallotPaperCount, CandidateIndex, CandidateSet};
+#[cfg(feature = "std")]
+use crate::irv::Votes;
+use crate::RaireError;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use alloc::boxed::Box;
+use core::cmp::Ordering;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -31,15 +41,16 @@ pub struct NotEliminatedBefore {
 
 impl NotEliminatedBefore {
     /// compute the difficulty and margin for this assertion.
+    #[cfg(feature = "std")]
     pub fn difficulty<A:AuditType>(&self, votes:&Votes, audit:&A) -> (AssertionDifficulty,BallotPaperCount) {
-        let tally_winner = votes.first_preference_only_tally(self.winner);
-        let tallies = votes.restricted_tallies(&vec![self.winner,self.loser]);
-        let tally_loser = tallies[1];
-        let difficulty = audit.difficulty(tally_winner, tally_loser); // active paper count = tally_winner+tally_loser for historical reenactment
+        let neb_tallies = |votes:&Votes|(votes.first_preference_only_tally(self.winner),votes.restricted_tallies(&[self.winner,self.loser])[1]);
+        let (tally_winner,tally_loser) = neb_tallies(votes);
+        let difficulty = stratified_difficulty(votes,audit,(tally_winner,tally_loser),neb_tallies); // active paper count = tally_winner+tally_loser for historical reenactment
         (difficulty,if tally_winner>=tally_loser {tally_winner-tally_loser} else {BallotPaperCount(0)})
     }
 
     /// Find the NEB assertion that best rules out the given candidate being the next eliminated, with later_in_pi being the other continuing candidates.
+    #[cfg(feature = "std")]
     pub fn find_best_assertion<A:AuditType>(c:CandidateIndex, later_in_pi:&[CandidateIndex], votes:&Votes, audit:&A) -> Option<AssertionAndDifficulty> {
         let mut best_difficulty = f64::MAX;
         let mut best_assertion : Option<NotEliminatedBefore> = None;
@@ -63,11 +74,12 @@ impl NotEliminatedBefore {
             }
         }
         if let Some(assertion) = best_assertion {
-            Some(AssertionAndDifficulty { assertion:Assertion::NEB(assertion), margin: best_margin, difficulty:best_difficulty })
+            Some(AssertionAndDifficulty { assertion:Assertion::NEB(assertion), margin: best_margin, difficulty:best_difficulty, tallies: None })
         } else {None}
     }
 
     /// Find the NEB assertion that best rules out the given candidate being the next eliminated, with later_in_pi being the other continuing candidates.
+    #[cfg(feature = "std")]
     pub fn find_best_assertion_using_cache(c:CandidateIndex, later_in_pi:&[CandidateIndex],votes:&Votes,cache:&NotEliminatedBeforeCache) -> Option<AssertionAndDifficulty> {
         let mut best_difficulty = f64::MAX;
         let mut best_assertion : Option<NotEliminatedBefore> = None;
@@ -91,7 +103,7 @@ impl NotEliminatedBefore {
             }
         }
         if let Some(assertion) = best_assertion {
-            Some(AssertionAndDifficulty { assertion:Assertion::NEB(assertion), margin: best_margin, difficulty: best_difficulty })
+            Some(AssertionAndDifficulty { assertion:Assertion::NEB(assertion), margin: best_margin, difficulty: best_difficulty, tallies: None })
         } else {None}
     }
 
@@ -104,10 +116,12 @@ impl NotEliminatedBefore {
 
 /// Pre-compute all NEB entries to prevent duplicate computations.
 /// Store difficulty and margin
+#[cfg(feature = "std")]
 pub struct NotEliminatedBeforeCache {
     pub cache : Vec<Vec<(AssertionDifficulty,BallotPaperCount)>>
 }
 
+#[cfg(feature = "std")]
 impl NotEliminatedBeforeCache {
     /// Get the cached difficulty for given winner and loser.
     pub fn difficulty(&self,entry:NotEliminatedBefore) -> (AssertionDifficulty,BallotPaperCount) {
@@ -126,6 +140,13 @@ impl NotEliminatedBeforeCache {
     }
 }
 
+/// The difficulty of an assertion with the given (winner,loser) tallies, computed from each stratum's votes with `tallies` if the votes are stratified.
+#[cfg(feature = "std")]
+fn stratified_difficulty<A:AuditType>(votes:&Votes,audit:&A,(tally_winner,tally_loser):(BallotPaperCount,BallotPaperCount),tallies:impl Fn(&Votes)->(BallotPaperCount,BallotPaperCount)) -> AssertionDifficulty {
+    if votes.strata().is_empty() { audit.difficulty(tally_winner,tally_loser) }
+    else { audit.stratified_difficulty(&votes.strata().iter().map(|stratum|tallies(&stratum.votes)).collect::<Vec<_>>()) }
+}
+
 /// An elimination order will be either compatible with a suffix or not.
 /// A suffix of an elimination order may be compatible or not or it may just not have enough information to be sure.
 #[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize,Deserialize)]
@@ -158,6 +179,7 @@ pub struct SpecificLoserAmongstContinuing {
     pub losers : Vec<CandidateIndex>,
 }
 
+#[cfg(feature = "std")]
 impl SpecificLoserAmongstContinuing {
     pub fn difficulty<A:AuditType>(&self, votes:&Votes, audit:&A) -> AssertionDifficulty {
         let tallies = votes.restricted_tallies(&self.continuing);
@@ -175,6 +197,9 @@ impl SpecificLoserAmongstContinuing {
 /// those in _remaining_ have been removed.
 ///
 /// In particular, this means that _winner_ can not be the next candidate eliminated.
+/// This is a statement about one-at-a-time elimination; under [crate::irv::EliminationRule::BatchElimination]
+/// the set _remaining_ may never occur in the official count, but the assertion is still about the same ballots and
+/// still rules out the same (one-at-a-time) elimination orders, which determine the same winner.
 ///
 /// This was called IRV in the original paper.
 #[derive(Debug,Clone,PartialEq,Eq,Serialize,Deserialize)]
@@ -186,18 +211,23 @@ pub struct NotEliminatedNext {
 }
 
 impl NotEliminatedNext {
+    #[cfg(feature = "std")]
     pub fn difficulty<A:AuditType>(&self, votes:&Votes, audit:&A) -> AssertionDifficulty {
-        let tallies = votes.restricted_tallies(&self.continuing);
-        let mut tally_winner = BallotPaperCount(usize::MAX);
-        let mut tally_loser = BallotPaperCount(0);
-        for i in 0..self.continuing.len() {
-            if self.loser==self.continuing[i] { tally_loser=tallies[i]; }
-            else if self.winner==self.continuing[i] { tally_winner=tallies[i]; }
-        }
-        audit.difficulty(tally_winner, tally_loser) // active paper count = tallies.iter().cloned().sum() for historical reenactment
+        let nen_tallies = |votes:&Votes|{
+            let tallies = votes.restricted_tallies(&self.continuing);
+            let mut tally_winner = BallotPaperCount(usize::MAX);
+            let mut tally_loser = BallotPaperCount(0);
+            for i in 0..self.continuing.len() {
+                if self.loser==self.continuing[i] { tally_loser=tallies[i]; }
+                else if self.winner==self.continuing[i] { tally_winner=tallies[i]; }
+            }
+            (tally_winner,tally_loser)
+        };
+        stratified_difficulty(votes,audit,nen_tallies(votes),nen_tallies) // active paper count = tallies.iter().cloned().sum() for historical reenactment
     }
 
     /// Find the best NEN cote to rule out winner from being the next eliminated when only the given candidates are continuing
+    #[cfg(feature = "std")]
     pub fn find_best_difficulty<A:AuditType>(votes:&Votes, audit:&A, continuing:&[CandidateIndex], winner:CandidateIndex) -> Option<AssertionAndDifficulty> {
         let tallies = votes.restricted_tallies(&continuing);
         let mut tally_winner = BallotPaperCount(usize::MAX);
@@ -209,18 +239,17 @@ impl NotEliminatedNext {
             else if tallies[i]<=tally_loser { best_loser=Some(continuing[i]);  tally_loser=tallies[i]; }
         }
         if let Some(loser) = best_loser {
-            let difficulty = audit.difficulty(tally_winner, tally_loser);  // active paper count = tallies.iter().cloned().sum() for historical reenactment
             let margin = if tally_winner>=tally_loser {tally_winner-tally_loser} else {BallotPaperCount(0)};
-            let mut continuing = continuing.to_vec();
-            continuing.sort_unstable_by_key(|c|c.0); // important to make it canonical so that equality checks of assertions work, and so is_continuing can use a binary search. Also sorted is easier to read.
+            let continuing = continuing.to_vec().into(); // sorted, which is canonical so that equality checks of assertions work. Also sorted is easier to read.
             let assertion = NotEliminatedNext { winner, loser, continuing };
-            Some(AssertionAndDifficulty { assertion:Assertion::NEN(assertion), margin, difficulty })
+            let difficulty = if votes.strata().is_empty() { audit.difficulty(tally_winner, tally_loser) } else { assertion.difficulty(votes,audit) };  // active paper count = tallies.iter().cloned().sum() for historical reenactment
+            Some(AssertionAndDifficulty { assertion:Assertion::NEN(assertion), margin, difficulty, tallies: None })
         } else {None}
     }
 
     /// See if the given candidate is in the continuing list
     fn is_continuing(&self,c:CandidateIndex) -> bool {
-        self.continuing.binary_search_by_key(&c.0,|e|e.0).is_ok()
+        self.continuing.includes(c)
     }
 
     /// see if the assertion doesn't rule out the given elimination order suffix.
@@ -240,6 +269,51 @@ impl NotEliminatedNext {
     }
 }
 
+/// An [Assertion] preprocessed so that checking it against an elimination order suffix is mostly bit operations on
+/// the set of candidates in the suffix, rather than scans of the suffix and the continuing candidates.
+/// This is used when building trees of elimination orders, where every relevant assertion is checked at every node.
+#[derive(Clone,Copy,Debug)]
+pub enum PrecomputedAssertion {
+    NEB{ winner:CandidateIndex, loser:CandidateIndex },
+    NEN{ winner:CandidateIndex, continuing:CandidateSet, num_continuing:usize },
+}
+
+impl PrecomputedAssertion {
+    /// None if the assertion mentions a candidate too large for a [CandidateSet].
+    pub fn new(assertion:&Assertion) -> Option<Self> {
+        match assertion {
+            Assertion::NEB(neb) => if neb.winner.0.max(neb.loser.0)<CandidateSet::MAX_CANDIDATES { Some(PrecomputedAssertion::NEB{winner:neb.winner,loser:neb.loser}) } else { None },
+            Assertion::NEN(nen) => match nen.continuing.as_set() {
+                Some(continuing) if nen.winner.0.max(nen.loser.0)<CandidateSet::MAX_CANDIDATES => Some(PrecomputedAssertion::NEN{winner:nen.winner,continuing,num_continuing:nen.continuing.len()}),
+                _ => None,
+            },
+        }
+    }
+
+    /// The same as [Assertion::ok_elimination_order_suffix], given `suffix_set`, the set of candidates in `elimination_order_suffix`.
+    pub fn ok_elimination_order_suffix(&self,elimination_order_suffix:&[CandidateIndex],suffix_set:CandidateSet) -> EffectOfAssertionOnEliminationOrderSuffix {
+        match *self {
+            PrecomputedAssertion::NEB{winner,loser} => match (suffix_set.contains(winner),suffix_set.contains(loser)) {
+                (false,false) => EffectOfAssertionOnEliminationOrderSuffix::NeedsMoreDetail,
+                (true,false) => EffectOfAssertionOnEliminationOrderSuffix::Ok, // the loser was eliminated before anyone in the suffix.
+                (false,true) => EffectOfAssertionOnEliminationOrderSuffix::Contradiction, // the winner was eliminated before anyone in the suffix.
+                (true,true) => check_winner_eliminated_after_loser(elimination_order_suffix,winner,loser),
+            },
+            PrecomputedAssertion::NEN{winner,continuing,num_continuing} => {
+                let len = elimination_order_suffix.len();
+                if len>num_continuing { // only the last num_continuing matter.
+                    let suffix = &elimination_order_suffix[len-num_continuing..];
+                    if suffix[0]==winner && suffix.iter().all(|&c|continuing.contains(c)) { EffectOfAssertionOnEliminationOrderSuffix::Contradiction } else { EffectOfAssertionOnEliminationOrderSuffix::Ok }
+                } else if !suffix_set.is_subset(continuing) { EffectOfAssertionOnEliminationOrderSuffix::Ok } // the continuing candidates are wrong.
+                else if len==num_continuing { // exactly the continuing candidates. The winner cannot be the first eliminated.
+                    if elimination_order_suffix[0]==winner { EffectOfAssertionOnEliminationOrderSuffix::Contradiction } else { EffectOfAssertionOnEliminationOrderSuffix::Ok }
+                } else if suffix_set.contains(winner) { EffectOfAssertionOnEliminationOrderSuffix::Ok } // winner wasn't the first eliminated.
+                else { EffectOfAssertionOnEliminationOrderSuffix::NeedsMoreDetail }
+            }
+        }
+    }
+}
+
 #[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 #[serde(tag = "type")]
 pub enum Assertion {
@@ -248,12 +322,109 @@ pub enum Assertion {
 }
 
 impl Assertion {
+    /// Compute the tallies from which this assertion's margin and difficulty are derived.
+    #[cfg(feature = "std")]
+    pub fn tallies<A:AuditType>(&self,votes:&Votes,audit:&A) -> AssertionTallies {
+        let (continuing,winner,loser) = match self {
+            Assertion::NEB(neb) => (vec![neb.winner,neb.loser],neb.winner,neb.loser),
+            Assertion::NEN(nen) => (nen.continuing.to_vec(),nen.winner,nen.loser),
+        };
+        let tallies = votes.restricted_tallies(&continuing);
+        let tally = |c:CandidateIndex|continuing.iter().position(|&e|e==c).map(|i|tallies[i]).unwrap_or(BallotPaperCount(0));
+        let winner_tally = if self.is_neb() { votes.first_preference_only_tally(winner) } else { tally(winner) };
+        AssertionTallies{ winner_tally, loser_tally: tally(loser), continuing, total_auditable_ballots: audit.total_auditable_ballots() }
+    }
+
     pub fn is_neb(&self) -> bool {
         match self {
             Assertion::NEB(_) => true,
             Assertion::NEN(_) => false,
         }
     }
+
+    /// The contribution of a single ballot with the given preferences to the assertion's margin:
+    /// 1 if it counts for the winner, -1 if it counts for the loser, otherwise 0.
+    /// The margin of the assertion is the sum of this over all ballots.
+    pub fn vote_score(&self,prefs:&[CandidateIndex]) -> i32 {
+        match self {
+            Assertion::NEB(neb) => {
+                if prefs.first()==Some(&neb.winner) { 1 }
+                else if prefs.iter().position(|&c|c==neb.loser).is_some_and(|l|prefs.iter().position(|&c|c==neb.winner).is_none_or(|w|l<w)) { -1 }
+                else { 0 }
+            }
+            Assertion::NEN(nen) => match prefs.iter().find(|&&c|nen.is_continuing(c)) {
+                Some(&c) if c==nen.winner => 1,
+                Some(&c) if c==nen.loser => -1,
+                _ => 0,
+            }
+        }
+    }
+
+    /// The value of the SHANGRLA assorter for the assertion on a single ballot with the given preferences:
+    /// 1 if it counts for the winner, 0 if it counts for the loser, otherwise 1/2. That is, `(1+vote_score)/2`.
+    /// The assertion is true if and only if the mean of this over all ballots is more than 1/2.
+    pub fn assorter_value(&self,prefs:&[CandidateIndex]) -> f64 {
+        (1+self.vote_score(prefs)) as f64/2.0
+    }
+
+    /// The discrepancy for the assertion between the CVR of a ballot and the manual interpretation of the paper ballot: the number of
+    /// votes, -2 to 2, by which the CVR overstates the assertion's margin. Negative for an understatement. This is twice the difference
+    /// between the assorter values of the two.
+    pub fn discrepancy(&self,cvr:&[CandidateIndex],manual:&[CandidateIndex]) -> i8 {
+        (self.vote_score(cvr)-self.vote_score(manual)) as i8
+    }
+
+    /// The margin of the assertion with the given votes, the sum of [Assertion::vote_score] over all ballots. Negative if the assertion is false.
+    /// This is an i128, which can't overflow as [Votes::new] checks the total number of ballots fits in a usize.
+    #[cfg(feature = "std")]
+    pub fn signed_margin(&self,votes:&Votes) -> i128 {
+        votes.votes.iter().map(|v|v.n.0 as i128*self.vote_score(&v.prefs) as i128).sum()
+    }
+
+    /// Whether the assertion holds for a complete elimination order, listed from first eliminated to winner.
+    ///
+    /// This is computed directly from the definition of the assertion, independently of the code used to generate assertions,
+    /// so that it can be used to check assertions produced elsewhere.
+    pub fn is_consistent_with_full_order(&self,elimination_order:&[CandidateIndex]) -> bool {
+        let position = |c:CandidateIndex|elimination_order.iter().position(|&e|e==c);
+        match self {
+            // the winner can't be eliminated before the loser.
+            Assertion::NEB(neb) => position(neb.winner)>=position(neb.loser),
+            Assertion::NEN(nen) => {
+                if elimination_order.len()<nen.continuing.len() { return true; }
+                let remaining = &elimination_order[elimination_order.len()-nen.continuing.len()..];
+                // only says something if exactly the continuing candidates remain, in which case the winner can't be next eliminated.
+                !(remaining.iter().all(|c|nen.continuing.contains(c)) && remaining[0]==nen.winner)
+            }
+        }
+    }
+
+    /// Whether the assertion is false for every elimination order ending with the given suffix (listed from first eliminated to winner).
+    /// Candidates not in the suffix are eliminated before those in it.
+    fn contradicts_suffix(&self,elimination_order_suffix:&[CandidateIndex]) -> bool {
+        match self {
+            Assertion::NEB(neb) => match (elimination_order_suffix.iter().position(|&c|c==neb.winner),elimination_order_suffix.iter().position(|&c|c==neb.loser)) {
+                (winner,Some(loser)) => winner.is_none_or(|winner|winner<loser),
+                _ => false,
+            }
+            Assertion::NEN(nen) => elimination_order_suffix.len()>=nen.continuing.len() && !self.is_consistent_with_full_order(elimination_order_suffix),
+        }
+    }
+
+    /// A short human readable description of the assertion, in the same style as the web explainer,
+    /// e.g. `Chuan NEB Bob` or `NEN: Alice > Diego if only {Alice,Diego} remain`.
+    pub fn describe(&self,candidate_names:&[String]) -> String {
+        let name = |c:CandidateIndex|candidate_name(candidate_names,c);
+        match self {
+            Assertion::NEB(neb) => format!("{} NEB {}",name(neb.winner),name(neb.loser)),
+            Assertion::NEN(nen) => format!("NEN: {} > {} if only {{{}}} remain",name(nen.winner),name(nen.loser),nen.continuing.iter().map(|&c|name(c)).collect::<Vec<_>>().join(",")),
+        }
+    }
+}
+
+/// Get the name of a candidate from a list of names, or `#3` style if the list doesn't include the candidate.
+pub fn candidate_name(candidate_names:&[String],candidate:CandidateIndex) -> String {
+    candidate_names.get(candidate.0 as usize).cloned().unwrap_or_else(||format!("#{}",candidate.0))
 }
 
 #[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
@@ -262,6 +433,24 @@ pub struct AssertionAndDifficulty {
     /// The number of votes between the winner and loser.
     pub margin : BallotPaperCount,
     pub difficulty: f64,
+    /// The tallies the margin and difficulty were computed from, if requested with [crate::raire_algorithm::RaireOptions::include_tallies].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub tallies : Option<AssertionTallies>,
+}
+
+/// The tallies from which the margin and difficulty of an assertion are computed, so they can be checked by hand.
+/// The margin is `winner_tally-loser_tally` (or 0 if negative), and the difficulty is [AuditType::difficulty]`(winner_tally,loser_tally)`.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct AssertionTallies {
+    /// The candidates continuing when the loser's tally is counted: the winner and loser for a NEB assertion, or the continuing candidates for a NEN assertion.
+    pub continuing : Vec<CandidateIndex>,
+    /// The winner's tally: their first preferences for a NEB assertion, or their tally with only the continuing candidates for a NEN assertion.
+    pub winner_tally : BallotPaperCount,
+    /// The loser's tally with only the continuing candidates.
+    pub loser_tally : BallotPaperCount,
+    /// The total auditable ballots used by the audit type, if it uses one.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub total_auditable_ballots : Option<BallotPaperCount>,
 }
 
 
@@ -299,6 +488,22 @@ impl Assertion {
         }
     }
 
+    /// Every candidate mentioned in the assertion.
+    pub fn candidates(&self) -> Vec<CandidateIndex> {
+        match self {
+            Assertion::NEB(neb) => vec![neb.winner,neb.loser],
+            Assertion::NEN(nen) => nen.continuing.iter().copied().chain([nen.winner,nen.loser]).collect(),
+        }
+    }
+
+    /// Check every candidate mentioned is less than `num_candidates`. Assertions read from a file may not be, and
+    /// computing tallies or building trees for them would then index out of bounds.
+    pub fn validate(&self,num_candidates:u32) -> Result<(),RaireError> {
+        match self.candidates().into_iter().find(|c|c.0>=num_candidates) {
+            Some(candidate) => Err(RaireError::InvalidCandidateNumberIn{ context: format!("assertion {}",self.describe(&[])), candidate, num_candidates }),
+            None => Ok(()),
+        }
+    }
 }
 
 // Code to check what a set of assertions implies.
@@ -313,8 +518,82 @@ pub type ReverseEliminationOrder = Vec<CandidateIndex>;
 pub type ReverseEliminationOrderPrefix = Vec<CandidateIndex>;
 /// A suffix to an EliminationOrder. That is, the first entry is the winner.
 pub type EliminationOrderSuffix = Vec<CandidateIndex>;
-/// A set of candidates, listed in ascending order of CandidateIndex.
-pub type SortedCandidateSubset = Vec<CandidateIndex>;
+/// A set of candidates, listed in ascending order of CandidateIndex, such as the continuing candidates of a [NotEliminatedNext].
+///
+/// Contests with many candidates have many NEN assertions with long lists of continuing candidates, so as well as the list
+/// this keeps a [CandidateSet] bitmask of the candidates when they all fit in one, making equality, ordering and membership
+/// tests take constant time rather than time proportional to the number of candidates. Dereferences to the list.
+#[derive(Clone)]
+pub struct SortedCandidateSubset {
+    candidates : Box<[CandidateIndex]>,
+    set : Option<CandidateSet>,
+}
+
+impl SortedCandidateSubset {
+    /// Whether the candidate is in the set.
+    pub fn includes(&self,candidate:CandidateIndex) -> bool {
+        match self.set {
+            Some(set) => candidate.0<CandidateSet::MAX_CANDIDATES && set.contains(candidate),
+            None => self.candidates.binary_search_by_key(&candidate.0,|c|c.0).is_ok(),
+        }
+    }
+    /// The candidates as a bitmask, if they all fit in one.
+    pub fn as_set(&self) -> Option<CandidateSet> { self.set }
+}
+
+/// Sorts the candidates, and removes any repeated.
+impl From<Vec<CandidateIndex>> for SortedCandidateSubset {
+    fn from(mut candidates: Vec<CandidateIndex>) -> Self {
+        candidates.sort_unstable_by_key(|c|c.0);
+        candidates.dedup();
+        let set = if candidates.iter().all(|c|c.0<CandidateSet::MAX_CANDIDATES) { Some(candidates.iter().cloned().collect()) } else { None };
+        SortedCandidateSubset{ candidates: candidates.into_boxed_slice(), set }
+    }
+}
+
+impl FromIterator<CandidateIndex> for SortedCandidateSubset {
+    fn from_iter<T: IntoIterator<Item=CandidateIndex>>(iter: T) -> Self { iter.into_iter().collect::<Vec<_>>().into() }
+}
+
+impl core::ops::Deref for SortedCandidateSubset {
+    type Target = [CandidateIndex];
+    fn deref(&self) -> &Self::Target { &self.candidates }
+}
+
+impl PartialEq for SortedCandidateSubset {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.set,other.set) {
+            (Some(set),Some(other_set)) => set==other_set,
+            _ => self.candidates==other.candidates,
+        }
+    }
+}
+impl Eq for SortedCandidateSubset {}
+
+/// Smaller sets first, then sets of the same size lexicographically as lists.
+impl Ord for SortedCandidateSubset {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.len().cmp(&other.len()).then_with(||match (self.set,other.set) {
+            (Some(set),Some(other_set)) => set.cmp_same_size(other_set),
+            _ => self.candidates.iter().map(|c|c.0).cmp(other.candidates.iter().map(|c|c.0)),
+        })
+    }
+}
+impl PartialOrd for SortedCandidateSubset {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl core::fmt::Debug for SortedCandidateSubset {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result { f.debug_list().entries(self.candidates.iter()).finish() }
+}
+
+impl Serialize for SortedCandidateSubset {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> { serializer.collect_seq(self.candidates.iter()) }
+}
+
+impl <'de> Deserialize<'de> for SortedCandidateSubset {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> { Vec::<CandidateIndex>::deserialize(deserializer).map(Self::from) }
+}
 
 /// Get all num_candidates factorial possible orderings
 pub fn all_elimination_orders(num_candidates:u32) -> Vec<CandidatePermutation> {
@@ -336,3 +615,63 @@ pub fn all_elimination_orders(num_candidates:u32) -> Vec<CandidatePermutation> {
 
 
 
+
+/// The elimination orders, each listed from first eliminated to winner, consistent with every one of the assertions.
+///
+/// Every one of the num_candidates factorial elimination orders is checked with [Assertion::is_consistent_with_full_order], so this
+/// is independent of the code used to generate and trim assertions, but only practical for a small number of candidates.
+pub fn elimination_orders_consistent_with_assertions(assertions:&[Assertion],num_candidates:u32) -> Vec<EliminationOrder> {
+    all_elimination_orders(num_candidates).into_iter().filter(|order|assertions.iter().all(|a|a.is_consistent_with_full_order(order))).collect()
+}
+
+/// The candidates who win some elimination order consistent with every one of the assertions, in increasing order.
+/// For a sound set of assertions about a contest, this is just the winner. Checks every elimination order, as
+/// [elimination_orders_consistent_with_assertions].
+pub fn winners_consistent_with_assertions(assertions:&[Assertion],num_candidates:u32) -> Vec<CandidateIndex> {
+    let mut winners : Vec<CandidateIndex> = elimination_orders_consistent_with_assertions(assertions,num_candidates).iter().filter_map(|order|order.last().copied()).collect();
+    winners.sort_unstable_by_key(|c|c.0);
+    winners.dedup();
+    winners
+}
+
+/// With at most this many candidates, [verify_assertions_rule_out_all_other_winners] checks every elimination order.
+pub const MAX_CANDIDATES_FOR_EXHAUSTIVE_VERIFICATION : u32 = 7;
+
+/// Check that a set of assertions rules out every elimination order in which a candidate other than `winner` wins.
+///
+/// This does not use any of the code used to generate assertions, so it can be used to check assertion sets produced
+/// by other implementations. With up to [MAX_CANDIDATES_FOR_EXHAUSTIVE_VERIFICATION] candidates every elimination order is checked
+/// with [Assertion::is_consistent_with_full_order]; otherwise a tree of elimination order suffixes is searched, abandoning a branch
+/// as soon as some assertion contradicts it.
+///
+/// If some other candidate can win, returns the elimination order (first eliminated to winner) allowed by all the assertions.
+pub fn verify_assertions_rule_out_all_other_winners(assertions:&[Assertion],winner:CandidateIndex,num_candidates:u32) -> Result<(),EliminationOrder> {
+    if num_candidates<=MAX_CANDIDATES_FOR_EXHAUSTIVE_VERIFICATION {
+        for order in all_elimination_orders(num_candidates) {
+            if order.last()!=Some(&winner) && assertions.iter().all(|a|a.is_consistent_with_full_order(&order)) { return Err(order); }
+        }
+        Ok(())
+    } else {
+        fn search(suffix:&mut EliminationOrderSuffix,assertions:&[Assertion],num_candidates:u32) -> Option<EliminationOrder> {
+            if assertions.iter().any(|a|a.contradicts_suffix(suffix)) { return None; }
+            if suffix.len()==num_candidates as usize { return Some(suffix.clone()); }
+            for c in 0..num_candidates {
+                let c = CandidateIndex(c);
+                if !suffix.contains(&c) {
+                    suffix.insert(0,c);
+                    let found = search(suffix,assertions,num_candidates);
+                    suffix.remove(0);
+                    if found.is_some() { return found; }
+                }
+            }
+            None
+        }
+        for c in 0..num_candidates {
+            let c = CandidateIndex(c);
+            if c!=winner {
+                if let Some(order) = search(&mut vec![c],assertions,num_candidates) { return Err(order); }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+//! Tracking the progress of a ballot comparison audit of a set of assertions as sampled ballots are examined.
+//!
+//! For each sampled ballot the user supplies both the preferences on its CVR and the preferences read from the paper ballot
+//! by the audit board. The difference between them gives, for each assertion, an overstatement of between -2 and 2 votes, which
+//! is fed into an [AlphaMartingale]. The current risk measure (p-value) of each assertion is then available, and once every
+//! assertion's p-value is at most the risk limit the audit is complete. Assertions not yet confirmed at the end of a round require escalation.
+
+use serde::Deserialize;
+use serde::Serialize;
+use crate::assertions::Assertion;
+use crate::audit_type::{AlphaMartingale, BallotComparisonALPHA};
+use crate::irv::CandidateIndex;
+use crate::raire_algorithm::RaireResult;
+use crate::simulate::ErrorRates;
+
+/// What was recorded for one sampled ballot.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct BallotInterpretation {
+    /// An identifier for the ballot, for the user's records.
+    #[serde(default,skip_serializing_if="Option::is_none")]
+    pub ballot_id : Option<String>,
+    /// The preferences on the cast vote record.
+    pub cvr : Vec<CandidateIndex>,
+    /// The preferences on the paper ballot, as interpreted by the audit board.
+    pub paper : Vec<CandidateIndex>,
+}
+
+impl BallotInterpretation {
+    /// The discrepancy (see [Assertion::discrepancy]) for each of the assertions.
+    pub fn discrepancies(&self,assertions:&[Assertion]) -> Vec<i8> {
+        assertions.iter().map(|a|a.discrepancy(&self.cvr,&self.paper)).collect()
+    }
+}
+
+/// The number of ballots with each kind of discrepancy for an assertion.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+pub struct DiscrepancyCounts {
+    pub ballots : usize,
+    pub one_vote_overstatements : usize,
+    pub two_vote_overstatements : usize,
+    pub one_vote_understatements : usize,
+    pub two_vote_understatements : usize,
+}
+
+impl DiscrepancyCounts {
+    /// Include a ballot with the given discrepancy.
+    pub fn add(&mut self,discrepancy:i8) {
+        self.ballots+=1;
+        match discrepancy {
+            2 => self.two_vote_overstatements+=1,
+            1 => self.one_vote_overstatements+=1,
+            -1 => self.one_vote_understatements+=1,
+            -2 => self.two_vote_understatements+=1,
+            _ => {}
+        }
+    }
+
+    /// The observed rate of each kind of discrepancy, as used to estimate sample sizes. All zero if there are no ballots.
+    pub fn rates(&self) -> ErrorRates {
+        let rate = |n:usize| if self.ballots==0 { 0.0 } else { n as f64/self.ballots as f64 };
+        ErrorRates{
+            one_vote_overstatement: rate(self.one_vote_overstatements),
+            two_vote_overstatement: rate(self.two_vote_overstatements),
+            one_vote_understatement: rate(self.one_vote_understatements),
+            two_vote_understatement: rate(self.two_vote_understatements),
+        }
+    }
+}
+
+/// The discrepancy of each assertion (the inner index) for each ballot (the outer index).
+pub fn discrepancies(assertions:&[Assertion],ballots:&[BallotInterpretation]) -> Vec<Vec<i8>> {
+    ballots.iter().map(|b|b.discrepancies(assertions)).collect()
+}
+
+/// The number of each kind of discrepancy for each assertion over the given ballots.
+pub fn count_discrepancies(assertions:&[Assertion],ballots:&[BallotInterpretation]) -> Vec<DiscrepancyCounts> {
+    let mut counts = vec![DiscrepancyCounts::default();assertions.len()];
+    for ballot in ballots {
+        for (count,discrepancy) in counts.iter_mut().zip(ballot.discrepancies(assertions)) { count.add(discrepancy); }
+    }
+    counts
+}
+
+/// The state of the audit of one assertion.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct AssertionProgress {
+    /// The index of the assertion in the RAIRE result.
+    pub assertion : usize,
+    /// The number of sampled ballots where the CVR overstated the margin by 1 vote.
+    pub one_vote_overstatements : usize,
+    pub two_vote_overstatements : usize,
+    pub one_vote_understatements : usize,
+    pub two_vote_understatements : usize,
+    /// The current risk measure (p-value).
+    pub p_value : f64,
+    /// True if the p-value is at most the risk limit.
+    pub confirmed : bool,
+}
+
+/// A summary of the state of an audit.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct AuditProgressReport {
+    pub risk_limit : f64,
+    pub ballots_sampled : usize,
+    pub assertions : Vec<AssertionProgress>,
+    /// True if every assertion has been confirmed, so the audit can stop.
+    pub complete : bool,
+    /// The indices of the assertions not yet confirmed. If the sample for the current round is finished, these require escalation.
+    pub needs_escalation : Vec<usize>,
+}
+
+/// Track the audit of the assertions in a RAIRE result as sampled ballots are examined.
+pub struct AuditProgress {
+    risk_limit : f64,
+    assertions : Vec<Assertion>,
+    tests : Vec<AlphaMartingale>,
+    progress : Vec<AssertionProgress>,
+    ballots_sampled : usize,
+}
+
+impl AuditProgress {
+    /// Start an audit of the assertions in `result`, each tested with the given ALPHA parameters.
+    pub fn new(result:&RaireResult,test:&BallotComparisonALPHA) -> Self {
+        let total = test.total_auditable_ballots.0.max(1) as f64;
+        AuditProgress{
+            risk_limit: test.confidence,
+            assertions: result.assertions.iter().map(|a|a.assertion.clone()).collect(),
+            tests: result.assertions.iter().map(|a|AlphaMartingale::new(test,a.margin.0 as f64/total)).collect(),
+            progress: (0..result.assertions.len()).map(|assertion|AssertionProgress{ assertion, one_vote_overstatements: 0, two_vote_overstatements: 0, one_vote_understatements: 0, two_vote_understatements: 0, p_value: 1.0, confirmed: false }).collect(),
+            ballots_sampled: 0,
+        }
+    }
+
+    /// Include the next sampled ballot.
+    pub fn observe(&mut self,ballot:&BallotInterpretation) {
+        for ((assertion,test),progress) in self.assertions.iter().zip(self.tests.iter_mut()).zip(self.progress.iter_mut()) {
+            let overstatement = assertion.discrepancy(&ballot.cvr,&ballot.paper);
+            match overstatement {
+                2 => progress.two_vote_overstatements+=1,
+                1 => progress.one_vote_overstatements+=1,
+                -1 => progress.one_vote_understatements+=1,
+                -2 => progress.two_vote_understatements+=1,
+                _ => {}
+            }
+            let x = test.assorter_value(overstatement.into());
+            test.observe(x);
+            progress.p_value=test.p_value();
+            progress.confirmed=test.is_confirmed();
+        }
+        self.ballots_sampled+=1;
+    }
+
+    pub fn ballots_sampled(&self) -> usize { self.ballots_sampled }
+
+    pub fn report(&self) -> AuditProgressReport {
+        let needs_escalation : Vec<usize> = self.progress.iter().filter(|p|!p.confirmed).map(|p|p.assertion).collect();
+        AuditProgressReport{
+            risk_limit: self.risk_limit,
+            ballots_sampled: self.ballots_sampled,
+            assertions: self.progress.clone(),
+            complete: needs_escalation.is_empty(),
+            needs_escalation,
+        }
+    }
+}
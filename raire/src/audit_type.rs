@@ -11,12 +11,95 @@
 //! The types of assertions about the election, generally relative standings of various candidates
 
 
-use crate::irv::{BallotPaperCount};
+use crate::irv::{BallotPaperCount, Votes};
+use crate::RaireError;
 use serde::Deserialize;
 use serde::Serialize;
 
 pub type AssertionDifficulty = f64;
 
+/// What a difficulty measures, which depends on the [AuditType].
+#[derive(Clone,Copy,Debug,Default,PartialEq,Serialize,Deserialize)]
+pub enum DifficultyUnits {
+    /// An estimate of the number of ballots that need to be sampled to confirm an assertion at the given risk limit (BRAVO, MACRO, ALPHA).
+    ExpectedSampleSize{risk_limit:f64},
+    /// The reciprocal of the diluted margin, that is, the total auditable ballots divided by the margin.
+    OneOnDilutedMargin,
+    /// The square of the reciprocal of the diluted margin.
+    OneOnDilutedMarginSquared,
+    /// Not known, e.g. for an [AuditType] defined outside this crate.
+    #[default]
+    Unknown,
+}
+
+impl DifficultyUnits {
+    pub fn is_unknown(&self) -> bool { *self==DifficultyUnits::Unknown }
+
+    /// A short description of the units, suitable for a column heading.
+    pub fn description(&self) -> String {
+        match self {
+            DifficultyUnits::ExpectedSampleSize{risk_limit} => format!("expected ballots sampled at risk limit {}",risk_limit),
+            DifficultyUnits::OneOnDilutedMargin => "1/diluted margin".to_string(),
+            DifficultyUnits::OneOnDilutedMarginSquared => "1/diluted margin²".to_string(),
+            DifficultyUnits::Unknown => "difficulty".to_string(),
+        }
+    }
+}
+
+/// A difficulty along with what it measures, so that it can be converted to other measures and shown appropriately.
+/// Internally the algorithm just uses the number, an [AssertionDifficulty].
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Difficulty {
+    pub value : AssertionDifficulty,
+    pub units : DifficultyUnits,
+}
+
+impl Difficulty {
+    pub fn new(value:AssertionDifficulty,units:DifficultyUnits) -> Self { Difficulty{value,units} }
+
+    /// The diluted margin (margin divided by total auditable ballots), if it can be determined from the difficulty alone.
+    pub fn diluted_margin(&self) -> Option<f64> {
+        match self.units {
+            DifficultyUnits::OneOnDilutedMargin => Some(1.0/self.value),
+            DifficultyUnits::OneOnDilutedMarginSquared => Some(1.0/self.value.sqrt()),
+            _ => None,
+        }
+    }
+
+    /// An estimate of the number of ballots that need to be sampled to confirm an assertion of this difficulty at the given risk limit,
+    /// assuming no discrepancies. A diluted margin is converted using [BallotComparisonALPHA] with default parameters for
+    /// [DifficultyUnits::OneOnDilutedMargin] (a comparison audit), or the BRAVO average sample number for [DifficultyUnits::OneOnDilutedMarginSquared]
+    /// (a polling audit), assuming every ballot is for the winner or loser. An expected sample size at a different risk limit is scaled by
+    /// ln(1/risk limit), which is exact for MACRO and a reasonable approximation otherwise. `None` if the units are unknown.
+    pub fn expected_sample_size(&self,risk_limit:f64) -> Option<f64> {
+        if self.value.is_infinite() { return Some(f64::INFINITY); }
+        match self.units {
+            DifficultyUnits::ExpectedSampleSize{risk_limit:measured_at} => Some(if measured_at==risk_limit { self.value } else { self.value*risk_limit.ln()/measured_at.ln() }),
+            DifficultyUnits::OneOnDilutedMargin => Some(BallotComparisonALPHA{ confidence: risk_limit, total_auditable_ballots: BallotPaperCount(0), d: BallotComparisonALPHA::default_d(), eta0: None }.expected_sample_size(self.diluted_margin()?)),
+            DifficultyUnits::OneOnDilutedMarginSquared => {
+                let s = (1.0+self.diluted_margin()?)/2.0; // the winner's share of the ballots.
+                let loser_term = if s>=1.0 { 0.0 } else { (1.0-s)*(2.0-2.0*s).ln() };
+                Some((0.5*(2.0*s).ln()-risk_limit.ln())/(s*(2.0*s).ln()+loser_term))
+            }
+            DifficultyUnits::Unknown => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.units {
+            DifficultyUnits::ExpectedSampleSize{risk_limit} => write!(f,"{:.1} ballots expected to be sampled at risk limit {}",self.value,risk_limit),
+            DifficultyUnits::OneOnDilutedMargin|DifficultyUnits::OneOnDilutedMarginSquared => {
+                write!(f,"{} ({})",self.value,self.units.description())?;
+                if let Some(margin) = self.diluted_margin().filter(|m|*m>0.0) { write!(f,", diluted margin {:.3}%",100.0*margin)?; }
+                Ok(())
+            }
+            DifficultyUnits::Unknown => write!(f,"{}",self.value),
+        }
+    }
+}
+
 /// An audit type is a method for determining a difficulty (higher means more difficult, infinite means impossible) for
 /// a comparison of two claimed tallys.
 ///
@@ -25,9 +108,87 @@ pub type AssertionDifficulty = f64;
 /// This is not done as:
 /// * The gains would be very small and the extra complexity would be significant
 /// * It makes pre-specifying the difficulty difficult.
-pub trait AuditType {
+///
+/// The RAIRE algorithm is generic over this trait, so you can supply your own risk function without changing this crate.
+/// The [Audit] enum is just the set of built in types that can be specified in JSON.
+/// ```
+/// use raire::audit_type::{AssertionDifficulty, AuditType};
+/// use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
+/// use raire::progress::NoProgressReporter;
+/// use raire::raire_algorithm::{raire, TrimAlgorithm};
+/// use raire::timeout::TimeOut;
+///
+/// /// Difficulty is the reciprocal of the margin as a fraction of the winner's tally.
+/// struct RelativeMargin;
+/// impl AuditType for RelativeMargin {
+///     fn difficulty(&self, winner:BallotPaperCount, loser:BallotPaperCount) -> AssertionDifficulty {
+///         if winner<=loser { f64::INFINITY } else { winner.0 as f64/(winner-loser).0 as f64 }
+///     }
+/// }
+///
+/// let votes = Votes::new(vec![Vote{n:BallotPaperCount(60),prefs:vec![CandidateIndex(0)]},Vote{n:BallotPaperCount(40),prefs:vec![CandidateIndex(1)]}],2).unwrap();
+/// let result = raire(&votes,None,&RelativeMargin,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+/// assert_eq!(CandidateIndex(0),result.winner);
+/// assert_eq!(3.0,result.difficulty);
+/// ```
+pub trait AuditType : Sync {
     fn difficulty(&self, lowest_tally_winner:BallotPaperCount, highest_tally_loser:BallotPaperCount) -> AssertionDifficulty;
+    /// The total number of ballots used in computing the difficulty, if any. Only used for reporting.
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> { None }
+    /// The difficulty given the (winner,loser) tallies in each stratum of [Votes::strata], used instead of [AuditType::difficulty]
+    /// when the votes are stratified. By default, the difficulty of the total tallies over all strata.
+    fn stratified_difficulty(&self, tallies:&[(BallotPaperCount,BallotPaperCount)]) -> AssertionDifficulty {
+        self.difficulty(tallies.iter().map(|t|t.0).sum(),tallies.iter().map(|t|t.1).sum())
+    }
+    /// What the difficulty measures. Only used for reporting.
+    fn difficulty_units(&self) -> DifficultyUnits { DifficultyUnits::Unknown }
+    /// If the difficulty depends only on the margin, and never increases as the margin increases, the reciprocal of the diluted margin
+    /// as an exact ratio, which orders assertions the same way as the difficulty. Used by [crate::raire_algorithm::RaireOptions::exact_arithmetic].
+    /// By default None, meaning the difficulty is not known to depend only on the margin.
+    fn exact_difficulty(&self, _lowest_tally_winner:BallotPaperCount, _highest_tally_loser:BallotPaperCount) -> Option<ExactDifficulty> { None }
+}
+
+/// The most ballots for which an [ExactDifficulty] converts to a floating point number without losing its order.
+pub const MAX_EXACT_BALLOTS : u64 = 1<<50;
+
+/// The reciprocal of the diluted margin, total auditable ballots divided by margin, kept as a ratio of integers so that
+/// difficulties can be compared without rounding. A margin of zero is infinitely difficult.
+#[derive(Clone,Copy,Debug)]
+pub struct ExactDifficulty {
+    pub total_auditable_ballots : BallotPaperCount,
+    pub margin : BallotPaperCount,
+}
+
+impl ExactDifficulty {
+    pub fn new(total_auditable_ballots:BallotPaperCount,lowest_tally_winner:BallotPaperCount,highest_tally_loser:BallotPaperCount) -> Self {
+        ExactDifficulty{ total_auditable_ballots, margin: BallotPaperCount(lowest_tally_winner.0.saturating_sub(highest_tally_loser.0)) }
+    }
+
+    /// The ratio as a floating point number, rounded once. If the total and margin are both less than [MAX_EXACT_BALLOTS], they
+    /// are exactly representable and the ratio differs from any other with the same total by more than the rounding, so
+    /// different ratios with the same total give different numbers in the same order.
+    pub fn to_f64(&self) -> f64 {
+        if self.margin.0==0 { f64::INFINITY } else { self.total_auditable_ballots.0 as f64/self.margin.0 as f64 }
+    }
+}
+
+impl Ord for ExactDifficulty {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self.margin.0,other.margin.0) {
+            (0,0) => std::cmp::Ordering::Equal,
+            (0,_) => std::cmp::Ordering::Greater,
+            (_,0) => std::cmp::Ordering::Less,
+            (margin,other_margin) => (self.total_auditable_ballots.0 as u128*other_margin as u128).cmp(&(other.total_auditable_ballots.0 as u128*margin as u128)),
+        }
+    }
+}
+impl PartialOrd for ExactDifficulty {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> { Some(self.cmp(other)) }
+}
+impl PartialEq for ExactDifficulty {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other)==std::cmp::Ordering::Equal }
 }
+impl Eq for ExactDifficulty {}
 
 
 /// A BRAVO ballot polling audit as described in the original paper.
@@ -47,6 +208,7 @@ impl BallotPollingBRAVO {
     }
 
     /// This function is only public for testing some historical data. You probably don't want to use this directly.
+    /// If the loser has no votes, this is the limit as the loser's tally goes to zero (earlier versions gave NaN).
     pub fn bravo_function(&self,winner_tally:BallotPaperCount,loser_tally:BallotPaperCount,paper_count:BallotPaperCount) -> AssertionDifficulty {
         if winner_tally.0<=loser_tally.0 { f64::INFINITY } else {
             let w = winner_tally.0 as f64;
@@ -55,7 +217,8 @@ impl BallotPollingBRAVO {
             let twos = 2.0*s;
             let ln2s = twos.ln();
             let numerator = 0.5*ln2s-self.confidence.ln();
-            let denominator = (w*ln2s+l*(2.0-twos).ln())/(paper_count.0 as f64);
+            let loser_term = if l==0.0 { 0.0 } else { l*(2.0-twos).ln() }; // the limit as l→0, rather than 0×-∞.
+            let denominator = (w*ln2s+loser_term)/(paper_count.0 as f64);
             numerator/denominator
         }
     }
@@ -65,6 +228,8 @@ impl AuditType for BallotPollingBRAVO {
     fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
         self.average_sample_number_original_paper_using_total_auditable_ballots(lowest_tally_winner,highest_tally_loser)
     }
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> { Some(self.total_auditable_ballots) }
+    fn difficulty_units(&self) -> DifficultyUnits { DifficultyUnits::ExpectedSampleSize{risk_limit:self.confidence} }
 }
 
 
@@ -95,6 +260,11 @@ impl AuditType for BallotComparisonMACRO {
     fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
         self.average_sample_number_original_paper(lowest_tally_winner,highest_tally_loser)
     }
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> { Some(self.total_auditable_ballots) }
+    fn difficulty_units(&self) -> DifficultyUnits { DifficultyUnits::ExpectedSampleSize{risk_limit:self.confidence} }
+    fn exact_difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> Option<ExactDifficulty> {
+        Some(ExactDifficulty::new(self.total_auditable_ballots,lowest_tally_winner,highest_tally_loser))
+    }
 }
 
 /// A comparison where the difficulty = 1/diluted margin.
@@ -111,6 +281,11 @@ impl AuditType for BallotComparisonOneOnDilutedMargin {
             reciprocal_diluted_margin
         }
     }
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> { Some(self.total_auditable_ballots) }
+    fn difficulty_units(&self) -> DifficultyUnits { DifficultyUnits::OneOnDilutedMargin }
+    fn exact_difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> Option<ExactDifficulty> {
+        Some(ExactDifficulty::new(self.total_auditable_ballots,lowest_tally_winner,highest_tally_loser))
+    }
 }
 
 
@@ -128,6 +303,313 @@ impl AuditType for BallotPollingOneOnDilutedMarginSquared {
             reciprocal_diluted_margin*reciprocal_diluted_margin
         }
     }
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> { Some(self.total_auditable_ballots) }
+    fn difficulty_units(&self) -> DifficultyUnits { DifficultyUnits::OneOnDilutedMarginSquared }
+    fn exact_difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> Option<ExactDifficulty> {
+        Some(ExactDifficulty::new(self.total_auditable_ballots,lowest_tally_winner,highest_tally_loser))
+    }
+}
+
+/// A ballot level comparison audit using the ALPHA supermartingale test (Stark 2023, <https://arxiv.org/abs/2201.02707>)
+/// applied to the overstatement assorter, as used by SHANGRLA.
+///
+/// The difficulty is an estimate of the number of ballots that need to be sampled to confirm the assertion,
+/// assuming no discrepancies are found and sampling with replacement.
+#[derive(Clone,Copy,Debug,Serialize,Deserialize)]
+pub struct BallotComparisonALPHA {
+    /// The desired confidence α (risk limit). A number between 0 and 1 bounding the probability of not rejecting a false result.
+    pub confidence : f64,
+    pub total_auditable_ballots : BallotPaperCount,
+    /// The weight d given to the initial estimate η₀ relative to the observed sample mean when estimating the mean. Default 100.
+    #[serde(default = "BallotComparisonALPHA::default_d")]
+    pub d : f64,
+    /// The initial estimate η₀ of the mean of the overstatement assorter. Default (if None) is halfway between its value when
+    /// there are no discrepancies and its upper bound, a moderately aggressive choice.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub eta0 : Option<f64>,
+}
+
+impl BallotComparisonALPHA {
+    pub(crate) fn default_d() -> f64 { 100.0 }
+
+    /// The number of draws after which the per draw factor is assumed constant, to bound the computation time.
+    const MAX_SIMULATED_DRAWS : usize = 10000;
+
+    /// Estimate the sample size for a diluted margin v (assorter upper bound 1).
+    pub fn expected_sample_size(&self,diluted_margin:f64) -> AssertionDifficulty {
+        if diluted_margin.is_nan() || diluted_margin<=0.0 { return f64::INFINITY; }
+        let mut test = AlphaMartingale::new(self,diluted_margin);
+        let x = test.assorter_value(0);
+        let mut factor = 1.0;
+        for i in 0..Self::MAX_SIMULATED_DRAWS {
+            factor = test.factor(x);
+            if test.observe(x) { return (i+1) as f64; }
+        }
+        if factor<=1.0 { f64::INFINITY } else { Self::MAX_SIMULATED_DRAWS as f64+((test.target-test.log_t)/factor.ln()).ceil() }
+    }
+}
+
+/// The state of an ALPHA test of a single assertion as ballots are sampled (with replacement) in a ballot comparison audit.
+#[derive(Clone,Debug)]
+pub struct AlphaMartingale {
+    diluted_margin : f64,
+    mu : f64,
+    upper : f64,
+    eta0 : f64,
+    d : f64,
+    /// ln(1/α)
+    target : f64,
+    log_t : f64,
+    /// the largest value log_t has taken.
+    max_log_t : f64,
+    sum : f64,
+    n : usize,
+}
+
+impl AlphaMartingale {
+    pub fn new(params:&BallotComparisonALPHA,diluted_margin:f64) -> Self {
+        let upper = 2.0/(2.0-diluted_margin); // upper bound on the overstatement assorter.
+        let no_discrepancy = 1.0/(2.0-diluted_margin);
+        AlphaMartingale{
+            diluted_margin,
+            mu: 0.5, // the null hypothesis is that the assorter mean is at most 1/2.
+            upper,
+            eta0: params.eta0.unwrap_or((no_discrepancy+upper)/2.0),
+            d: params.d,
+            target: -params.confidence.ln(),
+            log_t: 0.0,
+            max_log_t: 0.0,
+            sum: 0.0,
+            n: 0,
+        }
+    }
+
+    /// The value of the overstatement assorter for a ballot whose CVR overstates the margin by the given number of votes (-2 to 2).
+    pub fn assorter_value(&self,overstatement:i32) -> f64 { (1.0-overstatement as f64/2.0)/(2.0-self.diluted_margin) }
+
+    /// The factor by which the test statistic would be multiplied by the next observation x.
+    fn factor(&self,x:f64) -> f64 {
+        let epsilon = 1e-9*(self.upper-self.mu);
+        let eta = ((self.d*self.eta0+self.sum)/(self.d+self.n as f64)).clamp(self.mu+epsilon,self.upper-epsilon);
+        (x/self.mu)*(eta-self.mu)/(self.upper-self.mu)+(self.upper-eta)/(self.upper-self.mu)
+    }
+
+    /// Include the next sampled ballot's assorter value. Returns true if the assertion is now confirmed at the risk limit.
+    pub fn observe(&mut self,x:f64) -> bool {
+        self.log_t+=self.factor(x).ln();
+        self.max_log_t=self.max_log_t.max(self.log_t);
+        self.sum+=x;
+        self.n+=1;
+        self.is_confirmed()
+    }
+
+    /// Whether the assertion has been confirmed at the risk limit. Once confirmed, it stays confirmed.
+    pub fn is_confirmed(&self) -> bool { self.max_log_t>=self.target }
+    /// The current risk measure (p-value) of the assertion: the reciprocal of the largest value the test statistic has taken.
+    pub fn p_value(&self) -> f64 { (-self.max_log_t).exp().min(1.0) }
+    /// The number of ballots observed so far.
+    pub fn sample_size(&self) -> usize { self.n }
+}
+
+impl AuditType for BallotComparisonALPHA {
+    fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
+        if lowest_tally_winner<=highest_tally_loser { f64::INFINITY } else {
+            self.expected_sample_size((lowest_tally_winner-highest_tally_loser).0 as f64/self.total_auditable_ballots.0 as f64)
+        }
+    }
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> { Some(self.total_auditable_ballots) }
+    fn difficulty_units(&self) -> DifficultyUnits { DifficultyUnits::ExpectedSampleSize{risk_limit:self.confidence} }
+    fn exact_difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> Option<ExactDifficulty> {
+        Some(ExactDifficulty::new(self.total_auditable_ballots,lowest_tally_winner,highest_tally_loser))
+    }
+}
+
+/// The largest [StratifiedAudit::allocation_steps] allowed, as the time to find each difficulty grows with its square.
+pub const MAX_ALLOCATION_STEPS : usize = 100;
+
+/// One stratum of a [StratifiedAudit], such as the ballots with CVRs (audited by ballot comparison) or those without (ballot polling).
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct AuditStratum {
+    pub name : String,
+    /// The audit used in this stratum. Its total auditable ballots is the upper bound on the number of ballots in the stratum.
+    pub audit : Audit,
+}
+
+/// An audit made up of independent audits of several strata, in the style of SUITE
+/// (Ottoboni, Stark, Lindeman and McBurnett 2018, <https://arxiv.org/abs/1809.04235>).
+///
+/// The margin of an assertion over all strata is divided between the strata, and each stratum's audit confirms that the
+/// stratum's share of the margin is not overstated, as if the stratum's winner and loser tallies had that margin. The difficulty is the sum of the strata's difficulties for the best
+/// division, trying every division into multiples of 1/`allocation_steps` of the margin. This is only meaningful if the strata's
+/// difficulties are sample sizes, as for BRAVO, MACRO and ALPHA. `allocation_steps` is at most [MAX_ALLOCATION_STEPS].
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct StratifiedAudit {
+    pub strata : Vec<AuditStratum>,
+    #[serde(default = "StratifiedAudit::default_allocation_steps")]
+    pub allocation_steps : usize,
+}
+
+impl StratifiedAudit {
+    fn default_allocation_steps() -> usize { 20 }
+
+    /// Check that no stratum of the votes has more ballots than the upper bound for that stratum.
+    pub fn check_bounds(&self,votes:&Votes) -> Result<(),RaireError> {
+        for (stratum,stratum_votes) in self.strata.iter().zip(votes.strata()) {
+            let ballots = stratum_votes.votes.total_votes();
+            if let Some(bound) = stratum.audit.total_auditable_ballots() {
+                if ballots>bound { return Err(RaireError::StratumExceedsBound{ stratum: stratum.name.clone(), ballots, bound }); }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that [StratifiedAudit::allocation_steps] is between 1 and [MAX_ALLOCATION_STEPS], here and in any stratified strata.
+    pub fn check_allocation_steps(&self) -> Result<(),RaireError> {
+        if self.allocation_steps==0 || self.allocation_steps>MAX_ALLOCATION_STEPS { return Err(RaireError::InvalidAllocationSteps{ steps: self.allocation_steps, max: MAX_ALLOCATION_STEPS }); }
+        for stratum in &self.strata {
+            if let Audit::Stratified(audit) = &stratum.audit { audit.check_allocation_steps()?; }
+        }
+        Ok(())
+    }
+
+    /// The lowest total difficulty over divisions of the margin into `allocation_steps` steps amongst the strata.
+    /// As the total is a sum over the strata, this is found one stratum at a time: `best[steps]` is the lowest total for the strata
+    /// so far sharing `steps` steps. This takes time proportional to the number of strata times the square of `allocation_steps`.
+    fn best_division(&self,tallies:&[(BallotPaperCount,BallotPaperCount)],margin:usize) -> AssertionDifficulty {
+        let mut best : Vec<AssertionDifficulty> = vec![0.0]; // no strata can only share no steps.
+        for (&(winner,loser),stratum) in tallies.iter().zip(&self.strata) {
+            let difficulties : Vec<AssertionDifficulty> = (0..=self.allocation_steps).map(|steps|{
+                let share = (margin as u128*steps as u128/self.allocation_steps as u128) as usize; // rounded down, so the shares never add to more than the margin.
+                // the stratum's tallies, adjusted to have the stratum's share of the margin. Only the margin matters for comparison audits.
+                let loser = (winner.0+loser.0).saturating_sub(share)/2;
+                let difficulty = stratum.audit.difficulty(BallotPaperCount(loser+share),BallotPaperCount(loser));
+                if difficulty.is_nan() { f64::INFINITY } else { difficulty }
+            }).collect();
+            best = (0..=self.allocation_steps).map(|steps|{
+                (0..=steps.min(best.len()-1)).map(|previous|best[previous]+difficulties[steps-previous]).fold(f64::INFINITY,f64::min)
+            }).collect();
+        }
+        best[self.allocation_steps]
+    }
+}
+
+impl AuditType for StratifiedAudit {
+    /// Without the tallies in each stratum, assume each stratum has a share of the tallies proportional to its total auditable ballots.
+    fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
+        let total = self.total_auditable_ballots().map(|b|b.0).unwrap_or(0).max(1);
+        let share = |tally:BallotPaperCount,stratum:&AuditStratum|BallotPaperCount(((tally.0 as u128*stratum.audit.total_auditable_ballots().map(|b|b.0).unwrap_or(0) as u128)/total as u128) as usize);
+        let tallies : Vec<_> = self.strata.iter().map(|stratum|(share(lowest_tally_winner,stratum),share(highest_tally_loser,stratum))).collect();
+        self.stratified_difficulty(&tallies)
+    }
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> {
+        self.strata.iter().map(|stratum|stratum.audit.total_auditable_ballots()).sum()
+    }
+    fn stratified_difficulty(&self, tallies: &[(BallotPaperCount, BallotPaperCount)]) -> AssertionDifficulty {
+        let winner : BallotPaperCount = tallies.iter().map(|t|t.0).sum();
+        let loser : BallotPaperCount = tallies.iter().map(|t|t.1).sum();
+        if winner<=loser || tallies.is_empty() || tallies.len()!=self.strata.len() || self.allocation_steps==0 { f64::INFINITY }
+        else { self.best_division(tallies,(winner-loser).0) }
+    }
+    /// A sum of sample sizes, at the risk limit of the strata if they all have the same one.
+    fn difficulty_units(&self) -> DifficultyUnits {
+        let units : Vec<DifficultyUnits> = self.strata.iter().map(|stratum|stratum.audit.difficulty_units()).collect();
+        if units.windows(2).all(|w|w[0]==w[1]) && matches!(units.first(),Some(DifficultyUnits::ExpectedSampleSize{..})) { units[0] } else { DifficultyUnits::Unknown }
+    }
+}
+
+/// An audit for which the ballot manifest lists more ballots than there are CVRs. As in SHANGRLA, the missing ballots are phantoms:
+/// their CVRs show no vote, and a phantom sampled in the audit is treated as a vote for the loser. Each phantom is thus a potential
+/// one vote overstatement, and reduces the margin of every assertion by one.
+pub struct WithPhantoms<'a,A:AuditType> {
+    pub audit : &'a A,
+    /// Phantom ballots: total auditable ballots minus the number of votes.
+    pub phantoms : BallotPaperCount,
+    /// For a [StratifiedAudit], the phantoms in each stratum.
+    pub stratum_phantoms : Vec<BallotPaperCount>,
+}
+
+impl <'a> WithPhantoms<'a,Audit> {
+    /// The phantoms for the given votes, which fails if the manifest (total auditable ballots) is smaller than the number of votes.
+    pub fn new(audit:&'a Audit,votes:&Votes) -> Result<Self,RaireError> {
+        let phantoms = |bound:Option<BallotPaperCount>,votes:&Votes| match bound {
+            Some(manifest) if manifest<votes.total_votes() => Err(RaireError::ManifestSmallerThanVotes{ manifest, votes: votes.total_votes() }),
+            Some(manifest) => Ok(manifest-votes.total_votes()),
+            None => Ok(BallotPaperCount(0)),
+        };
+        let stratum_phantoms = match audit {
+            Audit::Stratified(stratified) => stratified.strata.iter().zip(votes.strata()).map(|(stratum,stratum_votes)|phantoms(stratum.audit.total_auditable_ballots(),&stratum_votes.votes)).collect::<Result<Vec<_>,_>>()?,
+            _ => vec![],
+        };
+        Ok(WithPhantoms{ audit, phantoms: phantoms(audit.total_auditable_ballots(),votes)?, stratum_phantoms })
+    }
+}
+
+impl <A:AuditType> AuditType for WithPhantoms<'_,A> {
+    fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
+        self.audit.difficulty(lowest_tally_winner,highest_tally_loser+self.phantoms)
+    }
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> { self.audit.total_auditable_ballots() }
+    fn stratified_difficulty(&self, tallies: &[(BallotPaperCount, BallotPaperCount)]) -> AssertionDifficulty {
+        let tallies : Vec<_> = tallies.iter().enumerate().map(|(i,&(winner,loser))|(winner,loser+self.stratum_phantoms.get(i).copied().unwrap_or(BallotPaperCount(0)))).collect();
+        self.audit.stratified_difficulty(&tallies)
+    }
+    fn difficulty_units(&self) -> DifficultyUnits { self.audit.difficulty_units() }
+    fn exact_difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> Option<ExactDifficulty> {
+        self.audit.exact_difficulty(lowest_tally_winner,highest_tally_loser+self.phantoms)
+    }
+}
+
+/// An audit whose difficulty is the [ExactDifficulty] of another audit, rounded once, so that the RAIRE algorithm compares
+/// assertions exactly. See [crate::raire_algorithm::RaireOptions::exact_arithmetic].
+pub struct ExactlyOrdered<'a,A:AuditType> {
+    audit : &'a A,
+    /// The largest winner tally that can occur, the votes plus the total auditable ballots, allowing for phantoms.
+    max_tally : BallotPaperCount,
+}
+
+impl <'a,A:AuditType> ExactlyOrdered<'a,A> {
+    /// Fails with [RaireError::ExactArithmeticNotSupported] unless the audit has an [AuditType::exact_difficulty] and there are
+    /// fewer than [MAX_EXACT_BALLOTS] votes and total auditable ballots.
+    pub fn new(audit:&'a A,votes:&Votes) -> Result<Self,RaireError> {
+        let small = |ballots:BallotPaperCount|(ballots.0 as u64)<MAX_EXACT_BALLOTS;
+        match audit.exact_difficulty(BallotPaperCount(1),BallotPaperCount(0)) {
+            Some(exact) if small(exact.total_auditable_ballots) && small(votes.total_votes()) => Ok(ExactlyOrdered{ audit, max_tally: votes.total_votes()+exact.total_auditable_ballots }),
+            _ => Err(RaireError::ExactArithmeticNotSupported),
+        }
+    }
+
+    /// The smallest winner tally, with a loser tally of zero, for which `easy` holds, which must then hold for all larger tallies.
+    fn smallest_winner_tally(&self,easy:impl Fn(BallotPaperCount)->bool) -> Option<BallotPaperCount> {
+        if !easy(self.max_tally) { return None; }
+        if easy(BallotPaperCount(0)) { return Some(BallotPaperCount(0)); }
+        let (mut hard,mut easy_tally) = (0,self.max_tally.0);
+        while easy_tally-hard>1 {
+            let middle = hard+(easy_tally-hard)/2;
+            if easy(BallotPaperCount(middle)) { easy_tally=middle } else { hard=middle }
+        }
+        Some(BallotPaperCount(easy_tally))
+    }
+
+    /// The largest difficulty computed by this of an assertion whose difficulty with the underlying audit is at most `difficulty`, if any.
+    pub fn from_audit_difficulty(&self,difficulty:AssertionDifficulty) -> Option<AssertionDifficulty> {
+        self.smallest_winner_tally(|winner|self.audit.difficulty(winner,BallotPaperCount(0))<=difficulty).map(|winner|self.difficulty(winner,BallotPaperCount(0)))
+    }
+
+    /// The difficulty with the underlying audit of an assertion whose difficulty computed by this is `difficulty`.
+    pub fn to_audit_difficulty(&self,difficulty:AssertionDifficulty) -> AssertionDifficulty {
+        self.smallest_winner_tally(|winner|self.difficulty(winner,BallotPaperCount(0))<=difficulty).map_or(0.0,|winner|self.audit.difficulty(winner,BallotPaperCount(0)))
+    }
+}
+
+impl <A:AuditType> AuditType for ExactlyOrdered<'_,A> {
+    fn difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> AssertionDifficulty {
+        self.audit.exact_difficulty(lowest_tally_winner,highest_tally_loser).map_or(f64::INFINITY,|exact|exact.to_f64())
+    }
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> { self.audit.total_auditable_ballots() }
+    fn difficulty_units(&self) -> DifficultyUnits { DifficultyUnits::OneOnDilutedMargin }
+    fn exact_difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> Option<ExactDifficulty> {
+        self.audit.exact_difficulty(lowest_tally_winner,highest_tally_loser)
+    }
 }
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
@@ -135,10 +617,12 @@ impl AuditType for BallotPollingOneOnDilutedMarginSquared {
 pub enum Audit {
     BRAVO(BallotPollingBRAVO),
     MACRO(BallotComparisonMACRO),
+    ALPHA(BallotComparisonALPHA),
     #[serde(alias = "Margin")] // for backwards compatibility
     OneOnMargin(BallotComparisonOneOnDilutedMargin),
     #[serde(alias = "MarginSq")] // for backwards compatibility
     OneOnMarginSq(BallotPollingOneOnDilutedMarginSquared),
+    Stratified(StratifiedAudit),
 }
 
 impl AuditType for Audit {
@@ -146,8 +630,45 @@ impl AuditType for Audit {
         match self {
             Audit::BRAVO(audit) => audit.difficulty(lowest_tally_winner,highest_tally_loser),
             Audit::MACRO(audit) => audit.difficulty(lowest_tally_winner,highest_tally_loser),
+            Audit::ALPHA(audit) => audit.difficulty(lowest_tally_winner,highest_tally_loser),
             Audit::OneOnMargin(audit) => audit.difficulty(lowest_tally_winner, highest_tally_loser),
             Audit::OneOnMarginSq(audit) => audit.difficulty(lowest_tally_winner, highest_tally_loser),
+            Audit::Stratified(audit) => audit.difficulty(lowest_tally_winner, highest_tally_loser),
         }
     }
-}
\ No newline at end of file
+    fn total_auditable_ballots(&self) -> Option<BallotPaperCount> {
+        match self {
+            Audit::BRAVO(audit) => audit.total_auditable_ballots(),
+            Audit::MACRO(audit) => audit.total_auditable_ballots(),
+            Audit::ALPHA(audit) => audit.total_auditable_ballots(),
+            Audit::OneOnMargin(audit) => audit.total_auditable_ballots(),
+            Audit::OneOnMarginSq(audit) => audit.total_auditable_ballots(),
+            Audit::Stratified(audit) => audit.total_auditable_ballots(),
+        }
+    }
+    fn stratified_difficulty(&self, tallies: &[(BallotPaperCount, BallotPaperCount)]) -> AssertionDifficulty {
+        match self {
+            Audit::Stratified(audit) => audit.stratified_difficulty(tallies),
+            _ => self.difficulty(tallies.iter().map(|t|t.0).sum(),tallies.iter().map(|t|t.1).sum()),
+        }
+    }
+    fn difficulty_units(&self) -> DifficultyUnits {
+        match self {
+            Audit::BRAVO(audit) => audit.difficulty_units(),
+            Audit::MACRO(audit) => audit.difficulty_units(),
+            Audit::ALPHA(audit) => audit.difficulty_units(),
+            Audit::OneOnMargin(audit) => audit.difficulty_units(),
+            Audit::OneOnMarginSq(audit) => audit.difficulty_units(),
+            Audit::Stratified(audit) => audit.difficulty_units(),
+        }
+    }
+    fn exact_difficulty(&self, lowest_tally_winner: BallotPaperCount, highest_tally_loser: BallotPaperCount) -> Option<ExactDifficulty> {
+        match self {
+            Audit::MACRO(audit) => audit.exact_difficulty(lowest_tally_winner,highest_tally_loser),
+            Audit::ALPHA(audit) => audit.exact_difficulty(lowest_tally_winner,highest_tally_loser),
+            Audit::OneOnMargin(audit) => audit.exact_difficulty(lowest_tally_winner, highest_tally_loser),
+            Audit::OneOnMarginSq(audit) => audit.exact_difficulty(lowest_tally_winner, highest_tally_loser),
+            Audit::BRAVO(_) | Audit::Stratified(_) => None,
+        }
+    }
+}
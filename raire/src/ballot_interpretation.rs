@@ -0,0 +1,126 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! How the marks on a ballot paper become a preference list.
+//!
+//! Jurisdictions differ in what they do with skipped ranks, overvotes (more than one candidate at a rank) and candidates ranked more than
+//! once. The same [BallotInterpretationRules] are used by the ballot file parsers in `utilities` and, for ballots given as
+//! [RankedVote]s in a [crate::RaireProblem], by the IRV count, so the assertions are about the ballots as the official count interpreted them.
+
+use serde::{Deserialize, Serialize};
+use crate::irv::{BallotPaperCount, CandidateIndex, Vote};
+
+/// What a ballot has at one rank.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum RankMark {
+    /// Nothing at this rank.
+    Skipped,
+    Candidate(CandidateIndex),
+    /// More than one candidate at this rank.
+    Overvote,
+}
+
+impl RankMark {
+    /// Convert a list of (rank, candidate) marks, ranks starting at 1, into the mark at each rank.
+    pub fn from_ranked_candidates(mut ranked:Vec<(u32,CandidateIndex)>) -> Vec<RankMark> {
+        ranked.sort_unstable_by_key(|(rank,c)|(*rank,c.0));
+        ranked.dedup();
+        let mut res = vec![];
+        for (rank,candidate) in ranked {
+            while (res.len() as u32)<rank { res.push(RankMark::Skipped); }
+            let mark = &mut res[(rank.max(1)-1) as usize];
+            *mark = if *mark==RankMark::Skipped { RankMark::Candidate(candidate) } else { RankMark::Overvote };
+        }
+        res
+    }
+}
+
+/// What to do when a ballot has more than one candidate at the same rank.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Default,Serialize,Deserialize)]
+pub enum OvervoteRule {
+    /// The ballot is exhausted at the overvoted rank; earlier ranks still count. This is the usual rule in US RCV jurisdictions.
+    #[default]
+    Exhaust,
+    /// The overvoted rank is treated as if it were skipped.
+    SkipRank,
+}
+
+/// What to do when a ballot ranks the same candidate more than once.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Default,Serialize,Deserialize)]
+pub enum DuplicateRule {
+    /// The candidate only counts at their highest rank; later rankings of them are ignored.
+    #[default]
+    Ignore,
+    /// The ballot is exhausted at the repeated ranking.
+    Exhaust,
+}
+
+/// Rules for converting the marks at each rank on a ballot into a preference list.
+/// These vary between jurisdictions; the default is typical of US RCV contests, with skipped ranks ignored.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+pub struct BallotInterpretationRules {
+    #[serde(default)]
+    pub overvote : OvervoteRule,
+    #[serde(default)]
+    pub duplicate : DuplicateRule,
+    /// If Some(n), a ballot is exhausted once it has more than n consecutive skipped ranks (e.g. Some(1) for the Maine rule). Default no limit.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_consecutive_skipped_ranks : Option<usize>,
+}
+
+impl BallotInterpretationRules {
+    /// Convert the marks at each rank, first preference first, into a preference list.
+    pub fn preferences(&self,marks:impl IntoIterator<Item=RankMark>) -> Vec<CandidateIndex> {
+        let mut prefs : Vec<CandidateIndex> = vec![];
+        let mut skipped = 0;
+        for mark in marks {
+            match mark {
+                RankMark::Skipped => { skipped+=1; }
+                RankMark::Overvote => match self.overvote {
+                    OvervoteRule::Exhaust => break,
+                    OvervoteRule::SkipRank => { skipped+=1; }
+                },
+                RankMark::Candidate(candidate) => {
+                    if self.max_consecutive_skipped_ranks.is_some_and(|max_skipped|skipped>max_skipped) { break; }
+                    skipped=0;
+                    if !prefs.contains(&candidate) { prefs.push(candidate); }
+                    else if self.duplicate==DuplicateRule::Exhaust { break; }
+                }
+            }
+        }
+        prefs
+    }
+
+    /// The vote that a [RankedVote] counts as.
+    pub fn interpret(&self,vote:&RankedVote) -> Vote {
+        Vote{ n: vote.n, prefs: self.preferences(vote.marks()) }
+    }
+}
+
+/// Some number of identical ballots, given as the candidates marked at each rank rather than as a preference list.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct RankedVote {
+    /// The number of voters who voted this way
+    pub n : BallotPaperCount,
+    /// ranks[0] is the candidates marked at the first rank: none if skipped, or more than one if overvoted.
+    pub ranks : Vec<Vec<CandidateIndex>>,
+}
+
+impl RankedVote {
+    /// The mark at each rank.
+    pub fn marks(&self) -> impl Iterator<Item=RankMark> + '_ {
+        self.ranks.iter().map(|rank|{
+            match rank.split_first() {
+                None => RankMark::Skipped,
+                Some((&first,rest)) => if rest.iter().all(|&c|c==first) { RankMark::Candidate(first) } else { RankMark::Overvote },
+            }
+        })
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A ballot manifest: the batches (boxes, trays or scanner batches) the paper ballots are stored in, and how many ballots are
+//! in each, as recorded by the election office. The ballots are numbered from 1 in manifest order, which is how [crate::sampling]
+//! identifies them, and [BallotManifest::locate] says where each is to be found.
+//!
+//! The number of ballots in the manifest should be the total auditable ballots of the audit, which should be at least the number of
+//! CVRs; [BallotManifest::check_against] checks this.
+
+use serde::{Deserialize, Serialize};
+use crate::audit_type::{Audit, AuditType};
+use crate::irv::BallotPaperCount;
+use crate::RaireError;
+
+/// The batches the ballots are stored in, in order. Ballot number 1 is the first ballot of the first batch.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct BallotManifest {
+    pub batches : Vec<ManifestBatch>,
+}
+
+/// A batch of ballots, stored together.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct ManifestBatch {
+    pub batch_id : String,
+    /// The number of ballots in the batch.
+    pub ballots : usize,
+}
+
+/// Where a ballot is to be found.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct BallotLocation {
+    pub batch_id : String,
+    /// The position of the ballot in its batch, counting from 1.
+    pub position : usize,
+}
+
+impl BallotManifest {
+    /// A manifest with a single batch, for when the ballots are just numbered 1 to `ballots`.
+    pub fn unbatched(ballots:usize) -> Self {
+        BallotManifest{ batches: vec![ManifestBatch{ batch_id: String::new(), ballots }] }
+    }
+
+    pub fn total_ballots(&self) -> usize { self.batches.iter().map(|b|b.ballots).sum() }
+
+    /// Check that there are some ballots and the batch ids are distinct.
+    pub fn check(&self) -> Result<(),RaireError> {
+        if self.total_ballots()==0 { return Err(RaireError::InvalidBallotManifest("it has no ballots".to_string())); }
+        for (i,batch) in self.batches.iter().enumerate() {
+            if self.batches[..i].iter().any(|b|b.batch_id==batch.batch_id) { return Err(RaireError::InvalidBallotManifest(format!("the batch id {} is repeated",batch.batch_id))); }
+        }
+        Ok(())
+    }
+
+    /// Check the manifest has the total auditable ballots of the audit, if it says, and at least the given number of votes (CVRs).
+    pub fn check_against(&self,audit:&Audit,votes:BallotPaperCount) -> Result<(),RaireError> {
+        let manifest = BallotPaperCount(self.total_ballots());
+        if let Some(total_auditable_ballots) = audit.total_auditable_ballots() {
+            if manifest!=total_auditable_ballots { return Err(RaireError::ManifestDisagreesWithAudit{ manifest, total_auditable_ballots }); }
+        }
+        if manifest<votes { return Err(RaireError::ManifestSmallerThanVotes{ manifest, votes }); }
+        Ok(())
+    }
+
+    /// Where ballot number `ballot` (counting from 1) is, or None if there is no such ballot.
+    pub fn locate(&self,ballot:usize) -> Option<BallotLocation> {
+        let mut position = ballot.checked_sub(1)?;
+        for batch in &self.batches {
+            if position<batch.ballots { return Some(BallotLocation{ batch_id: batch.batch_id.clone(), position: position+1 }); }
+            position-=batch.ballots;
+        }
+        None
+    }
+}
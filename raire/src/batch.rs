@@ -0,0 +1,95 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Solving all the IRV contests in an election in one run.
+//!
+//! Real elections may have dozens of IRV contests. [solve_many] solves them together; with the `parallel` feature the contests are
+//! shared between the machine's threads, the largest first, rather than each contest in turn using every thread. [BatchReport]
+//! summarises the results, including the hardest contest, which determines the sample size if all the contests are audited together.
+
+use serde::{Deserialize, Serialize};
+use crate::assertions::candidate_name;
+use crate::audit_type::AssertionDifficulty;
+use crate::explain::candidate_names_from_metadata;
+use crate::irv::BallotPaperCount;
+use crate::progress::NoProgressReporter;
+use crate::{RaireError, RaireProblem, RaireSolution};
+
+/// Solve each of the problems, returning the solutions in the same order.
+pub fn solve_many(problems:Vec<RaireProblem>) -> Vec<RaireSolution> {
+    #[cfg(feature = "parallel")]
+    {
+        use std::sync::Mutex;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let available = std::thread::available_parallelism().map(|n|n.get()).unwrap_or(1);
+        let num_workers = available.min(problems.len()).max(1);
+        let threads_per_contest = (available/num_workers).max(1);
+        let mut order : Vec<usize> = (0..problems.len()).collect();
+        order.sort_by_key(|&i|std::cmp::Reverse((problems[i].num_candidates,problems[i].votes.len()+problems[i].ranked_votes.as_ref().map_or(0,|v|v.len()))));
+        let jobs : Vec<Mutex<Option<RaireProblem>>> = problems.into_iter().map(|p|Mutex::new(Some(p))).collect();
+        let solutions : Vec<Mutex<Option<RaireSolution>>> = jobs.iter().map(|_|Mutex::new(None)).collect();
+        let next_job = AtomicUsize::new(0);
+        std::thread::scope(|scope|{
+            for _ in 0..num_workers {
+                scope.spawn(||{
+                    while let Some(&job) = order.get(next_job.fetch_add(1,Ordering::Relaxed)) {
+                        let problem = jobs[job].lock().unwrap().take().unwrap();
+                        *solutions[job].lock().unwrap() = Some(problem.solve_with_threads(&mut NoProgressReporter,Some(threads_per_contest),None,None));
+                    }
+                });
+            }
+        });
+        solutions.into_iter().map(|s|s.into_inner().unwrap().unwrap()).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    problems.into_iter().map(|p|p.solve_with_threads(&mut NoProgressReporter,None,None,None)).collect()
+}
+
+/// A one line summary of the solution for one contest.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct ContestSummary {
+    pub name : String,
+    /// The winner's name, from the `candidates` field of the metadata if present.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub winner : Option<String>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub assertions : Option<usize>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub difficulty : Option<AssertionDifficulty>,
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub margin : Option<BallotPaperCount>,
+    /// Why there is no solution, if there isn't one.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub error : Option<RaireError>,
+}
+
+/// A summary of the solutions for all the contests in an election.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct BatchReport {
+    pub contests : Vec<ContestSummary>,
+    /// The index in `contests` of the solved contest with the greatest difficulty, if any were solved.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub hardest_contest : Option<usize>,
+    /// The number of contests for which there is no solution.
+    pub failed : usize,
+}
+
+impl BatchReport {
+    /// Summarise the solutions, named by the corresponding entries in `names`.
+    pub fn new(names:&[String],solutions:&[RaireSolution]) -> Self {
+        let contests : Vec<ContestSummary> = names.iter().zip(solutions).map(|(name,solution)|match &solution.solution {
+            Ok(result) => ContestSummary{ name: name.clone(), winner: Some(candidate_name(&candidate_names_from_metadata(&solution.metadata),result.winner)), assertions: Some(result.assertions.len()), difficulty: Some(result.difficulty), margin: Some(result.margin), error: None },
+            Err(e) => ContestSummary{ name: name.clone(), winner: None, assertions: None, difficulty: None, margin: None, error: Some(e.clone()) },
+        }).collect();
+        let hardest_contest = contests.iter().enumerate().filter_map(|(i,c)|c.difficulty.map(|d|(i,d))).reduce(|a,b|if b.1>a.1 { b } else { a }).map(|(i,_)|i);
+        let failed = contests.iter().filter(|c|c.error.is_some()).count();
+        BatchReport{contests,hardest_contest,failed}
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Candidate names, so that programs can refer to candidates by name rather than keeping their own map from [CandidateIndex] to name.
+//!
+//! The names are conventionally the `candidates` array in the `metadata` of a [crate::RaireProblem], copied into the [crate::RaireSolution].
+//! Names are matched ignoring case, accents on Latin letters, and leading, trailing and repeated whitespace, so two candidates whose names
+//! only differ in these ways are rejected as ambiguous.
+
+use serde::{Deserialize, Serialize};
+use crate::assertions::{candidate_name, Assertion, NotEliminatedBefore, NotEliminatedNext};
+use crate::audit_type::AssertionDifficulty;
+use crate::explain::candidate_names_from_metadata;
+use crate::irv::{BallotPaperCount, CandidateIndex};
+use crate::raire_algorithm::RaireResult;
+use crate::RaireError;
+
+/// The unaccented lower case letter for each character from U+00C0 to U+017F, or the character itself if it has none.
+const UNACCENTED : &str = "aaaaaaÆceeeeiiiidnooooo×ouuuuyÞßaaaaaaæceeeeiiiidnooooo÷ouuuuyþyaaaaaaccccccccddddeeeeeeeeeegggggggghhhhiiiiiiiiiiĲĳjjkkkllllllllllnnnnnnnnnooooooŒœrrrrrrssssssssttttttuuuuuuuuuuuuwwyyyzzzzzzs";
+
+/// The form of a name used for matching: lower case, without accents on Latin letters, and with words separated by single spaces.
+pub fn normalize_name(name:&str) -> String {
+    let mut res = String::new();
+    for word in name.split_whitespace() {
+        if !res.is_empty() { res.push(' '); }
+        for c in word.chars().flat_map(char::to_lowercase) {
+            if ('\u{300}'..='\u{36f}').contains(&c) { continue; } // combining accents, as in decomposed text.
+            let unaccented = (c as u32).checked_sub(0xC0).and_then(|i|UNACCENTED.chars().nth(i as usize)).unwrap_or(c);
+            res.extend(unaccented.to_lowercase());
+        }
+    }
+    res
+}
+
+/// The names of the candidates, in [CandidateIndex] order.
+#[derive(Clone,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct CandidateList {
+    names : Vec<String>,
+    normalized : Vec<String>,
+}
+
+impl TryFrom<Vec<String>> for CandidateList {
+    type Error = RaireError;
+    fn try_from(names:Vec<String>) -> Result<Self,RaireError> { CandidateList::new(names) }
+}
+
+impl From<CandidateList> for Vec<String> {
+    fn from(list:CandidateList) -> Self { list.names }
+}
+
+impl CandidateList {
+    /// Fails with [RaireError::AmbiguousCandidateNames] if two names are the same after [normalize_name]. Empty names are allowed, but cannot be looked up.
+    pub fn new(names:Vec<String>) -> Result<Self,RaireError> {
+        let normalized : Vec<String> = names.iter().map(|n|normalize_name(n)).collect();
+        for (i,n) in normalized.iter().enumerate() {
+            if let Some(j) = normalized[..i].iter().position(|earlier|earlier==n && !n.is_empty()) { return Err(RaireError::AmbiguousCandidateNames(names[j].clone(),names[i].clone())); }
+        }
+        Ok(CandidateList{names,normalized})
+    }
+
+    /// The names in the `candidates` field of the metadata, or an empty list if there is none.
+    pub fn from_metadata(metadata:&serde_json::Value) -> Result<Self,RaireError> { CandidateList::new(candidate_names_from_metadata(metadata)) }
+
+    pub fn names(&self) -> &[String] { &self.names }
+    pub fn len(&self) -> usize { self.names.len() }
+    pub fn is_empty(&self) -> bool { self.names.is_empty() }
+
+    /// The name of a candidate, or `#3` style if the candidate has no name.
+    pub fn name(&self,candidate:CandidateIndex) -> String {
+        let name = candidate_name(&self.names,candidate);
+        if name.is_empty() { format!("#{}",candidate.0) } else { name }
+    }
+
+    /// Find a candidate by name, ignoring case, accents and spacing. The `#3` style of [CandidateList::name] is also accepted for unnamed candidates.
+    pub fn index(&self,name:&str) -> Result<CandidateIndex,RaireError> {
+        let normalized = normalize_name(name);
+        if let Some(i) = self.normalized.iter().position(|n|*n==normalized && !n.is_empty()) { return Ok(CandidateIndex(i as u32)); }
+        match normalized.strip_prefix('#').and_then(|i|i.parse::<usize>().ok()) {
+            Some(i) if i<self.names.len() && self.names[i].is_empty() => Ok(CandidateIndex(i as u32)),
+            _ => Err(RaireError::UnknownCandidateName(name.to_string())),
+        }
+    }
+
+    fn indices(&self,names:&[&str]) -> Result<Vec<CandidateIndex>,RaireError> { names.iter().map(|n|self.index(n)).collect() }
+
+    /// The assertion that the candidate named `winner` is not eliminated before the candidate named `loser`.
+    pub fn neb(&self,winner:&str,loser:&str) -> Result<Assertion,RaireError> {
+        Ok(Assertion::NEB(NotEliminatedBefore{ winner: self.index(winner)?, loser: self.index(loser)? }))
+    }
+
+    /// The assertion that the candidate named `winner` beats the candidate named `loser` when only the named `continuing` candidates remain.
+    pub fn nen(&self,winner:&str,loser:&str,continuing:&[&str]) -> Result<Assertion,RaireError> {
+        Ok(Assertion::NEN(NotEliminatedNext{ winner: self.index(winner)?, loser: self.index(loser)?, continuing: self.indices(continuing)?.into() }))
+    }
+
+    /// The assertion with candidates given by name.
+    pub fn named_assertion(&self,assertion:&Assertion) -> NamedAssertion {
+        match assertion {
+            Assertion::NEB(neb) => NamedAssertion::NEB{ winner: self.name(neb.winner), loser: self.name(neb.loser) },
+            Assertion::NEN(nen) => NamedAssertion::NEN{ winner: self.name(nen.winner), loser: self.name(nen.loser), continuing: nen.continuing.iter().map(|&c|self.name(c)).collect() },
+        }
+    }
+
+    /// The inverse of [CandidateList::named_assertion].
+    pub fn resolve_assertion(&self,assertion:&NamedAssertion) -> Result<Assertion,RaireError> {
+        match assertion {
+            NamedAssertion::NEB{winner,loser} => self.neb(winner,loser),
+            NamedAssertion::NEN{winner,loser,continuing} => self.nen(winner,loser,&continuing.iter().map(|c|c.as_str()).collect::<Vec<_>>()),
+        }
+    }
+
+    /// The winner and assertions of a result with candidates given by name.
+    pub fn named_result(&self,result:&RaireResult) -> NamedResult {
+        NamedResult{
+            winner: self.name(result.winner),
+            difficulty: result.difficulty,
+            margin: result.margin,
+            assertions: result.assertions.iter().map(|a|NamedAssertionAndDifficulty{ assertion: self.named_assertion(&a.assertion), margin: a.margin, difficulty: a.difficulty }).collect(),
+        }
+    }
+}
+
+/// An [Assertion] with candidates given by name. The JSON form is the same as an assertion's, with names in place of indices.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(tag = "type")]
+pub enum NamedAssertion {
+    NEB{ winner:String, loser:String },
+    NEN{ winner:String, loser:String, continuing:Vec<String> },
+}
+
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct NamedAssertionAndDifficulty {
+    pub assertion : NamedAssertion,
+    pub margin : BallotPaperCount,
+    pub difficulty : AssertionDifficulty,
+}
+
+/// The parts of a [RaireResult] that mention candidates, with candidates given by name.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct NamedResult {
+    pub winner : String,
+    pub difficulty : AssertionDifficulty,
+    pub margin : BallotPaperCount,
+    pub assertions : Vec<NamedAssertionAndDifficulty>,
+}
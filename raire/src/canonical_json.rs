@@ -0,0 +1,65 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! A canonical JSON serialization, so that the same value is written as the same bytes on every machine, and hashes of outputs
+//! can be compared across machines and with other implementations.
+//!
+//! The JSON is compact (no whitespace), object keys are sorted by their UTF-8 bytes at every level (so the order does not depend on
+//! the order fields are declared in, or on whether `serde_json` keeps insertion order), and numbers are written as `serde_json` writes
+//! them: integers exactly, and floating point numbers as the shortest decimal that reads back as the same number, never using the
+//! locale. Non-finite floating point numbers are written as `null`.
+//!
+//! Difficulties are computed in floating point, so may differ in the last bits between machines. To compare outputs across machines,
+//! round them with [crate::RaireProblem::difficulty_decimal_places], see [round_to_decimal_places].
+
+use std::fmt::Write;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Serialize a value as canonical JSON.
+pub fn to_canonical_json<T:Serialize+?Sized>(value:&T) -> serde_json::Result<String> {
+    let mut out = String::new();
+    write_value(&mut out,&serde_json::to_value(value)?);
+    Ok(out)
+}
+
+fn write_value(out:&mut String,value:&Value) {
+    match value {
+        Value::Object(map) => {
+            let mut entries : Vec<(&String,&Value)> = map.iter().collect();
+            entries.sort_by(|(a,_),(b,_)|a.as_bytes().cmp(b.as_bytes()));
+            out.push('{');
+            for (i,(key,value)) in entries.into_iter().enumerate() {
+                if i>0 { out.push(','); }
+                write_value(out,&Value::String(key.clone()));
+                out.push(':');
+                write_value(out,value);
+            }
+            out.push('}');
+        }
+        Value::Array(values) => {
+            out.push('[');
+            for (i,value) in values.iter().enumerate() {
+                if i>0 { out.push(','); }
+                write_value(out,value);
+            }
+            out.push(']');
+        }
+        _ => { let _ = write!(out,"{}",value); } // scalars are written the same whatever the serde_json features.
+    }
+}
+
+/// Round a number to the given number of decimal places, rounding halves away from zero. Infinite and NaN numbers, and numbers
+/// too large to be scaled, are unchanged.
+pub fn round_to_decimal_places(value:f64,decimal_places:u32) -> f64 {
+    let scale = 10f64.powi(decimal_places.min(i32::MAX as u32) as i32);
+    let rounded = (value*scale).round()/scale;
+    if rounded.is_finite() { rounded } else { value }
+}
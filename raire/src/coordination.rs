@@ -0,0 +1,106 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Estimating the number of distinct ballots to retrieve when several contests on the same ballots are audited together.
+//!
+//! Each contest needs a sample from the ballots containing it, given by the ballot styles that include it. If the samples are
+//! drawn independently, the cost is the sum of the sample sizes. With consistent sampling (each ballot is given a random
+//! priority, and each contest takes the ballots containing it with the lowest priorities) a ballot retrieved for one contest
+//! counts for every other contest on it. A contest with sample size n from N ballots then samples a fraction n/N of every
+//! style containing it, so a style is sampled at the largest fraction of any of its contests.
+//!
+//! Sample sizes are estimated for a ballot comparison audit using the ALPHA test with no discrepancies, as in
+//! [BallotComparisonALPHA::expected_sample_size], for each of several risk limits so officials can compare them.
+
+use serde::{Deserialize, Serialize};
+use crate::audit_type::BallotComparisonALPHA;
+use crate::irv::BallotPaperCount;
+use crate::raire_algorithm::RaireResult;
+use crate::RaireError;
+
+/// A set of ballots that all contain the same contests, such as one ballot style in one precinct.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct BallotStyle {
+    pub name : String,
+    /// The number of ballots of this style.
+    pub ballots : BallotPaperCount,
+    /// The names of the contests on this style. Contests that are not being audited are ignored.
+    pub contests : Vec<String>,
+}
+
+/// The estimated sample for one contest at each risk limit.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct ContestSample {
+    pub name : String,
+    /// The number of ballots containing the contest, from the ballot styles.
+    pub ballots : BallotPaperCount,
+    /// The smallest assertion margin divided by `ballots`.
+    pub diluted_margin : f64,
+    /// The estimated sample size for each risk limit, in the same order as [CoordinationReport::risk_limits]. At most `ballots`.
+    pub sample_sizes : Vec<usize>,
+}
+
+/// The total cost of auditing all the contests together at one risk limit.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct CoordinatedSample {
+    pub risk_limit : f64,
+    /// The sum of the contests' sample sizes, the ballots retrieved if each contest's sample is drawn separately (ignoring
+    /// the rare ballots drawn for more than one contest).
+    pub independent_ballots : usize,
+    /// The expected number of distinct ballots retrieved with consistent sampling.
+    pub consistent_ballots : f64,
+    /// The largest sample size of any one contest, a lower bound on the ballots retrieved however the samples are coordinated.
+    pub largest_contest_sample : usize,
+    /// The index in [CoordinationReport::contests] of the contest with the largest sample size, if there are any contests.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub largest_contest : Option<usize>,
+}
+
+/// The estimated ballots to retrieve when auditing several contests together, at each of several risk limits.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct CoordinationReport {
+    pub risk_limits : Vec<f64>,
+    pub contests : Vec<ContestSample>,
+    /// The totals for each risk limit, in the same order as `risk_limits`.
+    pub totals : Vec<CoordinatedSample>,
+}
+
+/// Estimate the ballots to retrieve to audit the contests, given as (name,result) pairs, on the ballot styles, at each risk limit.
+/// Every contest must be on at least one style with some ballots.
+pub fn coordinate_samples(contests:&[(String,&RaireResult)],styles:&[BallotStyle],risk_limits:&[f64]) -> Result<CoordinationReport,RaireError> {
+    let contest_ballots = |name:&str| -> BallotPaperCount { styles.iter().filter(|s|s.contests.iter().any(|c|c==name)).map(|s|s.ballots).sum() };
+    let contests : Vec<ContestSample> = contests.iter().map(|(name,result)|{
+        let ballots = contest_ballots(name);
+        if ballots.0==0 { return Err(RaireError::ContestNotOnAnyBallots(name.clone())); }
+        let diluted_margin = result.margin.0 as f64/ballots.0 as f64;
+        let sample_sizes = risk_limits.iter().map(|&confidence|{
+            let test = BallotComparisonALPHA{ confidence, total_auditable_ballots: ballots, d: 100.0, eta0: None };
+            let n = test.expected_sample_size(diluted_margin);
+            if n.is_finite() { (n.ceil() as usize).min(ballots.0) } else { ballots.0 }
+        }).collect();
+        Ok(ContestSample{ name: name.clone(), ballots, diluted_margin, sample_sizes })
+    }).collect::<Result<_,_>>()?;
+    let totals = risk_limits.iter().enumerate().map(|(i,&risk_limit)|{
+        let sampled_fraction = |contest:&ContestSample| contest.sample_sizes[i] as f64/contest.ballots.0 as f64;
+        let consistent_ballots = styles.iter().map(|style|{
+            let fraction = contests.iter().filter(|c|style.contests.contains(&c.name)).map(sampled_fraction).fold(0.0,f64::max);
+            style.ballots.0 as f64*fraction
+        }).sum();
+        let largest_contest = contests.iter().enumerate().max_by_key(|(_,c)|c.sample_sizes[i]).map(|(index,_)|index);
+        CoordinatedSample{
+            risk_limit,
+            independent_ballots: contests.iter().map(|c|c.sample_sizes[i]).sum(),
+            consistent_ballots,
+            largest_contest_sample: largest_contest.map_or(0,|index|contests[index].sample_sizes[i]),
+            largest_contest,
+        }
+    }).collect();
+    Ok(CoordinationReport{ risk_limits: risk_limits.to_vec(), contests, totals })
+}
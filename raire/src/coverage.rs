@@ -0,0 +1,98 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Which assertion prunes which elimination order suffix, as a matrix built from the trees of
+//! [crate::raire_algorithm::RaireResult::elimination_trees].
+//!
+//! There is one row per frontier node of the trees (an elimination order suffix ruled out by at least one assertion),
+//! and one column per assertion. Columns with few entries, or whose entries are all shared with other columns,
+//! show assertions doing little work. The same information is also summed over the frontier nodes for each alternate winner.
+
+use serde::{Deserialize, Serialize};
+use crate::assertions::EliminationOrderSuffix;
+use crate::irv::CandidateIndex;
+use crate::tree_showing_what_assertions_pruned_leaves::TreeNodeShowingWhatAssertionsPrunedIt;
+
+/// A frontier node of the trees, and the assertions that prune it.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct CoverageRow {
+    /// The elimination order suffix ruled out, from first eliminated to the alternate winner.
+    pub suffix : EliminationOrderSuffix,
+    /// The indices of the assertions that rule it out, in increasing order.
+    pub pruned_by : Vec<usize>,
+}
+
+impl CoverageRow {
+    /// The candidate who would win with this elimination order suffix.
+    pub fn alternate_winner(&self) -> CandidateIndex { *self.suffix.last().unwrap() }
+}
+
+/// How the assertions rule out one alternate winner.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct AlternateWinnerCoverage {
+    pub candidate : CandidateIndex,
+    /// The number of frontier nodes in this candidate's tree.
+    pub frontier : usize,
+    /// For each assertion, the number of those frontier nodes it prunes.
+    pub pruned_by_assertion : Vec<usize>,
+}
+
+/// Which assertions prune which frontier nodes of the elimination order trees.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct CoverageMatrix {
+    /// The number of assertions, that is, columns.
+    pub num_assertions : usize,
+    /// One row per frontier node, in depth first order through the trees.
+    pub rows : Vec<CoverageRow>,
+    /// One entry per tree, that is, per candidate other than the winner.
+    pub alternate_winners : Vec<AlternateWinnerCoverage>,
+}
+
+impl CoverageMatrix {
+    pub fn new(trees:&[TreeNodeShowingWhatAssertionsPrunedIt],num_assertions:usize) -> Self {
+        let mut rows = vec![];
+        let mut alternate_winners = vec![];
+        for tree in trees {
+            let start = rows.len();
+            add_rows(tree,&mut vec![],&mut rows);
+            let mut pruned_by_assertion = vec![0;num_assertions];
+            for row in &rows[start..] {
+                for &assertion in &row.pruned_by { pruned_by_assertion[assertion]+=1; }
+            }
+            alternate_winners.push(AlternateWinnerCoverage{ candidate: tree.candidate_being_eliminated_at_this_node, frontier: rows.len()-start, pruned_by_assertion });
+        }
+        CoverageMatrix{num_assertions,rows,alternate_winners}
+    }
+
+    /// Whether the given assertion prunes the given row.
+    pub fn prunes(&self,row:usize,assertion:usize) -> bool { self.rows[row].pruned_by.contains(&assertion) }
+
+    /// For each assertion, whether every row it prunes is also pruned by some other assertion.
+    /// Such assertions may be redundant, although removing one could leave descendants of those rows to be pruned.
+    pub fn redundant(&self) -> Vec<bool> {
+        let mut needed = vec![false;self.num_assertions];
+        for row in &self.rows {
+            if let [only] = row.pruned_by[..] { needed[only]=true; }
+        }
+        needed.into_iter().map(|needed|!needed).collect()
+    }
+}
+
+/// path is the reverse elimination order from the root down to this node's parent.
+fn add_rows(node:&TreeNodeShowingWhatAssertionsPrunedIt,path:&mut Vec<CandidateIndex>,rows:&mut Vec<CoverageRow>) {
+    path.push(node.candidate_being_eliminated_at_this_node);
+    if !node.pruning_assertions.is_empty() {
+        let mut pruned_by = node.pruning_assertions.clone();
+        pruned_by.sort_unstable();
+        rows.push(CoverageRow{ suffix: path.iter().rev().cloned().collect(), pruned_by });
+    }
+    for child in &node.children { add_rows(child,path,rows); }
+    path.pop();
+}
@@ -0,0 +1,114 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The differences between two sets of assertions for the same contest, such as from preliminary and final counts, or from
+//! this and another implementation of RAIRE.
+//!
+//! Assertions are matched if they are the same assertion. If both solutions have candidate names in their metadata, candidates
+//! are matched by name (see [crate::candidates]), so the two need not number the candidates the same way; otherwise by index.
+//! Everything is reported in the numbering of the first solution.
+
+use serde::{Deserialize, Serialize};
+use crate::assertions::{Assertion, AssertionAndDifficulty};
+use crate::audit_type::AssertionDifficulty;
+use crate::candidates::CandidateList;
+use crate::irv::{BallotPaperCount, CandidateIndex};
+use crate::raire_algorithm::RaireResult;
+use crate::RaireError;
+
+/// An assertion in both solutions, with its margin and difficulty in each.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct CommonAssertion {
+    pub assertion : Assertion,
+    pub first_margin : BallotPaperCount,
+    pub second_margin : BallotPaperCount,
+    pub first_difficulty : AssertionDifficulty,
+    pub second_difficulty : AssertionDifficulty,
+}
+
+impl CommonAssertion {
+    /// Whether the margins differ, or the difficulties differ by more than the given fraction of the larger.
+    pub fn is_changed(&self,relative_tolerance:f64) -> bool {
+        self.first_margin!=self.second_margin || !approximately_equal(self.first_difficulty,self.second_difficulty,relative_tolerance)
+    }
+}
+
+/// Whether two difficulties differ by at most the given fraction of the larger. Infinite difficulties are only equal to themselves.
+fn approximately_equal(a:AssertionDifficulty,b:AssertionDifficulty,relative_tolerance:f64) -> bool {
+    a==b || (a-b).abs()<=relative_tolerance*a.abs().max(b.abs())
+}
+
+/// The differences between two sets of assertions.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct AssertionSetDiff {
+    /// Whether candidates were matched by name rather than index.
+    pub matched_by_name : bool,
+    pub first_winner : CandidateIndex,
+    pub second_winner : CandidateIndex,
+    pub first_difficulty : AssertionDifficulty,
+    pub second_difficulty : AssertionDifficulty,
+    pub first_margin : BallotPaperCount,
+    pub second_margin : BallotPaperCount,
+    /// Assertions only in the first solution, in its order.
+    pub removed : Vec<AssertionAndDifficulty>,
+    /// Assertions only in the second solution, in its order.
+    pub added : Vec<AssertionAndDifficulty>,
+    /// Assertions in both solutions, in the order of the first.
+    pub common : Vec<CommonAssertion>,
+}
+
+impl AssertionSetDiff {
+    /// Compare two results, given the names of their candidates (which may be empty).
+    /// Fails if a candidate of the second result has a name that is not one of the first's candidates.
+    pub fn new(first:&RaireResult,first_names:&CandidateList,second:&RaireResult,second_names:&CandidateList) -> Result<Self,RaireError> {
+        let matched_by_name = !(first_names.is_empty() || second_names.is_empty());
+        let renumber = |assertion:&Assertion| if matched_by_name { first_names.resolve_assertion(&second_names.named_assertion(assertion)) } else { Ok(assertion.clone()) };
+        let second_winner = if matched_by_name { first_names.index(&second_names.name(second.winner))? } else { second.winner };
+        let mut added = vec![];
+        let mut second_assertions = vec![];
+        for a in &second.assertions {
+            let assertion = renumber(&a.assertion)?;
+            second_assertions.push(AssertionAndDifficulty{ assertion, ..a.clone() });
+        }
+        let mut matched = vec![false;second_assertions.len()];
+        let mut removed = vec![];
+        let mut common = vec![];
+        for a in &first.assertions {
+            match (0..second_assertions.len()).find(|&i|!matched[i] && second_assertions[i].assertion==a.assertion) {
+                Some(i) => {
+                    matched[i]=true;
+                    let b = &second_assertions[i];
+                    common.push(CommonAssertion{ assertion: a.assertion.clone(), first_margin: a.margin, second_margin: b.margin, first_difficulty: a.difficulty, second_difficulty: b.difficulty });
+                }
+                None => removed.push(a.clone()),
+            }
+        }
+        for (a,matched) in second_assertions.into_iter().zip(matched) { if !matched { added.push(a); } }
+        Ok(AssertionSetDiff{
+            matched_by_name,
+            first_winner: first.winner, second_winner,
+            first_difficulty: first.difficulty, second_difficulty: second.difficulty,
+            first_margin: first.margin, second_margin: second.margin,
+            removed, added, common,
+        })
+    }
+
+    /// The assertions in both solutions whose margin or difficulty changed, as for [CommonAssertion::is_changed].
+    pub fn changed(&self,relative_tolerance:f64) -> impl Iterator<Item=&CommonAssertion> {
+        self.common.iter().filter(move |a|a.is_changed(relative_tolerance))
+    }
+
+    /// Whether the two solutions have the same winner, assertions, overall margin and difficulty, and assertion margins and difficulties,
+    /// with difficulties compared as for [CommonAssertion::is_changed]. The order of the assertions does not matter.
+    pub fn is_same(&self,relative_tolerance:f64) -> bool {
+        self.first_winner==self.second_winner && self.first_margin==self.second_margin && approximately_equal(self.first_difficulty,self.second_difficulty,relative_tolerance)
+            && self.removed.is_empty() && self.added.is_empty() && self.changed(relative_tolerance).next().is_none()
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The complete elimination orders consistent with a set of assertions, that is, the accounts of how the count could have gone
+//! that the assertions still admit, so that researchers can see what a set of assertions does not rule out and compute statistics over it.
+//!
+//! The orders are found from the trees of [TreeNodeShowingWhatAssertionsPrunedIt], one with each candidate as the winner. A valid leaf
+//! of a tree is an elimination order suffix that no assertion can rule out however it is extended, so it stands for every order of the
+//! other candidates followed by the suffix. There may be up to num_candidates factorial consistent orders, so they are counted, sampled
+//! and summarised from the suffixes without listing them; [ConsistentEliminationOrders::iter] lists them one at a time.
+
+use serde::{Deserialize, Serialize};
+use crate::assertions::{Assertion, EliminationOrder, EliminationOrderSuffix};
+use crate::irv::CandidateIndex;
+use crate::manifest::sha256;
+use crate::RaireError;
+use crate::sampling::draw_ballot;
+use crate::timeout::TimeOut;
+use crate::tree_showing_what_assertions_pruned_leaves::{HowFarToContinueSearchTreeWhenPruningAssertionFound, TreeNodeShowingWhatAssertionsPrunedIt};
+
+/// The elimination orders consistent with every one of a set of assertions.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct ConsistentEliminationOrders {
+    pub num_candidates : u32,
+    /// The elimination order suffixes of the valid leaves of the trees, each from first eliminated to winner. Every consistent
+    /// order is exactly one of these preceded by some order of the candidates not in it.
+    pub suffixes : Vec<EliminationOrderSuffix>,
+}
+
+impl ConsistentEliminationOrders {
+    /// Find the orders consistent with the assertions. If a tree would have more than `max_nodes` nodes, fails with [RaireError::TreeTooLarge].
+    pub fn new(assertions:&[Assertion],num_candidates:u32,max_nodes:Option<usize>,timeout:&mut TimeOut) -> Result<Self,RaireError> {
+        let all_assertion_indices : Vec<usize> = (0..assertions.len()).collect();
+        let mut suffixes = vec![];
+        for candidate in (0..num_candidates).map(CandidateIndex) {
+            let tree = TreeNodeShowingWhatAssertionsPrunedIt::new_with_node_limit(&[],candidate,&all_assertion_indices,assertions,num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,max_nodes,timeout)?;
+            suffixes.extend(tree.unpruned_elimination_order_suffixes());
+        }
+        Ok(ConsistentEliminationOrders{num_candidates,suffixes})
+    }
+
+    /// The number of candidates not in a suffix, who may be eliminated in any order before it.
+    fn unordered(&self,suffix:&EliminationOrderSuffix) -> usize { self.num_candidates as usize-suffix.len() }
+
+    /// The number of consistent elimination orders, or None if it is more than [u128::MAX].
+    pub fn count(&self) -> Option<u128> {
+        self.suffixes.iter().try_fold(0u128,|total,suffix|total.checked_add(factorial(self.unordered(suffix))?))
+    }
+
+    /// The candidates who win some consistent order, in increasing order.
+    pub fn winners(&self) -> Vec<CandidateIndex> {
+        let mut winners : Vec<CandidateIndex> = self.suffixes.iter().filter_map(|suffix|suffix.last().copied()).collect();
+        winners.sort_unstable_by_key(|c|c.0);
+        winners.dedup();
+        winners
+    }
+
+    /// All the consistent elimination orders, each from first eliminated to winner. Orders ending in the same suffix are consecutive,
+    /// with the candidates before it in lexicographic order of their indices.
+    pub fn iter(&self) -> EliminationOrders<'_> {
+        EliminationOrders{ orders: self, next_suffix: 0, current: None }
+    }
+
+    /// `sample_size` consistent elimination orders drawn at random with replacement, each equally likely (up to the rounding of floating
+    /// point probabilities). The draws are reproducible from the `seed`, using the SHA-256 counter generator of [crate::sampling].
+    /// Empty if there are no consistent orders.
+    pub fn sample(&self,seed:&str,sample_size:usize) -> Vec<EliminationOrder> {
+        if self.suffixes.is_empty() { return vec![]; }
+        let weights : Vec<f64> = self.suffixes.iter().map(|suffix|factorial_f64(self.unordered(suffix))).collect();
+        let total : f64 = weights.iter().sum();
+        let mut draw = 0;
+        (0..sample_size).map(|_|{
+            draw+=1;
+            let mut target = uniform(seed,draw)*total;
+            let chosen = weights.iter().position(|&w|{ target-=w; target<0.0 }).unwrap_or(weights.len()-1);
+            let suffix = &self.suffixes[chosen];
+            let mut order : EliminationOrder = (0..self.num_candidates).map(CandidateIndex).filter(|c|!suffix.contains(c)).collect();
+            for i in (1..order.len()).rev() { // Fisher-Yates shuffle.
+                draw+=1;
+                order.swap(i,draw_ballot(seed,draw,i+1)-1);
+            }
+            order.extend_from_slice(suffix);
+            order
+        }).collect()
+    }
+
+    /// Statistics over all the consistent elimination orders.
+    pub fn statistics(&self) -> EliminationOrderStatistics {
+        let n = self.num_candidates as usize;
+        let total : f64 = self.suffixes.iter().map(|suffix|factorial_f64(self.unordered(suffix))).sum();
+        let mut winner_fraction = vec![0.0;n];
+        let mut position_fraction = vec![vec![0.0;n];n];
+        for suffix in &self.suffixes {
+            let unordered = self.unordered(suffix);
+            let fraction = factorial_f64(unordered)/total;
+            if let Some(&winner) = suffix.last() { winner_fraction[winner.0 as usize]+=fraction; }
+            for (i,&c) in suffix.iter().enumerate() { position_fraction[c.0 as usize][unordered+i]+=fraction; }
+            // each candidate before the suffix is equally likely to be in each of the first `unordered` positions.
+            for c in (0..self.num_candidates).map(CandidateIndex).filter(|c|!suffix.contains(c)) {
+                for position in &mut position_fraction[c.0 as usize][..unordered] { *position+=fraction/unordered as f64; }
+            }
+        }
+        EliminationOrderStatistics{ orders: self.count(), winner_fraction, position_fraction }
+    }
+}
+
+/// Statistics over the elimination orders consistent with some assertions, computed without listing them.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct EliminationOrderStatistics {
+    /// The number of consistent orders, as for [ConsistentEliminationOrders::count].
+    pub orders : Option<u128>,
+    /// For each candidate, the fraction of the orders in which they win.
+    pub winner_fraction : Vec<f64>,
+    /// For each candidate, the fraction of the orders in which they are eliminated at each position, 0 being eliminated first
+    /// and num_candidates-1 winning.
+    pub position_fraction : Vec<Vec<f64>>,
+}
+
+/// An iterator over the elimination orders consistent with some assertions. See [ConsistentEliminationOrders::iter].
+pub struct EliminationOrders<'a> {
+    orders : &'a ConsistentEliminationOrders,
+    next_suffix : usize,
+    /// The suffix being extended, and the candidates before it in the next order to be returned.
+    current : Option<(&'a EliminationOrderSuffix,Vec<CandidateIndex>)>,
+}
+
+impl Iterator for EliminationOrders<'_> {
+    type Item = EliminationOrder;
+
+    fn next(&mut self) -> Option<EliminationOrder> {
+        loop {
+            if let Some((suffix,before)) = &mut self.current {
+                let order : EliminationOrder = before.iter().chain(suffix.iter()).copied().collect();
+                if !next_permutation(before) { self.current=None; }
+                return Some(order);
+            }
+            let suffix = self.orders.suffixes.get(self.next_suffix)?;
+            self.next_suffix+=1;
+            self.current=Some((suffix,(0..self.orders.num_candidates).map(CandidateIndex).filter(|c|!suffix.contains(c)).collect()));
+        }
+    }
+}
+
+/// Change the candidates to the next permutation in lexicographic order of their indices, returning false if they were the last.
+fn next_permutation(candidates:&mut [CandidateIndex]) -> bool {
+    let Some(i) = (1..candidates.len()).rev().find(|&i|candidates[i-1].0<candidates[i].0) else { return false; };
+    let j = (i..candidates.len()).rev().find(|&j|candidates[i-1].0<candidates[j].0).unwrap();
+    candidates.swap(i-1,j);
+    candidates[i..].reverse();
+    true
+}
+
+fn factorial(n:usize) -> Option<u128> { (1..=n as u128).try_fold(1u128,|product,i|product.checked_mul(i)) }
+
+fn factorial_f64(n:usize) -> f64 { (1..=n).map(|i|i as f64).product() }
+
+/// A number uniformly distributed in [0,1) from the SHA-256 counter generator, as for [draw_ballot].
+fn uniform(seed:&str,draw:usize) -> f64 {
+    let hash = sha256(format!("{},{}",seed,draw).as_bytes());
+    let bits = u64::from_be_bytes(hash[..8].try_into().unwrap());
+    (bits>>11) as f64/(1u64<<53) as f64
+}
@@ -0,0 +1,101 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+//! Plain English explanations of assertions, and of why they show the announced winner is the only possible winner,
+//! for audit boards and the public.
+
+use std::fmt::Write;
+use crate::assertions::{candidate_name, Assertion, AssertionAndDifficulty};
+use crate::irv::CandidateIndex;
+use crate::raire_algorithm::RaireResult;
+use crate::RaireError;
+use crate::timeout::TimeOut;
+use crate::tree_showing_what_assertions_pruned_leaves::TreeNodeShowingWhatAssertionsPrunedIt;
+
+/// Get the candidate names from the `candidates` field of a problem's metadata, as used by the web front end.
+/// Missing names are empty strings, which [candidate_name] replaces by the candidate number.
+pub fn candidate_names_from_metadata(metadata:&serde_json::Value) -> Vec<String> {
+    metadata["candidates"].as_array().map(|a|a.iter().map(|v|v.as_str().unwrap_or_default().to_string()).collect()).unwrap_or_default()
+}
+
+fn name(candidate_names:&[String],c:CandidateIndex) -> String {
+    let name = candidate_name(candidate_names,c);
+    if name.is_empty() { format!("#{}",c.0) } else { name }
+}
+
+/// A list of names like `Alice, Bob and Chuan`.
+fn name_list(candidate_names:&[String],candidates:&[CandidateIndex]) -> String {
+    let names : Vec<String> = candidates.iter().map(|&c|name(candidate_names,c)).collect();
+    match names.split_last() {
+        None => String::new(),
+        Some((last,[])) => last.clone(),
+        Some((last,rest)) => format!("{} and {}",rest.join(", "),last),
+    }
+}
+
+/// Explain what an assertion says, and why it matters, in a sentence or two.
+pub fn explain_assertion(assertion:&AssertionAndDifficulty,candidate_names:&[String]) -> String {
+    let name = |c:CandidateIndex|name(candidate_names,c);
+    match &assertion.assertion {
+        Assertion::NEB(neb) => format!("{} has more first preference votes than {} has votes preferring {} to {}, by {} votes. So {} cannot be eliminated before {}, whatever else happens in the count.",
+                                       name(neb.winner),name(neb.loser),name(neb.loser),name(neb.winner),assertion.margin.0,name(neb.winner),name(neb.loser)),
+        Assertion::NEN(nen) => format!("When only {} remain, {} has more votes than {}, by {} votes. So {} is not the next candidate eliminated at that point.",
+                                       name_list(candidate_names,&nen.continuing),name(nen.winner),name(nen.loser),assertion.margin.0,name(nen.winner)),
+    }
+}
+
+/// Describe the scenarios ruled out in one tree. `path` is the reverse elimination order down to and including this node.
+fn explain_tree_node(node:&TreeNodeShowingWhatAssertionsPrunedIt,path:&mut Vec<CandidateIndex>,candidate_names:&[String],out:&mut String) {
+    path.push(node.candidate_being_eliminated_at_this_node);
+    let order : Vec<CandidateIndex> = path.iter().rev().cloned().collect();
+    let (last,eliminated) = order.split_last().unwrap();
+    let scenario = match eliminated {
+        [] => format!("{} winning",name(candidate_names,*last)),
+        [c] => format!("{} being the last candidate eliminated, leaving {} the winner",name(candidate_names,*c),name(candidate_names,*last)),
+        _ => format!("the last candidates eliminated being {}, leaving {} the winner",eliminated.iter().map(|&c|name(candidate_names,c)).collect::<Vec<_>>().join(" then "),name(candidate_names,*last)),
+    };
+    if !node.pruning_assertions.is_empty() {
+        let numbers : Vec<String> = node.pruning_assertions.iter().map(|a|(a+1).to_string()).collect();
+        writeln!(out,"  - {} is ruled out by assertion {}.",capitalize(&scenario),numbers.join(" and ")).unwrap();
+    } else if node.children.is_empty() {
+        writeln!(out,"  - {} is NOT ruled out by any assertion.",capitalize(&scenario)).unwrap();
+    }
+    for child in &node.children { explain_tree_node(child,path,candidate_names,out); }
+    path.pop();
+}
+
+fn capitalize(s:&str) -> String {
+    let mut chars = s.chars();
+    chars.next().map(|c|c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+/// Explain why the assertions in a RAIRE result show that the winner is the only possible winner:
+/// each assertion in words, then, for each other candidate, how every way they could win is ruled out.
+pub fn explain_solution(result:&RaireResult,candidate_names:&[String],timeout:&mut TimeOut) -> Result<String,RaireError> {
+    let mut out = String::new();
+    let winner = name(candidate_names,result.winner);
+    writeln!(out,"{} won the election. To confirm this, the audit checks {} assertion{} about the ballots:",winner,result.assertions.len(),if result.assertions.len()==1 {""} else {"s"}).unwrap();
+    for (i,a) in result.assertions.iter().enumerate() {
+        writeln!(out,"{}. {}",i+1,explain_assertion(a,candidate_names)).unwrap();
+    }
+    writeln!(out,"If these assertions are true, no other candidate could have won:").unwrap();
+    let trees = result.elimination_trees(timeout)?;
+    for tree in &trees {
+        let candidate = name(candidate_names,tree.candidate_being_eliminated_at_this_node);
+        if tree.valid { writeln!(out,"* {} is NOT ruled out.",candidate).unwrap(); }
+        else { writeln!(out,"* {} cannot have won, as every way they could win is ruled out:",candidate).unwrap(); }
+        explain_tree_node(tree,&mut vec![],candidate_names,&mut out);
+    }
+    if trees.iter().all(|tree|!tree.valid) {
+        writeln!(out,"So {} is the only candidate who could have won.",winner).unwrap();
+    }
+    Ok(out)
+}
@@ -0,0 +1,55 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The SHANGRLA assorter value of each assertion on each distinct ballot, for audit software that computes the discrepancy
+//! for a sampled ballot itself. The overstatement of a sampled ballot for an assertion is the assorter value of its CVR minus that
+//! of the paper ballot, in units of half a vote.
+//!
+//! See [Assertion::assorter_value] for the assorter of a single ballot.
+
+use serde::Deserialize;
+use serde::Serialize;
+use crate::assertions::Assertion;
+use crate::irv::{BallotPaperCount, CandidateIndex, Vote};
+
+/// The assorter values of a list of assertions on a list of ballots.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct AssorterMatrix {
+    /// The assertions, in the order of the values in each row.
+    pub assertions : Vec<Assertion>,
+    /// One row per ballot record.
+    pub rows : Vec<AssorterRow>,
+}
+
+/// The assorter values of each assertion on ballots with the same preferences.
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct AssorterRow {
+    /// The number of ballots with these preferences.
+    pub n : BallotPaperCount,
+    pub prefs : Vec<CandidateIndex>,
+    /// The assorter value, 0, 1/2 or 1, for each assertion.
+    pub values : Vec<f64>,
+}
+
+impl AssorterMatrix {
+    /// The assorter values of the given assertions on the given votes, with a row for each vote in the same order.
+    pub fn new(assertions:&[Assertion],votes:&[Vote]) -> Self {
+        let rows = votes.iter().map(|v|AssorterRow{ n: v.n, prefs: v.prefs.clone(), values: assertions.iter().map(|a|a.assorter_value(&v.prefs)).collect() }).collect();
+        AssorterMatrix{ assertions: assertions.to_vec(), rows }
+    }
+
+    /// The mean assorter value of each assertion over all the ballots, weighted by the number of each. An assertion is true
+    /// for these ballots if and only if its mean is more than 1/2. Each is 1/2 if there are no ballots.
+    pub fn means(&self) -> Vec<f64> {
+        let total : usize = self.rows.iter().map(|r|r.n.0).sum();
+        (0..self.assertions.len()).map(|i|{
+            if total==0 { 0.5 } else { self.rows.iter().map(|r|r.n.0 as f64*r.values[i]).sum::<f64>()/total as f64 }
+        }).collect()
+    }
+}
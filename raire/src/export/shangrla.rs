@@ -0,0 +1,88 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Export assertions in the JSON format read by [SHANGRLA](https://github.com/pbstark/SHANGRLA)
+//! (`Assertion.make_assertions_from_json`) and Arlo, so the output of raire-rs can be fed straight into a
+//! Python based audit.
+//!
+//! NEB assertions become `WINNER_ONLY` assertions, and NEN assertions become `IRV_ELIMINATION` assertions
+//! with `already_eliminated` being the candidates not in the NEN continuing list. Candidates are identified
+//! by name if the metadata has a `candidates` list, otherwise by index.
+
+use serde::Deserialize;
+use serde::Serialize;
+use crate::assertions::{candidate_name, Assertion};
+use crate::irv::CandidateIndex;
+use crate::{RaireError, RaireSolution};
+
+/// The top level SHANGRLA assertion file.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct ShangrlaAssertions {
+    pub audits : Vec<ShangrlaAudit>,
+}
+
+/// The assertions for one contest.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct ShangrlaAudit {
+    pub contest : String,
+    pub winner : String,
+    /// All candidates other than the winner.
+    pub eliminated : Vec<String>,
+    pub assertions : Vec<ShangrlaAssertion>,
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub enum ShangrlaAssertionType {
+    /// A NEB assertion.
+    #[serde(rename = "WINNER_ONLY")]
+    WinnerOnly,
+    /// A NEN assertion.
+    #[serde(rename = "IRV_ELIMINATION")]
+    IrvElimination,
+}
+
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct ShangrlaAssertion {
+    pub winner : String,
+    pub loser : String,
+    /// For IRV_ELIMINATION, the candidates eliminated before the assertion applies. Empty for WINNER_ONLY.
+    pub already_eliminated : Vec<String>,
+    pub assertion_type : ShangrlaAssertionType,
+    pub explanation : String,
+    /// The margin in votes, as computed by RAIRE.
+    pub margin : usize,
+    /// The difficulty, as computed by RAIRE for the requested audit type.
+    pub difficulty : f64,
+    /// The upper bound on the assorter for this assertion. Both NEB and NEN assorters take values in [0,1].
+    pub upper_bound : f64,
+}
+
+/// Convert a RAIRE solution into SHANGRLA form. The contest name is taken from the metadata `contest` field, or `default_contest` if not present.
+/// If RAIRE failed, the error is returned.
+pub fn to_shangrla(solution:&RaireSolution,default_contest:&str) -> Result<ShangrlaAssertions,RaireError> {
+    let result = solution.solution.as_ref().map_err(|e|e.clone())?;
+    let candidate_names : Vec<String> = (0..result.num_candidates).map(|c|{
+        solution.metadata["candidates"][c as usize].as_str().map(|s|s.to_string()).unwrap_or_else(||c.to_string())
+    }).collect();
+    let name = |c:CandidateIndex|candidate_name(&candidate_names,c);
+    let assertions = result.assertions.iter().map(|a|{
+        let (winner,loser,already_eliminated,assertion_type) = match &a.assertion {
+            Assertion::NEB(neb) => (neb.winner,neb.loser,vec![],ShangrlaAssertionType::WinnerOnly),
+            Assertion::NEN(nen) => (nen.winner,nen.loser,(0..result.num_candidates).map(CandidateIndex).filter(|c|!nen.continuing.contains(c)).map(name).collect(),ShangrlaAssertionType::IrvElimination),
+        };
+        ShangrlaAssertion{ winner:name(winner), loser:name(loser), already_eliminated, assertion_type, explanation:a.assertion.describe(&candidate_names), margin:a.margin.0, difficulty:a.difficulty, upper_bound:1.0 }
+    }).collect();
+    let audit = ShangrlaAudit{
+        contest: solution.metadata["contest"].as_str().unwrap_or(default_contest).to_string(),
+        winner: name(result.winner),
+        eliminated: (0..result.num_candidates).map(CandidateIndex).filter(|&c|c!=result.winner).map(name).collect(),
+        assertions,
+    };
+    Ok(ShangrlaAssertions{audits:vec![audit]})
+}
@@ -0,0 +1,69 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! How the RAIRE search chooses which elimination order suffix on the frontier to expand next.
+//!
+//! The search keeps a frontier of elimination order suffixes not yet ruled out, and repeatedly takes the one with the highest
+//! priority, either ruling it out with an assertion or replacing it by its extensions with one more candidate. The assertions
+//! found, and so the difficulty, do not depend on the order (other than through which of several equally good assertions is found first),
+//! but the amount of work done can depend on it a lot. The original paper, and [HardestFirst], take the suffix whose best assertion
+//! is hardest first; other orders can be supplied via [crate::raire_algorithm::RaireOptions::frontier_heuristic] to experiment with hard contests.
+
+use std::fmt::Debug;
+use crate::assertions::AssertionAndDifficulty;
+use crate::audit_type::AssertionDifficulty;
+use crate::irv::{CandidateIndex, Votes};
+
+/// What is known about an elimination order suffix on the frontier when it is given a priority.
+pub struct FrontierNode<'a> {
+    /// The elimination order suffix, from first eliminated to the alternate winner.
+    pub suffix : &'a [CandidateIndex],
+    /// The easiest assertion found that rules out this suffix or one of the shorter suffixes it extends.
+    pub best_assertion_for_ancestor : &'a AssertionAndDifficulty,
+    /// The length of the suffix `best_assertion_for_ancestor` was found for, the last elements of `suffix`.
+    pub best_ancestor_length : usize,
+    pub votes : &'a Votes,
+}
+
+impl FrontierNode<'_> {
+    /// The difficulty of the best assertion found so far for this suffix.
+    pub fn difficulty(&self) -> AssertionDifficulty { self.best_assertion_for_ancestor.difficulty }
+}
+
+/// A way of prioritizing the frontier of the RAIRE search. [Send] and [Sync] as the search may be done on several threads.
+pub trait FrontierHeuristic : Debug+Send+Sync {
+    /// The priority of a suffix on the frontier; the one with the highest priority is expanded next. Should not be NaN.
+    fn priority(&self,node:&FrontierNode) -> f64;
+}
+
+/// Expand the suffix whose best assertion is hardest first, as in the original paper. This is the default.
+///
+/// Easy suffixes are often ruled out without expansion, as the lower bound on the difficulty rises above them.
+#[derive(Clone,Copy,Debug,Default)]
+pub struct HardestFirst;
+
+impl FrontierHeuristic for HardestFirst {
+    fn priority(&self,node:&FrontierNode) -> f64 { node.difficulty() }
+}
+
+/// Expand the longest suffix first, hardest first amongst suffixes of the same length.
+///
+/// This is a depth first search, which reaches complete elimination orders (which raise the lower bound) sooner, at the
+/// cost of expanding suffixes that a higher lower bound would have let be ruled out without expansion.
+#[derive(Clone,Copy,Debug,Default)]
+pub struct DeepestFirst;
+
+impl FrontierHeuristic for DeepestFirst {
+    fn priority(&self,node:&FrontierNode) -> f64 {
+        // difficulties are at least 0 and may be infinite, so map them into [0,0.5] to break ties in length.
+        let difficulty = node.difficulty();
+        node.suffix.len() as f64+if difficulty.is_finite() { 0.5*difficulty/(1.0+difficulty) } else { 0.5 }
+    }
+}
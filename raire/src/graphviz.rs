@@ -0,0 +1,69 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Export the trees of elimination orders as [Graphviz](https://graphviz.org/) DOT files,
+//! so one can visualize why each alternative winner is ruled out.
+//!
+//! Each tree is drawn with the candidate remaining at the end at the top, and children
+//! being the candidate eliminated just before. Nodes ruled out by an assertion are labeled with
+//! that assertion, and colored red; nodes not ruled out are colored green.
+//! Render with e.g. `dot -Tsvg trees.dot > trees.svg`.
+
+use std::fmt::Write;
+use crate::assertions::{candidate_name, Assertion};
+use crate::tree_showing_what_assertions_pruned_leaves::TreeNodeShowingWhatAssertionsPrunedIt;
+
+impl TreeNodeShowingWhatAssertionsPrunedIt {
+    /// Produce a DOT digraph of this tree. `all_assertions` are the assertions referred to by index in the tree.
+    /// Candidate names are used if present, otherwise candidate numbers.
+    pub fn to_dot(&self,all_assertions:&[Assertion],candidate_names:&[String]) -> String {
+        trees_to_dot(std::slice::from_ref(self),all_assertions,candidate_names)
+    }
+
+    /// Write the nodes and edges of this tree, returning the id of this node.
+    fn write_dot_nodes(&self,tree_index:usize,next_id:&mut usize,all_assertions:&[Assertion],candidate_names:&[String],out:&mut String) -> String {
+        let id = format!("t{}n{}",tree_index,*next_id);
+        *next_id+=1;
+        let mut label = dot_escape(&candidate_name(candidate_names,self.candidate_being_eliminated_at_this_node));
+        for &a in &self.pruning_assertions {
+            label.push_str("\\n");
+            label.push_str(&dot_escape(&all_assertions[a].describe(candidate_names)));
+        }
+        let color = if self.valid { "palegreen" } else if self.pruning_assertions.is_empty() { "white" } else { "lightpink" };
+        writeln!(out,"    {} [label=\"{}\",fillcolor={}];",id,label,color).unwrap();
+        for child in &self.children {
+            let child_id = child.write_dot_nodes(tree_index,next_id,all_assertions,candidate_names,out);
+            writeln!(out,"    {} -> {};",id,child_id).unwrap();
+        }
+        id
+    }
+}
+
+/// Produce a single DOT digraph containing all the given trees, typically one per candidate other than the winner
+/// as produced by [crate::raire_algorithm::RaireResult::elimination_trees].
+pub fn trees_to_dot(trees:&[TreeNodeShowingWhatAssertionsPrunedIt],all_assertions:&[Assertion],candidate_names:&[String]) -> String {
+    let mut out = String::new();
+    out.push_str("digraph elimination_orders {\n");
+    out.push_str("  node [shape=box,style=filled];\n");
+    for (tree_index,tree) in trees.iter().enumerate() {
+        writeln!(out,"  subgraph cluster_{} {{",tree_index).unwrap();
+        let name = candidate_name(candidate_names,tree.candidate_being_eliminated_at_this_node);
+        writeln!(out,"    label=\"{} {}\";",dot_escape(&name),if tree.valid {"is NOT ruled out by the assertions"} else {"is ruled out by the assertions"}).unwrap();
+        let mut next_id = 0;
+        tree.write_dot_nodes(tree_index,&mut next_id,all_assertions,candidate_names,&mut out);
+        out.push_str("  }\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Escape a string for use inside a double quoted DOT string.
+fn dot_escape(s:&str) -> String {
+    s.replace('\\',"\\\\").replace('"',"\\\"")
+}
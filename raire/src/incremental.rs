@@ -0,0 +1,156 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+//! Updating a solution when a few ballots change, e.g. after an audit finds CVR errors or a batch of ballots is re-scanned.
+//!
+//! The assertions in a solution rule out every other winner whatever the ballots are; only whether each assertion is
+//! true, and how hard it is to audit, depends on the ballots. So after a small change the previous assertions are re-scored
+//! against the new ballots, and reused if the winner is unchanged and every assertion still holds. Otherwise the full search is run.
+//! The re-scored assertions are still sufficient, but may no longer be the easiest set to audit.
+//!
+//! Similarly [RaireSolution::rescore] re-scores a solution's assertions for a different audit type or number of auditable ballots,
+//! to compare the costs of different audits of the same assertions without searching again.
+
+use serde::Deserialize;
+use serde::Serialize;
+use crate::assertions::{Assertion, AssertionAndDifficulty};
+use crate::audit_type::{Audit, AuditType, WithPhantoms};
+use crate::irv::{BallotPaperCount, Vote, Votes, VotesBuilder};
+use crate::raire_algorithm::RaireResult;
+use crate::margin::MarginOfVictory;
+use crate::{RaireError, RaireProblem, RaireSolution};
+use crate::schema::FORMAT_VERSION;
+use crate::timeout::{TimeOut, TimeTaken};
+
+/// A change to the ballots. A modified ballot is represented by removing the old version and adding the new one.
+#[derive(Clone,Debug,Default,Serialize,Deserialize)]
+pub struct BallotDelta {
+    #[serde(default)]
+    pub added : Vec<Vote>,
+    #[serde(default)]
+    pub removed : Vec<Vote>,
+}
+
+/// The result of [RaireProblem::solve_incrementally].
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct IncrementalSolution {
+    pub solution : RaireSolution,
+    /// True if the full search was rerun, false if the previous assertions were re-scored and reused.
+    pub recomputed : bool,
+    /// The indices, in the previous solution, of assertions that are no longer true with the new ballots.
+    pub flipped_assertions : Vec<usize>,
+}
+
+/// The difficulty and margin (and tallies, if wanted) of an assertion with the given votes, and whether it is still true.
+pub(crate) fn rescore<A:AuditType>(assertion:&Assertion,votes:&Votes,audit:&A,include_tallies:bool) -> (AssertionAndDifficulty,bool) {
+    let signed_margin = assertion.signed_margin(votes);
+    let difficulty = match assertion {
+        Assertion::NEB(neb) => neb.difficulty(votes,audit).0,
+        Assertion::NEN(nen) => nen.difficulty(votes,audit),
+    };
+    let tallies = if include_tallies { Some(assertion.tallies(votes,audit)) } else { None };
+    (AssertionAndDifficulty{ assertion: assertion.clone(), margin: BallotPaperCount(signed_margin.max(0) as usize), difficulty, tallies },signed_margin>0)
+}
+
+impl RaireSolution {
+    /// Recompute the difficulties and margins of the assertions in this solution for the given votes and a different audit, e.g. ballot
+    /// polling rather than comparison, or a different number of auditable ballots. The assertions are unchanged, so still rule out every
+    /// other winner, but may no longer be the easiest set to audit with the new audit. Phantom ballots are allowed for if they were in the solution.
+    pub fn rescore(&self,votes:&Votes,audit:&Audit) -> RaireSolution {
+        let solution = match &self.solution {
+            Ok(result) => (||{
+                result.validate()?;
+                let with_phantoms = WithPhantoms::new(audit,votes)?;
+                let include_tallies = result.assertions.iter().any(|a|a.tallies.is_some());
+                let assertions : Vec<AssertionAndDifficulty> = result.assertions.iter().map(|a| if result.phantoms.is_some() { rescore(&a.assertion,votes,&with_phantoms,include_tallies).0 } else { rescore(&a.assertion,votes,audit,include_tallies).0 }).collect();
+                Ok(RaireResult{
+                    difficulty: assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max),
+                    difficulty_units: audit.difficulty_units(),
+                    margin: assertions.iter().map(|a|a.margin).min().unwrap_or(BallotPaperCount(0)),
+                    phantoms: result.phantoms.map(|_|with_phantoms.phantoms),
+                    assertions,
+                    ..result.clone()
+                })
+            })(),
+            Err(e) => Err(e.clone()),
+        };
+        RaireSolution{ metadata: self.metadata.clone(), solution, manifest: None, sample_sizes: None, tied_rounds: None, format_version: FORMAT_VERSION }
+    }
+}
+
+impl RaireProblem {
+    /// Apply a change to the ballots in this problem, and produce a solution for the new ballots, reusing the previous solution
+    /// (for the ballots before the change) if possible. `self.audit` should describe the audit for the new ballots.
+    /// If the change cannot be applied, the problem is unchanged and the solution is an error.
+    pub fn solve_incrementally(&mut self,previous:&RaireSolution,delta:&BallotDelta) -> IncrementalSolution {
+        if self.vote_strata.is_some() {
+            return IncrementalSolution{ solution: RaireSolution{ metadata: self.metadata.clone(), solution: Err(RaireError::IncrementalStratifiedAudit), manifest: None, sample_sizes: None, tied_rounds: None, format_version: FORMAT_VERSION }, recomputed: false, flipped_assertions: vec![] };
+        }
+        let mut builder = VotesBuilder::new(self.num_candidates);
+        let updated = self.votes.iter().chain(delta.added.iter()).try_for_each(|v|builder.add_weighted(&v.prefs,v.n))
+            .and_then(|_|delta.removed.iter().try_for_each(|v|builder.remove_weighted(&v.prefs,v.n)));
+        if let Err(e) = updated {
+            return IncrementalSolution{ solution: RaireSolution{ metadata: self.metadata.clone(), solution: Err(e), manifest: None, sample_sizes: None, tied_rounds: None, format_version: FORMAT_VERSION }, recomputed: false, flipped_assertions: vec![] };
+        }
+        self.votes = builder.build().votes;
+        let mut flipped_assertions = vec![];
+        if let Ok(previous_result) = &previous.solution {
+            if !previous_result.partial && previous_result.num_candidates as usize==self.num_candidates && self.winner.is_none_or(|w|w==previous_result.winner) {
+                match self.rescore(previous_result,&mut flipped_assertions) {
+                    Ok(Some(result)) => return IncrementalSolution{ solution: RaireSolution{ metadata: self.metadata.clone(), solution: Ok(result), manifest: None, sample_sizes: None, tied_rounds: None, format_version: FORMAT_VERSION }, recomputed: false, flipped_assertions },
+                    Ok(None) => {} // need to recompute.
+                    Err(e) => return IncrementalSolution{ solution: RaireSolution{ metadata: self.metadata.clone(), solution: Err(e), manifest: None, sample_sizes: None, tied_rounds: None, format_version: FORMAT_VERSION }, recomputed: false, flipped_assertions },
+                }
+            }
+        }
+        IncrementalSolution{ solution: self.clone().solve(), recomputed: true, flipped_assertions }
+    }
+
+    /// Re-score the assertions in the previous result with the current votes. None if the winner has changed or an assertion is no longer true.
+    fn rescore(&self,previous:&RaireResult,flipped_assertions:&mut Vec<usize>) -> Result<Option<RaireResult>,RaireError> {
+        let mut timeout = TimeOut::never();
+        let votes = self.counted_votes()?;
+        let with_phantoms = WithPhantoms::new(&self.audit,&votes)?;
+        let adjust_for_phantoms = self.adjust_for_phantoms.unwrap_or(false);
+        let mut assertions = vec![];
+        for (i,a) in previous.assertions.iter().enumerate() {
+            a.assertion.validate(previous.num_candidates)?;
+            let (rescored,holds) = if adjust_for_phantoms { rescore(&a.assertion,&votes,&with_phantoms,self.include_tallies.unwrap_or(false)) } else { rescore(&a.assertion,&votes,&self.audit,self.include_tallies.unwrap_or(false)) };
+            if !holds { flipped_assertions.push(i); }
+            assertions.push(rescored);
+        }
+        let time_to_find_assertions = timeout.time_taken();
+        let winners = votes.run_election(&mut timeout)?;
+        if !flipped_assertions.is_empty() || winners.possible_winners!=[previous.winner] { return Ok(None); }
+        let time_to_determine_winners = timeout.time_taken()-time_to_find_assertions;
+        Ok(Some(RaireResult{
+            difficulty: assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max),
+            difficulty_units: self.audit.difficulty_units(),
+            margin: assertions.iter().map(|a|a.margin).min().unwrap_or(BallotPaperCount(0)),
+            margin_of_victory: Some(MarginOfVictory::new(&votes,previous.winner,&winners.elimination_order,&assertions)),
+            assertions,
+            winner: previous.winner,
+            num_candidates: previous.num_candidates,
+            time_to_determine_winners,
+            time_to_find_assertions,
+            time_to_trim_assertions: TimeTaken{ work: 0, seconds: 0.0 },
+            warning_trim_timed_out: false,
+            trimming_statistics: None,
+            tie_breaks: winners.tie_breaks,
+            partial: false,
+            fallback: false,
+            refinement_incomplete: false,
+            unresolved_elimination_order_suffixes: vec![],
+            phantoms: if adjust_for_phantoms { Some(with_phantoms.phantoms) } else { None },
+            warm_start_difficulty: None,
+        }))
+    }
+}
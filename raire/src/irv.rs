@@ -14,14 +14,22 @@
 //! Rust allows zero cost abstractions for such wrappers, so there is little reason not to use them.
 
 
+#[cfg(feature = "std")]
 use std::collections::{HashMap, HashSet};
-use std::fmt;
-use std::iter::Sum;
-use std::ops::{Add, AddAssign, Sub, SubAssign};
-use std::str::FromStr;
+use core::fmt;
+use core::iter::Sum;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use alloc::string::String;
 use serde::Deserialize;
 use serde::Serialize;
+#[cfg(feature = "std")]
 use crate::RaireError;
+#[cfg(feature = "std")]
 use crate::timeout::TimeOut;
 
 /// A number representing a count of pieces of paper.
@@ -106,6 +114,53 @@ impl SubCandidateIndex {
     const INVALID : SubCandidateIndex = SubCandidateIndex(u32::MAX);
 }
 
+/// A set of candidates, stored as a bitmask so membership tests are O(1) and copying is free.
+/// Only supports candidates with index less than [CandidateSet::MAX_CANDIDATES].
+/// ```
+/// use raire::irv::{CandidateIndex, CandidateSet};
+/// let set = CandidateSet::default().with(CandidateIndex(3)).with(CandidateIndex(1));
+/// assert!(set.contains(CandidateIndex(3)));
+/// assert!(!set.contains(CandidateIndex(2)));
+/// assert_eq!(2,set.len());
+/// assert_eq!(vec![CandidateIndex(1),CandidateIndex(3)],set.iter().collect::<Vec<_>>());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct CandidateSet(u128);
+
+impl CandidateSet {
+    /// The maximum number of candidates that can be represented.
+    pub const MAX_CANDIDATES : u32 = 128;
+
+    pub fn contains(self,candidate:CandidateIndex) -> bool { self.0 & (1<<candidate.0) != 0 }
+    pub fn insert(&mut self,candidate:CandidateIndex) { self.0 |= 1<<candidate.0; }
+    /// This set plus the given candidate.
+    pub fn with(self,candidate:CandidateIndex) -> Self { CandidateSet(self.0 | (1<<candidate.0)) }
+    pub fn len(self) -> usize { self.0.count_ones() as usize }
+    pub fn is_empty(self) -> bool { self.0==0 }
+    /// True if every candidate in this set is also in `other`.
+    pub fn is_subset(self,other:CandidateSet) -> bool { self.0 & !other.0 == 0 }
+    /// Compare two sets with the same number of candidates as lists of their candidates in increasing order: the set
+    /// containing the smallest candidate that is in only one of them comes first.
+    pub fn cmp_same_size(self,other:CandidateSet) -> core::cmp::Ordering {
+        let different = self.0 ^ other.0;
+        if different==0 { core::cmp::Ordering::Equal }
+        else if self.0 & different & different.wrapping_neg() != 0 { core::cmp::Ordering::Less } // the lowest bit set in `different`.
+        else { core::cmp::Ordering::Greater }
+    }
+    /// The candidates in the set, in increasing order.
+    pub fn iter(self) -> impl Iterator<Item=CandidateIndex> {
+        (0..Self::MAX_CANDIDATES).filter(move |&c|self.0 & (1<<c) != 0).map(CandidateIndex)
+    }
+}
+
+impl FromIterator<CandidateIndex> for CandidateSet {
+    fn from_iter<T: IntoIterator<Item=CandidateIndex>>(iter: T) -> Self {
+        let mut res = CandidateSet::default();
+        for c in iter { res.insert(c); }
+        res
+    }
+}
+
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
 pub struct Vote {
@@ -116,6 +171,7 @@ pub struct Vote {
 }
 
 impl Vote {
+#[cfg(feature = "std")]
     /// find the highest preferenced candidate amongst the continuing candidates
     pub fn top_preference(&self,continuing:HashSet<CandidateIndex>) -> Option<CandidateIndex> {
         for c in &self.prefs {
@@ -123,6 +179,7 @@ impl Vote {
         }
         None
     }
+#[cfg(feature = "std")]
     /// find the highest preferenced candidate amongst the continuing candidates
     pub fn top_sub_preference(&self,continuing:&HashMap<CandidateIndex,SubCandidateIndex>) -> Option<SubCandidateIndex> {
         for c in &self.prefs {
@@ -141,28 +198,111 @@ impl Vote {
     }
 }
 
+#[cfg(feature = "std")]
 pub struct Votes {
+    /// The vote records. If these are modified after construction, call [Votes::clear_tally_cache].
     pub votes : Vec<Vote>,
     first_preference_votes : Vec<BallotPaperCount>,
+    /// Memoized results of [Votes::restricted_tallies], keyed by the set of continuing candidates, with tallies in increasing candidate order.
+    /// The same continuing sets come up again and again in the search and in scoring assertions.
+    tally_cache : Mutex<HashMap<CandidateSet,Vec<BallotPaperCount>>>,
+    /// The votes split into the strata of a stratified audit, if any. See [Votes::new_stratified].
+    strata : Vec<VotesStratum>,
+}
+
+/// The votes in one stratum of a stratified audit, such as the ballots for which CVRs are available.
+#[cfg(feature = "std")]
+pub struct VotesStratum {
+    pub name : String,
+    pub votes : Votes,
 }
 
+/// The maximum number of continuing sets whose tallies are kept by [Votes::restricted_tallies], bounding memory use.
+#[cfg(feature = "std")]
+pub const MAX_CACHED_TALLIES : usize = 1<<16;
 
 
+
+#[cfg(feature = "std")]
 impl Votes {
     pub fn new(votes:Vec<Vote>,num_candidates:usize) -> Result<Votes,RaireError> {
         let mut first_preference_votes = vec![BallotPaperCount(0);num_candidates];
+        let mut total : usize = 0;
         for v in &votes {
-            if let Some(c) = v.prefs.get(0) {
-                if c.0 as usize>=num_candidates { return Err(RaireError::InvalidCandidateNumber); }
-                first_preference_votes[c.0 as usize]+=v.n;
-            }
+            if v.prefs.iter().any(|c|c.0 as usize>=num_candidates) { return Err(RaireError::InvalidCandidateNumber); }
+            total = total.checked_add(v.n.0).ok_or(RaireError::TooManyBallots)?; // so no tally can overflow.
+            if let Some(c) = v.prefs.get(0) { first_preference_votes[c.0 as usize]+=v.n; }
         }
-        Ok(Votes { votes, first_preference_votes })
+        Ok(Votes { votes, first_preference_votes, tally_cache: Mutex::default(), strata: vec![] })
+    }
+
+    /// Make votes from the votes in each of several named strata. The combined votes determine the winner and margins, and the
+    /// votes in each stratum are used to compute difficulties with [crate::audit_type::AuditType::stratified_difficulty].
+    pub fn new_stratified(strata:Vec<(String,Vec<Vote>)>,num_candidates:usize) -> Result<Votes,RaireError> {
+        let strata = strata.into_iter().map(|(name,votes)|Ok(VotesStratum{name,votes:Votes::new(votes,num_candidates)?})).collect::<Result<Vec<_>,RaireError>>()?;
+        let mut res = Votes::new(strata.iter().flat_map(|s|s.votes.votes.iter().cloned()).collect(),num_candidates)?;
+        res.strata=strata;
+        Ok(res)
+    }
+
+    /// The strata the votes are split into, in the order given to [Votes::new_stratified]. Empty if the votes are not stratified.
+    pub fn strata(&self) -> &[VotesStratum] { &self.strata }
+
+    /// Combine vote records with identical preference lists into a single weighted record, dropping records for no ballots.
+    /// The difficulty computations iterate over vote records, so this can make them much faster for real elections.
+    pub fn deduplicate(&mut self) -> DeduplicationStatistics {
+        let records_before = self.votes.len();
+        let mut strata = core::mem::take(&mut self.strata);
+        for stratum in &mut strata { stratum.votes.deduplicate(); }
+        let mut builder = VotesBuilder::new(self.first_preference_votes.len());
+        for v in self.votes.drain(..).filter(|v|v.n.0>0) {
+            builder.add_weighted(&v.prefs,v.n).expect("candidate numbers were checked by Votes::new");
+        }
+        *self = builder.build();
+        self.strata = strata;
+        DeduplicationStatistics{ records_before, records_after: self.votes.len() }
+    }
+
+    /// The number of ballots with each distinct preference list, sorted by preference list, omitting preference lists with no ballots.
+    /// This is the same however the ballots are split into vote records, so is suitable for identifying the votes.
+    pub fn weighted_records(&self) -> Vec<(&[CandidateIndex],BallotPaperCount)> {
+        let mut combined : HashMap<&[CandidateIndex],BallotPaperCount> = HashMap::new();
+        for v in self.votes.iter().filter(|v|v.n.0>0) { *combined.entry(&v.prefs).or_insert(BallotPaperCount(0))+=v.n; }
+        let mut res : Vec<_> = combined.into_iter().collect();
+        res.sort_unstable_by(|a,b|a.0.iter().map(|c|c.0).cmp(b.0.iter().map(|c|c.0)));
+        res
     }
+
     pub fn first_preference_only_tally(&self,candidate:CandidateIndex) -> BallotPaperCount { self.first_preference_votes[candidate.0 as usize] }
 
-    /// Get the tallies for continuing candidates, returning a vector of the same length and order as the continuing structure
+    /// Get the tallies for continuing candidates, returning a vector of the same length and order as the continuing structure.
+    /// Results are cached, so asking again for the same set of continuing candidates (in any order) is fast.
     pub fn restricted_tallies(&self,continuing:&[CandidateIndex]) -> Vec<BallotPaperCount> {
+        if continuing.iter().any(|c|c.0>=CandidateSet::MAX_CANDIDATES) { return self.compute_restricted_tallies(continuing); }
+        let set : CandidateSet = continuing.iter().cloned().collect();
+        if set.len()!=continuing.len() { return self.compute_restricted_tallies(continuing); } // repeated candidates.
+        let sorted : Vec<CandidateIndex> = set.iter().collect();
+        let position = |c:&CandidateIndex|sorted.binary_search_by_key(&c.0,|s|s.0).unwrap();
+        let cached = self.tally_cache.lock().unwrap().get(&set).cloned();
+        let sorted_tallies = match cached {
+            Some(tallies) => tallies,
+            None => {
+                let tallies = self.compute_restricted_tallies(&sorted);
+                let mut cache = self.tally_cache.lock().unwrap();
+                if cache.len()<MAX_CACHED_TALLIES { cache.insert(set,tallies.clone()); }
+                tallies
+            }
+        };
+        continuing.iter().map(|c|sorted_tallies[position(c)]).collect()
+    }
+
+    /// Forget the cached results of [Votes::restricted_tallies]. Needed only if [Votes::votes] is modified.
+    pub fn clear_tally_cache(&self) { self.tally_cache.lock().unwrap().clear(); }
+
+    /// The number of continuing sets whose tallies are currently cached.
+    pub fn num_cached_tallies(&self) -> usize { self.tally_cache.lock().unwrap().len() }
+
+    fn compute_restricted_tallies(&self,continuing:&[CandidateIndex]) -> Vec<BallotPaperCount> {
         let mut res = vec![BallotPaperCount(0);continuing.len()];
         if continuing.len()>0 {
             //let mut continuing_map : HashMap<CandidateIndex,SubCandidateIndex> = Default::default();
@@ -192,62 +332,372 @@ impl Votes {
 
     /// only possible error is RaireError::TimeoutCheckingWinner
     pub fn run_election(&self,timeout:&mut TimeOut) -> Result<IRVResult,RaireError> {
-        let mut work = IRVElectionWork{ winner_given_continuing_candidates: Default::default(), elimination_order: vec![] };
+        self.run_election_with_rules(EliminationRule::OneAtATime,&TieResolution::Ambiguous,timeout)
+    }
+
+    /// Like [Votes::run_election], but using the given elimination rule and method of resolving ties.
+    pub fn run_election_with_rules(&self,rule:EliminationRule,ties:&TieResolution,timeout:&mut TimeOut) -> Result<IRVResult,RaireError> {
+        let mut work = IRVElectionWork{ rule, ties: ties.clone(), winner_given_continuing_candidates: Default::default(), elimination_order: vec![], tie_breaks: vec![], rounds: vec![], earlier_rounds: vec![] };
         let all_candidates : Vec<CandidateIndex> = (0..self.num_candidates()).into_iter().map(|c|CandidateIndex(c)).collect();
         let possible_winners = work.find_all_possible_winners(all_candidates,&self,timeout)?;
-        Ok(IRVResult{ possible_winners, elimination_order: work.elimination_order })
+        Ok(IRVResult{ possible_winners, elimination_order: work.elimination_order, tie_breaks: work.tie_breaks, rounds: work.rounds })
+    }
+
+    /// Check an elimination order reported by an election authority (first eliminated first, with or without the winner at the end) is
+    /// one the count could give with the given elimination rule and tie resolution. If not, describe the first round where they differ.
+    /// With batch elimination, the candidates eliminated together may be reported in any order.
+    pub fn check_reported_elimination_order(&self,rule:EliminationRule,ties:&TieResolution,reported:&[CandidateIndex]) -> Result<Option<EliminationOrderDivergence>,RaireError> {
+        let num_candidates = self.num_candidates() as usize;
+        let mut listed = vec![false;num_candidates];
+        let valid = reported.len()+1>=num_candidates && reported.len()<=num_candidates && reported.iter().all(|c|(c.0 as usize)<num_candidates && !std::mem::replace(&mut listed[c.0 as usize],true));
+        if !valid { return Err(RaireError::InvalidReportedEliminationOrder); }
+        let mut continuing : Vec<CandidateIndex> = (0..num_candidates as u32).map(CandidateIndex).collect();
+        let mut earlier_rounds : Vec<Vec<(CandidateIndex,BallotPaperCount)>> = vec![];
+        let mut reported = reported.iter().cloned();
+        while continuing.len()>1 {
+            let tallies = self.restricted_tallies(&continuing);
+            let tally = |c:&CandidateIndex|tallies[continuing.iter().position(|e|e==c).unwrap()];
+            let doomed = if rule==EliminationRule::BatchElimination { doomed_candidates(&continuing,&tallies) } else { None };
+            let (reported_eliminated,computed_eliminated,agrees,not_reported) = match doomed {
+                Some(doomed) => {
+                    let reported_eliminated : Vec<CandidateIndex> = reported.by_ref().take(doomed.len()).collect();
+                    let not_reported : BallotPaperCount = doomed.iter().filter(|c|!reported_eliminated.contains(c)).map(tally).sum();
+                    // the reported candidates are distinct, so the same number, all doomed, is the same set.
+                    let agrees = reported_eliminated.len()==doomed.len() && reported_eliminated.iter().all(|c|doomed.contains(c));
+                    (reported_eliminated,doomed,agrees,not_reported)
+                }
+                None => {
+                    let reported_eliminated : Vec<CandidateIndex> = reported.next().into_iter().collect();
+                    let min_tally = *tallies.iter().min().unwrap();
+                    let tied : Vec<CandidateIndex> = continuing.iter().zip(tallies.iter()).filter(|(_,t)|**t==min_tally).map(|(c,_)|*c).collect();
+                    let computed_eliminated = ties.candidates_to_eliminate(&tied,&earlier_rounds);
+                    let agrees = computed_eliminated.contains(&reported_eliminated[0]);
+                    (reported_eliminated,computed_eliminated,agrees,min_tally)
+                }
+            };
+            if !agrees {
+                let wrongly_reported : BallotPaperCount = reported_eliminated.iter().filter(|c|!computed_eliminated.contains(c)).map(tally).sum();
+                let exhausted = self.total_votes()-tallies.iter().cloned().sum();
+                return Ok(Some(EliminationOrderDivergence{ round: earlier_rounds.len()+1, continuing, tallies, exhausted, reported_eliminated, computed_eliminated, affected_ballots: wrongly_reported+not_reported }));
+            }
+            earlier_rounds.push(continuing.iter().cloned().zip(tallies.iter().cloned()).collect());
+            continuing.retain(|c|!reported_eliminated.contains(c));
+        }
+        Ok(None)
+    }
+
+}
+
+/// How much [Votes::deduplicate] reduced the number of vote records.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct DeduplicationStatistics {
+    pub records_before : usize,
+    pub records_after : usize,
+}
+
+impl DeduplicationStatistics {
+    /// The number of records before divided by the number after; 1.0 if there were no records.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.records_after==0 { 1.0 } else { self.records_before as f64/self.records_after as f64 }
+    }
+}
+
+/// Builds [Votes] from a stream of ballots, combining identical preference lists as they arrive
+/// so that only one record per distinct preference list is kept in memory, however many ballots there are.
+/// ```
+/// use raire::irv::{BallotPaperCount, CandidateIndex, VotesBuilder};
+/// let mut builder = VotesBuilder::new(3);
+/// builder.extend([vec![CandidateIndex(0),CandidateIndex(1)],vec![CandidateIndex(2)],vec![CandidateIndex(0),CandidateIndex(1)]]).unwrap();
+/// builder.add_weighted(&[CandidateIndex(2)],BallotPaperCount(5)).unwrap();
+/// assert_eq!(BallotPaperCount(8),builder.num_ballots());
+/// assert_eq!(2,builder.num_distinct());
+/// assert!(builder.add_ballot(&[CandidateIndex(3)]).is_err());
+/// let votes = builder.build();
+/// assert_eq!(BallotPaperCount(2),votes.first_preference_only_tally(CandidateIndex(0)));
+/// assert_eq!(BallotPaperCount(6),votes.first_preference_only_tally(CandidateIndex(2)));
+/// ```
+#[cfg(feature = "std")]
+pub struct VotesBuilder {
+    num_candidates : usize,
+    votes : HashMap<Vec<CandidateIndex>,BallotPaperCount>,
+    num_ballots : BallotPaperCount,
+    /// Total length of the preference lists stored in the keys of votes.
+    stored_preferences : usize,
+}
+
+#[cfg(feature = "std")]
+impl VotesBuilder {
+    pub fn new(num_candidates:usize) -> Self {
+        VotesBuilder{ num_candidates, votes: HashMap::new(), num_ballots: BallotPaperCount(0), stored_preferences: 0 }
+    }
+
+    /// Add n ballots with the given preference list, prefs[0] being the first preference.
+    pub fn add_weighted(&mut self,prefs:&[CandidateIndex],n:BallotPaperCount) -> Result<(),RaireError> {
+        if prefs.iter().any(|c|c.0 as usize>=self.num_candidates) { return Err(RaireError::InvalidCandidateNumber); }
+        self.num_ballots = BallotPaperCount(self.num_ballots.0.checked_add(n.0).ok_or(RaireError::TooManyBallots)?);
+        if let Some(count) = self.votes.get_mut(prefs) { *count+=n; }
+        else {
+            self.stored_preferences+=prefs.len();
+            self.votes.insert(prefs.to_vec(),n);
+        }
+        Ok(())
     }
 
+    /// Remove n ballots with the given preference list, which must previously have been added.
+    pub fn remove_weighted(&mut self,prefs:&[CandidateIndex],n:BallotPaperCount) -> Result<(),RaireError> {
+        match self.votes.get_mut(prefs) {
+            Some(count) if *count>=n => {
+                *count-=n;
+                self.num_ballots-=n;
+                if count.0==0 {
+                    self.votes.remove(prefs);
+                    self.stored_preferences-=prefs.len();
+                }
+                Ok(())
+            }
+            _ => Err(RaireError::RemovedBallotsNotPresent(prefs.to_vec())),
+        }
+    }
+
+    /// Add a single ballot with the given preference list.
+    pub fn add_ballot(&mut self,prefs:&[CandidateIndex]) -> Result<(),RaireError> { self.add_weighted(prefs,BallotPaperCount(1)) }
+
+    /// Add each ballot produced by an iterator (e.g. one reading a file a record at a time).
+    pub fn extend<P:AsRef<[CandidateIndex]>>(&mut self,ballots:impl IntoIterator<Item=P>) -> Result<(),RaireError> {
+        for prefs in ballots { self.add_ballot(prefs.as_ref())?; }
+        Ok(())
+    }
+
+    /// The number of ballots added so far.
+    pub fn num_ballots(&self) -> BallotPaperCount { self.num_ballots }
+    /// The number of distinct preference lists added so far.
+    pub fn num_distinct(&self) -> usize { self.votes.len() }
+
+    /// An estimate of the heap memory currently used, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        let per_entry = std::mem::size_of::<Vec<CandidateIndex>>()+std::mem::size_of::<BallotPaperCount>()+1; // +1 for the hash table control byte.
+        self.votes.capacity()*per_entry+self.stored_preferences*std::mem::size_of::<CandidateIndex>()
+    }
+
+    /// Produce the votes, sorted by preference list so the result does not depend upon the order the ballots were added.
+    pub fn build(self) -> Votes {
+        let mut votes : Vec<Vote> = self.votes.into_iter().map(|(prefs,n)|Vote{n,prefs}).collect();
+        votes.sort_unstable_by(|a,b|a.prefs.iter().map(|c|c.0).cmp(b.prefs.iter().map(|c|c.0)));
+        Votes::new(votes,self.num_candidates).expect("candidate numbers were checked when added")
+    }
+}
+
+/// How candidates are eliminated in each round of the count.
+///
+/// Batch elimination only ever eliminates candidates who would be eliminated next, in some order, one at a time,
+/// so the two rules always produce the same winner. The assertions RAIRE generates rule out every one-at-a-time elimination
+/// order in which someone else wins, and are therefore equally valid whichever rule the official count used.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Default,Serialize,Deserialize)]
+pub enum EliminationRule {
+    /// The candidate with the lowest tally is eliminated each round.
+    #[default]
+    OneAtATime,
+    /// The largest group of lowest candidates whose combined tally is less than the tally of every other candidate
+    /// (and so are mathematically doomed) are eliminated together. If there is no such group of two or more, one is eliminated.
+    BatchElimination,
+}
+
+/// How to choose which candidate to eliminate when two or more have the lowest tally.
+///
+/// Whatever the rule, if the winner depends upon a tie-break then a one vote difference would change the outcome,
+/// so RAIRE will not be able to find assertions for an audit. The tie-break is still recorded as a [TieBreak]
+/// so auditors can see why.
+#[derive(Clone,Debug,PartialEq,Eq,Default,Serialize,Deserialize)]
+pub enum TieResolution {
+    /// Try all possibilities. If they produce different winners, the contest is reported as tied.
+    #[default]
+    Ambiguous,
+    /// The tied candidate with the highest index (i.e. listed last on the ballot paper) is eliminated.
+    Lexicographic,
+    /// The tied candidate with the lowest tally in the most recent earlier round in which the tied candidates' tallies differed is eliminated.
+    /// If they were tied in every round, all possibilities are tried as for [TieResolution::Ambiguous].
+    EarlierRoundTallies,
+    /// An order supplied by the election authority, most favoured first. The tied candidate latest in the order is eliminated;
+    /// candidates not in the order are eliminated before those that are, and all possibilities amongst them are tried.
+    Order(Vec<CandidateIndex>),
+}
+
+#[cfg(feature = "std")]
+impl TieResolution {
+    /// The tied candidates that may be eliminated, given the tallies of the earlier rounds (first round first).
+    /// More than one means the tie is not resolved.
+    fn candidates_to_eliminate(&self,tied:&[CandidateIndex],earlier_rounds:&[Vec<(CandidateIndex,BallotPaperCount)>]) -> Vec<CandidateIndex> {
+        match self {
+            TieResolution::Ambiguous => tied.to_vec(),
+            TieResolution::Lexicographic => tied.iter().max_by_key(|c|c.0).cloned().into_iter().collect(),
+            TieResolution::EarlierRoundTallies => {
+                let mut remaining = tied.to_vec();
+                for round in earlier_rounds.iter().rev() {
+                    if remaining.len()<=1 { break; }
+                    let tally = |c:&CandidateIndex|round.iter().find(|(rc,_)|rc==c).map(|(_,t)|*t);
+                    let min = remaining.iter().filter_map(tally).min();
+                    remaining.retain(|c|tally(c)==min);
+                }
+                remaining
+            }
+            TieResolution::Order(order) => {
+                let position = |c:&CandidateIndex|order.iter().position(|o|o==c);
+                let unlisted : Vec<CandidateIndex> = tied.iter().filter(|c|position(c).is_none()).cloned().collect();
+                if !unlisted.is_empty() { unlisted } else { tied.iter().max_by_key(|c|position(c)).cloned().into_iter().collect() }
+            }
+        }
+    }
+}
+
+/// A point in the count where the candidate eliminated was chosen by a [TieResolution] rule.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct TieBreak {
+    /// The candidates with equal lowest tallies.
+    pub tied : Vec<CandidateIndex>,
+    pub eliminated : CandidateIndex,
+}
+
+/// A round of the count, ending with the elimination of one or more candidates.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct IRVRound {
+    /// The candidates continuing in this round, in increasing order.
+    pub continuing : Vec<CandidateIndex>,
+    /// The tally of each continuing candidate, in the same order.
+    pub tallies : Vec<BallotPaperCount>,
+    /// The votes not counting for any continuing candidate.
+    pub exhausted : BallotPaperCount,
+    /// The candidates eliminated at the end of the round, in the order they appear in [IRVResult::elimination_order].
+    pub eliminated : Vec<CandidateIndex>,
+}
+
+/// Where an elimination order reported by an election authority first differs from the count. See [Votes::check_reported_elimination_order].
+#[cfg(feature = "std")]
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct EliminationOrderDivergence {
+    /// The round, starting from 1, in which the count does not eliminate the candidates reported. Earlier rounds agree.
+    pub round : usize,
+    /// The candidates continuing in the round, in increasing order.
+    pub continuing : Vec<CandidateIndex>,
+    /// The tally of each continuing candidate, in the same order.
+    pub tallies : Vec<BallotPaperCount>,
+    /// The votes not counting for any continuing candidate.
+    pub exhausted : BallotPaperCount,
+    /// The candidates reported eliminated in the round; several if the count eliminates several together.
+    pub reported_eliminated : Vec<CandidateIndex>,
+    /// The candidates the count eliminates together in the round with batch elimination, or else the candidates it could eliminate
+    /// in the round, one unless there is a tie that the tie resolution does not resolve.
+    pub computed_eliminated : Vec<CandidateIndex>,
+    /// The votes transferred in the round by one of the counts but not the other: those for the candidates reported eliminated that the
+    /// count does not eliminate, and those for the candidates the count eliminates that were not reported (counting tied candidates once).
+    pub affected_ballots : BallotPaperCount,
 }
 
 /// The result of an IRV election.
+#[cfg(feature = "std")]
 pub struct IRVResult {
-    /// Possible winners under IRV with no tie resolution. There may be tie resolution rules, but such a close election is not auditable stochastically.
+    /// Possible winners under IRV with the given tie resolution. If there are ties, the election is not auditable stochastically.
     pub possible_winners : Vec<CandidateIndex>,
     /// A possible elimination order
     pub elimination_order : Vec<CandidateIndex>,
+    /// Where a tie resolution rule decided who was eliminated, in the count giving [IRVResult::elimination_order].
+    pub tie_breaks : Vec<TieBreak>,
+    /// The rounds of the count giving [IRVResult::elimination_order], until one candidate is left.
+    pub rounds : Vec<IRVRound>,
 }
 
 
+#[cfg(feature = "std")]
 struct IRVElectionWork {
+    rule : EliminationRule,
+    ties : TieResolution,
     /// Key is a list of continuing candidates, in canonical sorted order.
     /// Value is a list of possible candidates who could win from that point.
     winner_given_continuing_candidates : HashMap<Vec<CandidateIndex>,Vec<CandidateIndex>>,
     /// One order in which candidates are eliminated.
     elimination_order : Vec<CandidateIndex>,
+    /// Tie breaks in the count producing elimination_order.
+    tie_breaks : Vec<TieBreak>,
+    /// The rounds of the count producing elimination_order.
+    rounds : Vec<IRVRound>,
+    /// The tallies in each round leading to the current position in the search.
+    earlier_rounds : Vec<Vec<(CandidateIndex,BallotPaperCount)>>,
+}
+
+/// The largest group of two or more lowest candidates whose combined tally is less than the tally of every other candidate, lowest tally first, if any.
+#[cfg(feature = "std")]
+fn doomed_candidates(continuing:&[CandidateIndex],tallies:&[BallotPaperCount]) -> Option<Vec<CandidateIndex>> {
+    let mut sorted : Vec<(BallotPaperCount,CandidateIndex)> = tallies.iter().cloned().zip(continuing.iter().cloned()).collect();
+    sorted.sort_unstable_by_key(|(tally,c)|(*tally,c.0));
+    let mut cumulative = BallotPaperCount(0);
+    let mut doomed = 0;
+    for i in 0..sorted.len()-1 {
+        cumulative+=sorted[i].0;
+        if cumulative<sorted[i+1].0 { doomed=i+1; }
+    }
+    if doomed>=2 { Some(sorted[..doomed].iter().map(|(_,c)|*c).collect()) } else { None }
 }
 
+#[cfg(feature = "std")]
 impl IRVElectionWork {
-    /// Find all possible winners, trying all options with ties.
+    /// If using batch elimination and there are two or more doomed candidates, they are returned, lowest tally first.
+    fn batch_to_eliminate(&self,continuing:&[CandidateIndex],tallies:&[BallotPaperCount]) -> Option<Vec<CandidateIndex>> {
+        if self.rule!=EliminationRule::BatchElimination { return None; }
+        doomed_candidates(continuing,tallies)
+    }
+
+    /// Find all possible winners, trying all options with ties that are not resolved by the tie resolution rule.
     fn find_all_possible_winners(&mut self,continuing:Vec<CandidateIndex>,votes:&Votes,timeout:&mut TimeOut) -> Result<Vec<CandidateIndex>,RaireError> {
         if timeout.quick_check_timeout() { return Err(RaireError::TimeoutCheckingWinner); }
-        Ok(if continuing.len()==1 {
-            if self.elimination_order.len()+continuing.len()==votes.num_candidates() as usize {
-                // There may be multiple elimination orders. The check above checks that we are in the path of the first depth first traversal of the tree of elimination orders.
-                self.elimination_order.push(continuing[0]);
+        // There may be multiple elimination orders. This checks that we are in the path of the first depth first traversal of the tree of elimination orders.
+        let on_first_path = self.elimination_order.len()+continuing.len()==votes.num_candidates() as usize;
+        let history_matters = self.ties==TieResolution::EarlierRoundTallies;
+        if continuing.len()==1 {
+            if on_first_path { self.elimination_order.push(continuing[0]); }
+            return Ok(continuing);
+        }
+        if !history_matters {
+            if let Some(already_computed) = self.winner_given_continuing_candidates.get(&continuing) { return Ok(already_computed.clone()); }
+        }
+        let tallies = votes.restricted_tallies(&continuing);
+        self.earlier_rounds.push(continuing.iter().cloned().zip(tallies.iter().cloned()).collect());
+        if on_first_path {
+            let exhausted = votes.total_votes()-tallies.iter().cloned().sum();
+            self.rounds.push(IRVRound{ continuing: continuing.clone(), tallies: tallies.clone(), exhausted, eliminated: vec![] });
+        }
+        let winners = if let Some(doomed) = self.batch_to_eliminate(&continuing,&tallies) {
+            if on_first_path {
+                self.elimination_order.extend_from_slice(&doomed);
+                self.rounds.last_mut().unwrap().eliminated=doomed.clone();
             }
-            continuing
-        } else if let Some(already_computed) = self.winner_given_continuing_candidates.get(&continuing) {
-            already_computed.clone()
+            let new_continuing : Vec<CandidateIndex> = continuing.iter().filter(|c|!doomed.contains(c)).cloned().collect();
+            self.find_all_possible_winners(new_continuing,votes,timeout)
         } else {
-            let tallies = votes.restricted_tallies(&continuing);
             let min_tally = *tallies.iter().min().unwrap();
+            let tied : Vec<CandidateIndex> = continuing.iter().zip(tallies.iter()).filter(|(_,t)|**t==min_tally).map(|(c,_)|*c).collect();
+            let to_eliminate = self.ties.candidates_to_eliminate(&tied,&self.earlier_rounds[..self.earlier_rounds.len()-1]);
+            if on_first_path && tied.len()>1 && to_eliminate.len()==1 { self.tie_breaks.push(TieBreak{tied,eliminated:to_eliminate[0]}); }
             let mut winners = HashSet::new();
-            for i in 0..continuing.len() {
-                if min_tally==tallies[i] { // this is a plausible candidate to exclude. There may be a tie in which case there are multiple options. Try them all.
-                    if self.elimination_order.len()+continuing.len()==votes.num_candidates() as usize {
-                        // There may be multiple elimination orders. The check above checks that we are in the path of the first depth first traversal of the tree of elimination orders.
-                        self.elimination_order.push(continuing[i]);
-                    }
-                    let mut new_continuing = continuing[0..i].to_vec();
-                    new_continuing.extend_from_slice(&continuing[i+1..]);
-                    let res = self.find_all_possible_winners(new_continuing,votes,timeout)?;
-                    for c in res { winners.insert(c); }
+            let mut result = Ok(());
+            for eliminated in to_eliminate { // If there is an unresolved tie there are multiple options. Try them all.
+                if self.elimination_order.len()+continuing.len()==votes.num_candidates() as usize {
+                    self.elimination_order.push(eliminated);
+                    self.rounds.last_mut().unwrap().eliminated.push(eliminated);
+                }
+                let new_continuing : Vec<CandidateIndex> = continuing.iter().filter(|&&c|c!=eliminated).cloned().collect();
+                match self.find_all_possible_winners(new_continuing,votes,timeout) {
+                    Ok(res) => winners.extend(res),
+                    Err(e) => { result=Err(e); break; }
                 }
             }
-            let winners : Vec<CandidateIndex> = winners.into_iter().collect();
-            self.winner_given_continuing_candidates.insert(continuing,winners.clone());
-            winners
-        })
+            result.map(|_|{
+                let mut winners : Vec<CandidateIndex> = winners.into_iter().collect();
+                winners.sort_unstable_by_key(|c|c.0); // make the order independent of the hash.
+                winners
+            })
+        };
+        self.earlier_rounds.pop();
+        let winners : Vec<CandidateIndex> = winners?;
+        if !history_matters { self.winner_given_continuing_candidates.insert(continuing,winners.clone()); }
+        Ok(winners)
     }
 }
@@ -8,19 +8,108 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 
+//! Without the default `std` feature, this is a `no_std` crate (using `alloc`) containing just what is needed to check a set of
+//! assertions: [assertions], the candidate types in [irv], [tree_showing_what_assertions_pruned_leaves] (but not trimming), and
+//! [verify::check_assertion]. The solver, the file formats and reports need `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// The JSON schemas in [schema] are large enough to need more than the default for serde_json's json! macro.
+#![recursion_limit = "256"]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
 use std::time::Duration;
+#[cfg(feature = "std")]
+use crate::assertions::Assertion;
+#[cfg(feature = "std")]
 use crate::audit_type::Audit;
-use crate::irv::{CandidateIndex, Vote, Votes};
-use crate::raire_algorithm::{raire, RaireResult, TrimAlgorithm};
-use serde::Deserialize;
-use serde::Serialize;
+use crate::irv::CandidateIndex;
+#[cfg(feature = "std")]
+use crate::irv::{EliminationRule, TieResolution, Vote, Votes};
+#[cfg(feature = "std")]
+use crate::progress::{NoProgressReporter, ProgressReporter};
+#[cfg(feature = "std")]
+use crate::raire_algorithm::{raire_with_options, AssertionTypes, ExtendedTrim, RaireOptions, RaireResult, SearchObjective, TrimAlgorithm, TrimSelectionAlgorithm};
+#[cfg(feature = "std")]
+use serde::Deserializer;
+use serde::{Deserialize, Serialize};
+use alloc::string::String;
+use alloc::vec::Vec;
 
 pub mod assertions;
 pub mod irv;
-pub mod audit_type;
-pub mod raire_algorithm;
 pub mod tree_showing_what_assertions_pruned_leaves;
 pub mod timeout;
+pub mod verify;
+#[cfg(feature = "std")]
+pub mod audit_type;
+#[cfg(feature = "std")]
+pub mod raire_algorithm;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "std")]
+pub mod graphviz;
+#[cfg(feature = "std")]
+pub mod svg;
+#[cfg(feature = "std")]
+pub mod tree_json;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod stv;
+#[cfg(feature = "std")]
+pub mod simulate;
+#[cfg(feature = "std")]
+pub mod round_plan;
+#[cfg(feature = "std")]
+pub mod audit_progress;
+#[cfg(feature = "std")]
+pub mod explain;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod incremental;
+#[cfg(feature = "std")]
+pub mod schema;
+#[cfg(feature = "std")]
+pub mod candidates;
+#[cfg(feature = "std")]
+pub mod ballot_interpretation;
+#[cfg(feature = "std")]
+pub mod preprocess;
+#[cfg(feature = "std")]
+pub mod margin;
+#[cfg(feature = "std")]
+pub mod sensitivity;
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
+pub mod canonical_json;
+#[cfg(feature = "std")]
+pub mod coverage;
+#[cfg(feature = "std")]
+pub mod elimination_orders;
+#[cfg(feature = "std")]
+pub mod frontier_heuristic;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod coordination;
+#[cfg(feature = "std")]
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod sample_size;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod rounds;
+#[cfg(feature = "std")]
+pub mod ballot_manifest;
+#[cfg(feature = "std")]
+pub mod sampling;
 
 #[derive(thiserror::Error, Debug,Serialize,Deserialize,Clone)]
 pub enum RaireError {
@@ -28,6 +117,11 @@ pub enum RaireError {
     InvalidTimeout,
     #[error("candidate numbers in the preferences lists should be integers 0 to num_candidates-1")]
     InvalidCandidateNumber,
+    /// Like [RaireError::InvalidCandidateNumber], saying where the invalid candidate was found.
+    #[error("candidate {candidate} in {context} should be an integer 0 to num_candidates-1 = {}",.num_candidates.saturating_sub(1))]
+    InvalidCandidateNumberIn{ context:String, candidate:CandidateIndex, num_candidates:u32 },
+    #[error("the total number of ballots is too large to count")]
+    TooManyBallots,
     #[error("time out while checking all possible winners - this is a really nasty dataset")]
     TimeoutCheckingWinner,
     #[error("time out while finding assertions - difficulty at time of stopping {0}")]
@@ -35,6 +129,26 @@ pub enum RaireError {
     ///Timeout trimming assertions is used internally and is caught internally and replaced by a valid result but warning_trim_timed_out flag in the result.
     #[error("time out while trimming assertions - try rerunning with a faster trim algorithm.")]
     TimeoutTrimmingAssertions,
+    /// The computation was stopped by a [timeout::CancellationToken]. `partial` is what had been found, if anything: the
+    /// assertions found so far (a [RaireResult::partial] result) if the search was cancelled with [RaireOptions::partial_on_timeout]
+    /// set, or all the assertions, untrimmed (with [RaireResult::warning_trim_timed_out] set), if cancelled while trimming.
+    #[cfg(feature = "std")]
+    #[error("cancelled")]
+    Cancelled{ partial : Option<Box<RaireResult>> },
+    /// The search would have used more than [RaireOptions::max_memory_mb] megabytes. `high_water_mark_bytes` is (approximately) the most it used.
+    #[error("the search would use more than the memory budget of {limit_mb}MB (reached {high_water_mark_bytes} bytes)")]
+    OutOfMemoryBudget{ limit_mb : usize, high_water_mark_bytes : usize },
+    /// A [snapshot] of the search could not be resumed from. The string says why.
+    #[error("could not resume from the snapshot as {0}")]
+    InvalidSnapshot(String),
+    #[error("risk limit should be greater than zero and less than one")]
+    InvalidRiskLimit,
+    #[error("error rates should be between zero and one, and add up to at most one")]
+    InvalidErrorRates,
+    /// [RaireOptions::exact_arithmetic] was asked for, but the audit's difficulty does not depend only on the margin (BRAVO or a stratified audit),
+    /// or there are too many ballots (see [audit_type::MAX_EXACT_BALLOTS]).
+    #[error("exact arithmetic needs an audit whose difficulty depends only on the margin, and fewer than 2^50 ballots")]
+    ExactArithmeticNotSupported,
     /// An alternate winner is possible when there are ties. There may be tie resolution legislation
     /// that unambiguously resolves ties, but such a situation where the winner depends upon such
     /// tie resolution is implausible to audit stochastically as a one vote difference would change
@@ -43,18 +157,102 @@ pub enum RaireError {
     TiedWinners(Vec<CandidateIndex>),
     #[error("the asserted winner was not actually the winner - expecting {0:?}")]
     WrongWinner(Vec<CandidateIndex>),
+    /// [RaireProblem::reported_elimination_order] does not list every candidate, or every candidate but the winner, once each.
+    #[error("the reported elimination order should list every candidate, or every candidate but the winner, once each")]
+    InvalidReportedEliminationOrder,
+    /// [RaireProblem::reported_elimination_order] is not an order the count of the votes could give. Says where they first differ.
+    #[cfg(feature = "std")]
+    #[error("the reported elimination order differs from the count in round {}, eliminating {:?} rather than {:?}",.0.round,.0.reported_eliminated,.0.computed_eliminated)]
+    ReportedEliminationOrderDiffers(Box<irv::EliminationOrderDivergence>),
     #[error("could not rule out the elimination order {0:?}")]
     CouldNotRuleOut(Vec<CandidateIndex>),
-    #[error("internal error - ruled out the winner")]
-    InternalErrorRuledOutWinner,
-    #[error("internal error - did not rule out a loser")]
-    InternalErrorDidntRuleOutLoser,
+    #[error("too many candidates ({0}) - at most 128 are supported when building elimination order trees")]
+    TooManyCandidates(u32),
+    /// Like [RaireError::TimeoutTrimmingAssertions], this is caught internally when trimming and replaced by a valid result with the warning_trim_timed_out flag set.
+    #[error("the tree of elimination orders has more than the maximum allowed {0} nodes")]
+    TreeTooLarge(usize),
+    /// RAIRE only handles single winner IRV contests. See [stv].
+    #[error("contests with {seats} seats are not supported - only single winner IRV contests can be audited")]
+    StvNotSupported{seats:usize},
+    /// The assertions allow some candidate other than the winner to win. Each entry is an elimination order suffix (first eliminated to
+    /// an alternate winner) that no assertion rules out.
+    #[error("the assertions do not rule out the elimination order suffixes {0:?}")]
+    AssertionsAllowOtherWinners(Vec<Vec<CandidateIndex>>),
+    /// Some ballots to be removed in an [incremental] update were not in the votes.
+    #[error("cannot remove ballots with preferences {0:?} as there are not that many")]
+    RemovedBallotsNotPresent(Vec<CandidateIndex>),
+    /// A problem or solution has a [schema::FORMAT_VERSION] newer than this program understands.
+    #[error("format version {0} is newer than the version supported by this program")]
+    UnsupportedFormatVersion(u64),
+    /// A name given to [candidates::CandidateList::index] does not match any candidate.
+    #[error("no candidate is named {0:?}")]
+    UnknownCandidateName(String),
+    /// Two candidates have names differing only in case, accents or spacing, so a name may not identify a single candidate. See [candidates].
+    #[error("candidates {0:?} and {1:?} have names that are too similar to tell apart")]
+    AmbiguousCandidateNames(String,String),
+    /// [preprocess::Preprocessing] would remove the winner, as a withdrawn candidate or a dropped write-in.
+    #[error("preprocessing would remove the winner {0}")]
+    WinnerRemoved(CandidateIndex),
+    /// A stratified audit needs the stratum of every vote, given by [RaireProblem::vote_strata].
+    #[error("vote_strata should give the stratum of each of the {expected} votes, not {found}")]
+    WrongNumberOfVoteStrata{expected:usize,found:usize},
+    #[error("vote {vote} is in stratum {stratum}, but the audit has {num_strata} strata")]
+    InvalidStratum{vote:usize,stratum:usize,num_strata:usize},
+    /// The [audit_type::StratifiedAudit::allocation_steps] of an audit is 0 or more than [audit_type::MAX_ALLOCATION_STEPS].
+    #[error("allocation_steps should be between 1 and {max}, not {steps}")]
+    InvalidAllocationSteps{steps:usize,max:usize},
+    /// A stratum of a [audit_type::StratifiedAudit] has more ballots than the total auditable ballots of its audit.
+    #[error("stratum {stratum:?} has {ballots} ballots, more than its upper bound of {bound}")]
+    StratumExceedsBound{stratum:String,ballots:irv::BallotPaperCount,bound:irv::BallotPaperCount},
+    /// The total auditable ballots, which should be the number of ballots in the ballot manifest, is less than the number of votes (CVRs).
+    #[error("the ballot manifest has {manifest} ballots, fewer than the {votes} votes")]
+    ManifestSmallerThanVotes{manifest:irv::BallotPaperCount,votes:irv::BallotPaperCount},
+    /// The number of ballots in a [ballot_manifest::BallotManifest] is not the total auditable ballots of the audit.
+    #[error("the ballot manifest has {manifest} ballots, but the audit has {total_auditable_ballots} total auditable ballots")]
+    ManifestDisagreesWithAudit{manifest:irv::BallotPaperCount,total_auditable_ballots:irv::BallotPaperCount},
+    /// A [ballot_manifest::BallotManifest] cannot be sampled from. The string says why.
+    #[error("the ballot manifest is invalid as {0}")]
+    InvalidBallotManifest(String),
+    /// [incremental] updates do not say which stratum the changed ballots are in.
+    #[error("incremental updates are not supported for stratified audits")]
+    IncrementalStratifiedAudit,
+    /// A contest given to [coordination::coordinate_samples] is not on any of the ballot styles.
+    #[error("contest {0:?} is not on any ballots")]
+    ContestNotOnAnyBallots(String),
+    /// The assertions rule out every elimination order ending in `elimination_order`, a suffix ending with the winner. The
+    /// `assertions`, indices into the assertions being checked, are those responsible. Solutions before [schema] version 2
+    /// did not record these, so when read from one `winner` is None and the lists are empty.
+    #[error("internal error - assertions {assertions:?} ruled out the winner {} with elimination order suffix {elimination_order:?}",recorded_candidate(.winner))]
+    InternalErrorRuledOutWinner{
+        #[serde(default,skip_serializing_if = "Option::is_none")]
+        winner:Option<CandidateIndex>,
+        #[serde(default)]
+        elimination_order:Vec<CandidateIndex>,
+        #[serde(default)]
+        assertions:Vec<usize>
+    },
+    /// The assertions allow the elimination order suffix `elimination_order`, in which `candidate` wins, when they should not.
+    /// As for [RaireError::InternalErrorRuledOutWinner], `candidate` is None and `elimination_order` empty if read from a solution before version 2.
+    #[error("internal error - did not rule out the loser {} with elimination order suffix {elimination_order:?}",recorded_candidate(.candidate))]
+    InternalErrorDidntRuleOutLoser{
+        #[serde(default,skip_serializing_if = "Option::is_none")]
+        candidate:Option<CandidateIndex>,
+        #[serde(default)]
+        elimination_order:Vec<CandidateIndex>
+    },
     #[error("internal error - trimming couldn't work")]
     InternalErrorTrimming,
 }
+
+/// A candidate in an error message, which may not have been recorded.
+fn recorded_candidate(candidate:&Option<CandidateIndex>) -> String {
+    match candidate { Some(candidate) => alloc::format!("{}",candidate), None => String::from("(not recorded)") }
+}
+
 /// This file contains an API suitable for a web service.
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
+#[cfg(feature = "std")]
 pub struct RaireProblem {
     pub metadata : serde_json::Value,
     pub num_candidates : usize,
@@ -65,35 +263,307 @@ pub struct RaireProblem {
     #[serde(default,skip_serializing_if = "Option::is_none")]
     pub winner : Option<CandidateIndex>,
     pub audit : Audit,
+    /// the number of seats in the contest. Default (and the only supported value) is 1.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub seats : Option<usize>,
+    /// whether candidates are eliminated one at a time (default) or doomed candidates in batches. This does not change the winner or the assertions.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub elimination_rule : Option<EliminationRule>,
+    /// how ties for elimination are resolved when determining the winner. Default is to treat them as ambiguous.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub tie_resolution : Option<TieResolution>,
+    /// the elimination order reported by the election authority, first eliminated first, with or without the winner at the end.
+    /// If the count of the votes could not give it, solving fails with [RaireError::ReportedEliminationOrderDiffers] saying where they differ.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub reported_elimination_order : Option<Vec<CandidateIndex>>,
     /// the algorithm used to trim.
     #[serde(default,skip_serializing_if = "Option::is_none")]
     pub trim_algorithm : Option<TrimAlgorithm>,
+    /// how to choose which assertions to keep when trimming. Default is the fast heuristic.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub trim_selection_algorithm : Option<TrimSelectionAlgorithm>,
+    /// if true, check the trimming by also trimming with both the two pass heuristic and the optimal selection. Default false.
+    /// See [RaireOptions::cross_check_trimming].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub cross_check_trimming : Option<bool>,
+    /// if a tree of elimination orders built when trimming would have more than this many nodes, give up trimming. Default is no limit.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_tree_nodes : Option<usize>,
+    /// if given, expand the trees built when trimming beyond pruned nodes to find deeper redundancies. Default is to expand as far as the trim algorithm does.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub extended_trim : Option<ExtendedTrim>,
     /// don't bother optimizing below this difficulty level. A value of this > 0 may make the algorithm faster, but may make the results worse, but no worse than this.
     #[serde(default,skip_serializing_if = "Option::is_none")]
     pub difficulty_estimate : Option<f64>,
     #[serde(default,skip_serializing_if = "Option::is_none")]
     pub time_limit_seconds : Option<f64>,
+    /// the approximate maximum memory in megabytes the search may use before failing with [RaireError::OutOfMemoryBudget]. Default is no limit.
+    /// See [RaireOptions::max_memory_mb].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb : Option<usize>,
+    /// if true, running out of time while finding assertions gives a partial result rather than an error. Default false.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub partial_on_timeout : Option<bool>,
+    /// if true, running out of time while finding assertions gives the winner only assertions, if they are all true. Default false.
+    /// See [RaireOptions::winner_only_fallback].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub winner_only_fallback : Option<bool>,
+    /// if true, first find assertions quickly, then spend the rest of the time limit refining them towards the easiest, reporting each
+    /// improvement as a [progress::ProgressEvent::ImprovedSolution]. Default false. See [RaireOptions::two_phase].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub two_phase : Option<bool>,
+    /// if given, stop searching as soon as assertions are found with difficulty at most this, rather than finding the easiest audit.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub stop_when_difficulty_at_most : Option<f64>,
+    /// what makes one set of assertions better than another. Default is to minimize the difficulty of the hardest assertion.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub objective : Option<SearchObjective>,
+    /// if true, make the output reproducible byte for byte and record a [manifest::ReproducibilityManifest] in the solution. Default false.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub deterministic : Option<bool>,
+    /// if true, each assertion in the solution records the tallies its margin and difficulty were computed from. Default false.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub include_tallies : Option<bool>,
+    /// if given, round the difficulties in the solution to this many decimal places, so that solutions computed on different machines
+    /// can be compared exactly. See [canonical_json]. Default is not to round.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub difficulty_decimal_places : Option<u32>,
+    /// if true, choose assertions by comparing margins exactly rather than floating point difficulties. Default false. See [RaireOptions::exact_arithmetic].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub exact_arithmetic : Option<bool>,
+    /// which types of assertions may be used. Default is both NEB and NEN.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub assertion_types : Option<AssertionTypes>,
+    /// more votes, given as the candidates marked at each rank. They are counted as well as `votes`, after being interpreted with `ballot_interpretation`.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub ranked_votes : Option<Vec<ballot_interpretation::RankedVote>>,
+    /// how skipped ranks, overvotes and repeated candidates in `ranked_votes` are treated. Default as for [ballot_interpretation::BallotInterpretationRules::default].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub ballot_interpretation : Option<ballot_interpretation::BallotInterpretationRules>,
+    /// for a [audit_type::StratifiedAudit], the stratum of each vote, as an index into the audit's strata. Votes are numbered as in [RaireProblem::interpreted_votes].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub vote_strata : Option<Vec<usize>>,
+    /// if true, the total auditable ballots in excess of the votes are phantoms, and difficulties are adjusted for them as in [audit_type::WithPhantoms]. Default false.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub adjust_for_phantoms : Option<bool>,
+    /// assertions found previously, e.g. for a preliminary count, whose difficulty with these votes is used as an upper bound to stop the search early.
+    /// Ignored if they no longer rule out every other winner. See [RaireOptions::warm_start_assertions].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub warm_start_assertions : Option<Vec<Assertion>>,
+    /// if given, the solution includes a [sample_size::SampleSizeEstimate] of the ballots to sample to confirm the assertions at this risk limit.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub risk_limit : Option<f64>,
+    /// the discrepancy rates assumed by the sample size estimate for a ballot comparison audit. Default is no discrepancies.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub error_rates : Option<simulate::ErrorRates>,
+    /// the version of this format; see [schema]. Older versions are upgraded when read.
+    #[serde(default = "schema::current_format_version",deserialize_with = "schema::deserialize_format_version")]
+    pub format_version : u32,
 }
 
+#[cfg(feature = "std")]
 #[derive(Clone,Debug,Serialize,Deserialize)]
 pub struct RaireSolution {
     pub metadata : serde_json::Value,
+    /// the result is checked with [RaireResult::validate] when read.
+    #[serde(deserialize_with = "deserialize_valid_solution")]
     pub solution : Result<RaireResult,RaireError>,
+    /// present if the problem asked for a deterministic computation.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub manifest : Option<manifest::ReproducibilityManifest>,
+    /// present if the problem gave a risk limit and the solution succeeded.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub sample_sizes : Option<sample_size::SampleSizeEstimate>,
+    /// present if the solution is [RaireError::TiedWinners]: the rounds of one count of the votes, with the tallies showing the tie.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub tied_rounds : Option<Vec<irv::IRVRound>>,
+    /// the version of this format; see [schema]. Older versions are upgraded when read.
+    #[serde(default = "schema::current_format_version",deserialize_with = "schema::deserialize_format_version")]
+    pub format_version : u32,
+}
+
+#[cfg(feature = "std")]
+/// Read a solution, rejecting a result that mentions candidates it doesn't have. Errors written by older format versions are upgraded.
+fn deserialize_valid_solution<'de,D:Deserializer<'de>>(deserializer:D) -> Result<Result<RaireResult,RaireError>,D::Error> {
+    let mut solution = serde_json::Value::deserialize(deserializer)?;
+    if let Some(error) = solution.get_mut("Err") { schema::upgrade_error(error); }
+    let solution : Result<RaireResult,RaireError> = serde_json::from_value(solution).map_err(serde::de::Error::custom)?;
+    if let Ok(result) = &solution { result.validate().map_err(serde::de::Error::custom)?; }
+    Ok(solution)
+}
+
+#[cfg(feature = "std")]
+impl RaireSolution {
+    /// The candidate names, from the `candidates` field of the metadata copied from the problem.
+    pub fn candidate_list(&self) -> Result<candidates::CandidateList,RaireError> { candidates::CandidateList::from_metadata(&self.metadata) }
+
+    /// The winner and assertions with candidates given by name, or the error if solving failed.
+    pub fn named_result(&self) -> Result<candidates::NamedResult,RaireError> {
+        let candidates = self.candidate_list()?;
+        self.solution.as_ref().map(|result|candidates.named_result(result)).map_err(|e|e.clone())
+    }
+
+    /// The differences between the assertions of this and another solution for the same contest, or the error if either failed.
+    pub fn diff(&self,other:&RaireSolution) -> Result<diff::AssertionSetDiff,RaireError> {
+        let first = self.solution.as_ref().map_err(|e|e.clone())?;
+        let second = other.solution.as_ref().map_err(|e|e.clone())?;
+        diff::AssertionSetDiff::new(first,&self.candidate_list()?,second,&other.candidate_list()?)
+    }
 }
 
+#[cfg(feature = "std")]
 impl RaireProblem {
+    /// Check the winner, votes, tie resolution order and reported elimination order only mention candidates less than `num_candidates`.
+    /// This is done before solving.
+    pub fn validate(&self) -> Result<(),RaireError> {
+        let num_candidates = u32::try_from(self.num_candidates).unwrap_or(u32::MAX);
+        let check = |candidate:CandidateIndex,context:&dyn Fn()->String| if candidate.0>=num_candidates { Err(RaireError::InvalidCandidateNumberIn{ context: context(), candidate, num_candidates }) } else { Ok(()) };
+        if let Some(winner) = self.winner { check(winner,&||"the winner".to_string())?; }
+        for (i,vote) in self.votes.iter().enumerate() {
+            for &candidate in &vote.prefs { check(candidate,&||format!("vote {}",i))?; }
+        }
+        for (i,vote) in self.ranked_votes.iter().flatten().enumerate() {
+            for &candidate in vote.ranks.iter().flatten() { check(candidate,&||format!("ranked vote {}",i))?; }
+        }
+        if let Some(TieResolution::Order(order)) = &self.tie_resolution {
+            for &candidate in order { check(candidate,&||"the tie resolution order".to_string())?; }
+        }
+        for &candidate in self.reported_elimination_order.iter().flatten() { check(candidate,&||"the reported elimination order".to_string())?; }
+        Ok(())
+    }
+
+    /// The candidate names, from the `candidates` field of the metadata.
+    pub fn candidate_list(&self) -> Result<candidates::CandidateList,RaireError> { candidates::CandidateList::from_metadata(&self.metadata) }
+
+    /// The votes to count: `votes`, followed by `ranked_votes` interpreted with the `ballot_interpretation` rules.
+    pub fn interpreted_votes(&self) -> Vec<Vote> {
+        let rules = self.ballot_interpretation.unwrap_or_default();
+        self.votes.iter().cloned().chain(self.ranked_votes.iter().flatten().map(|v|rules.interpret(v))).collect()
+    }
+
+    /// The [RaireProblem::interpreted_votes], split into strata by `vote_strata` if the audit is a [audit_type::StratifiedAudit].
+    pub fn counted_votes(&self) -> Result<Votes,RaireError> {
+        let votes = self.interpreted_votes();
+        let vote_strata = self.vote_strata.as_deref().unwrap_or_default();
+        match &self.audit {
+            Audit::Stratified(audit) => {
+                audit.check_allocation_steps()?;
+                if vote_strata.len()!=votes.len() { return Err(RaireError::WrongNumberOfVoteStrata{ expected: votes.len(), found: vote_strata.len() }); }
+                let mut strata : Vec<(String,Vec<Vote>)> = audit.strata.iter().map(|stratum|(stratum.name.clone(),vec![])).collect();
+                for (vote,(v,&stratum)) in votes.into_iter().zip(vote_strata).enumerate() {
+                    strata.get_mut(stratum).ok_or(RaireError::InvalidStratum{ vote, stratum, num_strata: audit.strata.len() })?.1.push(v);
+                }
+                let votes = Votes::new_stratified(strata,self.num_candidates)?;
+                audit.check_bounds(&votes)?;
+                Ok(votes)
+            }
+            _ => {
+                if let Some(&stratum) = vote_strata.first() { return Err(RaireError::InvalidStratum{ vote: 0, stratum, num_strata: 0 }); }
+                Votes::new(votes,self.num_candidates)
+            }
+        }
+    }
+
     pub fn solve(self) -> RaireSolution {
+        self.solve_with_progress(&mut NoProgressReporter)
+    }
+
+    /// Check the [RaireProblem::reported_elimination_order], if any, is one the count of the votes could give.
+    fn check_reported_elimination_order(&self,votes:&Votes) -> Result<(),RaireError> {
+        let Some(reported) = &self.reported_elimination_order else { return Ok(()) };
+        match votes.check_reported_elimination_order(self.elimination_rule.unwrap_or_default(),&self.tie_resolution.clone().unwrap_or_default(),reported)? {
+            Some(divergence) => Err(RaireError::ReportedEliminationOrderDiffers(Box::new(divergence))),
+            None => Ok(()),
+        }
+    }
+
+    /// The round by round count of the votes, with the problem's elimination rule and tie resolution, annotated with the assertions of a result. See [rounds].
+    pub fn round_by_round(&self,result:&RaireResult) -> Result<rounds::RoundByRound,RaireError> {
+        let votes = self.counted_votes()?;
+        let irv = votes.run_election_with_rules(self.elimination_rule.unwrap_or_default(),&self.tie_resolution.clone().unwrap_or_default(),&mut timeout::TimeOut::never())?;
+        let assertions : Vec<Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+        Ok(rounds::RoundByRound::new(&irv,&assertions))
+    }
+
+    /// Like [RaireProblem::solve], but report progress of the computation to the provided reporter.
+    pub fn solve_with_progress(self,progress:&mut dyn ProgressReporter) -> RaireSolution {
+        self.solve_with_threads(progress,None,None,None)
+    }
+
+    /// Like [RaireProblem::solve_with_progress], but stop early with [RaireError::Cancelled] if `cancellation` is cancelled (e.g. by another thread).
+    pub fn solve_cancellable(self,progress:&mut dyn ProgressReporter,cancellation:timeout::CancellationToken) -> RaireSolution {
+        self.solve_with_threads(progress,None,Some(cancellation),None)
+    }
+
+    /// Like [RaireProblem::solve_cancellable], with the cancellation optional, but also keep snapshots of the search in the given store,
+    /// resuming from the snapshot there if any. See [snapshot].
+    pub fn solve_with_snapshots(self,progress:&mut dyn ProgressReporter,cancellation:Option<timeout::CancellationToken>,snapshots:Arc<dyn snapshot::SnapshotStore>) -> RaireSolution {
+        self.solve_with_threads(progress,None,cancellation,Some(snapshots))
+    }
+
+    /// Like [RaireProblem::solve_with_snapshots], using at most `max_threads` threads with the `parallel` feature (see [RaireOptions::max_threads]).
+    pub(crate) fn solve_with_threads(self,progress:&mut dyn ProgressReporter,max_threads:Option<usize>,cancellation:Option<timeout::CancellationToken>,snapshots:Option<Arc<dyn snapshot::SnapshotStore>>) -> RaireSolution {
+        let options = RaireOptions {
+            trim_algorithm: self.trim_algorithm.unwrap_or_default(),
+            trim_selection_algorithm: self.trim_selection_algorithm.unwrap_or_default(),
+            cross_check_trimming: self.cross_check_trimming.unwrap_or(false),
+            max_tree_nodes: self.max_tree_nodes,
+            max_memory_mb: self.max_memory_mb,
+            elimination_rule: self.elimination_rule.unwrap_or_default(),
+            tie_resolution: self.tie_resolution.clone().unwrap_or_default(),
+            partial_on_timeout: self.partial_on_timeout.unwrap_or(false),
+            winner_only_fallback: self.winner_only_fallback.unwrap_or(false),
+            two_phase: self.two_phase.unwrap_or(false),
+            stop_when_difficulty_at_most: self.stop_when_difficulty_at_most,
+            objective: self.objective.unwrap_or_default(),
+            deterministic: self.deterministic.unwrap_or(false),
+            include_tallies: self.include_tallies.unwrap_or(false),
+            exact_arithmetic: self.exact_arithmetic.unwrap_or(false),
+            assertion_types: self.assertion_types.unwrap_or_default(),
+            extended_trim: self.extended_trim,
+            frontier_heuristic: None,
+            warm_start_assertions: self.warm_start_assertions.clone().unwrap_or_default(),
+            max_threads,
+            snapshots,
+        };
+        let input_for_manifest = if options.deterministic { Some(self.clone()) } else { None };
         let solution = {
             if self.time_limit_seconds.is_some_and(|v|v<=0.0||v.is_nan()) {
                 Err(RaireError::InvalidTimeout)
+            } else if let Err(e) = self.risk_limit.map_or(Ok(()),|risk_limit|sample_size::check_parameters(risk_limit,&self.error_rates.unwrap_or_default())) {
+                Err(e)
+            } else if let Err(e) = stv::check_supported_seats(self.seats.unwrap_or(1)) {
+                Err(e)
+            } else if let Err(e) = self.validate() {
+                Err(e)
             } else {
-                let mut timeout = timeout::TimeOut::new(None,self.time_limit_seconds.map(|seconds|Duration::from_secs_f64(seconds)));
-                match Votes::new(self.votes,self.num_candidates) {
-                    Ok(votes) => raire(&votes,self.winner,&self.audit,self.trim_algorithm.unwrap_or(TrimAlgorithm::MinimizeTree),&mut timeout),
+                let mut timeout = timeout::TimeOut::new(None,self.time_limit_seconds.and_then(|seconds|Duration::try_from_secs_f64(seconds).ok())); // a limit too large for a Duration is no limit.
+                if let Some(cancellation) = cancellation { timeout=timeout.with_cancellation(cancellation); }
+                match self.counted_votes().and_then(|votes|Ok((audit_type::WithPhantoms::new(&self.audit,&votes)?,votes))) {
+                    Ok((with_phantoms,mut votes)) => {
+                        let deduplication = votes.deduplicate();
+                        log::debug!("Combined {} vote records into {} distinct preference lists",deduplication.records_before,deduplication.records_after);
+                        if let Err(e) = self.check_reported_elimination_order(&votes) {
+                            Err(e)
+                        } else if self.adjust_for_phantoms.unwrap_or(false) {
+                            raire_with_options(&votes,self.winner,&with_phantoms,&options,&mut timeout,progress).map(|result|RaireResult{ phantoms: Some(with_phantoms.phantoms), ..result })
+                        } else {
+                            raire_with_options(&votes,self.winner,&self.audit,&options,&mut timeout,progress)
+                        }
+                    }
                     Err(e) => Err(e)
                 }
             }
         };
-        RaireSolution{metadata:self.metadata,solution}
+        let solution = match self.difficulty_decimal_places {
+            Some(decimal_places) => solution.map(|result|result.with_rounded_difficulties(decimal_places)),
+            None => solution,
+        };
+        let manifest = input_for_manifest.map(|input|manifest::ReproducibilityManifest::new(&input,&options,&solution));
+        let sample_sizes = self.risk_limit.zip(solution.as_ref().ok()).and_then(|(risk_limit,result)|sample_size::SampleSizeEstimate::new(result,&self.audit,risk_limit,self.error_rates.unwrap_or_default()));
+        let tied_rounds = if matches!(solution,Err(RaireError::TiedWinners(_))) {
+            self.counted_votes().and_then(|votes|votes.run_election_with_rules(options.elimination_rule,&options.tie_resolution,&mut timeout::TimeOut::never())).ok().map(|irv|irv.rounds)
+        } else { None };
+        RaireSolution{metadata:self.metadata,solution,manifest,sample_sizes,tied_rounds,format_version:schema::FORMAT_VERSION}
     }
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A reproducibility manifest, recorded in a [RaireSolution] computed in [deterministic](RaireOptions::deterministic) mode,
+//! so that two parties running raire-rs on the same ballots can check that they got byte for byte the same answer.
+//!
+//! The hashes are SHA-256 of the [canonical JSON](crate::canonical_json) serialization of the problem and of the `solution` field.
+//! This does not depend on the whitespace or the order of fields in the input file, nor on the machine.
+
+use serde::{Deserialize, Serialize};
+use crate::{RaireError, RaireProblem};
+use crate::canonical_json::to_canonical_json;
+use crate::raire_algorithm::{RaireOptions, RaireResult};
+
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct ReproducibilityManifest {
+    /// The version of raire-rs that computed this.
+    pub raire_version : String,
+    /// The SHA-256 hash, in hex, of the problem, serialized as canonical JSON.
+    pub input_sha256 : String,
+    /// The options actually used, after applying defaults.
+    pub options : serde_json::Value,
+    /// The SHA-256 hash, in hex, of the `solution` field, serialized as canonical JSON.
+    pub output_sha256 : String,
+}
+
+impl ReproducibilityManifest {
+    pub fn new(problem:&RaireProblem,options:&RaireOptions,solution:&Result<RaireResult,RaireError>) -> Self {
+        ReproducibilityManifest{
+            raire_version: env!("CARGO_PKG_VERSION").to_string(),
+            input_sha256: problem_sha256(problem),
+            options: serde_json::to_value(options).unwrap_or_default(),
+            output_sha256: solution_sha256(solution),
+        }
+    }
+
+    /// Check that the given problem and solution are the ones this manifest describes.
+    pub fn matches(&self,problem:&RaireProblem,solution:&Result<RaireResult,RaireError>) -> bool {
+        self.input_sha256==problem_sha256(problem) && self.output_sha256==solution_sha256(solution)
+    }
+}
+
+pub fn problem_sha256(problem:&RaireProblem) -> String {
+    sha256_hex(to_canonical_json(problem).unwrap_or_default().as_bytes())
+}
+
+pub fn solution_sha256(solution:&Result<RaireResult,RaireError>) -> String {
+    sha256_hex(to_canonical_json(solution).unwrap_or_default().as_bytes())
+}
+
+/// The SHA-256 hash of some data, as lower case hex.
+pub fn sha256_hex(data:&[u8]) -> String {
+    sha256(data).iter().map(|b|format!("{:02x}",b)).collect()
+}
+
+const K : [u32;64] = [
+    0x428a2f98,0x71374491,0xb5c0fbcf,0xe9b5dba5,0x3956c25b,0x59f111f1,0x923f82a4,0xab1c5ed5,
+    0xd807aa98,0x12835b01,0x243185be,0x550c7dc3,0x72be5d74,0x80deb1fe,0x9bdc06a7,0xc19bf174,
+    0xe49b69c1,0xefbe4786,0x0fc19dc6,0x240ca1cc,0x2de92c6f,0x4a7484aa,0x5cb0a9dc,0x76f988da,
+    0x983e5152,0xa831c66d,0xb00327c8,0xbf597fc7,0xc6e00bf3,0xd5a79147,0x06ca6351,0x14292967,
+    0x27b70a85,0x2e1b2138,0x4d2c6dfc,0x53380d13,0x650a7354,0x766a0abb,0x81c2c92e,0x92722c85,
+    0xa2bfe8a1,0xa81a664b,0xc24b8b70,0xc76c51a3,0xd192e819,0xd6990624,0xf40e3585,0x106aa070,
+    0x19a4c116,0x1e376c08,0x2748774c,0x34b0bcb5,0x391c0cb3,0x4ed8aa4a,0x5b9cca4f,0x682e6ff3,
+    0x748f82ee,0x78a5636f,0x84c87814,0x8cc70208,0x90befffa,0xa4506ceb,0xbef9a3f7,0xc67178f2,
+];
+
+/// SHA-256 as per FIPS 180-4. Written out here rather than adding a dependency, as it is only used for hashes of inputs and
+/// outputs, and for [crate::sampling].
+pub(crate) fn sha256(data:&[u8]) -> [u8;32] {
+    let mut h : [u32;8] = [0x6a09e667,0xbb67ae85,0x3c6ef372,0xa54ff53a,0x510e527f,0x9b05688c,0x1f83d9ab,0x5be0cd19];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len()%64!=56 { message.push(0); }
+    message.extend_from_slice(&((data.len() as u64)*8).to_be_bytes());
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32;64];
+        for i in 0..16 { w[i]=u32::from_be_bytes([block[4*i],block[4*i+1],block[4*i+2],block[4*i+3]]); }
+        for i in 16..64 {
+            let s0 = w[i-15].rotate_right(7)^w[i-15].rotate_right(18)^(w[i-15]>>3);
+            let s1 = w[i-2].rotate_right(17)^w[i-2].rotate_right(19)^(w[i-2]>>10);
+            w[i]=w[i-16].wrapping_add(s0).wrapping_add(w[i-7]).wrapping_add(s1);
+        }
+        let [mut a,mut b,mut c,mut d,mut e,mut f,mut g,mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6)^e.rotate_right(11)^e.rotate_right(25);
+            let ch = (e&f)^(!e&g);
+            let t1 = hh.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2)^a.rotate_right(13)^a.rotate_right(22);
+            let maj = (a&b)^(a&c)^(b&c);
+            let t2 = s0.wrapping_add(maj);
+            hh=g; g=f; f=e; e=d.wrapping_add(t1); d=c; c=b; b=a; a=t1.wrapping_add(t2);
+        }
+        for (hi,v) in h.iter_mut().zip([a,b,c,d,e,f,g,hh]) { *hi=hi.wrapping_add(v); }
+    }
+    let mut res = [0u8;32];
+    for (i,v) in h.iter().enumerate() { res[4*i..4*i+4].copy_from_slice(&v.to_be_bytes()); }
+    res
+}
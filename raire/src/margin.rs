@@ -0,0 +1,67 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Bounds on the margin of victory of an IRV contest: the smallest number of ballots that would have to be changed to
+//! make the outcome different, or tied.
+//!
+//! Computing this exactly is NP-hard in general, but two bounds are easy, and often coincide:
+//! * A lower bound from the assertions. If every assertion holds, the winner is the only possible winner, and changing one ballot
+//!   changes the difference between an assertion's winner and loser tallies by at most two. So at least half the smallest assertion margin,
+//!   rounded up, must be changed.
+//! * An upper bound from the actual count. Changing ballots showing the winner first to blank ballots, or to ballots showing another
+//!   continuing candidate first, lowers the winner's tally in every round without affecting anyone else's except that candidate's.
+//!   So the winner can be made to tie for last in any round, or to tie with the runner up in the last round, by changing that many ballots.
+
+use serde::{Deserialize, Serialize};
+use crate::assertions::AssertionAndDifficulty;
+use crate::irv::{BallotPaperCount, CandidateIndex, Votes};
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct MarginOfVictory {
+    /// At least this many ballots must be changed to change the outcome.
+    pub lower_bound : BallotPaperCount,
+    /// Changing this many ballots is enough to change the outcome. None if there is only one candidate.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub upper_bound : Option<BallotPaperCount>,
+}
+
+impl MarginOfVictory {
+    /// Bound the margin of victory of `winner`, given the actual elimination order (first eliminated first) and assertions proving the winner.
+    pub fn new(votes:&Votes,winner:CandidateIndex,elimination_order:&[CandidateIndex],assertions:&[AssertionAndDifficulty]) -> Self {
+        MarginOfVictory{ lower_bound: lower_bound_from_assertions(assertions), upper_bound: upper_bound_from_count(votes,winner,elimination_order) }
+    }
+
+    /// True if the bounds are the same, so that is the margin of victory.
+    pub fn is_exact(&self) -> bool { self.upper_bound==Some(self.lower_bound) }
+}
+
+/// Half the smallest assertion margin, rounded up. This is zero if there are no assertions.
+pub fn lower_bound_from_assertions(assertions:&[AssertionAndDifficulty]) -> BallotPaperCount {
+    BallotPaperCount(assertions.iter().map(|a|a.margin.0.div_ceil(2)).min().unwrap_or(0))
+}
+
+/// The fewest ballots showing `winner` first that need to be changed to make the winner tie for last in some round of the count,
+/// or for the winner and runner up to tie in the last round.
+pub fn upper_bound_from_count(votes:&Votes,winner:CandidateIndex,elimination_order:&[CandidateIndex]) -> Option<BallotPaperCount> {
+    let first_preferences = votes.first_preference_only_tally(winner).0;
+    let mut continuing : Vec<CandidateIndex> = (0..votes.num_candidates()).map(CandidateIndex).collect();
+    let mut best : Option<usize> = None;
+    for eliminated in elimination_order.iter().copied().chain([winner]) {
+        if continuing.len()<2 || !continuing.contains(&winner) { break; }
+        let tallies = votes.restricted_tallies(&continuing);
+        let winner_tally = tallies[continuing.iter().position(|&c|c==winner).unwrap()].0;
+        let lowest_other = continuing.iter().zip(&tallies).filter(|(&c,_)|c!=winner).map(|(_,t)|t.0).min().unwrap();
+        let difference = winner_tally.saturating_sub(lowest_other);
+        let needed = if continuing.len()==2 { difference.div_ceil(2) } else { difference };
+        if needed<=first_preferences && best.is_none_or(|b|needed<b) { best=Some(needed); }
+        continuing.retain(|&c|c!=eliminated);
+    }
+    best.map(BallotPaperCount)
+}
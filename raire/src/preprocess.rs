@@ -0,0 +1,129 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Tidying up the candidates of a [RaireProblem] before solving it: merging write-in candidates into a single
+//! pseudo-candidate or dropping them, and dropping withdrawn candidates.
+//!
+//! The remaining candidates are renumbered, keeping their order, and the ballots, winner, tie resolution order and candidate names
+//! changed to match. A dropped candidate is removed from every ballot, so the ballot goes on to its next preference.
+//! The metadata records, in the [MAPPING_METADATA_KEY] field, what each original candidate became; as the metadata is copied
+//! into the solution, so is this table.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::candidates::{normalize_name, CandidateList};
+use crate::explain::candidate_names_from_metadata;
+use crate::irv::{CandidateIndex, TieResolution, Vote};
+use crate::{RaireError, RaireProblem};
+
+/// The metadata field listing, for each original candidate in order, an object with its `name` and `index` (null if dropped) after preprocessing.
+pub const MAPPING_METADATA_KEY : &str = "candidate_mapping";
+/// The name of the pseudo-candidate that merged write-ins become.
+pub const MERGED_WRITE_IN_NAME : &str = "Write-ins";
+
+/// What to do with write-in candidates.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Default,Serialize,Deserialize)]
+pub enum WriteInRule {
+    /// Leave them as separate candidates.
+    #[default]
+    Keep,
+    /// Replace them all by a single pseudo-candidate, named [MERGED_WRITE_IN_NAME], after all the other candidates.
+    Merge,
+    /// Remove them, as for withdrawn candidates.
+    Drop,
+}
+
+#[derive(Clone,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Preprocessing {
+    /// The write-in candidates. [Preprocessing::detect_write_ins] finds them by name.
+    #[serde(default)]
+    pub write_ins : Vec<CandidateIndex>,
+    #[serde(default)]
+    pub write_in_rule : WriteInRule,
+    /// Candidates to remove entirely.
+    #[serde(default)]
+    pub withdrawn : Vec<CandidateIndex>,
+}
+
+/// Whether a name is one commonly used for write-in candidates in CVR exports, such as `Write-in`, `WRITE-IN 3` or `UWI`.
+pub fn is_write_in_name(name:&str) -> bool {
+    let name = normalize_name(&name.replace('-'," "));
+    name.starts_with("write in") || name.starts_with("writein") || name=="uwi" || name.starts_with("undeclared write")
+}
+
+impl Preprocessing {
+    /// The candidates whose names satisfy [is_write_in_name].
+    pub fn detect_write_ins(candidates:&CandidateList) -> Vec<CandidateIndex> {
+        candidates.names().iter().enumerate().filter(|(_,name)|is_write_in_name(name)).map(|(i,_)|CandidateIndex(i as u32)).collect()
+    }
+
+    /// What each of the original candidates becomes: None if dropped. The second value is the new number of candidates.
+    pub fn mapping(&self,num_candidates:usize) -> (Vec<Option<CandidateIndex>>,usize) {
+        let mut mapping = vec![];
+        let mut next = 0;
+        let mut merged_write_ins = false;
+        for i in 0..num_candidates {
+            let candidate = CandidateIndex(i as u32);
+            let write_in = self.write_ins.contains(&candidate);
+            if self.withdrawn.contains(&candidate) || (write_in && self.write_in_rule==WriteInRule::Drop) { mapping.push(None); }
+            else if write_in && self.write_in_rule==WriteInRule::Merge { merged_write_ins=true; mapping.push(None); } // filled in below, once the number of other candidates is known.
+            else { mapping.push(Some(CandidateIndex(next))); next+=1; }
+        }
+        if merged_write_ins {
+            for (i,new) in mapping.iter_mut().enumerate() {
+                if self.write_ins.contains(&CandidateIndex(i as u32)) && !self.withdrawn.contains(&CandidateIndex(i as u32)) { *new=Some(CandidateIndex(next)); }
+            }
+            next+=1;
+        }
+        (mapping,next as usize)
+    }
+
+    /// The problem with candidates merged, dropped and renumbered. Fails if the problem is not valid, or the winner is dropped.
+    pub fn apply(&self,problem:&RaireProblem) -> Result<RaireProblem,RaireError> {
+        problem.validate()?;
+        let num_candidates = u32::try_from(problem.num_candidates).unwrap_or(u32::MAX);
+        for &candidate in self.write_ins.iter().chain(&self.withdrawn) {
+            if candidate.0>=num_candidates { return Err(RaireError::InvalidCandidateNumberIn{ context: "the preprocessing".to_string(), candidate, num_candidates }); }
+        }
+        let (mapping,new_num_candidates) = self.mapping(problem.num_candidates);
+        let map_list = |candidates:&[CandidateIndex]| -> Vec<CandidateIndex> {
+            let mut res : Vec<CandidateIndex> = vec![];
+            for new in candidates.iter().filter_map(|c|mapping[c.0 as usize]) {
+                if !res.contains(&new) { res.push(new); }
+            }
+            res
+        };
+        let mut res = problem.clone();
+        res.num_candidates = new_num_candidates;
+        res.votes = problem.votes.iter().map(|v|Vote{ n: v.n, prefs: map_list(&v.prefs) }).collect();
+        if let Some(ranked_votes) = &mut res.ranked_votes {
+            for vote in ranked_votes {
+                for rank in &mut vote.ranks { *rank = map_list(rank); }
+            }
+        }
+        if let Some(winner) = problem.winner {
+            res.winner = Some(mapping[winner.0 as usize].ok_or(RaireError::WinnerRemoved(winner))?);
+        }
+        if let Some(TieResolution::Order(order)) = &problem.tie_resolution { res.tie_resolution = Some(TieResolution::Order(map_list(order))); }
+        // candidate names and the mapping table.
+        let old_names = candidate_names_from_metadata(&problem.metadata);
+        let old_name = |i:usize| old_names.get(i).cloned().unwrap_or_default();
+        let mut new_names = vec![String::new();new_num_candidates];
+        for (i,new) in mapping.iter().enumerate() {
+            if let Some(new) = new { new_names[new.0 as usize] = if self.write_in_rule==WriteInRule::Merge && self.write_ins.contains(&CandidateIndex(i as u32)) { MERGED_WRITE_IN_NAME.to_string() } else { old_name(i) }; }
+        }
+        let table : Vec<serde_json::Value> = mapping.iter().enumerate().map(|(i,new)|json!({"name":old_name(i),"index":new})).collect();
+        if res.metadata.is_null() { res.metadata = json!({}); }
+        if !res.metadata.is_object() { res.metadata = json!({"original_metadata":res.metadata}); }
+        res.metadata["candidates"] = json!(new_names);
+        res.metadata[MAPPING_METADATA_KEY] = json!(table);
+        Ok(res)
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Reporting the progress of a (possibly long) RAIRE computation to whoever is interested.
+//!
+//! The library never writes progress to stdout. Instead, the main search and the trimming pass
+//! report [ProgressEvent]s to a [ProgressReporter] supplied by the caller, who can display them
+//! in a user interface, forward them down a channel, or ignore them.
+//!
+//! With the `tracing` feature, the phases of the computation are also [tracing] spans, and every
+//! [ProgressEvent] is additionally a structured tracing event, see [TracingProgressReporter].
+
+use std::sync::mpsc::Sender;
+use serde::Deserialize;
+use serde::Serialize;
+use crate::audit_type::AssertionDifficulty;
+use crate::irv::CandidateIndex;
+use crate::raire_algorithm::RaireResult;
+use crate::timeout::TimeTaken;
+
+/// Something that happened during a RAIRE computation that may be of interest to a user.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+#[serde(tag = "type")]
+pub enum ProgressEvent {
+    /// The IRV election has been run and the winner determined.
+    DeterminedWinner { winner : CandidateIndex, time_taken : TimeTaken },
+    /// A periodic update from the main search. Sent whenever the difficulty of the element being
+    /// considered changes, and also every [SEARCH_PROGRESS_INTERVAL] elements taken from the frontier.
+    Searching {
+        /// The length of the elimination order suffix being considered.
+        #[serde(default)]
+        depth : usize,
+        /// The number of elimination order suffixes in the priority queue.
+        frontier_size : usize,
+        /// The difficulty of the hardest elimination order suffix left in the frontier.
+        current_difficulty : AssertionDifficulty,
+        /// A lower bound on the difficulty of the final answer.
+        lower_bound : AssertionDifficulty,
+        /// The number of assertions found so far (before trimming).
+        assertions_found : usize,
+        /// The total units of work done so far.
+        work_done : u64,
+    },
+    /// The main search has finished, and trimming is about to start.
+    FinishedSearch { assertions_found : usize, difficulty : AssertionDifficulty, time_taken : TimeTaken },
+    /// The trimming pass has built the tree of elimination orders for one of the candidates.
+    TrimmingTreeBuilt {
+        candidate : CandidateIndex,
+        /// The number of nodes in the tree.
+        nodes : usize,
+        /// The number of trees built so far, including this one.
+        trees_built : usize,
+        /// The number of trees that will be built in total.
+        trees_total : usize,
+    },
+    /// The trimming pass has finished.
+    FinishedTrimming { assertions_before : usize, assertions_after : usize, time_taken : TimeTaken },
+    /// A two phase solve (see [crate::raire_algorithm::RaireOptions::two_phase]) has found assertions easier than any found before,
+    /// starting with those found quickly. They prove the winner, so can be used while the solve continues.
+    ImprovedSolution { result : Box<RaireResult> },
+}
+
+impl ProgressEvent {
+    /// A rough estimate of the percentage of the computation done, 0 to 100, after this event, or None if this event gives no estimate.
+    /// Determining the winner is quick; the search is most of the work, and is finished when the difficulty being considered falls
+    /// to the lower bound; trimming builds one tree per candidate.
+    pub fn percentage_estimate(&self) -> Option<f64> {
+        match self {
+            ProgressEvent::DeterminedWinner{..} => Some(5.0),
+            ProgressEvent::Searching{current_difficulty,lower_bound,..} if current_difficulty.is_finite() && *current_difficulty>0.0 => Some(5.0+75.0*(lower_bound/current_difficulty).clamp(0.0,1.0)),
+            ProgressEvent::Searching{..} => None,
+            ProgressEvent::FinishedSearch{..} => Some(80.0),
+            ProgressEvent::TrimmingTreeBuilt{trees_built,trees_total,..} => Some(80.0+20.0*(*trees_built as f64/(*trees_total).max(1) as f64)),
+            ProgressEvent::FinishedTrimming{..} => Some(100.0),
+            ProgressEvent::ImprovedSolution{..} => None,
+        }
+    }
+}
+
+/// How often (in units of frontier elements taken) the main search sends a [ProgressEvent::Searching]
+/// event even if the difficulty has not changed.
+pub const SEARCH_PROGRESS_INTERVAL : u64 = 1000;
+
+/// Something that wants to be told about progress of a RAIRE computation.
+///
+/// Implemented for closures taking a [ProgressEvent], and for channel senders, so one can write
+/// ```
+/// use raire::progress::{ProgressEvent, ProgressReporter};
+/// let mut events : Vec<ProgressEvent> = vec![];
+/// let mut reporter = |e:ProgressEvent|events.push(e);
+/// reporter.report(ProgressEvent::FinishedTrimming{assertions_before:3,assertions_after:2,time_taken:raire::timeout::TimeOut::never().time_taken()});
+/// assert_eq!(1,events.len());
+/// ```
+pub trait ProgressReporter {
+    fn report(&mut self,event:ProgressEvent);
+}
+
+/// A reporter that ignores everything. Use this if you don't care about progress.
+pub struct NoProgressReporter;
+
+impl ProgressReporter for NoProgressReporter {
+    fn report(&mut self, _event: ProgressEvent) {}
+}
+
+impl <F:FnMut(ProgressEvent)> ProgressReporter for F {
+    fn report(&mut self, event: ProgressEvent) { self(event) }
+}
+
+/// Passes events on to another reporter, first recording each as a structured [tracing] event at debug level
+/// (trace level for [ProgressEvent::Searching]). [crate::raire_algorithm::raire_with_options] wraps the caller's reporter in this.
+#[cfg(feature = "tracing")]
+pub struct TracingProgressReporter<'a>(pub &'a mut dyn ProgressReporter);
+
+#[cfg(feature = "tracing")]
+impl ProgressReporter for TracingProgressReporter<'_> {
+    fn report(&mut self, event: ProgressEvent) {
+        match &event {
+            ProgressEvent::DeterminedWinner{winner,time_taken} => tracing::debug!(winner=winner.0,seconds=time_taken.seconds,"determined winner"),
+            ProgressEvent::Searching{depth,frontier_size,current_difficulty,lower_bound,assertions_found,work_done} => tracing::trace!(depth,frontier_size,current_difficulty,lower_bound,assertions_found,work_done,"searching"),
+            ProgressEvent::FinishedSearch{assertions_found,difficulty,time_taken} => tracing::debug!(assertions_found,difficulty,seconds=time_taken.seconds,"finished search"),
+            ProgressEvent::TrimmingTreeBuilt{candidate,nodes,trees_built,trees_total} => tracing::debug!(candidate=candidate.0,nodes,trees_built,trees_total,"built trimming tree"),
+            ProgressEvent::FinishedTrimming{assertions_before,assertions_after,time_taken} => tracing::debug!(assertions_before,assertions_after,seconds=time_taken.seconds,"finished trimming"),
+            ProgressEvent::ImprovedSolution{result} => tracing::info!(assertions=result.assertions.len(),difficulty=result.difficulty,"improved solution"),
+        }
+        self.0.report(event);
+    }
+}
+
+/// Enter a [tracing] span at info level for a phase of the computation, which lasts until the returned guard is dropped.
+/// Without the `tracing` feature this does nothing.
+#[cfg(feature = "tracing")]
+macro_rules! phase_span { ($($arg:tt)*) => { tracing::info_span!($($arg)*).entered() } }
+#[cfg(not(feature = "tracing"))]
+macro_rules! phase_span { ($($arg:tt)*) => { () } }
+pub(crate) use phase_span;
+
+/// Send events down a channel, e.g. to a different thread running a user interface.
+/// If the receiver has hung up, events are silently dropped as no one is listening.
+impl ProgressReporter for Sender<ProgressEvent> {
+    fn report(&mut self, event: ProgressEvent) { let _ = self.send(event); }
+}
+
+#[cfg(all(test,feature = "tracing"))]
+mod tests {
+    use crate::progress::{ProgressEvent, ProgressReporter, TracingProgressReporter};
+    use crate::timeout::TimeOut;
+
+    #[test]
+    fn tracing_reporter_passes_events_on() {
+        let mut events : Vec<ProgressEvent> = vec![];
+        let mut reporter = |e:ProgressEvent|events.push(e);
+        let mut tracing_reporter = TracingProgressReporter(&mut reporter);
+        tracing_reporter.report(ProgressEvent::Searching{depth:2,frontier_size:5,current_difficulty:3.0,lower_bound:1.0,assertions_found:1,work_done:10});
+        tracing_reporter.report(ProgressEvent::FinishedSearch{assertions_found:1,difficulty:3.0,time_taken:TimeOut::never().time_taken()});
+        assert_eq!(2,events.len());
+        assert!(matches!(events[0],ProgressEvent::Searching{depth:2,..}));
+    }
+}
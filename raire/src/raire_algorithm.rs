@@ -13,14 +13,22 @@
 
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
 use crate::assertions::{all_elimination_orders, Assertion, AssertionAndDifficulty, NotEliminatedNext, NotEliminatedBefore, EliminationOrder, EliminationOrderSuffix, EffectOfAssertionOnEliminationOrderSuffix, NotEliminatedBeforeCache};
-use crate::audit_type::{AssertionDifficulty, AuditType};
-use crate::irv::{BallotPaperCount, CandidateIndex, Votes};
+use crate::audit_type::{AssertionDifficulty, AuditType, Difficulty, DifficultyUnits, ExactlyOrdered};
+use crate::irv::{BallotPaperCount, CandidateIndex, EliminationRule, IRVResult, TieBreak, TieResolution, Votes};
 use serde::Deserialize;
 use serde::Serialize;
+use crate::progress::{phase_span, ProgressEvent, ProgressReporter, SEARCH_PROGRESS_INTERVAL};
 use crate::RaireError;
 use crate::timeout::{TimeOut, TimeTaken};
-use crate::tree_showing_what_assertions_pruned_leaves::{HowFarToContinueSearchTreeWhenPruningAssertionFound, TreeNodeShowingWhatAssertionsPrunedIt};
+use crate::coverage::CoverageMatrix;
+use crate::elimination_orders::ConsistentEliminationOrders;
+use crate::frontier_heuristic::{FrontierHeuristic, FrontierNode, HardestFirst};
+use crate::tree_showing_what_assertions_pruned_leaves::{HowFarToContinueSearchTreeWhenPruningAssertionFound, TreeNodeShowingWhatAssertionsPrunedIt, TreeStatistics, TrimmingStatistics};
+use crate::margin::MarginOfVictory;
+use crate::snapshot::{FrontierSnapshot, SearchSnapshot, SnapshotStore, SNAPSHOT_FORMAT_VERSION};
 
 fn is_false(b:&bool) -> bool {!*b}
 
@@ -29,8 +37,14 @@ fn is_false(b:&bool) -> bool {!*b}
 pub struct RaireResult {
     pub assertions : Vec<AssertionAndDifficulty>,
     pub difficulty: AssertionDifficulty,
+    /// What the difficulties measure, which depends on the audit type. See [Self::difficulty_with_units].
+    #[serde(default,skip_serializing_if = "DifficultyUnits::is_unknown")]
+    pub difficulty_units : DifficultyUnits,
     /// The smallest margin in votes in one of the assertions. Provided primarily for informational purposes.
     pub margin : BallotPaperCount,
+    /// Bounds on the number of ballots that would need to be changed to change the outcome; see [crate::margin]. Absent for a [partial](Self::partial) result.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub margin_of_victory : Option<MarginOfVictory>,
     pub winner : CandidateIndex,
     pub num_candidates : u32,
     pub time_to_determine_winners : TimeTaken,
@@ -38,9 +52,69 @@ pub struct RaireResult {
     pub time_to_trim_assertions : TimeTaken,
     #[serde(default,skip_serializing_if = "is_false")]
     pub warning_trim_timed_out : bool,
+    /// What the trimming pass did. None if trimming did not finish (see warning_trim_timed_out).
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub trimming_statistics : Option<TrimmingStatistics>,
+    /// Where the winner was determined using a tie resolution rule. If not empty, an audit is unlikely to be possible.
+    #[serde(default,skip_serializing_if = "Vec::is_empty")]
+    pub tie_breaks : Vec<TieBreak>,
+    /// If true, the time limit expired while finding assertions and [RaireOptions::partial_on_timeout] was set.
+    /// The assertions found so far are returned untrimmed, and they do **not** prove the winner as some elimination
+    /// orders have not yet been ruled out; see [Self::unresolved_elimination_order_suffixes].
+    #[serde(default,skip_serializing_if = "is_false")]
+    pub partial : bool,
+    /// If true, the time limit expired while finding assertions and [RaireOptions::winner_only_fallback] was set, so the assertions are
+    /// the winner only ones, that the winner is not eliminated before each other candidate. They prove the winner, but are usually
+    /// much harder to audit than the assertions a complete search would find.
+    #[serde(default,skip_serializing_if = "is_false")]
+    pub fallback : bool,
+    /// If true, [RaireOptions::two_phase] was set and the time limit expired while refining the assertions found quickly, so the
+    /// assertions are the easiest found in time. They prove the winner, but an easier set may exist.
+    #[serde(default,skip_serializing_if = "is_false")]
+    pub refinement_incomplete : bool,
+    /// For a [partial](Self::partial) result, the elimination order suffixes (ending with an alternate winner) that were still being
+    /// searched when time ran out. Any elimination order not ruled out by the assertions ends with one of these.
+    #[serde(default,skip_serializing_if = "Vec::is_empty")]
+    pub unresolved_elimination_order_suffixes : Vec<Vec<CandidateIndex>>,
+    /// If the difficulties were adjusted for phantom ballots (see [crate::audit_type::WithPhantoms]), the number of phantoms.
+    /// Each assertion's difficulty is then computed with its margin reduced by this, although `margin` is the margin in the votes.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub phantoms : Option<BallotPaperCount>,
+    /// If [RaireOptions::warm_start_assertions] were given and still rule out every other winner, the difficulty of the hardest of them with these votes.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub warm_start_difficulty : Option<AssertionDifficulty>,
 }
 
 impl RaireResult {
+    /// Set the clock times (but not the work done) in the result to zero, as they differ from run to run.
+    pub fn clear_clock_times(&mut self) {
+        self.time_to_determine_winners.seconds=0.0;
+        self.time_to_find_assertions.seconds=0.0;
+        self.time_to_trim_assertions.seconds=0.0;
+        if let Some(statistics) = &mut self.trimming_statistics { statistics.time_taken.seconds=0.0; }
+    }
+
+    /// The overall difficulty, with what it measures.
+    pub fn difficulty_with_units(&self) -> Difficulty { Difficulty::new(self.difficulty,self.difficulty_units) }
+
+    /// Check the winner and every candidate in the assertions are less than `num_candidates`, as may not be the case for a result read from a file.
+    /// This is checked when a [crate::RaireSolution] is deserialized, and the tree building code checks the assertions itself.
+    pub fn validate(&self) -> Result<(),RaireError> {
+        if self.winner.0>=self.num_candidates { return Err(RaireError::InvalidCandidateNumberIn{ context: "the winner".to_string(), candidate: self.winner, num_candidates: self.num_candidates }); }
+        for a in &self.assertions { a.assertion.validate(self.num_candidates)?; }
+        Ok(())
+    }
+
+    /// This result with its difficulties, and those of its assertions, rounded to the given number of decimal places.
+    /// See [crate::canonical_json::round_to_decimal_places].
+    #[cfg(feature = "std")]
+    pub fn with_rounded_difficulties(mut self,decimal_places:u32) -> Self {
+        use crate::canonical_json::round_to_decimal_places;
+        self.difficulty = round_to_decimal_places(self.difficulty,decimal_places);
+        for a in &mut self.assertions { a.difficulty = round_to_decimal_places(a.difficulty,decimal_places); }
+        self
+    }
+
     /// Note this is not very efficient; you would only want to use this for tests.
     pub fn possible_elimination_orders_allowed_by_assertions(&self,num_candidates:u32) -> Vec<EliminationOrder> {
         let mut elimination_orders = all_elimination_orders(num_candidates);
@@ -63,16 +137,56 @@ impl RaireResult {
         elimination_orders
     }
 
+    /// Build the tree of elimination orders for each candidate other than the winner, expanded until an assertion rules it out.
+    /// This is the tree a human would check to verify that the assertions rule out every other winner.
+    pub fn elimination_trees(&self,timeout:&mut TimeOut) -> Result<Vec<TreeNodeShowingWhatAssertionsPrunedIt>,RaireError> {
+        let all_assertions : Vec<Assertion> = self.assertions.iter().map(|ad|ad.assertion.clone()).collect();
+        let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
+        let mut trees = vec![];
+        for candidate in 0..self.num_candidates {
+            let candidate = CandidateIndex(candidate);
+            if candidate!=self.winner {
+                trees.push(TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&all_assertion_indices,&all_assertions,self.num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,timeout)?);
+            }
+        }
+        Ok(trees)
+    }
+
+    /// Statistics about the [elimination trees](Self::elimination_trees): their size, depth, and how many nodes each assertion prunes.
+    pub fn tree_statistics(&self,timeout:&mut TimeOut) -> Result<TreeStatistics,RaireError> {
+        Ok(TreeStatistics::new(&self.elimination_trees(timeout)?,self.assertions.len()))
+    }
+
+    /// Which assertions prune which frontier nodes of the [elimination trees](Self::elimination_trees).
+    pub fn coverage_matrix(&self,timeout:&mut TimeOut) -> Result<CoverageMatrix,RaireError> {
+        Ok(CoverageMatrix::new(&self.elimination_trees(timeout)?,self.assertions.len()))
+    }
+
+    /// The complete elimination orders not ruled out by the assertions. See [crate::elimination_orders].
+    pub fn consistent_elimination_orders(&self,timeout:&mut TimeOut) -> Result<ConsistentEliminationOrders,RaireError> {
+        let all_assertions : Vec<Assertion> = self.assertions.iter().map(|ad|ad.assertion.clone()).collect();
+        ConsistentEliminationOrders::new(&all_assertions,self.num_candidates,None,timeout)
+    }
+
+    /// The elimination order suffixes, each ending in a candidate other than the winner, that are not ruled out by the assertions.
+    /// Empty if the assertions prove the winner. See [TreeNodeShowingWhatAssertionsPrunedIt::unpruned_elimination_order_suffixes].
+    pub fn unpruned_elimination_order_suffixes(&self,timeout:&mut TimeOut) -> Result<Vec<EliminationOrderSuffix>,RaireError> {
+        Ok(self.elimination_trees(timeout)?.iter().flat_map(|tree|tree.unpruned_elimination_order_suffixes()).collect())
+    }
+
     /// Note that this can be very slow to check that the winner is not eliminated.
+    /// If a candidate other than the winner is not ruled out, the error [RaireError::AssertionsAllowOtherWinners] lists the scenarios not ruled out.
     pub fn verify_result_does_prove_winner(&self) -> Result<(),RaireError> {
         let all_assertions : Vec<Assertion> = self.assertions.iter().map(|ad|ad.assertion.clone()).collect();
         let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
+        let mut unpruned = vec![];
         for candidate in 0..self.num_candidates {
             let candidate = CandidateIndex(candidate);
             let tree = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&all_assertion_indices,&all_assertions,self.num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,&mut TimeOut::never())?;
-            if tree.valid!= (candidate==self.winner) { return Err(if candidate==self.winner { RaireError::InternalErrorRuledOutWinner} else { RaireError::InternalErrorDidntRuleOutLoser })}
+            if candidate==self.winner { if !tree.valid { return Err(tree.ruled_out_winner_error()) } }
+            else { unpruned.extend(tree.unpruned_elimination_order_suffixes()); }
         }
-        Ok(())
+        if unpruned.is_empty() { Ok(()) } else { Err(RaireError::AssertionsAllowOtherWinners(unpruned)) }
     }
 }
 
@@ -86,6 +200,8 @@ struct SequenceAndEffort {
     best_ancestor_length : usize,
     /// if not null, then a dive has already been done on the specified candidate.
     dive_done : Option<CandidateIndex>,
+    /// the priority in the frontier, from the [FrontierHeuristic].
+    priority : f64,
 }
 
 impl SequenceAndEffort {
@@ -97,21 +213,33 @@ impl SequenceAndEffort {
         &self.pi[(self.pi.len()-self.best_ancestor_length)..]
     }
 
-    pub fn extend_by_candidate<A:AuditType>(&self,c:CandidateIndex,votes:&Votes,audit:&A,neb_cache:&NotEliminatedBeforeCache)-> Self {
+    /// Make a new entry, not yet dived on, with its priority from the heuristic.
+    fn new(pi:EliminationOrderSuffix,best_ancestor_length:usize,best_assertion_for_ancestor:AssertionAndDifficulty,votes:&Votes,heuristic:&dyn FrontierHeuristic) -> Self {
+        let priority = heuristic.priority(&FrontierNode{ suffix: &pi, best_assertion_for_ancestor: &best_assertion_for_ancestor, best_ancestor_length, votes });
+        SequenceAndEffort { pi, best_ancestor_length, best_assertion_for_ancestor, dive_done: None, priority }
+    }
+
+    /// Make an entry from a snapshot, with its priority from the heuristic.
+    fn resume(snapshot:FrontierSnapshot,votes:&Votes,heuristic:&dyn FrontierHeuristic) -> Self {
+        SequenceAndEffort{ dive_done: snapshot.dive_done, ..SequenceAndEffort::new(snapshot.suffix,snapshot.best_ancestor_length,snapshot.best_assertion_for_ancestor,votes,heuristic) }
+    }
+
+    fn snapshot(&self) -> FrontierSnapshot {
+        FrontierSnapshot{ suffix: self.pi.clone(), best_assertion_for_ancestor: self.best_assertion_for_ancestor.clone(), best_ancestor_length: self.best_ancestor_length, dive_done: self.dive_done }
+    }
+
+    pub fn extend_by_candidate<A:AuditType>(&self,c:CandidateIndex,votes:&Votes,audit:&A,neb_cache:&NotEliminatedBeforeCache,assertion_types:AssertionTypes,heuristic:&dyn FrontierHeuristic)-> Self {
         let mut pi_prime = vec![c];
         pi_prime.extend_from_slice(&self.pi); // π ′ ← [c] ++π
-        let a : AssertionAndDifficulty = find_best_audit(&pi_prime, votes, audit,neb_cache); // a in the original paper
+        let a : AssertionAndDifficulty = find_best_audit(&pi_prime, votes, audit,neb_cache,assertion_types); // a in the original paper
         let (best_ancestor_length,best_assertion_for_ancestor) = if a.difficulty < self.difficulty() { (pi_prime.len(), a.clone()) } else { (self.best_ancestor_length, self.best_assertion_for_ancestor.clone()) };
-        SequenceAndEffort { pi:pi_prime, best_ancestor_length, best_assertion_for_ancestor, dive_done: None }
+        SequenceAndEffort::new(pi_prime,best_ancestor_length,best_assertion_for_ancestor,votes,heuristic)
     }
 
     /// Called when the only use for this is to take the assertion and add it to the list of assertions.
     /// This checks that it is not already there and removes elements from the frontier that obviously match it.
     pub fn just_take_assertion(self,assertions:&mut Vec<AssertionAndDifficulty>,frontier:&mut BinaryHeap<SequenceAndEffort>) {
-        if assertions.iter().any(|a|a.assertion==self.best_assertion_for_ancestor.assertion) {
-            //println!("Didn't add assertion as it was already there");
-        } else {
-            //println!("Just including it");
+        if !assertions.iter().any(|a|a.assertion==self.best_assertion_for_ancestor.assertion) {
             let best_ancestor_pi = self.best_ancestor();
             // 15 F ← F \ {π ′ ∈ F | ba[π] is a suffix of π ′ }
             frontier.retain(|s|!s.pi.ends_with(best_ancestor_pi));
@@ -122,14 +250,14 @@ impl SequenceAndEffort {
     }
 
     /// Called when a sequence has gone as far as it can - i.e. all candidates are in the exclusion order list.
-    pub fn contains_all_candidates(self,assertions:&mut Vec<AssertionAndDifficulty>,frontier:&mut BinaryHeap<SequenceAndEffort>,bound:&mut f64) -> Result<(),RaireError> {
+    fn contains_all_candidates(self,assertions:&mut Vec<AssertionAndDifficulty>,frontier:&mut BinaryHeap<SequenceAndEffort>,bound:&LowerBound) -> Result<(),RaireError> {
         if self.difficulty().is_infinite() { // 23 if (ASN (asr[ba[π ′ ]]) = ∞):
-            //println!("Couldn't deal with {:?}",new_sequence.pi);
+            log::trace!("Could not rule out elimination sequence {:?}",self.pi);
             Err(RaireError::CouldNotRuleOut(self.pi)) // 24 terminate algorithm, full recount necessary
         } else {
-            if *bound<self.difficulty() {
-                *bound=self.difficulty(); // 27 LB ← max(LB, ASN (asr[ba[π′]]))
-                log::trace!("Found bound {} on elimination sequence {:?}",*bound,self.pi)
+            if bound.get()<self.difficulty() {
+                bound.raise(self.difficulty()); // 27 LB ← max(LB, ASN (asr[ba[π′]]))
+                log::trace!("Found bound {} on elimination sequence {:?}",self.difficulty(),self.pi)
             }
             self.just_take_assertion(assertions,frontier); // Steps 26 and 28 are same as 14 and 15.
             Ok(())
@@ -141,7 +269,7 @@ impl SequenceAndEffort {
 
 impl PartialOrd<Self> for SequenceAndEffort {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.difficulty().partial_cmp(&other.difficulty())
+        self.priority.partial_cmp(&other.priority)
     }
 }
 
@@ -155,33 +283,525 @@ impl PartialEq<Self> for SequenceAndEffort {
 
 impl Ord for SequenceAndEffort {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.difficulty().partial_cmp(&other.difficulty()).unwrap_or(Ordering::Equal) // should always unwrap fine as NaN etc not allowed.
+        self.priority.partial_cmp(&other.priority).unwrap_or(Ordering::Equal) // should always unwrap fine as NaN etc not allowed.
     }
 }
 
-fn find_best_audit<A:AuditType>(pi:&[CandidateIndex],votes:&Votes,audit:&A,neb_cache:&NotEliminatedBeforeCache) -> AssertionAndDifficulty {
+fn find_best_audit<A:AuditType>(pi:&[CandidateIndex],votes:&Votes,audit:&A,neb_cache:&NotEliminatedBeforeCache,assertion_types:AssertionTypes) -> AssertionAndDifficulty {
     let c = pi[0];
-    let mut res : AssertionAndDifficulty = AssertionAndDifficulty { assertion: Assertion::NEB(NotEliminatedBefore { winner: c, loser: c }), margin: BallotPaperCount(0), difficulty: f64::INFINITY }; // dummy infinitely bad assertion
+    let mut res : AssertionAndDifficulty = AssertionAndDifficulty { assertion: Assertion::NEB(NotEliminatedBefore { winner: c, loser: c }), margin: BallotPaperCount(0), difficulty: f64::INFINITY, tallies: None }; // dummy infinitely bad assertion
     // consider WO contests
-    if let Some(assertion) = NotEliminatedBefore::find_best_assertion_using_cache(c, &pi[1..],votes, neb_cache) {
-        if assertion.difficulty < res.difficulty { res=assertion; }
+    if assertion_types!=AssertionTypes::NENOnly {
+        if let Some(assertion) = NotEliminatedBefore::find_best_assertion_using_cache(c, &pi[1..],votes, neb_cache) {
+            if assertion.difficulty < res.difficulty { res=assertion; }
+        }
     }
     // consider IRV(c,c′,{c′′ | c′′ ∈ π}): Assertion that c beats some c′ != c ∈ π
-    if let Some(assertion) = NotEliminatedNext::find_best_difficulty(votes, audit, pi, c) {
-        //println!("{:?}",assertion);
-        if assertion.difficulty < res.difficulty { res=assertion; }
+    if assertion_types!=AssertionTypes::NEBOnly {
+        if let Some(assertion) = NotEliminatedNext::find_best_difficulty(votes, audit, pi, c) {
+            if assertion.difficulty < res.difficulty { res=assertion; }
+        }
     }
-    //println!("FindBestAudit({:?})={:?}",pi,res);
     res
 }
 
+/// A lower bound on the difficulty of the problem (LB in the original paper), which only ever increases.
+/// It is shared between threads when searching in parallel.
+#[derive(Default)]
+struct LowerBound(AtomicU64);
+
+impl LowerBound {
+    fn new(bound:AssertionDifficulty) -> Self { LowerBound(AtomicU64::new(bound.to_bits())) }
+    fn get(&self) -> AssertionDifficulty { f64::from_bits(self.0.load(AtomicOrdering::Relaxed)) }
+    /// Increase the bound to at least `bound`.
+    fn raise(&self,bound:AssertionDifficulty) {
+        let _ = self.0.fetch_update(AtomicOrdering::Relaxed,AtomicOrdering::Relaxed,|old|if f64::from_bits(old)<bound { Some(bound.to_bits()) } else { None });
+    }
+}
+
+/// An approximate count of the memory used by the frontiers of the search (and the assertions found), shared between threads,
+/// checked against [RaireOptions::max_memory_mb].
+struct MemoryBudget {
+    limit_mb : Option<usize>,
+    used : AtomicUsize,
+    high_water_mark : AtomicUsize,
+}
+
+impl MemoryBudget {
+    fn new(limit_mb:Option<usize>) -> Self { MemoryBudget{ limit_mb, used: AtomicUsize::new(0), high_water_mark: AtomicUsize::new(0) } }
+    fn tracker(&self) -> MemoryUse<'_> { MemoryUse{ budget: self, bytes: 0 } }
+}
+
+/// The memory used by one frontier, counted in a [MemoryBudget] until dropped.
+struct MemoryUse<'a> {
+    budget : &'a MemoryBudget,
+    bytes : usize,
+}
+
+impl MemoryUse<'_> {
+    /// Change the memory used to `bytes`, failing if the total is then over budget.
+    fn set(&mut self,bytes:usize) -> Result<(),RaireError> {
+        if bytes>self.bytes {
+            let used = self.budget.used.fetch_add(bytes-self.bytes,AtomicOrdering::Relaxed)+bytes-self.bytes;
+            self.bytes=bytes;
+            let high_water_mark_bytes = self.budget.high_water_mark.fetch_max(used,AtomicOrdering::Relaxed).max(used);
+            if let Some(limit_mb) = self.budget.limit_mb {
+                if used>limit_mb.saturating_mul(BYTES_PER_MB) { return Err(RaireError::OutOfMemoryBudget{limit_mb,high_water_mark_bytes}); }
+            }
+        } else {
+            self.budget.used.fetch_sub(self.bytes-bytes,AtomicOrdering::Relaxed);
+            self.bytes=bytes;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for MemoryUse<'_> {
+    fn drop(&mut self) { self.budget.used.fetch_sub(self.bytes,AtomicOrdering::Relaxed); }
+}
+
+const BYTES_PER_MB : usize = 1<<20;
+
+/// The approximate memory used by an entry in the frontier or a found assertion, each of which contains at most two lists of candidates.
+fn approximate_entry_bytes(num_candidates:u32) -> usize {
+    std::mem::size_of::<SequenceAndEffort>()+2*num_candidates as usize*std::mem::size_of::<CandidateIndex>()
+}
+
+/// Where to keep snapshots of a search; see [crate::snapshot].
+struct Snapshotting<'a> {
+    store : &'a dyn SnapshotStore,
+    /// identifies the search; see [SearchSnapshot::fingerprint].
+    fingerprint : String,
+    /// the work done before the search was resumed from a snapshot, if it was.
+    work_before_resumption : u64,
+}
+
+/// What is needed to expand a frontier of elimination order suffixes that need to be ruled out.
+struct Search<'a,A:AuditType> {
+    votes : &'a Votes,
+    audit : &'a A,
+    neb_cache : &'a NotEliminatedBeforeCache,
+    /// see [RaireOptions::assertion_types].
+    assertion_types : AssertionTypes,
+    /// the actual elimination order, used for diving.
+    elimination_order : &'a [CandidateIndex],
+    lower_bound : LowerBound,
+    /// set if some thread has failed or run out of time, so the others may as well stop.
+    stop : AtomicBool,
+    /// see [RaireOptions::partial_on_timeout].
+    partial_on_timeout : bool,
+    /// set if the search ran out of time with partial_on_timeout set.
+    timed_out : AtomicBool,
+    /// the elimination order suffixes not yet dealt with when the search stopped early.
+    unresolved : Mutex<Vec<Vec<CandidateIndex>>>,
+    /// the order in which to expand the frontier.
+    heuristic : &'a dyn FrontierHeuristic,
+    /// the memory used by the frontiers, checked against [RaireOptions::max_memory_mb].
+    memory : MemoryBudget,
+    /// where to keep snapshots, if anywhere. Only set for a search on a single thread.
+    snapshots : Option<Snapshotting<'a>>,
+}
+
+impl <A:AuditType> Search<'_,A> {
+    /// Repeatedly expand the sequence with highest priority (by default, the largest ASN) in F until F is empty, adding the assertions needed to `assertions`.
+    fn expand_frontier(&self,mut frontier:BinaryHeap<SequenceAndEffort>,assertions:&mut Vec<AssertionAndDifficulty>,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<(),RaireError> {
+        let (votes,audit,neb_cache,assertion_types,heuristic) = (self.votes,self.audit,self.neb_cache,self.assertion_types,self.heuristic);
+        let mut last_difficulty:f64 = f64::INFINITY;
+        let (mut memory,entry_bytes) = (self.memory.tracker(),approximate_entry_bytes(votes.num_candidates()));
+        let mut last_snapshot_seconds = timeout.clock_time_taken_since_start().as_secs_f64();
+        while let Some(mut sequence_being_considered) = frontier.pop() { // 10-12
+            memory.set((frontier.len()+1+assertions.len())*entry_bytes)?;
+            let lower_bound = self.lower_bound.get();
+            if timeout.quick_check_timeout() {
+                self.save_snapshot(&sequence_being_considered,&frontier,assertions,timeout.get_work_done());
+                if !self.partial_on_timeout { // the hardest sequence on the frontier is an upper bound, usually the one just popped.
+                    let hardest = frontier.iter().map(|s|s.difficulty()).fold(sequence_being_considered.difficulty(),f64::max);
+                    return Err(RaireError::TimeoutFindingAssertions(hardest.max(lower_bound)))
+                }
+                self.timed_out.store(true,AtomicOrdering::Relaxed);
+                self.stop.store(true,AtomicOrdering::Relaxed);
+            }
+            if self.stop.load(AtomicOrdering::Relaxed) {
+                frontier.push(sequence_being_considered);
+                self.unresolved.lock().unwrap().extend(frontier.into_iter().map(|s|s.pi));
+                return Ok(())
+            }
+            let difficulty_changed = sequence_being_considered.difficulty()!=last_difficulty;
+            if difficulty_changed {
+                last_difficulty=sequence_being_considered.difficulty();
+                log::trace!("Difficulty reduced to {}{}",last_difficulty,if last_difficulty<= lower_bound {" OK"} else {""});
+            }
+            if difficulty_changed || timeout.get_work_done().is_multiple_of(SEARCH_PROGRESS_INTERVAL) {
+                progress.report(ProgressEvent::Searching{depth:sequence_being_considered.pi.len(),frontier_size:frontier.len()+1,current_difficulty:last_difficulty,lower_bound,assertions_found:assertions.len(),work_done:timeout.get_work_done()});
+            }
+            if let Some(snapshots) = &self.snapshots {
+                let seconds = timeout.clock_time_taken_since_start().as_secs_f64();
+                if seconds-last_snapshot_seconds>=snapshots.store.interval_seconds() {
+                    self.save_snapshot(&sequence_being_considered,&frontier,assertions,timeout.get_work_done());
+                    last_snapshot_seconds=seconds;
+                }
+            }
+            log::trace!("Considering elimination sequence {:?} with difficulty {}",sequence_being_considered.pi,sequence_being_considered.difficulty());
+            if sequence_being_considered.difficulty()<= lower_bound { // may as well just include.
+                sequence_being_considered.just_take_assertion(assertions,&mut frontier);
+            } else {
+                if USE_DIVING && sequence_being_considered.dive_done.is_none() {
+                    let mut last : Option<SequenceAndEffort> = None;
+                    assert_eq!(self.elimination_order.len(),votes.num_candidates() as usize);
+                    for &c in self.elimination_order.iter().rev() {
+                        if !sequence_being_considered.pi.contains(&c) {
+                            let new_sequence = match last.take() { // don't repeat work! Mark that this path has already been dealt with.
+                                Some(mut l) => {
+                                    l.dive_done=Some(c);
+                                    let new_sequence = l.extend_by_candidate(c,votes,audit,neb_cache,assertion_types,heuristic);
+                                    frontier.push(l);
+                                    new_sequence
+                                }
+                                None => {
+                                    sequence_being_considered.dive_done=Some(c);
+                                    sequence_being_considered.extend_by_candidate(c,votes,audit,neb_cache,assertion_types,heuristic)
+                                },
+                            };
+                            if new_sequence.difficulty()<= self.lower_bound.get() {
+                                new_sequence.just_take_assertion(assertions,&mut frontier);
+                                break;
+                            } else {
+                                last = Some(new_sequence);
+                            }
+                        }
+                    }
+                    if let Some(last) = last {
+                        assert_eq!(last.pi.len(),votes.num_candidates() as usize);
+                        last.contains_all_candidates(assertions,&mut frontier,&self.lower_bound)?;
+                        if sequence_being_considered.difficulty()<= self.lower_bound.get() { // the lower bound may have changed in such a way that there is no point continuing this assertion.
+                            sequence_being_considered.just_take_assertion(assertions,&mut frontier);
+                            continue;
+                        }
+                    }
+                }
+                for c in 0..votes.num_candidates() { // for each(c ∈ C \ π):
+                    let c = CandidateIndex(c);
+                    if !(sequence_being_considered.pi.contains(&c)||sequence_being_considered.dive_done==Some(c)) {
+                        let new_sequence = sequence_being_considered.extend_by_candidate(c,votes,audit,neb_cache,assertion_types,heuristic);
+                        if new_sequence.pi.len()==votes.num_candidates() as usize { // 22 if (|π′| = |C|):
+                            new_sequence.contains_all_candidates(assertions,&mut frontier,&self.lower_bound)?;
+                        } else {
+                            frontier.push(new_sequence) // 31 F ← F ∪ {π ′ }
+                        }
+                    }
+                }
+            }
+            log::trace!("Frontier now includes {} elimination sequences",frontier.len());
+        }
+        Ok(())
+    }
+
+    /// Give a snapshot of the search to the [SnapshotStore], if there is one. `current` has just been taken from the frontier.
+    fn save_snapshot(&self,current:&SequenceAndEffort,frontier:&BinaryHeap<SequenceAndEffort>,assertions:&[AssertionAndDifficulty],work_done:u64) {
+        if let Some(snapshots) = &self.snapshots {
+            let snapshot = SearchSnapshot{
+                format_version: SNAPSHOT_FORMAT_VERSION,
+                fingerprint: snapshots.fingerprint.clone(),
+                lower_bound: self.lower_bound.get(),
+                frontier: std::iter::once(current).chain(frontier.iter()).map(SequenceAndEffort::snapshot).collect(),
+                assertions: assertions.to_vec(),
+                work_done: snapshots.work_before_resumption+work_done,
+            };
+            match snapshots.store.save(&snapshot) {
+                Ok(()) => log::debug!("Saved a snapshot of the search with {} elimination order suffixes on the frontier",snapshot.frontier.len()),
+                Err(e) => log::warn!("Could not save a snapshot of the search : {}",e),
+            }
+        }
+    }
+}
+
+impl <A:AuditType> Search<'_,A> {
+    /// Expand the frontier on the current thread, in the order of the original paper, starting with the given assertions.
+    fn sequential(&self,initial:Vec<SequenceAndEffort>,mut assertions:Vec<AssertionAndDifficulty>,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<Vec<AssertionAndDifficulty>,RaireError> {
+        let _span = phase_span!("search",alternate_winners=initial.len());
+        self.expand_frontier(BinaryHeap::from(initial),&mut assertions,timeout,progress)?;
+        Ok(assertions)
+    }
+}
+
+/// The index of an alternate winner being searched, and the assertions needed to rule it out.
+#[cfg(feature = "parallel")]
+type JobResult = (usize,Result<Vec<AssertionAndDifficulty>,RaireError>);
+
+#[cfg(feature = "parallel")]
+impl <A:AuditType> Search<'_,A> {
+    /// Expand the frontier on several threads. The elimination order suffixes leading to each alternate winner are
+    /// disjoint, so each alternate winner is searched separately, with the lower bound shared between them.
+    ///
+    /// The resulting difficulty is the same as for the sequential search, but the assertions may differ as
+    /// the lower bound is raised in a different order. Progress is not reported during the search.
+    fn parallel(&self,initial:Vec<SequenceAndEffort>,max_threads:Option<usize>,timeout:&mut TimeOut) -> Result<Vec<AssertionAndDifficulty>,RaireError> {
+        let num_threads = max_threads.unwrap_or_else(||std::thread::available_parallelism().map(|n|n.get()).unwrap_or(1)).min(initial.len()).max(1);
+        let _span = phase_span!("search",alternate_winners=initial.len(),threads=num_threads);
+        log::debug!("Searching {} alternate winners on {} threads",initial.len(),num_threads);
+        let next_job = std::sync::atomic::AtomicUsize::new(0);
+        let jobs : Vec<Mutex<Option<SequenceAndEffort>>> = initial.into_iter().map(|s|Mutex::new(Some(s))).collect();
+        let work_at_start = timeout.get_work_done();
+        let results : Vec<(u64,Vec<JobResult>)> = std::thread::scope(|scope|{
+            let threads : Vec<_> = (0..num_threads).map(|_|{
+                let mut thread_timeout = timeout.for_thread();
+                let (jobs,next_job) = (&jobs,&next_job);
+                scope.spawn(move ||{
+                    let mut done = vec![];
+                    loop {
+                        let job = next_job.fetch_add(1,AtomicOrdering::Relaxed);
+                        if job>=jobs.len() { break; }
+                        let start = jobs[job].lock().unwrap().take().unwrap();
+                        let mut assertions = vec![];
+                        let result = self.expand_frontier(BinaryHeap::from(vec![start]),&mut assertions,&mut thread_timeout,&mut crate::progress::NoProgressReporter);
+                        if result.is_err() { self.stop.store(true,AtomicOrdering::Relaxed); }
+                        done.push((job,result.map(|_|assertions)));
+                    }
+                    (thread_timeout.get_work_done()-work_at_start,done)
+                })
+            }).collect();
+            threads.into_iter().map(|t|t.join().unwrap()).collect()
+        });
+        timeout.add_work_done(results.iter().map(|(work,_)|*work).sum());
+        self.unresolved.lock().unwrap().extend(jobs.into_iter().filter_map(|job|job.into_inner().unwrap()).map(|s|s.pi)); // never started.
+        let mut by_job : Vec<JobResult> = results.into_iter().flat_map(|(_,done)|done).collect();
+        by_job.sort_by_key(|(job,_)|*job);
+        let mut assertions : Vec<AssertionAndDifficulty> = vec![];
+        for (_,result) in by_job {
+            for a in result? {
+                if !assertions.iter().any(|existing|existing.assertion==a.assertion) { assertions.push(a); }
+            }
+        }
+        Ok(assertions)
+    }
+}
+
 /// If true, use Michelle's diving search order optimization.
 /// Testing shows that it is almost always a moderate improvement in speed.
 const USE_DIVING : bool = true;
 
-pub fn raire<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,trim_algorithm:TrimAlgorithm,timeout:&mut TimeOut) -> Result<RaireResult,RaireError> {
+/// Options controlling how the RAIRE algorithm runs, other than the votes, winner and audit type.
+///
+/// The defaults are sensible; [RaireOptions::default()] gives the same behaviour as [raire].
+#[derive(Clone,Debug,Default,Serialize)]
+pub struct RaireOptions {
+    /// The algorithm used to trim redundant assertions.
+    pub trim_algorithm : TrimAlgorithm,
+    /// How to choose which assertions to keep when trimming.
+    pub trim_selection_algorithm : TrimSelectionAlgorithm,
+    /// When trimming, also select the assertions to keep with both [TrimSelectionAlgorithm::TwoPassHeuristic] and [TrimSelectionAlgorithm::Optimal],
+    /// check that each selection still rules out every alternate winner, and record how many each kept in [TrimmingStatistics::cross_check].
+    /// If either check fails, the result is an internal error. A sanity check for high stakes runs, and for testing new trimming code;
+    /// as the optimal selection may be slow, trimming may take much longer. Ignored if [RaireOptions::trim_algorithm] is [TrimAlgorithm::None].
+    pub cross_check_trimming : bool,
+    /// If a tree of elimination orders built when trimming would have more than this many nodes, give up trimming
+    /// and return the untrimmed assertions with [RaireResult::warning_trim_timed_out] set. `None` means no limit.
+    pub max_tree_nodes : Option<usize>,
+    /// The approximate maximum memory, in megabytes, used by the search's frontier and the trees built when trimming. If the search
+    /// would use more, it fails with [RaireError::OutOfMemoryBudget]; if the trees would, trimming is abandoned as for [Self::max_tree_nodes].
+    /// This does not count the votes, which are already in memory. `None` means no limit.
+    pub max_memory_mb : Option<usize>,
+    /// The elimination rule used to determine the winner. The assertions produced are valid for either rule.
+    pub elimination_rule : EliminationRule,
+    /// How ties for elimination are resolved when determining the winner.
+    pub tie_resolution : TieResolution,
+    /// If the time limit expires while finding assertions, return the assertions found so far as a [RaireResult::partial]
+    /// result rather than [RaireError::TimeoutFindingAssertions].
+    pub partial_on_timeout : bool,
+    /// If the time limit expires while finding assertions, return the winner only assertions (see [RaireResult::fallback]) if they are all true,
+    /// rather than an error or partial result.
+    pub winner_only_fallback : bool,
+    /// First find assertions quickly, accepting the first assertion found for each elimination order suffix and trimming cheaply, then
+    /// spend the rest of the time limit refining them, each time asking for assertions at most [TWO_PHASE_REFINEMENT_FACTOR] times as hard
+    /// as the best so far, until the easiest are found. Each improvement, starting with the quick answer, is reported as a
+    /// [ProgressEvent::ImprovedSolution]. If the time limit expires while refining, the result is the best found, with
+    /// [RaireResult::refinement_incomplete] set. For election night, when a fast answer to whether an audit is feasible matters most.
+    /// Snapshots are not kept by a two phase solve.
+    pub two_phase : bool,
+    /// If given, stop optimizing as soon as a set of assertions is found whose maximum difficulty is at most this,
+    /// e.g. corresponding to the largest sample size that can be afforded. The result's difficulty is then that of the
+    /// assertions found, which may be higher than the best possible but is no higher than this. If no such set exists,
+    /// this has no effect on the result. Infinite values are ignored.
+    pub stop_when_difficulty_at_most : Option<AssertionDifficulty>,
+    /// What makes one set of assertions better than another.
+    pub objective : SearchObjective,
+    /// Make the result depend only on the votes and options, so that it can be compared byte for byte with someone else's:
+    /// the search is done on a single thread even with the `parallel` feature, and clock times in the result are recorded as zero.
+    /// A time limit is still measured by the clock, so a computation that runs out of time is not reproducible.
+    pub deterministic : bool,
+    /// Record, in each assertion in the result, the tallies its margin and difficulty were computed from.
+    pub include_tallies : bool,
+    /// Which types of assertions may be used. Restricting them may make the audit harder, or impossible ([RaireError::CouldNotRuleOut]).
+    pub assertion_types : AssertionTypes,
+    /// Choose assertions by comparing their margins exactly, with integer arithmetic, rather than by comparing floating point difficulties,
+    /// so that rounding cannot affect which assertions are chosen on any platform. The difficulties in the result are computed with the audit
+    /// only after the assertions are chosen; progress reported during the search gives difficulties as 1/diluted margin. Needs an audit with
+    /// an [AuditType::exact_difficulty], that is, not BRAVO or a stratified audit, otherwise the result is [RaireError::ExactArithmeticNotSupported].
+    pub exact_arithmetic : bool,
+    /// If given, expand the trees built when trimming beyond pruned nodes to the given depth, so that more redundant assertions
+    /// can be found. Ignored if [RaireOptions::trim_algorithm] is [TrimAlgorithm::None].
+    pub extended_trim : Option<ExtendedTrim>,
+    /// The order in which the search expands elimination order suffixes. `None` means [HardestFirst], as in the original paper.
+    /// This affects how long the search takes, but not the difficulty of the result.
+    #[serde(skip)]
+    pub frontier_heuristic : Option<Arc<dyn FrontierHeuristic>>,
+    /// Assertions found previously, e.g. for a preliminary count. If they are all true with these votes and rule out every other winner,
+    /// their difficulty is an upper bound, and the search stops as soon as it finds a set at least as easy, as for [RaireOptions::stop_when_difficulty_at_most].
+    /// This is often much faster, but the result is no easier than the previous assertions even if an easier set exists. Ignored if not usable.
+    pub warm_start_assertions : Vec<Assertion>,
+    /// With the `parallel` feature, the most threads the search may use. `None` means as many as the machine has; `Some(1)` searches on the current thread.
+    /// This affects how long the search takes, but not the difficulty of the result.
+    #[serde(skip)]
+    pub max_threads : Option<usize>,
+    /// Where to keep snapshots of the search, so that it can be resumed if stopped. If the store has a snapshot of this search,
+    /// the search resumes from it; if it has a snapshot of some other search, the result is [RaireError::InvalidSnapshot].
+    /// See [crate::snapshot]. A search with snapshots is done on a single thread.
+    #[serde(skip)]
+    pub snapshots : Option<Arc<dyn SnapshotStore>>,
+}
+
+/// Run the RAIRE algorithm, reporting progress to the given reporter. Use [crate::progress::NoProgressReporter] if you don't care about progress.
+pub fn raire<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,trim_algorithm:TrimAlgorithm,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<RaireResult,RaireError> {
+    raire_with_options(votes,winner,audit,&RaireOptions{trim_algorithm,..Default::default()},timeout,progress)
+}
+
+/// Run the RAIRE algorithm with the given options, reporting progress to the given reporter.
+pub fn raire_with_options<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,options:&RaireOptions,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<RaireResult,RaireError> {
+    let _span = phase_span!("raire",candidates=votes.num_candidates(),distinct_votes=votes.votes.len());
+    #[cfg(feature = "tracing")]
+    let progress = &mut crate::progress::TracingProgressReporter(progress);
+    let result = if options.two_phase { raire_in_two_phases(votes,winner,audit,options,timeout,progress) } else { raire_with_objective(votes,winner,audit,options,timeout,progress) };
+    let result = match result {
+        // a result that is incomplete because it was cancelled rather than because it ran out of time.
+        Err(RaireError::TimeoutCheckingWinner|RaireError::TimeoutFindingAssertions(_)) if timeout.is_cancelled() => return Err(RaireError::Cancelled{partial:None}),
+        Ok(result) if timeout.is_cancelled() && (result.partial || result.fallback || result.refinement_incomplete || result.warning_trim_timed_out) => return Err(RaireError::Cancelled{partial:Some(Box::new(result))}),
+        result => result?,
+    };
+    Ok(finish_result(result,votes,audit,options))
+}
+
+/// Apply [RaireOptions::deterministic] and [RaireOptions::include_tallies] to a result.
+fn finish_result<A:AuditType>(mut result:RaireResult,votes:&Votes,audit:&A,options:&RaireOptions) -> RaireResult {
+    if options.deterministic { result.clear_clock_times(); }
+    if options.include_tallies {
+        for a in &mut result.assertions { a.tallies=Some(a.assertion.tallies(votes,audit)); }
+    }
+    result
+}
+
+/// For [RaireOptions::two_phase], each refinement asks for assertions at most this multiple of the difficulty of the best found so far.
+pub const TWO_PHASE_REFINEMENT_FACTOR : f64 = 0.5;
+
+/// Find assertions quickly, and then refine them, as for [RaireOptions::two_phase].
+fn raire_in_two_phases<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,options:&RaireOptions,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<RaireResult,RaireError> {
+    let options = RaireOptions{two_phase:false,snapshots:None,..options.clone()};
+    // Any finite difficulty is acceptable, so the first assertion found for each elimination order suffix is used.
+    let quick_options = RaireOptions{
+        stop_when_difficulty_at_most: Some(f64::MAX),
+        objective: SearchObjective::MinimizeMaximumDifficulty,
+        trim_algorithm: if options.trim_algorithm==TrimAlgorithm::None { TrimAlgorithm::None } else { TrimAlgorithm::MinimizeTree },
+        trim_selection_algorithm: TrimSelectionAlgorithm::TwoPassHeuristic,
+        cross_check_trimming: false,
+        extended_trim: None,
+        exact_arithmetic: false,
+        warm_start_assertions: vec![],
+        ..options.clone()
+    };
+    let best = {
+        let _span = phase_span!("quick_phase");
+        raire_with_objective(votes,winner,audit,&quick_options,timeout,progress)?
+    };
+    if best.partial || best.fallback { return Ok(best); }
+    log::debug!("Quick phase found {} assertions with difficulty {}",best.assertions.len(),best.difficulty);
+    progress.report(ProgressEvent::ImprovedSolution{result:Box::new(finish_result(best.clone(),votes,audit,&options))});
+    let _span = phase_span!("refinement_phase");
+    let mut best = best;
+    let requested = options.stop_when_difficulty_at_most.filter(|d|d.is_finite());
+    let refine_options = RaireOptions{partial_on_timeout:false,winner_only_fallback:false,..options.clone()};
+    loop {
+        if requested.is_some_and(|d|best.difficulty<=d) || !(best.difficulty>0.0 && best.difficulty.is_finite()) { break; } // as easy as asked for, or can't be halved.
+        let threshold = best.difficulty*TWO_PHASE_REFINEMENT_FACTOR;
+        let threshold_options = RaireOptions{stop_when_difficulty_at_most:Some(requested.map_or(threshold,|d|d.max(threshold))),..refine_options.clone()};
+        // with an objective other than the default, the search tries its own thresholds, so is done just once.
+        let refining_options = if options.objective==SearchObjective::MinimizeMaximumDifficulty { &threshold_options } else { &refine_options };
+        match raire_with_objective(votes,winner,audit,refining_options,timeout,progress) {
+            Ok(result) => {
+                let done = options.objective!=SearchObjective::MinimizeMaximumDifficulty || result.difficulty>threshold; // there is nothing easier than the threshold.
+                let improved = options.objective.cost(&result.assertions)<options.objective.cost(&best.assertions);
+                log::debug!("Refinement found {} assertions with difficulty {}",result.assertions.len(),result.difficulty);
+                if improved {
+                    progress.report(ProgressEvent::ImprovedSolution{result:Box::new(finish_result(result.clone(),votes,audit,&options))});
+                    best=result;
+                }
+                if done { break; }
+            }
+            Err(RaireError::TimeoutCheckingWinner|RaireError::TimeoutFindingAssertions(_)|RaireError::TimeoutTrimmingAssertions) => {
+                log::debug!("Ran out of time refining the assertions");
+                best.refinement_incomplete=true;
+                break;
+            }
+            Err(e) => { return Err(e); }
+        }
+    }
+    best.time_to_find_assertions = timeout.time_taken()-best.time_to_determine_winners-best.time_to_trim_assertions;
+    Ok(best)
+}
+
+/// Find assertions that are best according to [RaireOptions::objective].
+fn raire_with_objective<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,options:&RaireOptions,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<RaireResult,RaireError> {
+    if options.objective==SearchObjective::MinimizeMaximumDifficulty { return raire_minimizing_maximum_difficulty(votes,winner,audit,options,timeout,progress); }
+    // Other objectives: find the easiest audit, and then see if allowing harder assertions gives fewer of them at a lower cost.
+    let best = raire_minimizing_maximum_difficulty(votes,winner,audit,options,timeout,progress)?;
+    if best.partial || best.fallback { return Ok(best); }
+    let optimal_difficulty = best.difficulty;
+    let mut best_cost = options.objective.cost(&best.assertions);
+    let mut best = best;
+    for factor in SEARCH_OBJECTIVE_DIFFICULTY_FACTORS {
+        let threshold = optimal_difficulty*factor;
+        let relaxed_options = RaireOptions{stop_when_difficulty_at_most:Some(threshold),partial_on_timeout:false,winner_only_fallback:false,snapshots:None,..options.clone()};
+        match raire_minimizing_maximum_difficulty(votes,winner,audit,&relaxed_options,timeout,progress) {
+            Ok(result) => {
+                let cost = options.objective.cost(&result.assertions);
+                log::debug!("Allowing difficulty up to {} gave {} assertions with cost {}",threshold,result.assertions.len(),cost);
+                if cost<best_cost { best_cost=cost; best=result; }
+            }
+            Err(RaireError::TimeoutFindingAssertions(_)) => { break; } // keep the best found so far.
+            Err(e) => { return Err(e); }
+        }
+    }
+    best.time_to_find_assertions = timeout.time_taken()-best.time_to_determine_winners-best.time_to_trim_assertions;
+    Ok(best)
+}
+
+/// When looking for assertions for a [SearchObjective] other than the default, the multiples of the
+/// easiest possible difficulty that are tried as an upper bound on the difficulty of each assertion.
+const SEARCH_OBJECTIVE_DIFFICULTY_FACTORS : [f64;8] = [1.5,2.0,3.0,5.0,10.0,20.0,50.0,100.0];
+
+/// The branch and bound search of the original paper, minimizing the difficulty of the hardest assertion, using [RaireOptions::exact_arithmetic] if asked.
+fn raire_minimizing_maximum_difficulty<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,options:&RaireOptions,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<RaireResult,RaireError> {
+    if !options.exact_arithmetic { return search_minimizing_maximum_difficulty(votes,winner,audit,options,timeout,progress); }
+    let exact = ExactlyOrdered::new(audit,votes)?;
+    let stop_when_difficulty_at_most = options.stop_when_difficulty_at_most.filter(|d|d.is_finite()).and_then(|d|exact.from_audit_difficulty(d));
+    let exact_options = RaireOptions{stop_when_difficulty_at_most,exact_arithmetic:false,..options.clone()};
+    let mut result = match search_minimizing_maximum_difficulty(votes,winner,&exact,&exact_options,timeout,progress) {
+        Err(RaireError::TimeoutFindingAssertions(difficulty)) => return Err(RaireError::TimeoutFindingAssertions(exact.to_audit_difficulty(difficulty))),
+        result => result?,
+    };
+    // The assertions have been chosen; only now compute the audit's difficulties.
+    for a in &mut result.assertions { a.difficulty=crate::incremental::rescore(&a.assertion,votes,audit,false).0.difficulty; }
+    result.difficulty=exact.to_audit_difficulty(result.difficulty);
+    result.difficulty_units=audit.difficulty_units();
+    result.warm_start_difficulty=result.warm_start_difficulty.map(|d|exact.to_audit_difficulty(d));
+    Ok(result)
+}
+
+/// The branch and bound search of the original paper, minimizing the difficulty of the hardest assertion.
+fn search_minimizing_maximum_difficulty<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,options:&RaireOptions,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<RaireResult,RaireError> {
     log::debug!("Starting raire with {} candidates and {} distinct votes",votes.num_candidates(),votes.votes.len());
-    let irv_result = votes.run_election(timeout)?;
+    let irv_result = {
+        let _span = phase_span!("determine_winner",elimination_rule=?options.elimination_rule);
+        votes.run_election_with_rules(options.elimination_rule,&options.tie_resolution,timeout)?
+    };
     let time_to_determine_winners = timeout.time_taken();
     if let Some(winner) = winner {
         if !irv_result.possible_winners.contains(&winner) { return Err(RaireError::WrongWinner(irv_result.possible_winners))}
@@ -189,105 +809,141 @@ pub fn raire<A:AuditType>(votes:&Votes,winner:Option<CandidateIndex>,audit:&A,tr
     if irv_result.possible_winners.len()!=1 { return Err(RaireError::TiedWinners(irv_result.possible_winners))}
     let winner : CandidateIndex = irv_result.possible_winners[0]; // replace option by actual value.
     log::debug!("IRV winner {} elimination order {:?}",winner,irv_result.elimination_order);
+    progress.report(ProgressEvent::DeterminedWinner{winner,time_taken:time_to_determine_winners});
     let neb_cache = NotEliminatedBeforeCache::new(votes,audit);
     log::trace!("Created NEB cache");
-    //println!("Calling raire with {} votes {} candidates winner {}",votes.total_votes(),votes.num_candidates(),winner);
-    let mut assertions : Vec<AssertionAndDifficulty> = vec![]; // A in the original paper
-    let mut lower_bound: AssertionDifficulty = 0.0; // LB in the original paper. A lower bound on the difficulty of the problem.
-    let mut frontier = BinaryHeap::new(); // F in the original paper
-    let mut last_difficulty:f64 = f64::INFINITY;
+    let warm_start_difficulty = warm_start_difficulty(&options.warm_start_assertions,votes,audit,winner,options.assertion_types,timeout)?;
+    if let Some(difficulty) = warm_start_difficulty { log::debug!("Warm start assertions have difficulty {}",difficulty); }
+    let threshold = options.stop_when_difficulty_at_most.filter(|d|d.is_finite()).into_iter().chain(warm_start_difficulty).reduce(f64::min);
+    let heuristic : &dyn FrontierHeuristic = match &options.frontier_heuristic { Some(heuristic) => heuristic.as_ref(), None => &HardestFirst };
     // Populate F with single-candidate sequences
-    for c in 0..votes.num_candidates() {
-        let c = CandidateIndex(c);
-        if c!=winner { // 4 for each(c ∈ C \ {c w }):
-            let pi = vec![c];
-            //  asr[π] ← a ⊲ Record best assertion for π
-            let best_assertion_for_pi = find_best_audit(&pi,votes,audit,&neb_cache);  // a in the original paper
-            //  ba[π] ← π ⊲ Record best ancestor sequence for π
-            let best_ancestor_length = pi.len();
-            frontier.push(SequenceAndEffort{pi,best_ancestor_length,best_assertion_for_ancestor:best_assertion_for_pi, dive_done: None }); // difficulty comes from asr[π].
-        }
-    }
-    // Repeatedly expand the sequence with largest ASN in F
-    while let Some(mut sequence_being_considered) = frontier.pop() { // 10-12
-        if timeout.quick_check_timeout() { return Err(RaireError::TimeoutFindingAssertions(sequence_being_considered.difficulty().max(lower_bound))) }
-        if sequence_being_considered.difficulty()!=last_difficulty {
-            last_difficulty=sequence_being_considered.difficulty();
-            log::trace!("Difficulty reduced to {}{}",last_difficulty,if last_difficulty<= lower_bound {" OK"} else {""});
-        }
-        //println!("Considering {:?}",sequence_being_considered);
-        if sequence_being_considered.difficulty()<= lower_bound { // may as well just include.
-            sequence_being_considered.just_take_assertion(&mut assertions,&mut frontier);
-        } else {
-            if USE_DIVING && !sequence_being_considered.dive_done.is_some() {
-                let mut last : Option<SequenceAndEffort> = None;
-                assert_eq!(irv_result.elimination_order.len(),votes.num_candidates() as usize);
-                for &c in irv_result.elimination_order.iter().rev() {
-                    if !sequence_being_considered.pi.contains(&c) {
-                        let new_sequence = match last.take() { // don't repeat work! Mark that this path has already been dealt with.
-                            Some(mut l) => {
-                                l.dive_done=Some(c);
-                                let new_sequence = l.extend_by_candidate(c,votes,audit,&neb_cache);
-                                frontier.push(l);
-                                new_sequence
-                            }
-                            None => {
-                                sequence_being_considered.dive_done=Some(c);
-                                sequence_being_considered.extend_by_candidate(c,votes,audit,&neb_cache)
-                            },
-                        };
-                        if new_sequence.difficulty()<= lower_bound {
-                            new_sequence.just_take_assertion(&mut assertions,&mut frontier);
-                            break;
-                        } else {
-                            last = Some(new_sequence);
-                        }
-                    }
-                }
-                if let Some(last) = last {
-                    assert_eq!(last.pi.len(),votes.num_candidates() as usize);
-                    last.contains_all_candidates(&mut assertions,&mut frontier,&mut lower_bound)?;
-                    if sequence_being_considered.difficulty()<= lower_bound { // the lower bound may have changed in such a way that there is no point continuing this assertion.
-                        sequence_being_considered.just_take_assertion(&mut assertions,&mut frontier);
-                        continue;
-                    }
-                }
-            }
-            for c in 0..votes.num_candidates() { // for each(c ∈ C \ π):
-                let c = CandidateIndex(c);
-                if !(sequence_being_considered.pi.contains(&c)||sequence_being_considered.dive_done==Some(c)) {
-                    let new_sequence = sequence_being_considered.extend_by_candidate(c,votes,audit,&neb_cache);
-                    if new_sequence.pi.len()==votes.num_candidates() as usize { // 22 if (|π′| = |C|):
-                        new_sequence.contains_all_candidates(&mut assertions,&mut frontier,&mut lower_bound)?;
-                    } else {
-                        frontier.push(new_sequence) // 31 F ← F ∪ {π ′ }
-                    }
+    let initial : Vec<SequenceAndEffort> = (0..votes.num_candidates()).map(CandidateIndex).filter(|&c|c!=winner).map(|c|{ // 4 for each(c ∈ C \ {c w }):
+        let pi = vec![c];
+        //  asr[π] ← a ⊲ Record best assertion for π
+        let best_assertion_for_pi = find_best_audit(&pi,votes,audit,&neb_cache,options.assertion_types);  // a in the original paper
+        //  ba[π] ← π ⊲ Record best ancestor sequence for π
+        let best_ancestor_length = pi.len();
+        SequenceAndEffort::new(pi,best_ancestor_length,best_assertion_for_pi,votes,heuristic) // difficulty comes from asr[π].
+    }).collect();
+    let lower_bound = LowerBound::new(threshold.unwrap_or(0.0));
+    let (snapshots,initial,resumed_assertions) = match &options.snapshots {
+        Some(store) => {
+            let initial_difficulties : Vec<AssertionDifficulty> = initial.iter().map(|s|s.difficulty()).collect();
+            let fingerprint = crate::manifest::sha256_hex(&serde_json::to_vec(&(votes.weighted_records(),winner,&irv_result.elimination_order,threshold,initial_difficulties,options.assertion_types)).unwrap_or_default());
+            let mut snapshotting = Snapshotting{ store: store.as_ref(), fingerprint, work_before_resumption: 0 };
+            match store.load()? {
+                Some(snapshot) => {
+                    snapshot.check(&snapshotting.fingerprint,votes.num_candidates())?;
+                    log::debug!("Resuming the search from a snapshot with {} elimination order suffixes on the frontier and {} assertions",snapshot.frontier.len(),snapshot.assertions.len());
+                    lower_bound.raise(snapshot.lower_bound);
+                    snapshotting.work_before_resumption=snapshot.work_done;
+                    let frontier = snapshot.frontier.into_iter().map(|s|SequenceAndEffort::resume(s,votes,heuristic)).collect();
+                    (Some(snapshotting),frontier,snapshot.assertions)
                 }
+                None => (Some(snapshotting),initial,vec![]),
             }
         }
-        //println!("frontier now includes {} elements",frontier.len())
+        None => (None,initial,vec![]),
+    };
+    let search = Search{votes,audit,neb_cache:&neb_cache,assertion_types:options.assertion_types,elimination_order:&irv_result.elimination_order,lower_bound,stop:AtomicBool::new(false),partial_on_timeout:options.partial_on_timeout,timed_out:AtomicBool::new(false),unresolved:Mutex::new(vec![]),heuristic,memory:MemoryBudget::new(options.max_memory_mb),snapshots};
+    #[cfg(feature = "parallel")]
+    let searched = if options.deterministic || options.max_threads==Some(1) || search.snapshots.is_some() { search.sequential(initial,resumed_assertions,timeout,progress) } else { search.parallel(initial,options.max_threads,timeout) };
+    #[cfg(not(feature = "parallel"))]
+    let searched = search.sequential(initial,resumed_assertions,timeout,progress);
+    let partial = search.timed_out.load(AtomicOrdering::Relaxed);
+    if options.winner_only_fallback && (partial || matches!(searched,Err(RaireError::TimeoutFindingAssertions(_)))) {
+        if let Some(result) = winner_only_fallback(votes,audit,winner,&irv_result,time_to_determine_winners,warm_start_difficulty,timeout) {
+            log::debug!("Ran out of time finding assertions, so using the winner only assertions");
+            return Ok(result);
+        }
     }
+    let mut assertions = searched?; // A in the original paper
+    let mut lower_bound = search.lower_bound.get(); // LB in the original paper. A lower bound on the difficulty of the problem.
+    if let Some(snapshots) = &search.snapshots { if !partial { snapshots.store.finished(); } }
+    let mut unresolved_elimination_order_suffixes = if partial { search.unresolved.into_inner().unwrap() } else { vec![] };
+    unresolved_elimination_order_suffixes.sort_unstable_by(|a,b|a.iter().rev().map(|c|c.0).cmp(b.iter().rev().map(|c|c.0)));
+    unresolved_elimination_order_suffixes.dedup();
     let time_to_find_assertions = timeout.time_taken()-time_to_determine_winners;
     log::debug!("Finished generating {} assertions difficulty {}, now need to trim.",assertions.len(),lower_bound);
-    let warning_trim_timed_out = match crate::tree_showing_what_assertions_pruned_leaves::order_assertions_and_remove_unnecessary(&mut assertions,winner,votes.num_candidates(),trim_algorithm,timeout) {
-        Ok(_) => false,
-        Err(RaireError::TimeoutTrimmingAssertions) => true,
-        Err(e) => {return Err(e);}
+    progress.report(ProgressEvent::FinishedSearch{assertions_found:assertions.len(),difficulty:lower_bound,time_taken:time_to_find_assertions});
+    let (warning_trim_timed_out,trimming_statistics) = if partial {
+        log::debug!("Ran out of time with {} elimination order suffixes unresolved; not trimming.",unresolved_elimination_order_suffixes.len());
+        (false,None) // trimming needs a complete set of assertions.
+    } else {
+        match crate::tree_showing_what_assertions_pruned_leaves::order_assertions_and_remove_unnecessary(&mut assertions,winner,votes.num_candidates(),options,timeout,progress) {
+            Ok(statistics) => (false,Some(statistics)),
+            Err(RaireError::TimeoutTrimmingAssertions|RaireError::TreeTooLarge(_)) => (true,None),
+            Err(e) => {return Err(e);}
+        }
     };
     let time_to_trim_assertions = timeout.time_taken()-time_to_find_assertions-time_to_determine_winners;
     log::debug!("Trimmed assertions down to {}.",assertions.len());
     let margin = assertions.iter().map(|a|a.margin).min().unwrap_or(BallotPaperCount(0));
+    if threshold.is_some() { // the bound started at the threshold, which may be higher than needed.
+        lower_bound = assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max);
+    }
     // simple fast consistency check - make sure that the ostensible elimination order is consistent with all the assertions. If so, then the winner is not ruled out, and all is good.
-    for a in &assertions {
-        match a.assertion.ok_elimination_order_suffix(&irv_result.elimination_order) {
-            EffectOfAssertionOnEliminationOrderSuffix::Ok => {}
-            _ => { return Err(RaireError::InternalErrorRuledOutWinner); }
+    let ruling_out_winner : Vec<usize> = assertions.iter().enumerate().filter(|(_,a)|a.assertion.ok_elimination_order_suffix(&irv_result.elimination_order)!=EffectOfAssertionOnEliminationOrderSuffix::Ok).map(|(i,_)|i).collect();
+    if !ruling_out_winner.is_empty() { return Err(RaireError::InternalErrorRuledOutWinner{winner:Some(winner),elimination_order:irv_result.elimination_order.clone(),assertions:ruling_out_winner}); }
+    let margin_of_victory = if partial { None } else { Some(MarginOfVictory::new(votes,winner,&irv_result.elimination_order,&assertions)) };
+    Ok(RaireResult{assertions, difficulty: lower_bound, difficulty_units: audit.difficulty_units(), margin, margin_of_victory, winner,num_candidates:votes.num_candidates(), time_to_determine_winners, time_to_find_assertions, time_to_trim_assertions, warning_trim_timed_out, trimming_statistics, tie_breaks: irv_result.tie_breaks, partial, fallback: false, refinement_incomplete: false, unresolved_elimination_order_suffixes, phantoms: None, warm_start_difficulty })
+}
+
+/// The winner only assertions, that the winner is not eliminated before each other candidate, as a [RaireResult::fallback] result, if they are all true.
+fn winner_only_fallback<A:AuditType>(votes:&Votes,audit:&A,winner:CandidateIndex,irv_result:&IRVResult,time_to_determine_winners:TimeTaken,warm_start_difficulty:Option<AssertionDifficulty>,timeout:&TimeOut) -> Option<RaireResult> {
+    let mut assertions = vec![];
+    for loser in (0..votes.num_candidates()).map(CandidateIndex).filter(|&c|c!=winner) {
+        let (assertion,holds) = crate::incremental::rescore(&Assertion::NEB(NotEliminatedBefore{winner,loser}),votes,audit,false);
+        if !(holds && assertion.difficulty.is_finite()) { return None; }
+        assertions.push(assertion);
+    }
+    Some(RaireResult{
+        difficulty: assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max),
+        difficulty_units: audit.difficulty_units(),
+        margin: assertions.iter().map(|a|a.margin).min().unwrap_or(BallotPaperCount(0)),
+        margin_of_victory: Some(MarginOfVictory::new(votes,winner,&irv_result.elimination_order,&assertions)),
+        assertions,
+        winner,
+        num_candidates: votes.num_candidates(),
+        time_to_determine_winners,
+        time_to_find_assertions: timeout.time_taken()-time_to_determine_winners,
+        time_to_trim_assertions: TimeTaken{ work: 0, seconds: 0.0 },
+        warning_trim_timed_out: false,
+        trimming_statistics: None,
+        tie_breaks: irv_result.tie_breaks.clone(),
+        partial: false,
+        fallback: true,
+        refinement_incomplete: false,
+        unresolved_elimination_order_suffixes: vec![],
+        phantoms: None,
+        warm_start_difficulty,
+    })
+}
+
+/// The difficulty of the hardest of the warm start assertions with these votes, if they are all of the allowed types, true, and rule out every candidate other than the winner.
+fn warm_start_difficulty<A:AuditType>(assertions:&[Assertion],votes:&Votes,audit:&A,winner:CandidateIndex,assertion_types:AssertionTypes,timeout:&mut TimeOut) -> Result<Option<AssertionDifficulty>,RaireError> {
+    if assertions.is_empty() || !assertions.iter().all(|a|assertion_types.allows(a)) { return Ok(None); }
+    let num_candidates = votes.num_candidates();
+    for assertion in assertions { assertion.validate(num_candidates)?; }
+    let mut difficulty : AssertionDifficulty = 0.0;
+    for assertion in assertions {
+        let (rescored,holds) = crate::incremental::rescore(assertion,votes,audit,false);
+        if !holds { return Ok(None); }
+        difficulty=difficulty.max(rescored.difficulty);
+    }
+    if !difficulty.is_finite() { return Ok(None); }
+    let all_assertion_indices : Vec<usize> = (0..assertions.len()).collect();
+    for candidate in (0..num_candidates).map(CandidateIndex).filter(|&c|c!=winner) {
+        match TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&all_assertion_indices,assertions,num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,timeout) {
+            Ok(tree) => if tree.valid { return Ok(None); }
+            Err(RaireError::TimeoutTrimmingAssertions) => return Ok(None), // the search will run out of time too.
+            Err(e) => return Err(e),
         }
     }
-    Ok(RaireResult{assertions, difficulty: lower_bound, margin, winner,num_candidates:votes.num_candidates(), time_to_determine_winners, time_to_find_assertions, time_to_trim_assertions, warning_trim_timed_out })
+    Ok(Some(difficulty))
 }
 
-#[derive(Clone,Copy,Debug,Serialize,Deserialize)]
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
 /// After the RAIRE algorithm has generated the assertions, it is possible that there are redundant assertions.
 ///
 /// This could happen as the algorithm found some assertion to trim one path, and then later some other
@@ -326,7 +982,104 @@ pub enum TrimAlgorithm {
     /// Don't do any trimming
     None,
     /// Expand the tree until an assertion rules the path out, removing redundant assertions with a simple heuristic. Minimizes size of tree for human to verify, but may have unnecessary assertions.
+    #[default]
     MinimizeTree,
     /// Expand the tree until all all assertions are resolved or an NEB rules the path out, and remove redundant assertions with a simple heuristic. Minimizes the number of assertions, but may increase the size of the tree to verify.
     MinimizeAssertions,
-}
\ No newline at end of file
+}
+
+/// How far beyond a node pruned by an assertion the trees built when trimming are expanded, to see whether the node's
+/// descendants are all pruned by other assertions, in which case the pruning assertion may be redundant.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub enum TrimDepth {
+    /// Expand this many levels below a pruned node. `Levels(0)` is the same as [TrimAlgorithm::MinimizeTree], and `Levels(1)` expands exactly one extra level.
+    Levels(u32),
+    /// Expand until every assertion is resolved. This finds the most redundant assertions, but the trees may be very large.
+    Unlimited,
+}
+
+/// Expand the trees built when trimming further than the [TrimAlgorithm] would, to find deeper redundancies.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct ExtendedTrim {
+    pub depth : TrimDepth,
+    /// If a tree so expanded would have more than this many nodes, trimming is done with the trees the [TrimAlgorithm] would build instead.
+    /// `None` means no limit other than [RaireOptions::max_tree_nodes].
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub max_nodes : Option<usize>,
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+/// When trimming, a node in the tree of elimination orders may be pruned by more than one assertion,
+/// and only one of them is needed. This chooses how to decide which assertions to keep.
+///
+/// This is independent of the [TrimAlgorithm], which decides how far the trees are expanded.
+pub enum TrimSelectionAlgorithm {
+    /// First take assertions that are the only way of pruning some node, then go through the
+    /// remaining nodes taking the first pruning assertion for any node not already pruned.
+    /// Fast, and optimal for almost every real contest tested, but not guaranteed optimal.
+    #[default]
+    TwoPassHeuristic,
+    /// After taking assertions that are the only way of pruning some node, repeatedly take the
+    /// assertion that prunes the most nodes not yet pruned. Fast, and often better than the
+    /// two pass heuristic when there are many overlapping assertions, but not guaranteed optimal.
+    GreedyCoverage,
+    /// Find the smallest possible set of assertions sufficient to prune the tree, via a branch
+    /// and bound search. Guaranteed optimal, but may be slow (or time out) for large trees.
+    Optimal,
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Serialize,Deserialize)]
+/// Which types of assertions the search may use.
+///
+/// Some audit tools only support NEB (winner only) assertions, and restricting the types shows what the other type adds to the cost
+/// of an audit. With only one type, some elimination orders may not be possible to rule out, so the contest cannot be audited.
+pub enum AssertionTypes {
+    /// Both NEB and NEN assertions, as in the original paper.
+    #[default]
+    Both,
+    /// Only NEB (not eliminated before, or winner only) assertions.
+    NEBOnly,
+    /// Only NEN (not eliminated next, or IRV) assertions.
+    NENOnly,
+}
+
+impl AssertionTypes {
+    /// Whether the assertion is of an allowed type.
+    pub fn allows(&self,assertion:&Assertion) -> bool {
+        matches!((self,assertion),(AssertionTypes::Both,_) | (AssertionTypes::NEBOnly,Assertion::NEB(_)) | (AssertionTypes::NENOnly,Assertion::NEN(_)))
+    }
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq,Serialize,Deserialize)]
+/// What to minimize when choosing a set of assertions.
+///
+/// The difficulty of an assertion is roughly proportional to the number of ballots that need to be sampled to confirm it.
+/// All assertions are tested on the same sample, so if there are no discrepancies only the hardest assertion matters.
+/// But each assertion has its own chance of needing escalation, so fewer but harder assertions are sometimes cheaper overall.
+///
+/// Other than the default, these are heuristics: the easiest audit is found, and then audits allowing each assertion
+/// to be some multiple of that difficulty (up to 100 times) are tried, and the cheapest is chosen.
+pub enum SearchObjective {
+    /// Minimize the difficulty of the hardest assertion, as in the original paper.
+    #[default]
+    MinimizeMaximumDifficulty,
+    /// Minimize the estimated total sample size. This is the difficulty of the hardest assertion, plus the difficulty of each other
+    /// assertion multiplied by `1-overlap`, where `overlap` (0 to 1) is the fraction of its sample assumed to be shared with the hardest.
+    /// An overlap of 0 is the sum of the difficulties; an overlap of 1 is the same as [SearchObjective::MinimizeMaximumDifficulty].
+    MinimizeTotalSampleSize{overlap:f64},
+    /// Minimize `maximum_weight` times the difficulty of the hardest assertion plus `total_weight` times the estimated total sample size as above.
+    Weighted{maximum_weight:f64,total_weight:f64,overlap:f64},
+}
+
+impl SearchObjective {
+    /// The cost of a set of assertions according to this objective. Lower is better.
+    pub fn cost(&self,assertions:&[AssertionAndDifficulty]) -> f64 {
+        let maximum = assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max);
+        let total = |overlap:f64|maximum+(1.0-overlap.clamp(0.0,1.0))*(assertions.iter().map(|a|a.difficulty).sum::<f64>()-maximum);
+        match *self {
+            SearchObjective::MinimizeMaximumDifficulty => maximum,
+            SearchObjective::MinimizeTotalSampleSize{overlap} => total(overlap),
+            SearchObjective::Weighted{maximum_weight,total_weight,overlap} => maximum_weight*maximum+total_weight*total(overlap),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+//! A self contained HTML report of a [RaireSolution], with no external scripts or style sheets,
+//! suitable for publishing alongside an audit.
+//!
+//! The report contains a summary of the contest, a table of assertions sorted by difficulty (hardest first),
+//! the tree of elimination orders ruled out for each candidate other than the winner (as collapsible lists and [pictures](crate::svg)),
+//! and what trimming did.
+
+use std::fmt::Write;
+use crate::assertions::{candidate_name, Assertion};
+use crate::irv::CandidateIndex;
+use crate::explain::{candidate_names_from_metadata, explain_assertion};
+use crate::raire_algorithm::RaireResult;
+use crate::sample_size::SampleSizeEstimate;
+use crate::{RaireError, RaireSolution};
+use crate::timeout::TimeOut;
+use crate::tree_showing_what_assertions_pruned_leaves::TreeNodeShowingWhatAssertionsPrunedIt;
+
+const STYLE : &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #999; padding: 0.3em 0.6em; text-align: left; }
+td.number { text-align: right; }
+.pruned { background-color: #fdd; }
+.valid { background-color: #dfd; }
+.error { color: #a00; }
+ul.tree { list-style-type: none; }
+details > summary { cursor: pointer; }
+";
+
+/// Escape text for inclusion in HTML.
+pub fn html_escape(s:&str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+/// Produce a complete HTML document describing the solution. Candidate names and the contest name are taken from the
+/// `candidates` and `contest` fields of the metadata if present.
+pub fn solution_to_html(solution:&RaireSolution,timeout:&mut TimeOut) -> Result<String,RaireError> {
+    let candidate_names = candidate_names_from_metadata(&solution.metadata);
+    let contest = solution.metadata["contest"].as_str().unwrap_or("IRV contest");
+    let mut out = String::new();
+    writeln!(out,"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>RAIRE assertions for {}</title>\n<style>\n{}</style>\n</head>\n<body>",html_escape(contest),STYLE).unwrap();
+    writeln!(out,"<h1>RAIRE assertions for {}</h1>",html_escape(contest)).unwrap();
+    match &solution.solution {
+        Ok(result) => write_result(result,solution.sample_sizes.as_ref(),&candidate_names,timeout,&mut out)?,
+        Err(e) => { writeln!(out,"<p class=\"error\">No assertions could be found: {}</p>",html_escape(&e.to_string())).unwrap(); }
+    }
+    out.push_str("</body>\n</html>\n");
+    Ok(out)
+}
+
+fn write_result(result:&RaireResult,sample_sizes:Option<&SampleSizeEstimate>,candidate_names:&[String],timeout:&mut TimeOut,out:&mut String) -> Result<(),RaireError> {
+    let name = |c|html_escape(&candidate_name(candidate_names,c));
+    out.push_str("<h2>Summary</h2>\n<table>\n");
+    let mut row = |label:&str,value:String| { writeln!(out,"<tr><th>{}</th><td>{}</td></tr>",label,value).unwrap(); };
+    row("Winner",name(result.winner));
+    row("Candidates",(0..result.num_candidates).map(|c|name(CandidateIndex(c))).collect::<Vec<_>>().join(", "));
+    row("Number of assertions",result.assertions.len().to_string());
+    row("Overall difficulty",html_escape(&result.difficulty_with_units().to_string()));
+    row("Smallest margin",result.margin.0.to_string());
+    if let Some(sample_sizes) = sample_sizes { row("Estimated initial sample size",format!("{} ballots at risk limit {}",sample_sizes.overall,sample_sizes.risk_limit)); }
+    row("Time taken",format!("{:.3} seconds",result.time_to_determine_winners.seconds+result.time_to_find_assertions.seconds+result.time_to_trim_assertions.seconds));
+    out.push_str("</table>\n");
+    if result.warning_trim_timed_out { out.push_str("<p class=\"error\">Trimming timed out, so some assertions may be unnecessary.</p>\n"); }
+    if !result.tie_breaks.is_empty() { out.push_str("<p class=\"error\">The winner was determined using a tie resolution rule, so an audit is unlikely to be possible.</p>\n"); }
+
+    writeln!(out,"<h2>Assertions</h2>\n<table>\n<tr><th>#</th><th>Type</th><th>Assertion</th><th>Margin</th><th>Difficulty{}</th>{}<th>Explanation</th></tr>",
+             if result.difficulty_units.is_unknown() { String::new() } else { format!(" ({})",html_escape(&result.difficulty_units.description())) },
+             if sample_sizes.is_some() { "<th>Estimated sample size</th>" } else { "" }).unwrap();
+    let mut by_difficulty : Vec<usize> = (0..result.assertions.len()).collect();
+    by_difficulty.sort_by(|&a,&b|result.assertions[b].difficulty.total_cmp(&result.assertions[a].difficulty));
+    for i in by_difficulty {
+        let a = &result.assertions[i];
+        let sample_size = sample_sizes.and_then(|s|s.assertions.get(i)).map(|n|format!("<td class=\"number\">{}</td>",n)).unwrap_or_default();
+        writeln!(out,"<tr><td class=\"number\">{}</td><td>{}</td><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td>{}<td>{}</td></tr>",
+                 i+1,if a.assertion.is_neb() {"NEB"} else {"NEN"},html_escape(&a.assertion.describe(candidate_names)),a.margin.0,a.difficulty,sample_size,html_escape(&explain_assertion(a,candidate_names))).unwrap();
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Elimination orders ruled out</h2>\n<p>For each candidate other than the winner, the tree of ways the count could end with them winning. Each level down is the candidate eliminated just before; the numbers are the assertions ruling out that ending.</p>\n");
+    let all_assertions : Vec<Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+    for tree in result.elimination_trees(timeout)? {
+        writeln!(out,"<details>\n<summary>{} {}</summary>\n<ul class=\"tree\">",name(tree.candidate_being_eliminated_at_this_node),if tree.valid {"is NOT ruled out by the assertions"} else {"is ruled out by the assertions"}).unwrap();
+        write_tree_node(&tree,&all_assertions,candidate_names,out);
+        out.push_str("</ul>\n");
+        out.push_str(&tree.to_svg(&all_assertions,candidate_names));
+        out.push_str("</details>\n");
+    }
+
+    if let Some(stats) = &result.trimming_statistics {
+        out.push_str("<h2>Trimming</h2>\n<table>\n");
+        writeln!(out,"<tr><th>Algorithm</th><td>{:?}, {:?}</td></tr>",stats.trim_algorithm,stats.trim_selection_algorithm).unwrap();
+        writeln!(out,"<tr><th>Assertions before trimming</th><td class=\"number\">{}</td></tr>",stats.assertions_before).unwrap();
+        writeln!(out,"<tr><th>Assertions after trimming</th><td class=\"number\">{}</td></tr>",stats.assertions_after).unwrap();
+        writeln!(out,"<tr><th>Time taken</th><td>{:.3} seconds</td></tr>",stats.time_taken.seconds).unwrap();
+        for size in &stats.tree_nodes {
+            writeln!(out,"<tr><th>Tree nodes for {}</th><td class=\"number\">{}</td></tr>",name(size.candidate),size.nodes).unwrap();
+        }
+        out.push_str("</table>\n");
+    }
+    Ok(())
+}
+
+fn write_tree_node(node:&TreeNodeShowingWhatAssertionsPrunedIt,all_assertions:&[Assertion],candidate_names:&[String],out:&mut String) {
+    let class = if node.valid { "valid" } else if node.pruning_assertions.is_empty() { "expanded" } else { "pruned" };
+    let mut label = html_escape(&candidate_name(candidate_names,node.candidate_being_eliminated_at_this_node));
+    for &a in &node.pruning_assertions {
+        write!(label," &mdash; ruled out by #{} <i>{}</i>",a+1,html_escape(&all_assertions[a].describe(candidate_names))).unwrap();
+    }
+    if node.children.is_empty() {
+        writeln!(out,"<li class=\"{}\">{}</li>",class,label).unwrap();
+    } else {
+        writeln!(out,"<li class=\"{}\"><details><summary>{}</summary>\n<ul class=\"tree\">",class,label).unwrap();
+        for child in &node.children { write_tree_node(child,all_assertions,candidate_names,out); }
+        out.push_str("</ul></details></li>\n");
+    }
+}
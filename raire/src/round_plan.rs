@@ -0,0 +1,120 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+//! Planning the rounds of a ballot comparison audit of a set of assertions, as in [Arlo](https://github.com/votingworks/arlo).
+//!
+//! An audit proceeds in rounds. In each round more ballots are drawn and examined; if all assertions are then confirmed the audit
+//! stops, otherwise it escalates to another, larger, round. The first round is sized so that, by [simulation](crate::simulate),
+//! the audit has a given probability of finishing in it. Each later round grows the cumulative sample by an escalation factor,
+//! until the last round, which is a full hand count.
+
+use serde::Deserialize;
+use serde::Serialize;
+use crate::raire_algorithm::RaireResult;
+use crate::simulate::{diluted_margins, simulate_stopping_times, SimulationParameters};
+
+/// Parameters for [plan_rounds].
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct RoundPlanParameters {
+    /// How the audit is simulated to size the first round, including the risk limit and the number of auditable ballots.
+    pub simulation : SimulationParameters,
+    /// The desired probability that the audit finishes in the first round. Arlo uses 0.9 by default.
+    #[serde(default="default_first_round_completion_probability")]
+    pub first_round_completion_probability : f64,
+    /// The factor by which the cumulative sample size grows from one round to the next.
+    #[serde(default="default_escalation_factor")]
+    pub escalation_factor : f64,
+}
+
+fn default_first_round_completion_probability() -> f64 { 0.9 }
+fn default_escalation_factor() -> f64 { 2.0 }
+
+/// The sample needed to confirm one assertion on its own.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct AssertionPlan {
+    /// The index of the assertion in the RAIRE result.
+    pub assertion : usize,
+    /// The mean, over simulated audits, of the number of ballots needed to confirm this assertion.
+    pub expected_sample_size : f64,
+    /// The number of ballots needed to confirm this assertion with the first round completion probability.
+    pub first_round_sample_size : usize,
+}
+
+/// One round of an audit.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct PlannedRound {
+    /// The round number, starting at 1.
+    pub round_num : usize,
+    /// The number of ballots drawn in this round, which is what Arlo calls the round's sample size.
+    pub sample_size : usize,
+    /// The total number of ballots drawn in this and earlier rounds.
+    pub cumulative_sample_size : usize,
+    /// The simulated probability that the audit has finished by the end of this round.
+    pub completion_probability : f64,
+    /// True if this round examines every auditable ballot.
+    pub full_hand_count : bool,
+}
+
+/// A recommended schedule of rounds for an audit.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct RoundPlan {
+    pub risk_limit : f64,
+    pub total_auditable_ballots : usize,
+    pub escalation_factor : f64,
+    pub assertions : Vec<AssertionPlan>,
+    pub rounds : Vec<PlannedRound>,
+}
+
+/// The q quantile (0 ≤ q ≤ 1) of some sorted values, rounding up so that at least a fraction q of values are no larger.
+fn quantile(sorted:&[usize],q:f64) -> usize {
+    if sorted.is_empty() { return 0; }
+    let index = ((sorted.len() as f64*q.clamp(0.0,1.0)).ceil() as usize).clamp(1,sorted.len())-1;
+    sorted[index]
+}
+
+/// Produce a recommended round schedule for a ballot comparison audit of the assertions in a RAIRE result.
+pub fn plan_rounds(result:&RaireResult,params:&RoundPlanParameters) -> RoundPlan {
+    let total = params.simulation.test.total_auditable_ballots.0;
+    let stopping_times = simulate_stopping_times(&diluted_margins(result,total),&params.simulation);
+    let sorted = |values:Vec<usize>| { let mut values=values; values.sort_unstable(); values };
+    let assertions = (0..result.assertions.len()).map(|assertion|{
+        let times = sorted(stopping_times.iter().map(|trial|trial[assertion]).collect());
+        AssertionPlan{
+            assertion,
+            expected_sample_size: if times.is_empty() { 0.0 } else { times.iter().sum::<usize>() as f64/times.len() as f64 },
+            first_round_sample_size: quantile(&times,params.first_round_completion_probability),
+        }
+    }).collect();
+    let audit_sizes = sorted(stopping_times.iter().map(|trial|trial.iter().copied().max().unwrap_or(0)).collect());
+    let completion = |cumulative:usize| if audit_sizes.is_empty() { 1.0 } else { audit_sizes.iter().filter(|&&n|n<=cumulative).count() as f64/audit_sizes.len() as f64 };
+    let mut rounds : Vec<PlannedRound> = vec![];
+    let mut cumulative = quantile(&audit_sizes,params.first_round_completion_probability).clamp(1,total.max(1));
+    loop {
+        let previous = rounds.last().map(|r|r.cumulative_sample_size).unwrap_or(0);
+        let full_hand_count = cumulative>=total;
+        rounds.push(PlannedRound{
+            round_num: rounds.len()+1,
+            sample_size: cumulative-previous,
+            cumulative_sample_size: cumulative,
+            completion_probability: if full_hand_count { 1.0 } else { completion(cumulative) },
+            full_hand_count,
+        });
+        if full_hand_count { break; }
+        cumulative = ((cumulative as f64*params.escalation_factor.max(1.0)).ceil() as usize).max(cumulative+1).min(total);
+    }
+    RoundPlan{
+        risk_limit: params.simulation.test.confidence,
+        total_auditable_ballots: total,
+        escalation_factor: params.escalation_factor,
+        assertions,
+        rounds,
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The round by round count of an IRV election, as election authorities publish it, with each round annotated with the
+//! assertions about it, so that audit boards can connect the assertions to the official round summary.
+//!
+//! An assertion is about a round if
+//! * it is a NEN assertion whose continuing candidates are exactly those continuing in the round, confirming that its winner is not
+//!   the candidate eliminated in that round, or
+//! * it is a NEB assertion whose loser is eliminated in the round while its winner continues.
+//!
+//! Each NEB assertion is about exactly one round. NEN assertions about elimination orders other than the one in the count are about none.
+
+use serde::{Deserialize, Serialize};
+use crate::assertions::Assertion;
+use crate::irv::{BallotPaperCount, CandidateIndex, IRVResult};
+
+/// A candidate's tally in a round.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct RoundTally {
+    pub candidate : CandidateIndex,
+    pub tally : BallotPaperCount,
+    /// The votes transferred to the candidate from those eliminated at the end of the previous round. Zero in the first round.
+    pub transferred : BallotPaperCount,
+}
+
+/// A round of the count.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct AnnotatedRound {
+    /// The tallies of the continuing candidates.
+    pub tallies : Vec<RoundTally>,
+    /// The votes not counting for any continuing candidate.
+    pub exhausted : BallotPaperCount,
+    /// The votes that became exhausted when the candidates eliminated at the end of the previous round were eliminated. Zero in the first round.
+    pub newly_exhausted : BallotPaperCount,
+    /// The candidates eliminated at the end of the round.
+    pub eliminated : Vec<CandidateIndex>,
+    /// The indices of the assertions about this round.
+    pub assertions : Vec<usize>,
+}
+
+/// The rounds of the count, annotated with the assertions about each.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct RoundByRound {
+    pub rounds : Vec<AnnotatedRound>,
+    /// The candidate left after the last round.
+    pub winner : CandidateIndex,
+    /// The indices of the assertions not about any round.
+    pub other_assertions : Vec<usize>,
+}
+
+impl RoundByRound {
+    /// Annotate the rounds of a count with the assertions about them.
+    pub fn new(irv:&IRVResult,assertions:&[Assertion]) -> Self {
+        let mut rounds : Vec<AnnotatedRound> = vec![];
+        let mut about_a_round = vec![false;assertions.len()];
+        for round in &irv.rounds {
+            let previous = rounds.last();
+            let tallies = round.continuing.iter().zip(&round.tallies).map(|(&candidate,&tally)|{
+                let transferred = previous.and_then(|p|p.tallies.iter().find(|t|t.candidate==candidate)).map_or(BallotPaperCount(0),|t|tally-t.tally);
+                RoundTally{ candidate, tally, transferred }
+            }).collect();
+            let newly_exhausted = previous.map_or(BallotPaperCount(0),|p|round.exhausted-p.exhausted);
+            let is_about = |assertion:&Assertion| match assertion {
+                Assertion::NEB(neb) => round.eliminated.contains(&neb.loser) && round.continuing.contains(&neb.winner) && !round.eliminated.contains(&neb.winner),
+                Assertion::NEN(nen) => *nen.continuing==*round.continuing,
+            };
+            let about : Vec<usize> = (0..assertions.len()).filter(|&i|is_about(&assertions[i])).collect();
+            for &i in &about { about_a_round[i]=true; }
+            rounds.push(AnnotatedRound{ tallies, exhausted: round.exhausted, newly_exhausted, eliminated: round.eliminated.clone(), assertions: about });
+        }
+        let winner = irv.elimination_order.last().cloned().unwrap_or(CandidateIndex(0));
+        let other_assertions = (0..assertions.len()).filter(|&i|!about_a_round[i]).collect();
+        RoundByRound{ rounds, winner, other_assertions }
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Estimates of the initial sample size needed to confirm the assertions of a solution at a given risk limit, so that the
+//! solution carries what an audit board needs to start the audit. Included in a [crate::RaireSolution] if the problem has a `risk_limit`.
+//!
+//! Unlike [crate::simulate], the estimates are deterministic. For a ballot comparison audit (MACRO, ALPHA or OneOnMargin) each
+//! assertion is tested with an [AlphaMartingale] on a sample in which discrepancies occur at the assumed [ErrorRates], evenly
+//! spread through the sample, as in SHANGRLA's initial sample size estimates. For a ballot polling audit (BRAVO or OneOnMarginSq)
+//! the estimate is the BRAVO average sample number, assuming every ballot is for the winner or loser of the assertion; error rates
+//! do not apply. Stratified audits are not estimated.
+
+use serde::{Deserialize, Serialize};
+use crate::audit_type::{AlphaMartingale, Audit, AuditType, BallotComparisonALPHA, Difficulty, DifficultyUnits};
+use crate::irv::BallotPaperCount;
+use crate::raire_algorithm::RaireResult;
+use crate::RaireError;
+use crate::simulate::ErrorRates;
+
+/// The estimated number of ballots that need to be sampled to confirm the assertions at a risk limit.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct SampleSizeEstimate {
+    pub risk_limit : f64,
+    /// The discrepancy rates assumed for a ballot comparison audit.
+    #[serde(default)]
+    pub error_rates : ErrorRates,
+    /// The estimate for each assertion, in the same order as the assertions in the result. An assertion that is not expected to be
+    /// confirmed before every ballot is sampled has the number of auditable ballots, that is, a full hand count.
+    pub assertions : Vec<usize>,
+    /// The estimate for the whole audit, the largest for any assertion, as all assertions are tested on the same sample.
+    pub overall : usize,
+}
+
+impl SampleSizeEstimate {
+    /// Estimate the sample sizes for the assertions in a result, or None if the audit is stratified.
+    pub fn new(result:&RaireResult,audit:&Audit,risk_limit:f64,error_rates:ErrorRates) -> Option<Self> {
+        let total = audit.total_auditable_ballots()?.0;
+        let phantoms = result.phantoms.unwrap_or(BallotPaperCount(0));
+        let diluted_margins = result.assertions.iter().map(|a|a.margin.0.saturating_sub(phantoms.0) as f64/total.max(1) as f64);
+        let assertions : Vec<usize> = match audit {
+            Audit::MACRO(_) | Audit::OneOnMargin(_) => {
+                let test = BallotComparisonALPHA{ confidence: risk_limit, total_auditable_ballots: BallotPaperCount(total), d: BallotComparisonALPHA::default_d(), eta0: None };
+                diluted_margins.map(|v|comparison_sample_size(&test,v,&error_rates)).collect()
+            }
+            Audit::ALPHA(alpha) => {
+                let test = BallotComparisonALPHA{ confidence: risk_limit, ..*alpha };
+                diluted_margins.map(|v|comparison_sample_size(&test,v,&error_rates)).collect()
+            }
+            Audit::BRAVO(_) | Audit::OneOnMarginSq(_) => diluted_margins.map(|v|{
+                let size = Difficulty::new(1.0/(v*v),DifficultyUnits::OneOnDilutedMarginSquared).expected_sample_size(risk_limit).unwrap_or(f64::INFINITY);
+                if size.is_finite() && size>=0.0 { (size.ceil() as usize).min(total) } else { total }
+            }).collect(),
+            Audit::Stratified(_) => return None,
+        };
+        let overall = assertions.iter().copied().max().unwrap_or(0);
+        Some(SampleSizeEstimate{ risk_limit, error_rates, assertions, overall })
+    }
+}
+
+/// Check a risk limit is strictly between 0 and 1, and the error rates are probabilities adding to at most 1.
+pub fn check_parameters(risk_limit:f64,error_rates:&ErrorRates) -> Result<(),RaireError> {
+    if !(risk_limit>0.0 && risk_limit<1.0) { return Err(RaireError::InvalidRiskLimit); }
+    let rates = [error_rates.one_vote_overstatement,error_rates.two_vote_overstatement,error_rates.one_vote_understatement,error_rates.two_vote_understatement];
+    if !(rates.iter().all(|r|(0.0..=1.0).contains(r)) && rates.iter().sum::<f64>()<=1.0) { return Err(RaireError::InvalidErrorRates); }
+    Ok(())
+}
+
+/// The number of ballots sampled before an ALPHA test of an assertion with the given diluted margin is confirmed, when discrepancies
+/// occur at the given rates, evenly spread. The number of auditable ballots if it is never confirmed.
+fn comparison_sample_size(test:&BallotComparisonALPHA,diluted_margin:f64,error_rates:&ErrorRates) -> usize {
+    let total = test.total_auditable_ballots.0;
+    if diluted_margin<=0.0 { return total; }
+    let discrepancies = [(error_rates.two_vote_overstatement,2),(error_rates.one_vote_overstatement,1),(error_rates.one_vote_understatement,-1),(error_rates.two_vote_understatement,-2)];
+    let mut seen = [0;4];
+    let mut martingale = AlphaMartingale::new(test,diluted_margin);
+    for sampled in 1..=total {
+        // the first kind of discrepancy of which fewer than expected have been seen so far, if any.
+        let overstatement = match (0..discrepancies.len()).find(|&i|(sampled as f64*discrepancies[i].0).floor() as usize>seen[i]) {
+            Some(i) => { seen[i]+=1; discrepancies[i].1 }
+            None => 0,
+        };
+        if martingale.observe(martingale.assorter_value(overstatement)) { return sampled; }
+    }
+    total
+}
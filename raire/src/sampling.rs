@@ -0,0 +1,76 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Choosing the ballots to sample, reproducibly from a public random seed, such as the result of rolling dice in public,
+//! so that anyone can check the sample was not chosen to avoid particular ballots.
+//!
+//! This uses the SHA-256 counter pseudo-random number generator of Rivest's `sampler` program, as used by Arlo and SHANGRLA:
+//! the `k`th ballot drawn (counting from 1) is ballot number `SHA256(seed+","+k) mod N + 1`, where the hash is read as a big
+//! endian integer and `N` is the number of ballots in the ballot manifest. Ballots are drawn with replacement, so a ballot may be
+//! drawn more than once, and counts once in the sample each time. A later round continues the sequence of draws from where the
+//! previous round stopped, so the samples of all rounds together are the same as if drawn in one go.
+//!
+//! Ballot numbers are positions in a [BallotManifest], which says which batch (box, tray or scanner batch) each is in.
+//! See [crate::ballot_manifest].
+
+use serde::{Deserialize, Serialize};
+use crate::ballot_manifest::{BallotLocation, BallotManifest};
+use crate::manifest::sha256;
+use crate::RaireError;
+
+/// The ballot number, 1 to `num_ballots`, drawn at draw number `draw` (counting from 1) with the given seed.
+pub fn draw_ballot(seed:&str,draw:usize,num_ballots:usize) -> usize {
+    let hash = sha256(format!("{},{}",seed,draw).as_bytes());
+    let n = num_ballots as u128;
+    hash.iter().fold(0u128,|remainder,&byte|(remainder*256+byte as u128)%n) as usize+1
+}
+
+/// One ballot drawn.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct SampledBallot {
+    /// The draw number, counting from 1 across all rounds.
+    pub draw : usize,
+    /// The ballot number in the manifest, counting from 1.
+    pub ballot : usize,
+    pub location : BallotLocation,
+    /// Whether this ballot was drawn at an earlier draw, in this round or an earlier one, so it does not need to be retrieved again.
+    pub repeat : bool,
+}
+
+/// The ballots drawn in a round of an audit.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct SampleRound {
+    pub seed : String,
+    /// The draws in this round, in draw order.
+    pub draws : Vec<SampledBallot>,
+}
+
+impl SampleRound {
+    /// Draw `sample_size` ballots from the manifest, continuing after the first `previous_draws` draws made in earlier rounds.
+    pub fn new(seed:&str,manifest:&BallotManifest,previous_draws:usize,sample_size:usize) -> Result<Self,RaireError> {
+        manifest.check()?;
+        let total = manifest.total_ballots();
+        let mut drawn = std::collections::HashSet::new();
+        for draw in 1..=previous_draws { drawn.insert(draw_ballot(seed,draw,total)); }
+        let draws = (previous_draws+1..=previous_draws+sample_size).map(|draw|{
+            let ballot = draw_ballot(seed,draw,total);
+            let repeat = !drawn.insert(ballot);
+            SampledBallot{ draw, ballot, location: manifest.locate(ballot).unwrap(), repeat } // ballot is at most total, so is in the manifest.
+        }).collect();
+        Ok(SampleRound{ seed: seed.to_string(), draws })
+    }
+
+    /// The ballots to retrieve in this round, each once, in manifest order.
+    pub fn pull_list(&self) -> Vec<&SampledBallot> {
+        let mut pull : Vec<&SampledBallot> = self.draws.iter().filter(|d|!d.repeat).collect();
+        pull.sort_by_key(|d|d.ballot);
+        pull
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Versioning and a [JSON Schema](https://json-schema.org/) for the [RaireProblem](crate::RaireProblem) and
+//! [RaireSolution](crate::RaireSolution) JSON formats, which are used by other programs.
+//!
+//! Both have a `format_version` field. Files without one are from before versioning (version 0).
+//! Any version up to [FORMAT_VERSION] is accepted when reading, and upgraded to the current version;
+//! a newer version is rejected rather than misinterpreted. Any change that would stop an existing
+//! consumer from reading the output will increase [FORMAT_VERSION], and add the upgrade here.
+//!
+//! Version history:
+//! * 0 : no `format_version` field.
+//! * 1 : added `format_version`. Otherwise the same as version 0.
+//! * 2 : the errors `InternalErrorRuledOutWinner` and `InternalErrorDidntRuleOutLoser` are objects saying which candidate, elimination
+//!   order suffix and (for the former) assertions are involved, rather than just the error's name. Older ones are upgraded to objects
+//!   without `winner` or `candidate` and with empty lists.
+
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Value};
+use crate::RaireError;
+
+/// The current version of the problem and solution JSON formats.
+pub const FORMAT_VERSION : u32 = 2;
+
+/// The format version of a file with no `format_version` field.
+const UNVERSIONED : u32 = 0;
+
+/// The format version of a problem or solution once read, as it is upgraded to the current version.
+pub(crate) fn current_format_version() -> u32 { FORMAT_VERSION }
+
+/// Read a format_version, rejecting versions newer than this library understands.
+/// Older versions need no conversion other than defaults for missing fields and [upgrade_error], so are just upgraded.
+pub(crate) fn deserialize_format_version<'de,D:Deserializer<'de>>(deserializer:D) -> Result<u32,D::Error> {
+    let version = u64::deserialize(deserializer)?;
+    if version>FORMAT_VERSION as u64 { Err(serde::de::Error::custom(RaireError::UnsupportedFormatVersion(version))) }
+    else { Ok(FORMAT_VERSION) }
+}
+
+/// Upgrade the JSON form of a problem or solution from an older format version to [FORMAT_VERSION],
+/// for programs that process the JSON without converting it to a [RaireProblem](crate::RaireProblem) or [RaireSolution](crate::RaireSolution).
+pub fn upgrade(mut value:Value) -> Result<Value,RaireError> {
+    let version = value.get("format_version").and_then(|v|v.as_u64()).unwrap_or(UNVERSIONED as u64);
+    if version>FORMAT_VERSION as u64 { return Err(RaireError::UnsupportedFormatVersion(version)); }
+    // version 1 to 2 : errors that became objects.
+    if let Some(error) = value.get_mut("solution").and_then(|s|s.get_mut("Err")) { upgrade_error(error); }
+    // version 0 to 1 : just add the version.
+    if let Some(object) = value.as_object_mut() { object.insert("format_version".to_string(),json!(FORMAT_VERSION)); }
+    Ok(value)
+}
+
+/// Upgrade the JSON form of a [RaireError] from before version 2, when `InternalErrorRuledOutWinner` and
+/// `InternalErrorDidntRuleOutLoser` were just their names, to an object with no information about them.
+pub(crate) fn upgrade_error(error:&mut Value) {
+    if let Some(name@("InternalErrorRuledOutWinner"|"InternalErrorDidntRuleOutLoser")) = error.as_str() {
+        *error = json!({name:{}});
+    }
+}
+
+/// Definitions shared by the problem and solution schemas.
+fn definitions() -> Value {
+    let candidate = json!({"type":"integer","minimum":0,"description":"a candidate index, 0 to num_candidates-1"});
+    let candidates = json!({"type":"array","items":{"$ref":"#/$defs/candidate"}});
+    let time_taken = json!({"type":"object","properties":{"work":{"type":"integer","minimum":0},"seconds":{"type":"number"}},"required":["work","seconds"]});
+    json!({
+        "candidate": candidate,
+        "candidates": candidates,
+        "time_taken": time_taken,
+        "error_rates": {"type":"object","properties":{
+            "one_vote_overstatement":{"type":"number","minimum":0,"maximum":1},
+            "two_vote_overstatement":{"type":"number","minimum":0,"maximum":1},
+            "one_vote_understatement":{"type":"number","minimum":0,"maximum":1},
+            "two_vote_understatement":{"type":"number","minimum":0,"maximum":1},
+        }},
+        "audit": {
+            "type":"object",
+            "description":"the type of audit, which determines how difficulty is measured",
+            "properties":{
+                "type":{"enum":["BRAVO","MACRO","ALPHA","OneOnMargin","OneOnMarginSq","Margin","MarginSq","Stratified"]},
+                "total_auditable_ballots":{"type":"integer","minimum":0},
+                "confidence":{"type":"number"},
+                "error_inflation_factor":{"type":"number"},
+                "d":{"type":"number"},
+                "eta0":{"type":"number"},
+                "strata":{"type":"array","items":{"type":"object","properties":{"name":{"type":"string"},"audit":{"$ref":"#/$defs/audit"}},"required":["name","audit"]},"description":"only for Stratified"},
+                "allocation_steps":{"type":"integer","minimum":1,"maximum":100,"description":"only for Stratified"},
+            },
+            "required":["type"],
+            "if":{"properties":{"type":{"const":"Stratified"}}},
+            "then":{"required":["strata"]},
+            "else":{"required":["total_auditable_ballots"]},
+        },
+        "trim_algorithm": {"enum":["None","MinimizeTree","MinimizeAssertions"]},
+        "trim_selection_algorithm": {"enum":["TwoPassHeuristic","GreedyCoverage","Optimal"]},
+        "assertion": {
+            "type":"object",
+            "properties":{
+                "type":{"enum":["NEB","NEN"]},
+                "winner":{"$ref":"#/$defs/candidate"},
+                "loser":{"$ref":"#/$defs/candidate"},
+                "continuing":{"$ref":"#/$defs/candidates","description":"only for NEN"},
+            },
+            "required":["type","winner","loser"],
+        },
+    })
+}
+
+/// A JSON Schema for the [RaireProblem](crate::RaireProblem) format.
+pub fn problem_schema() -> Value {
+    json!({
+        "$schema":"https://json-schema.org/draft/2020-12/schema",
+        "title":"RaireProblem",
+        "description":"An IRV contest for which RAIRE should find assertions. See the README for details of each field.",
+        "type":"object",
+        "properties":{
+            "format_version":{"type":"integer","minimum":0,"maximum":FORMAT_VERSION,"description":"version of this format; absent means 0"},
+            "metadata":{"description":"anything, copied to the solution. The fields candidates and contest are used for display."},
+            "num_candidates":{"type":"integer","minimum":0},
+            "votes":{"type":"array","items":{"type":"object","properties":{"n":{"type":"integer","minimum":0},"prefs":{"$ref":"#/$defs/candidates"}},"required":["n","prefs"]}},
+            "winner":{"$ref":"#/$defs/candidate"},
+            "audit":{"$ref":"#/$defs/audit"},
+            "seats":{"type":"integer","minimum":1},
+            "elimination_rule":{"enum":["OneAtATime","BatchElimination"]},
+            "tie_resolution":{"oneOf":[{"enum":["Ambiguous","Lexicographic","EarlierRoundTallies"]},{"type":"object","properties":{"Order":{"$ref":"#/$defs/candidates"}},"required":["Order"]}]},
+            "reported_elimination_order":{"$ref":"#/$defs/candidates"},
+            "trim_algorithm":{"$ref":"#/$defs/trim_algorithm"},
+            "trim_selection_algorithm":{"$ref":"#/$defs/trim_selection_algorithm"},
+            "cross_check_trimming":{"type":"boolean"},
+            "max_tree_nodes":{"type":"integer","minimum":0},
+            "extended_trim":{"type":"object","properties":{
+                "depth":{"oneOf":[{"const":"Unlimited"},{"type":"object","properties":{"Levels":{"type":"integer","minimum":0}},"required":["Levels"]}]},
+                "max_nodes":{"type":"integer","minimum":0},
+            },"required":["depth"]},
+            "difficulty_estimate":{"type":"number"},
+            "time_limit_seconds":{"type":"number","exclusiveMinimum":0},
+            "max_memory_mb":{"type":"integer","minimum":0},
+            "partial_on_timeout":{"type":"boolean"},
+            "winner_only_fallback":{"type":"boolean"},
+            "two_phase":{"type":"boolean"},
+            "stop_when_difficulty_at_most":{"type":"number"},
+            "objective":{"oneOf":[
+                {"const":"MinimizeMaximumDifficulty"},
+                {"type":"object","properties":{"MinimizeTotalSampleSize":{"type":"object","properties":{"overlap":{"type":"number"}},"required":["overlap"]}},"required":["MinimizeTotalSampleSize"]},
+                {"type":"object","properties":{"Weighted":{"type":"object","properties":{"maximum_weight":{"type":"number"},"total_weight":{"type":"number"},"overlap":{"type":"number"}},"required":["maximum_weight","total_weight","overlap"]}},"required":["Weighted"]},
+            ]},
+            "deterministic":{"type":"boolean"},
+            "include_tallies":{"type":"boolean"},
+            "difficulty_decimal_places":{"type":"integer","minimum":0},
+            "exact_arithmetic":{"type":"boolean"},
+            "assertion_types":{"enum":["Both","NEBOnly","NENOnly"]},
+            "warm_start_assertions":{"type":"array","items":{"$ref":"#/$defs/assertion"}},
+            "risk_limit":{"type":"number","exclusiveMinimum":0,"exclusiveMaximum":1},
+            "error_rates":{"$ref":"#/$defs/error_rates"},
+            "ranked_votes":{"type":"array","items":{"type":"object","properties":{"n":{"type":"integer","minimum":0},"ranks":{"type":"array","items":{"$ref":"#/$defs/candidates"}}},"required":["n","ranks"]}},
+            "ballot_interpretation":{"type":"object","properties":{
+                "overvote":{"enum":["Exhaust","SkipRank"]},
+                "duplicate":{"enum":["Ignore","Exhaust"]},
+                "max_consecutive_skipped_ranks":{"type":"integer","minimum":0},
+            }},
+            "vote_strata":{"type":"array","items":{"type":"integer","minimum":0},"description":"for a Stratified audit, the index of the stratum of each vote, votes then ranked_votes"},
+            "adjust_for_phantoms":{"type":"boolean"},
+        },
+        "required":["metadata","num_candidates","votes","audit"],
+        "$defs":definitions(),
+    })
+}
+
+/// A JSON Schema for the [RaireSolution](crate::RaireSolution) format.
+pub fn solution_schema() -> Value {
+    let result = json!({
+        "type":"object",
+        "properties":{
+            "assertions":{"type":"array","items":{"type":"object","properties":{
+                "assertion":{"$ref":"#/$defs/assertion"},
+                "difficulty":{"type":"number"},
+                "margin":{"type":"integer","minimum":0},
+                "tallies":{"type":"object","properties":{
+                    "continuing":{"$ref":"#/$defs/candidates"},
+                    "winner_tally":{"type":"integer","minimum":0},
+                    "loser_tally":{"type":"integer","minimum":0},
+                    "total_auditable_ballots":{"type":"integer","minimum":0},
+                },"required":["continuing","winner_tally","loser_tally"]},
+            },"required":["assertion","difficulty","margin"]}},
+            "difficulty":{"type":"number"},
+            "margin":{"type":"integer","minimum":0},
+            "winner":{"$ref":"#/$defs/candidate"},
+            "num_candidates":{"type":"integer","minimum":0},
+            "time_to_determine_winners":{"$ref":"#/$defs/time_taken"},
+            "time_to_find_assertions":{"$ref":"#/$defs/time_taken"},
+            "time_to_trim_assertions":{"$ref":"#/$defs/time_taken"},
+            "warning_trim_timed_out":{"type":"boolean"},
+            "trimming_statistics":{"type":"object","properties":{
+                "assertions_before":{"type":"integer","minimum":0},
+                "assertions_after":{"type":"integer","minimum":0},
+                "tree_nodes":{"type":"array","items":{"type":"object","properties":{"candidate":{"$ref":"#/$defs/candidate"},"nodes":{"type":"integer","minimum":0}},"required":["candidate","nodes"]}},
+                "time_taken":{"$ref":"#/$defs/time_taken"},
+                "trim_algorithm":{"$ref":"#/$defs/trim_algorithm"},
+                "trim_selection_algorithm":{"$ref":"#/$defs/trim_selection_algorithm"},
+                "extended_trim_within_budget":{"type":"boolean"},
+                "cross_check":{"type":"object","properties":{
+                    "heuristic_assertions":{"type":"integer","minimum":0},
+                    "optimal_assertions":{"type":"integer","minimum":0},
+                    "size_difference":{"type":"integer","minimum":0},
+                },"required":["heuristic_assertions","optimal_assertions","size_difference"]},
+            },"required":["assertions_before","assertions_after","tree_nodes","time_taken","trim_algorithm","trim_selection_algorithm"]},
+            "tie_breaks":{"type":"array","items":{"type":"object","properties":{"tied":{"$ref":"#/$defs/candidates"},"eliminated":{"$ref":"#/$defs/candidate"}},"required":["tied","eliminated"]}},
+            "partial":{"type":"boolean"},
+            "fallback":{"type":"boolean"},
+            "refinement_incomplete":{"type":"boolean"},
+            "margin_of_victory":{"type":"object","properties":{"lower_bound":{"type":"integer","minimum":0},"upper_bound":{"type":"integer","minimum":0}},"required":["lower_bound"]},
+            "unresolved_elimination_order_suffixes":{"type":"array","items":{"$ref":"#/$defs/candidates"}},
+            "phantoms":{"type":"integer","minimum":0},
+            "warm_start_difficulty":{"type":"number"},
+            "difficulty_units":{"description":"what the difficulties measure, which depends on the audit type","oneOf":[
+                {"enum":["OneOnDilutedMargin","OneOnDilutedMarginSquared","Unknown"]},
+                {"type":"object","properties":{"ExpectedSampleSize":{"type":"object","properties":{"risk_limit":{"type":"number"}},"required":["risk_limit"]}},"required":["ExpectedSampleSize"]},
+            ]},
+        },
+        "required":["assertions","difficulty","margin","winner","num_candidates","time_to_determine_winners","time_to_find_assertions","time_to_trim_assertions"],
+    });
+    let error = json!({
+        "description":"see enum RaireError. Either a string, for errors without data, or an object with a single field naming the error.",
+        "oneOf":[{"type":"string"},{"type":"object","minProperties":1,"maxProperties":1}],
+    });
+    json!({
+        "$schema":"https://json-schema.org/draft/2020-12/schema",
+        "title":"RaireSolution",
+        "description":"The assertions found by RAIRE, or why they could not be found. See the README for details of each field.",
+        "type":"object",
+        "properties":{
+            "format_version":{"type":"integer","minimum":0,"maximum":FORMAT_VERSION,"description":"version of this format; absent means 0"},
+            "metadata":{"description":"a copy of the problem's metadata"},
+            "solution":{"oneOf":[
+                {"type":"object","properties":{"Ok":result},"required":["Ok"],"additionalProperties":false},
+                {"type":"object","properties":{"Err":error},"required":["Err"],"additionalProperties":false},
+            ]},
+            "manifest":{"type":"object","properties":{
+                "raire_version":{"type":"string"},
+                "input_sha256":{"type":"string","pattern":"^[0-9a-f]{64}$"},
+                "options":{"type":"object"},
+                "output_sha256":{"type":"string","pattern":"^[0-9a-f]{64}$"},
+            },"required":["raire_version","input_sha256","options","output_sha256"]},
+            "sample_sizes":{"type":"object","properties":{
+                "risk_limit":{"type":"number"},
+                "error_rates":{"$ref":"#/$defs/error_rates"},
+                "assertions":{"type":"array","items":{"type":"integer","minimum":0}},
+                "overall":{"type":"integer","minimum":0},
+            },"required":["risk_limit","assertions","overall"]},
+            "tied_rounds":{"type":"array","items":{"type":"object","properties":{
+                "continuing":{"$ref":"#/$defs/candidates"},
+                "tallies":{"type":"array","items":{"type":"integer","minimum":0}},
+                "exhausted":{"type":"integer","minimum":0},
+                "eliminated":{"$ref":"#/$defs/candidates"},
+            },"required":["continuing","tallies","exhausted","eliminated"]}},
+        },
+        "required":["metadata","solution"],
+        "$defs":definitions(),
+    })
+}
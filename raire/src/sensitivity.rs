@@ -0,0 +1,82 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! How fragile each assertion is: how many ballots would have to be changed, in various ways, to bring its margin down to zero.
+//!
+//! An assertion that a few changed ballots would break is where an auditor should look hardest, for instance by inspecting or
+//! rescanning the ballots that count for its winner.
+
+use serde::{Deserialize, Serialize};
+use crate::assertions::Assertion;
+use crate::irv::{BallotPaperCount, Votes};
+
+/// A way a ballot could be changed, e.g. by a misread mark, that reduces an assertion's margin.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub enum BallotChange {
+    /// A ballot counting for the assertion's winner is changed to count for the loser, reducing the margin by two.
+    WinnerToLoser,
+    /// A ballot counting for the winner is changed to count for neither, e.g. by being exhausted, reducing the margin by one.
+    WinnerToNeither,
+    /// A ballot counting for neither is changed to count for the loser, reducing the margin by one.
+    NeitherToLoser,
+}
+
+impl BallotChange {
+    pub const ALL : [BallotChange;3] = [BallotChange::WinnerToLoser,BallotChange::WinnerToNeither,BallotChange::NeitherToLoser];
+
+    /// How much one such change reduces the margin.
+    pub fn margin_reduction(self) -> usize { if self==BallotChange::WinnerToLoser { 2 } else { 1 } }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct ChangesNeeded {
+    pub change : BallotChange,
+    /// The number of ballots changed this way needed to bring the margin to zero, or None if there are not enough ballots that could be changed this way.
+    pub ballots : Option<BallotPaperCount>,
+}
+
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub struct AssertionSensitivity {
+    /// The position of the assertion in the list analysed.
+    pub index : usize,
+    pub assertion : Assertion,
+    /// The margin with the given votes, zero if the assertion does not hold.
+    pub margin : BallotPaperCount,
+    /// The ballots counting for the assertion's winner, the ones most worth checking.
+    pub ballots_for_winner : BallotPaperCount,
+    pub ballots_for_loser : BallotPaperCount,
+    pub ballots_for_neither : BallotPaperCount,
+    /// For each of the requested kinds of change, how many would bring the margin to zero.
+    pub changes_needed : Vec<ChangesNeeded>,
+    /// The fewest changed ballots, of any of the requested kinds, that bring the margin to zero. Assertions are ranked by this.
+    pub fewest_changes : Option<BallotPaperCount>,
+}
+
+/// Analyse each assertion against the votes for the given kinds of change, returning them most fragile first.
+/// Assertions that no requested change can break come last; ties are in the original order.
+pub fn sensitivity(assertions:&[Assertion],votes:&Votes,changes:&[BallotChange]) -> Vec<AssertionSensitivity> {
+    let mut res : Vec<AssertionSensitivity> = assertions.iter().enumerate().map(|(index,assertion)|{
+        let mut counts = [0usize;3]; // for winner, loser, neither.
+        for vote in &votes.votes {
+            let slot = match assertion.vote_score(&vote.prefs) { 1 => 0, -1 => 1, _ => 2 };
+            counts[slot] = counts[slot].saturating_add(vote.n.0);
+        }
+        let margin = assertion.signed_margin(votes).clamp(0,usize::MAX as i128) as usize;
+        let changes_needed : Vec<ChangesNeeded> = changes.iter().map(|&change|{
+            let available = if change==BallotChange::NeitherToLoser { counts[2] } else { counts[0] };
+            let needed = margin.div_ceil(change.margin_reduction());
+            ChangesNeeded{ change, ballots: if needed<=available { Some(BallotPaperCount(needed)) } else { None } }
+        }).collect();
+        let fewest_changes = changes_needed.iter().filter_map(|c|c.ballots).min();
+        AssertionSensitivity{ index, assertion: assertion.clone(), margin: BallotPaperCount(margin), ballots_for_winner: BallotPaperCount(counts[0]), ballots_for_loser: BallotPaperCount(counts[1]), ballots_for_neither: BallotPaperCount(counts[2]), changes_needed, fewest_changes }
+    }).collect();
+    res.sort_by_key(|s|(s.fewest_changes.is_none(),s.fewest_changes.map(|b|b.0)));
+    res
+}
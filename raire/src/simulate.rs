@@ -0,0 +1,132 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Monte Carlo simulation of a ballot comparison audit of a set of assertions, to help budget an audit before it starts.
+//!
+//! Each simulated audit draws ballots with replacement, one at a time. Each drawn ballot has a discrepancy between its
+//! CVR and the paper ballot chosen at random with the given [ErrorRates], and the same discrepancy is applied to every
+//! assertion (a pessimistic simplification, as in practice a discrepancy rarely affects all assertions). Every assertion
+//! is tested with an [AlphaMartingale] on the same sample, and the audit stops when all are confirmed, or
+//! escalates to a full hand count when the sample reaches the number of auditable ballots.
+
+use serde::Deserialize;
+use serde::Serialize;
+use crate::audit_type::{AlphaMartingale, BallotComparisonALPHA};
+use crate::raire_algorithm::RaireResult;
+
+/// The assumed rates (probability per ballot sampled) of discrepancies between CVRs and paper ballots.
+#[derive(Clone,Copy,Debug,Default,Serialize,Deserialize)]
+pub struct ErrorRates {
+    #[serde(default)]
+    pub one_vote_overstatement : f64,
+    #[serde(default)]
+    pub two_vote_overstatement : f64,
+    #[serde(default)]
+    pub one_vote_understatement : f64,
+    #[serde(default)]
+    pub two_vote_understatement : f64,
+}
+
+impl ErrorRates {
+    /// Choose a discrepancy (an overstatement in votes, -2 to 2) given a uniform random number in [0,1).
+    fn discrepancy(&self,r:f64) -> i32 {
+        let mut cumulative = 0.0;
+        for (rate,overstatement) in [(self.two_vote_overstatement,2),(self.one_vote_overstatement,1),(self.one_vote_understatement,-1),(self.two_vote_understatement,-2)] {
+            cumulative+=rate;
+            if r<cumulative { return overstatement; }
+        }
+        0
+    }
+}
+
+/// Parameters for [simulate_audit].
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct SimulationParameters {
+    /// The test used for each assertion, including the risk limit (as `confidence`) and the number of auditable ballots.
+    pub test : BallotComparisonALPHA,
+    #[serde(default)]
+    pub error_rates : ErrorRates,
+    /// The number of simulated audits.
+    pub trials : usize,
+    /// Seed for the random number generator, so results are reproducible.
+    #[serde(default)]
+    pub seed : u64,
+}
+
+/// The distribution of the number of ballots sampled over the simulated audits.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct SimulationResult {
+    /// The total ballots sampled in each trial, sorted ascending.
+    pub sample_sizes : Vec<usize>,
+    pub mean : f64,
+    /// The fraction of trials that escalated to a full hand count.
+    pub full_hand_count_fraction : f64,
+}
+
+impl SimulationResult {
+    /// The q quantile (0 ≤ q ≤ 1) of the sample sizes, e.g. 0.5 for the median.
+    pub fn quantile(&self,q:f64) -> usize {
+        if self.sample_sizes.is_empty() { return 0; }
+        let index = ((self.sample_sizes.len()-1) as f64*q.clamp(0.0,1.0)).round() as usize;
+        self.sample_sizes[index]
+    }
+}
+
+/// A small, fast, deterministic pseudo random number generator (SplitMix64), adequate for simulation.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+    /// uniform in [0,1)
+    fn next_f64(&mut self) -> f64 { (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64 }
+}
+
+/// Simulate ballot comparison audits of assertions with the given diluted margins. For each trial, return the number of ballots
+/// sampled before each assertion was confirmed, or the total number of auditable ballots if it was not.
+pub(crate) fn simulate_stopping_times(margins:&[f64],params:&SimulationParameters) -> Vec<Vec<usize>> {
+    let total = params.test.total_auditable_ballots.0;
+    let mut rng = SplitMix64(params.seed);
+    let mut res = Vec::with_capacity(params.trials);
+    for _ in 0..params.trials {
+        let mut tests : Vec<AlphaMartingale> = margins.iter().map(|&v|AlphaMartingale::new(&params.test,v)).collect();
+        let mut sampled = 0;
+        while sampled<total && tests.iter().any(|t|!t.is_confirmed()) {
+            let discrepancy = params.error_rates.discrepancy(rng.next_f64());
+            for test in tests.iter_mut().filter(|t|!t.is_confirmed()) {
+                let x = test.assorter_value(discrepancy);
+                test.observe(x);
+            }
+            sampled+=1;
+        }
+        res.push(tests.iter().map(|t|if t.is_confirmed() { t.sample_size() } else { total }).collect());
+    }
+    res
+}
+
+pub(crate) fn diluted_margins(result:&RaireResult,total_auditable_ballots:usize) -> Vec<f64> {
+    result.assertions.iter().map(|a|a.margin.0 as f64/total_auditable_ballots.max(1) as f64).collect()
+}
+
+/// Simulate ballot comparison audits of the assertions in a RAIRE result.
+pub fn simulate_audit(result:&RaireResult,params:&SimulationParameters) -> SimulationResult {
+    let total = params.test.total_auditable_ballots.0;
+    let stopping_times = simulate_stopping_times(&diluted_margins(result,total),params);
+    let mut sample_sizes : Vec<usize> = stopping_times.iter().map(|trial|trial.iter().copied().max().unwrap_or(0)).collect();
+    let full_hand_counts = sample_sizes.iter().filter(|&&n|n>=total).count();
+    sample_sizes.sort_unstable();
+    let mean = if sample_sizes.is_empty() { 0.0 } else { sample_sizes.iter().sum::<usize>() as f64/sample_sizes.len() as f64 };
+    SimulationResult{ sample_sizes, mean, full_hand_count_fraction: if params.trials==0 { 0.0 } else { full_hand_counts as f64/params.trials as f64 } }
+}
@@ -0,0 +1,131 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Snapshots of the RAIRE search, so that a long computation that is stopped (by running out of time, being cancelled,
+//! or the computer being restarted) can be resumed from where it got to rather than starting again.
+//!
+//! The search is the part of the algorithm that can take days for a hard contest; determining the winner and trimming are
+//! comparatively quick, and are just redone. A snapshot contains the frontier of elimination order suffixes not yet ruled out,
+//! the assertions found so far, and the lower bound on the difficulty. It is taken every [SnapshotStore::interval_seconds]
+//! and when the search stops early, and given to a [SnapshotStore], such as a [SnapshotFile], set in [crate::raire_algorithm::RaireOptions::snapshots].
+//! The next search with the same store starts from the snapshot, if there is one.
+//!
+//! Snapshots are only taken by a search on a single thread, so a search with a snapshot store does not use several threads even
+//! with the `parallel` feature.
+
+use std::fmt::Debug;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::assertions::{AssertionAndDifficulty, EliminationOrderSuffix};
+use crate::audit_type::AssertionDifficulty;
+use crate::irv::CandidateIndex;
+use crate::RaireError;
+
+/// The version of the snapshot format. Snapshots with a different version cannot be resumed from.
+pub const SNAPSHOT_FORMAT_VERSION : u32 = 1;
+
+/// The default for [SnapshotStore::interval_seconds].
+pub const DEFAULT_SNAPSHOT_INTERVAL_SECONDS : f64 = 60.0;
+
+/// The state of a search, from which it can be resumed.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct SearchSnapshot {
+    /// [SNAPSHOT_FORMAT_VERSION] when written.
+    pub format_version : u32,
+    /// The SHA-256 hash, in hex, of the votes (as [crate::irv::Votes::weighted_records]), winner, elimination order, initial lower bound, the difficulty of the best assertion
+    /// for each alternate winner and the assertion types allowed. A snapshot can only be resumed by a search with the same fingerprint, that is, for the same problem.
+    pub fingerprint : String,
+    /// The lower bound on the difficulty of the problem (LB in the original paper).
+    pub lower_bound : AssertionDifficulty,
+    /// The elimination order suffixes not yet ruled out.
+    pub frontier : Vec<FrontierSnapshot>,
+    /// The assertions found so far.
+    pub assertions : Vec<AssertionAndDifficulty>,
+    /// The units of work done by the search when the snapshot was taken, including before any previous resumption. For information only.
+    pub work_done : u64,
+}
+
+/// An elimination order suffix on the frontier of the search.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct FrontierSnapshot {
+    /// The elimination order suffix, from first eliminated to the alternate winner.
+    pub suffix : EliminationOrderSuffix,
+    /// The easiest assertion found that rules out this suffix or one of the shorter suffixes it extends.
+    pub best_assertion_for_ancestor : AssertionAndDifficulty,
+    /// The length of the suffix `best_assertion_for_ancestor` was found for, the last elements of `suffix`.
+    pub best_ancestor_length : usize,
+    /// If present, the extension of the suffix by this candidate has already been searched.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub dive_done : Option<CandidateIndex>,
+}
+
+impl SearchSnapshot {
+    /// Check the snapshot can be resumed by a search with the given fingerprint and number of candidates.
+    pub fn check(&self,fingerprint:&str,num_candidates:u32) -> Result<(),RaireError> {
+        if self.format_version!=SNAPSHOT_FORMAT_VERSION { return Err(RaireError::InvalidSnapshot(format!("format version {} is not the supported version {}",self.format_version,SNAPSHOT_FORMAT_VERSION))); }
+        if self.fingerprint!=fingerprint { return Err(RaireError::InvalidSnapshot("it is of a different problem".to_string())); }
+        for a in &self.assertions { a.assertion.validate(num_candidates)?; }
+        for f in &self.frontier {
+            let valid_candidate = |c:&CandidateIndex|c.0<num_candidates;
+            if f.best_ancestor_length==0 || f.best_ancestor_length>f.suffix.len() || !(f.suffix.iter().all(valid_candidate) && f.dive_done.iter().all(valid_candidate)) {
+                return Err(RaireError::InvalidSnapshot(format!("the frontier entry {:?} is invalid",f.suffix)));
+            }
+            f.best_assertion_for_ancestor.assertion.validate(num_candidates)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where snapshots are kept. [Send] and [Sync] as it is part of the [crate::raire_algorithm::RaireOptions].
+pub trait SnapshotStore : Debug+Send+Sync {
+    /// The snapshot to resume from, if any.
+    fn load(&self) -> Result<Option<SearchSnapshot>,RaireError>;
+    /// Keep a snapshot, replacing any previous one. Failing to do so is not fatal to the search.
+    fn save(&self,snapshot:&SearchSnapshot) -> std::io::Result<()>;
+    /// Called when the search finishes, so no snapshot is needed any more.
+    fn finished(&self) {}
+    /// The number of seconds between snapshots.
+    fn interval_seconds(&self) -> f64 { DEFAULT_SNAPSHOT_INTERVAL_SECONDS }
+}
+
+/// Keep snapshots in a JSON file, which is removed when the search finishes.
+#[derive(Clone,Debug)]
+pub struct SnapshotFile {
+    pub path : PathBuf,
+    pub interval_seconds : f64,
+}
+
+impl SnapshotFile {
+    /// Keep snapshots in the given file, every [DEFAULT_SNAPSHOT_INTERVAL_SECONDS].
+    pub fn new(path:PathBuf) -> Self { SnapshotFile{ path, interval_seconds: DEFAULT_SNAPSHOT_INTERVAL_SECONDS } }
+}
+
+impl SnapshotStore for SnapshotFile {
+    fn load(&self) -> Result<Option<SearchSnapshot>,RaireError> {
+        match std::fs::File::open(&self.path) {
+            Ok(file) => serde_json::from_reader(std::io::BufReader::new(file)).map(Some).map_err(|e|RaireError::InvalidSnapshot(format!("{} could not be read : {}",self.path.display(),e))),
+            Err(e) if e.kind()==std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(RaireError::InvalidSnapshot(format!("{} could not be opened : {}",self.path.display(),e))),
+        }
+    }
+
+    fn save(&self,snapshot:&SearchSnapshot) -> std::io::Result<()> {
+        let mut temporary = self.path.clone().into_os_string(); // write to another file, so a snapshot is never partly written.
+        temporary.push(".tmp");
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(&temporary)?);
+        serde_json::to_writer(&mut writer,snapshot)?;
+        writer.into_inner().map_err(|e|e.into_error())?.sync_all()?;
+        std::fs::rename(&temporary,&self.path)
+    }
+
+    fn finished(&self) { let _ = std::fs::remove_file(&self.path); }
+
+    fn interval_seconds(&self) -> f64 { self.interval_seconds }
+}
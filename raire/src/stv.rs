@@ -0,0 +1,24 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Multi-winner STV is not yet supported.
+//!
+//! RAIRE's assertions (NEB and NEN) are statements about the order candidates are eliminated in a single winner IRV count.
+//! In STV, surplus transfers between elected candidates change ballot weights part way through the count, so these
+//! assertions no longer describe the outcome, and a sound method of generating assertions for STV is an open research question.
+//! Until there is one, contests with more than one seat are detected and rejected with [RaireError::StvNotSupported]
+//! rather than silently treated as IRV.
+
+use crate::RaireError;
+
+/// Check that a contest with the given number of seats can be audited by RAIRE, i.e. it is a single winner IRV contest.
+pub fn check_supported_seats(seats:usize) -> Result<(),RaireError> {
+    if seats==1 { Ok(()) } else { Err(RaireError::StvNotSupported{seats}) }
+}
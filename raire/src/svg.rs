@@ -0,0 +1,120 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+//! Draw the trees of elimination orders directly as SVG, without needing Graphviz (compare [crate::graphviz]).
+//!
+//! As in the DOT output, each tree has the candidate remaining at the end at the top, and children are the candidate
+//! eliminated just before. Nodes ruled out by an assertion are labeled with its number (1 based, with the full
+//! assertion as a tooltip) and colored red; nodes not ruled out are green; nodes that needed expanding are white.
+//! The output is suitable for embedding in an HTML page.
+
+use std::fmt::Write;
+use crate::assertions::{candidate_name, Assertion};
+use crate::report::html::html_escape;
+use crate::tree_showing_what_assertions_pruned_leaves::TreeNodeShowingWhatAssertionsPrunedIt;
+
+const LEVEL_HEIGHT : f64 = 70.0;
+const NODE_HEIGHT : f64 = 36.0;
+const CHAR_WIDTH : f64 = 7.5;
+const GAP : f64 = 12.0;
+const TITLE_HEIGHT : f64 = 30.0;
+
+/// A node placed on the page.
+struct PlacedNode<'a> {
+    node : &'a TreeNodeShowingWhatAssertionsPrunedIt,
+    /// the centre of the node.
+    x : f64,
+    y : f64,
+    parent : Option<usize>,
+}
+
+fn node_label(node:&TreeNodeShowingWhatAssertionsPrunedIt,candidate_names:&[String]) -> (String,String) {
+    let name = candidate_name(candidate_names,node.candidate_being_eliminated_at_this_node);
+    let assertions = node.pruning_assertions.iter().map(|a|format!("#{}",a+1)).collect::<Vec<_>>().join(" ");
+    (name,assertions)
+}
+
+/// Place the nodes of a tree, leaves side by side at intervals of `slot_width`. Returns the x coordinate of this node.
+fn place<'a>(node:&'a TreeNodeShowingWhatAssertionsPrunedIt,depth:usize,parent:Option<usize>,slot_width:f64,next_slot:&mut usize,placed:&mut Vec<PlacedNode<'a>>) -> f64 {
+    let index = placed.len();
+    placed.push(PlacedNode{node,x:0.0,y:TITLE_HEIGHT+NODE_HEIGHT/2.0+depth as f64*LEVEL_HEIGHT,parent});
+    let x = if node.children.is_empty() {
+        *next_slot+=1;
+        (*next_slot as f64-0.5)*slot_width
+    } else {
+        let child_xs : Vec<f64> = node.children.iter().map(|child|place(child,depth+1,Some(index),slot_width,next_slot,placed)).collect();
+        (child_xs[0]+child_xs[child_xs.len()-1])/2.0
+    };
+    placed[index].x=x;
+    x
+}
+
+impl TreeNodeShowingWhatAssertionsPrunedIt {
+    /// Produce an SVG picture of this tree. `all_assertions` are the assertions referred to by index in the tree.
+    /// Candidate names are used if present, otherwise candidate numbers.
+    pub fn to_svg(&self,all_assertions:&[Assertion],candidate_names:&[String]) -> String {
+        trees_to_svg(std::slice::from_ref(self),all_assertions,candidate_names)
+    }
+
+    fn max_label_chars(&self,candidate_names:&[String]) -> usize {
+        let (name,assertions) = node_label(self,candidate_names);
+        self.children.iter().map(|c|c.max_label_chars(candidate_names)).fold(name.chars().count().max(assertions.chars().count()),usize::max)
+    }
+}
+
+/// Produce a single SVG picture containing all the given trees side by side, typically one per candidate other than the winner
+/// as produced by [crate::raire_algorithm::RaireResult::elimination_trees].
+pub fn trees_to_svg(trees:&[TreeNodeShowingWhatAssertionsPrunedIt],all_assertions:&[Assertion],candidate_names:&[String]) -> String {
+    let node_width = trees.iter().map(|t|t.max_label_chars(candidate_names)).max().unwrap_or(0).max(3) as f64*CHAR_WIDTH+GAP;
+    let slot_width = node_width+GAP;
+    let mut body = String::new();
+    let mut offset = 0.0;
+    let mut height : f64 = TITLE_HEIGHT;
+    for tree in trees {
+        let mut placed = vec![];
+        let mut slots = 0;
+        place(tree,0,None,slot_width,&mut slots,&mut placed);
+        let title = format!("{} {}",candidate_name(candidate_names,tree.candidate_being_eliminated_at_this_node),if tree.valid {"is NOT ruled out"} else {"is ruled out"});
+        let width = (slots as f64*slot_width).max(title.chars().count() as f64*CHAR_WIDTH+GAP);
+        for p in &mut placed { p.x+=(width-slots as f64*slot_width)/2.0; } // centre the tree under the title.
+        writeln!(body,"<g transform=\"translate({},0)\">",offset).unwrap();
+        writeln!(body,"<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-weight=\"bold\">{}</text>",width/2.0,TITLE_HEIGHT/2.0,html_escape(&title)).unwrap();
+        for p in &placed {
+            if let Some(parent) = p.parent {
+                let parent = &placed[parent];
+                writeln!(body,"<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#666\"/>",parent.x,parent.y+NODE_HEIGHT/2.0,p.x,p.y-NODE_HEIGHT/2.0).unwrap();
+            }
+        }
+        for p in &placed {
+            let (name,assertions) = node_label(p.node,candidate_names);
+            let class = if p.node.valid { "valid" } else if p.node.pruning_assertions.is_empty() { "expanded" } else { "pruned" };
+            let fill = match class { "valid" => "palegreen", "pruned" => "lightpink", _ => "white" };
+            write!(body,"<g class=\"{}\">",class).unwrap();
+            if !p.node.pruning_assertions.is_empty() {
+                let descriptions : Vec<String> = p.node.pruning_assertions.iter().map(|&a|format!("#{} {}",a+1,all_assertions[a].describe(candidate_names))).collect();
+                write!(body,"<title>{}</title>",html_escape(&descriptions.join("\n"))).unwrap();
+            }
+            write!(body,"<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"{}\" stroke=\"#333\"/>",p.x-node_width/2.0,p.y-NODE_HEIGHT/2.0,node_width,NODE_HEIGHT,fill).unwrap();
+            if assertions.is_empty() {
+                write!(body,"<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>",p.x,p.y,html_escape(&name)).unwrap();
+            } else {
+                write!(body,"<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",p.x,p.y-2.0,html_escape(&name)).unwrap();
+                write!(body,"<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"11\">{}</text>",p.x,p.y+12.0,html_escape(&assertions)).unwrap();
+            }
+            body.push_str("</g>\n");
+            height=height.max(p.y+NODE_HEIGHT/2.0+GAP);
+        }
+        body.push_str("</g>\n");
+        offset+=width+2.0*GAP;
+    }
+    let width = (offset-2.0*GAP).max(0.0);
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-family=\"sans-serif\" font-size=\"13\">\n{}</svg>\n",width,height,width,height,body)
+}
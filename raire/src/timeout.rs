@@ -8,18 +8,84 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 
-use std::ops::Sub;
-use std::time::{Duration, Instant};
+use core::ops::Sub;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
 use serde::Deserialize;
 use serde::Serialize;
 
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use std::time::Instant;
+#[cfg(all(feature = "std", target_arch = "wasm32", target_os = "unknown"))]
+use wasm_clock::Instant;
+#[cfg(all(feature = "std", target_arch = "wasm32", target_os = "unknown"))]
+pub use wasm_clock::set_clock;
+#[cfg(not(feature = "std"))]
+use no_clock::Instant;
+
+/// There is no clock in the standard library for WebAssembly in a browser (`std::time::Instant::now()` panics),
+/// so the embedding program supplies one, typically `Date.now()` or `performance.now()`.
+#[cfg(all(feature = "std", target_arch = "wasm32", target_os = "unknown"))]
+mod wasm_clock {
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    static CLOCK : OnceLock<fn() -> f64> = OnceLock::new();
+
+    /// Set the function giving the current time in milliseconds. Until this is called, clock time limits never expire.
+    pub fn set_clock(now_milliseconds:fn() -> f64) { let _ = CLOCK.set(now_milliseconds); }
+
+    fn now_milliseconds() -> f64 { CLOCK.get().map(|clock|clock()).unwrap_or(0.0) }
+
+    #[derive(Clone,Copy)]
+    pub(crate) struct Instant(f64);
+
+    impl Instant {
+        pub(crate) fn now() -> Self { Instant(now_milliseconds()) }
+        pub(crate) fn elapsed(&self) -> Duration { Duration::from_secs_f64(((now_milliseconds()-self.0)/1000.0).max(0.0)) }
+    }
+}
+
+/// Without `std` there is no clock, so clock time limits never expire and no clock time is taken. Work limits still apply.
+#[cfg(not(feature = "std"))]
+mod no_clock {
+    use core::time::Duration;
+
+    #[derive(Clone,Copy)]
+    pub(crate) struct Instant;
+
+    impl Instant {
+        pub(crate) fn now() -> Self { Instant }
+        pub(crate) fn elapsed(&self) -> Duration { Duration::ZERO }
+    }
+}
+
+/// A way for another thread (e.g. a user interface) to stop a computation early. Clones share the same state,
+/// so give a clone to [TimeOut::with_cancellation] and keep one to call [CancellationToken::cancel] on.
+///
+/// The computation checks it at the same points as the time limit, so stops shortly after it is cancelled,
+/// giving [crate::RaireError::Cancelled].
+#[derive(Clone,Debug,Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self { Self::default() }
+    /// Ask any computation using this token to stop.
+    pub fn cancel(&self) { self.0.store(true,Ordering::Relaxed); }
+    pub fn is_cancelled(&self) -> bool { self.0.load(Ordering::Relaxed) }
+}
+
 /// A check to see that we are not taking too long.
-/// Allows efficient checking against clock time taken or work done.
+/// Allows efficient checking against clock time taken or work done, and whether the computation has been cancelled.
 pub struct TimeOut {
     start_time : Instant,
     work_done : u64,
     work_limit : Option<u64>,
     duration_limit : Option<Duration>,
+    cancellation : Option<CancellationToken>,
 }
 
 /// In case the clock is expensive to check, only check every UNITS_OF_WORK_PER_CLOCK_CHECK units of work.
@@ -29,9 +95,18 @@ impl TimeOut {
     /// Make a new timeout structure.
     pub fn new(work_limit : Option<u64>,duration_limit : Option<Duration>) -> Self {
         let start_time = Instant::now();
-        TimeOut{start_time,work_done:0,work_limit,duration_limit}
+        TimeOut{start_time,work_done:0,work_limit,duration_limit,cancellation:None}
+    }
+
+    /// Also stop if the given token is cancelled.
+    pub fn with_cancellation(self,cancellation:CancellationToken) -> Self {
+        TimeOut{cancellation:Some(cancellation),..self}
     }
 
+    /// Whether the computation has been cancelled via the token given to [Self::with_cancellation].
+    /// If so, [Self::quick_check_timeout] returns true, and the resulting timeout error should be reported as a cancellation.
+    pub fn is_cancelled(&self) -> bool { self.cancellation.as_ref().is_some_and(|c|c.is_cancelled()) }
+
     /// make a dummy timer that will never timeout
     pub fn never() -> Self { Self::new(None,None) }
 
@@ -46,7 +121,7 @@ impl TimeOut {
         TimeTaken{ work: self.work_done, seconds: self.clock_time_taken_since_start().as_secs_f64() }
     }
 
-    /// increments work_done by 1, and returns true if a limit is exceeded
+    /// increments work_done by 1, and returns true if a limit is exceeded or the computation has been cancelled
     /// * only checks duration every 100 calls.
     pub fn quick_check_timeout(&mut self) -> bool {
         self.work_done+=1;
@@ -56,8 +131,18 @@ impl TimeOut {
         if let Some(duration_limit) = self.duration_limit {
             if self.work_done%UNITS_OF_WORK_PER_CLOCK_CHECK==0 && self.clock_time_taken_since_start()>duration_limit { return true; }
         }
-        false
+        self.is_cancelled()
+    }
+
+    /// Make a timer for work done on another thread, with the same start time and limits and the work done so far.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn for_thread(&self) -> Self {
+        TimeOut{start_time:self.start_time,work_done:self.work_done,work_limit:self.work_limit,duration_limit:self.duration_limit,cancellation:self.cancellation.clone()}
     }
+
+    /// Account for work done by the timers made by [Self::for_thread], given their total work done beyond what this timer had when they were made.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn add_work_done(&mut self,work:u64) { self.work_done+=work; }
 }
 
 #[derive(Clone,Copy,Debug,Serialize,Deserialize)]
@@ -77,8 +162,10 @@ impl Sub for TimeTaken {
 
 impl TimeTaken {
     pub fn pretty_print(&self) -> String {
-        let duration = Duration::from_secs_f64(self.seconds);
-        format!("{:?}",duration)
+        match Duration::try_from_secs_f64(self.seconds) {
+            Ok(duration) => format!("{:?}",duration),
+            Err(_) => format!("{}s",self.seconds), // negative or not finite, e.g. read from a corrupt file.
+        }
     }
 }
 
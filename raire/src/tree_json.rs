@@ -0,0 +1,84 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A stable JSON form of the trees of elimination orders showing what assertions pruned them,
+//! with candidate names resolved, for front ends such as the RAIRE explainer web page.
+//!
+//! The format is versioned by [EliminationTreesJson::schema_version]. Any change that would
+//! break an existing consumer will increase [ELIMINATION_TREES_SCHEMA_VERSION].
+
+use serde::Deserialize;
+use serde::Serialize;
+use crate::assertions::{candidate_name, Assertion};
+use crate::irv::CandidateIndex;
+use crate::tree_showing_what_assertions_pruned_leaves::TreeNodeShowingWhatAssertionsPrunedIt;
+
+/// The current version of the [EliminationTreesJson] format.
+pub const ELIMINATION_TREES_SCHEMA_VERSION : u32 = 1;
+
+/// A set of elimination order trees (typically one per candidate other than the winner),
+/// along with the assertions and candidate names needed to interpret them.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct EliminationTreesJson {
+    /// The version of this format, [ELIMINATION_TREES_SCHEMA_VERSION] when produced by this library.
+    pub schema_version : u32,
+    /// The names of the candidates, indexed by candidate number.
+    pub candidates : Vec<String>,
+    /// The assertions referred to (by index) in [EliminationTreeNodeJson::pruning_assertions].
+    pub assertions : Vec<Assertion>,
+    pub trees : Vec<EliminationTreeNodeJson>,
+}
+
+/// A node in an elimination order tree. The root is the candidate remaining at the end; children are the candidate eliminated just before.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct EliminationTreeNodeJson {
+    pub candidate : CandidateIndex,
+    pub candidate_name : String,
+    /// The indices of the assertions, if any, that rule out all elimination orders ending in the path to this node.
+    #[serde(default,skip_serializing_if = "Vec::is_empty")]
+    pub pruning_assertions : Vec<usize>,
+    /// true if this node or a child thereof is not eliminated by any assertion.
+    pub valid : bool,
+    #[serde(default,skip_serializing_if = "Vec::is_empty")]
+    pub children : Vec<EliminationTreeNodeJson>,
+}
+
+impl EliminationTreeNodeJson {
+    pub fn new(tree:&TreeNodeShowingWhatAssertionsPrunedIt,candidate_names:&[String]) -> Self {
+        EliminationTreeNodeJson{
+            candidate: tree.candidate_being_eliminated_at_this_node,
+            candidate_name: candidate_name(candidate_names,tree.candidate_being_eliminated_at_this_node),
+            pruning_assertions: tree.pruning_assertions.clone(),
+            valid: tree.valid,
+            children: tree.children.iter().map(|c|EliminationTreeNodeJson::new(c,candidate_names)).collect(),
+        }
+    }
+
+    /// Convert back to the internal form, discarding names.
+    pub fn to_tree(&self) -> TreeNodeShowingWhatAssertionsPrunedIt {
+        TreeNodeShowingWhatAssertionsPrunedIt{
+            candidate_being_eliminated_at_this_node: self.candidate,
+            pruning_assertions: self.pruning_assertions.clone(),
+            children: self.children.iter().map(|c|c.to_tree()).collect(),
+            valid: self.valid,
+        }
+    }
+}
+
+impl EliminationTreesJson {
+    /// Make the JSON form of the given trees. If `candidate_names` is shorter than `num_candidates`, `#3` style names are used for the missing candidates.
+    pub fn new(trees:&[TreeNodeShowingWhatAssertionsPrunedIt],all_assertions:&[Assertion],num_candidates:u32,candidate_names:&[String]) -> Self {
+        EliminationTreesJson{
+            schema_version: ELIMINATION_TREES_SCHEMA_VERSION,
+            candidates: (0..num_candidates).map(|c|candidate_name(candidate_names,CandidateIndex(c))).collect(),
+            assertions: all_assertions.to_vec(),
+            trees: trees.iter().map(|t|EliminationTreeNodeJson::new(t,candidate_names)).collect(),
+        }
+    }
+}
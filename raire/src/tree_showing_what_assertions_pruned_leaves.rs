@@ -9,12 +9,24 @@
 
 #![doc = include_str!("../AssertionTrimmingAlgorithm.md")]
 
-use std::cmp::Ordering;
-use crate::assertions::{Assertion, AssertionAndDifficulty, EffectOfAssertionOnEliminationOrderSuffix};
-use crate::irv::CandidateIndex;
-use crate::raire_algorithm::TrimAlgorithm;
+#[cfg(feature = "std")]
+use core::cmp::{Ordering, Reverse};
+use crate::assertions::{Assertion, EffectOfAssertionOnEliminationOrderSuffix, EliminationOrderSuffix, PrecomputedAssertion};
+#[cfg(feature = "std")]
+use crate::assertions::AssertionAndDifficulty;
+use crate::irv::{CandidateIndex, CandidateSet};
+#[cfg(feature = "std")]
+use crate::progress::{phase_span, ProgressEvent, ProgressReporter};
+#[cfg(feature = "std")]
+use crate::raire_algorithm::{RaireOptions, TrimAlgorithm, TrimDepth, TrimSelectionAlgorithm};
 use crate::RaireError;
 use crate::timeout::TimeOut;
+#[cfg(feature = "std")]
+use crate::timeout::TimeTaken;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde::Deserialize;
+use serde::Serialize;
 
 /// Produce a tree of reverse-elimination-order descending down until either
 /// * At least one assertion prunes all subsequent orders
@@ -24,6 +36,9 @@ use crate::timeout::TimeOut;
 /// for their children to be pruned. See HowFarToContinueSearchTreeWhenPruningAssertionFound for details.
 /// This is useful for finding redundant assertions
 /// that can be removed, at the cost of making the frontier larger.
+///
+/// For a JSON form with candidate names resolved suitable for front ends, see [crate::tree_json].
+#[derive(Clone,Debug,Serialize,Deserialize)]
 pub struct TreeNodeShowingWhatAssertionsPrunedIt {
     pub candidate_being_eliminated_at_this_node: CandidateIndex, // The candidate eliminated at this step.
     pub pruning_assertions : Vec<usize>, // if any assertions prune it, their index in the main assertion list.
@@ -34,40 +49,210 @@ pub struct TreeNodeShowingWhatAssertionsPrunedIt {
 impl TreeNodeShowingWhatAssertionsPrunedIt {
     /// Create a new tree node with a given path back to the root and candidate being eliminated.
     pub fn new (parent_elimination_order_suffix:&[CandidateIndex], candidate_being_eliminated_at_this_node:CandidateIndex, relevant_assertions:&[usize],all_assertions:&[Assertion],num_candidates:u32,consider_children_of_eliminated_nodes:HowFarToContinueSearchTreeWhenPruningAssertionFound,timeout:&mut TimeOut) -> Result<Self,RaireError> {
-        if timeout.quick_check_timeout() { return Err(RaireError::TimeoutTrimmingAssertions) }
-        let mut elimination_order_suffix=vec![candidate_being_eliminated_at_this_node]; // elimination order including this node
-        elimination_order_suffix.extend_from_slice(parent_elimination_order_suffix);
+        Self::new_with_node_limit(parent_elimination_order_suffix,candidate_being_eliminated_at_this_node,relevant_assertions,all_assertions,num_candidates,consider_children_of_eliminated_nodes,None,timeout)
+    }
+
+    /// Like [Self::new], but if the tree would have more than `max_nodes` nodes, give up with [RaireError::TreeTooLarge].
+    ///
+    /// The tree is built with an explicit stack rather than recursion, so deep trees cannot overflow the call stack.
+    /// Gives [RaireError::InvalidCandidateNumber] if the parent suffix and the candidate at this node are not distinct candidates
+    /// less than `num_candidates`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_node_limit(parent_elimination_order_suffix:&[CandidateIndex], candidate_being_eliminated_at_this_node:CandidateIndex, relevant_assertions:&[usize],all_assertions:&[Assertion],num_candidates:u32,consider_children_of_eliminated_nodes:HowFarToContinueSearchTreeWhenPruningAssertionFound,max_nodes:Option<usize>,timeout:&mut TimeOut) -> Result<Self,RaireError> {
+        if num_candidates>CandidateSet::MAX_CANDIDATES { return Err(RaireError::TooManyCandidates(num_candidates)) }
+        for assertion in all_assertions { assertion.validate(num_candidates)?; }
+        let valid_candidate = |c:&CandidateIndex|c.0<num_candidates;
+        if !parent_elimination_order_suffix.iter().all(valid_candidate) || !valid_candidate(&candidate_being_eliminated_at_this_node) { return Err(RaireError::InvalidCandidateNumber) }
+        let parent_set : CandidateSet = parent_elimination_order_suffix.iter().cloned().collect();
+        if parent_set.len()!=parent_elimination_order_suffix.len() || parent_set.contains(candidate_being_eliminated_at_this_node) { return Err(RaireError::InvalidCandidateNumber) }
+        let precomputed : Vec<PrecomputedAssertion> = all_assertions.iter().map(PrecomputedAssertion::new).collect::<Option<_>>().ok_or(RaireError::InvalidCandidateNumber)?;
+        let mut builder = TreeBuilder{ elimination_order_buffer: vec![CandidateIndex(0);num_candidates as usize], precomputed, num_candidates, max_nodes, nodes_built:0, timeout };
+        let start = builder.elimination_order_buffer.len()-parent_elimination_order_suffix.len();
+        builder.elimination_order_buffer[start..].copy_from_slice(parent_elimination_order_suffix);
+        builder.build(start-1,parent_set,candidate_being_eliminated_at_this_node,relevant_assertions.to_vec(),consider_children_of_eliminated_nodes)
+    }
+
+    /// The number of nodes in this tree, including this one.
+    pub fn count_nodes(&self) -> usize {
+        1+self.children.iter().map(|c|c.count_nodes()).sum::<usize>()
+    }
+
+    /// The elimination order suffixes that no assertion rules out, from the valid leaves of this tree.
+    /// Each is listed from first eliminated to winner (this tree's root); any order of the candidates not in a suffix,
+    /// followed by the suffix, is an elimination order allowed by the assertions.
+    pub fn unpruned_elimination_order_suffixes(&self) -> Vec<EliminationOrderSuffix> {
+        let mut res = vec![];
+        let mut path = vec![];
+        self.add_unpruned_elimination_order_suffixes(&mut path,&mut res);
+        res
+    }
+
+    /// The indices of the assertions pruning some node of this tree, in ascending order.
+    pub fn pruning_assertions_used(&self) -> Vec<usize> {
+        let mut res = vec![];
+        self.add_pruning_assertions(&mut res);
+        res.sort_unstable();
+        res.dedup();
+        res
+    }
+
+    fn add_pruning_assertions(&self,res:&mut Vec<usize>) {
+        res.extend_from_slice(&self.pruning_assertions);
+        for child in &self.children { child.add_pruning_assertions(res); }
+    }
+
+    /// The error to return if this tree, for the winner, is not valid. See [RaireError::InternalErrorRuledOutWinner].
+    #[cfg(feature = "std")]
+    pub(crate) fn ruled_out_winner_error(&self) -> RaireError {
+        let winner = self.candidate_being_eliminated_at_this_node;
+        RaireError::InternalErrorRuledOutWinner{winner:Some(winner),elimination_order:vec![winner],assertions:self.pruning_assertions_used()}
+    }
+
+    /// The error to return if this tree, for a candidate other than the winner, is valid. See [RaireError::InternalErrorDidntRuleOutLoser].
+    #[cfg(feature = "std")]
+    pub(crate) fn didnt_rule_out_loser_error(&self) -> RaireError {
+        RaireError::InternalErrorDidntRuleOutLoser{candidate:Some(self.candidate_being_eliminated_at_this_node),elimination_order:self.unpruned_elimination_order_suffixes().into_iter().next().unwrap_or_default()}
+    }
+
+    /// Add this tree, whose root is at the given depth (1 for a tree's root), to the statistics.
+    fn add_statistics(&self,depth:usize,statistics:&mut TreeStatistics) {
+        statistics.nodes+=1;
+        statistics.max_depth=statistics.max_depth.max(depth);
+        if !self.pruning_assertions.is_empty() { statistics.frontier+=1; }
+        else if self.children.is_empty() && self.valid { statistics.unpruned_leaves+=1; }
+        if let [only] = self.pruning_assertions[..] { statistics.only_pruned_by_assertion[only]+=1; }
+        for &assertion in &self.pruning_assertions { statistics.pruned_by_assertion[assertion]+=1; }
+        for child in &self.children { child.add_statistics(depth+1,statistics); }
+    }
+
+    /// path is the reverse elimination order from the root down to this node's parent.
+    fn add_unpruned_elimination_order_suffixes(&self,path:&mut Vec<CandidateIndex>,res:&mut Vec<EliminationOrderSuffix>) {
+        if !self.valid { return; }
+        path.push(self.candidate_being_eliminated_at_this_node);
+        if self.children.is_empty() { res.push(path.iter().rev().cloned().collect()); }
+        for child in &self.children { child.add_unpruned_elimination_order_suffixes(path,res); }
+        path.pop();
+    }
+}
+
+/// Statistics about the trees of elimination orders for a set of assertions, such as those from [crate::raire_algorithm::RaireResult::elimination_trees].
+/// These are useful for comparing variants of RAIRE, or different sets of assertions for the same contest.
+#[derive(Clone,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct TreeStatistics {
+    /// The total number of nodes in the trees.
+    pub nodes : usize,
+    /// The number of nodes pruned by an assertion: the elimination order suffixes at the frontier of the search where some assertion rules them out.
+    pub frontier : usize,
+    /// The number of leaves not pruned by any assertion, each an elimination order suffix that is not ruled out. Zero if the assertions prove the winner.
+    pub unpruned_leaves : usize,
+    /// The greatest depth of a node, a tree's root (the last candidate in the elimination order) being at depth 1.
+    pub max_depth : usize,
+    /// For each assertion, the number of nodes it prunes. A node may be pruned by more than one assertion.
+    pub pruned_by_assertion : Vec<usize>,
+    /// For each assertion, the number of nodes pruned by it and no other assertion.
+    pub only_pruned_by_assertion : Vec<usize>,
+}
+
+impl TreeStatistics {
+    pub fn new(trees:&[TreeNodeShowingWhatAssertionsPrunedIt],num_assertions:usize) -> Self {
+        let mut statistics = TreeStatistics{ nodes: 0, frontier: 0, unpruned_leaves: 0, max_depth: 0, pruned_by_assertion: vec![0;num_assertions], only_pruned_by_assertion: vec![0;num_assertions] };
+        for tree in trees { tree.add_statistics(1,&mut statistics); }
+        statistics
+    }
+}
+
+/// The state shared by all nodes while building a tree.
+struct TreeBuilder<'a> {
+    /// The elimination order suffix for a node at depth d is the last d elements of this buffer, so no allocation is needed per node.
+    elimination_order_buffer : Vec<CandidateIndex>,
+    /// The assertions, in a form faster to check.
+    precomputed : Vec<PrecomputedAssertion>,
+    num_candidates : u32,
+    max_nodes : Option<usize>,
+    nodes_built : usize,
+    timeout : &'a mut TimeOut,
+}
+
+/// A node that is still having its children built.
+struct PartiallyBuiltNode {
+    /// where in the elimination order buffer this node's candidate is.
+    position : usize,
+    /// the candidates in the elimination order suffix including this node.
+    elimination_order_set : CandidateSet,
+    candidate_being_eliminated_at_this_node : CandidateIndex,
+    pruning_assertions : Vec<usize>,
+    /// The assertions that may prune children. Empty if children should not be built.
+    still_relevant_assertions : Vec<usize>,
+    next_consider_children_of_eliminated_nodes : HowFarToContinueSearchTreeWhenPruningAssertionFound,
+    children : Vec<TreeNodeShowingWhatAssertionsPrunedIt>,
+    valid : bool,
+    /// The next candidate to consider as a child.
+    next_child : u32,
+}
+
+impl PartiallyBuiltNode {
+    fn finish(self) -> TreeNodeShowingWhatAssertionsPrunedIt {
+        TreeNodeShowingWhatAssertionsPrunedIt{candidate_being_eliminated_at_this_node:self.candidate_being_eliminated_at_this_node,pruning_assertions:self.pruning_assertions,children:self.children,valid:self.valid}
+    }
+}
+
+impl TreeBuilder<'_> {
+    /// Start a new tree node, where the parent elimination order suffix is `elimination_order_buffer[position+1..]`, containing exactly the candidates `parent_set`.
+    fn start_node(&mut self,position:usize,parent_set:CandidateSet,candidate_being_eliminated_at_this_node:CandidateIndex, relevant_assertions:&[usize],consider_children_of_eliminated_nodes:HowFarToContinueSearchTreeWhenPruningAssertionFound) -> Result<PartiallyBuiltNode,RaireError> {
+        if self.timeout.quick_check_timeout() { return Err(RaireError::TimeoutTrimmingAssertions) }
+        self.nodes_built+=1;
+        if let Some(max_nodes) = self.max_nodes { if self.nodes_built>max_nodes { return Err(RaireError::TreeTooLarge(max_nodes)) } }
+        self.elimination_order_buffer[position]=candidate_being_eliminated_at_this_node;
+        let elimination_order_suffix = &self.elimination_order_buffer[position..]; // elimination order including this node
+        let elimination_order_set = parent_set.with(candidate_being_eliminated_at_this_node);
         let mut pruning_assertions : Vec<usize> = vec![];
         let mut still_relevant_assertions : Vec<usize> = vec![];
         for &assertion_index in relevant_assertions {
-            match all_assertions[assertion_index].ok_elimination_order_suffix(&elimination_order_suffix) {
+            match self.precomputed[assertion_index].ok_elimination_order_suffix(elimination_order_suffix,elimination_order_set) {
                 EffectOfAssertionOnEliminationOrderSuffix::Contradiction => { pruning_assertions.push(assertion_index); }
                 EffectOfAssertionOnEliminationOrderSuffix::Ok => {} // can ignore
                 EffectOfAssertionOnEliminationOrderSuffix::NeedsMoreDetail => { still_relevant_assertions.push(assertion_index); }
             }
         }
-        let mut children : Vec<Self> = vec![];
-        let mut valid : bool = pruning_assertions.is_empty() && still_relevant_assertions.is_empty();
-        let pruned_by_neb = pruning_assertions.iter().any(|a|all_assertions[*a].is_neb());
-        if (pruning_assertions.is_empty()||consider_children_of_eliminated_nodes.should_continue_if_pruning_assertion_found(pruned_by_neb)) && !still_relevant_assertions.is_empty() {
-            let next_consider_children_of_eliminated_nodes = if pruning_assertions.is_empty() { consider_children_of_eliminated_nodes } else { consider_children_of_eliminated_nodes.next_level_if_pruning_assertion_found() };
-            for candidate in 0..num_candidates {
-                let candidate = CandidateIndex(candidate);
-                if !elimination_order_suffix.contains(&candidate) { // could make more efficient by using binary search,
-                    let child = TreeNodeShowingWhatAssertionsPrunedIt::new(&elimination_order_suffix,candidate,&still_relevant_assertions,all_assertions,num_candidates,next_consider_children_of_eliminated_nodes,timeout)?;
-                    if child.valid {
-                        if pruning_assertions.is_empty() {
-                            valid=true;
-                        } else { // we were continuing searching beyond a pruned branch. There is no point doing this.
-                            children.clear();
-                            break;
+        let valid : bool = pruning_assertions.is_empty() && still_relevant_assertions.is_empty();
+        let pruned_by_neb = pruning_assertions.iter().any(|a|matches!(self.precomputed[*a],PrecomputedAssertion::NEB{..}));
+        if !(pruning_assertions.is_empty()||consider_children_of_eliminated_nodes.should_continue_if_pruning_assertion_found(pruned_by_neb)) { still_relevant_assertions.clear(); } // don't build children.
+        let next_consider_children_of_eliminated_nodes = if pruning_assertions.is_empty() { consider_children_of_eliminated_nodes } else { consider_children_of_eliminated_nodes.next_level_if_pruning_assertion_found() };
+        Ok(PartiallyBuiltNode{position,elimination_order_set,candidate_being_eliminated_at_this_node,pruning_assertions,still_relevant_assertions,next_consider_children_of_eliminated_nodes,children:vec![],valid,next_child:0})
+    }
+
+    /// Build the tree rooted at the given node, depth first.
+    fn build(&mut self,position:usize,parent_set:CandidateSet,candidate_being_eliminated_at_this_node:CandidateIndex, relevant_assertions:Vec<usize>,consider_children_of_eliminated_nodes:HowFarToContinueSearchTreeWhenPruningAssertionFound) -> Result<TreeNodeShowingWhatAssertionsPrunedIt,RaireError> {
+        let mut stack : Vec<PartiallyBuiltNode> = vec![self.start_node(position,parent_set,candidate_being_eliminated_at_this_node,&relevant_assertions,consider_children_of_eliminated_nodes)?];
+        loop {
+            let top = stack.last_mut().unwrap();
+            let next_child = if top.still_relevant_assertions.is_empty() { None } else { (top.next_child..self.num_candidates).map(CandidateIndex).find(|&c|!top.elimination_order_set.contains(c)) };
+            if let Some(candidate) = next_child { // there is a candidate not yet in the suffix, so position>0.
+                top.next_child=candidate.0+1;
+                let (position,set,how_far) = (top.position-1,top.elimination_order_set,top.next_consider_children_of_eliminated_nodes);
+                let relevant = core::mem::take(&mut top.still_relevant_assertions);
+                let child = self.start_node(position,set,candidate,&relevant,how_far);
+                stack.last_mut().unwrap().still_relevant_assertions=relevant;
+                stack.push(child?);
+            } else {
+                let child = stack.pop().unwrap().finish();
+                match stack.last_mut() {
+                    None => return Ok(child),
+                    Some(parent) => {
+                        if child.valid {
+                            if parent.pruning_assertions.is_empty() {
+                                parent.valid=true;
+                            } else { // we were continuing searching beyond a pruned branch. There is no point doing this.
+                                parent.children.clear();
+                                parent.still_relevant_assertions.clear(); // stop building children.
+                                continue;
+                            }
                         }
+                        parent.children.push(child);
                     }
-                    children.push(child);
                 }
             }
         }
-        Ok(TreeNodeShowingWhatAssertionsPrunedIt{candidate_being_eliminated_at_this_node,pruning_assertions,children,valid})
     }
 }
 
@@ -77,6 +262,8 @@ pub enum HowFarToContinueSearchTreeWhenPruningAssertionFound {
     StopImmediately,
     /// When a pruning assertion is found, continue and see if its descendents are sufficient to stop it. But once it is stopped by a frontier of descendents, don't try each of their descendents.
     ContinueOnce,
+    /// Like ContinueOnce, but continue through this many levels of pruned nodes. `ContinueLevels(1)` is the same as ContinueOnce, and `ContinueLevels(0)` as StopImmediately.
+    ContinueLevels(u32),
     /// When a pruning assertion is found, continue. Don't stop unless no assertions left.
     Forever,
     /// Like forever, but do stop at a pruning assertion if at least one NEB prunes it. This is a useful heuristic as in practice NEBs are almost never redundant but often have very large descendent trees that need searching.
@@ -86,7 +273,7 @@ pub enum HowFarToContinueSearchTreeWhenPruningAssertionFound {
 impl HowFarToContinueSearchTreeWhenPruningAssertionFound {
     fn should_continue_if_pruning_assertion_found(self,pruned_by_neb:bool) -> bool {
         match self {
-            Self::StopImmediately => false,
+            Self::StopImmediately | Self::ContinueLevels(0) => false,
             Self::StopOnNEB => !pruned_by_neb,
             _ => true,
         }
@@ -95,6 +282,7 @@ impl HowFarToContinueSearchTreeWhenPruningAssertionFound {
         match self {
             Self::StopImmediately => Self::StopImmediately, // should never happen.
             Self::ContinueOnce => Self::StopImmediately,
+            Self::ContinueLevels(levels) => Self::ContinueLevels(levels.saturating_sub(1)),
             Self::Forever => Self::Forever,
             Self::StopOnNEB => Self::StopOnNEB,
         }
@@ -105,15 +293,84 @@ impl HowFarToContinueSearchTreeWhenPruningAssertionFound {
 /// and check that that is not trimmed. This is a nice consistency check. However, the
 /// computation of the boundary for the winning candidate is often vastly more expensive than
 /// the rest of the computation. So it is not enabled.
+#[cfg(feature = "std")]
 const CHECK_WINNER_NOT_ELIMINATED:bool=false;
 
+/// What the trimming pass did, returned by [order_assertions_and_remove_unnecessary] for machine-readable reporting.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+#[cfg(feature = "std")]
+pub struct TrimmingStatistics {
+    pub assertions_before : usize,
+    pub assertions_after : usize,
+    /// The size of the tree of elimination orders built for each candidate other than the winner. Empty if no trimming was done.
+    pub tree_nodes : Vec<TreeSize>,
+    pub time_taken : TimeTaken,
+    pub trim_algorithm : TrimAlgorithm,
+    pub trim_selection_algorithm : TrimSelectionAlgorithm,
+    /// If [RaireOptions::extended_trim] was given, whether the expanded trees were within its node budget and so used for trimming.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub extended_trim_within_budget : Option<bool>,
+    /// Present if [RaireOptions::cross_check_trimming] was set.
+    #[serde(default,skip_serializing_if = "Option::is_none")]
+    pub cross_check : Option<TrimCrossCheck>,
+}
+
+/// The result of trimming the same assertions, with the same trees, with both [TrimSelectionAlgorithm::TwoPassHeuristic]
+/// and [TrimSelectionAlgorithm::Optimal], as a check on the trimming code. See [RaireOptions::cross_check_trimming].
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
+pub struct TrimCrossCheck {
+    /// The number of assertions kept by the two pass heuristic.
+    pub heuristic_assertions : usize,
+    /// The number of assertions kept by the optimal selection.
+    pub optimal_assertions : usize,
+    /// How many more assertions the heuristic kept than were needed.
+    pub size_difference : usize,
+}
+
+#[cfg(feature = "std")]
+impl TrimCrossCheck {
+    /// Select the assertions to keep with both strategies, and check that the assertions each keeps still rule out every
+    /// alternate winner, by building the trees of elimination orders afresh from just those assertions, and that the
+    /// optimal selection kept no more than the heuristic.
+    fn new(trees:&[TreeNodeShowingWhatAssertionsPrunedIt],assertions:&[Assertion],winner:CandidateIndex,num_candidates:u32,timeout:&mut TimeOut) -> Result<Self,RaireError> {
+        let heuristic = HeuristicWorkOutWhichAssertionsAreUsed::assertions_used(trees,assertions.len(),timeout)?;
+        let optimal = OptimalWorkOutWhichAssertionsAreUsed::assertions_used(trees,assertions.len(),timeout)?;
+        for (used,trim_selection_algorithm) in [(&heuristic,TrimSelectionAlgorithm::TwoPassHeuristic),(&optimal,TrimSelectionAlgorithm::Optimal)] {
+            let kept : Vec<usize> = (0..assertions.len()).filter(|&i|used[i]).collect();
+            for candidate in (0..num_candidates).map(CandidateIndex).filter(|&c|c!=winner) {
+                let tree = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&kept,assertions,num_candidates,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,timeout)?;
+                if tree.valid {
+                    log::error!("The assertions kept by {:?} when trimming do not rule out {}",trim_selection_algorithm,candidate);
+                    return Err(tree.didnt_rule_out_loser_error());
+                }
+            }
+        }
+        let heuristic_assertions = heuristic.iter().filter(|&&used|used).count();
+        let optimal_assertions = optimal.iter().filter(|&&used|used).count();
+        if optimal_assertions>heuristic_assertions {
+            log::error!("The optimal selection kept {} assertions when trimming, more than the {} kept by the heuristic",optimal_assertions,heuristic_assertions);
+            return Err(RaireError::InternalErrorTrimming);
+        }
+        Ok(TrimCrossCheck{ heuristic_assertions, optimal_assertions, size_difference: heuristic_assertions-optimal_assertions })
+    }
+}
+
+/// The number of nodes in the tree of elimination orders ending in a given candidate.
+#[derive(Clone,Copy,Debug,Serialize,Deserialize)]
+pub struct TreeSize {
+    pub candidate : CandidateIndex,
+    pub nodes : usize,
+}
+
 /// Sort the assertions in a human sensible manner, and then trim them.
 ///
 /// Note that if a timeout error is produced, the assertions array will be sorted but otherwise unchanged
 /// from the original call.
 ///
 /// The algorithm is described in [../AssertionTrimmingAlgorithm.md]
-pub fn order_assertions_and_remove_unnecessary(assertions:&mut Vec<AssertionAndDifficulty>,winner:CandidateIndex,num_candidates:u32,trim_algorithm:TrimAlgorithm,timeout:&mut TimeOut) -> Result<(),RaireError> {
+#[cfg(feature = "std")]
+pub fn order_assertions_and_remove_unnecessary(assertions:&mut Vec<AssertionAndDifficulty>,winner:CandidateIndex,num_candidates:u32,options:&RaireOptions,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<TrimmingStatistics,RaireError> {
+    let _span = phase_span!("trim",assertions=assertions.len(),trim_algorithm=?options.trim_algorithm);
     assertions.sort_unstable_by(|a,b|{
         // sort all NEBs before NENs,
         // sort NENs by length
@@ -122,50 +379,89 @@ pub fn order_assertions_and_remove_unnecessary(assertions:&mut Vec<AssertionAndD
             (Assertion::NEN(_), Assertion::NEB(_)) => Ordering::Greater,
             (Assertion::NEB(_), Assertion::NEN(_)) => Ordering::Less,
             (Assertion::NEN(a), Assertion::NEN(b)) => {
-                a.continuing.len().cmp(&b.continuing.len()).then_with(||a.winner.0.cmp(&b.winner.0).then_with(||a.loser.0.cmp(&b.loser.0)).then_with(||{
-                    // compare continuing
-                    for i in 0..a.continuing.len() {
-                        let res = a.continuing[i].0.cmp(&b.continuing[i].0);
-                        if res!=Ordering::Equal { return res}
-                    }
-                    Ordering::Equal
-                }))
+                a.continuing.len().cmp(&b.continuing.len()).then_with(||a.winner.0.cmp(&b.winner.0)).then_with(||a.loser.0.cmp(&b.loser.0)).then_with(||a.continuing.cmp(&b.continuing))
             },
             (Assertion::NEB(a), Assertion::NEB(b)) => a.winner.0.cmp(&b.winner.0).then_with(||a.loser.0.cmp(&b.loser.0)),
         }
     });
-    if let Some(consider_children_of_eliminated_nodes) = match trim_algorithm {
+    let assertions_before = assertions.len();
+    let time_at_start = timeout.time_taken();
+    let mut tree_nodes = vec![];
+    let mut extended_trim_within_budget = None;
+    let mut cross_check = None;
+    if let Some(consider_children_of_eliminated_nodes) = match options.trim_algorithm {
         TrimAlgorithm::None => None,
         TrimAlgorithm::MinimizeTree => Some(HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately),
         TrimAlgorithm::MinimizeAssertions => Some(HowFarToContinueSearchTreeWhenPruningAssertionFound::StopOnNEB),
     } { // do the actual trimming
         let all_assertions : Vec<Assertion> = assertions.iter().map(|ad|ad.assertion.clone()).collect();
-        let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
-        let mut find_used = HeuristicWorkOutWhichAssertionsAreUsed::new(assertions.len());
-        let mut trees = vec![];
-        for candidate in 0..num_candidates { // create trees and do first pass
-            let candidate = CandidateIndex(candidate);
-            if candidate!=winner || CHECK_WINNER_NOT_ELIMINATED {
-                let tree = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],candidate,&all_assertion_indices,&all_assertions,num_candidates,consider_children_of_eliminated_nodes,timeout)?;
-                if tree.valid!= (candidate==winner) { return Err(if candidate==winner { RaireError::InternalErrorRuledOutWinner} else { RaireError::InternalErrorDidntRuleOutLoser })}
-                if candidate!=winner {
-                    find_used.add_tree_forced(&tree);
-                    trees.push(tree);
-                }
+        let max_total_nodes = options.max_memory_mb.map(|mb|mb.saturating_mul(1<<20)/APPROXIMATE_TREE_NODE_BYTES);
+        let extended = options.extended_trim.map(|extended|{
+            let how_far = match extended.depth {
+                TrimDepth::Levels(levels) => HowFarToContinueSearchTreeWhenPruningAssertionFound::ContinueLevels(levels),
+                TrimDepth::Unlimited => HowFarToContinueSearchTreeWhenPruningAssertionFound::Forever,
+            };
+            let max_nodes = match (extended.max_nodes,options.max_tree_nodes) { (Some(a),Some(b)) => Some(a.min(b)), (a,b) => a.or(b) };
+            build_trimming_trees(&all_assertions,winner,num_candidates,how_far,max_nodes,max_total_nodes,timeout,progress)
+        });
+        let (trees,sizes) = match extended {
+            Some(Ok(built)) => { extended_trim_within_budget=Some(true); built }
+            Some(Err(RaireError::TreeTooLarge(_))) => { // over budget, so use the usual trees.
+                extended_trim_within_budget=Some(false);
+                build_trimming_trees(&all_assertions,winner,num_candidates,consider_children_of_eliminated_nodes,options.max_tree_nodes,max_total_nodes,timeout,progress)?
             }
-        }
-        for tree in trees {
-            find_used.add_tree_second_pass(&tree,timeout)?;
-        }
-        find_used.finish_second_pass()?;
+            Some(Err(e)) => return Err(e),
+            None => build_trimming_trees(&all_assertions,winner,num_candidates,consider_children_of_eliminated_nodes,options.max_tree_nodes,max_total_nodes,timeout,progress)?,
+        };
+        tree_nodes=sizes;
+        let assertions_used = match options.trim_selection_algorithm {
+            TrimSelectionAlgorithm::TwoPassHeuristic => HeuristicWorkOutWhichAssertionsAreUsed::assertions_used(&trees,assertions.len(),timeout)?,
+            TrimSelectionAlgorithm::GreedyCoverage => GreedyWorkOutWhichAssertionsAreUsed::assertions_used(&trees,assertions.len(),timeout)?,
+            TrimSelectionAlgorithm::Optimal => OptimalWorkOutWhichAssertionsAreUsed::assertions_used(&trees,assertions.len(),timeout)?,
+        };
+        if options.cross_check_trimming { cross_check=Some(TrimCrossCheck::new(&trees,&all_assertions,winner,num_candidates,timeout)?); }
         let mut res = vec![];
         for (index,a) in assertions.drain(..).enumerate() {
-            if find_used.uses(index) { res.push(a); }
+            if assertions_used[index] { res.push(a); }
         }
         assertions.extend(res.drain(..));
-        // println!(" Trimmed {} assertions down to {}",all_assertion_indices.len(),assertions.len());
+        progress.report(ProgressEvent::FinishedTrimming{assertions_before,assertions_after:assertions.len(),time_taken:timeout.time_taken()-time_at_start});
     }
-    Ok(())
+    Ok(TrimmingStatistics{assertions_before,assertions_after:assertions.len(),tree_nodes,time_taken:timeout.time_taken()-time_at_start,trim_algorithm:options.trim_algorithm,trim_selection_algorithm:options.trim_selection_algorithm,extended_trim_within_budget,cross_check})
+}
+
+/// The approximate memory used by a node of a tree built when trimming, which usually has one pruning assertion.
+#[cfg(feature = "std")]
+const APPROXIMATE_TREE_NODE_BYTES : usize = core::mem::size_of::<TreeNodeShowingWhatAssertionsPrunedIt>()+core::mem::size_of::<usize>();
+
+/// Build the trees used for trimming, one for each candidate other than the winner, along with their sizes.
+/// Each tree has at most `max_nodes` nodes, and all of them together at most `max_total_nodes`.
+#[cfg(feature = "std")]
+#[allow(clippy::too_many_arguments)]
+fn build_trimming_trees(all_assertions:&[Assertion],winner:CandidateIndex,num_candidates:u32,consider_children_of_eliminated_nodes:HowFarToContinueSearchTreeWhenPruningAssertionFound,max_nodes:Option<usize>,max_total_nodes:Option<usize>,timeout:&mut TimeOut,progress:&mut dyn ProgressReporter) -> Result<(Vec<TreeNodeShowingWhatAssertionsPrunedIt>,Vec<TreeSize>),RaireError> {
+    let all_assertion_indices : Vec<usize> = (0..all_assertions.len()).collect();
+    let mut trees = vec![];
+    let mut tree_nodes = vec![];
+    let trees_total = if CHECK_WINNER_NOT_ELIMINATED { num_candidates } else { num_candidates-1 } as usize;
+    let mut trees_built = 0;
+    let mut total_nodes = 0;
+    for candidate in 0..num_candidates { // create trees
+        let candidate = CandidateIndex(candidate);
+        if candidate!=winner || CHECK_WINNER_NOT_ELIMINATED {
+            let max_nodes = match (max_nodes,max_total_nodes.map(|total|total.saturating_sub(total_nodes))) { (Some(a),Some(b)) => Some(a.min(b)), (a,b) => a.or(b) };
+            let tree = TreeNodeShowingWhatAssertionsPrunedIt::new_with_node_limit(&[],candidate,&all_assertion_indices,all_assertions,num_candidates,consider_children_of_eliminated_nodes,max_nodes,timeout)?;
+            if tree.valid!= (candidate==winner) { return Err(if candidate==winner { tree.ruled_out_winner_error() } else { tree.didnt_rule_out_loser_error() })}
+            trees_built+=1;
+            let nodes = tree.count_nodes();
+            total_nodes+=nodes;
+            progress.report(ProgressEvent::TrimmingTreeBuilt{candidate,nodes,trees_built,trees_total});
+            if candidate!=winner {
+                tree_nodes.push(TreeSize{candidate,nodes});
+                trees.push(tree);
+            }
+        }
+    }
+    Ok((trees,tree_nodes))
 }
 
 /// A pretty simple method of computing which assertions are used which may not always
@@ -176,15 +472,29 @@ pub fn order_assertions_and_remove_unnecessary(assertions:&mut Vec<AssertionAndD
 /// of expressions. The heuristic is a first pass choosing ones where there is no choice, and
 /// a second pass of choosing arbitrarily amongst the remaining ones where prior choices have
 /// not solved it.
+#[cfg(feature = "std")]
 struct HeuristicWorkOutWhichAssertionsAreUsed {
     assertions_used : Vec<bool>,
 }
 
+#[cfg(feature = "std")]
 impl HeuristicWorkOutWhichAssertionsAreUsed {
     fn new(len:usize) -> Self { Self{assertions_used:vec![false;len]}}
-    fn uses(&self,index:usize) -> bool { self.assertions_used[index] }
+    /// Work out which of the num_assertions assertions are used to prune the trees (one per candidate other than the winner).
+    fn assertions_used(trees:&[TreeNodeShowingWhatAssertionsPrunedIt],num_assertions:usize,timeout:&mut TimeOut) -> Result<Vec<bool>,RaireError> {
+        let mut find_used = Self::new(num_assertions);
+        for tree in trees {
+            find_used.add_tree_forced(tree,timeout)?;
+        }
+        for tree in trees {
+            find_used.add_tree_second_pass(tree,timeout)?;
+        }
+        find_used.finish_second_pass()?;
+        Ok(find_used.assertions_used)
+    }
     /// Some (most) nodes have exactly one assertion. Assign these assertions, as they MUST be used.
-    fn add_tree_forced(&mut self,node:&TreeNodeShowingWhatAssertionsPrunedIt) {
+    fn add_tree_forced(&mut self,node:&TreeNodeShowingWhatAssertionsPrunedIt,timeout:&mut TimeOut) -> Result<(),RaireError> {
+        if timeout.quick_check_timeout() { return Err(RaireError::TimeoutTrimmingAssertions); }
         if node.pruning_assertions.len()>0 {
             //print!("{}",node.pruning_assertions.len());
             if node.children.is_empty() {
@@ -196,9 +506,10 @@ impl HeuristicWorkOutWhichAssertionsAreUsed {
             }
         } else {
             for child in &node.children {
-                self.add_tree_forced(child);
+                self.add_tree_forced(child,timeout)?;
             }
         }
+        Ok(())
     }
     /// See if a node is already eliminated by the assertions marked as being used.
     fn node_already_eliminated(&self,node:&TreeNodeShowingWhatAssertionsPrunedIt) -> bool {
@@ -225,137 +536,207 @@ impl HeuristicWorkOutWhichAssertionsAreUsed {
     fn finish_second_pass(&self)  -> Result<(),RaireError> {Ok(())}
 }
 
-/*
-use xdd::{BDDFactory, DecisionDiagramFactory, NodeIndex, NoMultiplicity, VariableIndex};
-use std::collections::HashMap;
-
-/// a more complex method of computing which assertions are used - just use the first from each list. Benefits: minimizes number of assertions. Drawbacks: often much slower, complex, requires dependencies.
-/// This is not used as the simplistic method turns out to be optimal on all samples tested when the Forever option is used, and it is prohibitively slow when the ContinueOnce option is used.
-struct OptimalWorkOutWhichAssertionsAreUsed {
-    simple : SimplisticWorkOutWhichAssertionsAreUsed,
-    factory : BDDFactory<u32,NoMultiplicity>,
-    required : NodeIndex<u32,NoMultiplicity>,
-    variables : GetXDDVariable,
-}
+/// A greedy method of computing which assertions are used. After taking the assertions
+/// that must be used (as in the first pass of [HeuristicWorkOutWhichAssertionsAreUsed]),
+/// repeatedly take the assertion that prunes the largest number of nodes that are not yet pruned,
+/// until everything is pruned. Ties are resolved in favour of the earliest assertion.
+///
+/// This is the classic greedy approximation to set cover. It is not guaranteed optimal, but unlike
+/// taking the first assertion for each node it will not pick an assertion that only helps one node
+/// when another assertion would deal with that node and many others at once. Each step is a single
+/// pass over the trees, so it remains fast for large contests.
+#[cfg(feature = "std")]
+struct GreedyWorkOutWhichAssertionsAreUsed {}
 
-impl OptimalWorkOutWhichAssertionsAreUsed {
-    fn new(len:usize) -> Result<Self,RaireError> {
-        if len>u16::MAX as usize { Err(RaireError::InternalErrorTrimming) }
-        else {
-            let variables = GetXDDVariable::new(len);
-            Ok(Self{simple:SimplisticWorkOutWhichAssertionsAreUsed::new(len), factory: BDDFactory::new(variables.max_variable), required:NodeIndex::TRUE, variables })
-        }
-    }
-    fn uses(&self,index:usize) -> bool { self.simple.uses(index) }
-    /// Some (most) nodes have exactly one assertion. Assign these assertions, as they MUST be used.
-    fn add_tree_forced(&mut self,node:&TreeNodeShowingWhatAssertionsPrunedIt) {
-        self.simple.add_tree_forced(node);
-    }
-    /// compute an xdd function representing the constraints implied by the tree
-    fn tree_to_xdd(&mut self,node:&TreeNodeShowingWhatAssertionsPrunedIt) -> NodeIndex<u32,NoMultiplicity> {
-        if node.pruning_assertions.iter().any(|&a|self.uses(a)) {
-            // short cut, nothing to do as the first pass dealt with it!
-            print!("!");
-            return NodeIndex::TRUE;
+#[cfg(feature = "std")]
+impl GreedyWorkOutWhichAssertionsAreUsed {
+    /// Work out which of the num_assertions assertions are used to prune the trees (one per candidate other than the winner).
+    fn assertions_used(trees:&[TreeNodeShowingWhatAssertionsPrunedIt],num_assertions:usize,timeout:&mut TimeOut) -> Result<Vec<bool>,RaireError> {
+        let mut find_used = HeuristicWorkOutWhichAssertionsAreUsed::new(num_assertions);
+        for tree in trees {
+            find_used.add_tree_forced(tree,timeout)?;
         }
-        // compute an xdd function representing the constraints implied by the children of this node
-        let children = if node.children.is_empty() { NodeIndex::FALSE} else {
-            let mut res = NodeIndex::TRUE;
-            for child in &node.children {
-                let child_xdd = self.tree_to_xdd(child);
-                res = self.factory.and(res,child_xdd);
+        loop {
+            if timeout.quick_check_timeout() { return Err(RaireError::TimeoutTrimmingAssertions); }
+            let mut coverage = vec![0;num_assertions];
+            for tree in trees {
+                Self::add_coverage(&find_used.assertions_used,tree,&mut coverage);
             }
-            res
-        };
-        if children.is_true() {// short cut, nothing to do as the first pass dealt with it via children!
-            print!("^");
-            return NodeIndex::TRUE;
-        }
-        // compute an xdd function representing the constraints implied by the pruning_assertions of this node
-        let direct = {
-            let mut res = NodeIndex::FALSE;
-            for &a in &node.pruning_assertions {
-                let variable = self.variables.variable(a);
-                let a_xdd = self.factory.single_variable(variable);
-                res = self.factory.or(res,a_xdd);
+            match (0..num_assertions).filter(|&a|coverage[a]>0).max_by_key(|&a|(coverage[a],Reverse(a))) {
+                Some(best) => find_used.assertions_used[best]=true,
+                None => break, // everything is pruned.
             }
-            res
-        };
-        print!(".");
-        self.factory.or(direct,children)
-    }
-    fn add_tree_second_pass(&mut self,node:&TreeNodeShowingWhatAssertionsPrunedIt) {
-        let tree = self.tree_to_xdd(node);
-        self.required=self.factory.and(tree,self.required);
-        println!("xdd sub-size {}",self.factory.len());
-        let renamer = self.factory.gc([self.required]);
-        self.required=renamer.rename(self.required).expect("Lost main point");
-        println!("xdd sub-size {}",self.factory.len());
-    }
-    fn finish_second_pass(&mut self) -> Result<(),RaireError> {
-        println!("xdd size {}",self.factory.len());
-        let solution = self.factory.find_satisfying_solution_with_minimum_number_of_variables(self.required).ok_or(RaireError::InternalErrorTrimming)?;
-        for v in solution {
-            let assertion = self.variables.decode(v);
-            self.simple.assertions_used[assertion]=true;
         }
-        Ok(())
+        Ok(find_used.assertions_used)
     }
 
+    /// For each assertion, add to coverage the number of nodes it prunes that are not already pruned by used assertions.
+    /// Returns true iff the node is already pruned.
+    fn add_coverage(assertions_used:&[bool],node:&TreeNodeShowingWhatAssertionsPrunedIt,coverage:&mut [usize]) -> bool {
+        if node.pruning_assertions.iter().any(|&a|assertions_used[a]) { return true; }
+        let mut all_children_pruned = !node.children.is_empty();
+        for child in &node.children {
+            if !Self::add_coverage(assertions_used,child,coverage) { all_children_pruned=false; }
+        }
+        if all_children_pruned { return true; }
+        for &a in &node.pruning_assertions { coverage[a]+=1; }
+        false
+    }
+}
+
+/// The state of an assertion during the search for an optimal set.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+#[cfg(feature = "std")]
+enum AssertionChoice {
+    Undecided,
+    Used,
+    NotUsed,
 }
 
-/// Order XDD variables in inverse order that they are received.
-struct GetXDDVariable {
-    max_variable : u16,
-    next_variable : u16,
-    variable_of_assertion : Vec<Option<VariableIndex>>,
-    assertion_of_variable : Vec<usize>
+/// The result of evaluating whether a node is eliminated given a partial choice of assertions.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+#[cfg(feature = "std")]
+enum Eliminated {
+    Yes,
+    No,
+    Unknown,
 }
 
-impl GetXDDVariable {
-    fn new(len:usize) -> Self {
-        GetXDDVariable {
-            max_variable: u16::MAX - 10, // The -10 is probably not needed.
-            next_variable: u16::MAX - 10, // The -10 is probably not needed.
-            variable_of_assertion: vec![None;len],
-            assertion_of_variable: vec![],
+/// An exact method of computing which assertions are used, producing the smallest possible set of assertions
+/// sufficient to prune all the trees.
+///
+/// Each tree describes a constraint: a node is eliminated if any of its pruning assertions is used,
+/// or if it has children and all of its children are eliminated. For a [HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately]
+/// tree this is a minimum hitting set problem; for extended trees it is a slightly more general AND/OR problem.
+/// Either way it is NP-hard in general, so this is a straightforward branch and bound search, seeded with the
+/// answer from [HeuristicWorkOutWhichAssertionsAreUsed] as the initial upper bound. This means that in the
+/// (common) case where the heuristic is optimal, the search is just proving that nothing better exists.
+///
+/// This can be slow for large problems; it checks the timeout frequently.
+#[cfg(feature = "std")]
+struct OptimalWorkOutWhichAssertionsAreUsed<'a> {
+    trees : &'a [TreeNodeShowingWhatAssertionsPrunedIt],
+    choices : Vec<AssertionChoice>,
+    num_used : usize,
+    /// the number of nodes each assertion prunes. Used to choose which assertion to branch on first.
+    occurrences : Vec<usize>,
+    best : Vec<bool>,
+    best_num_used : usize,
+}
+
+#[cfg(feature = "std")]
+impl <'a> OptimalWorkOutWhichAssertionsAreUsed<'a> {
+    /// Work out which of the num_assertions assertions are used to prune the trees (one per candidate other than the winner).
+    fn assertions_used(trees:&'a [TreeNodeShowingWhatAssertionsPrunedIt],num_assertions:usize,timeout:&mut TimeOut) -> Result<Vec<bool>,RaireError> {
+        let best = HeuristicWorkOutWhichAssertionsAreUsed::assertions_used(trees,num_assertions,timeout)?;
+        let best_num_used = best.iter().filter(|&&used|used).count();
+        let mut choices = vec![AssertionChoice::Undecided;num_assertions];
+        // Assertions that are the sole way of pruning a node that must be pruned are definitely used.
+        let mut forced = HeuristicWorkOutWhichAssertionsAreUsed::new(num_assertions);
+        for tree in trees { forced.add_tree_forced(tree,timeout)?; }
+        for (choice,&is_forced) in choices.iter_mut().zip(forced.assertions_used.iter()) {
+            if is_forced { *choice=AssertionChoice::Used; }
+        }
+        let num_used = forced.assertions_used.iter().filter(|&&used|used).count();
+        let mut occurrences = vec![0;num_assertions];
+        fn count_occurrences(node:&TreeNodeShowingWhatAssertionsPrunedIt,occurrences:&mut [usize]) {
+            for &a in &node.pruning_assertions { occurrences[a]+=1; }
+            for child in &node.children { count_occurrences(child,occurrences); }
         }
+        for tree in trees { count_occurrences(tree,&mut occurrences); }
+        let mut work = OptimalWorkOutWhichAssertionsAreUsed{trees,choices,num_used,occurrences,best,best_num_used};
+        work.search(timeout)?;
+        Ok(work.best)
     }
-    fn variable(&mut self,assertion:usize) -> VariableIndex {
-        if let Some(v) = self.variable_of_assertion[assertion] {
-            v
-        } else {
-            let v = VariableIndex(self.next_variable);
-            self.next_variable-=1; // Should have some check against underflow.
-            self.variable_of_assertion[assertion]=Some(v);
-            self.assertion_of_variable.push(assertion);
-            v
+
+    /// See if the given node is eliminated given the current choices.
+    fn eliminated(&self,node:&TreeNodeShowingWhatAssertionsPrunedIt) -> Eliminated {
+        let mut directly = Eliminated::No;
+        for &a in &node.pruning_assertions {
+            match self.choices[a] {
+                AssertionChoice::Used => return Eliminated::Yes,
+                AssertionChoice::Undecided => directly=Eliminated::Unknown,
+                AssertionChoice::NotUsed => {}
+            }
+        }
+        let via_children = if node.children.is_empty() { Eliminated::No } else { self.all_eliminated(&node.children) };
+        match (directly,via_children) {
+            (_,Eliminated::Yes) => Eliminated::Yes,
+            (Eliminated::No,Eliminated::No) => Eliminated::No,
+            _ => Eliminated::Unknown,
         }
     }
-    fn decode(&self,variable:VariableIndex) -> usize {
-        let index = (self.max_variable-variable.0) as usize;
-        self.assertion_of_variable[index]
+
+    /// See if all the given nodes are eliminated given the current choices.
+    fn all_eliminated(&self,nodes:&[TreeNodeShowingWhatAssertionsPrunedIt]) -> Eliminated {
+        let mut res = Eliminated::Yes;
+        for node in nodes {
+            match self.eliminated(node) {
+                Eliminated::No => return Eliminated::No,
+                Eliminated::Unknown => res=Eliminated::Unknown,
+                Eliminated::Yes => {}
+            }
+        }
+        res
+    }
+
+    /// Find an undecided assertion that would help resolve the status of some node whose elimination is currently unknown.
+    /// Choose the one that prunes the most nodes, as it is most likely to be useful.
+    fn assertion_to_branch_on(&self,nodes:&[TreeNodeShowingWhatAssertionsPrunedIt]) -> Option<usize> {
+        for node in nodes {
+            if self.eliminated(node)==Eliminated::Unknown {
+                let direct = node.pruning_assertions.iter().cloned().filter(|&a|self.choices[a]==AssertionChoice::Undecided).max_by_key(|&a|self.occurrences[a]);
+                if direct.is_some() { return direct; }
+                if let Some(a) = self.assertion_to_branch_on(&node.children) { return Some(a); }
+            }
+        }
+        None
+    }
+
+    fn search(&mut self,timeout:&mut TimeOut) -> Result<(),RaireError> {
+        if timeout.quick_check_timeout() { return Err(RaireError::TimeoutTrimmingAssertions); }
+        match self.all_eliminated(self.trees) {
+            Eliminated::No => {} // no way of completing this.
+            Eliminated::Yes => {
+                if self.num_used<self.best_num_used {
+                    self.best_num_used=self.num_used;
+                    self.best=self.choices.iter().map(|&c|c==AssertionChoice::Used).collect();
+                }
+            }
+            Eliminated::Unknown => {
+                if self.num_used+1<self.best_num_used { // at least one more assertion is needed, so there is only any point continuing if that would be an improvement.
+                    let a = self.assertion_to_branch_on(self.trees).ok_or(RaireError::InternalErrorTrimming)?;
+                    self.choices[a]=AssertionChoice::Used;
+                    self.num_used+=1;
+                    self.search(timeout)?;
+                    self.num_used-=1;
+                    self.choices[a]=AssertionChoice::NotUsed;
+                    self.search(timeout)?;
+                    self.choices[a]=AssertionChoice::Undecided;
+                }
+            }
+        }
+        Ok(())
     }
-    fn len(&self) -> u16 { self.max_variable-self.next_variable }
 }
-*/
 
 #[cfg(test)]
 mod tests {
     use crate::assertions::{Assertion, NotEliminatedBefore, NotEliminatedNext};
     use crate::irv::CandidateIndex;
+    use crate::RaireError;
     use crate::timeout::TimeOut;
-    use crate::tree_showing_what_assertions_pruned_leaves::{HowFarToContinueSearchTreeWhenPruningAssertionFound, TreeNodeShowingWhatAssertionsPrunedIt};
+    use crate::tree_showing_what_assertions_pruned_leaves::{GreedyWorkOutWhichAssertionsAreUsed, HeuristicWorkOutWhichAssertionsAreUsed, HowFarToContinueSearchTreeWhenPruningAssertionFound, OptimalWorkOutWhichAssertionsAreUsed, TreeNodeShowingWhatAssertionsPrunedIt};
 
     /// Get the assertions listed in "A guide to RAIRE".
     fn raire_guide_assertions() -> Vec<Assertion> {
         vec![
-            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(0),loser:CandidateIndex(1),continuing:vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(2),CandidateIndex(3)]}),
-            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(0),loser:CandidateIndex(3),continuing:vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(3)]}),
-            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(2),loser:CandidateIndex(0),continuing:vec![CandidateIndex(0),CandidateIndex(2)]}),
-            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(2),loser:CandidateIndex(3),continuing:vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(3)]}),
+            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(0),loser:CandidateIndex(1),continuing:vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(2),CandidateIndex(3)].into()}),
+            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(0),loser:CandidateIndex(3),continuing:vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(3)].into()}),
+            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(2),loser:CandidateIndex(0),continuing:vec![CandidateIndex(0),CandidateIndex(2)].into()}),
+            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(2),loser:CandidateIndex(3),continuing:vec![CandidateIndex(0),CandidateIndex(2),CandidateIndex(3)].into()}),
             Assertion::NEB(NotEliminatedBefore{winner:CandidateIndex(2),loser:CandidateIndex(1)}),
-            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(0),loser:CandidateIndex(3),continuing:vec![CandidateIndex(0),CandidateIndex(3)]}),
+            Assertion::NEN(NotEliminatedNext{winner:CandidateIndex(0),loser:CandidateIndex(3),continuing:vec![CandidateIndex(0),CandidateIndex(3)].into()}),
         ]
     }
 
@@ -395,4 +776,96 @@ mod tests {
         assert_eq!(0,tree3.children[2].children[1].pruning_assertions.len());
         assert_eq!(vec![0],tree3.children[2].children[1].children[0].pruning_assertions);
     }
+
+    #[test]
+    fn test_node_limit() {
+        let all_assertions = raire_guide_assertions();
+        let relevant_assertions : Vec<usize> = (0..all_assertions.len()).collect();
+        let mut timeout = TimeOut::never();
+        let unlimited = TreeNodeShowingWhatAssertionsPrunedIt::new(&[],CandidateIndex(3),&relevant_assertions,&all_assertions,4,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,&mut timeout).unwrap();
+        let nodes = unlimited.count_nodes();
+        assert_eq!(7,nodes);
+        let limited = TreeNodeShowingWhatAssertionsPrunedIt::new_with_node_limit(&[],CandidateIndex(3),&relevant_assertions,&all_assertions,4,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,Some(nodes),&mut timeout).unwrap();
+        assert_eq!(nodes,limited.count_nodes());
+        assert!(matches!(TreeNodeShowingWhatAssertionsPrunedIt::new_with_node_limit(&[],CandidateIndex(3),&relevant_assertions,&all_assertions,4,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,Some(nodes-1),&mut timeout),Err(RaireError::TreeTooLarge(6))));
+    }
+
+    #[test]
+    fn test_invalid_parent_suffix() {
+        let all_assertions = raire_guide_assertions();
+        let relevant_assertions : Vec<usize> = (0..all_assertions.len()).collect();
+        let mut timeout = TimeOut::never();
+        let mut tree = |suffix:&[CandidateIndex],candidate:u32| TreeNodeShowingWhatAssertionsPrunedIt::new(suffix,CandidateIndex(candidate),&relevant_assertions,&all_assertions,4,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,&mut timeout).err();
+        let all = [CandidateIndex(0),CandidateIndex(1),CandidateIndex(2),CandidateIndex(3)];
+        assert!(matches!(tree(&all,0),Some(RaireError::InvalidCandidateNumber)));
+        assert!(matches!(tree(&[CandidateIndex(200)],0),Some(RaireError::InvalidCandidateNumber)));
+        assert!(matches!(tree(&[],200),Some(RaireError::InvalidCandidateNumber)));
+        assert!(matches!(tree(&[CandidateIndex(1),CandidateIndex(1)],0),Some(RaireError::InvalidCandidateNumber)));
+        assert!(matches!(tree(&[CandidateIndex(1)],1),Some(RaireError::InvalidCandidateNumber)));
+        assert!(tree(&all[2..],1).is_none());
+        assert!(tree(&all[1..],0).is_none());
+    }
+
+    #[test]
+    fn test_internal_error_context() {
+        let all_assertions = raire_guide_assertions();
+        let relevant_assertions : Vec<usize> = (0..all_assertions.len()).collect();
+        let tree = |candidate:u32|TreeNodeShowingWhatAssertionsPrunedIt::new(&[],CandidateIndex(candidate),&relevant_assertions,&all_assertions,4,HowFarToContinueSearchTreeWhenPruningAssertionFound::StopImmediately,&mut TimeOut::never()).unwrap();
+        let tree0 = tree(0);
+        assert_eq!(vec![2,3,4],tree0.pruning_assertions_used());
+        match tree0.ruled_out_winner_error() {
+            RaireError::InternalErrorRuledOutWinner{winner,elimination_order,assertions} => {
+                assert_eq!(Some(CandidateIndex(0)),winner);
+                assert_eq!(vec![CandidateIndex(0)],elimination_order);
+                assert_eq!(vec![2,3,4],assertions);
+            }
+            e => panic!("Unexpected error {e}"),
+        }
+        match tree(2).didnt_rule_out_loser_error() {
+            RaireError::InternalErrorDidntRuleOutLoser{candidate,elimination_order} => {
+                assert_eq!(Some(CandidateIndex(2)),candidate);
+                assert_eq!(Some(&CandidateIndex(2)),elimination_order.last());
+            }
+            e => panic!("Unexpected error {e}"),
+        }
+    }
+
+    /// Make a tree with a root node with no pruning assertions, and children pruned by the given assertions.
+    fn tree_with_pruned_children(children:&[&[usize]]) -> TreeNodeShowingWhatAssertionsPrunedIt {
+        let children = children.iter().enumerate().map(|(i,&pruning)|TreeNodeShowingWhatAssertionsPrunedIt{candidate_being_eliminated_at_this_node:CandidateIndex(i as u32+1),pruning_assertions:pruning.to_vec(),children:vec![],valid:false}).collect();
+        TreeNodeShowingWhatAssertionsPrunedIt{candidate_being_eliminated_at_this_node:CandidateIndex(0),pruning_assertions:vec![],children,valid:false}
+    }
+
+    #[test]
+    fn test_optimal_better_than_heuristic() {
+        // assertion 1 alone prunes everything, but the heuristic takes assertion 0 for the first node.
+        let trees = vec![tree_with_pruned_children(&[&[0,1],&[1,2],&[1,3]])];
+        let heuristic = HeuristicWorkOutWhichAssertionsAreUsed::assertions_used(&trees,4,&mut TimeOut::never()).unwrap();
+        assert_eq!(vec![true,true,false,false],heuristic);
+        let optimal = OptimalWorkOutWhichAssertionsAreUsed::assertions_used(&trees,4,&mut TimeOut::never()).unwrap();
+        assert_eq!(vec![false,true,false,false],optimal);
+        // assertion 2 is forced, and 0 is needed for the first node, but the heuristic takes 1 for the first node, and then needs 0 for the last.
+        let trees = vec![tree_with_pruned_children(&[&[1,0],&[2],&[0,3]])];
+        let optimal = OptimalWorkOutWhichAssertionsAreUsed::assertions_used(&trees,4,&mut TimeOut::never()).unwrap();
+        assert_eq!(vec![true,false,true,false],optimal);
+        assert!(OptimalWorkOutWhichAssertionsAreUsed::assertions_used(&trees,4,&mut TimeOut::new(Some(1),None)).is_err());
+    }
+
+    #[test]
+    fn test_greedy() {
+        let trees = vec![tree_with_pruned_children(&[&[0,1],&[1,2],&[1,3]])];
+        let greedy = GreedyWorkOutWhichAssertionsAreUsed::assertions_used(&trees,4,&mut TimeOut::never()).unwrap();
+        assert_eq!(vec![false,true,false,false],greedy);
+        // forced assertions are always included.
+        let trees = vec![tree_with_pruned_children(&[&[1,0],&[2],&[0,3]])];
+        let greedy = GreedyWorkOutWhichAssertionsAreUsed::assertions_used(&trees,4,&mut TimeOut::never()).unwrap();
+        assert_eq!(vec![true,false,true,false],greedy);
+        // a node pruned by all its children doesn't need its own assertion
+        let mut tree = tree_with_pruned_children(&[&[2],&[3]]);
+        tree.pruning_assertions=vec![0];
+        let trees = vec![tree,tree_with_pruned_children(&[&[2],&[3],&[1]])];
+        let greedy = GreedyWorkOutWhichAssertionsAreUsed::assertions_used(&trees,4,&mut TimeOut::never()).unwrap();
+        assert_eq!(vec![false,true,true,true],greedy);
+    }
 }
+
@@ -0,0 +1,114 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+//! Independent checking of a set of assertions against the ballots, so that public observers can audit the auditor.
+//!
+//! This checks that each assertion's margin is what the ballots say it is, and that the assertions together
+//! rule out every winner other than the claimed one. It does not use the code that generates assertions, so
+//! it can check assertions produced by other implementations.
+
+use serde::Deserialize;
+use serde::Serialize;
+use crate::assertions::{Assertion, EliminationOrder};
+#[cfg(feature = "std")]
+use crate::assertions::verify_assertions_rule_out_all_other_winners;
+use crate::irv::{BallotPaperCount, CandidateIndex, Vote};
+#[cfg(feature = "std")]
+use crate::irv::Votes;
+#[cfg(feature = "std")]
+use crate::RaireError;
+#[cfg(feature = "std")]
+use crate::timeout::TimeOut;
+use alloc::vec::Vec;
+
+/// An assertion to be checked, and optionally the margin claimed for it.
+/// This has the same JSON representation as [crate::assertions::AssertionAndDifficulty], other fields being ignored.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct ClaimedAssertion {
+    pub assertion : Assertion,
+    #[serde(default,skip_serializing_if="Option::is_none")]
+    pub margin : Option<BallotPaperCount>,
+}
+
+/// The result of checking one assertion.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct AssertionCheck {
+    pub assertion : Assertion,
+    #[serde(default,skip_serializing_if="Option::is_none")]
+    pub claimed_margin : Option<BallotPaperCount>,
+    /// The winner's tally minus the loser's tally, computed from the ballots. Negative if the assertion is false.
+    pub computed_margin : i64,
+    /// True if the assertion is true for the ballots, that is, the computed margin is positive.
+    pub holds : bool,
+    /// True if there was no claimed margin, or it equals the computed margin.
+    pub margin_correct : bool,
+}
+
+/// The result of checking a set of assertions.
+#[derive(Clone,Debug,Serialize,Deserialize)]
+pub struct VerificationReport {
+    pub num_candidates : u32,
+    pub claimed_winner : CandidateIndex,
+    /// The possible winners of the election, from the ballots.
+    pub computed_winners : Vec<CandidateIndex>,
+    pub assertions : Vec<AssertionCheck>,
+    /// True if every claimed margin is correct.
+    pub margins_correct : bool,
+    /// True if every assertion holds for the ballots.
+    pub assertions_hold : bool,
+    /// True if the assertions rule out every winner other than the claimed winner.
+    pub rules_out_other_winners : bool,
+    /// If the assertions do not rule out every other winner, an elimination order (first eliminated to winner) they allow.
+    #[serde(default,skip_serializing_if="Option::is_none")]
+    pub counterexample : Option<EliminationOrder>,
+    /// True if all of the above checks pass, and the claimed winner is the only computed winner.
+    pub verified : bool,
+}
+
+/// Check one assertion's margin against the ballots. Unlike [verify_assertions], this does not need [crate::irv::Votes] or to
+/// run the election, so it is available without the `std` feature, and the votes can be checked a batch at a time.
+pub fn check_assertion(assertion:&ClaimedAssertion,votes:&[Vote]) -> AssertionCheck {
+    let signed_margin : i128 = votes.iter().map(|v|v.n.0 as i128*assertion.assertion.vote_score(&v.prefs) as i128).sum();
+    let computed_margin = signed_margin.clamp(i64::MIN as i128,i64::MAX as i128) as i64; // only clamped for more than 2^63 ballots.
+    AssertionCheck{
+        assertion: assertion.assertion.clone(),
+        claimed_margin: assertion.margin,
+        computed_margin,
+        holds: signed_margin>0,
+        margin_correct: assertion.margin.is_none_or(|m|m.0 as i128==signed_margin),
+    }
+}
+
+/// Check a set of assertions that claim to show that `claimed_winner` won the election with the given ballots.
+#[cfg(feature = "std")]
+pub fn verify_assertions(votes:&Votes,claimed_winner:CandidateIndex,assertions:&[ClaimedAssertion],timeout:&mut TimeOut) -> Result<VerificationReport,RaireError> {
+    let num_candidates = votes.num_candidates();
+    for a in assertions { a.assertion.validate(num_candidates)?; }
+    if claimed_winner.0>=num_candidates { return Err(RaireError::InvalidCandidateNumberIn{ context: "the claimed winner".to_string(), candidate: claimed_winner, num_candidates }); }
+    let computed_winners = votes.run_election(timeout)?.possible_winners;
+    let checks : Vec<AssertionCheck> = assertions.iter().map(|a|check_assertion(a,&votes.votes)).collect();
+    let all_assertions : Vec<Assertion> = assertions.iter().map(|a|a.assertion.clone()).collect();
+    let counterexample = verify_assertions_rule_out_all_other_winners(&all_assertions,claimed_winner,num_candidates).err();
+    let margins_correct = checks.iter().all(|c|c.margin_correct);
+    let assertions_hold = checks.iter().all(|c|c.holds);
+    let rules_out_other_winners = counterexample.is_none();
+    Ok(VerificationReport{
+        num_candidates,
+        claimed_winner,
+        verified: margins_correct && assertions_hold && rules_out_other_winners && computed_winners==[claimed_winner],
+        computed_winners,
+        assertions: checks,
+        margins_correct,
+        assertions_hold,
+        rules_out_other_winners,
+        counterexample,
+    })
+}
@@ -0,0 +1,88 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The example of chapter 6 of the "Guide to RAIRE" document, used by the tests of the guide's examples and of other modules.
+
+#![allow(dead_code)] // each test file uses some of these.
+
+use serde_json::json;
+use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
+use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
+use raire::raire_algorithm::{RaireResult, TrimAlgorithm};
+use raire::RaireProblem;
+use raire::schema::FORMAT_VERSION;
+
+pub const A : CandidateIndex = CandidateIndex(0); // Alice
+pub const B : CandidateIndex = CandidateIndex(1); // Bob
+pub const C : CandidateIndex = CandidateIndex(2); // Chuan
+pub const D : CandidateIndex = CandidateIndex(3); // Diego
+
+/// Get the votes in Example 10 (at the time of writing), used in examples in chapter 6, "Using RAIRE to generate assertions".
+pub fn get_votes() -> Votes {
+    let votes = vec![
+        Vote{ n: BallotPaperCount(5000), prefs: vec![C,B,A]},
+        Vote{ n: BallotPaperCount(1000), prefs: vec![B,C,D]},
+        Vote{ n: BallotPaperCount(1500), prefs: vec![D,A]},
+        Vote{ n: BallotPaperCount(4000), prefs: vec![A,D]},
+        Vote{ n: BallotPaperCount(2000), prefs: vec![D]},
+    ];
+    Votes::new(votes, 4).unwrap()
+}
+
+/// The audit used in the examples.
+pub const AUDIT : BallotComparisonOneOnDilutedMargin = BallotComparisonOneOnDilutedMargin { total_auditable_ballots : BallotPaperCount(13500) };
+
+/// The problem of the examples, Chuan winning, with the given audit and every option left at its default.
+/// Tests change it with struct update syntax, e.g. `RaireProblem{ seats: Some(2), ..guide_problem(audit) }`.
+pub fn guide_problem(audit:Audit) -> RaireProblem {
+    RaireProblem {
+        metadata: json!({}),
+        num_candidates: 4,
+        votes: get_votes().votes,
+        winner: Some(C),
+        audit,
+        seats: None,
+        elimination_rule: None,
+        tie_resolution: None,
+        reported_elimination_order: None,
+        trim_algorithm: None,
+        trim_selection_algorithm: None,
+        cross_check_trimming: None,
+        two_phase: None,
+        max_tree_nodes: None,
+        extended_trim: None,
+        difficulty_estimate: None,
+        time_limit_seconds: None,
+        max_memory_mb: None,
+        risk_limit: None,
+        error_rates: None,
+        partial_on_timeout: None,
+        winner_only_fallback: None,
+        stop_when_difficulty_at_most: None,
+        objective: None,
+        deterministic: None,
+        include_tallies: None,
+        difficulty_decimal_places: None,
+        exact_arithmetic: None,
+        assertion_types: None,
+        ranked_votes: None,
+        ballot_interpretation: None,
+        vote_strata: None,
+        adjust_for_phantoms: None,
+        warm_start_assertions: None,
+        format_version: FORMAT_VERSION,
+    }
+}
+
+/// Solve the example with the default trimming.
+pub fn get_solution_minimize_tree() -> RaireResult {
+    let problem = RaireProblem{ trim_algorithm: Some(TrimAlgorithm::MinimizeTree), ..guide_problem(Audit::OneOnMargin(AUDIT)) };
+    problem.solve().solution.unwrap()
+}
@@ -0,0 +1,48 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test tracking the progress of an audit as ballots are sampled.
+
+use raire::audit_type::{AuditType, BallotComparisonALPHA};
+use raire::audit_progress::{AuditProgress, BallotInterpretation};
+use raire::irv::{BallotPaperCount, CandidateIndex};
+
+mod common;
+use common::{get_votes, AUDIT, get_solution_minimize_tree};
+
+#[test]
+/// Test that the audit of the example is complete once enough ballots agreeing with their CVRs are sampled, and that overstatements are counted.
+fn test_audit_progress() {
+    let votes = get_votes();
+    let solution = get_solution_minimize_tree();
+    for a in &solution.assertions { // the ballot level scores add up to the margin.
+        assert_eq!(a.margin.0 as i64,votes.votes.iter().map(|v|v.n.0 as i64*a.assertion.vote_score(&v.prefs) as i64).sum::<i64>());
+    }
+    let test = BallotComparisonALPHA{ confidence: 0.05, total_auditable_ballots: AUDIT.total_auditable_ballots, d: 100.0, eta0: None };
+    let hardest = solution.assertions.iter().map(|a|test.difficulty(BallotPaperCount(a.margin.0),BallotPaperCount(0))).fold(0.0,f64::max) as usize;
+    let ballots : Vec<BallotInterpretation> = votes.votes.iter().cycle().take(hardest).map(|v|BallotInterpretation{ ballot_id: None, cvr: v.prefs.clone(), paper: v.prefs.clone() }).collect();
+    let mut progress = AuditProgress::new(&solution,&test);
+    for ballot in &ballots[..hardest-1] { progress.observe(ballot); }
+    let report = progress.report();
+    assert!(!report.complete);
+    assert!(!report.needs_escalation.is_empty());
+    assert!(report.assertions.iter().all(|a|a.confirmed==(a.p_value<=0.05)));
+    progress.observe(&ballots[hardest-1]);
+    let report = progress.report();
+    assert!(report.complete);
+    assert_eq!(hardest,report.ballots_sampled);
+    // a ballot whose CVR says Chuan but the paper is blank overstates the margin of assertions where Chuan is the winner.
+    let mut progress = AuditProgress::new(&solution,&test);
+    progress.observe(&BallotInterpretation{ ballot_id: Some("1-1".to_string()), cvr: vec![CandidateIndex(2)], paper: vec![] });
+    let report = progress.report();
+    for (a,p) in solution.assertions.iter().zip(report.assertions.iter()) {
+        assert_eq!(a.assertion.vote_score(&[CandidateIndex(2)]).max(0) as usize,p.one_vote_overstatements);
+        assert!(p.p_value<=1.0);
+    }
+}
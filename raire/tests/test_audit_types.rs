@@ -0,0 +1,160 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Test the audit types not covered by the examples in the papers.
+
+use serde_json::json;
+use raire::assertions::Assertion;
+use raire::audit_type::{Audit, AuditStratum, AuditType, BallotComparisonALPHA, BallotComparisonMACRO, BallotComparisonOneOnDilutedMargin, BallotPollingBRAVO, StratifiedAudit, WithPhantoms, MAX_ALLOCATION_STEPS};
+use raire::irv::BallotPaperCount;
+use raire::{RaireError, RaireProblem};
+
+mod common;
+use common::{AUDIT, get_votes, guide_problem, get_solution_minimize_tree};
+
+const ALPHA : BallotComparisonALPHA = BallotComparisonALPHA{ confidence: 0.05, total_auditable_ballots: BallotPaperCount(1000), d: 100.0, eta0: None };
+
+#[test]
+/// Test that ALPHA difficulties are infinite for a loser at least as big as the winner, fall as the margin widens, and match the formula when η is fixed.
+fn test_alpha() {
+    assert_eq!(f64::INFINITY,ALPHA.difficulty(BallotPaperCount(400),BallotPaperCount(400)));
+    assert_eq!(f64::INFINITY,ALPHA.difficulty(BallotPaperCount(300),BallotPaperCount(400)));
+    let wide = ALPHA.difficulty(BallotPaperCount(550),BallotPaperCount(450));
+    let narrow = ALPHA.difficulty(BallotPaperCount(510),BallotPaperCount(490));
+    assert!(wide.is_finite() && narrow.is_finite());
+    assert!(wide<narrow);
+    // If η is fixed (d huge) at the upper bound, the martingale grows by 1/(1-v/2) per ballot, so n = ⌈ln(1/α)/ln(1/(1-v/2))⌉.
+    let v = 0.1;
+    let fixed = BallotComparisonALPHA{ d: 1e15, eta0: Some(2.0/(2.0-v)), ..ALPHA };
+    let expected = ((1.0/0.05f64).ln()/(1.0/(1.0-v/2.0)).ln()).ceil();
+    assert_eq!(expected,fixed.difficulty(BallotPaperCount(550),BallotPaperCount(450)));
+    // shrinking towards the observed mean makes it more conservative.
+    assert!(expected<wide);
+    // a very small margin needs more than the number of draws simulated.
+    assert!(ALPHA.expected_sample_size(1e-4)>10000.0);
+}
+
+#[test]
+/// Test that an ALPHA audit read from JSON gets the default d and eta0.
+fn test_alpha_json() {
+    let audit : Audit = serde_json::from_str(r#"{"type":"ALPHA","confidence":0.05,"total_auditable_ballots":1000}"#).unwrap();
+    match audit {
+        Audit::ALPHA(alpha) => {
+            assert_eq!(100.0,alpha.d);
+            assert_eq!(None,alpha.eta0);
+            assert_eq!(ALPHA.difficulty(BallotPaperCount(550),BallotPaperCount(450)),alpha.difficulty(BallotPaperCount(550),BallotPaperCount(450)));
+        }
+        _ => panic!("Expecting ALPHA"),
+    }
+}
+
+#[test]
+/// Test that BRAVO's difficulty when the loser has no votes is the limit as the loser's tally goes to zero, not NaN.
+fn test_bravo_loser_with_no_votes() {
+    let bravo = BallotPollingBRAVO{ confidence: 0.05, total_auditable_ballots: BallotPaperCount(1000) };
+    let expected = (0.5*2f64.ln()-0.05f64.ln())/(100.0*2f64.ln()/1000.0);
+    assert_eq!(expected,bravo.difficulty(BallotPaperCount(100),BallotPaperCount(0)));
+    assert!((expected-48.2193).abs()<1e-4);
+    // the limit of the nearby difficulties.
+    let near = |l:usize|bravo.bravo_function(BallotPaperCount(1_000_000),BallotPaperCount(l),BallotPaperCount(10_000_000));
+    assert!(near(0)<near(1) && (near(1)-near(0))/near(0)<1e-4);
+}
+
+#[test]
+/// Test that a stratified audit of the example with one stratum is the same as the unstratified audit, and with CVR and polled strata composes their difficulties.
+fn test_stratified_audit() {
+    let bravo = |total:usize|Audit::BRAVO(BallotPollingBRAVO{ confidence: 0.05, total_auditable_ballots: BallotPaperCount(total) });
+    let macro_audit = |total:usize|Audit::MACRO(BallotComparisonMACRO{ confidence: 0.05, error_inflation_factor: 1.1, total_auditable_ballots: BallotPaperCount(total) });
+    let stratified = |strata:Vec<(&str,Audit)>|Audit::Stratified(StratifiedAudit{ strata: strata.into_iter().map(|(name,audit)|AuditStratum{ name: name.to_string(), audit }).collect(), allocation_steps: 20 });
+    let problem = |audit:Audit,vote_strata:Option<Vec<usize>>| RaireProblem{ vote_strata, ..guide_problem(audit) };
+    // a single stratum is the same as the unstratified audit.
+    let plain = problem(bravo(13500),None).solve().solution.unwrap();
+    let single = problem(stratified(vec![("all",bravo(13500))]),Some(vec![0;5])).solve().solution.unwrap();
+    assert_eq!(plain.difficulty,single.difficulty);
+    // the first three vote records have CVRs, the other 6000 ballots are polled.
+    let mixed = problem(stratified(vec![("CVR",macro_audit(7500)),("no CVR",bravo(6000))]),Some(vec![0,0,0,1,1]));
+    let votes = mixed.counted_votes().unwrap();
+    assert_eq!(vec!["CVR","no CVR"],votes.strata().iter().map(|s|s.name.as_str()).collect::<Vec<_>>());
+    assert_eq!(BallotPaperCount(13500),votes.total_votes());
+    let result = mixed.clone().solve().solution.unwrap();
+    assert!(result.difficulty.is_finite());
+    for a in &result.assertions {
+        let difficulty = match &a.assertion { Assertion::NEB(neb) => neb.difficulty(&votes,&mixed.audit).0, Assertion::NEN(nen) => nen.difficulty(&votes,&mixed.audit) };
+        assert_eq!(difficulty,a.difficulty);
+    }
+    // the stratum's share of the margin must be confirmed by its own audit, so halving the polled stratum makes it harder.
+    let stratified_difficulty = |strata:&[(usize,usize)],audit:&Audit|audit.stratified_difficulty(&strata.iter().map(|&(w,l)|(BallotPaperCount(w),BallotPaperCount(l))).collect::<Vec<_>>());
+    let audit = stratified(vec![("CVR",macro_audit(7500)),("no CVR",bravo(6000))]);
+    assert!(stratified_difficulty(&[(4000,3000),(3000,2000)],&audit)<stratified_difficulty(&[(4000,3000),(1500,1000)],&audit));
+    assert_eq!(f64::INFINITY,stratified_difficulty(&[(3000,4000),(2000,1000)],&audit)); // no margin overall.
+    // the strata must match the audit.
+    assert!(matches!(problem(stratified(vec![("CVR",macro_audit(7500)),("no CVR",bravo(6000))]),None).solve().solution,Err(RaireError::WrongNumberOfVoteStrata{ expected: 5, found: 0 })));
+    assert!(matches!(problem(stratified(vec![("CVR",macro_audit(7500)),("no CVR",bravo(6000))]),Some(vec![0,0,2,1,1])).solve().solution,Err(RaireError::InvalidStratum{ vote: 2, stratum: 2, num_strata: 2 })));
+    assert!(matches!(problem(Audit::OneOnMargin(AUDIT),Some(vec![0;5])).solve().solution,Err(RaireError::InvalidStratum{ num_strata: 0, .. })));
+    assert!(matches!(problem(stratified(vec![("CVR",macro_audit(7500)),("no CVR",bravo(5000))]),Some(vec![0,0,0,1,1])).solve().solution,Err(RaireError::StratumExceedsBound{ stratum, ballots: BallotPaperCount(6000), bound: BallotPaperCount(5000) }) if stratum=="no CVR"));
+    // in JSON.
+    let from_json : RaireProblem = serde_json::from_value(json!({"metadata":{},"num_candidates":4,"votes":serde_json::to_value(get_votes().votes).unwrap(),"winner":2,"vote_strata":[0,0,0,1,1],
+        "audit":{"type":"Stratified","strata":[{"name":"CVR","audit":{"type":"MACRO","confidence":0.05,"error_inflation_factor":1.1,"total_auditable_ballots":7500}},{"name":"no CVR","audit":{"type":"BRAVO","confidence":0.05,"total_auditable_ballots":6000}}]}})).unwrap();
+    assert_eq!(result.difficulty,from_json.solve().solution.unwrap().difficulty);
+}
+
+#[test]
+/// Test that a stratified audit finds the best division of the margin without trying every one, so many strata are fast, and rejects too many allocation steps.
+fn test_stratified_audit_many_strata() {
+    let bravo = |total:usize|Audit::BRAVO(BallotPollingBRAVO{ confidence: 0.05, total_auditable_ballots: BallotPaperCount(total) });
+    let macro_audit = |total:usize|Audit::MACRO(BallotComparisonMACRO{ confidence: 0.05, error_inflation_factor: 1.1, total_auditable_ballots: BallotPaperCount(total) });
+    let stratified = |strata:Vec<Audit>,allocation_steps:usize|StratifiedAudit{ strata: strata.into_iter().enumerate().map(|(i,audit)|AuditStratum{ name: i.to_string(), audit }).collect(), allocation_steps };
+    let tallies = |t:&[(usize,usize)]|t.iter().map(|&(w,l)|(BallotPaperCount(w),BallotPaperCount(l))).collect::<Vec<_>>();
+    // the same as trying every division, for three strata.
+    let audit = stratified(vec![macro_audit(1000),bravo(2000),macro_audit(3000)],10);
+    let strata_tallies = tallies(&[(600,300),(1000,900),(1500,1400)]);
+    let margin = 500;
+    let share_difficulty = |stratum:usize,steps:usize|{
+        let share = margin*steps/10;
+        let (w,l) = strata_tallies[stratum];
+        let loser = (w.0+l.0).saturating_sub(share)/2;
+        let difficulty = audit.strata[stratum].audit.difficulty(BallotPaperCount(loser+share),BallotPaperCount(loser));
+        if difficulty.is_nan() { f64::INFINITY } else { difficulty }
+    };
+    let mut every_division = f64::INFINITY;
+    for first in 0..=10 { for second in 0..=10-first {
+        every_division = every_division.min(share_difficulty(0,first)+share_difficulty(1,second)+share_difficulty(2,10-first-second));
+    }}
+    assert!((every_division-audit.stratified_difficulty(&strata_tallies)).abs()<=1e-9*every_division);
+    // twenty strata, which would be C(39,19) (about 7×10^10) divisions to try.
+    let many = stratified((0..20).map(|i|if i%2==0 { macro_audit(1000) } else { bravo(1000) }).collect(),20);
+    assert!(many.stratified_difficulty(&tallies(&[(600,300);20])).is_finite());
+    // allocation steps must be between 1 and MAX_ALLOCATION_STEPS.
+    assert!(stratified(vec![bravo(1000)],MAX_ALLOCATION_STEPS).check_allocation_steps().is_ok());
+    assert!(matches!(stratified(vec![bravo(1000)],MAX_ALLOCATION_STEPS+1).check_allocation_steps(),Err(RaireError::InvalidAllocationSteps{ steps: 101, max: 100 })));
+    let problem = RaireProblem{ vote_strata: Some(vec![0;5]), ..guide_problem(Audit::Stratified(stratified(vec![bravo(13500)],0))) };
+    assert!(matches!(problem.solve().solution,Err(RaireError::InvalidAllocationSteps{ steps: 0, .. })));
+}
+
+#[test]
+/// Test that phantom ballots, in the manifest but without CVRs, reduce the margins of the example's assertions if asked, and that a manifest smaller than the CVRs is an error.
+fn test_phantom_ballots() {
+    let problem = |total:usize,adjust_for_phantoms:Option<bool>| RaireProblem{ adjust_for_phantoms, ..guide_problem(Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(total) })) };
+    // 100 ballots in the manifest have no CVR. The hardest assertion has margin 500, reduced to 400.
+    let unadjusted = problem(13600,None).solve().solution.unwrap();
+    assert_eq!(None,unadjusted.phantoms);
+    assert_eq!(13600.0/500.0,unadjusted.difficulty);
+    let adjusted = problem(13600,Some(true)).solve().solution.unwrap();
+    assert_eq!(Some(BallotPaperCount(100)),adjusted.phantoms);
+    assert_eq!(13600.0/400.0,adjusted.difficulty);
+    assert_eq!(BallotPaperCount(500),adjusted.margin); // the margin in the votes.
+    // no phantoms makes no difference.
+    assert_eq!(get_solution_minimize_tree().difficulty,problem(13500,Some(true)).solve().solution.unwrap().difficulty);
+    // a manifest with fewer ballots than there are CVRs is an error.
+    assert!(matches!(problem(13000,None).solve().solution,Err(RaireError::ManifestSmallerThanVotes{ manifest: BallotPaperCount(13000), votes: BallotPaperCount(13500) })));
+    let audit = Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(13600) });
+    let with_phantoms = WithPhantoms::new(&audit,&get_votes()).unwrap();
+    assert_eq!(f64::INFINITY,with_phantoms.difficulty(BallotPaperCount(1100),BallotPaperCount(1000))); // the phantoms could all be for the loser.
+}
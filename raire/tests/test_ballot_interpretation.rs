@@ -0,0 +1,50 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the rules for interpreting ranked ballots with skipped, repeated and overvoted ranks.
+
+use serde_json::json;
+use raire::irv::{BallotPaperCount, CandidateIndex};
+use raire::{RaireError, RaireProblem};
+use raire::ballot_interpretation::{BallotInterpretationRules, DuplicateRule, OvervoteRule, RankedVote};
+
+mod common;
+use common::{A, B, C, D, get_solution_minimize_tree};
+
+#[test]
+/// Test each rule on single ballots, and that a rule exhausting ballots at a skipped rank changes the winner of the example.
+fn test_ballot_interpretation_rules() {
+    let ranked = |ranks:Vec<Vec<CandidateIndex>>|RankedVote{ n: BallotPaperCount(1), ranks };
+    let vote = ranked(vec![vec![C],vec![],vec![B,B],vec![A,D],vec![A]]);
+    assert_eq!(vec![C,B],BallotInterpretationRules::default().interpret(&vote).prefs);
+    assert_eq!(vec![C,B,A],BallotInterpretationRules{ overvote: OvervoteRule::SkipRank, ..Default::default() }.interpret(&vote).prefs);
+    assert_eq!(vec![C],BallotInterpretationRules{ max_consecutive_skipped_ranks: Some(0), ..Default::default() }.interpret(&vote).prefs);
+    let repeated = ranked(vec![vec![A],vec![A],vec![D]]);
+    assert_eq!(vec![A,D],BallotInterpretationRules::default().interpret(&repeated).prefs);
+    assert_eq!(vec![A],BallotInterpretationRules{ duplicate: DuplicateRule::Exhaust, ..Default::default() }.interpret(&repeated).prefs);
+    // The guide example, with the 1000 ballots for Bob, Chuan then Diego skipping a rank before Chuan.
+    let problem = json!({"metadata":{},"num_candidates":4,"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13500},
+        "votes":[{"n":5000,"prefs":[2,1,0]},{"n":1500,"prefs":[3,0]},{"n":4000,"prefs":[0,3]},{"n":2000,"prefs":[3]}],
+        "ranked_votes":[{"n":1000,"ranks":[[1],[],[2],[3]]}]});
+    let problem : RaireProblem = serde_json::from_value(problem).unwrap();
+    let expected = get_solution_minimize_tree();
+    let solution = problem.clone().solve().solution.unwrap();
+    assert_eq!(expected.difficulty,solution.difficulty);
+    // if a skipped rank exhausts the ballot, those voters' preferences for Chuan are not counted, and Alice wins.
+    let mut strict = problem.clone();
+    strict.ballot_interpretation = Some(BallotInterpretationRules{ max_consecutive_skipped_ranks: Some(0), ..Default::default() });
+    assert!(strict.interpreted_votes().iter().any(|v|v.n==BallotPaperCount(1000) && v.prefs==vec![B]));
+    assert!(matches!(strict.clone().solve().solution,Err(RaireError::WrongWinner(winners)) if winners==vec![A]));
+    strict.winner = None;
+    assert_eq!(A,strict.solve().solution.unwrap().winner);
+    // out of range candidates in ranked votes are caught.
+    let mut bad = problem;
+    bad.ranked_votes = Some(vec![ranked(vec![vec![A,CandidateIndex(5)]])]);
+    assert!(matches!(bad.solve().solution,Err(RaireError::InvalidCandidateNumberIn{ candidate: CandidateIndex(5), .. })));
+}
@@ -0,0 +1,49 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test candidate names: matching them, and giving assertions and solutions in terms of them.
+
+use serde_json::json;
+use raire::assertions::{Assertion, NotEliminatedBefore, NotEliminatedNext};
+use raire::{RaireError, RaireProblem};
+use raire::candidates::{normalize_name, CandidateList, NamedAssertion};
+
+mod common;
+use common::{A, B, C, D, get_votes};
+
+#[test]
+/// Test that names match ignoring case, accents and spacing, that ambiguous names are rejected, and that assertions and results can be given by name.
+fn test_candidate_names() {
+    assert_eq!("jose maria zoe",normalize_name("  José   MARÍA\tZoë "));
+    assert_eq!(normalize_name("Jose\u{301}"),normalize_name("José")); // decomposed accent.
+    let candidates = CandidateList::new(vec!["Alice".to_string(),"Bob".to_string(),"Chuan".to_string(),"".to_string()]).unwrap();
+    assert_eq!(C,candidates.index(" chuan ").unwrap());
+    assert_eq!(D,candidates.index("#3").unwrap()); // unnamed.
+    assert_eq!("#3",candidates.name(D));
+    assert!(matches!(candidates.index("Diego"),Err(RaireError::UnknownCandidateName(name)) if name=="Diego"));
+    assert!(matches!(candidates.index("#1"),Err(RaireError::UnknownCandidateName(_)))); // Bob has a name.
+    assert!(matches!(CandidateList::new(vec!["Renée Smith".to_string(),"Bob".to_string(),"renee  smith".to_string()]),Err(RaireError::AmbiguousCandidateNames(a,b)) if a=="Renée Smith" && b=="renee  smith"));
+    assert!(serde_json::from_value::<CandidateList>(json!(["Bob","BOB"])).is_err());
+    assert_eq!(json!(["Alice","Bob","Chuan",""]),serde_json::to_value(&candidates).unwrap());
+    // name based constructors.
+    assert_eq!(Assertion::NEB(NotEliminatedBefore{ winner: C, loser: B }),candidates.neb("Chuan","bob").unwrap());
+    let nen = candidates.nen("Chuan","Alice",&["#3","Alice","Chuan"]).unwrap();
+    assert_eq!(Assertion::NEN(NotEliminatedNext{ winner: C, loser: A, continuing: vec![A,C,D].into() }),nen);
+    let named = candidates.named_assertion(&nen);
+    assert_eq!(json!({"type":"NEN","winner":"Chuan","loser":"Alice","continuing":["Alice","Chuan","#3"]}),serde_json::to_value(&named).unwrap());
+    assert_eq!(nen,candidates.resolve_assertion(&serde_json::from_value::<NamedAssertion>(json!({"type":"NEN","winner":"chuan","loser":"ALICE","continuing":["#3","alice","chuan"]})).unwrap()).unwrap());
+    // names carried in the problem and solution metadata.
+    let problem : RaireProblem = serde_json::from_value(json!({"metadata":{"candidates":["Alice","Bob","Chuan","Diego"]},"num_candidates":4,"votes":serde_json::to_value(get_votes().votes).unwrap(),"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13500}})).unwrap();
+    assert_eq!(D,problem.candidate_list().unwrap().index("diego").unwrap());
+    let solution = problem.solve();
+    let named = solution.named_result().unwrap();
+    assert_eq!("Chuan",named.winner);
+    assert_eq!(solution.solution.as_ref().unwrap().assertions.len(),named.assertions.len());
+    assert!(named.assertions.iter().any(|a|a.assertion==NamedAssertion::NEB{ winner: "Chuan".to_string(), loser: "Bob".to_string() }));
+}
@@ -0,0 +1,45 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test canonical JSON output, and rounding difficulties to a number of decimal places.
+
+use serde_json::json;
+use raire::audit_type::Audit;
+use raire::canonical_json::{round_to_decimal_places, to_canonical_json};
+use raire::{RaireProblem, RaireSolution};
+
+mod common;
+use common::{AUDIT, guide_problem};
+
+#[test]
+/// Test that canonical JSON sorts keys and writes numbers exactly, and that rounded difficulties of the example are in its manifest and read back unchanged.
+fn test_canonical_json() {
+    let value = json!({"b":1,"a":[1.5,{"d":null,"c":"x\"y"}],"A":-2.0,"é":1e-7});
+    assert_eq!(r#"{"A":-2.0,"a":[1.5,{"c":"x\"y","d":null}],"b":1,"é":1e-7}"#,to_canonical_json(&value).unwrap());
+    assert_eq!(1.235,round_to_decimal_places(1.23456,3));
+    assert_eq!(0.3,round_to_decimal_places(0.1+0.2,2));
+    assert_eq!(-3.0,round_to_decimal_places(-2.5,0));
+    assert_eq!(f64::INFINITY,round_to_decimal_places(f64::INFINITY,2));
+    assert_eq!(1e300,round_to_decimal_places(1e300,10));
+    // rounding difficulties happens before the manifest is made, so it describes the rounded output.
+    let mut problem = RaireProblem{ deterministic: Some(true), ..guide_problem(Audit::OneOnMargin(AUDIT)) };
+    let unrounded = problem.clone().solve();
+    assert!(unrounded.solution.as_ref().unwrap().assertions.iter().any(|a|a.difficulty.fract()!=0.0));
+    problem.difficulty_decimal_places = Some(0);
+    let rounded = problem.clone().solve();
+    let result = rounded.solution.as_ref().unwrap();
+    assert!(result.assertions.iter().all(|a|a.difficulty.fract()==0.0));
+    for (a,b) in result.assertions.iter().zip(&unrounded.solution.as_ref().unwrap().assertions) { assert_eq!(b.difficulty.round(),a.difficulty); }
+    assert!(rounded.manifest.as_ref().unwrap().matches(&problem,&rounded.solution));
+    // canonical JSON reads back as the same solution.
+    let canonical = to_canonical_json(&rounded).unwrap();
+    assert!(canonical.starts_with(r#"{"format_version":"#),"{}",canonical);
+    let read_back : RaireSolution = serde_json::from_str(&canonical).unwrap();
+    assert_eq!(canonical,to_canonical_json(&read_back).unwrap());
+}
@@ -0,0 +1,95 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test finding the elimination orders that a set of assertions does or does not rule out.
+
+use raire::assertions::{elimination_orders_consistent_with_assertions, winners_consistent_with_assertions, Assertion};
+use raire::elimination_orders::ConsistentEliminationOrders;
+use raire::irv::CandidateIndex;
+use raire::timeout::TimeOut;
+use raire::RaireError;
+
+mod common;
+use common::{A, B, C, D, get_votes, get_solution_minimize_tree};
+
+#[test]
+/// Test that removing an assertion leaves elimination order suffixes for other winners that only it ruled out, reported when verifying the result.
+fn test_unpruned_elimination_orders() {
+    let mut solution = get_solution_minimize_tree();
+    assert!(solution.unpruned_elimination_order_suffixes(&mut TimeOut::never()).unwrap().is_empty());
+    let removed = solution.assertions.remove(0);
+    let suffixes = solution.unpruned_elimination_order_suffixes(&mut TimeOut::never()).unwrap();
+    assert!(!suffixes.is_empty());
+    for suffix in &suffixes {
+        assert_ne!(CandidateIndex(2),*suffix.last().unwrap());
+        // complete the suffix with the other candidates in any order.
+        let mut order : Vec<CandidateIndex> = (0..4).map(CandidateIndex).filter(|c|!suffix.contains(c)).collect();
+        order.extend_from_slice(suffix);
+        assert!(solution.assertions.iter().all(|a|a.assertion.is_consistent_with_full_order(&order)));
+        assert!(!removed.assertion.is_consistent_with_full_order(&order));
+    }
+    match solution.verify_result_does_prove_winner() {
+        Err(RaireError::AssertionsAllowOtherWinners(unpruned)) => assert_eq!(suffixes,unpruned),
+        other => panic!("Expecting AssertionsAllowOtherWinners, got {:?}",other),
+    }
+}
+
+#[test]
+/// Test that only Chuan can win in elimination orders consistent with the example's assertions, which include the actual order, and that any candidate can with no assertions.
+fn test_winners_consistent_with_assertions() {
+    let votes = get_votes();
+    let solution = get_solution_minimize_tree();
+    let assertions : Vec<Assertion> = solution.assertions.iter().map(|a|a.assertion.clone()).collect();
+    assert_eq!(vec![C],winners_consistent_with_assertions(&assertions,4));
+    // the assertions are true, so the actual elimination order is consistent with them.
+    let irv = votes.run_election(&mut TimeOut::never()).unwrap();
+    assert!(elimination_orders_consistent_with_assertions(&assertions,4).contains(&irv.elimination_order));
+    assert_eq!(24,elimination_orders_consistent_with_assertions(&[],4).len());
+    assert_eq!(vec![A,B,C,D],winners_consistent_with_assertions(&[],4));
+    assert!(winners_consistent_with_assertions(&assertions[1..],4).len()>1);
+}
+
+#[test]
+/// Test that the example's consistent elimination orders are each listed once, counted, sampled reproducibly from a seed and summarised, with and without assertions.
+fn test_consistent_elimination_orders() {
+    let solution = get_solution_minimize_tree();
+    let assertions : Vec<Assertion> = solution.assertions.iter().map(|a|a.assertion.clone()).collect();
+    let orders = solution.consistent_elimination_orders(&mut TimeOut::never()).unwrap();
+    let listed : Vec<Vec<CandidateIndex>> = orders.iter().collect();
+    let mut expected = elimination_orders_consistent_with_assertions(&assertions,4);
+    let mut sorted = listed.clone();
+    expected.sort_by_key(|order|order.iter().map(|c|c.0).collect::<Vec<_>>());
+    sorted.sort_by_key(|order|order.iter().map(|c|c.0).collect::<Vec<_>>());
+    assert_eq!(expected,sorted); // each consistent order exactly once.
+    assert_eq!(Some(listed.len() as u128),orders.count());
+    assert_eq!(vec![C],orders.winners());
+    let statistics = orders.statistics();
+    assert_eq!(vec![0.0,0.0,1.0,0.0],statistics.winner_fraction);
+    for (candidate,positions) in statistics.position_fraction.iter().enumerate() {
+        for (position,&fraction) in positions.iter().enumerate() {
+            let expected = listed.iter().filter(|order|order[position].0 as usize==candidate).count() as f64/listed.len() as f64;
+            assert!((expected-fraction).abs()<1e-12);
+        }
+    }
+    let sample = orders.sample("12345",20);
+    assert_eq!(20,sample.len());
+    assert!(sample.iter().all(|order|listed.contains(order)));
+    assert_eq!(sample,orders.sample("12345",20));
+    assert_ne!(sample,orders.sample("54321",20));
+    // with no assertions, every order is consistent.
+    let unconstrained = ConsistentEliminationOrders::new(&[],4,None,&mut TimeOut::never()).unwrap();
+    assert_eq!(Some(24),unconstrained.count());
+    assert_eq!(24,unconstrained.iter().count());
+    assert_eq!(vec![A,B,C,D],unconstrained.winners());
+    assert!(unconstrained.statistics().winner_fraction.iter().all(|&f|(f-0.25).abs()<1e-12));
+    // without the first assertion, other winners are possible.
+    let fewer = ConsistentEliminationOrders::new(&assertions[1..],4,None,&mut TimeOut::never()).unwrap();
+    assert_eq!(winners_consistent_with_assertions(&assertions[1..],4),fewer.winners());
+    assert!(matches!(ConsistentEliminationOrders::new(&assertions,4,Some(3),&mut TimeOut::never()),Err(RaireError::TreeTooLarge(3))));
+}
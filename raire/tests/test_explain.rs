@@ -0,0 +1,41 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the plain English explanations of assertions and solutions.
+
+use serde_json::json;
+use raire::assertions::Assertion;
+use raire::explain::{candidate_names_from_metadata, explain_assertion, explain_solution};
+use raire::irv::CandidateIndex;
+use raire::timeout::TimeOut;
+
+mod common;
+use common::get_solution_minimize_tree;
+
+#[test]
+/// Test the explanations of the example's assertions and solution, with and without candidate names, and when the assertions are not enough.
+fn test_explain() {
+    let solution = get_solution_minimize_tree();
+    let names = candidate_names_from_metadata(&json!({"candidates":["Alice","Bob","Chuan","Diego"]}));
+    let neb = solution.assertions.iter().find(|a|a.assertion.is_neb()).unwrap();
+    assert_eq!("Chuan has more first preference votes than Bob has votes preferring Bob to Chuan, by 4000 votes. So Chuan cannot be eliminated before Bob, whatever else happens in the count.",explain_assertion(neb,&names));
+    let nen = solution.assertions.iter().find(|a|matches!(&a.assertion,Assertion::NEN(nen) if nen.continuing.len()==3 && nen.loser==CandidateIndex(3) && nen.winner==CandidateIndex(0))).unwrap();
+    assert_eq!("When only Alice, Chuan and Diego remain, Alice has more votes than Diego, by 500 votes. So Alice is not the next candidate eliminated at that point.",explain_assertion(nen,&names));
+    let text = explain_solution(&solution,&names,&mut TimeOut::never()).unwrap();
+    assert!(text.starts_with("Chuan won the election."));
+    assert!(text.contains("* Bob cannot have won"));
+    assert!(text.ends_with("So Chuan is the only candidate who could have won.\n"));
+    // without names, candidate numbers are used.
+    let mut insufficient = solution.clone();
+    insufficient.assertions.remove(0);
+    let text = explain_solution(&insufficient,&[],&mut TimeOut::never()).unwrap();
+    assert!(text.starts_with("#2 won the election."));
+    assert!(text.contains("is NOT ruled out"));
+    assert!(!text.contains("only candidate who could have won"));
+}
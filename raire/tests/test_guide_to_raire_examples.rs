@@ -11,34 +11,56 @@
 //! Test the examples given in the new "Guide to RAIRE" document, chapter 6.
 
 
+use std::sync::Arc;
 use serde_json::json;
-use raire::assertions::{NotEliminatedNext, NotEliminatedBefore};
-use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
-use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
-use raire::raire_algorithm::TrimAlgorithm;
-use raire::RaireProblem;
-use raire::timeout::TimeOut;
-
-const A : CandidateIndex = CandidateIndex(0); // Alice
-const B : CandidateIndex = CandidateIndex(1); // Bob
-const C : CandidateIndex = CandidateIndex(2); // Chuan
-const D : CandidateIndex = CandidateIndex(3); // Diego
-
-/// Get the votes in Example 10 (at the time of writing), used in examples in chapter 6, "Using RAIRE to generate assertions".
-fn get_votes() -> Votes {
-    let votes = vec![
-        Vote{ n: BallotPaperCount(5000), prefs: vec![C,B,A]},
-        Vote{ n: BallotPaperCount(1000), prefs: vec![B,C,D]},
-        Vote{ n: BallotPaperCount(1500), prefs: vec![D,A]},
-        Vote{ n: BallotPaperCount(4000), prefs: vec![A,D]},
-        Vote{ n: BallotPaperCount(2000), prefs: vec![D]},
-    ];
-    Votes::new(votes, 4).unwrap()
-}
+use raire::assertions::{AssertionTallies, PrecomputedAssertion, verify_assertions_rule_out_all_other_winners, Assertion, NotEliminatedNext, NotEliminatedBefore, SortedCandidateSubset};
+use raire::audit_type::{Audit, AuditType, BallotComparisonALPHA, BallotComparisonMACRO, BallotComparisonOneOnDilutedMargin, BallotPollingBRAVO, Difficulty, DifficultyUnits, ExactDifficulty};
+use raire::audit_progress::BallotInterpretation;
+use raire::incremental::BallotDelta;
+use raire::simulate::{simulate_audit, ErrorRates, SimulationParameters};
+use raire::irv::{BallotPaperCount, CandidateIndex, EliminationRule, TieBreak, TieResolution, Vote, Votes};
+use raire::progress::{NoProgressReporter, ProgressEvent};
+use raire::frontier_heuristic::{DeepestFirst, FrontierHeuristic, FrontierNode, HardestFirst};
+use raire::raire_algorithm::{raire, raire_with_options, AssertionTypes, ExtendedTrim, RaireOptions, RaireResult, SearchObjective, TrimAlgorithm, TrimDepth, TrimSelectionAlgorithm};
+use raire::{RaireError, RaireProblem, RaireSolution};
+use raire::export::shangrla::to_shangrla;
+use raire::schema::FORMAT_VERSION;
+use raire::snapshot::{SearchSnapshot, SnapshotStore, SNAPSHOT_FORMAT_VERSION};
+use raire::timeout::{CancellationToken, TimeOut};
+use raire::tree_json::{EliminationTreesJson, ELIMINATION_TREES_SCHEMA_VERSION};
 
-/// The audit used in the examples.
-const AUDIT : BallotComparisonOneOnDilutedMargin = BallotComparisonOneOnDilutedMargin { total_auditable_ballots : BallotPaperCount(13500) };
+mod common;
+use common::{A, B, C, D, get_votes, AUDIT, guide_problem, get_solution_minimize_tree};
 
+#[test]
+/// Test that cached restricted tallies are the same whatever order the continuing candidates are given in.
+fn test_restricted_tallies_cache() {
+    let votes = get_votes();
+    assert_eq!(0,votes.num_cached_tallies());
+    let tallies = votes.restricted_tallies(&[A,C,D]);
+    assert_eq!(vec![BallotPaperCount(4000),BallotPaperCount(6000),BallotPaperCount(3500)],tallies);
+    assert_eq!(1,votes.num_cached_tallies());
+    assert_eq!(vec![BallotPaperCount(3500),BallotPaperCount(4000),BallotPaperCount(6000)],votes.restricted_tallies(&[D,A,C]));
+    assert_eq!(1,votes.num_cached_tallies());
+    votes.restricted_tallies(&[B,B]); // repeated candidates are not cached.
+    assert_eq!(1,votes.num_cached_tallies());
+    votes.clear_tally_cache();
+    assert_eq!(0,votes.num_cached_tallies());
+}
+
+#[test]
+/// Test that splitting each vote record in two and then deduplicating gives the same votes back.
+fn test_deduplicate() {
+    let original = get_votes();
+    let split : Vec<Vote> = original.votes.iter().flat_map(|v|[Vote{n:BallotPaperCount(v.n.0/2),prefs:v.prefs.clone()},Vote{n:v.n-BallotPaperCount(v.n.0/2),prefs:v.prefs.clone()}]).collect();
+    let mut votes = Votes::new(split,4).unwrap();
+    let stats = votes.deduplicate();
+    assert_eq!(10,stats.records_before);
+    assert_eq!(5,stats.records_after);
+    assert_eq!(2.0,stats.compression_ratio());
+    assert_eq!(original.total_votes(),votes.total_votes());
+    assert_eq!(original.restricted_tallies(&[A,C,D]),votes.restricted_tallies(&[A,C,D]));
+}
 
 #[test]
 /// Test the get_votes() function and the methods on the Votes object.
@@ -82,7 +104,7 @@ fn test_expansions() {
     let node12 = NotEliminatedNext{
         winner: A,
         loser: D,
-        continuing: vec![A,C,D],
+        continuing: vec![A,C,D].into(),
     };
     let node12_effort = node12.difficulty(&votes,&AUDIT);
     println!("node12 effort {node12_effort}");
@@ -91,18 +113,7 @@ fn test_expansions() {
 #[test]
 /// Test RAIRE
 fn test_raire() {
-    let problem = RaireProblem {
-        metadata : json!({
-            "candidates" : ["Alice","Bob","Chuan","Diego"]
-        }),
-        num_candidates : 4,
-        votes : get_votes().votes,
-        winner : Some(CandidateIndex(2)),
-        audit : Audit::OneOnMargin(AUDIT),
-        trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions),
-        difficulty_estimate: None,
-        time_limit_seconds: None,
-    };
+    let problem = RaireProblem{ metadata: json!({ "candidates" : ["Alice","Bob","Chuan","Diego"] }), trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions), ..guide_problem(Audit::OneOnMargin(AUDIT)) };
     println!("{}",serde_json::to_string_pretty(&problem).unwrap());
     let solution = problem.solve();
     println!("{}",serde_json::to_string_pretty(&solution).unwrap());
@@ -110,5 +121,1102 @@ fn test_raire() {
     assert_eq!(CandidateIndex(2),solution.winner);
     assert_eq!(27.0,solution.difficulty);
     assert_eq!(5,solution.assertions.len());
+    let statistics = solution.trimming_statistics.unwrap();
+    assert_eq!(5,statistics.assertions_after);
+    assert!(statistics.assertions_before>=5);
+    assert_eq!(vec![CandidateIndex(0),CandidateIndex(1),CandidateIndex(3)],statistics.tree_nodes.iter().map(|t|t.candidate).collect::<Vec<_>>());
+    assert_eq!(TrimAlgorithm::MinimizeAssertions,statistics.trim_algorithm);
+}
+
+#[test]
+/// Test that each assertion's margin is the difference between the winner's and loser's tallies, and matches the difficulty.
+fn test_assertion_margins() {
+    let votes = get_votes();
+    let solution = get_solution_minimize_tree();
+    for a in &solution.assertions {
+        let expected = match &a.assertion {
+            Assertion::NEN(nen) => {
+                let tallies = votes.restricted_tallies(&nen.continuing);
+                let tally = |c:CandidateIndex|tallies[nen.continuing.iter().position(|&x|x==c).unwrap()];
+                tally(nen.winner)-tally(nen.loser)
+            }
+            Assertion::NEB(neb) => { // winner's first preferences minus the most the loser could have before the winner is eliminated.
+                let loser_tally : BallotPaperCount = votes.votes.iter().filter(|v|v.prefs.iter().position(|&c|c==neb.loser).is_some_and(|l|v.prefs.iter().position(|&c|c==neb.winner).is_none_or(|w|l<w))).map(|v|v.n).sum();
+                votes.first_preference_only_tally(neb.winner)-loser_tally
+            }
+        };
+        assert_eq!(expected,a.margin);
+        assert_eq!(AUDIT.total_auditable_ballots.0 as f64/a.margin.0 as f64,a.difficulty);
+    }
+    assert_eq!(solution.assertions.iter().map(|a|a.margin).min().unwrap(),solution.margin);
+}
+
+#[test]
+/// Test that progress is reported through the RAIRE computation.
+fn test_raire_progress() {
+    let problem = RaireProblem{ trim_algorithm: Some(TrimAlgorithm::MinimizeTree), ..guide_problem(Audit::OneOnMargin(AUDIT)) };
+    let mut events : Vec<ProgressEvent> = vec![];
+    let solution = problem.solve_with_progress(&mut |e|events.push(e));
+    assert_eq!(27.0,solution.solution.unwrap().difficulty);
+    assert!(matches!(events[0],ProgressEvent::DeterminedWinner{winner:C,..}));
+    assert!(events.iter().any(|e|matches!(e,ProgressEvent::FinishedSearch{..})));
+    assert_eq!(3,events.iter().filter(|e|matches!(e,ProgressEvent::TrimmingTreeBuilt{..})).count());
+    assert!(matches!(events.last().unwrap(),ProgressEvent::FinishedTrimming{..}));
+}
+
+#[test]
+/// Test that solving the example in two phases reports the quick answer as an improvement, and stops there as it is already the easiest.
+fn test_two_phase() {
+    let votes = get_votes();
+    let mut improvements : Vec<RaireResult> = vec![];
+    let mut reporter = |e:ProgressEvent|if let ProgressEvent::ImprovedSolution{result} = e { improvements.push(*result); };
+    let result = raire_with_options(&votes,Some(C),&AUDIT,&RaireOptions{two_phase:true,..Default::default()},&mut TimeOut::never(),&mut reporter).unwrap();
+    assert_eq!(27.0,result.difficulty);
+    assert!(!result.refinement_incomplete);
+    // the quick answer is already the easiest, so there is nothing to refine.
+    assert_eq!(1,improvements.len());
+    assert_eq!(27.0,improvements[0].difficulty);
+    let assertions : Vec<Assertion> = improvements[0].assertions.iter().map(|a|a.assertion.clone()).collect();
+    assert!(verify_assertions_rule_out_all_other_winners(&assertions,C,4).is_ok());
+}
+
+#[test]
+/// Test that the optimal trimming produces no more assertions than the heuristics.
+fn test_raire_optimal_trim() {
+    for trim_algorithm in [TrimAlgorithm::MinimizeTree,TrimAlgorithm::MinimizeAssertions] {
+        let problem = RaireProblem{ trim_algorithm: Some(trim_algorithm), trim_selection_algorithm: Some(TrimSelectionAlgorithm::Optimal), ..guide_problem(Audit::OneOnMargin(AUDIT)) };
+        let heuristic = RaireProblem{trim_selection_algorithm:None,..problem.clone()}.solve().solution.unwrap();
+        let greedy = RaireProblem{trim_selection_algorithm:Some(TrimSelectionAlgorithm::GreedyCoverage),..problem.clone()}.solve().solution.unwrap();
+        greedy.verify_result_does_prove_winner().unwrap();
+        assert!(heuristic.trimming_statistics.as_ref().unwrap().cross_check.is_none());
+        // cross checking trimming also trims with the optimal selection, but outputs the heuristic's assertions.
+        let checked = RaireProblem{trim_selection_algorithm:None,cross_check_trimming:Some(true),..problem.clone()}.solve().solution.unwrap();
+        assert_eq!(heuristic.assertions.len(),checked.assertions.len());
+        let optimal = problem.solve().solution.unwrap();
+        let cross_check = checked.trimming_statistics.unwrap().cross_check.unwrap();
+        assert_eq!(heuristic.assertions.len(),cross_check.heuristic_assertions);
+        assert_eq!(optimal.assertions.len(),cross_check.optimal_assertions);
+        assert_eq!(cross_check.heuristic_assertions-cross_check.optimal_assertions,cross_check.size_difference);
+        assert_eq!(27.0,optimal.difficulty);
+        assert!(optimal.assertions.len()<=heuristic.assertions.len());
+        assert!(optimal.assertions.len()<=greedy.assertions.len());
+        optimal.verify_result_does_prove_winner().unwrap();
+    }
+}
+
+#[test]
+/// Test the Graphviz export of the elimination order trees.
+fn test_raire_dot() {
+    let solution = get_solution_minimize_tree();
+    let trees = solution.elimination_trees(&mut TimeOut::never()).unwrap();
+    assert_eq!(3,trees.len());
+    assert!(trees.iter().all(|t|!t.valid));
+    let all_assertions : Vec<_> = solution.assertions.iter().map(|a|a.assertion.clone()).collect();
+    let names : Vec<String> = ["Alice","Bob","Chuan","Diego"].iter().map(|s|s.to_string()).collect();
+    let dot = raire::graphviz::trees_to_dot(&trees,&all_assertions,&names);
+    assert!(dot.starts_with("digraph"));
+    assert_eq!(3,dot.matches("subgraph cluster_").count());
+    assert!(dot.contains("Alice is ruled out by the assertions"));
+    assert!(dot.contains("Chuan NEB Bob"));
+    assert_eq!(trees.iter().map(|t|t.count_nodes()).sum::<usize>(),dot.matches("[label=").count());
+    assert!(!dot.contains("palegreen"));
+    // without names, candidate numbers are used.
+    assert!(trees[0].to_dot(&all_assertions,&[]).contains("#0 is ruled out"));
+}
+
+#[test]
+/// Test the JSON form of the elimination order trees.
+fn test_raire_tree_json() {
+    let solution = get_solution_minimize_tree();
+    let trees = solution.elimination_trees(&mut TimeOut::never()).unwrap();
+    let all_assertions : Vec<_> = solution.assertions.iter().map(|a|a.assertion.clone()).collect();
+    let names : Vec<String> = ["Alice","Bob","Chuan"].iter().map(|s|s.to_string()).collect();
+    let json = EliminationTreesJson::new(&trees,&all_assertions,4,&names);
+    assert_eq!(ELIMINATION_TREES_SCHEMA_VERSION,json.schema_version);
+    assert_eq!(vec!["Alice","Bob","Chuan","#3"],json.candidates);
+    assert_eq!("Alice",json.trees[0].candidate_name);
+    let text = serde_json::to_string(&json).unwrap();
+    let parsed : EliminationTreesJson = serde_json::from_str(&text).unwrap();
+    assert_eq!(json.trees.len(),parsed.trees.len());
+    for (tree,parsed) in trees.iter().zip(parsed.trees.iter()) {
+        assert_eq!(serde_json::to_value(tree).unwrap(),serde_json::to_value(parsed.to_tree()).unwrap());
+    }
+}
+
+#[test]
+/// Test that exceeding the tree size limit when trimming produces untrimmed assertions with a warning.
+fn test_raire_max_tree_nodes() {
+    let problem = RaireProblem{ trim_algorithm: Some(TrimAlgorithm::MinimizeAssertions), max_tree_nodes: Some(2), ..guide_problem(Audit::OneOnMargin(AUDIT)) };
+    let solution = problem.solve().solution.unwrap();
+    assert!(solution.warning_trim_timed_out);
+    assert!(solution.trimming_statistics.is_none());
+    assert_eq!(27.0,solution.difficulty);
+    assert!(solution.assertions.len()>5);
+}
+
+#[test]
+/// Test that running out of time while trimming produces untrimmed assertions with a warning rather than an error.
+fn test_raire_trim_timeout() {
+    let votes = get_votes();
+    let untimed = raire(&votes,Some(CandidateIndex(2)),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert!(!untimed.warning_trim_timed_out);
+    // allow just enough work to finish the main search, but not the trimming.
+    let work_before_trimming = untimed.time_to_determine_winners.work+untimed.time_to_find_assertions.work;
+    let mut timeout = TimeOut::new(Some(work_before_trimming+1),None);
+    let timed = raire(&votes,Some(CandidateIndex(2)),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut timeout,&mut NoProgressReporter).unwrap();
+    assert!(timed.warning_trim_timed_out);
+    assert_eq!(untimed.difficulty,timed.difficulty);
+    assert!(timed.assertions.len()>untimed.assertions.len());
+}
+
+#[test]
+/// Test that cancelling a computation stops it with a Cancelled error containing what was found so far.
+fn test_raire_cancellation() {
+    let votes = get_votes();
+    let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,..Default::default()};
+    // cancel when the reporter is told of an event for which `cancel_on` is true.
+    let run = |options:&RaireOptions,cancel_on:fn(&ProgressEvent)->bool|{
+        let token = CancellationToken::new();
+        let mut reporter = |event:ProgressEvent|if cancel_on(&event) { token.cancel(); };
+        raire_with_options(&votes,Some(C),&AUDIT,options,&mut TimeOut::never().with_cancellation(token.clone()),&mut reporter)
+    };
+    let search_started = |event:&ProgressEvent|matches!(event,ProgressEvent::DeterminedWinner{..});
+    let search_finished = |event:&ProgressEvent|matches!(event,ProgressEvent::FinishedSearch{..});
+    assert!(matches!(run(&options,search_started),Err(RaireError::Cancelled{partial:None})));
+    match run(&RaireOptions{partial_on_timeout:true,..options.clone()},search_started) {
+        Err(RaireError::Cancelled{partial:Some(partial)}) => assert!(partial.partial),
+        other => panic!("Expecting a partial result, got {:?}",other),
+    }
+    match run(&options,search_finished) {
+        Err(RaireError::Cancelled{partial:Some(untrimmed)}) => { assert!(untrimmed.warning_trim_timed_out); assert_eq!(27.0,untrimmed.difficulty); }
+        other => panic!("Expecting an untrimmed result, got {:?}",other),
+    }
+    // a token that is never cancelled changes nothing.
+    let uncancelled = raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::never().with_cancellation(CancellationToken::new()),&mut NoProgressReporter).unwrap();
+    assert_eq!(27.0,uncancelled.difficulty);
+}
+
+/// Keeps snapshots as JSON in memory.
+#[derive(Debug,Default)]
+struct SnapshotsInMemory(std::sync::Mutex<Option<String>>);
+
+impl SnapshotStore for SnapshotsInMemory {
+    fn load(&self) -> Result<Option<SearchSnapshot>,RaireError> { Ok(self.0.lock().unwrap().as_ref().map(|json|serde_json::from_str(json).unwrap())) }
+    fn save(&self,snapshot:&SearchSnapshot) -> std::io::Result<()> { *self.0.lock().unwrap()=Some(serde_json::to_string(snapshot)?); Ok(()) }
+    fn finished(&self) { *self.0.lock().unwrap()=None; }
+}
+
+#[test]
+/// Test that a search stopped part way through can be resumed from a snapshot.
+fn test_raire_snapshot_resume() {
+    let votes = get_votes();
+    let store = Arc::new(SnapshotsInMemory::default());
+    let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,snapshots:Some(store.clone()),..Default::default()};
+    // cancel once some assertions have been found.
+    let token = CancellationToken::new();
+    let mut reporter = |event:ProgressEvent|if let ProgressEvent::Searching{assertions_found:1..,..} = event { token.cancel(); };
+    let stopped = raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::never().with_cancellation(token.clone()),&mut reporter);
+    assert!(matches!(stopped,Err(RaireError::Cancelled{partial:None})));
+    let snapshot = store.load().unwrap().unwrap();
+    assert!(!snapshot.assertions.is_empty());
+    assert!(!snapshot.frontier.is_empty());
+    // a different problem cannot resume from it.
+    let different_audit = BallotComparisonOneOnDilutedMargin{total_auditable_ballots:BallotPaperCount(27000)};
+    assert!(matches!(raire_with_options(&votes,Some(C),&different_audit,&options,&mut TimeOut::never(),&mut NoProgressReporter),Err(RaireError::InvalidSnapshot(_))));
+    let resumed = raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert_eq!(27.0,resumed.difficulty);
+    assert!(resumed.verify_result_does_prove_winner().is_ok());
+    assert!(store.load().unwrap().is_none(),"snapshot should be removed when the search finishes");
+    // snapshots of another version cannot be resumed from.
+    store.save(&SearchSnapshot{format_version:SNAPSHOT_FORMAT_VERSION+1,..snapshot}).unwrap();
+    assert!(matches!(raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::never(),&mut NoProgressReporter),Err(RaireError::InvalidSnapshot(_))));
+}
+
+#[test]
+/// Test that difficulties know what they measure.
+fn test_difficulty_units() {
+    let votes = get_votes();
+    let result = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert_eq!(DifficultyUnits::OneOnDilutedMargin,result.difficulty_units);
+    let difficulty = result.difficulty_with_units();
+    assert!((difficulty.diluted_margin().unwrap()-1.0/27.0).abs()<1e-12);
+    assert!(difficulty.to_string().contains("diluted margin 3.704%"));
+    let samples = difficulty.expected_sample_size(0.05).unwrap();
+    assert!(samples>27.0 && samples.is_finite());
+    assert!(difficulty.expected_sample_size(0.01).unwrap()>samples);
+    assert_eq!(json!("OneOnDilutedMargin"),serde_json::to_value(&result).unwrap()["difficulty_units"]);
+    let bravo = Difficulty::new(100.0,DifficultyUnits::ExpectedSampleSize{risk_limit:0.05});
+    assert_eq!(Some(100.0),bravo.expected_sample_size(0.05));
+    assert!((bravo.expected_sample_size(0.01).unwrap()-100.0*0.01f64.ln()/0.05f64.ln()).abs()<1e-9);
+    assert_eq!(None,bravo.diluted_margin());
+    let polling = Difficulty::new(100.0,DifficultyUnits::OneOnDilutedMarginSquared);
+    assert!((polling.diluted_margin().unwrap()-0.1).abs()<1e-12);
+    // the same margin as a polling audit needs more ballots than a comparison audit.
+    assert!(polling.expected_sample_size(0.05).unwrap()>Difficulty::new(10.0,DifficultyUnits::OneOnDilutedMargin).expected_sample_size(0.05).unwrap());
+    // units of an audit type not in this crate are unknown, and not written.
+    struct Custom;
+    impl AuditType for Custom {
+        fn difficulty(&self, winner: BallotPaperCount, loser: BallotPaperCount) -> f64 { AUDIT.difficulty(winner,loser) }
+    }
+    let custom = raire(&votes,Some(C),&Custom,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert_eq!(None,custom.difficulty_with_units().expected_sample_size(0.05));
+    assert!(serde_json::to_value(&custom).unwrap().get("difficulty_units").is_none());
+}
+
+#[test]
+/// Test that the same ballots give the same winner, tallies and assertions whether given as one weighted record per preference list,
+/// or split into one record per ballot, interleaved, with records for no ballots.
+fn test_weighted_and_expanded_votes_agree() {
+    let weighted = get_votes();
+    let mut records = vec![Vote{ n: BallotPaperCount(0), prefs: vec![A] }];
+    for i in 0..weighted.votes.iter().map(|v|v.n.0).max().unwrap() {
+        records.extend(weighted.votes.iter().filter(|v|v.n.0>i).map(|v|Vote{ n: BallotPaperCount(1), prefs: v.prefs.clone() }));
+    }
+    let expanded = Votes::new(records,4).unwrap();
+    assert_eq!(weighted.total_votes(),expanded.total_votes());
+    assert_eq!(weighted.weighted_records(),expanded.weighted_records());
+    for c in [A,B,C,D] { assert_eq!(weighted.first_preference_only_tally(c),expanded.first_preference_only_tally(c)); }
+    let weighted_election = weighted.run_election(&mut TimeOut::never()).unwrap();
+    let expanded_election = expanded.run_election(&mut TimeOut::never()).unwrap();
+    assert_eq!(weighted_election.possible_winners,expanded_election.possible_winners);
+    assert_eq!(weighted_election.elimination_order,expanded_election.elimination_order);
+    let weighted_result = raire(&weighted,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    let expanded_result = raire(&expanded,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert_eq!(weighted_result.assertions,expanded_result.assertions);
+    assert_eq!(weighted_result.difficulty,expanded_result.difficulty);
+    assert_eq!(weighted_result.margin,expanded_result.margin);
+    for a in &weighted_result.assertions {
+        assert_eq!(a.assertion.signed_margin(&weighted),a.assertion.signed_margin(&expanded));
+        assert_eq!(a.assertion.tallies(&weighted,&AUDIT),a.assertion.tallies(&expanded,&AUDIT));
+    }
+}
+
+#[test]
+/// Test that a risk limit gives sample size estimates for each assertion and overall, which grow with the assumed error rates.
+fn test_sample_size_estimate() {
+    let problem = |extra:serde_json::Value|{
+        let mut problem = json!({"metadata":{},"num_candidates":4,"votes":serde_json::to_value(get_votes().votes).unwrap(),"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13500}});
+        for (key,value) in extra.as_object().unwrap() { problem[key]=value.clone(); }
+        serde_json::from_value::<RaireProblem>(problem).unwrap().solve()
+    };
+    assert!(problem(json!({})).sample_sizes.is_none());
+    let solution = problem(json!({"risk_limit":0.05}));
+    let result = solution.solution.as_ref().unwrap();
+    let estimate = solution.sample_sizes.unwrap();
+    assert_eq!(result.assertions.len(),estimate.assertions.len());
+    assert_eq!(estimate.overall,*estimate.assertions.iter().max().unwrap());
+    // with no discrepancies, the estimate is the difficulty as an ALPHA audit would measure it.
+    let alpha = BallotComparisonALPHA{ confidence: 0.05, total_auditable_ballots: BallotPaperCount(13500), d: 100.0, eta0: None };
+    for (a,&n) in result.assertions.iter().zip(&estimate.assertions) {
+        assert_eq!(alpha.expected_sample_size(a.margin.0 as f64/13500.0),n as f64);
+    }
+    let with_errors = problem(json!({"risk_limit":0.05,"error_rates":{"one_vote_overstatement":0.002,"two_vote_overstatement":0.0005}})).sample_sizes.unwrap();
+    assert!(with_errors.overall>estimate.overall);
+    assert!(problem(json!({"risk_limit":0.01})).sample_sizes.unwrap().overall>estimate.overall);
+    // a polling audit needs more ballots for the same margins.
+    let polling = problem(json!({"risk_limit":0.05,"audit":{"type":"BRAVO","confidence":0.05,"total_auditable_ballots":13500}})).sample_sizes.unwrap();
+    assert!(polling.overall>estimate.overall && polling.overall<=13500);
+    assert!(matches!(problem(json!({"risk_limit":1.0})).solution,Err(RaireError::InvalidRiskLimit)));
+    assert!(matches!(problem(json!({"risk_limit":0.05,"error_rates":{"one_vote_overstatement":0.7,"two_vote_overstatement":0.7}})).solution,Err(RaireError::InvalidErrorRates)));
+}
+
+#[test]
+/// Test that exceeding the memory budget gives an error saying how much was used.
+fn test_raire_memory_budget() {
+    let votes = get_votes();
+    let run = |max_memory_mb:Option<usize>|raire_with_options(&votes,Some(C),&AUDIT,&RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,max_memory_mb,..Default::default()},&mut TimeOut::never(),&mut NoProgressReporter);
+    match run(Some(0)) {
+        Err(RaireError::OutOfMemoryBudget{limit_mb:0,high_water_mark_bytes}) => assert!(high_water_mark_bytes>0),
+        other => panic!("Expecting to exceed the memory budget, got {:?}",other),
+    }
+    assert_eq!(27.0,run(Some(100)).unwrap().difficulty);
+    assert_eq!(27.0,run(None).unwrap().difficulty);
+}
+
+#[test]
+/// Test that running out of time while finding assertions can give a partial result rather than an error.
+fn test_raire_partial_on_timeout() {
+    let votes = get_votes();
+    let untimed = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert!(!untimed.partial);
+    assert!(untimed.unresolved_elimination_order_suffixes.is_empty());
+    // allow just enough work to determine the winner and start the search.
+    let work_limit = Some(untimed.time_to_determine_winners.work+2);
+    let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,..Default::default()};
+    match raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::new(work_limit,None),&mut NoProgressReporter) {
+        Err(RaireError::TimeoutFindingAssertions(_)) => {}
+        other => panic!("Expecting a timeout, got {:?}",other),
+    }
+    let options = RaireOptions{partial_on_timeout:true,..options};
+    let partial = raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::new(work_limit,None),&mut NoProgressReporter).unwrap();
+    assert!(partial.partial);
+    assert!(partial.trimming_statistics.is_none());
+    assert!(!partial.unresolved_elimination_order_suffixes.is_empty());
+    assert!(partial.difficulty<=untimed.difficulty);
+    // every elimination order not yet ruled out that elects someone else ends with one of the unresolved suffixes.
+    for order in partial.possible_elimination_orders_allowed_by_assertions(4) {
+        if *order.last().unwrap()!=C {
+            assert!(partial.unresolved_elimination_order_suffixes.iter().any(|suffix|order.ends_with(suffix)),"{:?} is not covered",order);
+        }
+    }
+    let json = serde_json::to_value(&partial).unwrap();
+    assert_eq!(json["partial"],json!(true));
+    assert!(serde_json::to_value(&untimed).unwrap().get("partial").is_none());
+}
+
+#[test]
+/// Test that running out of time while finding assertions can give the winner only assertions, if they are true.
+fn test_raire_winner_only_fallback() {
+    let run = |votes:&Votes,audit:&BallotComparisonOneOnDilutedMargin,winner:CandidateIndex| {
+        let untimed = raire(votes,Some(winner),audit,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+        assert!(!untimed.fallback);
+        let work_limit = Some(untimed.time_to_determine_winners.work+1);
+        let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,winner_only_fallback:true,..Default::default()};
+        raire_with_options(votes,Some(winner),audit,&options,&mut TimeOut::new(work_limit,None),&mut NoProgressReporter)
+    };
+    // Chuan is not a winner only winner, as Alice gets more than his 5000 votes if only they continue.
+    assert!(matches!(run(&get_votes(),&AUDIT,C),Err(RaireError::TimeoutFindingAssertions(_))));
+    let votes = Votes::new(vec![Vote{n:BallotPaperCount(60),prefs:vec![A]},Vote{n:BallotPaperCount(25),prefs:vec![B,C]},Vote{n:BallotPaperCount(15),prefs:vec![C,B]}],3).unwrap();
+    let fallback = run(&votes,&BallotComparisonOneOnDilutedMargin{total_auditable_ballots:BallotPaperCount(100)},A).unwrap();
+    assert!(fallback.fallback);
+    assert!(!fallback.partial);
+    assert_eq!(vec![Assertion::NEB(NotEliminatedBefore{winner:A,loser:B}),Assertion::NEB(NotEliminatedBefore{winner:A,loser:C})],fallback.assertions.iter().map(|a|a.assertion.clone()).collect::<Vec<_>>());
+    assert_eq!(5.0,fallback.difficulty);
+    assert_eq!(BallotPaperCount(20),fallback.margin);
+    fallback.verify_result_does_prove_winner().unwrap();
+    assert_eq!(serde_json::to_value(&fallback).unwrap()["fallback"],json!(true));
+}
+
+#[test]
+/// Test stopping the search once an affordable audit is found.
+fn test_raire_stop_when_difficulty_at_most() {
+    let votes = get_votes();
+    let optimal = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    for threshold in [10.0,27.0,100.0,1000.0] {
+        let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,stop_when_difficulty_at_most:Some(threshold),..Default::default()};
+        let mut timeout = TimeOut::never();
+        let result = raire_with_options(&votes,Some(C),&AUDIT,&options,&mut timeout,&mut NoProgressReporter).unwrap();
+        assert!(result.difficulty>=optimal.difficulty);
+        assert!(result.difficulty<=threshold.max(optimal.difficulty));
+        assert_eq!(result.difficulty,result.assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max));
+        assert!(result.time_to_find_assertions.work<=optimal.time_to_find_assertions.work);
+        let assertions : Vec<Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+        assert_eq!(Ok(()),verify_assertions_rule_out_all_other_winners(&assertions,C,4));
+    }
+}
+
+#[test]
+/// Test that the bitmask form of assertions used when building trees gives the same answers as the original, for
+/// every NEB and NEN assertion with 4 candidates and every elimination order suffix.
+fn test_precomputed_assertions_match() {
+    let candidates = [A,B,C,D];
+    let subsets : Vec<Vec<CandidateIndex>> = (1..16u32).map(|mask|candidates.iter().cloned().filter(|c|mask&(1<<c.0)!=0).collect()).collect();
+    let mut assertions = vec![];
+    for &winner in &candidates {
+        for &loser in &candidates {
+            if winner==loser { continue; }
+            assertions.push(Assertion::NEB(NotEliminatedBefore{winner,loser}));
+            for continuing in subsets.iter().filter(|s|s.contains(&winner)&&s.contains(&loser)) {
+                assertions.push(Assertion::NEN(NotEliminatedNext{winner,loser,continuing:continuing.clone().into()}));
+            }
+        }
+    }
+    let mut suffixes : Vec<Vec<CandidateIndex>> = vec![vec![]];
+    let mut level : Vec<Vec<CandidateIndex>> = vec![vec![]];
+    for _ in 0..candidates.len() {
+        level = level.iter().flat_map(|s|candidates.iter().filter(|c|!s.contains(c)).map(move |&c|{ let mut v=vec![c]; v.extend_from_slice(s); v })).collect();
+        suffixes.extend(level.iter().cloned());
+    }
+    assert_eq!(1+4+12+24+24,suffixes.len());
+    for assertion in &assertions {
+        let precomputed = PrecomputedAssertion::new(assertion).unwrap();
+        for suffix in &suffixes {
+            assert_eq!(assertion.ok_elimination_order_suffix(suffix),precomputed.ok_elimination_order_suffix(suffix,suffix.iter().cloned().collect()),"{:?} {:?}",assertion,suffix);
+        }
+    }
+    assert!(PrecomputedAssertion::new(&Assertion::NEB(NotEliminatedBefore{winner:A,loser:CandidateIndex(200)})).is_none());
+}
+
+#[test]
+/// Test that the tallies recorded with each assertion reproduce its margin and difficulty.
+fn test_include_tallies() {
+    let votes = get_votes();
+    let without = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert!(without.assertions.iter().all(|a|a.tallies.is_none()));
+    let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,include_tallies:true,..Default::default()};
+    let result = raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert_eq!(without.assertions.len(),result.assertions.len());
+    for a in &result.assertions {
+        let tallies = a.tallies.as_ref().unwrap();
+        assert_eq!(a.margin.0,tallies.winner_tally.0.saturating_sub(tallies.loser_tally.0));
+        assert_eq!(a.difficulty,AUDIT.difficulty(tallies.winner_tally,tallies.loser_tally));
+        assert_eq!(Some(BallotPaperCount(13500)),tallies.total_auditable_ballots);
+    }
+    // Chuan NEB Bob: Chuan's 5000 first preferences against Bob's 1000 votes with only the two of them continuing.
+    let neb = result.assertions.iter().find(|a|a.assertion==Assertion::NEB(NotEliminatedBefore{winner:C,loser:B})).unwrap();
+    assert_eq!(Some(AssertionTallies{continuing:vec![C,B],winner_tally:BallotPaperCount(5000),loser_tally:BallotPaperCount(1000),total_auditable_ballots:Some(BallotPaperCount(13500))}),neb.tallies);
 }
 
+#[test]
+/// Test that choosing assertions with exact arithmetic gives the same assertions and difficulties, and is refused for BRAVO.
+fn test_exact_arithmetic() {
+    assert_eq!(ExactDifficulty::new(BallotPaperCount(13500),BallotPaperCount(5000),BallotPaperCount(2000)),ExactDifficulty::new(BallotPaperCount(4500),BallotPaperCount(1000),BallotPaperCount(0)));
+    assert!(ExactDifficulty::new(BallotPaperCount(13500),BallotPaperCount(5000),BallotPaperCount(1000))<ExactDifficulty::new(BallotPaperCount(13500),BallotPaperCount(5000),BallotPaperCount(1001)));
+    assert!(ExactDifficulty::new(BallotPaperCount(13500),BallotPaperCount(1),BallotPaperCount(1))>ExactDifficulty::new(BallotPaperCount(13500),BallotPaperCount(1),BallotPaperCount(0)));
+    let votes = get_votes();
+    let macro_audit = Audit::MACRO(BallotComparisonMACRO{confidence:0.05,error_inflation_factor:1.1,total_auditable_ballots:BallotPaperCount(13500)});
+    for audit in [Audit::OneOnMargin(AUDIT),macro_audit] {
+        let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,..Default::default()};
+        let floating = raire_with_options(&votes,Some(C),&audit,&options,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+        let exact = raire_with_options(&votes,Some(C),&audit,&RaireOptions{exact_arithmetic:true,..options},&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+        assert_eq!(floating.assertions.iter().map(|a|(&a.assertion,a.margin,a.difficulty)).collect::<Vec<_>>(),exact.assertions.iter().map(|a|(&a.assertion,a.margin,a.difficulty)).collect::<Vec<_>>());
+        assert_eq!(floating.difficulty,exact.difficulty);
+        assert_eq!(floating.difficulty_units,exact.difficulty_units);
+    }
+    let options = RaireOptions{exact_arithmetic:true,stop_when_difficulty_at_most:Some(30.0),..Default::default()};
+    let stopped = raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert!(stopped.difficulty<=30.0);
+    let bravo = Audit::BRAVO(BallotPollingBRAVO{confidence:0.05,total_auditable_ballots:BallotPaperCount(13500)});
+    let options = RaireOptions{exact_arithmetic:true,..Default::default()};
+    assert!(matches!(raire_with_options(&votes,Some(C),&bravo,&options,&mut TimeOut::never(),&mut NoProgressReporter),Err(RaireError::ExactArithmeticNotSupported)));
+}
+
+#[test]
+/// Test restricting the search to NEB or NEN assertions.
+fn test_assertion_types() {
+    let votes = get_votes();
+    let solve = |assertion_types:AssertionTypes| raire_with_options(&votes,Some(C),&AUDIT,&RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,assertion_types,..Default::default()},&mut TimeOut::never(),&mut NoProgressReporter);
+    let both = solve(AssertionTypes::Both).unwrap();
+    assert_eq!(27.0,both.difficulty);
+    // NEB assertions alone cannot rule out Alice winning after Bob, Diego and Chuan are eliminated.
+    assert!(matches!(solve(AssertionTypes::NEBOnly),Err(RaireError::CouldNotRuleOut(_))));
+    let nen = solve(AssertionTypes::NENOnly).unwrap();
+    assert!(nen.assertions.iter().all(|a|matches!(a.assertion,Assertion::NEN(_))));
+    assert!(nen.difficulty>=both.difficulty);
+    nen.verify_result_does_prove_winner().unwrap();
+    assert!(AssertionTypes::NEBOnly.allows(&Assertion::NEB(NotEliminatedBefore{winner:C,loser:B})));
+    assert!(!AssertionTypes::NENOnly.allows(&Assertion::NEB(NotEliminatedBefore{winner:C,loser:B})));
+}
+
+#[test]
+/// Test expanding the trimming trees below pruned nodes to find deeper redundancies, within a node budget.
+fn test_extended_trim() {
+    let votes = get_votes();
+    let trim = |extended_trim:Option<ExtendedTrim>| {
+        let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,extended_trim,..Default::default()};
+        raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::never(),&mut NoProgressReporter).unwrap()
+    };
+    let usual = trim(None);
+    assert_eq!(6,usual.assertions.len());
+    assert_eq!(None,usual.trimming_statistics.as_ref().unwrap().extended_trim_within_budget);
+    let no_levels = trim(Some(ExtendedTrim{depth:TrimDepth::Levels(0),max_nodes:None}));
+    assert_eq!(usual.assertions,no_levels.assertions);
+    assert_eq!(Some(true),no_levels.trimming_statistics.unwrap().extended_trim_within_budget);
+    // Chuan NEB Bob is needed to keep the tree small, but below it the NEN assertions rule everything out anyway.
+    for depth in [TrimDepth::Levels(3),TrimDepth::Unlimited] {
+        let extended = trim(Some(ExtendedTrim{depth,max_nodes:None}));
+        assert_eq!(5,extended.assertions.len());
+        let statistics = extended.trimming_statistics.as_ref().unwrap();
+        assert_eq!(Some(true),statistics.extended_trim_within_budget);
+        assert!(statistics.tree_nodes.iter().map(|t|t.nodes).sum::<usize>()>usual.trimming_statistics.as_ref().unwrap().tree_nodes.iter().map(|t|t.nodes).sum::<usize>());
+        let assertions : Vec<Assertion> = extended.assertions.iter().map(|a|a.assertion.clone()).collect();
+        assert_eq!(Ok(()),verify_assertions_rule_out_all_other_winners(&assertions,C,4));
+    }
+    // over budget, so the usual trees are used.
+    let over_budget = trim(Some(ExtendedTrim{depth:TrimDepth::Unlimited,max_nodes:Some(5)}));
+    assert_eq!(usual.assertions,over_budget.assertions);
+    assert_eq!(Some(false),over_budget.trimming_statistics.unwrap().extended_trim_within_budget);
+    let problem : RaireProblem = serde_json::from_value(json!({"metadata":{},"num_candidates":1,"votes":[],"audit":{"type":"OneOnMargin","total_auditable_ballots":1},"extended_trim":{"depth":{"Levels":2}}})).unwrap();
+    assert_eq!(Some(ExtendedTrim{depth:TrimDepth::Levels(2),max_nodes:None}),problem.extended_trim);
+}
+
+/// Expands the easiest suffix first, counting how often it is asked.
+#[derive(Debug,Default)]
+struct EasiestFirst(std::sync::atomic::AtomicUsize);
+
+impl FrontierHeuristic for EasiestFirst {
+    fn priority(&self,node:&FrontierNode) -> f64 {
+        self.0.fetch_add(1,std::sync::atomic::Ordering::Relaxed);
+        -node.difficulty()
+    }
+}
+
+#[test]
+/// Test that the order the search frontier is expanded in changes the work done but not the difficulty.
+fn test_frontier_heuristic() {
+    let votes = get_votes();
+    let solve = |frontier_heuristic:Option<Arc<dyn FrontierHeuristic>>| {
+        let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,deterministic:true,frontier_heuristic,..Default::default()};
+        raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::never(),&mut NoProgressReporter).unwrap()
+    };
+    let usual = solve(None);
+    assert_eq!(usual.assertions,solve(Some(Arc::new(HardestFirst))).assertions);
+    let easiest = Arc::new(EasiestFirst::default());
+    for heuristic in [Arc::new(DeepestFirst) as Arc<dyn FrontierHeuristic>,easiest.clone()] {
+        let result = solve(Some(heuristic));
+        assert_eq!(usual.difficulty,result.difficulty);
+        let assertions : Vec<Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+        assert_eq!(Ok(()),verify_assertions_rule_out_all_other_winners(&assertions,C,4));
+    }
+    assert!(easiest.0.load(std::sync::atomic::Ordering::Relaxed)>=3); // at least one call per alternate winner.
+}
+
+#[test]
+/// Test choosing assertions to minimize something other than the hardest assertion.
+fn test_search_objective() {
+    let votes = get_votes();
+    let optimal = raire(&votes,Some(C),&AUDIT,TrimAlgorithm::MinimizeAssertions,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert_eq!(27.0,SearchObjective::MinimizeMaximumDifficulty.cost(&optimal.assertions));
+    assert_eq!(SearchObjective::MinimizeTotalSampleSize{overlap:1.0}.cost(&optimal.assertions),27.0);
+    let sum : f64 = optimal.assertions.iter().map(|a|a.difficulty).sum();
+    assert_eq!(SearchObjective::MinimizeTotalSampleSize{overlap:0.0}.cost(&optimal.assertions),sum);
+    assert_eq!(SearchObjective::Weighted{maximum_weight:2.0,total_weight:1.0,overlap:0.5}.cost(&optimal.assertions),54.0+27.0+0.5*(sum-27.0));
+    for objective in [SearchObjective::MinimizeTotalSampleSize{overlap:0.0},SearchObjective::Weighted{maximum_weight:1.0,total_weight:1.0,overlap:0.5}] {
+        let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeAssertions,objective,..Default::default()};
+        let result = raire_with_options(&votes,Some(C),&AUDIT,&options,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+        assert!(objective.cost(&result.assertions)<=objective.cost(&optimal.assertions));
+        assert!(result.difficulty>=optimal.difficulty);
+        let assertions : Vec<Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+        assert_eq!(Ok(()),verify_assertions_rule_out_all_other_winners(&assertions,C,4));
+    }
+    let problem : RaireProblem = serde_json::from_value(json!({"metadata":{},"num_candidates":1,"votes":[],"audit":{"type":"OneOnMargin","total_auditable_ballots":1},"objective":{"MinimizeTotalSampleSize":{"overlap":0.5}}})).unwrap();
+    assert_eq!(Some(SearchObjective::MinimizeTotalSampleSize{overlap:0.5}),problem.objective);
+}
+
+#[test]
+/// Test format versioning and that the JSON schemas describe every field produced.
+fn test_format_version_and_schema() {
+    let problem = json!({"metadata":{},"num_candidates":4,"votes":[{"n":5000,"prefs":[2,1,0]},{"n":1000,"prefs":[1,2,3]},{"n":1500,"prefs":[3,0]},{"n":4000,"prefs":[0,3]},{"n":2000,"prefs":[3]}],"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13500}});
+    let unversioned : RaireProblem = serde_json::from_value(problem.clone()).unwrap();
+    assert_eq!(FORMAT_VERSION,unversioned.format_version);
+    let mut versioned = problem.clone();
+    versioned["format_version"]=json!(FORMAT_VERSION);
+    assert_eq!(versioned,raire::schema::upgrade(problem.clone()).unwrap());
+    let mut too_new = problem.clone();
+    too_new["format_version"]=json!(FORMAT_VERSION+1);
+    assert!(serde_json::from_value::<RaireProblem>(too_new.clone()).unwrap_err().to_string().contains("newer"));
+    assert!(matches!(raire::schema::upgrade(too_new),Err(RaireError::UnsupportedFormatVersion(v)) if v==FORMAT_VERSION as u64+1));
+    let solution = unversioned.clone().solve();
+    let solution_json = serde_json::to_value(&solution).unwrap();
+    assert_eq!(json!(FORMAT_VERSION),solution_json["format_version"]);
+    let read_back : RaireSolution = serde_json::from_value(solution_json.clone()).unwrap();
+    assert_eq!(FORMAT_VERSION,read_back.format_version);
+    // every field written appears in the schema.
+    let problem_schema = raire::schema::problem_schema();
+    let mut all_options = serde_json::to_value(&unversioned).unwrap();
+    for (key,value) in [("seats",json!(1)),("elimination_rule",json!("OneAtATime")),("tie_resolution",json!("Ambiguous")),("reported_elimination_order",json!([1,3,0,2])),("trim_algorithm",json!("MinimizeTree")),("trim_selection_algorithm",json!("TwoPassHeuristic")),("cross_check_trimming",json!(true)),("max_tree_nodes",json!(10)),("difficulty_estimate",json!(1.0)),("time_limit_seconds",json!(1.0)),("max_memory_mb",json!(1)),("risk_limit",json!(0.05)),("error_rates",json!({"one_vote_overstatement":0.001})),("partial_on_timeout",json!(true)),("winner_only_fallback",json!(true)),("two_phase",json!(true)),("stop_when_difficulty_at_most",json!(1.0)),("objective",json!("MinimizeMaximumDifficulty")),("deterministic",json!(true)),("include_tallies",json!(true)),("difficulty_decimal_places",json!(6)),("exact_arithmetic",json!(true)),("assertion_types",json!("NEBOnly"))] {
+        all_options[key]=value;
+    }
+    let all_options : RaireProblem = serde_json::from_value(all_options).unwrap();
+    for key in serde_json::to_value(&all_options).unwrap().as_object().unwrap().keys() {
+        assert!(problem_schema["properties"].get(key).is_some(),"{} missing from problem schema",key);
+    }
+    let solution_schema = raire::schema::solution_schema();
+    for key in solution_json.as_object().unwrap().keys() {
+        assert!(solution_schema["properties"].get(key).is_some(),"{} missing from solution schema",key);
+    }
+    let result_properties = &solution_schema["properties"]["solution"]["oneOf"][0]["properties"]["Ok"]["properties"];
+    for key in solution_json["solution"]["Ok"].as_object().unwrap().keys() {
+        assert!(result_properties.get(key).is_some(),"{} missing from result schema",key);
+    }
+}
+
+#[test]
+/// Test that the internal errors written as just their names before format version 2 are read, and upgraded, as objects without details.
+fn test_format_version_1_internal_errors() {
+    let old = json!({"format_version":1,"metadata":{},"solution":{"Err":"InternalErrorRuledOutWinner"}});
+    let solution : RaireSolution = serde_json::from_value(old.clone()).unwrap();
+    assert!(matches!(solution.solution,Err(RaireError::InternalErrorRuledOutWinner{ winner: None, ref elimination_order, ref assertions }) if elimination_order.is_empty() && assertions.is_empty()));
+    assert_eq!(json!({"InternalErrorRuledOutWinner":{"elimination_order":[],"assertions":[]}}),serde_json::to_value(&solution).unwrap()["solution"]["Err"]);
+    assert_eq!(json!({"format_version":FORMAT_VERSION,"metadata":{},"solution":{"Err":{"InternalErrorRuledOutWinner":{}}}}),raire::schema::upgrade(old).unwrap());
+    let old = json!({"metadata":{},"solution":{"Err":"InternalErrorDidntRuleOutLoser"}});
+    let solution : RaireSolution = serde_json::from_value(old).unwrap();
+    assert!(matches!(solution.solution,Err(RaireError::InternalErrorDidntRuleOutLoser{ candidate: None, .. })));
+    assert!(solution.solution.unwrap_err().to_string().contains("(not recorded)"));
+    // the current form, and other errors, are unchanged.
+    let current = json!({"metadata":{},"solution":{"Err":{"InternalErrorDidntRuleOutLoser":{"candidate":2,"elimination_order":[1,2]}}}});
+    let solution : RaireSolution = serde_json::from_value(current.clone()).unwrap();
+    assert_eq!(current["solution"],serde_json::to_value(&solution).unwrap()["solution"]);
+    let solution : RaireSolution = serde_json::from_value(json!({"metadata":{},"solution":{"Err":"InternalErrorTrimming"}})).unwrap();
+    assert!(matches!(solution.solution,Err(RaireError::InternalErrorTrimming)));
+}
+
+#[test]
+/// Test exporting to SHANGRLA format.
+fn test_raire_shangrla_export() {
+    let problem = RaireProblem{ metadata: json!({ "candidates" : ["Alice","Bob","Chuan","Diego"], "contest" : "Guide" }), trim_algorithm: Some(TrimAlgorithm::MinimizeTree), ..guide_problem(Audit::OneOnMargin(AUDIT)) };
+    let solution = problem.solve();
+    let exported = to_shangrla(&solution,"default").unwrap();
+    let json = serde_json::to_value(&exported).unwrap();
+    let audit = &json["audits"][0];
+    assert_eq!("Guide",audit["contest"]);
+    assert_eq!("Chuan",audit["winner"]);
+    assert_eq!(json!(["Alice","Bob","Diego"]),audit["eliminated"]);
+    let assertions = audit["assertions"].as_array().unwrap();
+    assert_eq!(solution.solution.as_ref().unwrap().assertions.len(),assertions.len());
+    assert!(assertions.iter().any(|a|a["assertion_type"]=="WINNER_ONLY" && a["winner"]=="Chuan" && a["loser"]=="Bob"));
+    assert!(assertions.iter().any(|a|a["assertion_type"]=="IRV_ELIMINATION" && a["winner"]=="Chuan" && a["loser"]=="Alice" && a["already_eliminated"]==json!(["Bob","Diego"])));
+    // failures are passed on.
+    let failed = RaireSolution{metadata:json!({}),solution:Err(raire::RaireError::TiedWinners(vec![CandidateIndex(0),CandidateIndex(1)])),manifest:None,sample_sizes:None,tied_rounds:None,format_version:FORMAT_VERSION};
+    assert!(to_shangrla(&failed,"default").is_err());
+}
+
+#[test]
+/// Test that multi-seat contests are rejected rather than treated as IRV.
+fn test_stv_not_supported() {
+    let problem = RaireProblem{ seats: Some(2), ..guide_problem(Audit::OneOnMargin(AUDIT)) };
+    assert!(matches!(problem.clone().solve().solution,Err(raire::RaireError::StvNotSupported{seats:2})));
+    assert!(RaireProblem{seats:Some(1),..problem}.solve().solution.is_ok());
+}
+
+#[test]
+/// Test batch elimination gives the same winner, eliminating doomed candidates together.
+fn test_batch_elimination() {
+    let votes = get_votes();
+    let result = votes.run_election_with_rules(EliminationRule::BatchElimination,&TieResolution::Ambiguous,&mut TimeOut::never()).unwrap();
+    assert_eq!(vec![C],result.possible_winners);
+    assert_eq!(vec![B,D,A,C],result.elimination_order);
+    // Bob (1) and Diego (2) are both doomed as together they have fewer first preferences than Alice (4) or Chuan (5).
+    let doomed = Votes::new(vec![
+        Vote{ n: BallotPaperCount(4), prefs: vec![A]},
+        Vote{ n: BallotPaperCount(5), prefs: vec![C]},
+        Vote{ n: BallotPaperCount(1), prefs: vec![B,A]},
+        Vote{ n: BallotPaperCount(2), prefs: vec![D,A]},
+    ],4).unwrap();
+    let batch = doomed.run_election_with_rules(EliminationRule::BatchElimination,&TieResolution::Ambiguous,&mut TimeOut::never()).unwrap();
+    let single = doomed.run_election(&mut TimeOut::never()).unwrap();
+    assert_eq!(vec![A],batch.possible_winners);
+    assert_eq!(single.possible_winners,batch.possible_winners);
+    assert_eq!(vec![B,D,C,A],batch.elimination_order);
+}
+
+#[test]
+/// Test the different tie resolution rules.
+fn test_tie_resolution() {
+    // Diego is eliminated first, then Alice and Bob are tied on 3 each, although Bob had fewer votes in the first round.
+    let votes = Votes::new(vec![
+        Vote{ n: BallotPaperCount(3), prefs: vec![A,C]},
+        Vote{ n: BallotPaperCount(2), prefs: vec![B,A]},
+        Vote{ n: BallotPaperCount(5), prefs: vec![C]},
+        Vote{ n: BallotPaperCount(1), prefs: vec![D,B,A]},
+    ],4).unwrap();
+    let run = |ties:TieResolution| votes.run_election_with_rules(EliminationRule::OneAtATime,&ties,&mut TimeOut::never()).unwrap();
+    let mut ambiguous = run(TieResolution::Ambiguous).possible_winners;
+    ambiguous.sort_by_key(|c|c.0);
+    assert_eq!(vec![A,C],ambiguous);
+    assert!(run(TieResolution::Ambiguous).tie_breaks.is_empty());
+    let lexicographic = run(TieResolution::Lexicographic);
+    assert_eq!(vec![A],lexicographic.possible_winners);
+    assert_eq!(vec![D,B,C,A],lexicographic.elimination_order);
+    assert_eq!(vec![TieBreak{tied:vec![A,B],eliminated:B}],lexicographic.tie_breaks);
+    assert_eq!(vec![A],run(TieResolution::EarlierRoundTallies).possible_winners);
+    let ordered = run(TieResolution::Order(vec![C,B,A,D]));
+    assert_eq!(vec![C],ordered.possible_winners);
+    assert_eq!(vec![TieBreak{tied:vec![A,B],eliminated:A}],ordered.tie_breaks);
+    // with no earlier round to break the tie, it is ambiguous.
+    let first_round_tie = Votes::new(vec![Vote{ n: BallotPaperCount(3), prefs: vec![A,C]},Vote{ n: BallotPaperCount(3), prefs: vec![B,A]},Vote{ n: BallotPaperCount(5), prefs: vec![C]}],3).unwrap();
+    assert_eq!(2,first_round_tie.run_election_with_rules(EliminationRule::OneAtATime,&TieResolution::EarlierRoundTallies,&mut TimeOut::never()).unwrap().possible_winners.len());
+}
+
+#[test]
+/// Test that an exact final round tie is reported as all the co-winners rather than picking one.
+fn test_exact_tie_reports_co_winners() {
+    let problem = RaireProblem{ num_candidates: 3, votes: vec![Vote{ n: BallotPaperCount(5), prefs: vec![A]},Vote{ n: BallotPaperCount(3), prefs: vec![B]},Vote{ n: BallotPaperCount(2), prefs: vec![C,B]}], winner: None, ..guide_problem(Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{total_auditable_ballots:BallotPaperCount(10)})) };
+    let solution = problem.solve();
+    match solution.solution {
+        Err(raire::RaireError::TiedWinners(mut winners)) => { winners.sort_by_key(|c|c.0); assert_eq!(vec![A,B],winners); }
+        other => panic!("Expecting TiedWinners, got {:?}",other),
+    }
+    // the tallies of the count show the tie.
+    let rounds = solution.tied_rounds.unwrap();
+    assert_eq!(2,rounds.len());
+    assert_eq!((vec![A,B,C],vec![BallotPaperCount(5),BallotPaperCount(3),BallotPaperCount(2)],vec![C]),(rounds[0].continuing.clone(),rounds[0].tallies.clone(),rounds[0].eliminated.clone()));
+    assert_eq!((vec![A,B],vec![BallotPaperCount(5),BallotPaperCount(5)]),(rounds[1].continuing.clone(),rounds[1].tallies.clone()));
+}
+
+#[test]
+/// Test Monte Carlo simulation of an audit of the assertions.
+fn test_simulate_audit() {
+    let solution = get_solution_minimize_tree();
+    let test = BallotComparisonALPHA{ confidence: 0.05, total_auditable_ballots: AUDIT.total_auditable_ballots, d: 100.0, eta0: None };
+    let no_errors = simulate_audit(&solution,&SimulationParameters{ test, error_rates: ErrorRates::default(), trials: 10, seed: 1 });
+    // with no errors, every trial samples the number needed by the hardest assertion.
+    let hardest = solution.assertions.iter().map(|a|test.difficulty(BallotPaperCount(a.margin.0),BallotPaperCount(0))).fold(0.0,f64::max);
+    assert_eq!(vec![hardest as usize;10],no_errors.sample_sizes);
+    assert_eq!(0.0,no_errors.full_hand_count_fraction);
+    let error_rates = ErrorRates{ one_vote_overstatement: 0.01, two_vote_overstatement: 0.002, ..Default::default() };
+    let with_errors = simulate_audit(&solution,&SimulationParameters{ test, error_rates, trials: 200, seed: 1 });
+    assert!(with_errors.mean>no_errors.mean);
+    assert!(with_errors.quantile(0.9)>=with_errors.quantile(0.5));
+    let again = simulate_audit(&solution,&SimulationParameters{ test, error_rates, trials: 200, seed: 1 });
+    assert_eq!(with_errors.sample_sizes,again.sample_sizes);
+    let hopeless = simulate_audit(&solution,&SimulationParameters{ test, error_rates: ErrorRates{ two_vote_overstatement: 0.5, ..Default::default() }, trials: 3, seed: 1 });
+    assert_eq!(1.0,hopeless.full_hand_count_fraction);
+    assert_eq!(vec![13500;3],hopeless.sample_sizes);
+}
+
+#[test]
+/// Test that malformed input, such as fuzzing produces, gives an error rather than a panic.
+fn test_malformed_input_gives_errors() {
+    // a candidate out of range other than as the first preference.
+    assert!(matches!(Votes::new(vec![Vote{ n: BallotPaperCount(1), prefs: vec![A,CandidateIndex(7)] }],4),Err(RaireError::InvalidCandidateNumber)));
+    let huge = ||Vote{ n: BallotPaperCount(usize::MAX), prefs: vec![A] };
+    assert!(matches!(Votes::new(vec![huge(),huge()],4),Err(RaireError::TooManyBallots)));
+    let problem : RaireProblem = serde_json::from_value(json!({"metadata":{},"num_candidates":4,"votes":serde_json::to_value(get_votes().votes).unwrap(),"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13500},"time_limit_seconds":1e300})).unwrap();
+    assert_eq!(C,problem.clone().solve().solution.unwrap().winner); // too long to represent is no limit.
+    // a previous solution whose assertions mention candidates that don't exist.
+    let mut previous = problem.clone().solve();
+    if let Ok(result) = &mut previous.solution { result.assertions[0].assertion = Assertion::NEB(NotEliminatedBefore{ winner: C, loser: CandidateIndex(9) }); }
+    let mut problem = problem;
+    let incremental = problem.solve_incrementally(&previous,&BallotDelta::default());
+    assert!(matches!(incremental.solution.solution,Err(RaireError::InvalidCandidateNumberIn{ candidate: CandidateIndex(9), num_candidates: 4, .. })));
+    assert!(matches!(Assertion::NEB(NotEliminatedBefore{ winner: C, loser: CandidateIndex(9) }).validate(4),Err(RaireError::InvalidCandidateNumberIn{ candidate: CandidateIndex(9), .. })));
+}
+
+#[test]
+/// Test solving several contests together.
+fn test_solve_many() {
+    use raire::batch::{solve_many, BatchReport};
+    let problem = |metadata:serde_json::Value,total:usize| -> RaireProblem { serde_json::from_value(json!({"metadata":metadata,"num_candidates":4,"votes":get_votes().votes,"audit":{"type":"OneOnMargin","total_auditable_ballots":total}})).unwrap() };
+    let problems = vec![
+        problem(json!({"candidates":["Alice","Bob","Chuan","Diego"]}),13500),
+        problem(json!({}),27000), // twice as many ballots, so the diluted margins are halved.
+        problem(json!({}),100), // fewer ballots in the manifest than votes.
+    ];
+    let solutions = solve_many(problems.clone());
+    assert_eq!(3,solutions.len());
+    for (problem,solution) in problems.into_iter().zip(&solutions) {
+        assert_eq!(serde_json::to_value(problem.solve().solution.map(|r|r.difficulty)).unwrap(),serde_json::to_value(solution.solution.as_ref().map(|r|r.difficulty)).unwrap());
+    }
+    let names = vec!["Mayor".to_string(),"Council".to_string(),"Broken".to_string()];
+    let report = BatchReport::new(&names,&solutions);
+    assert_eq!(Some(1),report.hardest_contest);
+    assert_eq!(1,report.failed);
+    assert_eq!(Some("Chuan".to_string()),report.contests[0].winner);
+    assert_eq!(Some(27.0),report.contests[0].difficulty);
+    assert_eq!(Some("#2".to_string()),report.contests[1].winner);
+    assert_eq!(Some(54.0),report.contests[1].difficulty);
+    assert!(matches!(report.contests[2].error,Some(RaireError::ManifestSmallerThanVotes{..})));
+    assert!(solve_many(vec![]).is_empty());
+}
+
+#[test]
+/// Test seeding the search with the assertions for a preliminary count.
+fn test_warm_start() {
+    let preliminary = raire(&get_votes(),Some(C),&AUDIT,TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    assert_eq!(None,preliminary.warm_start_difficulty);
+    let mut votes = get_votes().votes;
+    votes.push(Vote{ n: BallotPaperCount(100), prefs: vec![C,B] });
+    let votes = Votes::new(votes,4).unwrap();
+    let audit = BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(13600) };
+    let solve = |warm_start_assertions:Vec<Assertion>| {
+        let options = RaireOptions{trim_algorithm:TrimAlgorithm::MinimizeTree,warm_start_assertions,..Default::default()};
+        raire_with_options(&votes,Some(C),&audit,&options,&mut TimeOut::never(),&mut NoProgressReporter)
+    };
+    let cold = solve(vec![]).unwrap();
+    let previous : Vec<Assertion> = preliminary.assertions.iter().map(|a|a.assertion.clone()).collect();
+    let warm = solve(previous.clone()).unwrap();
+    let bound = warm.warm_start_difficulty.unwrap();
+    assert_eq!(13600.0/500.0,bound); // Chuan > Alice if only {Alice,Chuan} remain, now with a margin of 500.
+    assert!(warm.difficulty<=bound);
+    assert_eq!(cold.difficulty,warm.difficulty); // as the hardest assertion is unavoidable.
+    let assertions : Vec<Assertion> = warm.assertions.iter().map(|a|a.assertion.clone()).collect();
+    assert_eq!(Ok(()),verify_assertions_rule_out_all_other_winners(&assertions,C,4));
+    // not enough assertions to rule out every other winner, so the warm start is ignored.
+    let insufficient = solve(previous[1..].to_vec()).unwrap();
+    assert_eq!(None,insufficient.warm_start_difficulty);
+    assert_eq!(cold.assertions,insufficient.assertions);
+    assert!(matches!(solve(vec![Assertion::NEB(NotEliminatedBefore{winner:C,loser:CandidateIndex(7)})]),Err(RaireError::InvalidCandidateNumberIn{..})));
+}
+
+#[test]
+/// Test estimating the ballots to retrieve when two contests share ballots.
+fn test_coordinate_samples() {
+    use raire::coordination::{coordinate_samples, BallotStyle};
+    let solution = get_solution_minimize_tree();
+    let style = |name:&str,ballots:usize,contests:&[&str]| BallotStyle{ name: name.to_string(), ballots: BallotPaperCount(ballots), contests: contests.iter().map(|c|c.to_string()).collect() };
+    let styles = vec![style("both",13500,&["Mayor","Council"]),style("council only",13500,&["Council"]),style("neither",1000,&["Dog catcher"])];
+    let contests = vec![("Mayor".to_string(),&solution),("Council".to_string(),&solution)];
+    let report = coordinate_samples(&contests,&styles,&[0.05,0.1]).unwrap();
+    assert_eq!(BallotPaperCount(13500),report.contests[0].ballots);
+    assert_eq!(BallotPaperCount(27000),report.contests[1].ballots);
+    for (i,total) in report.totals.iter().enumerate() {
+        let mayor = report.contests[0].sample_sizes[i];
+        let council = report.contests[1].sample_sizes[i];
+        assert!(council>mayor); // same margin spread over twice as many ballots.
+        assert_eq!(mayor+council,total.independent_ballots);
+        assert_eq!(Some(1),total.largest_contest);
+        assert_eq!(council,total.largest_contest_sample);
+        let expected = 13500.0*(mayor as f64/13500.0).max(council as f64/27000.0)+13500.0*council as f64/27000.0;
+        assert!((expected-total.consistent_ballots).abs()<1e-6);
+        assert!(total.consistent_ballots<=total.independent_ballots as f64);
+        assert!(total.consistent_ballots>=total.largest_contest_sample as f64-1e-6);
+    }
+    assert!(report.totals[0].independent_ballots>report.totals[1].independent_ballots); // a lower risk limit costs more.
+    assert!(matches!(coordinate_samples(&[("Governor".to_string(),&solution)],&styles,&[0.05]),Err(RaireError::ContestNotOnAnyBallots(name)) if name=="Governor"));
+}
+
+#[test]
+/// Test sets of continuing candidates are canonical, and ordered the same whether or not they fit in a bitmask.
+fn test_sorted_candidate_subset() {
+    let subset = |candidates:&[u32]| candidates.iter().map(|&c|CandidateIndex(c)).collect::<SortedCandidateSubset>();
+    let list = |s:&SortedCandidateSubset| s.iter().map(|c|c.0).collect::<Vec<_>>();
+    assert_eq!(vec![0,2,3],list(&subset(&[3,0,2,0])));
+    assert_eq!(subset(&[3,0,2]),subset(&[0,2,3]));
+    assert!(subset(&[3,0,2]).includes(C));
+    assert!(!subset(&[3,0,2]).includes(B));
+    assert!(!subset(&[3,0,2]).includes(CandidateIndex(200)));
+    assert!(subset(&[3,0,2]).as_set().is_some());
+    assert!(subset(&[3,200]).as_set().is_none());
+    assert!(subset(&[3,200]).includes(CandidateIndex(200)));
+    assert!(!subset(&[3,200]).includes(CandidateIndex(100)));
+    // smaller sets first, then lexicographically as lists, with or without bitmasks.
+    let sets = [subset(&[5]),subset(&[0,7]),subset(&[1,2]),subset(&[1,3]),subset(&[0,1,127]),subset(&[0,2,3]),subset(&[0,2,200])];
+    for i in 0..sets.len() {
+        for j in 0..sets.len() {
+            assert_eq!(i.cmp(&j),sets[i].cmp(&sets[j]),"comparing {:?} and {:?}",sets[i],sets[j]);
+            assert_eq!(i==j,sets[i]==sets[j]);
+        }
+    }
+    // serialized as a list, and normalized when deserialized.
+    assert_eq!(json!([0,2,200]),serde_json::to_value(&sets[6]).unwrap());
+    assert_eq!(sets[5],serde_json::from_value::<SortedCandidateSubset>(json!([3,2,0,2])).unwrap());
+    assert_eq!("[#0, #2, #3]",format!("{:?}",sets[5]));
+}
+
+#[test]
+/// Test comparing the assertions of two solutions, matching candidates by index or by name.
+fn test_diff_solutions() {
+    use raire::assertions::AssertionAndDifficulty;
+    use raire::candidates::CandidateList;
+    let result = get_solution_minimize_tree();
+    let solution = |metadata:serde_json::Value,result:RaireResult| RaireSolution{ metadata, solution: Ok(result), manifest: None, sample_sizes: None, tied_rounds: None, format_version: FORMAT_VERSION };
+    let first = solution(json!({}),result.clone());
+    let same = first.diff(&first).unwrap();
+    assert!(same.is_same(0.0));
+    assert!(!same.matched_by_name);
+    assert_eq!(result.assertions.len(),same.common.len());
+    // remove one assertion, change the difficulty of another, add a third, and reorder.
+    let mut assertions = result.assertions.clone();
+    let removed = assertions.remove(0);
+    assertions[0].difficulty*=1.5;
+    let changed = assertions[0].assertion.clone();
+    let extra = AssertionAndDifficulty{ assertion: Assertion::NEB(NotEliminatedBefore{ winner: A, loser: B }), margin: BallotPaperCount(10), difficulty: 1350.0, tallies: None };
+    assert!(result.assertions.iter().all(|a|a.assertion!=extra.assertion));
+    assertions.push(extra.clone());
+    assertions.reverse();
+    let diff = first.diff(&solution(json!({}),RaireResult{ assertions, ..result.clone() })).unwrap();
+    assert!(!diff.is_same(1e-9));
+    assert_eq!(vec![removed],diff.removed);
+    assert_eq!(vec![extra],diff.added);
+    assert_eq!(result.assertions.len()-1,diff.common.len());
+    assert_eq!(vec![changed],diff.changed(1e-9).map(|a|a.assertion.clone()).collect::<Vec<_>>());
+    assert!(diff.changed(0.5).next().is_none());
+    // the same solution with candidates numbered the other way round.
+    let names = CandidateList::new(vec!["Alice".to_string(),"Bob".to_string(),"Chuan".to_string(),"Diego".to_string()]).unwrap();
+    let reversed_names = CandidateList::new(names.names().iter().rev().cloned().collect()).unwrap();
+    let reverse = |c:CandidateIndex|CandidateIndex(3-c.0);
+    let reversed = RaireResult{
+        winner: reverse(result.winner),
+        assertions: result.assertions.iter().map(|a|AssertionAndDifficulty{ assertion: reversed_names.resolve_assertion(&names.named_assertion(&a.assertion)).unwrap(), ..a.clone() }).collect(),
+        ..result.clone()
+    };
+    let first = solution(json!({"candidates":names.names()}),result.clone());
+    let diff = first.diff(&solution(json!({"candidates":reversed_names.names()}),reversed.clone())).unwrap();
+    assert!(diff.matched_by_name);
+    assert!(diff.is_same(0.0));
+    assert!(!solution(json!({}),result.clone()).diff(&solution(json!({}),reversed)).unwrap().is_same(0.0));
+    let unknown = solution(json!({"candidates":["Alice","Bob","Chuan","Eve"]}),result.clone());
+    assert!(matches!(first.diff(&unknown),Err(RaireError::UnknownCandidateName(name)) if name=="Eve"));
+    let failed = RaireSolution{ solution: Err(RaireError::TiedWinners(vec![A,B])), ..first.clone() };
+    assert!(matches!(first.diff(&failed),Err(RaireError::TiedWinners(_))));
+}
+
+#[test]
+/// Test the round by round count, and annotating it with the assertions about each round.
+fn test_round_by_round() {
+    use raire::rounds::RoundByRound;
+    let votes = get_votes();
+    let irv = votes.run_election(&mut TimeOut::never()).unwrap();
+    assert_eq!(vec![vec![B],vec![D],vec![A]],irv.rounds.iter().map(|r|r.eliminated.clone()).collect::<Vec<_>>());
+    assert_eq!(vec![A,B,C,D],irv.rounds[0].continuing);
+    for round in &irv.rounds { assert_eq!(votes.total_votes(),round.tallies.iter().cloned().sum::<BallotPaperCount>()+round.exhausted); }
+    let result = get_solution_minimize_tree();
+    let assertions : Vec<Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+    let rounds = RoundByRound::new(&irv,&assertions);
+    assert_eq!(C,rounds.winner);
+    assert_eq!(3,rounds.rounds.len());
+    for (previous,round) in rounds.rounds.iter().zip(&rounds.rounds[1..]) {
+        // the votes of the eliminated candidates are transferred or exhausted.
+        let eliminated : BallotPaperCount = previous.tallies.iter().filter(|t|previous.eliminated.contains(&t.candidate)).map(|t|t.tally).sum();
+        assert_eq!(eliminated,round.tallies.iter().map(|t|t.transferred).sum::<BallotPaperCount>()+round.newly_exhausted);
+    }
+    assert!(rounds.rounds[0].tallies.iter().all(|t|t.transferred==BallotPaperCount(0)));
+    // every assertion is about at most one round, and every NEB assertion about exactly one.
+    for (i,assertion) in assertions.iter().enumerate() {
+        let about = rounds.rounds.iter().filter(|r|r.assertions.contains(&i)).count()+if rounds.other_assertions.contains(&i) {1} else {0};
+        assert_eq!(1,about);
+        if let Assertion::NEB(_) = assertion { assert!(!rounds.other_assertions.contains(&i)); }
+    }
+    let nen_last_round = NotEliminatedNext{ winner: C, loser: A, continuing: vec![A,C].into() };
+    let rounds = RoundByRound::new(&irv,&[Assertion::NEN(nen_last_round),Assertion::NEB(NotEliminatedBefore{ winner: C, loser: D }),Assertion::NEB(NotEliminatedBefore{ winner: A, loser: B })]);
+    assert_eq!(vec![vec![2],vec![1],vec![0]],rounds.rounds.iter().map(|r|r.assertions.clone()).collect::<Vec<_>>());
+    assert!(rounds.other_assertions.is_empty());
+    // candidates eliminated together in a batch form one round.
+    let doomed = Votes::new(vec![
+        Vote{ n: BallotPaperCount(4), prefs: vec![A]},
+        Vote{ n: BallotPaperCount(6), prefs: vec![C]},
+        Vote{ n: BallotPaperCount(1), prefs: vec![B,A]},
+        Vote{ n: BallotPaperCount(2), prefs: vec![D]},
+    ],4).unwrap();
+    let batch = doomed.run_election_with_rules(EliminationRule::BatchElimination,&TieResolution::Ambiguous,&mut TimeOut::never()).unwrap();
+    let rounds = RoundByRound::new(&batch,&[Assertion::NEB(NotEliminatedBefore{ winner: A, loser: B }),Assertion::NEB(NotEliminatedBefore{ winner: B, loser: D })]);
+    assert_eq!(vec![vec![B,D],vec![A]],rounds.rounds.iter().map(|r|r.eliminated.clone()).collect::<Vec<_>>());
+    assert_eq!(BallotPaperCount(2),rounds.rounds[1].newly_exhausted);
+    assert_eq!(BallotPaperCount(1),rounds.rounds[1].tallies.iter().find(|t|t.candidate==A).unwrap().transferred);
+    assert_eq!(vec![0],rounds.rounds[0].assertions);
+    assert_eq!(vec![1],rounds.other_assertions); // Bob and Diego are eliminated together.
+    assert_eq!(C,rounds.winner);
+}
+
+#[test]
+/// Test checking an elimination order reported by the election authority against the count.
+fn test_reported_elimination_order() {
+    use raire::irv::EliminationOrderDivergence;
+    let problem = |reported:Vec<CandidateIndex>| RaireProblem{ reported_elimination_order: Some(reported), ..guide_problem(Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(13500) })) };
+    assert!(problem(vec![B,D,A]).solve().solution.is_ok());
+    assert!(problem(vec![B,D,A,C]).solve().solution.is_ok());
+    let votes = get_votes();
+    let tallies = votes.restricted_tallies(&[A,B,C,D]);
+    match problem(vec![D,B,A,C]).solve().solution {
+        Err(RaireError::ReportedEliminationOrderDiffers(divergence)) => assert_eq!(EliminationOrderDivergence{
+            round: 1, continuing: vec![A,B,C,D], tallies: tallies.clone(), exhausted: BallotPaperCount(0),
+            reported_eliminated: vec![D], computed_eliminated: vec![B], affected_ballots: tallies[1]+tallies[3],
+        },*divergence),
+        other => panic!("unexpected {:?}",other),
+    }
+    // diverging in the last round means a different winner.
+    assert!(matches!(problem(vec![B,D,C,A]).solve().solution,Err(RaireError::ReportedEliminationOrderDiffers(divergence)) if divergence.round==3 && divergence.reported_eliminated==vec![C]));
+    assert!(matches!(problem(vec![B,B,A]).solve().solution,Err(RaireError::InvalidReportedEliminationOrder)));
+    assert!(matches!(problem(vec![B,D]).solve().solution,Err(RaireError::InvalidReportedEliminationOrder)));
+    assert!(matches!(problem(vec![B,D,CandidateIndex(7)]).solve().solution,Err(RaireError::InvalidCandidateNumberIn{candidate:CandidateIndex(7),..})));
+    // ties, resolved or not.
+    let tied = Votes::new(vec![
+        Vote{ n: BallotPaperCount(3), prefs: vec![A,C]},
+        Vote{ n: BallotPaperCount(2), prefs: vec![B,A]},
+        Vote{ n: BallotPaperCount(5), prefs: vec![C]},
+        Vote{ n: BallotPaperCount(1), prefs: vec![D,B,A]},
+    ],4).unwrap();
+    let check = |rule:EliminationRule,ties:TieResolution,votes:&Votes,reported:&[CandidateIndex]| votes.check_reported_elimination_order(rule,&ties,reported).unwrap();
+    assert_eq!(None,check(EliminationRule::OneAtATime,TieResolution::Ambiguous,&tied,&[D,A,B]));
+    assert_eq!(None,check(EliminationRule::OneAtATime,TieResolution::Ambiguous,&tied,&[D,B,C]));
+    assert_eq!(None,check(EliminationRule::OneAtATime,TieResolution::Lexicographic,&tied,&[D,B,C]));
+    let divergence = check(EliminationRule::OneAtATime,TieResolution::Lexicographic,&tied,&[D,A,B]).unwrap();
+    assert_eq!((2,vec![A],vec![B],BallotPaperCount(6)),(divergence.round,divergence.reported_eliminated,divergence.computed_eliminated,divergence.affected_ballots));
+    // candidates eliminated together in a batch may be reported in either order.
+    let doomed = Votes::new(vec![
+        Vote{ n: BallotPaperCount(4), prefs: vec![A]},
+        Vote{ n: BallotPaperCount(6), prefs: vec![C]},
+        Vote{ n: BallotPaperCount(1), prefs: vec![B,A]},
+        Vote{ n: BallotPaperCount(2), prefs: vec![D]},
+    ],4).unwrap();
+    assert_eq!(None,check(EliminationRule::BatchElimination,TieResolution::Ambiguous,&doomed,&[D,B,A]));
+    assert_eq!(None,check(EliminationRule::BatchElimination,TieResolution::Ambiguous,&doomed,&[B,D,A,C]));
+    let divergence = check(EliminationRule::BatchElimination,TieResolution::Ambiguous,&doomed,&[B,A,D]).unwrap();
+    assert_eq!((1,vec![B,A],vec![B,D],BallotPaperCount(6)),(divergence.round,divergence.reported_eliminated,divergence.computed_eliminated,divergence.affected_ballots));
+    // a doomed candidate with no votes cannot stand in for a candidate that was not doomed.
+    let e = CandidateIndex(4);
+    let zero_tallies = Votes::new(vec![
+        Vote{ n: BallotPaperCount(5), prefs: vec![C]},
+        Vote{ n: BallotPaperCount(10), prefs: vec![D]},
+        Vote{ n: BallotPaperCount(12), prefs: vec![e]},
+    ],5).unwrap();
+    let divergence = check(EliminationRule::BatchElimination,TieResolution::Ambiguous,&zero_tallies,&[A,C,D,B]).unwrap();
+    let mut computed_eliminated = divergence.computed_eliminated.clone();
+    computed_eliminated.sort_by_key(|c|c.0);
+    assert_eq!((1,vec![A,C,D],vec![A,B,C],BallotPaperCount(10)),(divergence.round,divergence.reported_eliminated,computed_eliminated,divergence.affected_ballots));
+}
+
+#[test]
+/// Test the SHANGRLA assorter values of assertions on ballots.
+fn test_assorter_values() {
+    use raire::export::assorters::AssorterMatrix;
+    let neb = Assertion::NEB(NotEliminatedBefore{winner:C,loser:A});
+    assert_eq!(1.0,neb.assorter_value(&[C,A]));
+    assert_eq!(0.0,neb.assorter_value(&[B,A,C]));
+    assert_eq!(0.0,neb.assorter_value(&[A]));
+    assert_eq!(0.5,neb.assorter_value(&[B,C,A]));
+    assert_eq!(0.5,neb.assorter_value(&[]));
+    let nen = Assertion::NEN(NotEliminatedNext{winner:A,loser:D,continuing:vec![A,C,D].into()});
+    assert_eq!(1.0,nen.assorter_value(&[B,A,D]));
+    assert_eq!(0.0,nen.assorter_value(&[D,A]));
+    assert_eq!(0.5,nen.assorter_value(&[B]));
+    let votes = get_votes();
+    let matrix = AssorterMatrix::new(&[neb.clone(),nen.clone()],&votes.votes);
+    assert_eq!(5,matrix.rows.len());
+    assert_eq!(vec![1.0,0.5],matrix.rows[0].values); // C,B,A
+    assert_eq!(vec![0.0,0.0],matrix.rows[2].values); // D,A
+    // the mean is more than 1/2 exactly when the margin is positive, by half the diluted margin.
+    let total = votes.total_votes().0 as f64;
+    for (assertion,mean) in matrix.assertions.iter().zip(matrix.means()) {
+        assert!((mean-0.5-assertion.signed_margin(&votes) as f64/total/2.0).abs()<1e-12);
+    }
+    assert_eq!(vec![0.5,0.5],AssorterMatrix::new(&[neb,nen],&[]).means());
+}
+
+#[test]
+/// Test the discrepancies between CVRs and paper ballots.
+fn test_discrepancies() {
+    use raire::audit_progress::{count_discrepancies, discrepancies, DiscrepancyCounts};
+    let neb = Assertion::NEB(NotEliminatedBefore{winner:C,loser:A});
+    assert_eq!(2,neb.discrepancy(&[C],&[A,C]));
+    assert_eq!(1,neb.discrepancy(&[C],&[]));
+    assert_eq!(0,neb.discrepancy(&[C,A],&[C]));
+    assert_eq!(-1,neb.discrepancy(&[B],&[C,B]));
+    assert_eq!(-2,neb.discrepancy(&[A],&[C]));
+    let nen = Assertion::NEN(NotEliminatedNext{winner:A,loser:D,continuing:vec![A,C,D].into()});
+    for cvr in [vec![A],vec![D],vec![B,C],vec![]] {
+        for paper in [vec![A],vec![D],vec![B,C],vec![]] {
+            assert_eq!(2.0*(nen.assorter_value(&cvr)-nen.assorter_value(&paper)),nen.discrepancy(&cvr,&paper) as f64);
+        }
+    }
+    let ballot = |cvr:Vec<CandidateIndex>,paper:Vec<CandidateIndex>| BallotInterpretation{ ballot_id: None, cvr, paper };
+    let ballots = vec![ballot(vec![C],vec![A]),ballot(vec![A],vec![A]),ballot(vec![C],vec![D])];
+    let assertions = vec![neb,nen];
+    assert_eq!(vec![vec![2,-1],vec![0,0],vec![1,1]],discrepancies(&assertions,&ballots));
+    let counts = count_discrepancies(&assertions,&ballots);
+    assert_eq!(DiscrepancyCounts{ ballots: 3, one_vote_overstatements: 1, two_vote_overstatements: 1, one_vote_understatements: 0, two_vote_understatements: 0 },counts[0]);
+    assert_eq!(DiscrepancyCounts{ ballots: 3, one_vote_overstatements: 1, two_vote_overstatements: 0, one_vote_understatements: 1, two_vote_understatements: 0 },counts[1]);
+    let rates = counts[1].rates();
+    assert_eq!((1.0/3.0,0.0,1.0/3.0,0.0),(rates.one_vote_overstatement,rates.two_vote_overstatement,rates.one_vote_understatement,rates.two_vote_understatement));
+    assert_eq!(0.0,DiscrepancyCounts::default().rates().one_vote_overstatement);
+}
+
+#[test]
+/// Test drawing a sample of ballots from a seed, against draws computed separately with Python's hashlib.
+fn test_sampling() {
+    use raire::ballot_manifest::{BallotLocation, BallotManifest, ManifestBatch};
+    use raire::sampling::{draw_ballot, SampleRound};
+    let seed = "32108263917458210347";
+    assert_eq!(vec![640,160,654,60,951,819,814,683],(1..=8).map(|k|draw_ballot(seed,k,1000)).collect::<Vec<_>>());
+    assert_eq!(vec![12640,5160,1154,8560,1451,9319,13314,7183],(1..=8).map(|k|draw_ballot(seed,k,13500)).collect::<Vec<_>>());
+    let manifest = BallotManifest{ batches: vec![ManifestBatch{ batch_id: "Box 1".to_string(), ballots: 4 },ManifestBatch{ batch_id: "Box 2".to_string(), ballots: 3 }] };
+    assert_eq!(7,manifest.total_ballots());
+    assert_eq!(Some(BallotLocation{ batch_id: "Box 2".to_string(), position: 1 }),manifest.locate(5));
+    assert_eq!(None,manifest.locate(0));
+    assert_eq!(None,manifest.locate(8));
+    // the draws are 7,6,5,3,4,5,6,7.
+    let round = SampleRound::new(seed,&manifest,0,5).unwrap();
+    assert_eq!(vec![7,6,5,3,4],round.draws.iter().map(|d|d.ballot).collect::<Vec<_>>());
+    assert!(round.draws.iter().all(|d|!d.repeat));
+    assert_eq!(vec![3,4,5,6,7],round.pull_list().iter().map(|d|d.ballot).collect::<Vec<_>>());
+    assert_eq!(BallotLocation{ batch_id: "Box 1".to_string(), position: 3 },round.draws[3].location);
+    // the next round continues the sequence, and ballots drawn before need not be retrieved again.
+    let next = SampleRound::new(seed,&manifest,5,3).unwrap();
+    assert_eq!(vec![(6,5,true),(7,6,true),(8,7,true)],next.draws.iter().map(|d|(d.draw,d.ballot,d.repeat)).collect::<Vec<_>>());
+    assert!(next.pull_list().is_empty());
+    assert_eq!(round.draws,SampleRound::new(seed,&manifest,0,8).unwrap().draws[..5]);
+    assert!(matches!(SampleRound::new(seed,&BallotManifest::unbatched(0),0,1),Err(RaireError::InvalidBallotManifest(_))));
+    let repeated = BallotManifest{ batches: vec![manifest.batches[0].clone(),manifest.batches[0].clone()] };
+    assert!(matches!(repeated.check(),Err(RaireError::InvalidBallotManifest(_))));
+}
@@ -0,0 +1,50 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test solving a problem again when a few ballots change, starting from the previous solution.
+
+use raire::assertions::Assertion;
+use raire::audit_type::Audit;
+use raire::incremental::BallotDelta;
+use raire::irv::{BallotPaperCount, Vote};
+use raire::raire_algorithm::TrimAlgorithm;
+use raire::{RaireError, RaireProblem};
+
+mod common;
+use common::{A, B, C, AUDIT, guide_problem};
+
+#[test]
+/// Test that a small change to the example's ballots keeps the assertions with new margins, a change of winner recomputes them, and removing absent ballots is an error.
+fn test_solve_incrementally() {
+    let mut problem = RaireProblem{ trim_algorithm: Some(TrimAlgorithm::MinimizeTree), ..guide_problem(Audit::OneOnMargin(AUDIT)) };
+    let previous = problem.clone().solve();
+    let change = |n:usize|BallotDelta{ added: vec![Vote{ n: BallotPaperCount(n), prefs: vec![A] }], removed: vec![Vote{ n: BallotPaperCount(n), prefs: vec![C,B,A] }] };
+    // a small change keeps the same assertions, with updated margins.
+    let small = problem.solve_incrementally(&previous,&change(100));
+    assert!(!small.recomputed);
+    assert!(small.flipped_assertions.is_empty());
+    let small_result = small.solution.solution.as_ref().unwrap();
+    let previous_result = previous.solution.as_ref().unwrap();
+    assert_eq!(previous_result.assertions.len(),small_result.assertions.len());
+    let c_beats_a = small_result.assertions.iter().position(|a|matches!(&a.assertion,Assertion::NEN(nen) if nen.winner==C && nen.loser==A && nen.continuing.len()==2)).unwrap();
+    assert_eq!(previous_result.assertions[c_beats_a].margin-BallotPaperCount(200),small_result.assertions[c_beats_a].margin);
+    assert_eq!(previous_result.assertions[c_beats_a].assertion,small_result.assertions[c_beats_a].assertion);
+    assert_eq!(BallotPaperCount(5000-100),problem.votes.iter().find(|v|v.prefs==vec![C,B,A]).unwrap().n);
+    // a bigger change makes Alice the winner, so a full recomputation is needed.
+    problem.winner = None;
+    let big = problem.solve_incrementally(&small.solution,&change(300));
+    assert!(big.recomputed);
+    assert!(big.flipped_assertions.contains(&c_beats_a));
+    assert_eq!(A,big.solution.solution.unwrap().winner);
+    // can't remove ballots that aren't there.
+    let votes_before = problem.votes.clone();
+    let bad = problem.solve_incrementally(&previous,&BallotDelta{ added: vec![], removed: vec![Vote{ n: BallotPaperCount(1), prefs: vec![B] }] });
+    assert!(matches!(bad.solution.solution,Err(RaireError::RemovedBallotsNotPresent(_))));
+    assert_eq!(serde_json::to_value(&votes_before).unwrap(),serde_json::to_value(&problem.votes).unwrap());
+}
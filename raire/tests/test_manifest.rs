@@ -0,0 +1,43 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test deterministic solving, and the manifest recording how a solution can be reproduced.
+
+use raire::RaireProblem;
+
+#[test]
+/// Test that deterministic solving gives the same output however the input is formatted, with a manifest matching only that output.
+fn test_deterministic_manifest() {
+    assert_eq!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",raire::manifest::sha256_hex(b""));
+    assert_eq!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",raire::manifest::sha256_hex(b"abc"));
+    assert_eq!("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",raire::manifest::sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"));
+    let compact = r#"{"metadata":{"contest":"Guide","candidates":["Alice","Bob","Chuan","Diego"]},"num_candidates":4,"votes":[{"n":5000,"prefs":[2,1,0]},{"n":1000,"prefs":[1,2,3]},{"n":1500,"prefs":[3,0]},{"n":4000,"prefs":[0,3]},{"n":2000,"prefs":[3]}],"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13500},"deterministic":true}"#;
+    let reformatted = r#"{ "deterministic" : true, "audit" : {"total_auditable_ballots":13500,"type":"OneOnMargin"}, "winner" : 2,
+        "num_candidates" : 4, "metadata" : {"candidates":["Alice","Bob","Chuan","Diego"],"contest":"Guide"},
+        "votes" : [{"prefs":[2,1,0],"n":5000},{"n":1000,"prefs":[1,2,3]},{"n":1500,"prefs":[3,0]},{"n":4000,"prefs":[0,3]},{"n":2000,"prefs":[3]}] }"#;
+    let problem : RaireProblem = serde_json::from_str(compact).unwrap();
+    let first = problem.clone().solve();
+    let second = serde_json::from_str::<RaireProblem>(reformatted).unwrap().solve();
+    assert_eq!(serde_json::to_string(&first).unwrap(),serde_json::to_string(&second).unwrap());
+    let manifest = first.manifest.as_ref().unwrap();
+    assert!(manifest.matches(&problem,&first.solution));
+    assert_eq!(Some(true),manifest.options["deterministic"].as_bool());
+    let result = first.solution.as_ref().unwrap();
+    assert_eq!(27.0,result.difficulty);
+    assert_eq!(0.0,result.time_to_find_assertions.seconds);
+    assert!(result.time_to_find_assertions.work>0);
+    // not requested, no manifest.
+    let mut not_deterministic = problem.clone();
+    not_deterministic.deterministic=None;
+    assert!(not_deterministic.solve().manifest.is_none());
+    // a different answer does not match.
+    let mut altered = first.solution.clone();
+    altered.as_mut().unwrap().difficulty=28.0;
+    assert!(!manifest.matches(&problem,&altered));
+}
@@ -0,0 +1,39 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the bounds on the margin of victory reported with a solution.
+
+use raire::audit_type::BallotComparisonOneOnDilutedMargin;
+use raire::irv::{BallotPaperCount, Vote, Votes};
+use raire::progress::NoProgressReporter;
+use raire::raire_algorithm::{raire, TrimAlgorithm};
+use raire::timeout::TimeOut;
+use raire::margin::{lower_bound_from_assertions, upper_bound_from_count, MarginOfVictory};
+
+mod common;
+use common::{A, B, C, D, get_votes, get_solution_minimize_tree};
+
+#[test]
+/// Test that the margin of victory of the example is known exactly, and that the upper bound uses whichever round is closest to a tie.
+fn test_margin_of_victory() {
+    // Chuan beats Alice by 500 in the last round, so changing 250 ballots from Chuan to Alice makes them tie. The hardest assertion also has margin 500.
+    let solution = get_solution_minimize_tree();
+    let margin = solution.margin_of_victory.unwrap();
+    assert_eq!(MarginOfVictory{ lower_bound: BallotPaperCount(250), upper_bound: Some(BallotPaperCount(250)) },margin);
+    assert!(margin.is_exact());
+    let votes = get_votes();
+    assert_eq!(Some(BallotPaperCount(250)),upper_bound_from_count(&votes,C,&[B,D,A,C]));
+    assert_eq!(BallotPaperCount(0),lower_bound_from_assertions(&[]));
+    // tying for last in the first round can take fewer changes than tying in the last round.
+    let votes = Votes::new(vec![Vote{ n: BallotPaperCount(30), prefs: vec![C] },Vote{ n: BallotPaperCount(25), prefs: vec![B,C] },Vote{ n: BallotPaperCount(40), prefs: vec![A] }],3).unwrap();
+    assert_eq!(Some(BallotPaperCount(5)),upper_bound_from_count(&votes,C,&[B,A,C])); // rather than 8 in the last round.
+    let result = raire(&votes,Some(C),&BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(95) },TrimAlgorithm::MinimizeTree,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
+    let margin = result.margin_of_victory.unwrap();
+    assert!(margin.lower_bound.0>0 && margin.lower_bound<=margin.upper_bound.unwrap(),"{:?}",margin);
+}
@@ -21,6 +21,10 @@
 use std::fs::File;
 use std::path::PathBuf;
 use raire::{RaireProblem, RaireSolution};
+use raire::assertions::{verify_assertions_rule_out_all_other_winners, Assertion};
+use raire::progress::{NoProgressReporter, ProgressEvent};
+use raire::raire_algorithm::{raire_with_options, RaireOptions, RaireResult};
+use raire::timeout::TimeOut;
 
 fn test_folder(folder:&str) {
     for entry in std::fs::read_dir(folder).unwrap() {
@@ -49,4 +53,35 @@ fn test_folder(folder:&str) {
 #[test]
 fn test_nsw_2021() {
     test_folder("../Australian Examples/NSW Local Government/2021/");
-}
\ No newline at end of file
+}
+
+#[test]
+/// Byron has 9 candidates, and the first assertions found are much harder than the easiest.
+fn test_nsw_2021_two_phase() {
+    let folder = PathBuf::from("../Australian Examples/NSW Local Government/2021/");
+    let problem : RaireProblem = serde_json::from_reader(File::open(folder.join("Byron Mayoral.json")).unwrap()).unwrap();
+    let expected_solution : RaireSolution = serde_json::from_reader(File::open(folder.join("Byron Mayoral_out.json")).unwrap()).unwrap();
+    let expected = expected_solution.solution.unwrap();
+    let votes = problem.counted_votes().unwrap();
+    let options = RaireOptions{two_phase:true,deterministic:true,..Default::default()}; // deterministic so the work done is the same each time.
+    let mut improvements : Vec<RaireResult> = vec![];
+    let result = raire_with_options(&votes,problem.winner,&problem.audit,&options,&mut TimeOut::never(),&mut |e|if let ProgressEvent::ImprovedSolution{result} = e { improvements.push(*result); }).unwrap();
+    assert!((expected.difficulty-result.difficulty).abs()<0.001);
+    assert!(!result.refinement_incomplete);
+    assert!(improvements.len()>1);
+    assert!(improvements.windows(2).all(|w|w[1].difficulty<w[0].difficulty));
+    assert!(improvements[0].difficulty>2.0*result.difficulty);
+    for improvement in &improvements {
+        let assertions : Vec<Assertion> = improvement.assertions.iter().map(|a|a.assertion.clone()).collect();
+        assert!(verify_assertions_rule_out_all_other_winners(&assertions,expected.winner,problem.num_candidates as u32).is_ok());
+    }
+    // With only enough work for the quick answer, it is the result.
+    let quick_options = RaireOptions{stop_when_difficulty_at_most:Some(f64::MAX),deterministic:true,..Default::default()};
+    let quick_succeeds = |work:u64|raire_with_options(&votes,problem.winner,&problem.audit,&quick_options,&mut TimeOut::new(Some(work),None),&mut NoProgressReporter).is_ok();
+    let (mut too_little,mut enough) = (0,1);
+    while !quick_succeeds(enough) { too_little=enough; enough*=2; }
+    while enough-too_little>1 { let middle=(too_little+enough)/2; if quick_succeeds(middle) { enough=middle } else { too_little=middle } }
+    let stopped = raire_with_options(&votes,problem.winner,&problem.audit,&options,&mut TimeOut::new(Some(enough),None),&mut NoProgressReporter).unwrap();
+    assert!(stopped.refinement_incomplete);
+    assert_eq!(improvements[0].difficulty,stopped.difficulty);
+}
@@ -14,6 +14,7 @@
 use raire::assertions::{NotEliminatedNext, SpecificLoserAmongstContinuing, NotEliminatedBefore};
 use raire::audit_type::{BallotComparisonMACRO, BallotPollingBRAVO};
 use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
+use raire::progress::NoProgressReporter;
 use raire::raire_algorithm::{raire, TrimAlgorithm};
 use raire::timeout::TimeOut;
 
@@ -273,10 +274,10 @@ fn test_example12_asns() {
     assert_eq!(BRAVO_EG12.total_auditable_ballots,votes.total_votes());
     assert_eq!(MACRO_EG12.total_auditable_ballots,votes.total_votes());
     // ballot polling
-    let assertion1 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(1), continuing: vec![CandidateIndex(0), CandidateIndex(1)] };
-    let assertion2 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(2)] };
+    let assertion1 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(1), continuing: vec![CandidateIndex(0), CandidateIndex(1)].into() };
+    let assertion2 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(2)].into() };
     let assertion3 = NotEliminatedBefore { winner:CandidateIndex(0), loser: CandidateIndex(3) };
-    let assertion4 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(1), CandidateIndex(2)] };
+    let assertion4 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(1), CandidateIndex(2)].into() };
     let asn1 = assertion1.difficulty(&votes, &BRAVO_EG12);
     let asn2 = assertion2.difficulty(&votes, &BRAVO_EG12);
     let asn3 = assertion3.difficulty(&votes, &BRAVO_EG12).0;
@@ -292,12 +293,12 @@ fn test_example12_asns() {
     assert!((asn3p-0.4).abs()<0.1);
     assert!((asn4p-0.1).abs()<0.1);
     // ballot comparison
-    let assertion1 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(1), continuing: vec![CandidateIndex(0), CandidateIndex(1)] };
-    let assertion2 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(1), CandidateIndex(2)] };
-    let assertion3 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(2)] };
+    let assertion1 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(1), continuing: vec![CandidateIndex(0), CandidateIndex(1)].into() };
+    let assertion2 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(1), CandidateIndex(2)].into() };
+    let assertion3 = NotEliminatedNext { winner:CandidateIndex(0), loser: CandidateIndex(2), continuing: vec![CandidateIndex(0), CandidateIndex(2)].into() };
     let assertion4 = NotEliminatedBefore { winner:CandidateIndex(0), loser: CandidateIndex(3) };
-    let assertion5a = NotEliminatedNext { winner:CandidateIndex(1), loser: CandidateIndex(3), continuing: vec![CandidateIndex(1), CandidateIndex(3)] };
-    let assertion5b = NotEliminatedNext { winner:CandidateIndex(2), loser: CandidateIndex(3), continuing: vec![CandidateIndex(2), CandidateIndex(3)] };
+    let assertion5a = NotEliminatedNext { winner:CandidateIndex(1), loser: CandidateIndex(3), continuing: vec![CandidateIndex(1), CandidateIndex(3)].into() };
+    let assertion5b = NotEliminatedNext { winner:CandidateIndex(2), loser: CandidateIndex(3), continuing: vec![CandidateIndex(2), CandidateIndex(3)].into() };
     let asn1 = assertion1.difficulty(&votes, &MACRO_EG12);
     let asn2 = assertion2.difficulty(&votes, &MACRO_EG12);
     let asn3 = assertion3.difficulty(&votes, &MACRO_EG12);
@@ -325,7 +326,7 @@ fn test_example12_asns() {
 fn test_example12_raire_bravo() {
     let votes = get_votes_for_example12();
     assert_eq!(BRAVO_EG12.total_auditable_ballots, votes.total_votes());
-    let res = raire(&votes,Some(CandidateIndex(0)),&BRAVO_EG12,TrimAlgorithm::None,&mut TimeOut::never()).unwrap();
+    let res = raire(&votes,Some(CandidateIndex(0)),&BRAVO_EG12,TrimAlgorithm::None,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
     println!("{:?}",res);
     assert!((res.difficulty -278.25).abs()<0.01);
     let elimination_orders = res.possible_elimination_orders_allowed_by_assertions(votes.num_candidates());
@@ -349,7 +350,7 @@ fn test_example12_raire_bravo() {
 fn test_example12_raire_macro() {
     let votes = get_votes_for_example12();
     assert_eq!(MACRO_EG12.total_auditable_ballots, votes.total_votes());
-    let res = raire(&votes,Some(CandidateIndex(0)),&MACRO_EG12,TrimAlgorithm::None,&mut TimeOut::never()).unwrap();
+    let res = raire(&votes,Some(CandidateIndex(0)),&MACRO_EG12,TrimAlgorithm::None,&mut TimeOut::never(),&mut NoProgressReporter).unwrap();
     println!("{:?}",res);
     assert!((res.difficulty -44.49).abs()<0.01);
     let elimination_orders = res.possible_elimination_orders_allowed_by_assertions(votes.num_candidates());
@@ -0,0 +1,59 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test merging or dropping write-in candidates and removing withdrawn candidates before solving.
+
+use serde_json::json;
+use raire::irv::{BallotPaperCount, CandidateIndex, TieResolution, Vote};
+use raire::{RaireError, RaireProblem};
+use raire::candidates::CandidateList;
+use raire::preprocess::{is_write_in_name, Preprocessing, WriteInRule, MAPPING_METADATA_KEY};
+
+mod common;
+use common::{A, B, C, get_votes};
+
+#[test]
+/// Test detecting write-ins by name, merging or dropping them and withdrawing Bob from the example with write-ins added, with the mapping kept in the metadata.
+fn test_preprocess_write_ins_and_withdrawn() {
+    assert!(is_write_in_name("Write-in"));
+    assert!(is_write_in_name("WRITE IN 3"));
+    assert!(is_write_in_name("UWI"));
+    assert!(!is_write_in_name("Writer Smith"));
+    let (w1,w2) = (CandidateIndex(4),CandidateIndex(5));
+    let mut votes = get_votes().votes;
+    votes.push(Vote{ n: BallotPaperCount(10), prefs: vec![w1,w2,A] });
+    votes.push(Vote{ n: BallotPaperCount(5), prefs: vec![w2,B] });
+    let problem : RaireProblem = serde_json::from_value(json!({"metadata":{"candidates":["Alice","Bob","Chuan","Diego","Write-in 1","WRITE-IN 2"]},"num_candidates":6,
+        "votes":votes,"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13515},"tie_resolution":{"Order":[5,2,1]}})).unwrap();
+    let write_ins = Preprocessing::detect_write_ins(&CandidateList::from_metadata(&problem.metadata).unwrap());
+    assert_eq!(vec![w1,w2],write_ins);
+    // merge the write-ins and withdraw Bob.
+    let merged = Preprocessing{ write_ins: write_ins.clone(), write_in_rule: WriteInRule::Merge, withdrawn: vec![B] }.apply(&problem).unwrap();
+    assert_eq!(4,merged.num_candidates);
+    assert_eq!(json!(["Alice","Chuan","Diego","Write-ins"]),merged.metadata["candidates"]);
+    assert_eq!(json!([{"name":"Alice","index":0},{"name":"Bob","index":null},{"name":"Chuan","index":1},{"name":"Diego","index":2},{"name":"Write-in 1","index":3},{"name":"WRITE-IN 2","index":3}]),merged.metadata[MAPPING_METADATA_KEY]);
+    assert_eq!(Some(CandidateIndex(1)),merged.winner);
+    let prefs = |problem:&RaireProblem,n:usize|problem.votes.iter().find(|v|v.n==BallotPaperCount(n)).unwrap().prefs.clone();
+    assert_eq!(vec![CandidateIndex(3),A],prefs(&merged,10));
+    assert_eq!(vec![CandidateIndex(3)],prefs(&merged,5));
+    assert_eq!(vec![CandidateIndex(1),CandidateIndex(2)],prefs(&merged,1000)); // Bob, Chuan, Diego without Bob.
+    assert!(matches!(&merged.tie_resolution,Some(TieResolution::Order(order)) if *order==vec![CandidateIndex(3),CandidateIndex(1)]));
+    // the mapping table is copied into the solution.
+    let solution = merged.clone().solve();
+    assert_eq!(CandidateIndex(1),solution.solution.as_ref().unwrap().winner);
+    assert_eq!(merged.metadata[MAPPING_METADATA_KEY],solution.metadata[MAPPING_METADATA_KEY]);
+    // dropping the write-ins.
+    let dropped = Preprocessing{ write_ins, write_in_rule: WriteInRule::Drop, withdrawn: vec![] }.apply(&problem).unwrap();
+    assert_eq!(4,dropped.num_candidates);
+    assert_eq!(vec![A],prefs(&dropped,10));
+    assert_eq!(C,dropped.solve().solution.unwrap().winner);
+    // can't withdraw the winner.
+    assert!(matches!(Preprocessing{ withdrawn: vec![C], ..Default::default() }.apply(&problem),Err(RaireError::WinnerRemoved(c)) if c==C));
+    assert!(matches!(Preprocessing{ withdrawn: vec![CandidateIndex(6)], ..Default::default() }.apply(&problem),Err(RaireError::InvalidCandidateNumberIn{ .. })));
+}
@@ -0,0 +1,41 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the self contained HTML report of a solution.
+
+use serde_json::json;
+use raire::irv::CandidateIndex;
+use raire::report::html::solution_to_html;
+use raire::schema::FORMAT_VERSION;
+use raire::timeout::TimeOut;
+use raire::{RaireError, RaireSolution};
+
+mod common;
+use common::get_solution_minimize_tree;
+
+#[test]
+/// Test that the HTML report of the example escapes names, lists the assertions hardest first, and shows errors.
+fn test_html_report() {
+    let solution = RaireSolution{ metadata: json!({"candidates":["Alice","Bob","Chuan","<Diego>"],"contest":"Guide & example"}), solution: Ok(get_solution_minimize_tree()), manifest: None, sample_sizes: None, tied_rounds: None, format_version: FORMAT_VERSION };
+    let html = solution_to_html(&solution,&mut TimeOut::never()).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>RAIRE assertions for Guide &amp; example</title>"));
+    assert!(html.contains("&lt;Diego&gt;"));
+    assert!(!html.contains("<Diego>"));
+    assert!(html.contains("<tr><th>Winner</th><td>Chuan</td></tr>"));
+    // assertions are sorted by difficulty, hardest first.
+    let table = &html[html.find("<h2>Assertions</h2>").unwrap()..];
+    let first_row = &table[table.find("<tr><td").unwrap()..];
+    let first_row = &first_row[..first_row.find("</tr>").unwrap()];
+    assert!(first_row.contains(&format!("<td class=\"number\">{}</td>",solution.solution.as_ref().unwrap().difficulty)));
+    assert_eq!(3,html.matches("is ruled out by the assertions</summary>").count());
+    assert!(html.contains("<h2>Trimming</h2>"));
+    let failed = RaireSolution{ metadata: json!({}), solution: Err(RaireError::TiedWinners(vec![CandidateIndex(0),CandidateIndex(1)])), manifest: None, sample_sizes: None, tied_rounds: None, format_version: FORMAT_VERSION };
+    assert!(solution_to_html(&failed,&mut TimeOut::never()).unwrap().contains("class=\"error\""));
+}
@@ -0,0 +1,51 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test recomputing the margins and difficulties of a solution's assertions for a different audit.
+
+use serde_json::json;
+use raire::assertions::Assertion;
+use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin, BallotPollingBRAVO};
+use raire::irv::BallotPaperCount;
+use raire::schema::FORMAT_VERSION;
+use raire::{RaireError, RaireProblem, RaireSolution};
+
+mod common;
+use common::{A, B, get_votes, AUDIT};
+
+#[test]
+/// Test that rescoring the example for the same audit changes nothing, for twice the ballots doubles the difficulty, and for BRAVO keeps the assertions and margins.
+fn test_rescore_solution() {
+    let votes = get_votes();
+    let problem : RaireProblem = serde_json::from_value(json!({"metadata":{},"num_candidates":4,"votes":serde_json::to_value(&votes.votes).unwrap(),"winner":2,"audit":{"type":"OneOnMargin","total_auditable_ballots":13500},"include_tallies":true})).unwrap();
+    let solution = problem.solve();
+    // the same audit gives the same result.
+    let same = solution.rescore(&votes,&Audit::OneOnMargin(AUDIT)).solution.unwrap();
+    let result = solution.solution.as_ref().unwrap();
+    assert_eq!(result.difficulty,same.difficulty);
+    assert_eq!(result.assertions,same.assertions);
+    // twice the auditable ballots is twice the difficulty with 1/margin.
+    let doubled = solution.rescore(&votes,&Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(27000) })).solution.unwrap();
+    assert_eq!(2.0*result.difficulty,doubled.difficulty);
+    assert_eq!(Some(BallotPaperCount(27000)),doubled.assertions[0].tallies.as_ref().unwrap().total_auditable_ballots);
+    // a ballot polling audit of the same assertions.
+    let bravo = BallotPollingBRAVO{ confidence: 0.05, total_auditable_ballots: BallotPaperCount(13500) };
+    let polling = solution.rescore(&votes,&Audit::BRAVO(bravo)).solution.unwrap();
+    assert_eq!(result.assertions.len(),polling.assertions.len());
+    for (a,original) in polling.assertions.iter().zip(&result.assertions) {
+        assert_eq!(original.assertion,a.assertion);
+        assert_eq!(original.margin,a.margin);
+        let expected = match &a.assertion { Assertion::NEB(neb) => neb.difficulty(&votes,&bravo).0, Assertion::NEN(nen) => nen.difficulty(&votes,&bravo) };
+        assert_eq!(expected,a.difficulty);
+    }
+    assert_eq!(polling.assertions.iter().map(|a|a.difficulty).fold(0.0,f64::max),polling.difficulty);
+    // errors are kept.
+    let failed = RaireSolution{ metadata: json!({}), solution: Err(RaireError::TiedWinners(vec![A,B])), manifest: None, sample_sizes: None, tied_rounds: None, format_version: FORMAT_VERSION };
+    assert!(matches!(failed.rescore(&votes,&Audit::OneOnMargin(AUDIT)).solution,Err(RaireError::TiedWinners(_))));
+}
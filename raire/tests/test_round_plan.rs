@@ -0,0 +1,44 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test planning the rounds of an audit.
+
+use raire::audit_type::BallotComparisonALPHA;
+use raire::round_plan::{plan_rounds, RoundPlanParameters};
+use raire::simulate::{ErrorRates, SimulationParameters};
+
+mod common;
+use common::{AUDIT, get_solution_minimize_tree};
+
+#[test]
+/// Test that the rounds planned for the example grow by the escalation factor until a full hand count.
+fn test_round_plan() {
+    let solution = get_solution_minimize_tree();
+    let test = BallotComparisonALPHA{ confidence: 0.05, total_auditable_ballots: AUDIT.total_auditable_ballots, d: 100.0, eta0: None };
+    let simulation = SimulationParameters{ test, error_rates: ErrorRates{ one_vote_overstatement: 0.01, ..Default::default() }, trials: 100, seed: 1 };
+    let plan = plan_rounds(&solution,&RoundPlanParameters{ simulation, first_round_completion_probability: 0.9, escalation_factor: 2.0 });
+    assert_eq!(solution.assertions.len(),plan.assertions.len());
+    assert!(plan.rounds[0].completion_probability>=0.9);
+    // the first round must be big enough for the hardest assertion on its own.
+    assert!(plan.assertions.iter().all(|a|a.first_round_sample_size<=plan.rounds[0].sample_size));
+    for (i,round) in plan.rounds.iter().enumerate() {
+        assert_eq!(i+1,round.round_num);
+        if i>0 {
+            let previous = &plan.rounds[i-1];
+            assert_eq!(round.cumulative_sample_size,previous.cumulative_sample_size+round.sample_size);
+            assert_eq!(round.cumulative_sample_size,(previous.cumulative_sample_size*2).min(13500));
+            assert!(round.completion_probability>=previous.completion_probability);
+        }
+    }
+    let last = plan.rounds.last().unwrap();
+    assert!(last.full_hand_count);
+    assert_eq!(13500,last.cumulative_sample_size);
+    let json = serde_json::to_string(&plan).unwrap();
+    assert!(json.contains("\"round_num\":1"));
+}
@@ -0,0 +1,47 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test ranking assertions by how few ballot changes would make them false.
+
+use raire::assertions::{Assertion, NotEliminatedBefore};
+use raire::irv::{BallotPaperCount, Vote, Votes};
+use raire::sensitivity::{sensitivity, BallotChange, ChangesNeeded};
+
+mod common;
+use common::{A, B, C, get_votes, get_solution_minimize_tree};
+
+#[test]
+/// Test the changes needed to break single assertions, and that the example's assertions are ranked most fragile first.
+fn test_sensitivity() {
+    let votes = get_votes();
+    let c_neb_b = Assertion::NEB(NotEliminatedBefore{ winner: C, loser: B });
+    let b_neb_c = Assertion::NEB(NotEliminatedBefore{ winner: B, loser: C }); // false.
+    let analysis = sensitivity(&[c_neb_b.clone(),b_neb_c],&votes,&BallotChange::ALL);
+    assert_eq!(1,analysis[0].index); // already broken, so most fragile.
+    assert_eq!(BallotPaperCount(0),analysis[0].margin);
+    assert_eq!(Some(BallotPaperCount(0)),analysis[0].fewest_changes);
+    let c_neb_b = &analysis[1];
+    assert_eq!((BallotPaperCount(4000),BallotPaperCount(5000),BallotPaperCount(1000),BallotPaperCount(7500)),(c_neb_b.margin,c_neb_b.ballots_for_winner,c_neb_b.ballots_for_loser,c_neb_b.ballots_for_neither));
+    assert_eq!(vec![
+        ChangesNeeded{ change: BallotChange::WinnerToLoser, ballots: Some(BallotPaperCount(2000)) },
+        ChangesNeeded{ change: BallotChange::WinnerToNeither, ballots: Some(BallotPaperCount(4000)) },
+        ChangesNeeded{ change: BallotChange::NeitherToLoser, ballots: Some(BallotPaperCount(4000)) },
+    ],c_neb_b.changes_needed);
+    // not enough ballots for neither to get there.
+    let few_votes = Votes::new(vec![Vote{ n: BallotPaperCount(10), prefs: vec![C] },Vote{ n: BallotPaperCount(2), prefs: vec![B] },Vote{ n: BallotPaperCount(3), prefs: vec![A] }],3).unwrap();
+    let only_neither = sensitivity(&[Assertion::NEB(NotEliminatedBefore{ winner: C, loser: B })],&few_votes,&[BallotChange::NeitherToLoser]);
+    assert_eq!(None,only_neither[0].fewest_changes);
+    // the solution's assertions, ranked; the hardest to audit has the smallest margin so is the most fragile.
+    let solution = get_solution_minimize_tree();
+    let assertions : Vec<Assertion> = solution.assertions.iter().map(|a|a.assertion.clone()).collect();
+    let analysis = sensitivity(&assertions,&votes,&[BallotChange::WinnerToLoser]);
+    assert_eq!(Some(BallotPaperCount(250)),analysis[0].fewest_changes);
+    assert!(analysis.windows(2).all(|w|w[0].fewest_changes<=w[1].fewest_changes));
+    for a in &analysis { assert_eq!(votes.total_votes(),BallotPaperCount(a.ballots_for_winner.0+a.ballots_for_loser.0+a.ballots_for_neither.0)); }
+}
@@ -0,0 +1,37 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test drawing elimination order trees as SVG.
+
+use raire::assertions::Assertion;
+use raire::svg::trees_to_svg;
+use raire::timeout::TimeOut;
+
+mod common;
+use common::get_solution_minimize_tree;
+
+#[test]
+/// Test that the SVG of the example's trees has a box for each node and a line for each edge, with escaped names and the pruning assertions as tooltips.
+fn test_svg_trees() {
+    let solution = get_solution_minimize_tree();
+    let trees = solution.elimination_trees(&mut TimeOut::never()).unwrap();
+    let all_assertions : Vec<Assertion> = solution.assertions.iter().map(|a|a.assertion.clone()).collect();
+    let names : Vec<String> = vec!["Alice".into(),"Bob".into(),"Chuan".into(),"Diego & co".into()];
+    let svg = trees_to_svg(&trees,&all_assertions,&names);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(trees.iter().map(|t|t.count_nodes()).sum::<usize>(),svg.matches("<rect").count());
+    assert_eq!(trees.iter().map(|t|t.count_nodes()-1).sum::<usize>(),svg.matches("<line").count());
+    assert!(svg.contains("Diego &amp; co"));
+    assert_eq!(0,svg.matches("class=\"valid\"").count());
+    assert!(svg.contains("<title>#1 Chuan NEB Bob</title>"));
+    assert!(svg.contains("Bob is ruled out"));
+    let one = trees[0].to_svg(&all_assertions,&names);
+    assert_eq!(trees[0].count_nodes(),one.matches("<rect").count());
+}
@@ -0,0 +1,52 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test the statistics on the elimination order trees, and which assertions prune which parts of them.
+
+use raire::raire_algorithm::RaireResult;
+use raire::timeout::TimeOut;
+use raire::tree_showing_what_assertions_pruned_leaves::TreeStatistics;
+
+mod common;
+use common::{A, B, C, D, get_solution_minimize_tree};
+
+#[test]
+/// Test the node counts of the example's trees and how many nodes each assertion prunes, and that removing an assertion leaves unpruned leaves.
+fn test_tree_statistics() {
+    let solution = get_solution_minimize_tree();
+    let trees = solution.elimination_trees(&mut TimeOut::never()).unwrap();
+    let statistics = solution.tree_statistics(&mut TimeOut::never()).unwrap();
+    assert_eq!(trees.iter().map(|t|t.count_nodes()).sum::<usize>(),statistics.nodes);
+    // Chuan NEB Bob prunes 4 nodes, and each NEN one. Trimming leaves no redundant assertions.
+    assert_eq!(TreeStatistics{ nodes: 14, frontier: 9, unpruned_leaves: 0, max_depth: 4, pruned_by_assertion: vec![4,1,1,1,1,1], only_pruned_by_assertion: vec![4,1,1,1,1,1] },statistics);
+    // without the first assertion, some elimination orders are not ruled out.
+    let fewer = TreeStatistics::new(&RaireResult{ assertions: solution.assertions[1..].to_vec(), ..solution.clone() }.elimination_trees(&mut TimeOut::never()).unwrap(),solution.assertions.len()-1);
+    assert!(fewer.unpruned_leaves>0);
+}
+
+#[test]
+/// Test that the coverage matrix of the example agrees with the tree statistics, groups the frontier by alternate winner, and finds a repeated assertion redundant.
+fn test_coverage_matrix() {
+    let solution = get_solution_minimize_tree();
+    let coverage = solution.coverage_matrix(&mut TimeOut::never()).unwrap();
+    let statistics = solution.tree_statistics(&mut TimeOut::never()).unwrap();
+    assert_eq!(statistics.frontier,coverage.rows.len());
+    for assertion in 0..coverage.num_assertions {
+        assert_eq!(statistics.pruned_by_assertion[assertion],(0..coverage.rows.len()).filter(|&row|coverage.prunes(row,assertion)).count());
+    }
+    assert_eq!(vec![A,B,D],coverage.alternate_winners.iter().map(|w|w.candidate).collect::<Vec<_>>());
+    assert_eq!(statistics.frontier,coverage.alternate_winners.iter().map(|w|w.frontier).sum::<usize>());
+    for row in &coverage.rows { assert_ne!(C,row.alternate_winner()); }
+    assert_eq!(vec![false;6],coverage.redundant());
+    // A repeated assertion covers exactly the same suffixes as the original, so neither is needed given the other.
+    let mut assertions = solution.assertions.clone();
+    assertions.push(assertions[0].clone());
+    let repeated = RaireResult{ assertions, ..solution.clone() }.coverage_matrix(&mut TimeOut::never()).unwrap();
+    assert_eq!(vec![true,false,false,false,false,false,true],repeated.redundant());
+}
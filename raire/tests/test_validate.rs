@@ -0,0 +1,48 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test that candidate numbers out of range are rejected wherever they appear in problems, solutions and assertions.
+
+use serde_json::json;
+use raire::assertions::{Assertion, NotEliminatedBefore};
+use raire::audit_type::Audit;
+use raire::irv::{CandidateIndex, TieResolution};
+use raire::timeout::TimeOut;
+use raire::{RaireError, RaireSolution};
+
+mod common;
+use common::{A, C, AUDIT, guide_problem};
+
+#[test]
+/// Test that a candidate number out of range in the winner, tie resolution order, a solution file or an assertion is an error saying where it is.
+fn test_validate_candidate_numbers() {
+    let problem = || guide_problem(Audit::OneOnMargin(AUDIT));
+    assert!(problem().validate().is_ok());
+    let mut bad = problem();
+    bad.winner = Some(CandidateIndex(4));
+    assert!(matches!(bad.validate(),Err(RaireError::InvalidCandidateNumberIn{ candidate: CandidateIndex(4), num_candidates: 4, .. })));
+    let mut bad = problem();
+    bad.tie_resolution = Some(TieResolution::Order(vec![A,CandidateIndex(7)]));
+    match bad.clone().solve().solution {
+        Err(e@RaireError::InvalidCandidateNumberIn{ candidate: CandidateIndex(7), .. }) => assert_eq!("candidate 7 in the tie resolution order should be an integer 0 to num_candidates-1 = 3",e.to_string()),
+        other => panic!("unexpected {:?}",other),
+    }
+    // a solution file with an assertion about a candidate that doesn't exist is rejected when read.
+    let solution = problem().solve();
+    let mut json = serde_json::to_value(&solution).unwrap();
+    assert!(serde_json::from_value::<RaireSolution>(json.clone()).is_ok());
+    json["solution"]["Ok"]["assertions"][0]["assertion"] = json!({"type":"NEB","winner":2,"loser":7});
+    let error = serde_json::from_value::<RaireSolution>(json).unwrap_err().to_string();
+    assert!(error.contains("candidate 7 in assertion #2 NEB #7"),"{}",error);
+    // and the tree code checks assertions it is given directly.
+    let mut result = solution.solution.unwrap();
+    result.assertions[0].assertion = Assertion::NEB(NotEliminatedBefore{ winner: C, loser: CandidateIndex(7) });
+    assert!(matches!(result.validate(),Err(RaireError::InvalidCandidateNumberIn{ candidate: CandidateIndex(7), .. })));
+    assert!(matches!(result.elimination_trees(&mut TimeOut::never()),Err(RaireError::InvalidCandidateNumberIn{ candidate: CandidateIndex(7), .. })));
+}
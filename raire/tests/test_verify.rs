@@ -0,0 +1,76 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test checking assertions independently of the code that generates them.
+
+use raire::assertions::{all_elimination_orders, verify_assertions_rule_out_all_other_winners, Assertion, EffectOfAssertionOnEliminationOrderSuffix, NotEliminatedBefore};
+use raire::irv::{BallotPaperCount, CandidateIndex};
+use raire::timeout::TimeOut;
+use raire::verify::{check_assertion, verify_assertions, ClaimedAssertion};
+
+mod common;
+use common::{get_votes, get_solution_minimize_tree};
+
+#[test]
+/// Test that checking each assertion against whole elimination orders agrees with the search, and finds an order for an alternate winner when assertions are missing.
+fn test_verify_assertions_independently() {
+    let solution = get_solution_minimize_tree();
+    let assertions : Vec<Assertion> = solution.assertions.iter().map(|a|a.assertion.clone()).collect();
+    for order in all_elimination_orders(4) { // agrees with the checks used when generating assertions.
+        for a in &assertions {
+            assert_eq!(a.ok_elimination_order_suffix(&order)==EffectOfAssertionOnEliminationOrderSuffix::Ok,a.is_consistent_with_full_order(&order));
+        }
+    }
+    assert_eq!(Ok(()),verify_assertions_rule_out_all_other_winners(&assertions,CandidateIndex(2),4));
+    let order = verify_assertions_rule_out_all_other_winners(&assertions[1..],CandidateIndex(2),4).unwrap_err();
+    assert_ne!(CandidateIndex(2),*order.last().unwrap());
+    assert!(assertions[1..].iter().all(|a|a.is_consistent_with_full_order(&order)));
+    assert!(verify_assertions_rule_out_all_other_winners(&assertions,CandidateIndex(0),4).is_err());
+    // too many candidates to check exhaustively.
+    let num_candidates = 10;
+    let winner = CandidateIndex(3);
+    let nebs : Vec<Assertion> = (0..num_candidates).map(CandidateIndex).filter(|&c|c!=winner).map(|loser|Assertion::NEB(NotEliminatedBefore{ winner, loser })).collect();
+    assert_eq!(Ok(()),verify_assertions_rule_out_all_other_winners(&nebs,winner,num_candidates));
+    let order = verify_assertions_rule_out_all_other_winners(&nebs[1..],winner,num_candidates).unwrap_err();
+    assert_eq!(num_candidates as usize,order.len());
+    assert_eq!(CandidateIndex(0),*order.last().unwrap());
+    assert!(nebs[1..].iter().all(|a|a.is_consistent_with_full_order(&order)));
+}
+
+#[test]
+/// Test the report on claimed assertions and margins: correct ones are verified, and wrong margins, false assertions and missing assertions are found.
+fn test_verification_report() {
+    let votes = get_votes();
+    let solution = get_solution_minimize_tree();
+    let mut claimed : Vec<ClaimedAssertion> = solution.assertions.iter().map(|a|ClaimedAssertion{ assertion: a.assertion.clone(), margin: Some(a.margin) }).collect();
+    let report = verify_assertions(&votes,CandidateIndex(2),&claimed,&mut TimeOut::never()).unwrap();
+    assert!(report.verified);
+    assert_eq!(vec![CandidateIndex(2)],report.computed_winners);
+    claimed[0].margin = Some(claimed[0].margin.unwrap()+BallotPaperCount(1));
+    let report = verify_assertions(&votes,CandidateIndex(2),&claimed,&mut TimeOut::never()).unwrap();
+    assert!(!report.margins_correct);
+    assert!(report.rules_out_other_winners);
+    assert!(!report.verified);
+    // a false assertion, with no claimed margin.
+    let claimed = vec![ClaimedAssertion{ assertion: Assertion::NEB(NotEliminatedBefore{ winner: CandidateIndex(1), loser: CandidateIndex(2) }), margin: None }];
+    let report = verify_assertions(&votes,CandidateIndex(1),&claimed,&mut TimeOut::never()).unwrap();
+    assert!(report.margins_correct);
+    assert!(!report.assertions_hold);
+    assert!(report.assertions[0].computed_margin<0);
+    assert!(!report.rules_out_other_winners);
+    assert!(report.counterexample.is_some());
+    // the JSON of RAIRE assertions can be read as claimed assertions.
+    let claimed : Vec<ClaimedAssertion> = serde_json::from_value(serde_json::to_value(&solution.assertions).unwrap()).unwrap();
+    assert_eq!(Some(solution.assertions[0].margin),claimed[0].margin);
+    // checking one assertion a batch of votes at a time, as a device without std would.
+    let (first,rest) = votes.votes.split_at(2);
+    let batches = [check_assertion(&claimed[0],first),check_assertion(&claimed[0],rest)];
+    assert_eq!(solution.assertions[0].margin.0 as i64,batches.iter().map(|c|c.computed_margin).sum::<i64>());
+    assert!(check_assertion(&claimed[0],&votes.votes).margin_correct);
+}
@@ -0,0 +1,62 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use clap::{Parser};
+use raire::RaireSolution;
+use raire::audit_progress::{AuditProgress, BallotInterpretation};
+use raire::audit_type::BallotComparisonALPHA;
+use raire::irv::BallotPaperCount;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// This reads the output of a RAIRE computation and the interpretations of the ballots sampled so far in a ballot comparison audit,
+/// and reports the current risk measure of each assertion using the ALPHA test.
+struct CliOptions {
+    /// The output from RAIRE
+    input_file : PathBuf,
+    /// A JSON file containing a list of sampled ballots, each an object with fields `cvr` and `paper` giving the preferences on the CVR and on the paper ballot.
+    ballots : PathBuf,
+    /// The total number of auditable ballots
+    #[arg(long)]
+    total_ballots : usize,
+    /// The risk limit
+    #[arg(long,default_value_t=0.05)]
+    risk_limit : f64,
+    /// Optionally write the report as JSON.
+    #[arg(long)]
+    output_json : Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    let input : RaireSolution = serde_json::from_reader(File::open(&args.input_file)?)?;
+    let solution = input.solution.map_err(|e|anyhow::anyhow!("The RAIRE computation failed: {}",e))?;
+    let ballots : Vec<BallotInterpretation> = serde_json::from_reader(File::open(&args.ballots)?)?;
+    let candidate_names : Vec<String> = input.metadata["candidates"].as_array().map(|a|a.iter().map(|v|v.as_str().unwrap_or_default().to_string()).collect()).unwrap_or_default();
+    let test = BallotComparisonALPHA{ confidence: args.risk_limit, total_auditable_ballots: BallotPaperCount(args.total_ballots), d: 100.0, eta0: None };
+    let mut progress = AuditProgress::new(&solution,&test);
+    for ballot in &ballots { progress.observe(ballot); }
+    let report = progress.report();
+    for p in &report.assertions {
+        println!("{}  p-value {:.4}{}  overstatements {}/{} understatements {}/{} (1 vote/2 vote)",solution.assertions[p.assertion].assertion.describe(&candidate_names),p.p_value,if p.confirmed {" confirmed"} else {""},
+                 p.one_vote_overstatements,p.two_vote_overstatements,p.one_vote_understatements,p.two_vote_understatements);
+    }
+    if report.complete { println!("All assertions confirmed after {} ballots; the audit is complete.",report.ballots_sampled); }
+    else { println!("{} of {} assertions not yet confirmed after {} ballots; escalate the audit.",report.needs_escalation.len(),report.assertions.len(),report.ballots_sampled); }
+    if let Some(output) = &args.output_json {
+        serde_json::to_writer_pretty(File::create(output)?,&report)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use clap::{Parser};
+use raire::RaireSolution;
+use raire::coordination::{coordinate_samples, BallotStyle};
+use raire::raire_algorithm::RaireResult;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// This reads the outputs of RAIRE computations for several contests and a list of ballot styles saying which contests are on
+/// which ballots, and estimates the number of distinct ballots to retrieve to audit all the contests together.
+struct CliOptions {
+    /// A JSON list of ballot styles, each like {"name":"Precinct 1","ballots":1200,"contests":["Mayor","Council"]}
+    styles : PathBuf,
+    /// The outputs from RAIRE, one per contest. Each contest is named by its file name without the extension, and `_out` if present.
+    #[arg(required=true)]
+    solutions : Vec<PathBuf>,
+    /// The risk limit. May be given more than once to compare risk limits.
+    #[arg(long,default_values_t=[0.05])]
+    risk_limit : Vec<f64>,
+    /// Optionally write the full report as JSON.
+    #[arg(long)]
+    output_json : Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    let styles : Vec<BallotStyle> = serde_json::from_reader(File::open(&args.styles)?)?;
+    let mut results = vec![];
+    for path in &args.solutions {
+        let stem = path.file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_default();
+        let name = stem.strip_suffix("_out").unwrap_or(&stem).to_string();
+        let input : RaireSolution = serde_json::from_reader(File::open(path)?)?;
+        let result = input.solution.map_err(|e|anyhow::anyhow!("The RAIRE computation for {} failed: {}",name,e))?;
+        results.push((name,result));
+    }
+    let contests : Vec<(String,&RaireResult)> = results.iter().map(|(name,result)|(name.clone(),result)).collect();
+    let report = coordinate_samples(&contests,&styles,&args.risk_limit)?;
+    for total in &report.totals {
+        let largest = total.largest_contest.map(|i|report.contests[i].name.as_str()).unwrap_or("none");
+        println!("Risk limit {} : {} ballots if sampled separately, {:.0} with consistent sampling, at least {} for {}",total.risk_limit,total.independent_ballots,total.consistent_ballots,total.largest_contest_sample,largest);
+    }
+    if let Some(output) = &args.output_json {
+        serde_json::to_writer_pretty(File::create(output)?,&report)?;
+    }
+    Ok(())
+}
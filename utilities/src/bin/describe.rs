@@ -9,47 +9,18 @@
 
 
 
-use std::fs::File;
-use std::path::PathBuf;
-
 use clap::{Parser};
-use raire::irv::{CandidateIndex};
-use raire::{RaireSolution};
-use raire::assertions::Assertion;
+use utilities::commands::explain::ExplainArgs;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
-/// This reads the output of a RAIRE computation and produces a human readable description
+/// This reads the output of a RAIRE computation and produces a human readable description. This is the same as `raire explain`.
 struct CliOptions {
-    /// The output from RAIRE
-    input_file : PathBuf,
+    #[command(flatten)]
+    explain : ExplainArgs,
 }
 
-
 fn main() -> anyhow::Result<()> {
     let args = CliOptions::parse();
-    let input : RaireSolution = serde_json::from_reader(File::open(&args.input_file)?)?;
-    let name = |c:CandidateIndex| {
-        if let Some(metadata_name) = input.metadata["candidates"][c.0 as usize].as_str() {
-            metadata_name.to_string()
-        } else {
-            format!("#{}",c.0)
-        }
-    };
-    match &input.solution {
-        Ok(solution) => {
-            println!("Solution overall difficulty {}",solution.difficulty);
-            for a in &solution.assertions {
-                match &a.assertion {
-                    Assertion::NEB(neb) => print!("{} NEB {}",name(neb.winner),name(neb.loser)),
-                    Assertion::NEN(nen) => print!("{} > {} with {:?} continuing",name(nen.winner),name(nen.loser),nen.continuing.iter().cloned().map(name).collect::<Vec<_>>()),
-                }
-                println!("  Difficulty {}",a.difficulty);
-            }
-        }
-        Err(e) => {
-            println!("Could not find a solution because {:?}",e)
-        }
-    }
-    Ok(())
-}
\ No newline at end of file
+    args.explain.run()
+}
@@ -0,0 +1,46 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use clap::{Parser};
+use raire::RaireProblem;
+use utilities::parse::ParsedContest;
+use utilities::parse::preflib::write_preflib;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// This reads the JSON input for raire-rs and writes the votes as a PrefLib .soi (or .soc) file.
+/// Ballots with no preferences are omitted as PrefLib cannot represent them.
+struct CliOptions {
+    /// The RAIRE JSON problem file to read
+    input_json_file : PathBuf,
+    /// The file to store the output. Default is the input file with `.soi` added.
+    output_file : Option<PathBuf>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    let problem : RaireProblem = serde_json::from_reader(File::open(&args.input_json_file)?)?;
+    let default_name = args.input_json_file.file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_default();
+    let contest = ParsedContest::from_raire_problem(&problem,&default_name)?;
+    let output_file : PathBuf = args.output_file.unwrap_or_else(||{
+        let mut name = args.input_json_file.clone().into_os_string();
+        name.push(".soi");
+        name.into()
+    });
+    let file_name = output_file.file_name().map(|s|s.to_string_lossy().to_string()).unwrap_or_default();
+    write_preflib(&contest,&file_name,BufWriter::new(File::create(&output_file)?))?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+use utilities::cli_args::{AuditArgs, RankingArgs};
+use utilities::parse::csv::{read_csv_ballots, CsvBallotFormat, NameNormalization, WriteInRule};
+
+#[derive(Clone,Copy,Debug,ValueEnum)]
+enum WriteIns {
+    /// Unknown names are an error
+    Error,
+    /// Unknown names are treated as a skipped rank
+    Skip,
+    /// Unknown names are all counted as a single candidate called --write-in-name
+    Combine,
+    /// Each unknown name is a new candidate
+    Add,
+}
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// This reads a CSV file with one ballot per row and one column per rank (first preference first), each cell containing
+/// a candidate name, and converts it to the JSON input for raire-rs
+struct CliOptions {
+    /// The CSV file containing the ballots
+    input_csv_file : PathBuf,
+    /// The file to store the output. Default is the input file name, with path and extension if present removed and `.json` added.
+    output_json_file : Option<PathBuf>,
+    /// The name of the contest (default the input file name)
+    #[arg(long)]
+    name : Option<String>,
+    /// The field delimiter
+    #[arg(long,default_value_t=',')]
+    delimiter : char,
+    /// Set if the first row is a ballot rather than a header
+    #[arg(long)]
+    no_header : bool,
+    /// The columns (counting from 0) containing ranks, first preference first, comma separated. Default all but the ballot id and count columns.
+    #[arg(long,value_delimiter=',')]
+    rank_columns : Option<Vec<usize>>,
+    /// The column (counting from 0) containing a unique ballot id
+    #[arg(long)]
+    ballot_id_column : Option<usize>,
+    /// The column (counting from 0) containing the number of ballots each row stands for, for aggregated data. Default one ballot per row.
+    #[arg(long)]
+    count_column : Option<usize>,
+    /// The candidate names, comma separated, in order. Default is the names found in the file in order of appearance.
+    #[arg(long,value_delimiter=',')]
+    candidates : Option<Vec<String>>,
+    /// What to do with names not in --candidates
+    #[arg(long,value_enum,default_value_t=WriteIns::Error)]
+    write_ins : WriteIns,
+    /// The candidate name used for --write-ins combine
+    #[arg(long,default_value="Write-in")]
+    write_in_name : String,
+    /// A cell value meaning the rank is skipped, other than blank. May be repeated.
+    #[arg(long)]
+    blank_value : Vec<String>,
+    /// A cell value meaning the rank is overvoted. May be repeated.
+    #[arg(long)]
+    overvote_value : Vec<String>,
+    /// Compare names ignoring case and repeated spaces
+    #[arg(long)]
+    ignore_case : bool,
+    #[command(flatten)]
+    ranking : RankingArgs,
+    #[command(flatten)]
+    audit : AuditArgs,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    let format = CsvBallotFormat{
+        delimiter: u8::try_from(args.delimiter)?,
+        has_header: !args.no_header,
+        rank_columns: args.rank_columns,
+        ballot_id_column: args.ballot_id_column,
+        count_column: args.count_column,
+        blank_values: args.blank_value,
+        overvote_values: args.overvote_value,
+        name_normalization: if args.ignore_case { NameNormalization::TrimIgnoreCaseAndSpaces } else { NameNormalization::Trim },
+        candidates: args.candidates,
+        write_ins: match args.write_ins {
+            WriteIns::Error => WriteInRule::Error,
+            WriteIns::Skip => WriteInRule::Skip,
+            WriteIns::Combine => WriteInRule::Combine(args.write_in_name),
+            WriteIns::Add => WriteInRule::AddCandidate,
+        },
+        ranking: args.ranking.rules(),
+    };
+    let stem = args.input_csv_file.file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_else(||"output".to_string());
+    let name = args.name.unwrap_or_else(||stem.clone());
+    let contest = read_csv_ballots(File::open(&args.input_csv_file)?,&name,&format)?;
+    let num_ballots = contest.num_ballots();
+    println!("{num_ballots} ballots of which {} are unique",contest.votes.len());
+    let output = contest.to_raire_problem(args.audit.audit(num_ballots))?;
+    let output_file : PathBuf = args.output_json_file.unwrap_or_else(||PathBuf::from(format!("{stem}.json")));
+    serde_json::to_writer(File::create(&output_file)?,&output)?;
+    Ok(())
+}
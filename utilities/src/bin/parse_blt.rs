@@ -0,0 +1,49 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use clap::{Parser};
+use utilities::cli_args::{AuditArgs, RankingArgs};
+use utilities::parse::blt::read_blt;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// This reads a single seat .blt (OpenSTV or ConcreteSTV) ballot file and converts it to the JSON input for raire-rs.
+/// Tied candidates (written 1=2) are treated as an overvote at that rank.
+struct CliOptions {
+    /// The BLT file to read
+    input_file : PathBuf,
+    /// The file to store the output. Default is the input file with `.json` added.
+    output_json_file : Option<PathBuf>,
+    #[command(flatten)]
+    ranking : RankingArgs,
+    #[command(flatten)]
+    audit : AuditArgs,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    let default_name = args.input_file.file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_default();
+    let contest = read_blt(File::open(&args.input_file)?,&default_name,&args.ranking.rules())?;
+    let num_ballots = contest.num_ballots();
+    println!("{num_ballots} ballots of which {} are unique",contest.votes.len());
+    let output = contest.to_raire_problem(args.audit.audit(num_ballots))?;
+    let output_file : PathBuf = args.output_json_file.unwrap_or_else(||{
+        let mut name = args.input_file.clone().into_os_string();
+        name.push(".json");
+        name.into()
+    });
+    serde_json::to_writer(File::create(&output_file)?,&output)?;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use clap::{Parser};
+use utilities::cli_args::{AuditArgs, RankingArgs};
+use utilities::parse::dominion::{read_dominion_contest, DominionOptions};
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// This reads a Dominion Democracy Suite CVR export folder (containing ContestManifest.json, CandidateManifest.json and CvrExport*.json)
+/// and converts one contest to the JSON input for raire-rs
+struct CliOptions {
+    /// The folder containing the Dominion export
+    input_folder : PathBuf,
+    /// The contest, either the Dominion contest Id or its description
+    contest : String,
+    /// The file to store the output. Default is the contest name with `.json` added.
+    output_json_file : Option<PathBuf>,
+    #[command(flatten)]
+    ranking : RankingArgs,
+    /// Include marks flagged as ambiguous.
+    #[arg(long)]
+    include_ambiguous_marks : bool,
+    #[command(flatten)]
+    audit : AuditArgs,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    let options = DominionOptions{
+        ranking: args.ranking.rules(),
+        include_ambiguous_marks: args.include_ambiguous_marks,
+    };
+    let contest = read_dominion_contest(&args.input_folder,&args.contest,&options)?;
+    let num_ballots = contest.num_ballots();
+    println!("{num_ballots} ballots of which {} are unique",contest.votes.len());
+    let output = contest.to_raire_problem(args.audit.audit(num_ballots))?;
+    let output_file : PathBuf = args.output_json_file.unwrap_or_else(||PathBuf::from(format!("{}.json",contest.name)));
+    serde_json::to_writer(File::create(&output_file)?,&output)?;
+    Ok(())
+}
@@ -14,8 +14,7 @@ use std::path::PathBuf;
 use anyhow::anyhow;
 
 use clap::{Parser};
-use raire::audit_type::{Audit, BallotComparisonMACRO, BallotComparisonOneOnDilutedMargin, BallotPollingBRAVO, BallotPollingOneOnDilutedMarginSquared};
-use raire::irv::BallotPaperCount;
+use utilities::cli_args::AuditArgs;
 use utilities::parse_michelle_format::Contest;
 
 #[derive(Parser)]
@@ -31,18 +30,8 @@ struct CliOptions {
     /// If there are multiple contests in the input file, which one do you want, starting counting at 1 (default 1)
     #[arg(short, long)]
     contest : Option<usize>,
-    /// set if you want ballot polling (default ballot comparison)
-    #[arg(long)]
-    ballot_polling : bool,
-    /// the total number of ballots (if different from the number of votes in the file)
-    #[arg(long)]
-    total_ballots : Option<usize>,
-    /// the desired confidence level (for MACRO or BRAVO). If not specified, then a 1/margin (or 1/margin squared) computation will be done.
-    #[arg(long)]
-    confidence : Option<f64>,
-    /// the error_inflation_factor (for MACRO).
-    #[arg(long)]
-    error_inflation_factor : Option<f64>,
+    #[command(flatten)]
+    audit : AuditArgs,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -54,13 +43,7 @@ fn main() -> anyhow::Result<()> {
         let contest = &input[index-1];
         let num_ballots : usize = contest.votes.values().sum();
         println!("{num_ballots} ballots of which {} are unique",contest.votes.len());
-        let total_auditable_ballots = BallotPaperCount(args.total_ballots.unwrap_or(num_ballots));
-        let audit : Audit = match (args.ballot_polling,args.confidence) {
-            (false,None) => Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots }),
-            (true,None) => Audit::OneOnMarginSq(BallotPollingOneOnDilutedMarginSquared{ total_auditable_ballots }),
-            (false,Some(confidence)) => Audit::MACRO(BallotComparisonMACRO{total_auditable_ballots,confidence,error_inflation_factor:args.error_inflation_factor.unwrap_or(1.0)}),
-            (true,Some(confidence)) => Audit::BRAVO(BallotPollingBRAVO{total_auditable_ballots,confidence}),
-        };
+        let audit = args.audit.audit(num_ballots);
         contest.to_raire_problem(audit)?
     };
     let output_file : PathBuf = args.output_json_file.unwrap_or_else(||{
@@ -0,0 +1,30 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+
+use clap::{Parser};
+use utilities::commands::verify::VerifyArgs;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// This independently checks a set of assertions against the ballots: that each assertion's margin is correct,
+/// and that together they rule out every winner other than the claimed one. It writes a JSON verification report,
+/// and exits with status 1 if the assertions are not verified. This is the same as `raire verify`.
+struct CliOptions {
+    #[command(flatten)]
+    verify : VerifyArgs,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    if !args.verify.run()? { std::process::exit(1); }
+    Ok(())
+}
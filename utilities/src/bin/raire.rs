@@ -7,31 +7,64 @@
 // raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::fs::File;
-use std::path::PathBuf;
 
-use clap::{Parser};
-use raire::RaireProblem;
+use clap::{Parser, Subcommand};
+use utilities::commands::assorters::AssortersArgs;
+use utilities::commands::convert::ConvertArgs;
+use utilities::commands::diff::DiffArgs;
+use utilities::commands::explain::ExplainArgs;
+use utilities::commands::preprocess::PreprocessArgs;
+use utilities::commands::rescore::RescoreArgs;
+use utilities::commands::sample::SampleArgs;
+use utilities::commands::solve::SolveArgs;
+use utilities::commands::verify::VerifyArgs;
 
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
-/// This is a Rust port of RAIRE, originally written by Michelle Blom and ported to Rust by Andrew Conway
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true, arg_required_else_help = true)]
+/// This is a Rust port of RAIRE, originally written by Michelle Blom and ported to Rust by Andrew Conway.
+/// For compatibility with earlier versions, `raire input.json [output.json]` is the same as `raire solve input.json [output.json]`.
 struct CliOptions {
-    /// The JSON file containing the command to RAIRE
-    input_json_file : PathBuf,
-    /// The file to store the output. Default is the input file name, with path and extension if present removed and `_out.json` added.
-    output_json_file : Option<PathBuf>,
+    #[command(subcommand)]
+    command : Option<Command>,
+    #[command(flatten)]
+    solve : Option<SolveArgs>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compute assertions for a RAIRE problem, writing the RAIRE output JSON.
+    Solve(SolveArgs),
+    /// Independently check a set of assertions against the ballots, writing a JSON report and exiting with status 1 if they are not verified.
+    Verify(VerifyArgs),
+    /// Describe the output of RAIRE in human readable form, optionally writing trees of elimination orders ruled out or an HTML report.
+    Explain(ExplainArgs),
+    /// Convert between ballot file formats and the RAIRE JSON input format.
+    Convert(ConvertArgs),
+    /// Merge or drop write-in candidates, and drop withdrawn candidates, in a RAIRE problem.
+    Preprocess(PreprocessArgs),
+    /// Recompute the difficulties of the assertions in RAIRE output for a different audit type or number of ballots, without solving again.
+    Rescore(RescoreArgs),
+    /// Compare the assertions in two RAIRE outputs for the same contest, exiting with status 1 if they differ.
+    Diff(DiffArgs),
+    /// Write the SHANGRLA assorter value of each assertion in RAIRE output on each distinct ballot, for computing discrepancies.
+    Assorters(AssortersArgs),
+    /// Choose the ballots to retrieve for a round of the audit of the assertions in RAIRE output, from a public random seed.
+    Sample(SampleArgs),
 }
 
 fn main() -> anyhow::Result<()> {
     let args = CliOptions::parse();
-    let input : RaireProblem = serde_json::from_reader(File::open(&args.input_json_file)?)?;
-    let output = input.solve();
-    let output_file : PathBuf = args.output_json_file.unwrap_or_else(||{
-        let mut stem = args.input_json_file.file_stem().map(|s|PathBuf::from(s)).unwrap_or_else(||PathBuf::from("output"));
-        stem.as_mut_os_string().push("_out.json");
-        stem
-    });
-    serde_json::to_writer(File::create(&output_file)?,&output)?;
+    match (args.command,args.solve) {
+        (Some(Command::Solve(solve)),_) | (None,Some(solve)) => solve.run()?,
+        (Some(Command::Verify(verify)),_) => if !verify.run()? { std::process::exit(1); },
+        (Some(Command::Explain(explain)),_) => explain.run()?,
+        (Some(Command::Convert(convert)),_) => convert.run()?,
+        (Some(Command::Preprocess(preprocess)),_) => preprocess.run()?,
+        (Some(Command::Rescore(rescore)),_) => rescore.run()?,
+        (Some(Command::Diff(diff)),_) => if !diff.run()? { std::process::exit(1); },
+        (Some(Command::Assorters(assorters)),_) => assorters.run()?,
+        (Some(Command::Sample(sample)),_) => sample.run()?,
+        (None,None) => { <CliOptions as clap::CommandFactory>::command().print_help()?; }
+    }
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,97 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use clap::{Parser};
+use raire::RaireSolution;
+use raire::audit_type::BallotComparisonALPHA;
+use raire::irv::BallotPaperCount;
+use raire::round_plan::{plan_rounds, RoundPlanParameters};
+use raire::simulate::{simulate_audit, ErrorRates, SimulationParameters};
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+/// This reads the output of a RAIRE computation and simulates ballot comparison audits of the assertions using the ALPHA test,
+/// reporting the distribution of the number of ballots that need to be sampled.
+struct CliOptions {
+    /// The output from RAIRE
+    input_file : PathBuf,
+    /// The total number of auditable ballots
+    #[arg(long)]
+    total_ballots : usize,
+    /// The risk limit
+    #[arg(long,default_value_t=0.05)]
+    risk_limit : f64,
+    /// The number of simulated audits
+    #[arg(long,default_value_t=1000)]
+    trials : usize,
+    /// Seed for the random number generator
+    #[arg(long,default_value_t=0)]
+    seed : u64,
+    /// The assumed rate of one vote overstatements per ballot
+    #[arg(long,default_value_t=0.0)]
+    one_vote_overstatement : f64,
+    /// The assumed rate of two vote overstatements per ballot
+    #[arg(long,default_value_t=0.0)]
+    two_vote_overstatement : f64,
+    /// The assumed rate of one vote understatements per ballot
+    #[arg(long,default_value_t=0.0)]
+    one_vote_understatement : f64,
+    /// The assumed rate of two vote understatements per ballot
+    #[arg(long,default_value_t=0.0)]
+    two_vote_understatement : f64,
+    /// Optionally write the full result, including every trial's sample size, as JSON.
+    #[arg(long)]
+    output_json : Option<PathBuf>,
+    /// Optionally write a recommended schedule of audit rounds as JSON.
+    #[arg(long)]
+    round_plan : Option<PathBuf>,
+    /// For the round plan, the desired probability that the audit finishes in the first round
+    #[arg(long,default_value_t=0.9)]
+    first_round_probability : f64,
+    /// For the round plan, the factor by which the cumulative sample size grows each round
+    #[arg(long,default_value_t=2.0)]
+    escalation_factor : f64,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = CliOptions::parse();
+    let input : RaireSolution = serde_json::from_reader(File::open(&args.input_file)?)?;
+    let solution = input.solution.map_err(|e|anyhow::anyhow!("The RAIRE computation failed: {}",e))?;
+    let params = SimulationParameters{
+        test: BallotComparisonALPHA{ confidence: args.risk_limit, total_auditable_ballots: BallotPaperCount(args.total_ballots), d: 100.0, eta0: None },
+        error_rates: ErrorRates{
+            one_vote_overstatement: args.one_vote_overstatement,
+            two_vote_overstatement: args.two_vote_overstatement,
+            one_vote_understatement: args.one_vote_understatement,
+            two_vote_understatement: args.two_vote_understatement,
+        },
+        trials: args.trials,
+        seed: args.seed,
+    };
+    let result = simulate_audit(&solution,&params);
+    println!("Ballots sampled over {} simulated audits: mean {:.1}, median {}, 90th percentile {}, 99th percentile {}",args.trials,result.mean,result.quantile(0.5),result.quantile(0.9),result.quantile(0.99));
+    println!("Fraction escalating to a full hand count: {}",result.full_hand_count_fraction);
+    if let Some(output) = &args.output_json {
+        serde_json::to_writer(File::create(output)?,&result)?;
+    }
+    if let Some(output) = &args.round_plan {
+        let plan = plan_rounds(&solution,&RoundPlanParameters{ simulation: params, first_round_completion_probability: args.first_round_probability, escalation_factor: args.escalation_factor });
+        for round in &plan.rounds {
+            println!("Round {} : draw {} ballots ({} in total){}, finishing with probability {}",round.round_num,round.sample_size,round.cumulative_sample_size,if round.full_hand_count {" - a full hand count"} else {""},round.completion_probability);
+        }
+        serde_json::to_writer_pretty(File::create(output)?,&plan)?;
+    }
+    Ok(())
+}
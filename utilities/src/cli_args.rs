@@ -0,0 +1,78 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Command line options shared by the programs that convert ballot files to RAIRE problems.
+
+use clap::Args;
+use crate::parse::{DuplicateRule, OvervoteRule, BallotInterpretationRules};
+use raire::audit_type::{Audit, BallotComparisonALPHA, BallotComparisonMACRO, BallotComparisonOneOnDilutedMargin, BallotPollingBRAVO, BallotPollingOneOnDilutedMarginSquared};
+use raire::irv::BallotPaperCount;
+
+#[derive(Args,Debug)]
+pub struct AuditArgs {
+    /// set if you want ballot polling (default ballot comparison)
+    #[arg(long)]
+    pub ballot_polling : bool,
+    /// the total number of ballots (if different from the number of votes in the file)
+    #[arg(long)]
+    pub total_ballots : Option<usize>,
+    /// the desired confidence level (for MACRO or BRAVO). If not specified, then a 1/margin (or 1/margin squared) computation will be done.
+    #[arg(long)]
+    pub confidence : Option<f64>,
+    /// the error_inflation_factor (for MACRO).
+    #[arg(long)]
+    pub error_inflation_factor : Option<f64>,
+    /// use an ALPHA supermartingale sample size estimate rather than MACRO for a ballot comparison audit with a confidence level.
+    #[arg(long)]
+    pub alpha : bool,
+    /// the weight d given to the initial estimate of the mean (for ALPHA, default 100).
+    #[arg(long)]
+    pub alpha_d : Option<f64>,
+    /// the initial estimate η₀ of the mean of the overstatement assorter (for ALPHA).
+    #[arg(long)]
+    pub alpha_eta0 : Option<f64>,
+}
+
+impl AuditArgs {
+    /// Get the audit type requested, given the number of ballots in the file (used if total_ballots is not specified).
+    pub fn audit(&self,num_ballots:usize) -> Audit {
+        let total_auditable_ballots = BallotPaperCount(self.total_ballots.unwrap_or(num_ballots));
+        match (self.ballot_polling,self.confidence) {
+            (false,None) => Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots }),
+            (true,None) => Audit::OneOnMarginSq(BallotPollingOneOnDilutedMarginSquared{ total_auditable_ballots }),
+            (false,Some(confidence)) if self.alpha => Audit::ALPHA(BallotComparisonALPHA{total_auditable_ballots,confidence,d:self.alpha_d.unwrap_or(100.0),eta0:self.alpha_eta0}),
+            (false,Some(confidence)) => Audit::MACRO(BallotComparisonMACRO{total_auditable_ballots,confidence,error_inflation_factor:self.error_inflation_factor.unwrap_or(1.0)}),
+            (true,Some(confidence)) => Audit::BRAVO(BallotPollingBRAVO{total_auditable_ballots,confidence}),
+        }
+    }
+}
+
+/// Options describing how ranks on a ballot are turned into a preference list.
+#[derive(Args,Debug)]
+pub struct RankingArgs {
+    /// Treat an overvoted rank as skipped, rather than exhausting the ballot.
+    #[arg(long)]
+    pub skip_overvoted_rank : bool,
+    /// Exhaust a ballot when it ranks a candidate already ranked, rather than ignoring the repeat.
+    #[arg(long)]
+    pub exhaust_on_duplicate : bool,
+    /// Exhaust a ballot once it has more than this many consecutive skipped ranks (default no limit).
+    #[arg(long)]
+    pub max_consecutive_skipped_ranks : Option<usize>,
+}
+
+impl RankingArgs {
+    pub fn rules(&self) -> BallotInterpretationRules {
+        BallotInterpretationRules{
+            overvote: if self.skip_overvoted_rank { OvervoteRule::SkipRank } else { OvervoteRule::Exhaust },
+            duplicate: if self.exhaust_on_duplicate { DuplicateRule::Exhaust } else { DuplicateRule::Ignore },
+            max_consecutive_skipped_ranks: self.max_consecutive_skipped_ranks,
+        }
+    }
+}
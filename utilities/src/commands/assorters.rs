@@ -0,0 +1,65 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The `assorters` subcommand: write the SHANGRLA assorter value of each assertion on each distinct ballot.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use anyhow::anyhow;
+use clap::Args;
+use raire::assertions::candidate_name;
+use raire::export::assorters::AssorterMatrix;
+use raire::{RaireProblem, RaireSolution};
+
+#[derive(Args,Debug)]
+pub struct AssortersArgs {
+    /// The RAIRE input JSON file containing the ballots.
+    pub votes_file : PathBuf,
+    /// The RAIRE output JSON file with the assertions.
+    pub solution_file : PathBuf,
+    /// Write a CSV file with a row for each distinct ballot and a column for each assertion.
+    #[arg(long)]
+    pub csv : Option<PathBuf>,
+    /// Write the same matrix as JSON.
+    #[arg(long)]
+    pub json : Option<PathBuf>,
+}
+
+impl AssortersArgs {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let problem : RaireProblem = serde_json::from_reader(File::open(&self.votes_file)?)?;
+        let solution : RaireSolution = serde_json::from_reader(File::open(&self.solution_file)?)?;
+        let result = solution.solution.as_ref().map_err(|e|anyhow!("The solution records a failed RAIRE computation : {}",e))?;
+        let assertions : Vec<_> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+        let matrix = AssorterMatrix::new(&assertions,&problem.interpreted_votes());
+        let names = solution.candidate_list()?;
+        for (assertion,mean) in assertions.iter().zip(matrix.means()) {
+            println!("{} : mean assorter {}",assertion.describe(names.names()),mean);
+        }
+        if let Some(csv_file) = &self.csv { write_assorters_csv(&matrix,names.names(),File::create(csv_file)?)?; }
+        if let Some(json_file) = &self.json { serde_json::to_writer_pretty(File::create(json_file)?,&matrix)?; }
+        Ok(())
+    }
+}
+
+fn write_assorters_csv<W:Write>(matrix:&AssorterMatrix,candidate_names:&[String],writer:W) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    let mut header = vec!["Count".to_string(),"Preferences".to_string()];
+    header.extend(matrix.assertions.iter().map(|a|a.describe(candidate_names)));
+    writer.write_record(&header)?;
+    for row in &matrix.rows {
+        let mut record = vec![row.n.0.to_string(),row.prefs.iter().map(|&c|candidate_name(candidate_names,c)).collect::<Vec<_>>().join(" ")];
+        record.extend(row.values.iter().map(|v|v.to_string()));
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
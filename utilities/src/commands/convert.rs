@@ -0,0 +1,161 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The `convert` subcommand: convert between ballot file formats and the native RAIRE JSON.
+//!
+//! All conversions go through a [ParsedContest]. Writing RAIRE JSON needs a unique winner and an audit type;
+//! writing other formats only needs the ballots.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use anyhow::anyhow;
+use clap::{Args, ValueEnum};
+use raire::RaireProblem;
+use crate::cli_args::{AuditArgs, RankingArgs};
+use crate::parse::{ParsedContest, ParseLocation};
+use crate::parse::blt::{read_blt, write_blt};
+use crate::parse::csv::{read_csv_ballots, CsvBallotFormat};
+use crate::parse::dominion::{read_dominion_contest, DominionOptions};
+use crate::parse::hart::read_hart_contest;
+use crate::parse::nist_cdf::read_cdf_file;
+use crate::parse::preflib::{read_preflib, write_preflib};
+use crate::parse::vote_source::CountedCsvSource;
+use crate::parse_michelle_format::Contest;
+
+/// A ballot file format.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,ValueEnum)]
+pub enum Format {
+    /// The RAIRE JSON problem format.
+    Json,
+    /// A single seat .blt file.
+    Blt,
+    /// A PrefLib .soc, .soi, .toc or .toi file.
+    Preflib,
+    /// A CSV file with one ballot per row and one column per rank (only the default layout; use parse_ballot_csv for others).
+    Csv,
+    /// The CSV format of the original RAIRE examples (read only).
+    RaireCsv,
+    /// A Dominion Democracy Suite CVR export folder (read only).
+    Dominion,
+    /// A Hart InterCivic Verity CVR export folder of XML files (read only).
+    Hart,
+    /// A NIST CVR Common Data Format file, JSON, or XML if the name ends in .xml (read only).
+    NistCdf,
+    /// A CSV file with a header row, then for each group of identical ballots a row of the number of ballots followed by the candidates in
+    /// preference order, as a database query might give (read only).
+    CountedCsv,
+}
+
+impl Format {
+    /// Guess the format from a file name; a folder is taken to be a Dominion export if it contains `ContestManifest.json`, otherwise a Hart export.
+    pub fn guess(path:&Path) -> Option<Format> {
+        if path.is_dir() { return Some(if path.join("ContestManifest.json").exists() { Format::Dominion } else { Format::Hart }); }
+        match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "blt" => Some(Format::Blt),
+            "soc" | "soi" | "toc" | "toi" => Some(Format::Preflib),
+            "csv" => Some(Format::Csv),
+            "raire" => Some(Format::RaireCsv),
+            "xml" => Some(Format::NistCdf),
+            _ => None,
+        }
+    }
+
+    /// The extension added to the input file name to make the default output file name, or None if this format cannot be written.
+    fn output_extension(self) -> Option<&'static str> {
+        match self {
+            Format::Json => Some("json"),
+            Format::Blt => Some("blt"),
+            Format::Preflib => Some("soi"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Args,Debug)]
+pub struct ConvertArgs {
+    /// The file (or, for a Dominion or Hart export, folder) to read
+    pub input : PathBuf,
+    /// The file to write. Default is the input file with an extension for the output format added.
+    pub output : Option<PathBuf>,
+    /// The format of the input. Default is guessed from the input file name.
+    #[arg(long,value_enum)]
+    pub from : Option<Format>,
+    /// The format of the output. Default is guessed from the output file name, or RAIRE JSON if not given.
+    #[arg(long,value_enum)]
+    pub to : Option<Format>,
+    /// The contest, for input formats containing more than one: the id or name for Dominion, Hart and NIST CDF, or the number (counting from 1, default 1) for the original RAIRE CSV format.
+    #[arg(long)]
+    pub contest : Option<String>,
+    /// Include marks flagged as ambiguous (Dominion).
+    #[arg(long)]
+    pub include_ambiguous_marks : bool,
+    /// For .blt output, write each ballot on its own line with weight 1.
+    #[arg(long)]
+    pub expand : bool,
+    #[command(flatten)]
+    pub ranking : RankingArgs,
+    #[command(flatten)]
+    pub audit : AuditArgs,
+}
+
+impl ConvertArgs {
+    fn read(&self,from:Format) -> anyhow::Result<ParsedContest> {
+        let default_name = self.input.file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_default();
+        let rules = self.ranking.rules();
+        let contest = ||self.contest.as_deref().ok_or_else(||anyhow!("Please specify the contest with --contest"));
+        Ok(match from {
+            Format::Json => {
+                let problem : RaireProblem = serde_json::from_reader(BufReader::new(File::open(&self.input)?))?;
+                ParsedContest::from_raire_problem(&problem,&default_name)?
+            }
+            Format::Blt => read_blt(File::open(&self.input)?,&default_name,&rules)?,
+            Format::Preflib => read_preflib(BufReader::new(File::open(&self.input)?),&default_name,&rules)?,
+            Format::Csv => read_csv_ballots(File::open(&self.input)?,&default_name,&CsvBallotFormat{ranking:rules,..CsvBallotFormat::default()})?,
+            Format::RaireCsv => {
+                let contests = Contest::parse(&self.input)?;
+                let index : usize = self.contest.as_deref().map(|s|s.parse()).transpose()?.unwrap_or(1);
+                if index<1 || index>contests.len() { return Err(anyhow!("Contest number must be between 1 and {}",contests.len()))}
+                let contest = &contests[index-1];
+                let problem = contest.to_raire_problem(self.audit.audit(contest.votes.values().sum()))?;
+                ParsedContest::from_raire_problem(&problem,&default_name)?
+            }
+            Format::Dominion => read_dominion_contest(&self.input,contest()?,&DominionOptions{ranking:rules,include_ambiguous_marks:self.include_ambiguous_marks})?,
+            Format::Hart => read_hart_contest(&self.input,contest()?,&rules)?,
+            Format::NistCdf => read_cdf_file(&self.input,contest()?,&rules)?,
+            Format::CountedCsv => ParsedContest::from_vote_source(&mut CountedCsvSource::open(&self.input)?)?,
+        })
+    }
+
+    pub fn run(&self) -> anyhow::Result<()> {
+        let from = self.from.or_else(||Format::guess(&self.input)).ok_or_else(||anyhow!("Cannot tell the format of {}; use --from",self.input.display()))?;
+        let to = self.to.or_else(||self.output.as_deref().and_then(Format::guess)).unwrap_or(Format::Json);
+        let extension = to.output_extension().ok_or_else(||anyhow!("Cannot write the {:?} format",to))?;
+        let contest = self.read(from).map_err(|e|ParseLocation::in_file(e,&self.input))?;
+        let num_ballots = contest.num_ballots();
+        println!("{num_ballots} ballots of which {} are unique",contest.votes.len());
+        let output_file : PathBuf = self.output.clone().unwrap_or_else(||{
+            let mut name = self.input.clone().into_os_string();
+            name.push(".");
+            name.push(extension);
+            name.into()
+        });
+        match to {
+            Format::Json => serde_json::to_writer(File::create(&output_file)?,&contest.to_raire_problem(self.audit.audit(num_ballots))?)?,
+            Format::Blt => write_blt(&contest,self.expand,BufWriter::new(File::create(&output_file)?))?,
+            Format::Preflib => {
+                let file_name = output_file.file_name().map(|s|s.to_string_lossy().to_string()).unwrap_or_default();
+                write_preflib(&contest,&file_name,BufWriter::new(File::create(&output_file)?))?
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The `diff` subcommand: compare the assertions in two RAIRE outputs for the same contest.
+
+use std::fs::File;
+use std::path::PathBuf;
+use clap::Args;
+use raire::RaireSolution;
+
+#[derive(Args,Debug)]
+pub struct DiffArgs {
+    /// The first RAIRE output JSON file.
+    pub first : PathBuf,
+    /// The second RAIRE output JSON file.
+    pub second : PathBuf,
+    /// Also write the differences as JSON to this file.
+    #[arg(long)]
+    pub output : Option<PathBuf>,
+    /// Difficulties differing by at most this fraction of the larger are considered the same.
+    #[arg(long,default_value_t=1e-9)]
+    pub tolerance : f64,
+}
+
+impl DiffArgs {
+    /// Print the differences, returning whether the two outputs are the same.
+    pub fn run(&self) -> anyhow::Result<bool> {
+        let first : RaireSolution = serde_json::from_reader(File::open(&self.first)?)?;
+        let second : RaireSolution = serde_json::from_reader(File::open(&self.second)?)?;
+        let diff = first.diff(&second)?;
+        let names = first.candidate_list()?;
+        if diff.first_winner!=diff.second_winner { println!("Winner changed from {} to {}",names.name(diff.first_winner),names.name(diff.second_winner)); }
+        if diff.first_difficulty!=diff.second_difficulty || diff.first_margin!=diff.second_margin {
+            println!("Overall difficulty {} → {}, margin {} → {}",diff.first_difficulty,diff.second_difficulty,diff.first_margin.0,diff.second_margin.0);
+        }
+        for a in &diff.removed { println!("- {} : margin {} difficulty {}",a.assertion.describe(names.names()),a.margin.0,a.difficulty); }
+        for a in &diff.added { println!("+ {} : margin {} difficulty {}",a.assertion.describe(names.names()),a.margin.0,a.difficulty); }
+        for a in diff.changed(self.tolerance) {
+            println!("~ {} : margin {} → {}, difficulty {} → {}",a.assertion.describe(names.names()),a.first_margin.0,a.second_margin.0,a.first_difficulty,a.second_difficulty);
+        }
+        let same = diff.is_same(self.tolerance);
+        println!("{} assertions removed, {} added, {} in both of which {} changed{}",diff.removed.len(),diff.added.len(),diff.common.len(),diff.changed(self.tolerance).count(),if diff.matched_by_name {", matching candidates by name"} else {""});
+        if let Some(output) = &self.output { serde_json::to_writer_pretty(File::create(output)?,&diff)?; }
+        Ok(same)
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The `explain` subcommand: describe the output of RAIRE in human readable forms.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use clap::Args;
+use raire::irv::CandidateIndex;
+use raire::RaireSolution;
+use raire::assertions::{candidate_name, Assertion, EliminationOrder};
+use raire::coverage::CoverageMatrix;
+use raire::explain::{candidate_names_from_metadata, explain_solution};
+use raire::export::shangrla::to_shangrla;
+use raire::graphviz::trees_to_dot;
+use raire::svg::trees_to_svg;
+use raire::report::html::solution_to_html;
+use raire::timeout::TimeOut;
+use raire::tree_json::EliminationTreesJson;
+
+#[derive(Args,Debug)]
+pub struct ExplainArgs {
+    /// The output from RAIRE
+    pub input_file : PathBuf,
+    /// Optionally write a Graphviz DOT file showing the tree of elimination orders ruled out for each candidate other than the winner.
+    #[arg(long)]
+    pub dot : Option<PathBuf>,
+    /// Optionally write a JSON file containing the same trees as the DOT file, with candidate names resolved.
+    #[arg(long)]
+    pub tree_json : Option<PathBuf>,
+    /// Optionally write the assertions in the JSON format used by SHANGRLA.
+    #[arg(long)]
+    pub shangrla : Option<PathBuf>,
+    /// Also print a plain English explanation of the assertions and why they rule out every other winner.
+    #[arg(long)]
+    pub explain : bool,
+    /// Optionally write a self contained HTML report of the solution.
+    #[arg(long)]
+    pub html : Option<PathBuf>,
+    /// Optionally write an SVG picture of the same trees as the DOT file, without needing Graphviz.
+    #[arg(long)]
+    pub svg : Option<PathBuf>,
+    /// Optionally write a CSV file with a row for each elimination order suffix pruned in those trees and a column for each assertion,
+    /// with 1 where that assertion prunes that suffix.
+    #[arg(long)]
+    pub coverage_csv : Option<PathBuf>,
+    /// Optionally write the same matrix as JSON, along with how many pruned suffixes each assertion covers for each alternate winner.
+    #[arg(long)]
+    pub coverage_json : Option<PathBuf>,
+    /// Optionally write a CSV file with a row for each complete elimination order not ruled out by the assertions, from first eliminated to winner.
+    #[arg(long)]
+    pub elimination_orders : Option<PathBuf>,
+    /// With `--elimination-orders`, only write this many orders drawn at random (with replacement) from those not ruled out.
+    #[arg(long,requires_all = ["elimination_orders","seed"])]
+    pub sample_orders : Option<usize>,
+    /// The random seed for `--sample-orders`.
+    #[arg(long,requires = "sample_orders")]
+    pub seed : Option<String>,
+    /// Optionally write JSON statistics over the elimination orders not ruled out: how many there are, and the fraction in which
+    /// each candidate wins or is eliminated at each position.
+    #[arg(long)]
+    pub elimination_order_statistics : Option<PathBuf>,
+}
+
+impl ExplainArgs {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let input : RaireSolution = serde_json::from_reader(File::open(&self.input_file)?)?;
+        let name = |c:CandidateIndex| {
+            if let Some(metadata_name) = input.metadata["candidates"][c.0 as usize].as_str() {
+                metadata_name.to_string()
+            } else {
+                format!("#{}",c.0)
+            }
+        };
+        if let Some(shangrla_file) = &self.shangrla {
+            let contest = self.input_file.file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_default();
+            serde_json::to_writer_pretty(File::create(shangrla_file)?,&to_shangrla(&input,&contest)?)?;
+        }
+        if let Some(html_file) = &self.html {
+            std::fs::write(html_file,solution_to_html(&input,&mut TimeOut::never())?)?;
+        }
+        match &input.solution {
+            Ok(solution) => {
+                println!("Solution overall difficulty {}",solution.difficulty_with_units());
+                for a in &solution.assertions {
+                    match &a.assertion {
+                        Assertion::NEB(neb) => print!("{} NEB {}",name(neb.winner),name(neb.loser)),
+                        Assertion::NEN(nen) => print!("{} > {} with {:?} continuing",name(nen.winner),name(nen.loser),nen.continuing.iter().cloned().map(name).collect::<Vec<_>>()),
+                    }
+                    println!("  Difficulty {}",a.difficulty);
+                }
+                if let Some(sample_sizes) = &input.sample_sizes {
+                    println!("Estimated initial sample size {} ballots at risk limit {} (for each assertion in order {:?})",sample_sizes.overall,sample_sizes.risk_limit,sample_sizes.assertions);
+                }
+                if self.explain {
+                    println!();
+                    print!("{}",explain_solution(solution,&candidate_names_from_metadata(&input.metadata),&mut TimeOut::never())?);
+                }
+                if self.elimination_orders.is_some() || self.elimination_order_statistics.is_some() {
+                    let orders = solution.consistent_elimination_orders(&mut TimeOut::never())?;
+                    if let Some(csv_file) = &self.elimination_orders {
+                        let mut writer = csv::Writer::from_writer(File::create(csv_file)?);
+                        let mut header : Vec<String> = (1..solution.num_candidates).map(|i|format!("Eliminated {}",i)).collect();
+                        header.push("Winner".to_string());
+                        writer.write_record(&header)?;
+                        let mut write = |order:EliminationOrder|writer.write_record(order.into_iter().map(name));
+                        match (self.sample_orders,&self.seed) {
+                            (Some(sample_size),Some(seed)) => for order in orders.sample(seed,sample_size) { write(order)?; },
+                            _ => for order in orders.iter() { write(order)?; },
+                        }
+                        writer.flush()?;
+                    }
+                    if let Some(json_file) = &self.elimination_order_statistics {
+                        serde_json::to_writer_pretty(File::create(json_file)?,&orders.statistics())?;
+                    }
+                }
+                if self.dot.is_some() || self.tree_json.is_some() || self.svg.is_some() || self.coverage_csv.is_some() || self.coverage_json.is_some() {
+                    let trees = solution.elimination_trees(&mut TimeOut::never())?;
+                    let all_assertions : Vec<Assertion> = solution.assertions.iter().map(|a|a.assertion.clone()).collect();
+                    let candidate_names : Vec<String> = (0..solution.num_candidates).map(|c|name(CandidateIndex(c))).collect();
+                    if let Some(dot_file) = &self.dot {
+                        std::fs::write(dot_file,trees_to_dot(&trees,&all_assertions,&candidate_names))?;
+                    }
+                    if let Some(svg_file) = &self.svg {
+                        std::fs::write(svg_file,trees_to_svg(&trees,&all_assertions,&candidate_names))?;
+                    }
+                    if let Some(json_file) = &self.tree_json {
+                        let json = EliminationTreesJson::new(&trees,&all_assertions,solution.num_candidates,&candidate_names);
+                        serde_json::to_writer_pretty(File::create(json_file)?,&json)?;
+                    }
+                    if self.coverage_csv.is_some() || self.coverage_json.is_some() {
+                        let coverage = CoverageMatrix::new(&trees,all_assertions.len());
+                        if let Some(csv_file) = &self.coverage_csv {
+                            write_coverage_csv(&coverage,&all_assertions,&candidate_names,File::create(csv_file)?)?;
+                        }
+                        if let Some(json_file) = &self.coverage_json {
+                            serde_json::to_writer_pretty(File::create(json_file)?,&coverage)?;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                println!("Could not find a solution because {:?}",e)
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write the coverage matrix as CSV, with the suffix and alternate winner as names, and a column per assertion headed by its description.
+fn write_coverage_csv<W:Write>(coverage:&CoverageMatrix,assertions:&[Assertion],candidate_names:&[String],writer:W) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    let mut header = vec!["Elimination order suffix".to_string(),"Alternate winner".to_string()];
+    header.extend(assertions.iter().map(|a|a.describe(candidate_names)));
+    writer.write_record(&header)?;
+    for row in &coverage.rows {
+        let mut record = vec![row.suffix.iter().map(|&c|candidate_name(candidate_names,c)).collect::<Vec<_>>().join(" "),candidate_name(candidate_names,row.alternate_winner())];
+        record.extend((0..coverage.num_assertions).map(|a|if row.pruned_by.contains(&a) {"1"} else {"0"}.to_string()));
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
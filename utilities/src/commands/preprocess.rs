@@ -0,0 +1,54 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The `preprocess` subcommand: merge or drop write-ins and drop withdrawn candidates in a RAIRE problem.
+
+use std::fs::File;
+use std::path::PathBuf;
+use clap::Args;
+use raire::preprocess::{Preprocessing, WriteInRule};
+use raire::RaireProblem;
+
+#[derive(Args,Debug)]
+pub struct PreprocessArgs {
+    /// The RAIRE problem JSON file to read
+    pub input_json_file : PathBuf,
+    /// The file to write the changed problem to. Its metadata records what became of each original candidate.
+    pub output_json_file : PathBuf,
+    /// Replace all the write-in candidates by a single pseudo-candidate.
+    #[arg(long,conflicts_with="drop_write_ins")]
+    pub merge_write_ins : bool,
+    /// Remove the write-in candidates from the contest.
+    #[arg(long)]
+    pub drop_write_ins : bool,
+    /// The name of a write-in candidate. May be repeated. Default is the candidates with names like `Write-in`.
+    #[arg(long)]
+    pub write_in : Vec<String>,
+    /// The name of a candidate to remove from the contest. May be repeated.
+    #[arg(long)]
+    pub withdraw : Vec<String>,
+}
+
+impl PreprocessArgs {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let problem : RaireProblem = serde_json::from_reader(File::open(&self.input_json_file)?)?;
+        let candidates = problem.candidate_list()?;
+        let write_ins = if self.write_in.is_empty() { Preprocessing::detect_write_ins(&candidates) } else { self.write_in.iter().map(|name|candidates.index(name)).collect::<Result<_,_>>()? };
+        let preprocessing = Preprocessing{
+            write_ins,
+            write_in_rule: if self.merge_write_ins { WriteInRule::Merge } else if self.drop_write_ins { WriteInRule::Drop } else { WriteInRule::Keep },
+            withdrawn: self.withdraw.iter().map(|name|candidates.index(name)).collect::<Result<_,_>>()?,
+        };
+        let output = preprocessing.apply(&problem)?;
+        println!("{} candidates became {}",problem.num_candidates,output.num_candidates);
+        serde_json::to_writer(File::create(&self.output_json_file)?,&output)?;
+        Ok(())
+    }
+}
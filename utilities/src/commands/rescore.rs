@@ -0,0 +1,58 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The `rescore` subcommand: recompute the difficulties of a solution's assertions for a different audit, without solving again.
+
+use std::fs::File;
+use std::path::PathBuf;
+use anyhow::anyhow;
+use clap::Args;
+use raire::audit_type::Audit;
+use raire::explain::candidate_names_from_metadata;
+use raire::{RaireProblem, RaireSolution};
+use crate::cli_args::AuditArgs;
+
+#[derive(Args,Debug)]
+pub struct RescoreArgs {
+    /// The RAIRE input JSON file containing the ballots.
+    pub votes_file : PathBuf,
+    /// The RAIRE output JSON file with the assertions to rescore.
+    pub solution_file : PathBuf,
+    /// The file to write the rescored RAIRE output to. Default is to just print the difficulties.
+    #[arg(long)]
+    pub output : Option<PathBuf>,
+    /// A JSON file containing the new audit, in the same form as the `audit` field of the RAIRE input. Otherwise the audit is given by the options below.
+    #[arg(long)]
+    pub audit_json : Option<PathBuf>,
+    #[command(flatten)]
+    pub audit : AuditArgs,
+}
+
+impl RescoreArgs {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let problem : RaireProblem = serde_json::from_reader(File::open(&self.votes_file)?)?;
+        let solution : RaireSolution = serde_json::from_reader(File::open(&self.solution_file)?)?;
+        let votes = problem.counted_votes()?;
+        let audit : Audit = match &self.audit_json {
+            Some(file) => serde_json::from_reader(File::open(file)?)?,
+            None => self.audit.audit(votes.total_votes().0),
+        };
+        let rescored = solution.rescore(&votes,&audit);
+        let original = solution.solution.as_ref().map_err(|e|anyhow!("The solution records a failed RAIRE computation : {}",e))?;
+        let result = rescored.solution.as_ref().map_err(|e|anyhow!("Could not rescore : {}",e))?;
+        println!("Difficulty {} with the original audit, {} with the new audit",original.difficulty_with_units(),result.difficulty_with_units());
+        let names = candidate_names_from_metadata(&solution.metadata);
+        for (before,after) in original.assertions.iter().zip(&result.assertions) {
+            println!("{} : {} → {}",before.assertion.describe(&names),before.difficulty,after.difficulty);
+        }
+        if let Some(output) = &self.output { serde_json::to_writer(File::create(output)?,&rescored)?; }
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! The `sample` subcommand: choose the ballots to retrieve for a round of the audit from a public random seed.
+
+use std::fs::File;
+use std::path::PathBuf;
+use anyhow::anyhow;
+use clap::Args;
+use raire::{RaireProblem, RaireSolution};
+use raire::ballot_manifest::BallotManifest;
+use raire::sampling::SampleRound;
+use crate::parse::ballot_manifest::read_ballot_manifest;
+
+#[derive(Args,Debug)]
+pub struct SampleArgs {
+    /// The RAIRE output JSON file with the assertions to be audited. Its estimated overall sample size is used if `--sample-size` is not given.
+    pub solution_file : PathBuf,
+    /// The public random seed, such as the digits from rolling 20 ten sided dice.
+    #[arg(long)]
+    pub seed : String,
+    /// The ballot manifest. Either a CSV file with a header row and a row per batch, with a column headed `Number of Ballots` and optionally one of running totals,
+    /// or (if the name ends in `.json`) a JSON object with a field `batches`, each an object with fields `batch_id` and `ballots` (the number in the batch).
+    #[arg(long)]
+    pub manifest : Option<PathBuf>,
+    /// The RAIRE input JSON file. If given, the manifest is checked to have its total auditable ballots, and at least as many ballots as it has votes.
+    #[arg(long)]
+    pub votes_file : Option<PathBuf>,
+    /// Without a manifest, the ballots are numbered 1 to this.
+    #[arg(long)]
+    pub total_ballots : Option<usize>,
+    /// The number of ballots to draw in this round.
+    #[arg(long)]
+    pub sample_size : Option<usize>,
+    /// The number of ballots drawn in earlier rounds, which this round follows on from.
+    #[arg(long,default_value_t=0)]
+    pub previous_draws : usize,
+    /// Write the ballots to retrieve, each once in manifest order, as CSV.
+    #[arg(long)]
+    pub csv : Option<PathBuf>,
+    /// Write every draw of the round as JSON.
+    #[arg(long)]
+    pub json : Option<PathBuf>,
+}
+
+impl SampleArgs {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let solution : RaireSolution = serde_json::from_reader(File::open(&self.solution_file)?)?;
+        solution.solution.as_ref().map_err(|e|anyhow!("The solution records a failed RAIRE computation : {}",e))?;
+        let manifest = match (&self.manifest,self.total_ballots) {
+            (Some(file),_) => read_ballot_manifest(file)?,
+            (None,Some(total)) => BallotManifest::unbatched(total),
+            (None,None) => return Err(anyhow!("The number of ballots is not known; use --manifest or --total-ballots")),
+        };
+        if let Some(votes_file) = &self.votes_file {
+            let problem : RaireProblem = serde_json::from_reader(File::open(votes_file)?)?;
+            manifest.check_against(&problem.audit,problem.counted_votes()?.total_votes())?;
+        }
+        let sample_size = self.sample_size.or(solution.sample_sizes.as_ref().map(|s|s.overall)).ok_or_else(||anyhow!("No estimated sample size in the solution; use --sample-size"))?;
+        let round = SampleRound::new(&self.seed,&manifest,self.previous_draws,sample_size)?;
+        let pull = round.pull_list();
+        println!("Drew {} ballots (draws {} to {}), of which {} need to be retrieved.",round.draws.len(),self.previous_draws+1,self.previous_draws+round.draws.len(),pull.len());
+        if let Some(csv_file) = &self.csv {
+            let mut writer = csv::Writer::from_writer(File::create(csv_file)?);
+            writer.write_record(["Ballot","Batch","Position in batch","First draw","Times drawn"])?;
+            for ballot in pull {
+                let times = round.draws.iter().filter(|d|d.ballot==ballot.ballot).count();
+                writer.write_record([ballot.ballot.to_string(),ballot.location.batch_id.clone(),ballot.location.position.to_string(),ballot.draw.to_string(),times.to_string()])?;
+            }
+            writer.flush()?;
+        }
+        if let Some(json_file) = &self.json { serde_json::to_writer_pretty(File::create(json_file)?,&round)?; }
+        Ok(())
+    }
+}
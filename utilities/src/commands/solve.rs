@@ -0,0 +1,236 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The `solve` subcommand: compute assertions for a RAIRE problem.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use clap::{Args, ValueEnum};
+use anyhow::anyhow;
+use serde::Serialize;
+use raire::assertions::{candidate_name, Assertion};
+use raire::batch::{solve_many, BatchReport};
+use raire::canonical_json::to_canonical_json;
+use raire::irv::CandidateIndex;
+use raire::progress::{NoProgressReporter, ProgressReporter};
+use raire::raire_algorithm::AssertionTypes;
+use raire::rounds::RoundByRound;
+use raire::snapshot::{SnapshotFile, DEFAULT_SNAPSHOT_INTERVAL_SECONDS};
+use raire::{RaireProblem, RaireSolution};
+use crate::progress_display::ProgressDisplay;
+
+/// The values of `--assertion-types`.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,ValueEnum)]
+pub enum AssertionTypesArg {
+    /// Both NEB and NEN assertions.
+    Both,
+    /// Only NEB (not eliminated before) assertions.
+    NebOnly,
+    /// Only NEN (not eliminated next) assertions.
+    NenOnly,
+}
+
+impl From<AssertionTypesArg> for AssertionTypes {
+    fn from(value: AssertionTypesArg) -> Self {
+        match value {
+            AssertionTypesArg::Both => AssertionTypes::Both,
+            AssertionTypesArg::NebOnly => AssertionTypes::NEBOnly,
+            AssertionTypesArg::NenOnly => AssertionTypes::NENOnly,
+        }
+    }
+}
+
+#[derive(Args,Debug)]
+pub struct SolveArgs {
+    /// The JSON file containing the command to RAIRE
+    #[arg(required_unless_present = "batch")]
+    pub input_json_file : Option<PathBuf>,
+    /// The file to store the output. Default is the input file name, with path and extension if present removed and `_out.json` added.
+    pub output_json_file : Option<PathBuf>,
+    /// Solve every contest in this directory (each `.json` file not ending `_out.json`) in one run, writing each output next to its input
+    /// with `_out.json` added, and a summary of them all to the `--report` file.
+    #[arg(long,conflicts_with_all = ["input_json_file","named","warm_start"])]
+    pub batch : Option<PathBuf>,
+    /// With `--batch`, where to write the summary. Default is `batch_report.json` in the batch directory.
+    #[arg(long,requires = "batch")]
+    pub report : Option<PathBuf>,
+    /// Make the output reproducible byte for byte, and record a manifest of hashes of the input and output in it.
+    #[arg(long)]
+    pub deterministic : bool,
+    /// If the time limit expires while finding assertions, output the (harder to audit) winner only assertions instead, if they are all true.
+    #[arg(long)]
+    pub winner_only_fallback : bool,
+    /// Find assertions quickly, then spend the rest of the time limit refining them. Improvements are shown on the progress bar.
+    #[arg(long)]
+    pub two_phase : bool,
+    /// Record in each assertion the tallies its margin and difficulty were computed from.
+    #[arg(long)]
+    pub include_tallies : bool,
+    /// Check the trimming by also trimming with both the two pass heuristic and the optimal selection, recording how many assertions each kept.
+    #[arg(long)]
+    pub cross_check_trimming : bool,
+    /// Round the difficulties in the output to this many decimal places.
+    #[arg(long)]
+    pub difficulty_decimal_places : Option<u32>,
+    /// Write the output as canonical JSON, with object keys sorted, so that outputs from different machines can be compared byte for byte.
+    #[arg(long)]
+    pub canonical : bool,
+    /// Choose assertions by comparing margins exactly, so that rounding cannot affect the choice on any platform. Not for BRAVO or stratified audits.
+    #[arg(long)]
+    pub exact_arithmetic : bool,
+    /// Only use assertions of these types, e.g. `neb-only` for audit tools that only support NEB (winner only) assertions.
+    #[arg(long,value_enum)]
+    pub assertion_types : Option<AssertionTypesArg>,
+    /// The elimination order reported by the election authority, as candidate indices separated by commas, first eliminated first.
+    /// If the count of the votes differs, the output is an error saying where.
+    #[arg(long,value_delimiter = ',')]
+    pub reported_elimination_order : Option<Vec<u32>>,
+    /// Treat the total auditable ballots in excess of the votes as phantom ballots, reducing each assertion's margin by one for each.
+    #[arg(long)]
+    pub adjust_for_phantoms : bool,
+    /// Include in the output an estimate of the initial sample size needed at this risk limit, e.g. 0.05.
+    #[arg(long)]
+    pub risk_limit : Option<f64>,
+    /// Also write the winner and assertions to this file with candidates given by name, from the `candidates` field of the metadata, rather than by index.
+    #[arg(long)]
+    pub named : Option<PathBuf>,
+    /// Also write the round by round count, with each round annotated with the assertions about it, to this JSON file.
+    #[arg(long,conflicts_with = "batch")]
+    pub rounds : Option<PathBuf>,
+    /// Also write the round by round count as CSV to this file, with a row for each candidate (and the exhausted votes) in each round,
+    /// listing the assertions about that round with that candidate as loser.
+    #[arg(long,conflicts_with = "batch")]
+    pub rounds_csv : Option<PathBuf>,
+    /// The output of RAIRE for an earlier count of the same contest, e.g. a preliminary count; its assertions are used as `warm_start_assertions`.
+    #[arg(long)]
+    pub warm_start : Option<PathBuf>,
+    /// Do not show a progress bar while solving. It is only shown if standard error is a terminal, and not with `--batch`.
+    #[arg(long)]
+    pub no_progress : bool,
+    /// Keep snapshots of the search in this file, so that if it is stopped (e.g. by the time limit) it can be resumed by running the same
+    /// command again, which continues from the snapshot. The file is removed when the search finishes.
+    #[arg(long,conflicts_with = "batch")]
+    pub snapshot : Option<PathBuf>,
+    /// With `--snapshot`, the number of seconds between snapshots. Default 60.
+    #[arg(long,requires = "snapshot",default_value_t = DEFAULT_SNAPSHOT_INTERVAL_SECONDS)]
+    pub snapshot_interval : f64,
+}
+
+impl SolveArgs {
+    /// Apply the command line options that change the problem.
+    fn apply_options(&self,input:&mut RaireProblem) {
+        if self.deterministic { input.deterministic=Some(true); }
+        if self.include_tallies { input.include_tallies=Some(true); }
+        if self.cross_check_trimming { input.cross_check_trimming=Some(true); }
+        if self.difficulty_decimal_places.is_some() { input.difficulty_decimal_places=self.difficulty_decimal_places; }
+        if self.winner_only_fallback { input.winner_only_fallback=Some(true); }
+        if self.two_phase { input.two_phase=Some(true); }
+        if self.exact_arithmetic { input.exact_arithmetic=Some(true); }
+        if let Some(assertion_types) = self.assertion_types { input.assertion_types=Some(assertion_types.into()); }
+        if let Some(order) = &self.reported_elimination_order { input.reported_elimination_order=Some(order.iter().map(|&c|CandidateIndex(c)).collect()); }
+        if self.adjust_for_phantoms { input.adjust_for_phantoms=Some(true); }
+        if self.risk_limit.is_some() { input.risk_limit=self.risk_limit; }
+    }
+
+    pub fn run(&self) -> anyhow::Result<()> {
+        if let Some(batch) = &self.batch { return self.run_batch(batch); }
+        let input_json_file = self.input_json_file.as_ref().ok_or_else(||anyhow!("No input file"))?;
+        let mut input : RaireProblem = serde_json::from_reader(File::open(input_json_file)?)?;
+        self.apply_options(&mut input);
+        if let Some(warm_start) = &self.warm_start {
+            let previous : RaireSolution = serde_json::from_reader(File::open(warm_start)?)?;
+            let previous = previous.solution.map_err(|e|anyhow!("The warm start file has no assertions as RAIRE failed with {:?}",e))?;
+            input.warm_start_assertions=Some(previous.assertions.into_iter().map(|a|a.assertion).collect());
+        }
+        let problem = if self.rounds.is_some() || self.rounds_csv.is_some() { Some(input.clone()) } else { None };
+        let mut display = if self.no_progress { None } else { Some(ProgressDisplay::new(input.time_limit_seconds)) };
+        let progress : &mut dyn ProgressReporter = match &mut display { Some(display) => display, None => &mut NoProgressReporter };
+        let output = match &self.snapshot {
+            Some(path) => input.solve_with_snapshots(progress,None,Arc::new(SnapshotFile{ path: path.clone(), interval_seconds: self.snapshot_interval })),
+            None => input.solve_with_progress(progress),
+        };
+        drop(display);
+        let output_file : PathBuf = self.output_json_file.clone().unwrap_or_else(||{
+            let mut stem = input_json_file.file_stem().map(PathBuf::from).unwrap_or_else(||PathBuf::from("output"));
+            stem.as_mut_os_string().push("_out.json");
+            stem
+        });
+        self.write_json(&output_file,&output)?;
+        if let Some(named_file) = &self.named {
+            self.write_json(named_file,&output.named_result()?)?;
+        }
+        if let Some(problem) = problem {
+            let result = output.solution.as_ref().map_err(|e|anyhow!("No round by round count as RAIRE failed with {:?}",e))?;
+            let rounds = problem.round_by_round(result)?;
+            if let Some(rounds_file) = &self.rounds { serde_json::to_writer_pretty(File::create(rounds_file)?,&rounds)?; }
+            if let Some(csv_file) = &self.rounds_csv {
+                let assertions : Vec<Assertion> = result.assertions.iter().map(|a|a.assertion.clone()).collect();
+                write_rounds_csv(&rounds,&assertions,output.candidate_list()?.names(),File::create(csv_file)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write an output file as compact JSON, canonical if `--canonical` was given.
+    fn write_json<T:Serialize>(&self,path:&Path,value:&T) -> anyhow::Result<()> {
+        if self.canonical { std::fs::write(path,to_canonical_json(value)?)?; } else { serde_json::to_writer(File::create(path)?,value)?; }
+        Ok(())
+    }
+
+    fn run_batch(&self,dir:&Path) -> anyhow::Result<()> {
+        let report_file = self.report.clone().unwrap_or_else(||dir.join("batch_report.json"));
+        let mut input_files : Vec<PathBuf> = std::fs::read_dir(dir)?.map(|entry|entry.map(|e|e.path())).collect::<Result<_,_>>()?;
+        input_files.retain(|path|path.extension().is_some_and(|e|e=="json") && !path.to_string_lossy().ends_with("_out.json") && *path!=report_file);
+        input_files.sort();
+        let mut names = vec![];
+        let mut problems = vec![];
+        for file in &input_files {
+            let mut problem : RaireProblem = serde_json::from_reader(File::open(file)?).map_err(|e|anyhow!("Could not read {}: {}",file.display(),e))?;
+            self.apply_options(&mut problem);
+            names.push(file.file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_default());
+            problems.push(problem);
+        }
+        let solutions = solve_many(problems);
+        for (name,solution) in names.iter().zip(&solutions) {
+            self.write_json(&dir.join(format!("{}_out.json",name)),solution)?;
+        }
+        let report = BatchReport::new(&names,&solutions);
+        for contest in &report.contests {
+            match (&contest.error,contest.difficulty) {
+                (Some(e),_) => println!("{} : failed, {}",contest.name,e),
+                (None,Some(difficulty)) => println!("{} : {} wins, {} assertions, difficulty {}",contest.name,contest.winner.as_deref().unwrap_or_default(),contest.assertions.unwrap_or_default(),difficulty),
+                (None,None) => {}
+            }
+        }
+        if let Some(hardest) = report.hardest_contest { println!("Hardest contest {} with difficulty {}",report.contests[hardest].name,report.contests[hardest].difficulty.unwrap_or_default()); }
+        serde_json::to_writer_pretty(File::create(&report_file)?,&report)?;
+        Ok(())
+    }
+}
+
+/// Write the round by round count as CSV, with a row for each continuing candidate and the exhausted votes in each round.
+/// Each candidate's row lists the assertions about the round in which it is the loser.
+fn write_rounds_csv<W:Write>(rounds:&RoundByRound,assertions:&[Assertion],candidate_names:&[String],writer:W) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(["Round","Candidate","Tally","Transferred","Eliminated","Assertions"])?;
+    for (i,round) in rounds.rounds.iter().enumerate() {
+        let round_number = (i+1).to_string();
+        for tally in &round.tallies {
+            let loser = |a:&Assertion| match a { Assertion::NEB(neb) => neb.loser, Assertion::NEN(nen) => nen.loser };
+            let about : Vec<String> = round.assertions.iter().map(|&a|&assertions[a]).filter(|a|loser(a)==tally.candidate).map(|a|a.describe(candidate_names)).collect();
+            let eliminated = if round.eliminated.contains(&tally.candidate) { "yes" } else { "" };
+            writer.write_record([round_number.clone(),candidate_name(candidate_names,tally.candidate),tally.tally.to_string(),tally.transferred.to_string(),eliminated.to_string(),about.join("; ")])?;
+        }
+        writer.write_record([round_number,"Exhausted".to_string(),round.exhausted.to_string(),round.newly_exhausted.to_string(),String::new(),String::new()])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,63 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The `verify` subcommand: independently check a set of assertions against the ballots.
+
+use std::fs::File;
+use std::path::PathBuf;
+use anyhow::anyhow;
+use clap::Args;
+use raire::assertions::Assertion;
+use raire::irv::{CandidateIndex, Votes};
+use raire::RaireProblem;
+use raire::sensitivity::{sensitivity, BallotChange};
+use raire::timeout::TimeOut;
+use raire::verify::{verify_assertions, ClaimedAssertion};
+
+#[derive(Args,Debug)]
+pub struct VerifyArgs {
+    /// The RAIRE input JSON file containing the ballots.
+    pub votes_file : PathBuf,
+    /// The assertions to check. Either the output of RAIRE, or a JSON list of assertions in the same format as the assertions in RAIRE output (margins optional).
+    pub assertions_file : PathBuf,
+    /// The claimed winner. Default is the winner in the assertions file if present, otherwise the winner in the votes file.
+    #[arg(long)]
+    pub winner : Option<u32>,
+    /// The file to write the report to. Default is standard output.
+    #[arg(long)]
+    pub output : Option<PathBuf>,
+    /// Also write to this file, for each assertion, how many changed ballots would bring its margin to zero, most fragile first.
+    #[arg(long)]
+    pub sensitivity : Option<PathBuf>,
+}
+
+impl VerifyArgs {
+    /// Write the verification report, returning whether the assertions were verified.
+    pub fn run(&self) -> anyhow::Result<bool> {
+        let problem : RaireProblem = serde_json::from_reader(File::open(&self.votes_file)?)?;
+        let assertions_json : serde_json::Value = serde_json::from_reader(File::open(&self.assertions_file)?)?;
+        let (assertions,solution_winner) = if let Some(result) = assertions_json.get("solution") { // RAIRE output
+            let result = result.get("Ok").ok_or_else(||anyhow!("The assertions file records a failed RAIRE computation"))?;
+            let assertions : Vec<ClaimedAssertion> = serde_json::from_value(result["assertions"].clone())?;
+            (assertions,serde_json::from_value::<Option<CandidateIndex>>(result["winner"].clone())?)
+        } else { (serde_json::from_value(assertions_json)?,None) };
+        let winner = self.winner.map(CandidateIndex).or(solution_winner).or(problem.winner).ok_or_else(||anyhow!("No claimed winner; use --winner"))?;
+        let votes = Votes::new(problem.interpreted_votes(),problem.num_candidates)?;
+        let report = verify_assertions(&votes,winner,&assertions,&mut TimeOut::never())?;
+        if let Some(sensitivity_file) = &self.sensitivity {
+            let assertions : Vec<Assertion> = assertions.iter().map(|a|a.assertion.clone()).collect();
+            serde_json::to_writer_pretty(File::create(sensitivity_file)?,&sensitivity(&assertions,&votes,&BallotChange::ALL))?;
+        }
+        match &self.output {
+            Some(output) => serde_json::to_writer_pretty(File::create(output)?,&report)?,
+            None => println!("{}",serde_json::to_string_pretty(&report)?),
+        }
+        Ok(report.verified)
+    }
+}
@@ -8,4 +8,8 @@
 // You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
 
 pub mod parse_michelle_format;
-pub mod table_of_results;
\ No newline at end of file
+pub mod parse;
+pub mod cli_args;
+pub mod table_of_results;
+pub mod commands;
+pub mod progress_display;
\ No newline at end of file
@@ -0,0 +1,80 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Read ballot manifests from CSV files with a header row and a row per batch, such as the
+//! `Container,Tabulator,Batch Name,Number of Ballots` manifests used by Arlo.
+//!
+//! If the manifest has a column of running totals, each is checked against the sum of the counts so far, so a transcription
+//! error is reported at the row where it happens.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use anyhow::{anyhow, Context};
+use raire::ballot_manifest::{BallotManifest, ManifestBatch};
+use crate::parse::ParseLocation;
+
+/// A description of the layout of a ballot manifest CSV file.
+#[derive(Clone,Debug)]
+pub struct ManifestCsvFormat {
+    pub delimiter : u8,
+    /// The (0 based) column containing the number of ballots in each batch. If None, the column headed `Number of Ballots`, `Ballots`, `Ballot Count` or `Count` (ignoring case).
+    pub count_column : Option<usize>,
+    /// The (0 based) column containing the running total of the number of ballots, if any. If None, a column whose heading starts with `Cumulative` (ignoring case), if any.
+    pub cumulative_column : Option<usize>,
+    /// The (0 based) columns whose values, joined by ` / `, identify the batch. If None, all columns other than the count and running total.
+    pub batch_id_columns : Option<Vec<usize>>,
+}
+
+impl Default for ManifestCsvFormat {
+    fn default() -> Self { ManifestCsvFormat{ delimiter: b',', count_column: None, cumulative_column: None, batch_id_columns: None } }
+}
+
+/// Read a ballot manifest from a CSV file in the given format.
+pub fn read_ballot_manifest_csv<R:Read>(reader:R,format:&ManifestCsvFormat) -> anyhow::Result<BallotManifest> {
+    let mut csv_reader = ::csv::ReaderBuilder::new().delimiter(format.delimiter).has_headers(true).flexible(true).from_reader(reader);
+    let headings : Vec<String> = csv_reader.headers().context(ParseLocation::line(1))?.iter().map(|h|h.trim().to_lowercase()).collect();
+    let count_column = match format.count_column {
+        Some(column) => column,
+        None => headings.iter().position(|h|["number of ballots","ballots","ballot count","count"].contains(&h.as_str())).ok_or_else(||anyhow!("No column headed Number of Ballots")).context(ParseLocation::line(1))?,
+    };
+    let cumulative_column = format.cumulative_column.or_else(||headings.iter().position(|h|h.starts_with("cumulative")));
+    let batch_id_columns : Vec<usize> = match &format.batch_id_columns {
+        Some(columns) => columns.clone(),
+        None => (0..headings.len()).filter(|&c|c!=count_column && Some(c)!=cumulative_column).collect(),
+    };
+    let mut batches = vec![];
+    let mut batch_ids = HashSet::new();
+    let mut total : usize = 0;
+    for (row,record) in csv_reader.records().enumerate() {
+        let location = ||ParseLocation::line(row+2);
+        let record = record.with_context(location)?;
+        let batch_id = batch_id_columns.iter().map(|&c|record.get(c).unwrap_or("").trim()).collect::<Vec<_>>().join(" / ");
+        if !batch_ids.insert(batch_id.clone()) { return Err(anyhow!("Batch {} is repeated",batch_id)).with_context(location); }
+        let cell = record.get(count_column).unwrap_or("").trim();
+        let ballots = cell.parse::<usize>().map_err(|_|anyhow!("Invalid number of ballots {} for batch {}",cell,batch_id)).with_context(location)?;
+        total = total.checked_add(ballots).ok_or_else(||anyhow!("Too many ballots")).with_context(location)?;
+        if let Some(column) = cumulative_column {
+            let cell = record.get(column).unwrap_or("").trim();
+            let cumulative = cell.parse::<usize>().map_err(|_|anyhow!("Invalid running total {} for batch {}",cell,batch_id)).with_context(location)?;
+            if cumulative!=total { return Err(anyhow!("The running total {} for batch {} should be {}, the number of ballots in it and earlier batches",cumulative,batch_id,total)).with_context(location); }
+        }
+        batches.push(ManifestBatch{ batch_id, ballots });
+    }
+    Ok(BallotManifest{ batches })
+}
+
+/// Read a ballot manifest from a file, as JSON if its name ends in `.json`, otherwise as CSV in the default format.
+pub fn read_ballot_manifest(path:&Path) -> anyhow::Result<BallotManifest> {
+    let file = File::open(path)?;
+    if path.extension().is_some_and(|e|e.eq_ignore_ascii_case("json")) { Ok(serde_json::from_reader(file)?) }
+    else { read_ballot_manifest_csv(file,&ManifestCsvFormat::default()).map_err(|e|ParseLocation::in_file(e,path)) }
+}
@@ -0,0 +1,106 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Read and write the `.blt` ballot format used by OpenSTV, ConcreteSTV and many council election datasets.
+//!
+//! A file starts with a line containing the number of candidates and the number of seats, optionally followed by
+//! withdrawn candidates as negative numbers. Then come ballot lines `weight c1 c2 ... 0` (candidates numbered from
+//! 1, optionally preceded by a `(ballot id)`), terminated by a line `0`. Then the candidate names and finally the title, each quoted.
+//! Tied candidates written `1=2` are treated as an overvote at that rank and a `-` as a skipped rank, handled as per the [BallotInterpretationRules].
+//! Withdrawn candidates are removed from the preferences. As RAIRE only deals with single winner IRV, the number of seats must be 1.
+
+use std::io::{Read, Write};
+use anyhow::{anyhow, Context};
+use raire::irv::CandidateIndex;
+use raire::stv::check_supported_seats;
+use crate::parse::{ParsedContest, ParseLocation, RankMark, BallotInterpretationRules};
+
+fn unquote(s:&str) -> String {
+    let s = s.trim();
+    s.strip_prefix('"').and_then(|s|s.strip_suffix('"')).unwrap_or(s).to_string()
+}
+
+/// Read a BLT file. The contest name is the title in the file if present, otherwise `default_name`.
+pub fn read_blt<R:Read>(mut reader:R,default_name:&str,rules:&BallotInterpretationRules) -> anyhow::Result<ParsedContest> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut lines = text.lines().enumerate().map(|(i,l)|(i+1,l.trim())).filter(|(_,l)|!(l.is_empty()||l.starts_with('#')));
+    let (header_line,header) = lines.next().ok_or_else(||anyhow!("Empty BLT file"))?;
+    let mut header = header.split_whitespace();
+    let num_candidates : usize = header.next().ok_or_else(||anyhow!("Missing number of candidates"))?.parse().with_context(||ParseLocation::line(header_line))?;
+    let seats : usize = header.next().ok_or_else(||anyhow!("Missing number of seats"))?.parse().with_context(||ParseLocation::line(header_line))?;
+    check_supported_seats(seats)?;
+    let candidate = |s:&str| -> anyhow::Result<CandidateIndex> {
+        let n : usize = s.parse()?;
+        if n<1 || n>num_candidates { return Err(anyhow!("Candidate {} out of range",n)); }
+        Ok(CandidateIndex((n-1) as u32))
+    };
+    let mut withdrawn : Vec<CandidateIndex> = vec![];
+    let mut ballots : Vec<(usize,Vec<RankMark>)> = vec![];
+    loop {
+        let (line_number,line) = lines.next().ok_or_else(||anyhow!("Missing 0 line at the end of the ballots"))?;
+        if line.starts_with('-') && ballots.is_empty() {
+            for c in line.split_whitespace() { withdrawn.push(candidate(c.trim_start_matches('-')).with_context(||ParseLocation::line(line_number))?); }
+            continue;
+        }
+        let mut tokens = line.split_whitespace().peekable();
+        let mut ballot_id = None;
+        if tokens.peek().is_some_and(|t|t.starts_with('(')) { // a ballot id.
+            let mut id = vec![];
+            for t in tokens.by_ref() { id.push(t); if t.ends_with(')') { break; } }
+            ballot_id = Some(id.join(" ").trim_start_matches('(').trim_end_matches(')').to_string());
+        }
+        let location = ||ParseLocation::ballot(line_number,ballot_id.as_deref());
+        let weight : usize = tokens.next().ok_or_else(||anyhow!("Missing ballot weight")).with_context(location)?.parse().map_err(|_|anyhow!("Ballot weight in {} should be a non-negative integer",line)).with_context(location)?;
+        if weight==0 { break; }
+        let mut marks = vec![];
+        let mut terminated = false;
+        for token in tokens {
+            if token=="0" { terminated=true; break; }
+            marks.push(if token=="-" { RankMark::Skipped } else {
+                let tied : Vec<CandidateIndex> = token.split('=').map(candidate).collect::<anyhow::Result<_>>().with_context(location)?;
+                if tied.len()==1 { RankMark::Candidate(tied[0]) } else { RankMark::Overvote }
+            });
+        }
+        if !terminated { return Err(anyhow!("Ballot line {} should end with 0",line)).with_context(location); }
+        for mark in &mut marks {
+            if let RankMark::Candidate(c) = mark { if withdrawn.contains(c) { *mark=RankMark::Skipped; } }
+        }
+        ballots.push((weight,marks));
+    }
+    let mut candidate_names = vec![];
+    for _ in 0..num_candidates {
+        candidate_names.push(unquote(lines.next().ok_or_else(||anyhow!("Missing candidate name"))?.1));
+    }
+    let name = lines.next().map(|(_,l)|unquote(l)).unwrap_or_else(||default_name.to_string());
+    let mut res = ParsedContest::new(name,candidate_names);
+    for (weight,marks) in ballots {
+        res.add_votes(rules.preferences(marks),weight)?;
+    }
+    Ok(res)
+}
+
+/// Write a contest as a single seat BLT file. If `expand` is true, each ballot is written on its own line with weight 1;
+/// otherwise identical ballots are written once with their multiplicity as the weight.
+pub fn write_blt<W:Write>(contest:&ParsedContest,expand:bool,mut writer:W) -> anyhow::Result<()> {
+    let quote = |s:&str|format!("\"{}\"",s.replace('"',"'"));
+    let mut ballots : Vec<(&Vec<CandidateIndex>,usize)> = contest.votes.iter().map(|(prefs,&n)|(prefs,n)).collect();
+    ballots.sort_unstable_by(|(p1,n1),(p2,n2)|n2.cmp(n1).then_with(||p1.iter().map(|c|c.0).cmp(p2.iter().map(|c|c.0))));
+    writeln!(writer,"{} 1",contest.candidate_names.len())?;
+    for (prefs,n) in ballots {
+        let line : String = prefs.iter().map(|c|format!(" {}",c.0+1)).collect();
+        if expand { for _ in 0..n { writeln!(writer,"1{} 0",line)?; } }
+        else if n>0 { writeln!(writer,"{}{} 0",n,line)?; }
+    }
+    writeln!(writer,"0")?;
+    for candidate_name in &contest.candidate_names { writeln!(writer,"{}",quote(candidate_name))?; }
+    writeln!(writer,"{}",quote(&contest.name))?;
+    Ok(())
+}
@@ -0,0 +1,173 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parse generic "one ballot per row, one column per rank" CSV files.
+//!
+//! Most election offices can produce such a file even when their vendor format is obscure.
+//! The layout is described by a [CsvBallotFormat]. Each cell in a rank column is either blank
+//! (a skipped rank), an overvote marker, or a candidate name. Aggregated data, with one row per distinct
+//! ranking and a column giving the number of such ballots, can be read with [CsvBallotFormat::count_column].
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use anyhow::{anyhow, Context};
+use raire::irv::CandidateIndex;
+use crate::parse::{ParsedContest, ParseLocation, RankMark, BallotInterpretationRules};
+
+/// How candidate names in the file are compared to each other (and to [CsvBallotFormat::candidates]).
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Default)]
+pub enum NameNormalization {
+    /// Names must match exactly.
+    Exact,
+    /// Leading and trailing whitespace is ignored.
+    #[default]
+    Trim,
+    /// Leading and trailing whitespace, case, and repeated internal whitespace are ignored.
+    TrimIgnoreCaseAndSpaces,
+}
+
+impl NameNormalization {
+    pub fn normalize(self,name:&str) -> String {
+        match self {
+            NameNormalization::Exact => name.to_string(),
+            NameNormalization::Trim => name.trim().to_string(),
+            NameNormalization::TrimIgnoreCaseAndSpaces => name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase(),
+        }
+    }
+}
+
+/// What to do with a name that is not in [CsvBallotFormat::candidates].
+#[derive(Clone,Debug,PartialEq,Eq,Default)]
+pub enum WriteInRule {
+    /// Treat it as an error.
+    #[default]
+    Error,
+    /// Treat the rank as skipped.
+    Skip,
+    /// Count all such names as a single candidate with the given name, e.g. "Write-in".
+    Combine(String),
+    /// Each distinct name becomes a new candidate.
+    AddCandidate,
+}
+
+/// A description of the layout of a ranked ballot CSV file.
+#[derive(Clone,Debug)]
+pub struct CsvBallotFormat {
+    pub delimiter : u8,
+    /// Whether the first row is a header, to be skipped.
+    pub has_header : bool,
+    /// The (0 based) columns containing ranks, first preference first. If None, all columns other than [Self::ballot_id_column] and [Self::count_column].
+    pub rank_columns : Option<Vec<usize>>,
+    /// The (0 based) column containing a ballot id, if any. Ballot ids must be unique.
+    pub ballot_id_column : Option<usize>,
+    /// The (0 based) column containing the number of ballots the row stands for, if any. Default is one ballot per row.
+    pub count_column : Option<usize>,
+    /// Cell values, other than empty, meaning that the rank is skipped, e.g. "undervote" (compared after normalization).
+    pub blank_values : Vec<String>,
+    /// Cell values meaning that there is an overvote at this rank, e.g. "overvote" (compared after normalization).
+    pub overvote_values : Vec<String>,
+    pub name_normalization : NameNormalization,
+    /// The candidates, in order. If None, candidates are taken from the file in order of first appearance (and write_ins is ignored).
+    pub candidates : Option<Vec<String>>,
+    pub write_ins : WriteInRule,
+    pub ranking : BallotInterpretationRules,
+}
+
+impl Default for CsvBallotFormat {
+    fn default() -> Self {
+        CsvBallotFormat{
+            delimiter: b',',
+            has_header: true,
+            rank_columns: None,
+            ballot_id_column: None,
+            count_column: None,
+            blank_values: vec![],
+            overvote_values: vec![],
+            name_normalization: NameNormalization::default(),
+            candidates: None,
+            write_ins: WriteInRule::default(),
+            ranking: BallotInterpretationRules::default(),
+        }
+    }
+}
+
+/// Keeps track of the candidates found so far.
+struct CandidateLookup {
+    names : Vec<String>,
+    index : HashMap<String,CandidateIndex>,
+    fixed : bool,
+}
+
+impl CandidateLookup {
+    fn add(&mut self,normalized:String,display:String) -> CandidateIndex {
+        let candidate = CandidateIndex(self.names.len() as u32);
+        self.names.push(display);
+        self.index.insert(normalized,candidate);
+        candidate
+    }
+}
+
+/// Read ballots from a CSV file in the given format. The contest is given the name `name`.
+pub fn read_csv_ballots<R:Read>(reader:R,name:&str,format:&CsvBallotFormat) -> anyhow::Result<ParsedContest> {
+    let normalize = |s:&str|format.name_normalization.normalize(s);
+    let blank_values : HashSet<String> = format.blank_values.iter().map(|s|normalize(s)).collect();
+    let overvote_values : HashSet<String> = format.overvote_values.iter().map(|s|normalize(s)).collect();
+    let mut candidates = CandidateLookup{ names: vec![], index: HashMap::new(), fixed: format.candidates.is_some() };
+    for c in format.candidates.iter().flatten() {
+        if candidates.index.contains_key(&normalize(c)) { return Err(anyhow!("Candidate {} listed twice",c)); }
+        candidates.add(normalize(c),c.clone());
+    }
+    let mut csv_reader = ::csv::ReaderBuilder::new().delimiter(format.delimiter).has_headers(format.has_header).flexible(true).from_reader(reader);
+    let mut res = ParsedContest::new(name.to_string(),vec![]);
+    let mut ballot_ids : HashSet<String> = HashSet::new();
+    for (row,record) in csv_reader.records().enumerate() {
+        let row = row+1+if format.has_header {1} else {0}; // for error messages
+        let record = record.with_context(||ParseLocation::line(row))?;
+        let ballot_id = format.ballot_id_column.and_then(|c|record.get(c));
+        let location = ||ParseLocation::ballot(row,ballot_id);
+        if format.ballot_id_column.is_some() {
+            let id = ballot_id.ok_or_else(||anyhow!("No ballot id column")).with_context(location)?;
+            if !ballot_ids.insert(id.to_string()) { return Err(anyhow!("Ballot id {} is repeated",id)).with_context(location); }
+        }
+        let count = match format.count_column {
+            Some(c) => {
+                let cell = record.get(c).unwrap_or("").trim();
+                cell.parse::<usize>().map_err(|_|anyhow!("Invalid ballot count {}",cell)).with_context(location)?
+            }
+            None => 1,
+        };
+        let rank_cells : Vec<&str> = match &format.rank_columns {
+            Some(columns) => columns.iter().map(|&c|record.get(c).unwrap_or("")).collect(),
+            None => record.iter().enumerate().filter(|(c,_)|Some(*c)!=format.ballot_id_column && Some(*c)!=format.count_column).map(|(_,v)|v).collect(),
+        };
+        let mut marks = vec![];
+        for cell in rank_cells {
+            let normalized = normalize(cell);
+            let mark = if normalized.trim().is_empty() || blank_values.contains(&normalized) { RankMark::Skipped }
+            else if overvote_values.contains(&normalized) { RankMark::Overvote }
+            else if let Some(&candidate) = candidates.index.get(&normalized) { RankMark::Candidate(candidate) }
+            else if !candidates.fixed { RankMark::Candidate(candidates.add(normalized,cell.trim().to_string())) }
+            else {
+                match &format.write_ins {
+                    WriteInRule::Error => return Err(anyhow!("Unknown candidate {}",cell)).with_context(location),
+                    WriteInRule::Skip => RankMark::Skipped,
+                    WriteInRule::Combine(write_in) => RankMark::Candidate(match candidates.index.get(&normalize(write_in)) {
+                        Some(&candidate) => candidate,
+                        None => candidates.add(normalize(write_in),write_in.clone()),
+                    }),
+                    WriteInRule::AddCandidate => RankMark::Candidate(candidates.add(normalized,cell.trim().to_string())),
+                }
+            };
+            marks.push(mark);
+        }
+        res.add_votes(format.ranking.preferences(marks),count).with_context(location)?;
+    }
+    res.candidate_names=candidates.names;
+    Ok(res)
+}
@@ -0,0 +1,179 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parse Dominion Democracy Suite cast vote record exports.
+//!
+//! An export is a folder containing `ContestManifest.json`, `CandidateManifest.json`, and one or
+//! more `CvrExport*.json` files (large exports are split into `CvrExport_0.json`, `CvrExport_1.json`, ...).
+//!
+//! Each ballot session has an `Original` and optionally a `Modified` (adjudicated) interpretation; the
+//! one marked `IsCurrent` is used. Marks with `IsVote` false are ignored, as are ambiguous marks unless
+//! requested. Ranks are then converted into a preference list as per [BallotInterpretationRules]; a rank with marks for more
+//! than one distinct candidate is an overvote.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use anyhow::anyhow;
+use serde::Deserialize;
+use raire::irv::CandidateIndex;
+use crate::parse::{ParsedContest, RankMark, BallotInterpretationRules};
+
+/// Options for interpreting Dominion ranked marks.
+#[derive(Clone,Copy,Debug,Default)]
+pub struct DominionOptions {
+    pub ranking : BallotInterpretationRules,
+    /// If true, marks flagged as ambiguous (but still interpreted as votes) are used. Default false.
+    pub include_ambiguous_marks : bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Manifest<T> {
+    list : Vec<T>,
+}
+
+#[derive(Deserialize,Clone,Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct ManifestContest {
+    pub description : String,
+    pub id : u64,
+    #[serde(default)]
+    pub num_of_ranks : u32,
+}
+
+#[derive(Deserialize,Clone,Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct ManifestCandidate {
+    pub description : String,
+    pub id : u64,
+    pub contest_id : u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrExport {
+    sessions : Vec<Session>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Session {
+    original : Interpretation,
+    modified : Option<Interpretation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Interpretation {
+    #[serde(default = "default_true")]
+    is_current : bool,
+    /// Newer versions group contests by card.
+    #[serde(default)]
+    cards : Vec<Card>,
+    /// Older versions list contests directly.
+    #[serde(default)]
+    contests : Vec<CvrContest>,
+}
+
+fn default_true() -> bool { true }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Card {
+    contests : Vec<CvrContest>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CvrContest {
+    id : u64,
+    marks : Vec<Mark>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Mark {
+    candidate_id : u64,
+    rank : u32,
+    #[serde(default)]
+    is_ambiguous : bool,
+    #[serde(default = "default_true")]
+    is_vote : bool,
+}
+
+impl Interpretation {
+    fn contests(&self) -> impl Iterator<Item=&CvrContest> {
+        self.contests.iter().chain(self.cards.iter().flat_map(|c|c.contests.iter()))
+    }
+}
+
+/// The manifests of a Dominion export, describing the contests and candidates.
+pub struct DominionManifests {
+    pub contests : Vec<ManifestContest>,
+    pub candidates : Vec<ManifestCandidate>,
+}
+
+impl DominionManifests {
+    pub fn read(folder:&Path) -> anyhow::Result<Self> {
+        let contests : Manifest<ManifestContest> = serde_json::from_reader(BufReader::new(File::open(folder.join("ContestManifest.json"))?))?;
+        let candidates : Manifest<ManifestCandidate> = serde_json::from_reader(BufReader::new(File::open(folder.join("CandidateManifest.json"))?))?;
+        Ok(DominionManifests{contests:contests.list,candidates:candidates.list})
+    }
+
+    /// Find a contest by Dominion Id or by (case insensitive) description.
+    pub fn find_contest(&self,contest:&str) -> anyhow::Result<&ManifestContest> {
+        self.contests.iter().find(|c|c.id.to_string()==contest).or_else(||self.contests.iter().find(|c|c.description.eq_ignore_ascii_case(contest)))
+            .ok_or_else(||anyhow!("No contest {} in the contest manifest",contest))
+    }
+}
+
+/// The CvrExport files in the folder, in name order.
+pub fn cvr_export_files(folder:&Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut res = vec![];
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        if let Some(name) = path.file_name().and_then(|s|s.to_str()) {
+            if name.starts_with("CvrExport") && name.ends_with(".json") { res.push(path); }
+        }
+    }
+    if res.is_empty() { return Err(anyhow!("No CvrExport*.json files in {}",folder.display())); }
+    res.sort();
+    Ok(res)
+}
+
+/// Read the votes for a given contest (specified by Dominion Id or description) from a folder containing a Dominion export.
+pub fn read_dominion_contest(folder:&Path,contest:&str,options:&DominionOptions) -> anyhow::Result<ParsedContest> {
+    let manifests = DominionManifests::read(folder)?;
+    let contest = manifests.find_contest(contest)?;
+    let candidates : Vec<&ManifestCandidate> = manifests.candidates.iter().filter(|c|c.contest_id==contest.id).collect();
+    let candidate_index : HashMap<u64,CandidateIndex> = candidates.iter().enumerate().map(|(i,c)|(c.id,CandidateIndex(i as u32))).collect();
+    let mut res = ParsedContest::new(contest.description.clone(),candidates.iter().map(|c|c.description.clone()).collect());
+    for file in cvr_export_files(folder)? {
+        let export : CvrExport = serde_json::from_reader(BufReader::new(File::open(&file)?))?;
+        for session in &export.sessions {
+            let interpretation = match &session.modified {
+                Some(modified) if modified.is_current => modified,
+                _ => &session.original,
+            };
+            for cvr_contest in interpretation.contests().filter(|c|c.id==contest.id) {
+                let mut ranked : Vec<(u32,CandidateIndex)> = vec![];
+                for mark in &cvr_contest.marks {
+                    if mark.is_vote && (options.include_ambiguous_marks || !mark.is_ambiguous) {
+                        let candidate = *candidate_index.get(&mark.candidate_id).ok_or_else(||anyhow!("Candidate id {} in {} is not in the candidate manifest for contest {}",mark.candidate_id,file.display(),contest.id))?;
+                        ranked.push((mark.rank,candidate));
+                    }
+                }
+                res.add_vote(options.ranking.preferences(RankMark::from_ranked_candidates(ranked)));
+            }
+        }
+    }
+    Ok(res)
+}
@@ -0,0 +1,116 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parse Hart InterCivic Verity cast vote record exports. Reading them needs the `xml` feature.
+//!
+//! An export is a folder of XML files, one per ballot sheet, each a `Cvr` element whose `Contests` has a `Contest` for each
+//! contest on the sheet. A contest has a `Name` and an `Id`, and its `Options` has an `Option` for each choice marked, with a `Name`,
+//! an `Id` and, in a ranked contest, the `Rank` it was marked at (1 if absent). An option with `Value` 0 is not a mark.
+//!
+//! The CVRs only list the options marked, so the candidates are numbered in the order they are first seen, reading the files
+//! in name order. Ranks are then converted into a preference list as per [BallotInterpretationRules].
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use anyhow::anyhow;
+use serde_json::Value;
+use raire::irv::CandidateIndex;
+use crate::parse::{ParsedContest, ParseLocation, RankMark, BallotInterpretationRules};
+use crate::parse::nist_cdf::{list, text};
+
+/// The XML files in the folder, in name order.
+pub fn hart_cvr_files(folder:&Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut res = vec![];
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e|e.eq_ignore_ascii_case("xml")) { res.push(path); }
+    }
+    if res.is_empty() { return Err(anyhow!("No .xml files in {}",folder.display())); }
+    res.sort();
+    Ok(res)
+}
+
+/// Whether an XML document is a Hart CVR, that is, its root element is `Cvr`.
+#[cfg(feature = "xml")]
+pub fn is_hart_cvr(contents:&[u8]) -> bool {
+    use quick_xml::events::Event;
+    let mut reader = quick_xml::Reader::from_reader(contents);
+    let mut buf = vec![];
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => return e.local_name().as_ref()==b"Cvr",
+            Ok(Event::Eof) | Err(_) => return false,
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Whether an XML document is a Hart CVR; never, without the `xml` feature.
+#[cfg(not(feature = "xml"))]
+pub fn is_hart_cvr(_contents:&[u8]) -> bool { false }
+
+/// Read a Hart CVR file as a tree in the same form as the JSON NIST CDF.
+fn read_hart_cvr(path:&Path) -> anyhow::Result<Value> {
+    #[cfg(feature = "xml")]
+    return crate::parse::nist_cdf::xml_to_value(std::io::BufReader::new(std::fs::File::open(path)?)).map_err(|e|ParseLocation::in_file(e,path));
+    #[cfg(not(feature = "xml"))]
+    return Err(anyhow!("Reading {} requires the xml feature",path.display()));
+}
+
+/// The contests in a CVR.
+fn contests(cvr:&Value) -> impl Iterator<Item=&Value> {
+    list(cvr,"Contests").into_iter().flat_map(|c|list(c,"Contest"))
+}
+
+/// The names (or, if unnamed, `Id`s) of the contests in a Hart export folder, in the order they are first seen.
+pub fn hart_contest_names(folder:&Path) -> anyhow::Result<Vec<String>> {
+    let mut res : Vec<String> = vec![];
+    for file in hart_cvr_files(folder)? {
+        for name in contests(&read_hart_cvr(&file)?).filter_map(|c|text(c,"Name").or_else(||text(c,"Id"))) {
+            if !res.contains(&name) { res.push(name); }
+        }
+    }
+    Ok(res)
+}
+
+/// Read the votes for a given contest (specified by `Id` or case insensitive `Name`) from a folder containing a Hart export.
+/// Once a contest is found, later CVRs are matched by its `Id`.
+pub fn read_hart_contest(folder:&Path,contest:&str,rules:&BallotInterpretationRules) -> anyhow::Result<ParsedContest> {
+    let mut res : Option<(String,ParsedContest)> = None;
+    let mut candidate_index : HashMap<String,CandidateIndex> = HashMap::new();
+    for file in hart_cvr_files(folder)? {
+        let cvr = read_hart_cvr(&file)?;
+        let found = match &res {
+            Some((id,_)) => contests(&cvr).find(|c|text(c,"Id").as_ref()==Some(id)),
+            None => contests(&cvr).find(|c|text(c,"Id").as_deref()==Some(contest))
+                .or_else(||contests(&cvr).find(|c|text(c,"Name").is_some_and(|n|n.eq_ignore_ascii_case(contest)))),
+        };
+        let Some(found) = found else { continue };
+        let (_,parsed) = res.get_or_insert_with(||{
+            let id = text(found,"Id").unwrap_or_else(||contest.to_string());
+            (id.clone(),ParsedContest::new(text(found,"Name").unwrap_or(id),vec![]))
+        });
+        let mut ranked : Vec<(u32,CandidateIndex)> = vec![];
+        for option in list(found,"Options").into_iter().flat_map(|o|list(o,"Option")) {
+            if text(option,"Value").as_deref()==Some("0") { continue; }
+            let name = text(option,"Name").unwrap_or_default();
+            let id = text(option,"Id").unwrap_or_else(||name.clone());
+            let candidate = *candidate_index.entry(id).or_insert_with(||{
+                parsed.candidate_names.push(name.clone());
+                CandidateIndex(parsed.candidate_names.len() as u32-1)
+            });
+            let rank = text(option,"Rank").unwrap_or_else(||"1".to_string());
+            let rank = rank.parse().map_err(|_|ParseLocation::in_file(anyhow!("Option {} has rank {:?}, not a positive integer",name,rank),&file))?;
+            ranked.push((rank,candidate));
+        }
+        parsed.add_vote(rules.preferences(RankMark::from_ranked_candidates(ranked)));
+    }
+    res.map(|(_,parsed)|parsed).ok_or_else(||anyhow!("No contest {} in the CVRs in {}",contest,folder.display()))
+}
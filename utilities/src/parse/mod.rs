@@ -0,0 +1,156 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parsers for various ballot file formats, converting them into RAIRE problems.
+
+pub mod dominion;
+pub mod csv;
+pub mod preflib;
+pub mod blt;
+pub mod nist_cdf;
+pub mod hart;
+pub mod ballot_manifest;
+pub mod vote_source;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use anyhow::anyhow;
+use serde_json::json;
+use raire::assertions::candidate_name;
+use raire::audit_type::Audit;
+pub use raire::ballot_interpretation::{BallotInterpretationRules, DuplicateRule, OvervoteRule, RankMark};
+use raire::irv::{BallotPaperCount, CandidateIndex, Votes, VotesBuilder};
+use raire::RaireProblem;
+use raire::schema::FORMAT_VERSION;
+use raire::timeout::TimeOut;
+
+/// Where in the input a problem was found. The parsers attach this to their errors as [anyhow::Context], so the message says
+/// where to look and the underlying problem is the error's `source()`. Programs can get the location with
+/// `error.downcast_ref::<ParseLocation>()`. The parsers read from a reader, so the file is added by the caller with [ParseLocation::in_file].
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct ParseLocation {
+    pub file : Option<PathBuf>,
+    /// The line (or CSV row, counting any header), starting at 1.
+    pub line : Option<usize>,
+    pub ballot_id : Option<String>,
+}
+
+impl ParseLocation {
+    pub fn file(file:&Path) -> Self { ParseLocation{ file: Some(file.to_path_buf()), ..Default::default() } }
+    pub fn line(line:usize) -> Self { ParseLocation{ line: Some(line), ..Default::default() } }
+    pub fn ballot(line:usize,ballot_id:Option<&str>) -> Self { ParseLocation{ line: Some(line), ballot_id: ballot_id.map(|id|id.to_string()), ..Default::default() } }
+
+    /// Say that the error came from reading the given file, adding it to the error's location if it has one.
+    pub fn in_file(mut error:anyhow::Error,file:&Path) -> anyhow::Error {
+        match error.downcast_mut::<ParseLocation>() {
+            Some(location) => { location.file=Some(file.to_path_buf()); error }
+            None => error.context(ParseLocation::file(file)),
+        }
+    }
+}
+
+impl fmt::Display for ParseLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if let Some(file) = &self.file { parts.push(format!("in {}",file.display())); }
+        if let Some(line) = self.line { parts.push(format!("line {}",line)); }
+        if let Some(ballot_id) = &self.ballot_id { parts.push(format!("ballot id {}",ballot_id)); }
+        if parts.is_empty() { write!(f,"in the input") } else { write!(f,"{}",parts.join(", ")) }
+    }
+}
+
+/// A single IRV contest read from some file format.
+pub struct ParsedContest {
+    /// A human readable name for the contest.
+    pub name : String,
+    pub candidate_names : Vec<String>,
+    /// The number of ballots with each preference list.
+    pub votes : HashMap<Vec<CandidateIndex>,usize>,
+}
+
+impl ParsedContest {
+    pub fn new(name:String,candidate_names:Vec<String>) -> Self { ParsedContest{name,candidate_names,votes:HashMap::default()} }
+
+    pub fn add_vote(&mut self,prefs:Vec<CandidateIndex>) {
+        *self.votes.entry(prefs).or_insert(0)+=1;
+    }
+
+    /// Add `n` ballots with the given preferences, failing if the count doesn't fit in a usize, as may happen with a corrupt file.
+    pub fn add_votes(&mut self,prefs:Vec<CandidateIndex>,n:usize) -> anyhow::Result<()> {
+        let count = self.votes.entry(prefs).or_insert(0);
+        *count = count.checked_add(n).ok_or_else(||anyhow!("Too many ballots"))?;
+        Ok(())
+    }
+
+    /// The number of ballots. This saturates rather than overflowing; [ParsedContest::to_votes] fails in that case.
+    pub fn num_ballots(&self) -> usize { self.votes.values().fold(0,|total,&n|total.saturating_add(n)) }
+
+    /// Make a contest from an existing RAIRE problem, taking names from the metadata `candidates` and `contest` fields if present.
+    pub fn from_raire_problem(problem:&RaireProblem,default_name:&str) -> anyhow::Result<Self> {
+        let names : Vec<String> = problem.metadata["candidates"].as_array().map(|a|a.iter().map(|v|v.as_str().unwrap_or_default().to_string()).collect()).unwrap_or_default();
+        let name = problem.metadata["contest"].as_str().unwrap_or(default_name).to_string();
+        let mut res = ParsedContest::new(name,(0..problem.num_candidates).map(|c|candidate_name(&names,CandidateIndex(c as u32))).collect());
+        for vote in problem.interpreted_votes() {
+            res.add_votes(vote.prefs,vote.n.0)?;
+        }
+        Ok(res)
+    }
+
+    pub fn to_votes(&self) -> anyhow::Result<Votes> {
+        let mut builder = VotesBuilder::new(self.candidate_names.len());
+        for (prefs,n) in &self.votes { builder.add_weighted(prefs,BallotPaperCount(*n))?; }
+        Ok(builder.build())
+    }
+
+    pub fn to_raire_problem(&self,audit : Audit) -> anyhow::Result<RaireProblem> {
+        let votes = self.to_votes()?;
+        let winners = votes.run_election(&mut TimeOut::never())?;
+        if winners.possible_winners.len()!=1 { return Err(anyhow!("RAIRE only works if there is one possible winner."))}
+        let winner = winners.possible_winners[0];
+        let metadata = json!({"candidates":self.candidate_names,"contest":self.name});
+        Ok(RaireProblem{
+            metadata,
+            num_candidates: self.candidate_names.len(),
+            votes: votes.votes,
+            winner: Some(winner),
+            audit,
+            seats: None,
+            elimination_rule: None,
+            tie_resolution: None,
+            reported_elimination_order: None,
+            trim_algorithm: None,
+            trim_selection_algorithm: None,
+            cross_check_trimming: None,
+            two_phase: None,
+            max_tree_nodes: None,
+            extended_trim: None,
+            difficulty_estimate: None,
+            time_limit_seconds: None,
+            partial_on_timeout: None,
+            winner_only_fallback: None,
+            stop_when_difficulty_at_most: None,
+            objective: None,
+            deterministic: None,
+            include_tallies: None,
+            difficulty_decimal_places: None,
+            exact_arithmetic: None,
+            assertion_types: None,
+            ranked_votes: None,
+            ballot_interpretation: None,
+            vote_strata: None,
+            adjust_for_phantoms: None,
+            warm_start_assertions: None,
+            max_memory_mb: None,
+            risk_limit: None,
+            error_rates: None,
+            format_version: FORMAT_VERSION,
+        })
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Read cast vote records in the NIST CVR Common Data Format ([SP 1500-103](https://doi.org/10.6028/NIST.SP.1500-103)),
+//! in either its JSON or (with the `xml` feature) XML form.
+//!
+//! Both forms are first converted into a generic [serde_json::Value] tree with the same element names (in the XML,
+//! the `ObjectId` and `xsi:type` attributes become `@id` and `@type`), so one routine extracts the contest.
+//! The contest's `ContestSelection`s become candidates, in order, named after the referenced `Candidate`.
+//! For each `CVR` the current snapshot (`CurrentSnapshotId`, or the first snapshot) is used. A selection position counts as a mark
+//! at its rank unless `IsAllocable` or `HasIndication` is `no`. Ranks are then converted into a preference list as per the [BallotInterpretationRules].
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use anyhow::anyhow;
+use serde_json::Value;
+use raire::irv::CandidateIndex;
+use crate::parse::{ParsedContest, RankMark, BallotInterpretationRules};
+
+/// The values of a field that may be absent, a single value, or an array.
+pub(crate) fn list<'a>(v:&'a Value,key:&str) -> Vec<&'a Value> {
+    match v.get(key) {
+        None|Some(Value::Null) => vec![],
+        Some(Value::Array(a)) => a.iter().collect(),
+        Some(value) => vec![value],
+    }
+}
+
+/// The text of the first value of a field, if a string or number.
+pub(crate) fn text(v:&Value,key:&str) -> Option<String> {
+    list(v,key).first().and_then(|v|match v {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    })
+}
+
+/// The values of a field containing ids, which in XML are whitespace separated.
+fn ids(v:&Value,key:&str) -> Vec<String> {
+    list(v,key).into_iter().filter_map(|v|v.as_str()).flat_map(|s|s.split_whitespace()).map(|s|s.to_string()).collect()
+}
+
+/// The names (or, if unnamed, `@id`s) of the contests in a CastVoteRecordReport.
+pub fn cdf_contest_names(report:&Value) -> Vec<String> {
+    list(report,"Election").into_iter().flat_map(|e|list(e,"Contest")).filter_map(|c|text(c,"Name").or_else(||text(c,"@id"))).collect()
+}
+
+/// Extract the given contest (by `@id` or case insensitive `Name`) from a CastVoteRecordReport.
+pub fn read_cdf_contest(report:&Value,contest:&str,rules:&BallotInterpretationRules) -> anyhow::Result<ParsedContest> {
+    let elections = list(report,"Election");
+    let found = elections.iter().flat_map(|e|list(e,"Contest")).find(|c|text(c,"@id").as_deref()==Some(contest))
+        .or_else(||elections.iter().flat_map(|e|list(e,"Contest")).find(|c|text(c,"Name").is_some_and(|n|n.eq_ignore_ascii_case(contest))))
+        .ok_or_else(||anyhow!("No contest {} in the election definitions",contest))?;
+    let contest_id = text(found,"@id").ok_or_else(||anyhow!("Contest {} has no id",contest))?;
+    let candidate_names : HashMap<String,String> = elections.iter().flat_map(|e|list(e,"Candidate"))
+        .filter_map(|c|Some((text(c,"@id")?,text(c,"Name").unwrap_or_default()))).collect();
+    let mut selection_index : HashMap<String,CandidateIndex> = HashMap::new();
+    let mut names = vec![];
+    for selection in list(found,"ContestSelection") {
+        let id = text(selection,"@id").ok_or_else(||anyhow!("Contest selection in {} has no id",contest_id))?;
+        let name = ids(selection,"CandidateIds").iter().map(|c|candidate_names.get(c).cloned().unwrap_or_else(||c.clone())).collect::<Vec<_>>().join(" & ");
+        let name = if !name.is_empty() { name } else if text(selection,"IsWriteIn").as_deref()==Some("true") { "Write-in".to_string() } else { id.clone() };
+        selection_index.insert(id,CandidateIndex(names.len() as u32));
+        names.push(name);
+    }
+    let mut res = ParsedContest::new(text(found,"Name").unwrap_or_else(||contest_id.clone()),names);
+    for cvr in list(report,"CVR") {
+        let snapshots = list(cvr,"CVRSnapshot");
+        let current = text(cvr,"CurrentSnapshotId");
+        let snapshot = match snapshots.iter().find(|s|current.is_some() && text(s,"@id")==current) {
+            Some(s) => Some(*s),
+            None => snapshots.first().copied(),
+        };
+        for cvr_contest in snapshot.into_iter().flat_map(|s|list(s,"CVRContest")).filter(|c|text(c,"ContestId").as_deref()==Some(contest_id.as_str())) {
+            let mut ranked : Vec<(u32,CandidateIndex)> = vec![];
+            for selection in list(cvr_contest,"CVRContestSelection") {
+                let Some(selection_id) = text(selection,"ContestSelectionId") else { continue }; // e.g. an unattributed overvote.
+                let candidate = *selection_index.get(&selection_id).ok_or_else(||anyhow!("Contest selection {} is not in contest {}",selection_id,contest_id))?;
+                for position in list(selection,"SelectionPosition") {
+                    if text(position,"IsAllocable").as_deref()==Some("no") || text(position,"HasIndication").as_deref()==Some("no") { continue; }
+                    let rank = text(position,"Rank").or_else(||text(selection,"Rank")).ok_or_else(||anyhow!("Selection {} has no rank",selection_id))?;
+                    ranked.push((rank.parse()?,candidate));
+                }
+            }
+            res.add_vote(rules.preferences(RankMark::from_ranked_candidates(ranked)));
+        }
+    }
+    Ok(res)
+}
+
+/// Read the given contest from a NIST CDF CVR JSON file.
+pub fn read_cdf_json<R:Read>(reader:R,contest:&str,rules:&BallotInterpretationRules) -> anyhow::Result<ParsedContest> {
+    let report : Value = serde_json::from_reader(reader)?;
+    read_cdf_contest(&report,contest,rules)
+}
+
+/// Convert an XML document into the same form as the JSON CDF.
+#[cfg(feature = "xml")]
+pub(crate) fn xml_to_value<R:std::io::BufRead>(reader:R) -> anyhow::Result<Value> {
+    use quick_xml::events::{BytesStart, Event};
+    use serde_json::Map;
+    fn start(e:&BytesStart) -> anyhow::Result<(String,Map<String,Value>,String)> {
+        let mut attributes = Map::new();
+        for attribute in e.attributes() {
+            let attribute = attribute?;
+            let key = match attribute.key.local_name().as_ref() {
+                b"ObjectId" => "@id".to_string(),
+                b"type" => "@type".to_string(),
+                key => String::from_utf8_lossy(key).to_string(),
+            };
+            attributes.insert(key,Value::String(attribute.unescape_value()?.to_string()));
+        }
+        Ok((String::from_utf8_lossy(e.local_name().as_ref()).to_string(),attributes,String::new()))
+    }
+    fn finish(stack:&mut [(String,Map<String,Value>,String)],(name,fields,text):(String,Map<String,Value>,String)) -> Option<Value> {
+        let value = if fields.is_empty() { Value::String(text) } else { Value::Object(fields) };
+        match stack.last_mut() {
+            Some((_,parent,_)) => {
+                match parent.entry(name).or_insert_with(||Value::Array(vec![])) {
+                    Value::Array(a) => a.push(value),
+                    attribute => *attribute=Value::Array(vec![attribute.take(),value]),
+                }
+                None
+            }
+            None => Some(value),
+        }
+    }
+    let mut reader = quick_xml::Reader::from_reader(reader);
+    reader.config_mut().trim_text(true);
+    let mut stack = vec![];
+    let mut buf = vec![];
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => stack.push(start(&e)?),
+            Event::Empty(e) => { if let Some(root) = finish(&mut stack,start(&e)?) { return Ok(root); } }
+            Event::Text(t) => { if let Some((_,_,text)) = stack.last_mut() { text.push_str(&t.unescape()?); } }
+            Event::CData(t) => { if let Some((_,_,text)) = stack.last_mut() { text.push_str(&String::from_utf8_lossy(&t)); } }
+            Event::End(_) => {
+                let element = stack.pop().ok_or_else(||anyhow!("Unbalanced XML"))?;
+                if let Some(root) = finish(&mut stack,element) { return Ok(root); }
+            }
+            Event::Eof => return Err(anyhow!("Unexpected end of XML")),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Read the given contest from a NIST CDF CVR XML file.
+#[cfg(feature = "xml")]
+pub fn read_cdf_xml<R:std::io::BufRead>(reader:R,contest:&str,rules:&BallotInterpretationRules) -> anyhow::Result<ParsedContest> {
+    read_cdf_contest(&xml_to_value(reader)?,contest,rules)
+}
+
+/// Read a NIST CDF CVR file, XML if the file name ends in `.xml`, otherwise JSON, as a CastVoteRecordReport in the JSON form.
+pub fn read_cdf_report(path:&Path) -> anyhow::Result<Value> {
+    let reader = BufReader::new(File::open(path)?);
+    if path.extension().is_some_and(|e|e.eq_ignore_ascii_case("xml")) {
+        #[cfg(feature = "xml")]
+        return xml_to_value(reader);
+        #[cfg(not(feature = "xml"))]
+        return Err(anyhow!("Reading XML requires the xml feature"));
+    }
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Read the given contest from a NIST CDF CVR file, XML if the file name ends in `.xml`, otherwise JSON.
+pub fn read_cdf_file(path:&Path,contest:&str,rules:&BallotInterpretationRules) -> anyhow::Result<ParsedContest> {
+    read_cdf_contest(&read_cdf_report(path)?,contest,rules)
+}
@@ -0,0 +1,133 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Read and write [PrefLib](https://www.preflib.org/) order files (`.soc`, `.soi`, `.toc`, `.toi`).
+//!
+//! Both the current format (with `# ALTERNATIVE NAME i:` header comments and `count: order` lines)
+//! and the legacy format (candidate count, `i,name` lines, a totals line, then `count,order` lines) are read.
+//! Ties in `.toi`/`.toc` files, written `{1,3}`, are treated as an overvote at that rank and handled as per the [BallotInterpretationRules].
+
+use std::io::{BufRead, Write};
+use anyhow::{anyhow, Context};
+use raire::irv::CandidateIndex;
+use crate::parse::{ParsedContest, ParseLocation, RankMark, BallotInterpretationRules};
+
+/// Parse an order like `1,{2,3},4` (candidates numbered from 1) into the marks at each rank.
+fn parse_order(order:&str,num_candidates:usize) -> anyhow::Result<Vec<RankMark>> {
+    let mut marks = vec![];
+    let mut rest = order.trim();
+    let candidate = |s:&str| -> anyhow::Result<CandidateIndex> {
+        let n : usize = s.trim().parse()?;
+        if n<1 || n>num_candidates { return Err(anyhow!("Alternative {} out of range",n)); }
+        Ok(CandidateIndex((n-1) as u32))
+    };
+    while !rest.is_empty() {
+        if let Some(tied) = rest.strip_prefix('{') {
+            let end = tied.find('}').ok_or_else(||anyhow!("Unclosed {{ in {}",order))?;
+            let candidates : Vec<CandidateIndex> = tied[..end].split(',').filter(|s|!s.trim().is_empty()).map(candidate).collect::<anyhow::Result<_>>()?;
+            marks.push(match candidates.len() {
+                0 => RankMark::Skipped,
+                1 => RankMark::Candidate(candidates[0]),
+                _ => RankMark::Overvote,
+            });
+            rest = tied[end+1..].trim_start().trim_start_matches(',').trim_start();
+        } else {
+            let (first,remaining) = rest.split_once(',').unwrap_or((rest,""));
+            marks.push(RankMark::Candidate(candidate(first)?));
+            rest = remaining.trim_start();
+        }
+    }
+    Ok(marks)
+}
+
+fn parse_count_and_order(count:&str,order:&str,num_candidates:usize) -> anyhow::Result<(usize,Vec<RankMark>)> {
+    Ok((count.trim().parse()?,parse_order(order,num_candidates)?))
+}
+
+/// Read a PrefLib file. The contest name is the `# TITLE` if present, otherwise `default_name`.
+pub fn read_preflib<R:BufRead>(reader:R,default_name:&str,rules:&BallotInterpretationRules) -> anyhow::Result<ParsedContest> {
+    let mut lines = reader.lines().enumerate().map(|(i,l)|(i+1,l)).peekable();
+    let mut name = default_name.to_string();
+    let mut candidate_names : Vec<String> = vec![];
+    let mut numbered_names : Vec<(usize,String)> = vec![]; // (index starting at 1, name) from the current format, placed once the number of alternatives is known.
+    let mut num_candidates : Option<usize> = None;
+    let mut orders : Vec<(usize,Vec<RankMark>)> = vec![];
+    if lines.peek().is_some_and(|(_,l)|l.as_ref().is_ok_and(|l|!l.starts_with('#'))) { // legacy format
+        let (line_number,line) = lines.next().unwrap();
+        num_candidates = Some(line?.trim().parse().with_context(||ParseLocation::line(line_number))?);
+        for _ in 0..num_candidates.unwrap() {
+            let (line_number,line) = lines.next().ok_or_else(||anyhow!("Missing candidate name"))?;
+            let line = line?;
+            let (_,candidate_name) = line.split_once(',').ok_or_else(||anyhow!("Expecting number,name not {}",line)).with_context(||ParseLocation::line(line_number))?;
+            candidate_names.push(candidate_name.trim().to_string());
+        }
+        lines.next().ok_or_else(||anyhow!("Missing voter count line"))?.1?;
+        for (line_number,line) in lines {
+            let line = line?;
+            if line.trim().is_empty() { continue; }
+            let (count,order) = line.split_once(',').unwrap_or((&line,""));
+            orders.push(parse_count_and_order(count,order,candidate_names.len()).with_context(||ParseLocation::line(line_number))?);
+        }
+    } else {
+        for (line_number,line) in lines {
+            let line = line?;
+            let location = ||ParseLocation::line(line_number);
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some((key,value)) = comment.split_once(':') {
+                    let key = key.trim();
+                    if key=="TITLE" { name=value.trim().to_string(); }
+                    else if key=="NUMBER ALTERNATIVES" { num_candidates=Some(value.trim().parse().with_context(location)?); }
+                    else if let Some(index) = key.strip_prefix("ALTERNATIVE NAME") {
+                        let index : usize = index.trim().parse().with_context(location)?;
+                        if index<1 { return Err(anyhow!("Alternative numbering starts at 1")).with_context(location); }
+                        numbered_names.push((index,value.trim().to_string()));
+                    }
+                }
+            } else if !line.trim().is_empty() {
+                let (count,order) = line.split_once(':').ok_or_else(||anyhow!("Expecting count: order, not {}",line)).with_context(location)?;
+                let num_candidates = num_candidates.ok_or_else(||anyhow!("Missing # NUMBER ALTERNATIVES before the orders")).with_context(location)?;
+                orders.push(parse_count_and_order(count,order,num_candidates).with_context(location)?);
+            }
+        }
+    }
+    let num_candidates = num_candidates.unwrap_or(candidate_names.len().max(numbered_names.iter().map(|(index,_)|*index).max().unwrap_or(0)));
+    if candidate_names.len()>num_candidates || numbered_names.iter().any(|(index,_)|*index>num_candidates) { return Err(anyhow!("More alternative names than alternatives")); }
+    candidate_names.resize(num_candidates,String::new());
+    for (index,candidate_name) in numbered_names { candidate_names[index-1]=candidate_name; }
+    for (i,candidate_name) in candidate_names.iter_mut().enumerate() {
+        if candidate_name.is_empty() { *candidate_name=format!("Alternative {}",i+1); }
+    }
+    let mut res = ParsedContest::new(name,candidate_names);
+    for (count,marks) in orders {
+        res.add_votes(rules.preferences(marks),count)?;
+    }
+    Ok(res)
+}
+
+/// Write a contest in the current PrefLib format, as a `.soc` file if every ballot ranks every candidate, otherwise `.soi`.
+/// PrefLib has no representation of a ballot with no preferences, so such ballots are omitted.
+pub fn write_preflib<W:Write>(contest:&ParsedContest,file_name:&str,mut writer:W) -> anyhow::Result<()> {
+    let num_candidates = contest.candidate_names.len();
+    let mut orders : Vec<(&Vec<CandidateIndex>,usize)> = contest.votes.iter().filter(|(prefs,_)|!prefs.is_empty()).map(|(prefs,&n)|(prefs,n)).collect();
+    orders.sort_unstable_by(|(p1,n1),(p2,n2)|n2.cmp(n1).then_with(||p1.iter().map(|c|c.0).cmp(p2.iter().map(|c|c.0))));
+    let complete = orders.iter().all(|(prefs,_)|prefs.len()==num_candidates);
+    writeln!(writer,"# FILE NAME: {}",file_name)?;
+    writeln!(writer,"# TITLE: {}",contest.name)?;
+    writeln!(writer,"# DATA TYPE: {}",if complete {"soc"} else {"soi"})?;
+    writeln!(writer,"# NUMBER ALTERNATIVES: {}",num_candidates)?;
+    writeln!(writer,"# NUMBER VOTERS: {}",orders.iter().map(|(_,n)|n).sum::<usize>())?;
+    writeln!(writer,"# NUMBER UNIQUE ORDERS: {}",orders.len())?;
+    for (i,candidate_name) in contest.candidate_names.iter().enumerate() {
+        writeln!(writer,"# ALTERNATIVE NAME {}: {}",i+1,candidate_name)?;
+    }
+    for (prefs,n) in orders {
+        writeln!(writer,"{}: {}",n,prefs.iter().map(|c|(c.0+1).to_string()).collect::<Vec<_>>().join(","))?;
+    }
+    Ok(())
+}
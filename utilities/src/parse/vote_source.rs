@@ -0,0 +1,269 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sources of ranked ballots other than whole files, so that an election management system can give its ballots straight
+//! to the solver, e.g. from its database, without first exporting them in some file format.
+//!
+//! A [VoteSource] gives the ballots as groups of identical preference lists with the number of ballots in each, which a database
+//! can produce with a `GROUP BY` query. [ParsedContest::from_vote_source] collects them into a contest to be solved.
+//!
+//! Other sources provided besides [ParsedContestSource] (any file format read by the other parsers) are
+//! * [CountedCsvSource], reading CSV rows of a count followed by the candidates' names in preference order as they are read,
+//! * [CommandSource], running a program, such as a database's command line client, and reading its output as a [CountedCsvSource], and
+//! * with the `postgres` feature, `PostgresSource`, counting the ballots in a PostgreSQL table with a `GROUP BY` query.
+//!
+//! Other databases can be read with [CommandSource] and their own command line client, and programs using a database driver can
+//! implement [VoteSource] for their query results.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use anyhow::{anyhow, Context};
+use raire::irv::CandidateIndex;
+use crate::parse::{ParseLocation, ParsedContest};
+
+/// A source of ranked ballots.
+pub trait VoteSource {
+    /// A human readable name for the contest.
+    fn contest_name(&self) -> String;
+    /// The next group of ballots: the preferences, most preferred first, and the number of ballots with them.
+    /// The same preferences may appear in more than one group. None when there are no more.
+    fn next_ballots(&mut self) -> anyhow::Result<Option<(Vec<CandidateIndex>,usize)>>;
+    /// The names of the candidates, in index order. Only called once every ballot has been read, as some sources only learn the
+    /// candidates from the ballots.
+    fn candidate_names(&self) -> Vec<String>;
+}
+
+impl ParsedContest {
+    /// Read every ballot from a source.
+    pub fn from_vote_source(source:&mut dyn VoteSource) -> anyhow::Result<Self> {
+        let mut groups = vec![];
+        while let Some(group) = source.next_ballots()? { groups.push(group); }
+        let mut contest = ParsedContest::new(source.contest_name(),source.candidate_names());
+        for (prefs,n) in groups {
+            if let Some(candidate) = prefs.iter().find(|c|c.0 as usize>=contest.candidate_names.len()) { return Err(anyhow!("The ballots mention candidate {} but there are only {} candidates",candidate,contest.candidate_names.len())); }
+            contest.add_votes(prefs,n)?;
+        }
+        Ok(contest)
+    }
+}
+
+/// The ballots of a contest already read, e.g. from a file in one of the other formats.
+pub struct ParsedContestSource {
+    name : String,
+    candidate_names : Vec<String>,
+    votes : std::collections::hash_map::IntoIter<Vec<CandidateIndex>,usize>,
+}
+
+impl From<ParsedContest> for ParsedContestSource {
+    fn from(contest:ParsedContest) -> Self {
+        ParsedContestSource{ name: contest.name, candidate_names: contest.candidate_names, votes: contest.votes.into_iter() }
+    }
+}
+
+impl VoteSource for ParsedContestSource {
+    fn contest_name(&self) -> String { self.name.clone() }
+    fn next_ballots(&mut self) -> anyhow::Result<Option<(Vec<CandidateIndex>,usize)>> { Ok(self.votes.next()) }
+    fn candidate_names(&self) -> Vec<String> { self.candidate_names.clone() }
+}
+
+/// CSV rows, each the number of ballots followed by the candidates' names in preference order. Empty fields are ignored, so rows
+/// may have different lengths or be padded. Candidates are numbered in the order they are first seen, unless given in advance.
+/// Rows are read as they are needed, so the whole input is never in memory.
+pub struct CountedCsvSource<R:Read> {
+    name : String,
+    records : csv::StringRecordsIntoIter<R>,
+    candidates : CandidateNumbering,
+}
+
+/// Candidates numbered in the order they are first seen by name, or fixed in advance.
+#[derive(Default)]
+struct CandidateNumbering {
+    names : Vec<String>,
+    indices : HashMap<String,CandidateIndex>,
+    /// If true, a name not among the candidates given in advance is an error.
+    fixed : bool,
+}
+
+impl CandidateNumbering {
+    fn fixed(names:&[String]) -> Self {
+        let indices = names.iter().enumerate().map(|(i,name)|(name.trim().to_string(),CandidateIndex(i as u32))).collect();
+        CandidateNumbering{ names: names.to_vec(), indices, fixed: true }
+    }
+
+    fn get(&mut self,name:&str) -> anyhow::Result<CandidateIndex> {
+        if let Some(&candidate) = self.indices.get(name) { return Ok(candidate); }
+        if self.fixed { return Err(anyhow!("Unknown candidate {}",name)); }
+        let candidate = CandidateIndex(self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.indices.insert(name.to_string(),candidate);
+        Ok(candidate)
+    }
+
+    /// The preferences given by candidates' names, most preferred first, ignoring blank names.
+    fn preferences<'a>(&mut self,names:impl Iterator<Item=&'a str>) -> anyhow::Result<Vec<CandidateIndex>> {
+        let mut prefs = vec![];
+        for name in names.map(str::trim).filter(|name|!name.is_empty()) {
+            let candidate = self.get(name)?;
+            if prefs.contains(&candidate) { return Err(anyhow!("Candidate {} is ranked more than once",name)); }
+            prefs.push(candidate);
+        }
+        Ok(prefs)
+    }
+}
+
+impl <R:Read> CountedCsvSource<R> {
+    /// Read from a reader, skipping a header row if `has_header` is true.
+    pub fn new(reader:R,name:&str,has_header:bool) -> Self {
+        let records = csv::ReaderBuilder::new().has_headers(has_header).flexible(true).from_reader(reader).into_records();
+        CountedCsvSource{ name: name.to_string(), records, candidates: CandidateNumbering::default() }
+    }
+
+    /// Only allow these candidates, numbered in this order, e.g. so that the numbering does not depend on the order of the rows.
+    pub fn with_candidates(mut self,candidate_names:&[String]) -> Self {
+        self.candidates = CandidateNumbering::fixed(candidate_names);
+        self
+    }
+}
+
+impl CountedCsvSource<BufReader<File>> {
+    /// Read a file with a header row, naming the contest after the file.
+    pub fn open(path:&Path) -> anyhow::Result<Self> {
+        let name = path.file_stem().map(|s|s.to_string_lossy().to_string()).unwrap_or_default();
+        Ok(CountedCsvSource::new(BufReader::new(File::open(path)?),&name,true))
+    }
+}
+
+impl <R:Read> VoteSource for CountedCsvSource<R> {
+    fn contest_name(&self) -> String { self.name.clone() }
+
+    fn next_ballots(&mut self) -> anyhow::Result<Option<(Vec<CandidateIndex>,usize)>> {
+        let Some(record) = self.records.next() else { return Ok(None); };
+        let record = record?;
+        let line = record.position().map_or(0,|p|p.line() as usize);
+        let mut parse = || -> anyhow::Result<(Vec<CandidateIndex>,usize)> {
+            let n = record.get(0).unwrap_or_default().trim();
+            let n : usize = n.parse().map_err(|_|anyhow!("The number of ballots {:?} is not a non-negative integer",n))?;
+            Ok((self.candidates.preferences(record.iter().skip(1))?,n))
+        };
+        parse().context(ParseLocation::line(line)).map(Some)
+    }
+
+    fn candidate_names(&self) -> Vec<String> { self.candidates.names.clone() }
+}
+
+/// The output of a program, read as a [CountedCsvSource] without a header row while the program runs. Reading fails if the program
+/// does not succeed. What it writes to standard error is passed on to this program's standard error.
+pub struct CommandSource {
+    child : Child,
+    rows : CountedCsvSource<ChildStdout>,
+}
+
+impl CommandSource {
+    /// Start a program with the given arguments (not interpreted by a shell). The contest is given the name `name`.
+    /// If `candidate_names` is given, only those candidates are allowed, as for [CountedCsvSource::with_candidates].
+    pub fn new(program:&str,args:&[String],name:&str,candidate_names:Option<&[String]>) -> anyhow::Result<Self> {
+        let mut child = Command::new(program).args(args).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::inherit()).spawn()
+            .with_context(||format!("Could not run {}",program))?;
+        let stdout = child.stdout.take().ok_or_else(||anyhow!("No output from {}",program))?;
+        let rows = CountedCsvSource::new(stdout,name,false);
+        let rows = match candidate_names { Some(names) => rows.with_candidates(names), None => rows };
+        Ok(CommandSource{ child, rows })
+    }
+}
+
+impl VoteSource for CommandSource {
+    fn contest_name(&self) -> String { self.rows.contest_name() }
+
+    fn next_ballots(&mut self) -> anyhow::Result<Option<(Vec<CandidateIndex>,usize)>> {
+        match self.rows.next_ballots() {
+            Ok(None) => {
+                let status = self.child.wait()?;
+                if status.success() { Ok(None) } else { Err(anyhow!("The program reading the ballots failed with {}",status)) }
+            }
+            Err(e) => {
+                let _ = self.child.kill();
+                let _ = self.child.wait();
+                Err(e)
+            }
+            next => next,
+        }
+    }
+
+    fn candidate_names(&self) -> Vec<String> { self.rows.candidate_names() }
+}
+
+impl Drop for CommandSource {
+    /// Stop the program if not all its output was read.
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// The ballots in a PostgreSQL table with a row for each ballot and a column for each rank, holding the name of the candidate
+/// at that rank or NULL if none. Identical ballots are counted by the database with a `GROUP BY` query, so only the distinct
+/// preference lists are sent.
+#[cfg(feature = "postgres")]
+pub struct PostgresSource {
+    name : String,
+    rows : std::vec::IntoIter<postgres::Row>,
+    candidates : CandidateNumbering,
+}
+
+/// An SQL identifier, quoted so that it is not interpreted as SQL. A table may be qualified by its schema, as `schema.table`.
+#[cfg(feature = "postgres")]
+fn quote_identifier(identifier:&str) -> String {
+    identifier.split('.').map(|part|format!("\"{}\"",part.replace('"',"\"\""))).collect::<Vec<_>>().join(".")
+}
+
+#[cfg(feature = "postgres")]
+impl PostgresSource {
+    /// The query counting the ballots in `table`, grouped by the rank columns, first preference first. If `filter_column` is given,
+    /// only rows where that column (as text) equals the query's parameter `$1` are counted, e.g. the rows for one contest.
+    /// The table and columns are quoted, so are names rather than SQL expressions.
+    pub fn query(table:&str,rank_columns:&[String],filter_column:Option<&str>) -> anyhow::Result<String> {
+        if rank_columns.is_empty() { return Err(anyhow!("No rank columns given")); }
+        let columns = rank_columns.iter().map(|c|quote_identifier(c)).collect::<Vec<_>>();
+        let ranks = columns.iter().map(|c|format!("{c}::text")).collect::<Vec<_>>().join(", ");
+        let filter = filter_column.map(|c|format!(" WHERE {}::text = $1",quote_identifier(c))).unwrap_or_default();
+        Ok(format!("SELECT count(*), {ranks} FROM {}{filter} GROUP BY {}",quote_identifier(table),columns.join(", ")))
+    }
+
+    /// Count the ballots in a table with a connected client, as per [PostgresSource::query]. If `filter` is given, it is the column and
+    /// the value it must have. The contest is given the name `name`. If `candidate_names` is given, only those candidates
+    /// are allowed, as for [CountedCsvSource::with_candidates].
+    pub fn new(client:&mut postgres::Client,table:&str,rank_columns:&[String],filter:Option<(&str,&str)>,name:&str,candidate_names:Option<&[String]>) -> anyhow::Result<Self> {
+        let query = Self::query(table,rank_columns,filter.map(|(column,_)|column))?;
+        let rows = match filter {
+            Some((_,value)) => client.query(&query,&[&value]),
+            None => client.query(&query,&[]),
+        }.with_context(||format!("Could not count the ballots in {}",table))?;
+        let candidates = candidate_names.map(CandidateNumbering::fixed).unwrap_or_default();
+        Ok(PostgresSource{ name: name.to_string(), rows: rows.into_iter(), candidates })
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl VoteSource for PostgresSource {
+    fn contest_name(&self) -> String { self.name.clone() }
+
+    fn next_ballots(&mut self) -> anyhow::Result<Option<(Vec<CandidateIndex>,usize)>> {
+        let Some(row) = self.rows.next() else { return Ok(None); };
+        let n : i64 = row.try_get(0)?;
+        let names = (1..row.len()).map(|i|row.try_get::<_,Option<&str>>(i)).collect::<Result<Vec<_>,_>>()?;
+        Ok(Some((self.candidates.preferences(names.into_iter().flatten())?,n as usize)))
+    }
+
+    fn candidate_names(&self) -> Vec<String> { self.candidates.names.clone() }
+}
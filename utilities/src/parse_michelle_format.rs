@@ -14,12 +14,14 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use serde_json::json;
 use raire::audit_type::Audit;
 use raire::irv::{BallotPaperCount, CandidateIndex, Vote, Votes};
 use raire::RaireProblem;
+use raire::schema::FORMAT_VERSION;
 use raire::timeout::TimeOut;
+use crate::parse::ParseLocation;
 
 pub struct Contest {
     pub file_name_stem : String,
@@ -34,34 +36,42 @@ pub struct Contest {
 impl Contest {
     pub fn parse<P: AsRef<Path>>(path:P) -> anyhow::Result<Vec<Contest>> {
         let file_name_stem = path.as_ref().file_name().and_then(|s|s.to_str()).unwrap_or_default().trim_end_matches(".raire").to_string();
-        let mut lines = BufReader::new(File::open(path)?).lines();
+        Self::parse_reader(BufReader::new(File::open(path)?),&file_name_stem)
+    }
+
+    /// Like [Contest::parse], but reading from the given reader, with the given file name stem.
+    pub fn parse_reader<R:BufRead>(reader:R,file_name_stem:&str) -> anyhow::Result<Vec<Contest>> {
+        let file_name_stem = file_name_stem.to_string();
+        let mut lines = reader.lines().enumerate().map(|(i,l)|(i+1,l));
         // first line is number of contests
-        let num_contests : usize = lines.next().ok_or_else(||anyhow!("No number of contests on first line"))??.parse()?;
-        println!("File contains {num_contests} contests.");
+        let num_contests : usize = lines.next().ok_or_else(||anyhow!("No number of contests on first line"))?.1?.parse().with_context(||ParseLocation::line(1))?;
+        log::debug!("File contains {num_contests} contests.");
         let mut res = vec![];
         for i in 0..num_contests {
-            let line = lines.next().ok_or_else(||anyhow!("Missing contest {}",i+1))??;
+            let (line_number,line) = lines.next().ok_or_else(||anyhow!("Missing contest {}",i+1))?;
+            let line = line?;
+            let location = ||ParseLocation::line(line_number);
             let fields = line.split(',').collect::<Vec<_>>();
             // first field is typically "Contest" then and id then number of candidates, then candidate names
-            if fields.len()<3 { return Err(anyhow!("Contest {} doesn't have enough fields",i+1)); }
+            if fields.len()<3 { return Err(anyhow!("Contest {} doesn't have enough fields",i+1)).with_context(location); }
             let id = fields[1].to_string();
-            let num_candidates : usize = fields[2].parse()?;
-            let candidate_names : Vec<String> = if fields.len()>=3+num_candidates {
+            let num_candidates : usize = fields[2].parse().with_context(location)?;
+            let candidate_names : Vec<String> = if fields.len()-3>=num_candidates {
                 fields[3..(3+num_candidates)].iter().map(|s|s.to_string()).collect()
-            } else { return Err(anyhow!("Candidate ids missing")); };
+            } else { return Err(anyhow!("Candidate ids missing")).with_context(location); };
             let candidate_name_to_index : HashMap<String,CandidateIndex> = candidate_names.iter().enumerate().map(|(n,name)|(name.clone(),CandidateIndex(n as u32))).collect();
             res.push(Contest{ file_name_stem:file_name_stem.clone(), num_candidates,id,candidate_names,candidate_name_to_index,votes:Default::default()});
         }
         // rest of lines are contest,ballot_id,candidates (starting from 1)
-        for line in lines {
+        for (line_number,line) in lines {
             let line = line?;
             let mut fields = line.split(',');
             if let Some(contest_id) = fields.next() {
                 if let Some(contest) = res.iter_mut().find(|c|c.id.as_str()==contest_id) {
-                    if let Some(_ballot_id) = fields.next() {
+                    if let Some(ballot_id) = fields.next() {
                         let remaining = fields.collect::<Vec<_>>();
                         let candidates : Vec<CandidateIndex> = if remaining.len()==1 && remaining[0].is_empty() { vec![] } else {
-                            remaining.iter().map(|&s|*contest.candidate_name_to_index.get(s).expect("Expected integer candidate id")).collect()
+                            remaining.iter().map(|&s|contest.candidate_name_to_index.get(s).copied().ok_or_else(||anyhow!("Unknown candidate id {} for contest {}",s,contest_id))).collect::<anyhow::Result<_>>().with_context(||ParseLocation::ballot(line_number,Some(ballot_id)))?
                         };
                         *contest.votes.entry(candidates).or_insert(0)+=1;
                     }
@@ -86,9 +96,36 @@ impl Contest {
             votes: votes.votes,
             winner: Some(winner),
             audit,
+            seats: None,
+            elimination_rule: None,
+            tie_resolution: None,
+            reported_elimination_order: None,
             trim_algorithm: None,
+            trim_selection_algorithm: None,
+            cross_check_trimming: None,
+            two_phase: None,
+            max_tree_nodes: None,
+            extended_trim: None,
             difficulty_estimate: None,
             time_limit_seconds: None,
+            partial_on_timeout: None,
+            winner_only_fallback: None,
+            stop_when_difficulty_at_most: None,
+            objective: None,
+            deterministic: None,
+            include_tallies: None,
+            difficulty_decimal_places: None,
+            exact_arithmetic: None,
+            assertion_types: None,
+            ranked_votes: None,
+            ballot_interpretation: None,
+            vote_strata: None,
+            adjust_for_phantoms: None,
+            warm_start_assertions: None,
+            max_memory_mb: None,
+            risk_limit: None,
+            error_rates: None,
+            format_version: FORMAT_VERSION,
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A progress bar on the terminal showing the progress of a long RAIRE computation.
+//!
+//! The bar is drawn on standard error, and only if it is a terminal, so output redirected to a file is unaffected.
+
+use std::fmt::Write;
+use std::time::Duration;
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use raire::progress::{ProgressEvent, ProgressReporter};
+
+/// Shows [ProgressEvent]s as a progress bar. The bar is the [ProgressEvent::percentage_estimate], which is rough, so the ETA is too.
+/// If there is a time limit, the time left before it expires is also shown.
+pub struct ProgressDisplay {
+    bar : ProgressBar,
+}
+
+impl ProgressDisplay {
+    /// A progress bar for a computation with the given time limit in seconds, if any.
+    pub fn new(time_limit_seconds:Option<f64>) -> Self {
+        let bar = ProgressBar::new(100);
+        let budget = time_limit_seconds.filter(|t|t.is_finite() && *t>0.0);
+        let template = if budget.is_some() { "{spinner} [{elapsed_precise}] {bar:30} {pos:>3}% ETA {eta}, {budget} left of time limit : {wide_msg}" } else { "{spinner} [{elapsed_precise}] {bar:30} {pos:>3}% ETA {eta} : {wide_msg}" };
+        let style = ProgressStyle::with_template(template).unwrap().with_key("budget",move |state:&ProgressState,w:&mut dyn Write|{
+            let left = (budget.unwrap_or_default()-state.elapsed().as_secs_f64()).max(0.0);
+            let _ = write!(w,"{}",indicatif::HumanDuration(Duration::from_secs_f64(left)));
+        });
+        bar.set_style(style);
+        bar.set_message("determining winner");
+        bar.enable_steady_tick(Duration::from_millis(200)); // keep the clocks moving between events.
+        ProgressDisplay{bar}
+    }
+}
+
+impl ProgressReporter for ProgressDisplay {
+    fn report(&mut self, event: ProgressEvent) {
+        if let Some(percentage) = event.percentage_estimate() { self.bar.set_position(percentage.round() as u64); }
+        let message = match event {
+            ProgressEvent::DeterminedWinner{winner,..} => format!("winner is candidate {}, searching",winner.0),
+            ProgressEvent::Searching{frontier_size,current_difficulty,lower_bound,assertions_found,..} => format!("searching, frontier {frontier_size}, best difficulty between {lower_bound} and {current_difficulty}, {assertions_found} assertions found"),
+            ProgressEvent::FinishedSearch{assertions_found,difficulty,..} => format!("found {assertions_found} assertions with difficulty {difficulty}, trimming"),
+            ProgressEvent::TrimmingTreeBuilt{trees_built,trees_total,nodes,..} => format!("trimming, built tree {trees_built} of {trees_total} with {nodes} nodes"),
+            ProgressEvent::FinishedTrimming{assertions_after,..} => format!("finished with {assertions_after} assertions"),
+            ProgressEvent::ImprovedSolution{result} => format!("found {} assertions with difficulty {}, refining",result.assertions.len(),result.difficulty),
+        };
+        self.bar.set_message(message);
+    }
+}
+
+/// Remove the bar from the terminal when finished.
+impl Drop for ProgressDisplay {
+    fn drop(&mut self) { self.bar.finish_and_clear(); }
+}
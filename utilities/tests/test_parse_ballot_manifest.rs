@@ -0,0 +1,54 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test reading ballot manifest CSV files, and checking them against the audit.
+
+use raire::audit_type::{Audit, BallotComparisonOneOnDilutedMargin};
+use raire::ballot_manifest::{BallotLocation, ManifestBatch};
+use raire::irv::BallotPaperCount;
+use raire::RaireError;
+use utilities::parse::ballot_manifest::{read_ballot_manifest_csv, ManifestCsvFormat};
+use utilities::parse::ParseLocation;
+
+const ARLO : &str = "Container,Tabulator,Batch Name,Number of Ballots
+Box 1,T1,B1,250
+Box 1,T1,B2,150
+Box 2,T2,B1,100
+";
+
+#[test]
+fn test_read_ballot_manifest() {
+    let manifest = read_ballot_manifest_csv(ARLO.as_bytes(),&ManifestCsvFormat::default()).unwrap();
+    assert_eq!(vec![ManifestBatch{ batch_id: "Box 1 / T1 / B1".to_string(), ballots: 250 },ManifestBatch{ batch_id: "Box 1 / T1 / B2".to_string(), ballots: 150 },ManifestBatch{ batch_id: "Box 2 / T2 / B1".to_string(), ballots: 100 }],manifest.batches);
+    assert_eq!(500,manifest.total_ballots());
+    assert_eq!(Some(BallotLocation{ batch_id: "Box 1 / T1 / B2".to_string(), position: 150 }),manifest.locate(400));
+    assert_eq!(Some(BallotLocation{ batch_id: "Box 2 / T2 / B1".to_string(), position: 1 }),manifest.locate(401));
+    let batch_names = read_ballot_manifest_csv(ARLO.as_bytes(),&ManifestCsvFormat{ batch_id_columns: Some(vec![2]), ..Default::default() }).err().unwrap();
+    assert_eq!(Some(&ParseLocation::line(4)),batch_names.downcast_ref::<ParseLocation>()); // B1 is repeated.
+    // the total should be the total auditable ballots, and at least the number of votes.
+    let audit = |total:usize| Audit::OneOnMargin(BallotComparisonOneOnDilutedMargin{ total_auditable_ballots: BallotPaperCount(total) });
+    assert!(manifest.check_against(&audit(500),BallotPaperCount(480)).is_ok());
+    assert!(matches!(manifest.check_against(&audit(501),BallotPaperCount(480)),Err(RaireError::ManifestDisagreesWithAudit{ manifest: BallotPaperCount(500), total_auditable_ballots: BallotPaperCount(501) })));
+    assert!(matches!(manifest.check_against(&audit(500),BallotPaperCount(510)),Err(RaireError::ManifestSmallerThanVotes{..})));
+}
+
+#[test]
+fn test_ballot_manifest_running_totals() {
+    let good = "Batch,Ballots,Cumulative\nA,10,10\nB,5,15\nC,0,15\n";
+    let manifest = read_ballot_manifest_csv(good.as_bytes(),&ManifestCsvFormat::default()).unwrap();
+    assert_eq!(vec!["A","B","C"],manifest.batches.iter().map(|b|b.batch_id.as_str()).collect::<Vec<_>>());
+    assert_eq!(15,manifest.total_ballots());
+    let bad_total = read_ballot_manifest_csv("Batch,Ballots,Cumulative\nA,10,10\nB,5,16\nC,1,17\n".as_bytes(),&ManifestCsvFormat::default()).err().unwrap();
+    assert_eq!(Some(&ParseLocation::line(3)),bad_total.downcast_ref::<ParseLocation>());
+    let bad_count = read_ballot_manifest_csv("Batch,Ballots\nA,10\nB,five\n".as_bytes(),&ManifestCsvFormat::default()).err().unwrap();
+    assert_eq!(Some(&ParseLocation::line(3)),bad_count.downcast_ref::<ParseLocation>());
+    assert!(read_ballot_manifest_csv("Batch,Size\nA,10\n".as_bytes(),&ManifestCsvFormat::default()).is_err());
+    let explicit = read_ballot_manifest_csv("Batch,Size\nA,10\n".as_bytes(),&ManifestCsvFormat{ count_column: Some(1), ..Default::default() }).unwrap();
+    assert_eq!(10,explicit.total_ballots());
+}
@@ -0,0 +1,83 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Test reading and writing BLT files.
+
+use raire::irv::CandidateIndex;
+use utilities::parse::blt::{read_blt, write_blt};
+use utilities::parse::{BallotInterpretationRules, ParseLocation};
+
+const A : CandidateIndex = CandidateIndex(0);
+const B : CandidateIndex = CandidateIndex(1);
+const C : CandidateIndex = CandidateIndex(2);
+
+const BLT : &str = "4 1
+-4
+4 1 2 3 0
+(b17) 3 2 4 0
+2 3 1=2 0
+1 - 1 0
+0
+\"Alice\"
+\"Bob\"
+\"Chuan\"
+\"Diego\"
+\"Test election\"
+";
+
+#[test]
+fn test_read_blt() {
+    let contest = read_blt(BLT.as_bytes(),"default",&BallotInterpretationRules::default()).unwrap();
+    assert_eq!("Test election",contest.name);
+    assert_eq!(vec!["Alice","Bob","Chuan","Diego"],contest.candidate_names);
+    assert_eq!(10,contest.num_ballots());
+    assert_eq!(Some(&4),contest.votes.get(&vec![A,B,C]));
+    assert_eq!(Some(&3),contest.votes.get(&vec![B])); // Diego withdrawn
+    assert_eq!(Some(&2),contest.votes.get(&vec![C])); // tie exhausts
+    assert_eq!(Some(&1),contest.votes.get(&vec![A])); // skipped rank ignored
+}
+
+#[test]
+fn test_reject_multiple_seats() {
+    assert!(read_blt("2 2\n1 1 2 0\n0\n\"A\"\n\"B\"\n".as_bytes(),"stv",&BallotInterpretationRules::default()).is_err());
+}
+
+#[test]
+fn test_write_then_read() {
+    let contest = read_blt(BLT.as_bytes(),"default",&BallotInterpretationRules::default()).unwrap();
+    let mut written : Vec<u8> = vec![];
+    write_blt(&contest,false,&mut written).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert_eq!("4 1\n4 1 2 3 0\n3 2 0\n2 3 0\n1 1 0\n0\n\"Alice\"\n\"Bob\"\n\"Chuan\"\n\"Diego\"\n\"Test election\"\n",written);
+    let mut expanded : Vec<u8> = vec![];
+    write_blt(&contest,true,&mut expanded).unwrap();
+    let reread = read_blt(expanded.as_slice(),"default",&BallotInterpretationRules::default()).unwrap();
+    assert_eq!(contest.name,reread.name);
+    assert_eq!(contest.candidate_names,reread.candidate_names);
+    assert_eq!(contest.votes,reread.votes);
+}
+
+#[test]
+fn test_weights_too_large() {
+    let blt = "2 1\n18446744073709551615 1 0\n1 1 0\n0\nAlice\nBob\n";
+    assert!(read_blt(blt.as_bytes(),"default",&BallotInterpretationRules::default()).is_err());
+}
+
+#[test]
+fn test_error_location() {
+    let blt = "3 1\n# a comment\n2 1 2 0\n(b 7) 1 4 0\n0\nA\nB\nC\n";
+    let err = read_blt(blt.as_bytes(),"default",&BallotInterpretationRules::default()).err().unwrap();
+    let location = err.downcast_ref::<ParseLocation>().unwrap();
+    assert_eq!(&ParseLocation::ballot(4,Some("b 7")),location);
+    assert_eq!("line 4, ballot id b 7: Candidate 4 out of range",format!("{:#}",err));
+    let err = ParseLocation::in_file(err,std::path::Path::new("votes.blt"));
+    assert_eq!(Some(std::path::Path::new("votes.blt")),err.downcast_ref::<ParseLocation>().unwrap().file.as_deref());
+    assert_eq!("in votes.blt, line 4, ballot id b 7",err.to_string());
+}
@@ -0,0 +1,73 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test reading generic ranked ballot CSV files.
+
+use raire::irv::CandidateIndex;
+use utilities::parse::csv::{read_csv_ballots, CsvBallotFormat, NameNormalization, WriteInRule};
+use utilities::parse::{OvervoteRule, BallotInterpretationRules, ParseLocation};
+
+const A : CandidateIndex = CandidateIndex(0);
+const B : CandidateIndex = CandidateIndex(1);
+const C : CandidateIndex = CandidateIndex(2);
+
+const BALLOTS : &str = "Ballot,Rank 1,Rank 2,Rank 3
+1,Alice,Bob,
+2, alice ,,Chuan
+3,overvote,Bob,Alice
+4,Bob,Bob,Alice
+5,Zelda,Chuan,
+";
+
+#[test]
+fn test_csv_candidates_from_file() {
+    let contest = read_csv_ballots(BALLOTS.as_bytes(),"Test",&CsvBallotFormat{ballot_id_column:Some(0),overvote_values:vec!["overvote".to_string()],name_normalization:NameNormalization::TrimIgnoreCaseAndSpaces,..Default::default()}).unwrap();
+    assert_eq!("Test",contest.name);
+    assert_eq!(vec!["Alice","Bob","Chuan","Zelda"],contest.candidate_names);
+    assert_eq!(5,contest.num_ballots());
+    assert_eq!(Some(&1),contest.votes.get(&vec![A,B]));
+    assert_eq!(Some(&1),contest.votes.get(&vec![A,C])); // case and space normalized, skipped rank ignored
+    assert_eq!(Some(&1),contest.votes.get(&vec![])); // overvote exhausts
+    assert_eq!(Some(&1),contest.votes.get(&vec![B,A])); // duplicate ignored
+    assert_eq!(Some(&1),contest.votes.get(&vec![CandidateIndex(3),C]));
+}
+
+#[test]
+fn test_csv_fixed_candidates() {
+    let candidates = Some(vec!["Alice".to_string(),"Bob".to_string(),"Chuan".to_string()]);
+    let base = CsvBallotFormat{ballot_id_column:Some(0),candidates,overvote_values:vec!["overvote".to_string()],..Default::default()};
+    // exact trimmed names: "alice" and "Zelda" are unknown.
+    assert!(read_csv_ballots(BALLOTS.as_bytes(),"Test",&base).is_err());
+    let skip = read_csv_ballots(BALLOTS.as_bytes(),"Test",&CsvBallotFormat{write_ins:WriteInRule::Skip,..base.clone()}).unwrap();
+    assert_eq!(3,skip.candidate_names.len());
+    assert_eq!(Some(&2),skip.votes.get(&vec![C])); // alice and Zelda skipped
+    let combine = read_csv_ballots(BALLOTS.as_bytes(),"Test",&CsvBallotFormat{write_ins:WriteInRule::Combine("Write-in".to_string()),ranking:BallotInterpretationRules{overvote:OvervoteRule::SkipRank,..Default::default()},..base.clone()}).unwrap();
+    assert_eq!(vec!["Alice","Bob","Chuan","Write-in"],combine.candidate_names);
+    assert_eq!(Some(&2),combine.votes.get(&vec![CandidateIndex(3),C])); // both "alice" and "Zelda" are write-ins.
+    assert_eq!(Some(&2),combine.votes.get(&vec![B,A])); // overvote skipped, and duplicate ignored.
+    // only some rank columns
+    let first_only = read_csv_ballots(BALLOTS.as_bytes(),"Test",&CsvBallotFormat{rank_columns:Some(vec![1]),write_ins:WriteInRule::AddCandidate,..base.clone()}).unwrap();
+    assert_eq!(Some(&1),first_only.votes.get(&vec![A]));
+    assert_eq!(Some(&1),first_only.votes.get(&vec![CandidateIndex(3)]));
+    // repeated ballot ids are an error
+    let err = read_csv_ballots("Ballot,Rank 1\n1,Alice\n1,Bob\n".as_bytes(),"Test",&base).err().unwrap();
+    assert_eq!(Some(&ParseLocation::ballot(3,Some("1"))),err.downcast_ref::<ParseLocation>());
+}
+
+#[test]
+fn test_csv_count_column() {
+    let aggregated = "Count,Rank 1,Rank 2\n3,Alice,Bob\n2,Bob,\n0,Chuan,Alice\n4,Alice,Bob\n";
+    let contest = read_csv_ballots(aggregated.as_bytes(),"Test",&CsvBallotFormat{count_column:Some(0),..Default::default()}).unwrap();
+    assert_eq!(vec!["Alice","Bob","Chuan"],contest.candidate_names);
+    assert_eq!(9,contest.num_ballots());
+    assert_eq!(Some(&7),contest.votes.get(&vec![A,B]));
+    assert_eq!(Some(&2),contest.votes.get(&vec![B]));
+    let err = read_csv_ballots("Count,Rank 1\n1,Alice\nmany,Bob\n".as_bytes(),"Test",&CsvBallotFormat{count_column:Some(0),..Default::default()}).err().unwrap();
+    assert_eq!(Some(&ParseLocation::ballot(3,None)),err.downcast_ref::<ParseLocation>());
+}
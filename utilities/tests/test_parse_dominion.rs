@@ -0,0 +1,66 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test reading a small Dominion CVR export.
+
+use std::path::PathBuf;
+use raire::irv::CandidateIndex;
+use utilities::parse::dominion::{read_dominion_contest, DominionOptions};
+use utilities::parse::{DuplicateRule, OvervoteRule, ParsedContest, RankMark, BallotInterpretationRules};
+
+const A : CandidateIndex = CandidateIndex(0);
+const B : CandidateIndex = CandidateIndex(1);
+const C : CandidateIndex = CandidateIndex(2);
+
+fn example_folder() -> PathBuf { PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("dominion_example") }
+
+fn count(contest:&ParsedContest,prefs:&[CandidateIndex]) -> usize { contest.votes.get(prefs).cloned().unwrap_or(0) }
+
+#[test]
+fn test_dominion_default_rules() {
+    let contest = read_dominion_contest(&example_folder(),"Mayor",&DominionOptions::default()).unwrap();
+    assert_eq!("Mayor",contest.name);
+    assert_eq!(vec!["Alice","Bob","Chuan"],contest.candidate_names);
+    assert_eq!(7,contest.num_ballots());
+    assert_eq!(1,count(&contest,&[A,B]));
+    assert_eq!(1,count(&contest,&[A,C])); // duplicate ranking of Alice ignored
+    assert_eq!(1,count(&contest,&[])); // overvote at first rank
+    assert_eq!(1,count(&contest,&[C,B])); // skipped rank
+    assert_eq!(1,count(&contest,&[C,A])); // adjudicated
+    assert_eq!(1,count(&contest,&[C])); // ambiguous mark ignored
+    assert_eq!(1,count(&contest,&[B])); // mark not a vote
+    // selecting by id gives the same result.
+    assert_eq!(contest.votes,read_dominion_contest(&example_folder(),"3",&DominionOptions::default()).unwrap().votes);
+    assert!(read_dominion_contest(&example_folder(),"Governor",&DominionOptions::default()).is_err());
+}
+
+#[test]
+fn test_dominion_options() {
+    let options = DominionOptions{ ranking: BallotInterpretationRules{ overvote: OvervoteRule::SkipRank, duplicate: DuplicateRule::Ignore, max_consecutive_skipped_ranks: Some(0) }, include_ambiguous_marks: true };
+    let contest = read_dominion_contest(&example_folder(),"Mayor",&options).unwrap();
+    assert_eq!(2,count(&contest,&[C,A])); // adjudicated, and ambiguous mark included
+    assert_eq!(1,count(&contest,&[C])); // exhausted after a skipped rank
+    assert_eq!(2,count(&contest,&[])); // both the overvoted first rank and the first rank without a vote count as a skipped rank.
+    assert_eq!(0,count(&contest,&[B]));
+}
+
+#[test]
+fn test_ranking_rules() {
+    let marks = |ranked:Vec<(u32,CandidateIndex)>|RankMark::from_ranked_candidates(ranked);
+    assert_eq!(vec![RankMark::Overvote,RankMark::Skipped,RankMark::Candidate(A)],marks(vec![(1,B),(1,C),(3,A)]));
+    let skip_overvote = BallotInterpretationRules{overvote:OvervoteRule::SkipRank,..Default::default()};
+    assert_eq!(vec![A],skip_overvote.preferences(marks(vec![(1,B),(1,C),(2,A)])));
+    assert_eq!(Vec::<CandidateIndex>::new(),BallotInterpretationRules::default().preferences(marks(vec![(1,B),(1,C),(2,A)])));
+    assert_eq!(vec![B,A],BallotInterpretationRules::default().preferences(marks(vec![(1,B),(1,B),(3,A)])));
+    let duplicates_exhaust = BallotInterpretationRules{duplicate:DuplicateRule::Exhaust,..Default::default()};
+    assert_eq!(vec![B],duplicates_exhaust.preferences(marks(vec![(1,B),(2,B),(3,A)])));
+    assert_eq!(vec![B,A],BallotInterpretationRules::default().preferences(marks(vec![(1,B),(2,B),(3,A)])));
+    let maine = BallotInterpretationRules{max_consecutive_skipped_ranks:Some(1),..Default::default()};
+    assert_eq!(vec![B,A],maine.preferences(marks(vec![(1,B),(3,A),(6,C)])));
+}
@@ -0,0 +1,54 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test reading a small Hart CVR export.
+
+use std::path::PathBuf;
+use raire::irv::CandidateIndex;
+use utilities::parse::hart::{hart_contest_names, is_hart_cvr, read_hart_contest};
+use utilities::parse::{OvervoteRule, ParsedContest, BallotInterpretationRules};
+
+const A : CandidateIndex = CandidateIndex(0);
+const B : CandidateIndex = CandidateIndex(1);
+const C : CandidateIndex = CandidateIndex(2);
+
+fn example_folder() -> PathBuf { PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("hart_example") }
+
+fn count(contest:&ParsedContest,prefs:&[CandidateIndex]) -> usize { contest.votes.get(prefs).cloned().unwrap_or(0) }
+
+#[test]
+fn test_hart_default_rules() {
+    let contest = read_hart_contest(&example_folder(),"Mayor",&BallotInterpretationRules::default()).unwrap();
+    assert_eq!("Mayor",contest.name);
+    assert_eq!(vec!["Alice","Bob","Chuan"],contest.candidate_names);
+    assert_eq!(5,contest.num_ballots()); // one sheet does not have the contest.
+    assert_eq!(1,count(&contest,&[A,B,C]));
+    assert_eq!(1,count(&contest,&[B,A])); // skipped rank ignored
+    assert_eq!(1,count(&contest,&[C])); // option with value 0 ignored
+    assert_eq!(2,count(&contest,&[])); // overvote at first rank, and no options marked
+    // selecting by id gives the same result.
+    assert_eq!(contest.votes,read_hart_contest(&example_folder(),"3",&BallotInterpretationRules::default()).unwrap().votes);
+    assert!(read_hart_contest(&example_folder(),"Governor",&BallotInterpretationRules::default()).is_err());
+}
+
+#[test]
+fn test_hart_rules() {
+    let rules = BallotInterpretationRules{ overvote: OvervoteRule::SkipRank, ..Default::default() };
+    let contest = read_hart_contest(&example_folder(),"mayor",&rules).unwrap();
+    assert_eq!(1,count(&contest,&[B])); // the overvoted first rank is skipped
+    assert_eq!(1,count(&contest,&[]));
+}
+
+#[test]
+fn test_hart_contests() {
+    assert_eq!(vec!["Mayor","Proposition 1"],hart_contest_names(&example_folder()).unwrap());
+    assert!(is_hart_cvr(&std::fs::read(example_folder().join("cvr_1.xml")).unwrap()));
+    let nist_cdf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("nist_cdf_example").join("cvr.xml");
+    assert!(!is_hart_cvr(&std::fs::read(nist_cdf).unwrap()));
+}
@@ -0,0 +1,36 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+use raire::irv::CandidateIndex;
+use utilities::parse_michelle_format::Contest;
+
+const RAIRE : &str = "2
+Contest,1,3,a,b,c
+Contest,2,2,x,y
+1,b1,a,b
+1,b2,c
+2,b3,y
+1,b4,a,b
+";
+
+#[test]
+fn test_parse() {
+    let contests = Contest::parse_reader(RAIRE.as_bytes(),"test").unwrap();
+    assert_eq!(2,contests.len());
+    assert_eq!(vec!["a","b","c"],contests[0].candidate_names);
+    assert_eq!(Some(&2),contests[0].votes.get(&vec![CandidateIndex(0),CandidateIndex(1)]));
+    assert_eq!(Some(&1),contests[1].votes.get(&vec![CandidateIndex(1)]));
+}
+
+#[test]
+fn test_unknown_candidate() {
+    let raire = format!("{}1,b5,a,z\n",RAIRE);
+    assert!(Contest::parse_reader(raire.as_bytes(),"test").is_err());
+}
@@ -0,0 +1,45 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Test reading NIST CVR Common Data Format files in JSON and XML.
+
+use std::path::PathBuf;
+use raire::irv::CandidateIndex;
+use utilities::parse::nist_cdf::read_cdf_file;
+use utilities::parse::{ParsedContest, BallotInterpretationRules};
+
+const A : CandidateIndex = CandidateIndex(0);
+const B : CandidateIndex = CandidateIndex(1);
+const C : CandidateIndex = CandidateIndex(2);
+
+fn example_file(name:&str) -> PathBuf { PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("nist_cdf_example").join(name) }
+
+fn check_example(contest:&ParsedContest) {
+    assert_eq!("Mayor",contest.name);
+    assert_eq!(vec!["Alice","Bob","Chuan"],contest.candidate_names);
+    assert_eq!(5,contest.num_ballots());
+    assert_eq!(Some(&1),contest.votes.get(&vec![A,B,C]));
+    assert_eq!(Some(&1),contest.votes.get(&vec![B,A])); // skipped rank ignored
+    assert_eq!(Some(&2),contest.votes.get(&vec![C])); // overvote exhausts; not allocable mark ignored
+    assert_eq!(Some(&1),contest.votes.get(&vec![B])); // current (modified) snapshot used
+}
+
+#[test]
+fn test_cdf_json() {
+    check_example(&read_cdf_file(&example_file("cvr.json"),"contest-1",&BallotInterpretationRules::default()).unwrap());
+    check_example(&read_cdf_file(&example_file("cvr.json"),"mayor",&BallotInterpretationRules::default()).unwrap());
+    assert!(read_cdf_file(&example_file("cvr.json"),"Dog catcher",&BallotInterpretationRules::default()).is_err());
+}
+
+#[cfg(feature = "xml")]
+#[test]
+fn test_cdf_xml() {
+    check_example(&read_cdf_file(&example_file("cvr.xml"),"Mayor",&BallotInterpretationRules::default()).unwrap());
+}
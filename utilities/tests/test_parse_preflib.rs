@@ -0,0 +1,86 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+
+//! Test reading and writing PrefLib files.
+
+use raire::irv::CandidateIndex;
+use utilities::parse::preflib::{read_preflib, write_preflib};
+use utilities::parse::{OvervoteRule, BallotInterpretationRules};
+
+const A : CandidateIndex = CandidateIndex(0);
+const B : CandidateIndex = CandidateIndex(1);
+const C : CandidateIndex = CandidateIndex(2);
+
+const TOI : &str = "# FILE NAME: test.toi
+# TITLE: Test election
+# DATA TYPE: toi
+# NUMBER ALTERNATIVES: 3
+# NUMBER VOTERS: 10
+# NUMBER UNIQUE ORDERS: 4
+# ALTERNATIVE NAME 1: Alice
+# ALTERNATIVE NAME 2: Bob
+# ALTERNATIVE NAME 3: Chuan
+4: 1,2,3
+3: 2
+2: 3,{1,2}
+1: {1,3},2
+";
+
+#[test]
+fn test_read_toi() {
+    let contest = read_preflib(TOI.as_bytes(),"default",&BallotInterpretationRules::default()).unwrap();
+    assert_eq!("Test election",contest.name);
+    assert_eq!(vec!["Alice","Bob","Chuan"],contest.candidate_names);
+    assert_eq!(10,contest.num_ballots());
+    assert_eq!(Some(&4),contest.votes.get(&vec![A,B,C]));
+    assert_eq!(Some(&3),contest.votes.get(&vec![B]));
+    assert_eq!(Some(&2),contest.votes.get(&vec![C])); // tie exhausts
+    assert_eq!(Some(&1),contest.votes.get(&vec![]));
+    let contest = read_preflib(TOI.as_bytes(),"default",&BallotInterpretationRules{overvote:OvervoteRule::SkipRank,..Default::default()}).unwrap();
+    assert_eq!(Some(&2),contest.votes.get(&vec![C]));
+    assert_eq!(Some(&4),contest.votes.get(&vec![B])); // 3 + the tie then Bob
+}
+
+#[test]
+fn test_read_legacy_soi() {
+    let legacy = "3\n1,Alice\n2,Bob\n3,Chuan\n6,6,2\n4,1,2,3\n2,3\n";
+    let contest = read_preflib(legacy.as_bytes(),"legacy",&BallotInterpretationRules::default()).unwrap();
+    assert_eq!("legacy",contest.name);
+    assert_eq!(vec!["Alice","Bob","Chuan"],contest.candidate_names);
+    assert_eq!(Some(&4),contest.votes.get(&vec![A,B,C]));
+    assert_eq!(Some(&2),contest.votes.get(&vec![C]));
+}
+
+#[test]
+fn test_bad_alternative() {
+    assert!(read_preflib("# NUMBER ALTERNATIVES: 2\n1: 1,3\n".as_bytes(),"bad",&BallotInterpretationRules::default()).is_err());
+}
+
+#[test]
+fn test_write_then_read() {
+    let contest = read_preflib(TOI.as_bytes(),"default",&BallotInterpretationRules::default()).unwrap();
+    let mut written : Vec<u8> = vec![];
+    write_preflib(&contest,"test.soi",&mut written).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert!(written.contains("# DATA TYPE: soi\n"));
+    assert!(written.contains("# NUMBER VOTERS: 9\n")); // the empty ballot is omitted
+    assert!(written.ends_with("4: 1,2,3\n3: 2\n2: 3\n"));
+    let reread = read_preflib(written.as_bytes(),"default",&BallotInterpretationRules::default()).unwrap();
+    assert_eq!(contest.name,reread.name);
+    assert_eq!(contest.candidate_names,reread.candidate_names);
+    assert_eq!(9,reread.num_ballots());
+    assert_eq!(contest.votes.get(&vec![C]),reread.votes.get(&vec![C]));
+}
+
+#[test]
+fn test_alternative_name_out_of_range() {
+    let file = "# NUMBER ALTERNATIVES: 2\n# ALTERNATIVE NAME 1: Alice\n# ALTERNATIVE NAME 1000000000000: Bob\n1: 1,2\n";
+    assert!(read_preflib(file.as_bytes(),"default",&BallotInterpretationRules::default()).is_err());
+}
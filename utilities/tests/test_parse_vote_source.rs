@@ -0,0 +1,97 @@
+// Copyright 2023 Andrew Conway.
+// Based on software (c) Michelle Blom in C++ https://github.com/michelleblom/audit-irv-cp/tree/raire-branch
+// documented in https://arxiv.org/pdf/1903.08804.pdf
+//
+// This file is part of raire-rs.
+// raire-rs is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any later version.
+// raire-rs is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Affero General Public License for more details.
+// You should have received a copy of the GNU Affero General Public License along with ConcreteSTV.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test reading ballots from vote sources.
+
+use raire::irv::CandidateIndex;
+use utilities::parse::{ParseLocation, ParsedContest};
+use utilities::parse::vote_source::{CountedCsvSource, ParsedContestSource, VoteSource};
+
+const A : CandidateIndex = CandidateIndex(0);
+const B : CandidateIndex = CandidateIndex(1);
+const C : CandidateIndex = CandidateIndex(2);
+
+const COUNTED : &str = "count,first,second,third
+3,Alice,Bob,
+2,Bob,,
+1, Chuan ,Alice,Bob
+4,Alice,Bob
+0,Bob,Alice,
+";
+
+#[test]
+fn test_counted_csv_source() {
+    let mut source = CountedCsvSource::new(COUNTED.as_bytes(),"Test",true);
+    assert_eq!(Some((vec![A,B],3)),source.next_ballots().unwrap());
+    let contest = ParsedContest::from_vote_source(&mut source).unwrap();
+    assert_eq!("Test",contest.name);
+    assert_eq!(vec!["Alice","Bob","Chuan"],contest.candidate_names);
+    assert_eq!(Some(&4),contest.votes.get(&vec![A,B])); // the first row was already read.
+    assert_eq!(Some(&2),contest.votes.get(&vec![B]));
+    assert_eq!(Some(&1),contest.votes.get(&vec![C,A,B]));
+    assert_eq!(7,contest.num_ballots());
+    let contest = ParsedContest::from_vote_source(&mut CountedCsvSource::new(COUNTED.as_bytes(),"Test",true)).unwrap();
+    assert_eq!(Some(&7),contest.votes.get(&vec![A,B]));
+    // candidates given in advance fix the numbering.
+    let names = vec!["Chuan".to_string(),"Bob".to_string(),"Alice".to_string()];
+    let contest = ParsedContest::from_vote_source(&mut CountedCsvSource::new(COUNTED.as_bytes(),"Test",true).with_candidates(&names)).unwrap();
+    assert_eq!(names,contest.candidate_names);
+    assert_eq!(Some(&7),contest.votes.get(&vec![C,B]));
+    // a contest read from a file is also a source.
+    let mut source = ParsedContestSource::from(contest);
+    let copy = ParsedContest::from_vote_source(&mut source).unwrap();
+    assert_eq!(names,copy.candidate_names);
+    assert_eq!(Some(&7),copy.votes.get(&vec![C,B]));
+    assert_eq!(10,copy.num_ballots());
+}
+
+#[test]
+fn test_counted_csv_source_errors() {
+    let error = |input:&str,names:Option<&[String]>| {
+        let source = CountedCsvSource::new(input.as_bytes(),"Test",false);
+        let mut source = match names { Some(names) => source.with_candidates(names), None => source };
+        let error = ParsedContest::from_vote_source(&mut source).err().unwrap();
+        (error.downcast_ref::<ParseLocation>().and_then(|l|l.line),format!("{:#}",error))
+    };
+    let (line,message) = error("1,Alice\nmany,Bob\n",None);
+    assert_eq!(Some(2),line);
+    assert!(message.contains("\"many\""),"{}",message);
+    let (line,message) = error("1,Alice,Bob,Alice\n",None);
+    assert_eq!(Some(1),line);
+    assert!(message.contains("Alice is ranked more than once"),"{}",message);
+    let (line,message) = error("1,Alice\n2,Zelda\n",Some(&["Alice".to_string()]));
+    assert_eq!(Some(2),line);
+    assert!(message.contains("Unknown candidate Zelda"),"{}",message);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_command_source() {
+    use utilities::parse::vote_source::CommandSource;
+    let mut source = CommandSource::new("printf",&["2,Alice,Bob\\n1,Bob\\n".to_string()],"Test",None).unwrap();
+    let contest = ParsedContest::from_vote_source(&mut source).unwrap();
+    assert_eq!(vec!["Alice","Bob"],contest.candidate_names);
+    assert_eq!(Some(&2),contest.votes.get(&vec![A,B]));
+    assert_eq!(Some(&1),contest.votes.get(&vec![B]));
+    let mut failing = CommandSource::new("sh",&["-c".to_string(),"echo 1,Alice; exit 3".to_string()],"Test",None).unwrap();
+    let message = ParsedContest::from_vote_source(&mut failing).err().unwrap().to_string();
+    assert!(message.contains("failed"),"{}",message);
+    assert!(CommandSource::new("no-such-program-for-raire",&[],"Test",None).is_err());
+}
+
+#[cfg(feature = "postgres")]
+#[test]
+fn test_postgres_query() {
+    use utilities::parse::vote_source::PostgresSource;
+    let columns = ["first".to_string(),"second".to_string()];
+    assert_eq!(r#"SELECT count(*), "first"::text, "second"::text FROM "ballots" WHERE "contest"::text = $1 GROUP BY "first", "second""#,PostgresSource::query("ballots",&columns,Some("contest")).unwrap());
+    assert_eq!(r#"SELECT count(*), "first"::text, "second"::text FROM "public"."ballots" GROUP BY "first", "second""#,PostgresSource::query("public.ballots",&columns,None).unwrap());
+    assert_eq!(r#"SELECT count(*), "a""b"::text FROM "ballots" GROUP BY "a""b""#,PostgresSource::query("ballots",&[r#"a"b"#.to_string()],None).unwrap());
+    assert!(PostgresSource::query("ballots",&[],None).is_err());
+}